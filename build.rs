@@ -0,0 +1,16 @@
+//! Generates the C header for the `ffi` feature into `OUT_DIR`. The copy in
+//! `include/` is updated separately with the cbindgen CLI, since build
+//! scripts must not write to the source tree.
+
+fn main() {
+  #[cfg(feature = "ffi")]
+  {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::generate(&crate_dir)
+      .expect("Unable to generate the C header")
+      .write_to_file(format!("{out_dir}/wallter.h"));
+  }
+}
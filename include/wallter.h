@@ -0,0 +1,54 @@
+/* The C API of wallter. Generated by cbindgen; do not edit. */
+
+#ifndef WALLTER_H
+#define WALLTER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Sets the system color mode to `"light"`, `"dark"` or `"auto"` and saves
+// it to the configuration, as `wallter color set` does.
+//
+// # Safety
+//
+// `mode` must be a valid, NUL-terminated string.
+int wallter_set_mode(const char *mode);
+
+// Turns the system night light on if it is off and off if it is on. When
+// `enabled` isn't null, the new state is written to it.
+//
+// Windows Night Light, GNOME Night Light and KDE Night Color are supported;
+// elsewhere this fails with a permission error.
+//
+// # Safety
+//
+// `enabled` must be null or point to a writable `bool`.
+int wallter_toggle_nightlight(bool *enabled);
+
+// Sets the wallpaper from a local image, on the monitor named `monitor` or
+// on every monitor when `monitor` is null.
+//
+// # Safety
+//
+// `path` must be a valid, NUL-terminated string, and `monitor` null or one.
+int wallter_set_wallpaper_path(const char *path, const char *monitor);
+
+// Returns what is on screen as JSON, the same as `wallter status --json`,
+// or null on failure. Release the string with [wallter_string_free].
+char *wallter_get_status_json(void);
+
+// Returns the message of the last error on this thread, or null if there
+// was none. Release the string with [wallter_string_free].
+char *wallter_last_error(void);
+
+// Releases a string returned by wallter. Null is ignored.
+//
+// # Safety
+//
+// `string` must be null or have come from wallter, and not be freed twice.
+void wallter_string_free(char *string);
+
+#endif  /* WALLTER_H */
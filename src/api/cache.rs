@@ -0,0 +1,280 @@
+//! An on-disk cache of HTTP responses, keyed by URL.
+//!
+//! Searches and thumbnails rarely change between runs, so successful
+//! responses are kept in the cache directory. Within the configured TTL a
+//! stored response is used without asking the server at all. Past it, the
+//! request is sent with `If-None-Match` and `If-Modified-Since`, and a
+//! `304 Not Modified` answer renews the stored copy instead of sending it
+//! again.
+//!
+//! Each response is stored as two files named after a hash of the URL and
+//! the request's own headers, such as an API key: `<hash>.json` describes
+//! it and `<hash>.body` holds it.
+
+use super::retry;
+use crate::{
+  Config, Error, Result, config::search::HttpCache, simulate, utils::files
+};
+use chrono::{DateTime, Local, TimeDelta};
+use reqwest::{
+  Request, RequestBuilder, Response, StatusCode,
+  header::{
+    CONTENT_TYPE, ETAG, HeaderMap, HeaderName, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH, LAST_MODIFIED
+  }
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::RwLock
+};
+use tracing::{debug, warn};
+
+/// The name of the HTTP cache within the cache directory.
+const HTTP_DIR: &str = "http";
+
+static SETTINGS: RwLock<Option<(PathBuf, HttpCache)>> = RwLock::new(None);
+
+/// Returns the HTTP cache directory.
+pub fn dir(config: &Config) -> PathBuf {
+  config.path.cache_dir.join(HTTP_DIR)
+}
+
+/// Caches responses in `dir`, as `settings` say, from now on. Nothing is
+/// cached until this is called.
+pub fn configure(dir: PathBuf, settings: HttpCache) {
+  *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some((dir, settings));
+}
+
+/// Deletes every cached response, returning how many there were.
+pub fn clear(config: &Config) -> Result<usize> {
+  let dir = dir(config);
+  if !dir.is_dir() {
+    return Ok(0);
+  }
+  let mut removed = 0;
+  for entry in fs::read_dir(&dir)? {
+    let path = entry?.path();
+    if path.extension().is_some_and(|ext| ext == "json") {
+      removed += 1;
+    }
+    fs::remove_file(path)?;
+  }
+  Ok(removed)
+}
+
+/// A response read in full, whether from the server or the cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cached {
+  pub status: StatusCode,
+  pub content_type: Option<String>,
+  pub body: Vec<u8>,
+  /// Whether the body came from the cache.
+  pub from_cache: bool
+}
+
+impl Cached {
+  /// Reads `response` in full.
+  pub async fn read(response: Response) -> Result<Self> {
+    let status = response.status();
+    let content_type = header(response.headers(), CONTENT_TYPE);
+    let body = response.bytes().await.map_err(Error::from)?.to_vec();
+    Ok(Self {
+      status,
+      content_type,
+      body,
+      from_cache: false
+    })
+  }
+
+  /// Parses the body as JSON.
+  pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+    serde_json::from_slice(&self.body).map_err(|e| Error::API(e.to_string()))
+  }
+
+  /// Returns the body as text, for error messages.
+  pub fn text(&self) -> String {
+    String::from_utf8_lossy(&self.body).into_owned()
+  }
+}
+
+/// What is known about a stored response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+  url: String,
+  etag: Option<String>,
+  last_modified: Option<String>,
+  content_type: Option<String>,
+  fetched_at: DateTime<Local>
+}
+
+impl Entry {
+  /// Whether the response may still be used without asking the server.
+  fn is_fresh(&self, settings: &HttpCache) -> bool {
+    TimeDelta::from_std(settings.ttl.to_duration())
+      .is_ok_and(|ttl| Local::now() - self.fetched_at < ttl)
+  }
+
+  fn cached(&self, body: Vec<u8>) -> Cached {
+    Cached {
+      status: StatusCode::OK,
+      content_type: self.content_type.clone(),
+      body,
+      from_cache: true
+    }
+  }
+}
+
+/// Sends the request `build` makes to the provider called `name`, answering
+/// it from the cache where possible. Requests go out through
+/// [retry::send], and only successful responses are stored.
+pub async fn send(
+  name: &str,
+  build: impl Fn() -> RequestBuilder
+) -> Result<Cached> {
+  let configured = SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone();
+  let Some((dir, settings)) = configured.filter(|(_, s)| s.enabled) else {
+    return Cached::read(retry::send(name, build).await?).await;
+  };
+  let request = build().build().map_err(Error::from)?;
+  let url = request.url().to_string();
+  let base = dir.join(format!("{:016x}", key(&request)));
+
+  let stored = load(&base).filter(|(entry, _)| entry.url == url);
+  if let Some((entry, body)) = &stored {
+    if entry.is_fresh(&settings) {
+      debug!("Reusing the cached response for '{url}'");
+      return Ok(entry.cached(body.clone()));
+    }
+  }
+
+  let response = retry::send(name, || {
+    let mut request = build();
+    if let Some((entry, _)) = &stored {
+      if let Some(etag) = &entry.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+      }
+      if let Some(modified) = &entry.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, modified);
+      }
+    }
+    request
+  })
+  .await?;
+
+  if response.status() == StatusCode::NOT_MODIFIED {
+    if let Some((mut entry, body)) = stored {
+      debug!("'{url}' is unchanged; renewing the cached response");
+      entry.fetched_at = Local::now();
+      if let Err(e) = store(&base, &entry, None) {
+        warn!("Failed to renew the cached response for '{url}': {e}");
+      }
+      return Ok(entry.cached(body));
+    }
+  }
+
+  let entry = Entry {
+    url,
+    etag: header(response.headers(), ETAG),
+    last_modified: header(response.headers(), LAST_MODIFIED),
+    content_type: None,
+    fetched_at: Local::now()
+  };
+  let cached = Cached::read(response).await?;
+  if cached.status.is_success() {
+    let entry = Entry {
+      content_type: cached.content_type.clone(),
+      ..entry
+    };
+    if let Err(e) = store(&base, &entry, Some(&cached.body)) {
+      warn!("Failed to cache the response for '{}': {e}", entry.url);
+    }
+  }
+  Ok(cached)
+}
+
+/// Hashes what tells two requests apart: the URL and the headers the request
+/// sets itself.
+fn key(request: &Request) -> u64 {
+  let mut key = request.url().to_string();
+  for (name, value) in request.headers() {
+    key.push('\n');
+    key.push_str(name.as_str());
+    key.push(':');
+    key.push_str(&String::from_utf8_lossy(value.as_bytes()));
+  }
+  files::bytes_hash(key.as_bytes())
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+  headers
+    .get(name)
+    .and_then(|value| value.to_str().ok())
+    .map(str::to_owned)
+}
+
+/// Reads the response stored under `base`, if there is one.
+fn load(base: &Path) -> Option<(Entry, Vec<u8>)> {
+  let entry = fs::read_to_string(base.with_extension("json")).ok()?;
+  let entry = serde_json::from_str(&entry).ok()?;
+  let body = fs::read(base.with_extension("body")).ok()?;
+  Some((entry, body))
+}
+
+/// Stores `entry` under `base`, along with `body` if it changed. Nothing is
+/// stored when simulating.
+fn store(base: &Path, entry: &Entry, body: Option<&[u8]>) -> Result<()> {
+  if simulate::is_enabled() {
+    return Ok(());
+  }
+  if let Some(dir) = base.parent() {
+    fs::create_dir_all(dir)?;
+  }
+  //{ The body goes first, so a description always has a body to go with }
+  if let Some(body) = body {
+    fs::write(base.with_extension("body"), body)?;
+  }
+  let entry = serde_json::to_string_pretty(entry)
+    .map_err(|e| Error::Config(e.to_string()))?;
+  fs::write(base.with_extension("json"), entry)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use reqwest::Client;
+
+  #[test]
+  fn test_store_and_key() {
+    let client = Client::new();
+    let url = "https://wallhaven.cc/api/v1/search?q=lake";
+    let anonymous = client.get(url).build().unwrap();
+    let signed_in = client.get(url).header("X-API-Key", "abc").build().unwrap();
+    assert_ne!(key(&anonymous), key(&signed_in));
+    assert_eq!(key(&anonymous), key(&client.get(url).build().unwrap()));
+
+    let dir = std::env::temp_dir().join("wallter-http-cache-test");
+    let _ = fs::remove_dir_all(&dir);
+    let base = dir.join(format!("{:016x}", key(&anonymous)));
+    let entry = Entry {
+      url: url.into(),
+      etag: Some("\"v1\"".into()),
+      last_modified: None,
+      content_type: Some("application/json".into()),
+      fetched_at: Local::now()
+    };
+    store(&base, &entry, Some(b"{}")).unwrap();
+    let (loaded, body) = load(&base).unwrap();
+    assert_eq!((&loaded, body.as_slice()), (&entry, b"{}".as_slice()));
+    assert!(loaded.is_fresh(&HttpCache::default()));
+
+    let stale = Entry {
+      fetched_at: Local::now() - TimeDelta::hours(1),
+      ..entry
+    };
+    assert!(!stale.is_fresh(&HttpCache::default()));
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,153 @@
+//! Caps how many requests and downloads run at once against each provider.
+//!
+//! Some services tolerate more parallelism than others, so every provider
+//! bakes in its own [Concurrency], which a source can override with
+//! `max_concurrent_requests` and `max_concurrent_downloads`. The limits are
+//! enforced process-wide: API clients take a permit from [request] or
+//! [download] before going out, however many clients there are.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "online-sources")]
+use {
+  std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock}
+  },
+  tokio::sync::{OwnedSemaphorePermit, Semaphore}
+};
+
+/// How many requests and downloads may run at once against one provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Concurrency {
+  /// API requests, such as searches and detail lookups.
+  pub requests: usize,
+  /// Image downloads.
+  pub downloads: usize
+}
+
+impl Default for Concurrency {
+  fn default() -> Self {
+    Self::new(4, 4)
+  }
+}
+
+impl Concurrency {
+  /// Creates limits of `requests` and `downloads`, each at least 1.
+  pub const fn new(requests: usize, downloads: usize) -> Self {
+    Self {
+      requests: if requests == 0 { 1 } else { requests },
+      downloads: if downloads == 0 { 1 } else { downloads }
+    }
+  }
+
+  /// Returns the limits baked into the provider called `name`, or the
+  /// [default](Self::default) for others.
+  pub fn of(name: &str) -> Self {
+    match name {
+      "wallhaven" => super::wallhaven::CONCURRENCY,
+      "pexels" => super::pexels::CONCURRENCY,
+      "reddit" => super::reddit::CONCURRENCY,
+      _ => Self::default()
+    }
+  }
+}
+
+impl Display for Concurrency {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} requests, {} downloads at once",
+      self.requests, self.downloads
+    )
+  }
+}
+
+/// What a permit is for.
+#[cfg(feature = "online-sources")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+  Request,
+  Download
+}
+
+/// The permits of every provider, by name and kind.
+#[cfg(feature = "online-sources")]
+type Gates = HashMap<(String, Kind), Arc<Semaphore>>;
+
+#[cfg(feature = "online-sources")]
+fn gates() -> &'static Mutex<Gates> {
+  static GATES: OnceLock<Mutex<Gates>> = OnceLock::new();
+  GATES.get_or_init(Default::default)
+}
+
+/// Sets the limits of the provider called `name`. Operations already
+/// holding a permit keep it; later ones are held to the new limits.
+#[cfg(feature = "online-sources")]
+pub fn configure(name: &str, limits: Concurrency) {
+  let mut gates = gates().lock().unwrap_or_else(|e| e.into_inner());
+  for (kind, permits) in [
+    (Kind::Request, limits.requests),
+    (Kind::Download, limits.downloads)
+  ] {
+    gates.insert((name.to_string(), kind), Arc::new(Semaphore::new(permits)));
+  }
+}
+
+/// Sets the limits of every configured source.
+#[cfg(feature = "online-sources")]
+pub fn configure_all(config: &crate::config::search::Config) {
+  for source in &config.sources {
+    configure(&source.name, source.concurrency());
+  }
+}
+
+/// Waits for a free request slot with the provider called `name`. The slot
+/// is held until the permit is dropped.
+#[cfg(feature = "online-sources")]
+pub async fn request(name: &str) -> OwnedSemaphorePermit {
+  acquire(name, Kind::Request).await
+}
+
+/// Waits for a free download slot with the provider called `name`. The slot
+/// is held until the permit is dropped.
+#[cfg(feature = "online-sources")]
+pub async fn download(name: &str) -> OwnedSemaphorePermit {
+  acquire(name, Kind::Download).await
+}
+
+#[cfg(feature = "online-sources")]
+async fn acquire(name: &str, kind: Kind) -> OwnedSemaphorePermit {
+  let semaphore = {
+    let mut gates = gates().lock().unwrap_or_else(|e| e.into_inner());
+    gates
+      .entry((name.to_string(), kind))
+      .or_insert_with(|| {
+        let limits = Concurrency::of(name);
+        Arc::new(Semaphore::new(match kind {
+          Kind::Request => limits.requests,
+          Kind::Download => limits.downloads
+        }))
+      })
+      .clone()
+  };
+  semaphore
+    .acquire_owned()
+    .await
+    .expect("concurrency gates are never closed")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_of() {
+    assert_eq!(
+      Concurrency::of("wallhaven"),
+      super::super::wallhaven::CONCURRENCY
+    );
+    assert_eq!(Concurrency::of("photos"), Concurrency::default());
+    assert_eq!(Concurrency::new(0, 3), Concurrency::new(1, 3));
+  }
+}
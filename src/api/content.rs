@@ -0,0 +1,95 @@
+//! Validation of downloaded payloads.
+//!
+//! Servers sometimes answer image requests with HTML error pages or other
+//! non-image content. Downloads are checked against both the `Content-Type`
+//! header and the payload's magic bytes before they are moved into place by
+//! [download](super::download).
+
+use crate::{Error, Result};
+use image::ImageFormat;
+use std::path::{Path, PathBuf};
+
+/// Checks that a downloaded payload is an image, returning its format.
+///
+/// A `Content-Type` other than `image/*` or a generic binary type is rejected,
+/// as is a payload whose magic bytes don't match a known image format.
+pub fn validate(
+  url: &str,
+  content_type: Option<&str>,
+  bytes: &[u8]
+) -> Result<ImageFormat> {
+  if let Some(content_type) = content_type {
+    let mime = content_type
+      .split(';')
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .to_ascii_lowercase();
+    let binary = matches!(
+      mime.as_str(),
+      "application/octet-stream" | "binary/octet-stream"
+    );
+    if !mime.starts_with("image/") && !binary {
+      return Err(Error::API(format!(
+        "Expected an image from '{url}' but received '{mime}' content"
+      )));
+    }
+  }
+
+  image::guess_format(bytes).map_err(|_| {
+    Error::API(format!(
+      "Content downloaded from '{url}' is not a recognized image format"
+    ))
+  })
+}
+
+/// Returns `path` with an extension matching `format`, keeping the existing
+/// one if it is already valid for the format (e.g. `jpeg` for JPEG).
+pub fn with_extension(path: &Path, format: ImageFormat) -> PathBuf {
+  let current = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(str::to_ascii_lowercase);
+  let valid = format.extensions_str();
+  match current {
+    Some(ext) if valid.contains(&ext.as_str()) => path.to_path_buf(),
+    _ => match valid.first() {
+      Some(ext) => path.with_extension(ext),
+      None => path.to_path_buf()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+  const JPEG: &[u8] = b"\xff\xd8\xff\xe0\0\x10JFIF\0";
+
+  #[test]
+  fn test_validate() {
+    let url = "https://example.com/a.jpg";
+    assert_eq!(
+      validate(url, Some("image/png"), PNG).unwrap(),
+      ImageFormat::Png
+    );
+    assert_eq!(validate(url, None, JPEG).unwrap(), ImageFormat::Jpeg);
+    assert!(validate(url, Some("text/html; charset=utf-8"), PNG).is_err());
+    assert!(validate(url, Some("image/jpeg"), b"<!DOCTYPE html>").is_err());
+  }
+
+  #[test]
+  fn test_with_extension() {
+    let path = Path::new("/tmp/wall.jpg");
+    assert_eq!(with_extension(path, ImageFormat::Jpeg), path);
+    assert_eq!(
+      with_extension(path, ImageFormat::Png),
+      Path::new("/tmp/wall.png")
+    );
+    assert_eq!(
+      with_extension(Path::new("/tmp/wall"), ImageFormat::WebP),
+      Path::new("/tmp/wall.webp")
+    );
+  }
+}
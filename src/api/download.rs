@@ -0,0 +1,435 @@
+//! Downloads images to disk, resumably and with progress reports.
+//!
+//! An image is first written to `<path>.part` and only renamed into place
+//! once it is complete and [validated](content::validate), so an interrupted
+//! download never leaves a corrupt image behind. When a connection drops
+//! partway through, or an earlier run left a partial file, the download picks
+//! up where it stopped with a `Range` request; servers that ignore the range
+//! send the whole image again. The `ETag` or `Last-Modified` of the first
+//! response is kept beside the partial file and sent as `If-Range`, so an
+//! image that changed in between is downloaded afresh rather than spliced
+//! onto the old one. Downloads larger than the configured
+//! [max_file_size](crate::config::wallpaper::Limits) are abandoned.
+//!
+//! Progress is reported to the callback installed with [with_progress], so
+//! code deep in the download path needs no extra parameters. [queue] runs
+//! many downloads with bounded concurrency, on top of the process-wide
+//! [limits](super::concurrency) of each provider.
+
+use super::{content, retry};
+use crate::{Error, Result, config::wallpaper::Limits};
+use futures_util::{StreamExt, stream};
+use reqwest::{
+  Client, Response, StatusCode,
+  header::{
+    CONTENT_RANGE, CONTENT_TYPE, ETAG, HeaderMap, IF_RANGE, LAST_MODIFIED,
+    RANGE
+  }
+};
+use std::{
+  ffi::OsString,
+  fmt::{self, Display, Formatter},
+  future::Future,
+  path::{Path, PathBuf},
+  sync::{Arc, RwLock}
+};
+use tokio::{
+  fs::{self, OpenOptions},
+  io::{AsyncReadExt, AsyncWriteExt},
+  time::sleep
+};
+use tracing::{debug, warn};
+
+static MAX_SIZE: RwLock<Option<u64>> = RwLock::new(None);
+
+/// Sets the largest download accepted from now on.
+pub fn configure(max_size: u64) {
+  *MAX_SIZE.write().unwrap_or_else(|e| e.into_inner()) = Some(max_size);
+}
+
+/// Returns the largest download accepted, or the default limit if none was
+/// set.
+fn max_size() -> u64 {
+  MAX_SIZE
+    .read()
+    .unwrap_or_else(|e| e.into_inner())
+    .unwrap_or(Limits::default().max_file_size)
+}
+
+/// How far along a download is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+  pub url: String,
+  /// Bytes on disk so far, including any resumed from an earlier attempt.
+  pub received: u64,
+  /// The full size, if the server said.
+  pub total: Option<u64>
+}
+
+impl Progress {
+  /// Returns the share downloaded, from 0 to 1, if the size is known.
+  pub fn fraction(&self) -> Option<f64> {
+    self
+      .total
+      .filter(|total| *total > 0)
+      .map(|total| (self.received as f64 / total as f64).min(1.0))
+  }
+}
+
+impl Display for Progress {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    const MB: f64 = 1024.0 * 1024.0;
+    let received = self.received as f64 / MB;
+    match (self.fraction(), self.total) {
+      (Some(fraction), Some(total)) => write!(
+        f,
+        "{:>3.0}% ({received:.1} of {:.1} MB)",
+        fraction * 100.0,
+        total as f64 / MB
+      ),
+      _ => write!(f, "{received:.1} MB")
+    }
+  }
+}
+
+/// Receives progress reports.
+pub type Callback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+tokio::task_local! {
+  static PROGRESS: Callback;
+}
+
+/// Runs `future`, reporting the progress of the downloads it makes to
+/// `callback`.
+pub async fn with_progress<F: Future>(
+  callback: impl Fn(&Progress) + Send + Sync + 'static,
+  future: F
+) -> F::Output {
+  PROGRESS.scope(Arc::new(callback), future).await
+}
+
+fn report(progress: &Progress) {
+  let _ = PROGRESS.try_with(|callback| callback(progress));
+}
+
+/// Runs `task` on every item, at most `limit` at a time, and returns the
+/// results in the order of the items.
+pub async fn queue<T, F, Fut>(
+  items: impl IntoIterator<Item = T>,
+  limit: usize,
+  task: F
+) -> Vec<Fut::Output>
+where
+  F: FnMut(T) -> Fut,
+  Fut: Future
+{
+  stream::iter(items)
+    .map(task)
+    .buffered(limit.max(1))
+    .collect()
+    .await
+}
+
+/// Returns where the download of `path` is kept until it is complete.
+pub fn partial_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+  name.push(".part");
+  path.with_file_name(name)
+}
+
+/// Returns where the validator of a partial download is kept.
+fn validator_path(partial: &Path) -> PathBuf {
+  let mut name = partial.file_name().map(OsString::from).unwrap_or_default();
+  name.push(".validator");
+  partial.with_file_name(name)
+}
+
+/// Returns what identifies the version of the image in `headers`, to be
+/// sent as `If-Range`: a strong `ETag`, or else `Last-Modified`.
+fn validator(headers: &HeaderMap) -> Option<String> {
+  let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+  header(ETAG)
+    .filter(|tag| !tag.starts_with("W/"))
+    .or_else(|| header(LAST_MODIFIED))
+    .map(str::to_owned)
+}
+
+/// Returns the first byte of the range in a `Content-Range` header.
+fn range_start(headers: &HeaderMap) -> Option<u64> {
+  let range = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+  range
+    .strip_prefix("bytes ")?
+    .split('-')
+    .next()?
+    .trim()
+    .parse()
+    .ok()
+}
+
+/// Removes a partial download and its validator.
+async fn discard(partial: &Path) {
+  let _ = fs::remove_file(partial).await;
+  let _ = fs::remove_file(validator_path(partial)).await;
+}
+
+/// How one attempt at a download ended.
+enum Attempt {
+  /// The server reports the image missing.
+  Missing,
+  /// The image is complete, with the content type the server sent.
+  Complete(Option<String>),
+  /// The transfer broke off; what arrived is kept for a resume.
+  Broken(Error)
+}
+
+/// Downloads the image at `url` to `path` for the provider called
+/// `provider`, returning `None` if the server reports it missing. Passing
+/// failures are [retried](retry) as the provider's policy says, resuming
+/// from what already arrived.
+///
+/// The payload is [validated](content::validate) before it is moved into
+/// place, and the extension of `path` is corrected to match the actual
+/// image format.
+pub async fn file(
+  client: &Client,
+  provider: &str,
+  url: &str,
+  path: &Path
+) -> Result<Option<PathBuf>> {
+  let policy = retry::policy(provider);
+  let partial = partial_path(path);
+  let mut attempt = 1;
+  let content_type = loop {
+    match transfer(client, provider, url, &partial).await? {
+      Attempt::Missing => {
+        discard(&partial).await;
+        return Ok(None);
+      }
+      Attempt::Complete(content_type) => break content_type,
+      Attempt::Broken(e) if attempt < policy.max_attempts => {
+        let delay = policy.delay(attempt);
+        warn!("Downloading '{url}' broke off: {e}; resuming in {delay:?}");
+        sleep(delay).await;
+        attempt += 1;
+      }
+      Attempt::Broken(e) => return Err(e)
+    }
+  };
+
+  let mut head = Vec::with_capacity(64);
+  fs::File::open(&partial)
+    .await?
+    .take(64)
+    .read_to_end(&mut head)
+    .await?;
+  let format = match content::validate(url, content_type.as_deref(), &head) {
+    Ok(format) => format,
+    Err(e) => {
+      discard(&partial).await;
+      return Err(e);
+    }
+  };
+  let path = content::with_extension(path, format);
+  fs::rename(&partial, &path).await?;
+  let _ = fs::remove_file(validator_path(&partial)).await;
+  Ok(Some(path))
+}
+
+/// Requests the part of the image not yet in `partial` and appends it.
+async fn transfer(
+  client: &Client,
+  provider: &str,
+  url: &str,
+  partial: &Path
+) -> Result<Attempt> {
+  let mut offset = fs::metadata(partial).await.map_or(0, |m| m.len());
+  let saved = match offset {
+    0 => None,
+    _ => fs::read_to_string(validator_path(partial)).await.ok()
+  };
+  if offset > 0 && saved.is_none() {
+    //{ Without a validator there is no telling the image is unchanged }
+    debug!("'{url}' cannot be resumed safely; downloading it again");
+    offset = 0;
+  }
+  let response = retry::send(provider, || {
+    let request = client.get(url).timeout(super::http::timeouts().download());
+    match &saved {
+      Some(validator) if offset > 0 => request
+        .header(RANGE, format!("bytes={offset}-"))
+        .header(IF_RANGE, validator.as_str()),
+      _ => request
+    }
+  })
+  .await?;
+
+  let status = response.status();
+  if matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE) {
+    return Ok(Attempt::Missing);
+  }
+  if status == StatusCode::RANGE_NOT_SATISFIABLE {
+    //{ The partial file no longer matches the image; start over }
+    discard(partial).await;
+    return Ok(Attempt::Broken(Error::API(format!(
+      "'{url}' changed since it was partly downloaded"
+    ))));
+  }
+  if !status.is_success() {
+    return Err(Error::from_status(
+      provider,
+      status.as_u16(),
+      format!("Failed to download {url}")
+    ));
+  }
+
+  let resumed = status == StatusCode::PARTIAL_CONTENT;
+  if resumed && range_start(response.headers()) != Some(offset) {
+    discard(partial).await;
+    return Ok(Attempt::Broken(Error::API(format!(
+      "'{url}' resumed from another byte than the {offset} already downloaded"
+    ))));
+  }
+  if !resumed {
+    let path = validator_path(partial);
+    match validator(response.headers()) {
+      Some(validator) => fs::write(&path, validator).await?,
+      None => {
+        let _ = fs::remove_file(&path).await;
+      }
+    }
+  }
+  if offset > 0 {
+    if resumed {
+      debug!("Resuming '{url}' from {offset} bytes");
+    } else {
+      debug!("'{url}' cannot be resumed; downloading it again");
+    }
+  }
+  let content_type = response
+    .headers()
+    .get(CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .map(str::to_owned);
+  let received = if resumed { offset } else { 0 };
+  let limit = max_size();
+  if response
+    .content_length()
+    .is_some_and(|length| received + length > limit)
+  {
+    discard(partial).await;
+    return Err(too_large(url, limit));
+  }
+  let mut file = OpenOptions::new()
+    .create(true)
+    .write(true)
+    .append(resumed)
+    .truncate(!resumed)
+    .open(partial)
+    .await?;
+  let written = append(response, &mut file, url, received, limit);
+  let broken = match written.await {
+    Ok(broken) => broken,
+    Err(e) => {
+      drop(file);
+      discard(partial).await;
+      return Err(e);
+    }
+  };
+  file.flush().await?;
+  Ok(match broken {
+    Some(e) => Attempt::Broken(e),
+    None => Attempt::Complete(content_type)
+  })
+}
+
+/// Returns the error for a download of `url` beyond `limit` bytes.
+fn too_large(url: &str, limit: u64) -> Error {
+  Error::ImageRejected {
+    path: PathBuf::from(url),
+    reason: format!("download exceeds the limit of {limit} bytes")
+  }
+}
+
+/// Writes the body of `response` to `file`, reporting progress, and stops
+/// once more than `limit` bytes are on disk. Returns the error the transfer
+/// broke off with, if it did.
+async fn append(
+  mut response: Response,
+  file: &mut fs::File,
+  url: &str,
+  received: u64,
+  limit: u64
+) -> Result<Option<Error>> {
+  let mut progress = Progress {
+    url: url.to_string(),
+    received,
+    total: response.content_length().map(|length| length + received)
+  };
+  report(&progress);
+  loop {
+    match response.chunk().await {
+      Ok(Some(chunk)) => {
+        if progress.received + chunk.len() as u64 > limit {
+          return Err(too_large(url, limit));
+        }
+        file.write_all(&chunk).await?;
+        progress.received += chunk.len() as u64;
+        report(&progress);
+      }
+      Ok(None) => return Ok(None),
+      Err(e) => return Ok(Some(Error::from(e)))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_partial_path() {
+    assert_eq!(
+      partial_path(Path::new("/tmp/wall.jpg")),
+      Path::new("/tmp/wall.jpg.part")
+    );
+  }
+
+  #[test]
+  fn test_resume_headers() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(validator(&headers), None);
+    headers.insert(
+      LAST_MODIFIED,
+      "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap()
+    );
+    headers.insert(ETAG, "W/\"weak\"".parse().unwrap());
+    assert_eq!(
+      validator(&headers).as_deref(),
+      Some("Wed, 21 Oct 2026 07:28:00 GMT")
+    );
+    headers.insert(ETAG, "\"abc\"".parse().unwrap());
+    assert_eq!(validator(&headers).as_deref(), Some("\"abc\""));
+
+    assert_eq!(range_start(&headers), None);
+    headers.insert(CONTENT_RANGE, "bytes 1024-2047/2048".parse().unwrap());
+    assert_eq!(range_start(&headers), Some(1024));
+    assert_eq!(
+      validator_path(Path::new("/tmp/wall.jpg.part")),
+      Path::new("/tmp/wall.jpg.part.validator")
+    );
+  }
+
+  #[test]
+  fn test_progress() {
+    let mut progress = Progress {
+      url: "https://example.com/a.jpg".into(),
+      received: 3 * 1024 * 1024,
+      total: Some(4 * 1024 * 1024)
+    };
+    assert_eq!(progress.fraction(), Some(0.75));
+    assert_eq!(progress.to_string(), " 75% (3.0 of 4.0 MB)");
+    progress.total = None;
+    assert_eq!(progress.fraction(), None);
+    assert_eq!(progress.to_string(), "3.0 MB");
+  }
+}
@@ -0,0 +1,35 @@
+//! The HTTP client settings shared by every provider.
+//!
+//! Clients connect with the configured `source.timeouts.connect`, and each
+//! operation sets its own overall timeout: [Timeouts::request] for API
+//! calls, and the longer [Timeouts::download] for image files. A request
+//! that runs out of time fails with [Error::Timeout](crate::Error::Timeout).
+
+use crate::config::search::Timeouts;
+use reqwest::{Client, ClientBuilder};
+use std::sync::RwLock;
+
+static TIMEOUTS: RwLock<Option<Timeouts>> = RwLock::new(None);
+
+/// Sets the timeouts of clients and requests created from now on.
+pub fn configure(timeouts: Timeouts) {
+  *TIMEOUTS.write().unwrap_or_else(|e| e.into_inner()) = Some(timeouts);
+}
+
+/// Returns the configured timeouts, or the defaults if none were set.
+pub fn timeouts() -> Timeouts {
+  TIMEOUTS
+    .read()
+    .unwrap_or_else(|e| e.into_inner())
+    .unwrap_or_default()
+}
+
+/// Returns a client builder with the configured connect timeout.
+pub fn builder() -> ClientBuilder {
+  Client::builder().connect_timeout(timeouts().connect())
+}
+
+/// Creates a client with the configured connect timeout.
+pub fn client() -> Client {
+  builder().build().unwrap_or_default()
+}
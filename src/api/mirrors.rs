@@ -0,0 +1,73 @@
+//! Alternate addresses for images whose direct URL has gone missing.
+//!
+//! When a download answers 404 or 410, usually because the wallpaper was
+//! deleted or re-encoded, these are tried in order before giving up:
+//!
+//! - The same path with the other common extension, as Wallhaven serves each
+//!   image as either `.jpg` or `.png`.
+//! - For Wallhaven, the large and original thumbnails on `th.wallhaven.cc`,
+//!   which often outlive the full image.
+//! - The most recent copy in the Internet Archive's Wayback Machine.
+
+use crate::api::wallhaven;
+use std::path::Path;
+
+/// The Wayback Machine prefix that returns an archived file unmodified.
+const WAYBACK: &str = "https://web.archive.org/web/2id_/";
+
+/// Returns the addresses to try, best first, when `url` is missing.
+pub fn alternates(url: &str) -> Vec<String> {
+  let mut alternates = Vec::new();
+  if let Some(other) = other_extension(url) {
+    alternates.push(other);
+  }
+  if let Some(id) = url
+    .strip_prefix("https://w.wallhaven.cc/full/")
+    .and_then(|rest| wallhaven::id_from_path(Path::new(rest)))
+    .filter(|id| id.len() >= 2)
+  {
+    let prefix = &id[..2];
+    for size in ["lg", "orig"] {
+      alternates
+        .push(format!("https://th.wallhaven.cc/{size}/{prefix}/{id}.jpg"));
+    }
+  }
+  if !url.starts_with(WAYBACK) {
+    alternates.push(format!("{WAYBACK}{url}"));
+  }
+  alternates
+}
+
+/// Swaps a `.jpg` extension for `.png` and the other way around.
+fn other_extension(url: &str) -> Option<String> {
+  let (stem, extension) = url.rsplit_once('.')?;
+  let other = match extension.to_ascii_lowercase().as_str() {
+    "jpg" | "jpeg" => "png",
+    "png" => "jpg",
+    _ => return None
+  };
+  Some(format!("{stem}.{other}"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_alternates() {
+    assert_eq!(
+      alternates("https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg"),
+      [
+        "https://w.wallhaven.cc/full/94/wallhaven-94x38z.png",
+        "https://th.wallhaven.cc/lg/94/94x38z.jpg",
+        "https://th.wallhaven.cc/orig/94/94x38z.jpg",
+        "https://web.archive.org/web/2id_/https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg"
+      ]
+    );
+    assert_eq!(
+      alternates("https://example.com/image.webp"),
+      ["https://web.archive.org/web/2id_/https://example.com/image.webp"]
+    );
+    assert!(alternates("https://web.archive.org/web/2id_/x.gif").is_empty());
+  }
+}
@@ -1,4 +1,43 @@
+#[cfg(feature = "online-sources")]
 mod default;
+#[cfg(feature = "online-sources")]
 pub use default::Api;
 
+#[cfg(feature = "online-sources")]
+pub mod cache;
+
+pub mod concurrency;
+pub use concurrency::Concurrency;
+
+#[cfg(feature = "online-sources")]
+pub mod content;
+
+#[cfg(feature = "online-sources")]
+pub mod download;
+
+#[cfg(feature = "online-sources")]
+pub mod http;
+
+#[cfg(feature = "online-sources")]
+pub mod mirrors;
+
+#[cfg(feature = "online-sources")]
+pub mod oauth;
+
+pub mod pexels;
+
+#[cfg(feature = "online-sources")]
+pub mod rate_limit;
+
+pub mod provider;
+pub use provider::{Provider, Query, Wallpaper};
+
+pub mod reddit;
+
+#[cfg(feature = "online-sources")]
+pub mod retry;
+
+#[cfg(feature = "online-sources")]
+pub mod secrets;
+
 pub mod wallhaven;
@@ -0,0 +1,375 @@
+//! Signs in to sources whose write actions need a user, such as liking photos
+//! on Unsplash.
+//!
+//! [authorize] runs the authorization code flow with a one-shot HTTP listener
+//! on `127.0.0.1` as the redirect target. Tokens are kept in the system
+//! keyring, never in the config, and [access_token] refreshes them when they
+//! expire.
+
+use crate::{
+  Error, Result,
+  config::search::{OAuth, Source},
+  simulate::{self, Action}
+};
+use chrono::{DateTime, Duration, Utc};
+use keyring::Entry;
+use rand::{Rng, distr::Alphanumeric, rng};
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+  net::TcpListener,
+  time::timeout
+};
+use tracing::warn;
+
+/// The keyring service tokens are stored under.
+pub(crate) const KEYRING_SERVICE: &str = "wallter";
+
+/// How long to wait for the user to finish signing in.
+const SIGN_IN_TIMEOUT: std::time::Duration =
+  std::time::Duration::from_secs(300);
+
+/// Tokens this close to expiring are refreshed before use.
+const EXPIRY_MARGIN: Duration = Duration::seconds(60);
+
+/// The tokens for a signed-in source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+  pub access_token: String,
+  pub refresh_token: Option<String>,
+  /// When the access token expires, if it does.
+  pub expires_at: Option<DateTime<Utc>>
+}
+
+impl Token {
+  /// Whether the access token has expired or is about to.
+  pub fn is_expired(&self) -> bool {
+    self
+      .expires_at
+      .is_some_and(|at| at - EXPIRY_MARGIN <= Utc::now())
+  }
+}
+
+/// A token endpoint response.
+#[derive(Deserialize)]
+struct TokenReply {
+  access_token: String,
+  refresh_token: Option<String>,
+  expires_in: Option<i64>
+}
+
+impl From<TokenReply> for Token {
+  fn from(reply: TokenReply) -> Self {
+    Self {
+      access_token: reply.access_token,
+      refresh_token: reply.refresh_token,
+      expires_at: reply
+        .expires_in
+        .map(|seconds| Utc::now() + Duration::seconds(seconds))
+    }
+  }
+}
+
+/// Signs in to `source` in the browser and stores the tokens.
+pub async fn authorize(source: &Source) -> Result<Token> {
+  let oauth = settings(source)?;
+  let client_id = client_id(source, oauth)?;
+
+  let listener = TcpListener::bind("127.0.0.1:0").await?;
+  let redirect_uri = format!(
+    "http://127.0.0.1:{}/callback",
+    listener.local_addr()?.port()
+  );
+  let state: String = rng()
+    .sample_iter(&Alphanumeric)
+    .take(32)
+    .map(char::from)
+    .collect();
+  let url = Url::parse_with_params(
+    &oauth.authorize_url,
+    [
+      ("client_id", client_id.as_str()),
+      ("redirect_uri", &redirect_uri),
+      ("response_type", "code"),
+      ("scope", &oauth.scopes.join(" ")),
+      ("state", &state)
+    ]
+  )
+  .map_err(|e| Error::Config(format!("Invalid authorize URL: {e}")))?;
+
+  println!("Sign in to {} at:\n  {url}", source.name);
+  open_browser(url.as_str());
+
+  let code = timeout(SIGN_IN_TIMEOUT, receive_code(&listener, &state))
+    .await
+    .map_err(|_| {
+      Error::API("Timed out waiting for the sign-in".to_string())
+    })??;
+  let token = request_token(
+    oauth,
+    &client_id,
+    &[
+      ("grant_type", "authorization_code"),
+      ("code", &code),
+      ("redirect_uri", &redirect_uri)
+    ]
+  )
+  .await?;
+  store(&source.name, &token)?;
+  Ok(token)
+}
+
+/// Returns a current access token for `source`, refreshing it if it has
+/// expired, or `None` if the user hasn't signed in.
+pub async fn access_token(source: &Source) -> Result<Option<String>> {
+  let Some(token) = load(&source.name)? else {
+    return Ok(None);
+  };
+  if !token.is_expired() {
+    return Ok(Some(token.access_token));
+  }
+
+  let Some(refresh_token) = token.refresh_token else {
+    return Err(Error::API(format!(
+      "The {0} sign-in has expired; run `wallter auth {0}` again",
+      source.name
+    )));
+  };
+  let oauth = settings(source)?;
+  let mut renewed = request_token(
+    oauth,
+    &client_id(source, oauth)?,
+    &[
+      ("grant_type", "refresh_token"),
+      ("refresh_token", &refresh_token)
+    ]
+  )
+  .await?;
+
+  //{ Providers may keep the refresh token unchanged and leave it out }
+  if renewed.refresh_token.is_none() {
+    renewed.refresh_token = Some(refresh_token);
+  }
+  store(&source.name, &renewed)?;
+  Ok(Some(renewed.access_token))
+}
+
+/// Adds the signed-in user's access token to `request`, if there is one.
+pub async fn with_token(
+  request: RequestBuilder,
+  source: &Source
+) -> Result<RequestBuilder> {
+  Ok(match access_token(source).await? {
+    Some(token) => request.bearer_auth(token),
+    None => request
+  })
+}
+
+/// Loads the stored tokens for the named source.
+pub fn load(source: &str) -> Result<Option<Token>> {
+  match entry(source)?.get_password() {
+    Ok(secret) => serde_json::from_str(&secret)
+      .map(Some)
+      .map_err(|e| Error::Config(format!("Invalid stored token: {e}"))),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(Error::Config(format!("Failed to read the keyring: {e}")))
+  }
+}
+
+/// Deletes the stored tokens for the named source. Returns whether there were
+/// any.
+pub fn forget(source: &str) -> Result<bool> {
+  if simulate::dry_run(Action::StoreSecret {
+    name: source.to_string(),
+    stored: false
+  })? {
+    return Ok(load(source)?.is_some());
+  }
+  match entry(source)?.delete_credential() {
+    Ok(()) => Ok(true),
+    Err(keyring::Error::NoEntry) => Ok(false),
+    Err(e) => Err(Error::Config(format!("Failed to update the keyring: {e}")))
+  }
+}
+
+fn store(source: &str, token: &Token) -> Result<()> {
+  if simulate::dry_run(Action::StoreSecret {
+    name: source.to_string(),
+    stored: true
+  })? {
+    return Ok(());
+  }
+  let secret =
+    serde_json::to_string(token).map_err(|e| Error::Config(e.to_string()))?;
+  entry(source)?
+    .set_password(&secret)
+    .map_err(|e| Error::Config(format!("Failed to update the keyring: {e}")))
+}
+
+fn entry(source: &str) -> Result<Entry> {
+  Entry::new(KEYRING_SERVICE, source)
+    .map_err(|e| Error::Config(format!("Failed to open the keyring: {e}")))
+}
+
+fn settings(source: &Source) -> Result<&OAuth> {
+  source.oauth.as_ref().ok_or_else(|| {
+    Error::Config(format!("{} does not support signing in", source.name))
+  })
+}
+
+fn client_id(source: &Source, oauth: &OAuth) -> Result<String> {
+  oauth
+    .client_id
+    .clone()
+    .or_else(|| source.api_key())
+    .ok_or_else(|| {
+      Error::Config(format!(
+        "Set a client id or API key for {} before signing in",
+        source.name
+      ))
+    })
+}
+
+/// Exchanges a grant for tokens at the token endpoint.
+async fn request_token(
+  oauth: &OAuth,
+  client_id: &str,
+  grant: &[(&str, &str)]
+) -> Result<Token> {
+  let mut form = vec![("client_id", client_id)];
+  if let Some(secret) = &oauth.client_secret {
+    form.push(("client_secret", secret));
+  }
+  form.extend_from_slice(grant);
+
+  let response = super::http::client()
+    .post(&oauth.token_url)
+    .form(&form)
+    .timeout(super::http::timeouts().request())
+    .send()
+    .await?;
+  if !response.status().is_success() {
+    let status = response.status();
+    let error_text = response.text().await.unwrap_or_default();
+    return Err(Error::from_status(
+      &oauth.token_url,
+      status.as_u16(),
+      error_text
+    ));
+  }
+  let reply: TokenReply = response
+    .json()
+    .await
+    .map_err(|e| Error::API(e.to_string()))?;
+  Ok(reply.into())
+}
+
+/// Waits for the browser to be redirected back with an authorization code.
+async fn receive_code(listener: &TcpListener, state: &str) -> Result<String> {
+  loop {
+    let (mut stream, _) = listener.accept().await?;
+    let mut request_line = String::new();
+    BufReader::new(&mut stream)
+      .read_line(&mut request_line)
+      .await?;
+
+    let result = parse_callback(&request_line, state);
+    let (status, message) = match &result {
+      Ok(Some(_)) => ("200 OK", "Signed in. You can close this tab."),
+      Ok(None) => ("404 Not Found", "Not found."),
+      Err(_) => ("400 Bad Request", "Sign-in failed. Check the terminal.")
+    };
+    let response = format!(
+      "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\n{message}"
+    );
+    stream.write_all(response.as_bytes()).await?;
+
+    if let Some(code) = result? {
+      return Ok(code);
+    }
+  }
+}
+
+/// Reads the authorization code from the request line of the redirect.
+/// Returns `None` for requests to other paths, such as a favicon.
+fn parse_callback(request_line: &str, state: &str) -> Result<Option<String>> {
+  let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+  let url = Url::parse(&format!("http://127.0.0.1{target}"))
+    .map_err(|e| Error::API(format!("Invalid redirect: {e}")))?;
+  if url.path() != "/callback" {
+    return Ok(None);
+  }
+
+  let param = |name: &str| {
+    url
+      .query_pairs()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.into_owned())
+  };
+  if let Some(error) = param("error") {
+    return Err(Error::API(format!("Sign-in was refused: {error}")));
+  }
+  if param("state").as_deref() != Some(state) {
+    return Err(Error::API(
+      "Sign-in response did not match the request".to_string()
+    ));
+  }
+  param("code")
+    .map(Some)
+    .ok_or_else(|| Error::API("Sign-in response had no code".to_string()))
+}
+
+/// Opens `url` in the default browser, if possible.
+fn open_browser(url: &str) {
+  let mut command = if cfg!(windows) {
+    let mut command = Command::new("rundll32");
+    command.args(["url.dll,FileProtocolHandler", url]);
+    command
+  } else if cfg!(target_os = "macos") {
+    let mut command = Command::new("open");
+    command.arg(url);
+    command
+  } else {
+    let mut command = Command::new("xdg-open");
+    command.arg(url);
+    command
+  };
+  if command.spawn().is_err() {
+    warn!("Could not open a browser; open the address above manually.");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_callback() {
+    let line = |target: &str| format!("GET {target} HTTP/1.1\r\n");
+    assert_eq!(
+      parse_callback(&line("/callback?code=abc&state=xyz"), "xyz").unwrap(),
+      Some("abc".to_string())
+    );
+    assert_eq!(parse_callback(&line("/favicon.ico"), "xyz").unwrap(), None);
+    assert!(
+      parse_callback(&line("/callback?code=abc&state=bad"), "xyz").is_err()
+    );
+    assert!(
+      parse_callback(&line("/callback?error=access_denied"), "xyz").is_err()
+    );
+  }
+
+  #[test]
+  fn test_token_expiry() {
+    let token = |expires_at| Token {
+      access_token: "token".into(),
+      refresh_token: None,
+      expires_at
+    };
+    assert!(!token(None).is_expired());
+    assert!(token(Some(Utc::now())).is_expired());
+    assert!(!token(Some(Utc::now() + Duration::hours(1))).is_expired());
+  }
+}
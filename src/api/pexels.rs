@@ -0,0 +1,426 @@
+//! Pexels API v1 Client
+//!
+//! Covers the two endpoints a wallpaper source needs: the curated photos
+//! picked by the Pexels team, and the photo search. Every request needs a
+//! free API key from <https://www.pexels.com/api/>, sent as the
+//! `Authorization` header. All Pexels photos are safe for work.
+
+use super::Concurrency;
+#[cfg(feature = "online-sources")]
+use super::{concurrency, download, http, retry};
+use crate::{Error, Result};
+#[cfg(feature = "online-sources")]
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "online-sources")]
+use std::path::{Path, PathBuf};
+use std::{
+  fmt::{self, Display, Formatter},
+  str::FromStr
+};
+
+/// The most results Pexels returns per page.
+pub const MAX_PER_PAGE: u32 = 80;
+
+/// Pexels serves images from a CDN that takes many downloads at once.
+pub const CONCURRENCY: Concurrency = Concurrency::new(4, 8);
+
+// -- Data Structures for API Responses --
+
+/// One page of photos, as returned by both the curated and search endpoints.
+#[derive(Debug, Deserialize)]
+pub struct PhotosResponse {
+  pub page: u32,
+  pub per_page: u32,
+  pub photos: Vec<Photo>,
+  /// The number of matches. Only reported by the search endpoint.
+  #[serde(default)]
+  pub total_results: Option<u32>,
+  /// The URL of the following page, absent on the last one.
+  #[serde(default)]
+  pub next_page: Option<String>
+}
+
+/// Represents a single photo from the Pexels API.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Photo {
+  pub id: u64,
+  pub width: u32,
+  pub height: u32,
+  /// The photo's page on Pexels.
+  pub url: String,
+  pub photographer: String,
+  pub photographer_url: String,
+  /// The average color of the photo as a hex code, e.g. `"#7E5835"`.
+  #[serde(default)]
+  pub avg_color: Option<String>,
+  pub src: PhotoSource,
+  #[serde(default)]
+  pub alt: String
+}
+
+/// Direct image URLs of a photo at different sizes.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PhotoSource {
+  /// The photo at its full, original resolution.
+  pub original: String,
+  pub large2x: String,
+  pub large: String,
+  pub medium: String,
+  pub small: String,
+  pub portrait: String,
+  pub landscape: String,
+  pub tiny: String
+}
+
+// -- Enums for Type-Safe Search Parameters --
+
+/// The orientation a searched photo must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+  Landscape,
+  Portrait,
+  Square
+}
+
+impl Orientation {
+  /// Returns the orientation of a `width` x `height` screen.
+  pub fn of(width: u32, height: u32) -> Self {
+    match width.cmp(&height) {
+      std::cmp::Ordering::Greater => Orientation::Landscape,
+      std::cmp::Ordering::Less => Orientation::Portrait,
+      std::cmp::Ordering::Equal => Orientation::Square
+    }
+  }
+}
+
+impl Display for Orientation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Orientation::Landscape => "landscape",
+        Orientation::Portrait => "portrait",
+        Orientation::Square => "square"
+      }
+    )
+  }
+}
+
+impl FromStr for Orientation {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "landscape" => Ok(Orientation::Landscape),
+      "portrait" => Ok(Orientation::Portrait),
+      "square" => Ok(Orientation::Square),
+      _ => Err(Error::Settings(format!("Unknown orientation '{s}'")))
+    }
+  }
+}
+
+/// The minimum size of a searched photo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Size {
+  /// At least 4 megapixels.
+  Small,
+  /// At least 12 megapixels.
+  Medium,
+  /// At least 24 megapixels.
+  Large
+}
+
+impl Size {
+  /// Returns the smallest size filter that still guarantees photos with at
+  /// least as many pixels as a `width` x `height` screen.
+  pub fn at_least(width: u32, height: u32) -> Self {
+    match u64::from(width) * u64::from(height) {
+      pixels if pixels > 12_000_000 => Size::Large,
+      pixels if pixels > 4_000_000 => Size::Medium,
+      _ => Size::Small
+    }
+  }
+}
+
+impl Display for Size {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Size::Small => "small",
+        Size::Medium => "medium",
+        Size::Large => "large"
+      }
+    )
+  }
+}
+
+/// A builder for Pexels search and curated requests. Without a query, only
+/// the page settings apply and the curated photos are listed.
+#[derive(Debug, Default, Clone)]
+pub struct SearchParams {
+  pub query: Option<String>,
+  pub orientation: Option<Orientation>,
+  pub size: Option<Size>,
+  /// A color name such as `"blue"`, or a hex code such as `"#ffffff"`.
+  pub color: Option<String>,
+  /// The search locale, e.g. `"en-US"`.
+  pub locale: Option<String>,
+  pub page: Option<u32>,
+  /// Results per page, up to [MAX_PER_PAGE].
+  pub per_page: Option<u32>
+}
+
+impl SearchParams {
+  /// Creates a new, empty `SearchParams` instance.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the search query. Example: `"mountains"`, `"city at night"`
+  pub fn with_query(mut self, query: impl Into<String>) -> Self {
+    self.query = Some(query.into());
+    self
+  }
+
+  /// Sets the orientation of the photos.
+  pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+    self.orientation = Some(orientation);
+    self
+  }
+
+  /// Sets the minimum size of the photos.
+  pub fn with_size(mut self, size: Size) -> Self {
+    self.size = Some(size);
+    self
+  }
+
+  /// Sets the desired color of the photos.
+  pub fn with_color(mut self, color: impl Into<String>) -> Self {
+    self.color = Some(color.into());
+    self
+  }
+
+  /// Sets the locale of the search.
+  pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+    self.locale = Some(locale.into());
+    self
+  }
+
+  /// Sets the page number for pagination, starting at 1.
+  pub fn with_page(mut self, page: u32) -> Self {
+    self.page = Some(page);
+    self
+  }
+
+  /// Sets the number of results per page. Clamped to [MAX_PER_PAGE].
+  pub fn with_per_page(mut self, per_page: u32) -> Self {
+    self.per_page = Some(per_page.clamp(1, MAX_PER_PAGE));
+    self
+  }
+
+  /// Returns the query string pairs for a search request.
+  fn search_query(&self) -> Vec<(&'static str, String)> {
+    let mut query_params = Vec::new();
+    if let Some(q) = &self.query {
+      query_params.push(("query", q.clone()));
+    }
+    if let Some(orientation) = self.orientation {
+      query_params.push(("orientation", orientation.to_string()));
+    }
+    if let Some(size) = self.size {
+      query_params.push(("size", size.to_string()));
+    }
+    if let Some(color) = &self.color {
+      query_params.push(("color", color.trim_start_matches('#').to_string()));
+    }
+    if let Some(locale) = &self.locale {
+      query_params.push(("locale", locale.clone()));
+    }
+    query_params.extend(self.page_query());
+    query_params
+  }
+
+  /// Returns the pagination pairs, the only ones curated requests take.
+  fn page_query(&self) -> Vec<(&'static str, String)> {
+    let mut query_params = Vec::new();
+    if let Some(page) = self.page {
+      query_params.push(("page", page.to_string()));
+    }
+    if let Some(per_page) = self.per_page {
+      query_params.push(("per_page", per_page.min(MAX_PER_PAGE).to_string()));
+    }
+    query_params
+  }
+}
+
+// -- API Client --
+
+/// The main client for interacting with the Pexels API.
+#[cfg(feature = "online-sources")]
+#[derive(Debug, Clone)]
+pub struct Api {
+  client: Client,
+  base_url: String,
+  api_key: String
+}
+
+#[cfg(feature = "online-sources")]
+impl Api {
+  /// Creates a new Pexels API client.
+  ///
+  /// # Arguments
+  /// * `api_key` - The API key every Pexels request needs.
+  pub fn new(api_key: impl Into<String>) -> Self {
+    Self {
+      client: http::client(),
+      base_url: "https://api.pexels.com/v1".to_string(),
+      api_key: api_key.into()
+    }
+  }
+
+  /// Sends a request, handling authentication and error responses.
+  async fn send_request<T: for<'de> Deserialize<'de>>(
+    &self,
+    url: String,
+    params: &[(&str, String)]
+  ) -> Result<T> {
+    let _permit = concurrency::request("pexels").await;
+    let response = retry::send("pexels", || {
+      self
+        .client
+        .get(&url)
+        .query(params)
+        .timeout(http::timeouts().request())
+        .header("Authorization", &self.api_key)
+    })
+    .await?;
+
+    if !response.status().is_success() {
+      let status = response.status();
+      let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Could not read error body.".to_string());
+      return Err(Error::from_status("pexels", status.as_u16(), error_text));
+    }
+
+    response
+      .json::<T>()
+      .await
+      .map_err(|e| Error::API(e.to_string()))
+  }
+
+  /// Searches Pexels for photos matching `params.query`.
+  pub async fn search(&self, params: &SearchParams) -> Result<PhotosResponse> {
+    if params.query.is_none() {
+      return Err(Error::API("A Pexels search needs a query".into()));
+    }
+    let url = format!("{}/search", self.base_url);
+    self.send_request(url, &params.search_query()).await
+  }
+
+  /// Lists the photos curated by the Pexels team, which change hourly.
+  /// Only the pagination of `params` applies.
+  pub async fn curated(&self, params: &SearchParams) -> Result<PhotosResponse> {
+    let url = format!("{}/curated", self.base_url);
+    self.send_request(url, &params.page_query()).await
+  }
+
+  /// Searches for `params.query`, or lists the curated photos without one.
+  pub async fn photos(&self, params: &SearchParams) -> Result<PhotosResponse> {
+    match params.query {
+      Some(_) => self.search(params).await,
+      None => self.curated(params).await
+    }
+  }
+
+  /// Retrieves a single photo by its ID.
+  pub async fn photo(&self, id: &str) -> Result<Photo> {
+    let url = format!("{}/photos/{id}", self.base_url);
+    self.send_request(url, &[]).await
+  }
+
+  /// Downloads a photo from its direct URL (`photo.src.original`) to
+  /// `path`, correcting the extension to match the image format. Returns
+  /// the path the photo was written to, or [Error::Gone] if Pexels no
+  /// longer has it.
+  pub async fn download_photo(
+    &self,
+    url: &str,
+    path: &Path
+  ) -> Result<PathBuf> {
+    let _permit = concurrency::download("pexels").await;
+    download::file(&self.client, "pexels", url, path)
+      .await?
+      .ok_or_else(|| Error::Gone(url.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_response() {
+    let response: PhotosResponse = serde_json::from_str(
+      r##"{
+        "page": 2, "per_page": 1, "total_results": 8000,
+        "next_page": "https://api.pexels.com/v1/search/?page=3&per_page=1&query=nature",
+        "photos": [{
+          "id": 2014422, "width": 3024, "height": 3024,
+          "url": "https://www.pexels.com/photo/brown-rocks-2014422/",
+          "photographer": "Joey Farina", "photographer_url": "https://www.pexels.com/@joey",
+          "photographer_id": 680589, "avg_color": "#978E82", "liked": false,
+          "src": {
+            "original": "https://images.pexels.com/photos/2014422/pexels-photo-2014422.jpeg",
+            "large2x": "https://images.pexels.com/photos/2014422/a.jpeg",
+            "large": "https://images.pexels.com/photos/2014422/b.jpeg",
+            "medium": "https://images.pexels.com/photos/2014422/c.jpeg",
+            "small": "https://images.pexels.com/photos/2014422/d.jpeg",
+            "portrait": "https://images.pexels.com/photos/2014422/e.jpeg",
+            "landscape": "https://images.pexels.com/photos/2014422/f.jpeg",
+            "tiny": "https://images.pexels.com/photos/2014422/g.jpeg"
+          },
+          "alt": "Brown Rocks During Golden Hour"
+        }]
+      }"##
+    )
+    .unwrap();
+    assert_eq!(response.page, 2);
+    assert_eq!(response.total_results, Some(8000));
+    assert_eq!(response.photos[0].id, 2014422);
+    assert_eq!(response.photos[0].avg_color.as_deref(), Some("#978E82"));
+  }
+
+  #[test]
+  fn test_params() {
+    let params = SearchParams::new()
+      .with_query("city at night")
+      .with_orientation(Orientation::of(1920, 1080))
+      .with_size(Size::at_least(3840, 2160))
+      .with_color("#FFFFFF")
+      .with_page(3)
+      .with_per_page(200);
+    assert_eq!(
+      params.search_query(),
+      [
+        ("query", "city at night".to_string()),
+        ("orientation", "landscape".to_string()),
+        ("size", "medium".to_string()),
+        ("color", "FFFFFF".to_string()),
+        ("page", "3".to_string()),
+        ("per_page", "80".to_string())
+      ]
+    );
+    assert_eq!(
+      params.page_query(),
+      [("page", "3".to_string()), ("per_page", "80".to_string())]
+    );
+  }
+}
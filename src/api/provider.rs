@@ -0,0 +1,333 @@
+//! A common interface over the wallpaper APIs.
+//!
+//! Each API client keeps its own request builders and response types, which
+//! follow the service closely. The [Provider] trait sits on top of them: it
+//! takes a provider-agnostic [Query] and returns [Wallpaper]s, so code that
+//! only needs to find and download images does not have to know which
+//! service it is talking to.
+
+use super::{Concurrency, pexels, wallhaven};
+use crate::Result;
+use std::{
+  fmt::{self, Display, Formatter},
+  future::Future,
+  path::{Path, PathBuf}
+};
+
+/// A wallpaper as any provider describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wallpaper {
+  /// The name of the provider, e.g. `"wallhaven"`.
+  pub provider: String,
+  /// The identifier of the wallpaper within its provider.
+  pub id: String,
+  /// The direct URL of the full-size image.
+  pub url: String,
+  /// The wallpaper's page on the provider's site, if it has one.
+  pub page: Option<String>,
+  pub width: u32,
+  pub height: u32,
+  /// The dominant colors of the image as hex codes, if known.
+  pub colors: Vec<String>,
+  /// Tags, lowercased. Only known where the provider reports them.
+  pub tags: Vec<String>,
+  /// The photographer or uploader, if known.
+  pub author: Option<String>,
+  /// A provider-specific popularity count, such as favorites.
+  pub popularity: u64,
+  /// Whether the wallpaper is safe for work.
+  pub safe: bool
+}
+
+impl From<&wallhaven::Wallpaper> for Wallpaper {
+  fn from(wallpaper: &wallhaven::Wallpaper) -> Self {
+    Self {
+      provider: "wallhaven".into(),
+      id: wallpaper.id.clone(),
+      url: wallpaper.path.clone(),
+      page: Some(wallpaper.url.clone()),
+      width: wallpaper.dimension_x,
+      height: wallpaper.dimension_y,
+      colors: wallpaper.colors.clone(),
+      tags: wallpaper
+        .tags
+        .iter()
+        .flatten()
+        .map(|tag| tag.name.to_lowercase())
+        .collect(),
+      author: wallpaper
+        .uploader
+        .as_ref()
+        .map(|uploader| uploader.username.clone()),
+      popularity: wallpaper.favorites.into(),
+      safe: wallpaper.purity == "sfw"
+    }
+  }
+}
+
+impl From<&pexels::Photo> for Wallpaper {
+  fn from(photo: &pexels::Photo) -> Self {
+    Self {
+      provider: "pexels".into(),
+      id: photo.id.to_string(),
+      url: photo.src.original.clone(),
+      page: Some(photo.url.clone()),
+      width: photo.width,
+      height: photo.height,
+      colors: photo.avg_color.iter().cloned().collect(),
+      tags: Vec::new(),
+      author: Some(photo.photographer.clone()),
+      //{ Pexels does not publish like counts }
+      popularity: 0,
+      //{ Pexels only hosts safe photos }
+      safe: true
+    }
+  }
+}
+
+impl Display for Wallpaper {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}:{} ({}x{})",
+      self.provider, self.id, self.width, self.height
+    )
+  }
+}
+
+/// A search any provider understands. Settings a provider has no equivalent
+/// for are ignored by it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Query {
+  /// Free-text keywords. Without any, providers return their own selection,
+  /// such as the latest or curated wallpapers.
+  pub text: Option<String>,
+  /// The page of results, starting at 1.
+  pub page: Option<u32>,
+  /// The smallest acceptable resolution, as (width, height).
+  pub at_least: Option<(u32, u32)>,
+  /// Only return wallpapers that are safe for work.
+  pub safe_only: bool,
+  /// Return results in random order, where the provider supports it.
+  pub random: bool
+}
+
+impl Query {
+  /// Creates a new, empty `Query` instance.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the search keywords.
+  pub fn with_text(mut self, text: impl Into<String>) -> Self {
+    self.text = Some(text.into());
+    self
+  }
+
+  /// Sets the page of results.
+  pub fn with_page(mut self, page: u32) -> Self {
+    self.page = Some(page.max(1));
+    self
+  }
+
+  /// Sets the smallest acceptable resolution.
+  pub fn with_at_least(mut self, width: u32, height: u32) -> Self {
+    self.at_least = Some((width, height));
+    self
+  }
+
+  /// Limits results to wallpapers that are safe for work.
+  pub fn safe_only(mut self) -> Self {
+    self.safe_only = true;
+    self
+  }
+
+  /// Asks for results in random order.
+  pub fn random(mut self) -> Self {
+    self.random = true;
+    self
+  }
+}
+
+impl From<&Query> for wallhaven::SearchParams {
+  fn from(query: &Query) -> Self {
+    Self::new().with(query)
+  }
+}
+
+impl wallhaven::SearchParams {
+  /// Returns these parameters with the settings of `query` applied on top.
+  pub fn with(self, query: &Query) -> Self {
+    let mut params = self;
+    if let Some(text) = &query.text {
+      params = params.with_query(text);
+    }
+    if let Some(page) = query.page {
+      params = params.with_page(page);
+    }
+    if let Some((width, height)) = query.at_least {
+      params = params.with_atleast(format!("{width}x{height}"));
+    }
+    if query.safe_only {
+      params = params.with_purity((true, false, false));
+    }
+    if query.random {
+      params = params.with_sorting(wallhaven::Sorting::Random);
+    }
+    params
+  }
+}
+
+impl From<&Query> for pexels::SearchParams {
+  fn from(query: &Query) -> Self {
+    let mut params = Self::new();
+    if let Some(text) = &query.text {
+      params = params.with_query(text);
+    }
+    if let Some(page) = query.page {
+      params = params.with_page(page);
+    }
+    if let Some((width, height)) = query.at_least {
+      params = params
+        .with_orientation(pexels::Orientation::of(width, height))
+        .with_size(pexels::Size::at_least(width, height));
+    }
+    params
+  }
+}
+
+/// A service wallpapers can be searched on and downloaded from.
+pub trait Provider {
+  /// The name of the provider, as used for sources in the config.
+  fn name(&self) -> &'static str;
+
+  /// The limits baked into the provider, unless a source overrides them.
+  fn concurrency(&self) -> Concurrency {
+    Concurrency::of(self.name())
+  }
+
+  /// Returns one page of wallpapers matching `query`.
+  fn search(
+    &self,
+    query: &Query
+  ) -> impl Future<Output = Result<Vec<Wallpaper>>> + Send;
+
+  /// Retrieves a single wallpaper by its identifier.
+  fn details(&self, id: &str)
+  -> impl Future<Output = Result<Wallpaper>> + Send;
+
+  /// Downloads `wallpaper` to `path`, returning the path it was written to.
+  /// The extension may be corrected to match the actual image format.
+  fn download(
+    &self,
+    wallpaper: &Wallpaper,
+    path: &Path
+  ) -> impl Future<Output = Result<PathBuf>> + Send;
+}
+
+#[cfg(feature = "online-sources")]
+impl Provider for wallhaven::Api {
+  fn name(&self) -> &'static str {
+    "wallhaven"
+  }
+
+  async fn search(&self, query: &Query) -> Result<Vec<Wallpaper>> {
+    let params = self.defaults().clone().with(query);
+    let response = wallhaven::Api::search(self, &params).await?;
+    Ok(response.data.iter().map(Wallpaper::from).collect())
+  }
+
+  async fn details(&self, id: &str) -> Result<Wallpaper> {
+    Ok(Wallpaper::from(&self.get_wallpaper_details(id).await?))
+  }
+
+  async fn download(
+    &self,
+    wallpaper: &Wallpaper,
+    path: &Path
+  ) -> Result<PathBuf> {
+    self.download_wallpaper(&wallpaper.url, path).await
+  }
+}
+
+#[cfg(feature = "online-sources")]
+impl Provider for pexels::Api {
+  fn name(&self) -> &'static str {
+    "pexels"
+  }
+
+  async fn search(&self, query: &Query) -> Result<Vec<Wallpaper>> {
+    let response = self.photos(&query.into()).await?;
+    Ok(response.photos.iter().map(Wallpaper::from).collect())
+  }
+
+  async fn details(&self, id: &str) -> Result<Wallpaper> {
+    Ok(Wallpaper::from(&self.photo(id).await?))
+  }
+
+  async fn download(
+    &self,
+    wallpaper: &Wallpaper,
+    path: &Path
+  ) -> Result<PathBuf> {
+    self.download_photo(&wallpaper.url, path).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_query() {
+    let query = Query::new()
+      .with_text("lake")
+      .with_page(0)
+      .with_at_least(3840, 2160)
+      .safe_only();
+
+    let params = wallhaven::SearchParams::from(&query);
+    assert_eq!(params.query.as_deref(), Some("lake"));
+    assert_eq!(params.page, Some(1));
+    assert_eq!(params.atleast.as_deref(), Some("3840x2160"));
+    assert_eq!(params.purity, Some((true, false, false)));
+    assert_eq!(params.sorting, None);
+
+    //{ Base parameters are kept unless the query overrides them }
+    let base = wallhaven::SearchParams::new()
+      .with_categories((true, false, false))
+      .with_atleast("1920x1080");
+    let params = base.with(&Query::new().random());
+    assert_eq!(params.categories, Some((true, false, false)));
+    assert_eq!(params.atleast.as_deref(), Some("1920x1080"));
+    assert_eq!(params.sorting, Some(wallhaven::Sorting::Random));
+
+    let params = pexels::SearchParams::from(&query);
+    assert_eq!(params.orientation, Some(pexels::Orientation::Landscape));
+    assert_eq!(params.size, Some(pexels::Size::Medium));
+  }
+
+  #[test]
+  fn test_from_photo() {
+    let photo: pexels::Photo = serde_json::from_str(
+      r##"{
+        "id": 2014422, "width": 3024, "height": 3024,
+        "url": "https://www.pexels.com/photo/2014422/",
+        "photographer": "Joey Farina",
+        "photographer_url": "https://www.pexels.com/@joey",
+        "avg_color": "#978E82",
+        "src": {
+          "original": "https://images.pexels.com/photos/2014422/a.jpeg",
+          "large2x": "", "large": "", "medium": "", "small": "",
+          "portrait": "", "landscape": "", "tiny": ""
+        }
+      }"##
+    )
+    .unwrap();
+    let wallpaper = Wallpaper::from(&photo);
+    assert_eq!(wallpaper.to_string(), "pexels:2014422 (3024x3024)");
+    assert_eq!(wallpaper.author.as_deref(), Some("Joey Farina"));
+    assert_eq!(wallpaper.colors, ["#978E82"]);
+    assert!(wallpaper.safe);
+  }
+}
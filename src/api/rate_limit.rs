@@ -0,0 +1,153 @@
+//! Waits out rate limits instead of failing on them.
+//!
+//! Providers cap how often they may be called; Wallhaven allows 45 requests
+//! a minute. When one answers `429 Too Many Requests`, [send] waits as long
+//! as its `Retry-After` header asks, or [FALLBACK_WAIT] without one, and
+//! sends the request again, within the limits of `source.rate_limit`. The
+//! quota each provider last reported is kept, see [quota].
+
+use crate::{Error, Result, config::search::RateLimit};
+use reqwest::{
+  RequestBuilder, Response, StatusCode,
+  header::{HeaderMap, RETRY_AFTER}
+};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  sync::{Mutex, RwLock},
+  time::Duration
+};
+use tokio::time::sleep;
+use tracing::warn;
+
+/// How long a rate-limited request waits when the provider does not say.
+pub const FALLBACK_WAIT: Duration = Duration::from_secs(15);
+
+static SETTINGS: RwLock<Option<RateLimit>> = RwLock::new(None);
+
+static QUOTAS: Mutex<BTreeMap<String, Quota>> = Mutex::new(BTreeMap::new());
+
+/// How many more requests a provider will take before limiting them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+  /// The requests allowed per window, if the provider says.
+  pub limit: Option<u32>,
+  /// The requests left in the current window.
+  pub remaining: u32
+}
+
+impl Quota {
+  /// Reads the quota from the `X-RateLimit-*` headers of a response, if it
+  /// has them.
+  pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+    let number =
+      |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u32>().ok();
+    Some(Self {
+      limit: number("x-ratelimit-limit"),
+      remaining: number("x-ratelimit-remaining")?
+    })
+  }
+}
+
+impl Display for Quota {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self.limit {
+      Some(limit) => write!(f, "{} of {limit} requests left", self.remaining),
+      None => write!(f, "{} requests left", self.remaining)
+    }
+  }
+}
+
+/// Sets how rate-limited requests are handled from now on.
+pub fn configure(settings: RateLimit) {
+  *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(settings);
+}
+
+/// Returns the configured handling, or the default if none was set.
+pub fn settings() -> RateLimit {
+  SETTINGS
+    .read()
+    .unwrap_or_else(|e| e.into_inner())
+    .unwrap_or_default()
+}
+
+/// Returns the quota the provider called `name` last reported, if it has
+/// been called and reports one.
+pub fn quota(name: &str) -> Option<Quota> {
+  QUOTAS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .get(name)
+    .copied()
+}
+
+/// Returns how long a rate-limited response asks to be waited out, if it
+/// gives a number of seconds.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+  let seconds = headers
+    .get(RETRY_AFTER)?
+    .to_str()
+    .ok()?
+    .trim()
+    .parse()
+    .ok()?;
+  Some(Duration::from_secs(seconds))
+}
+
+/// Sends the request `build` makes to the provider called `name`, waiting
+/// out and retrying rate-limited responses as configured. Fails with
+/// [Error::RateLimited] once the retries run out or the provider asks to
+/// wait too long.
+pub async fn send(
+  name: &str,
+  build: impl Fn() -> RequestBuilder
+) -> Result<Response> {
+  let settings = settings();
+  let mut attempt = 0;
+  loop {
+    let response = build().send().await.map_err(Error::from)?;
+    if let Some(quota) = Quota::from_headers(response.headers()) {
+      QUOTAS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), quota);
+    }
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+      return Ok(response);
+    }
+
+    let wait = retry_after(response.headers()).unwrap_or(FALLBACK_WAIT);
+    attempt += 1;
+    if !settings.allows(attempt, wait) {
+      return Err(Error::RateLimited {
+        provider: name.to_string(),
+        retry_after: Some(wait)
+      });
+    }
+    warn!(
+      "{name} is limiting requests; retrying in {}s ({attempt} of {})",
+      wait.as_secs(),
+      settings.max_retries
+    );
+    sleep(wait).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use reqwest::header::HeaderValue;
+
+  #[test]
+  fn test_quota() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(Quota::from_headers(&headers), None);
+
+    headers.insert("x-ratelimit-limit", HeaderValue::from_static("45"));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from_static("43"));
+    headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+    let quota = Quota::from_headers(&headers).unwrap();
+    assert_eq!(quota.to_string(), "43 of 45 requests left");
+    assert_eq!(retry_after(&headers), Some(Duration::from_secs(12)));
+  }
+}
@@ -0,0 +1,433 @@
+//! Reddit Listings Client
+//!
+//! Reads image posts from subreddits through Reddit's public JSON listings,
+//! which need no account or key. Several subreddits are read in one request
+//! by joining them with `+`, as in `r/wallpapers+EarthPorn/top.json`.
+//!
+//! Only posts linking straight to an image are kept. Their resolution comes
+//! from the post's preview metadata, so nothing is downloaded to filter them.
+
+use super::Concurrency;
+#[cfg(feature = "online-sources")]
+use super::{concurrency, http, retry};
+use crate::{Error, Result};
+#[cfg(feature = "online-sources")]
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  str::FromStr
+};
+
+/// The most posts Reddit returns per listing.
+pub const MAX_LIMIT: u32 = 100;
+
+/// Reddit throttles anonymous clients hard, so listings are read one at a
+/// time.
+pub const CONCURRENCY: Concurrency = Concurrency::new(1, 4);
+
+/// Hosts whose links are always direct images.
+const IMAGE_HOSTS: [&str; 2] = ["i.redd.it", "i.imgur.com"];
+
+// -- Data Structures for API Responses --
+
+/// A page of posts.
+#[derive(Debug, Deserialize)]
+pub struct Listing {
+  pub data: ListingData
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListingData {
+  pub children: Vec<Child>,
+  /// The cursor of the following page, absent on the last one.
+  #[serde(default)]
+  pub after: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Child {
+  pub data: Post
+}
+
+/// Represents a single post, reduced to the fields needed for images.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Post {
+  pub id: String,
+  pub title: String,
+  pub subreddit: String,
+  /// What the post links to.
+  pub url: String,
+  pub permalink: String,
+  pub score: i64,
+  #[serde(default)]
+  pub over_18: bool,
+  #[serde(default)]
+  pub preview: Option<Preview>,
+  /// A small preview of the post, or a placeholder such as `"self"` or
+  /// `"nsfw"` when there is none.
+  #[serde(default)]
+  pub thumbnail: Option<String>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Preview {
+  pub images: Vec<PreviewImage>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PreviewImage {
+  /// The full-size image the previews were made from.
+  pub source: PreviewSource
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PreviewSource {
+  pub width: u32,
+  pub height: u32
+}
+
+impl Post {
+  /// Returns the dimensions of the linked image, if the post links straight
+  /// to one and reports its size.
+  pub fn image_size(&self) -> Option<(u32, u32)> {
+    if !is_image(&self.url) {
+      return None;
+    }
+    let source = &self.preview.as_ref()?.images.first()?.source;
+    Some((source.width, source.height))
+  }
+
+  /// Returns the URL of the post's preview image, if it has one.
+  pub fn thumbnail_url(&self) -> Option<&str> {
+    self
+      .thumbnail
+      .as_deref()
+      .filter(|thumbnail| thumbnail.starts_with("https://"))
+  }
+}
+
+/// Checks if `url` points directly at an image file.
+fn is_image(url: &str) -> bool {
+  let path = url.split(['?', '#']).next().unwrap_or(url);
+  let Some(rest) = path.split_once("://").map(|(_, rest)| rest) else {
+    return false;
+  };
+  let host = rest.split('/').next().unwrap_or(rest);
+  IMAGE_HOSTS.contains(&host)
+    || [".jpg", ".jpeg", ".png"]
+      .iter()
+      .any(|ext| path.to_ascii_lowercase().ends_with(ext))
+}
+
+// -- Enums for Type-Safe Listing Parameters --
+
+/// How a listing is sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+  Hot,
+  New,
+  Top,
+  Rising
+}
+
+impl Display for Sort {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Sort::Hot => "hot",
+        Sort::New => "new",
+        Sort::Top => "top",
+        Sort::Rising => "rising"
+      }
+    )
+  }
+}
+
+impl FromStr for Sort {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "hot" => Ok(Sort::Hot),
+      "new" => Ok(Sort::New),
+      "top" => Ok(Sort::Top),
+      "rising" => Ok(Sort::Rising),
+      _ => Err(Error::Settings(format!("Unknown Reddit sort '{s}'")))
+    }
+  }
+}
+
+/// The time range of a `top` listing or a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeRange {
+  Hour,
+  Day,
+  Week,
+  Month,
+  Year,
+  All
+}
+
+impl Display for TimeRange {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        TimeRange::Hour => "hour",
+        TimeRange::Day => "day",
+        TimeRange::Week => "week",
+        TimeRange::Month => "month",
+        TimeRange::Year => "year",
+        TimeRange::All => "all"
+      }
+    )
+  }
+}
+
+impl FromStr for TimeRange {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "hour" => Ok(TimeRange::Hour),
+      "day" => Ok(TimeRange::Day),
+      "week" => Ok(TimeRange::Week),
+      "month" => Ok(TimeRange::Month),
+      "year" => Ok(TimeRange::Year),
+      "all" => Ok(TimeRange::All),
+      _ => Err(Error::Settings(format!("Unknown time range '{s}'")))
+    }
+  }
+}
+
+/// A builder for listing requests. With a query, the subreddits are
+/// searched instead of listed.
+#[derive(Debug, Default, Clone)]
+pub struct ListingParams {
+  pub subreddits: Vec<String>,
+  pub query: Option<String>,
+  pub sort: Option<Sort>,
+  pub time: Option<TimeRange>,
+  /// Posts per page, up to [MAX_LIMIT].
+  pub limit: Option<u32>,
+  /// The cursor of the page to read, from [ListingData::after].
+  pub after: Option<String>
+}
+
+impl ListingParams {
+  /// Creates a listing of `subreddits`.
+  pub fn new<S: Into<String>>(subreddits: impl IntoIterator<Item = S>) -> Self {
+    Self {
+      subreddits: subreddits.into_iter().map(Into::into).collect(),
+      ..Default::default()
+    }
+  }
+
+  /// Sets a search query, limited to the subreddits.
+  pub fn with_query(mut self, query: impl Into<String>) -> Self {
+    self.query = Some(query.into());
+    self
+  }
+
+  /// Sets the sort order.
+  pub fn with_sort(mut self, sort: Sort) -> Self {
+    self.sort = Some(sort);
+    self
+  }
+
+  /// Sets the time range. Only `top` listings and searches use it.
+  pub fn with_time(mut self, time: TimeRange) -> Self {
+    self.time = Some(time);
+    self
+  }
+
+  /// Sets the number of posts per page. Clamped to [MAX_LIMIT].
+  pub fn with_limit(mut self, limit: u32) -> Self {
+    self.limit = Some(limit.clamp(1, MAX_LIMIT));
+    self
+  }
+
+  /// Sets the page cursor.
+  pub fn with_after(mut self, after: impl Into<String>) -> Self {
+    self.after = Some(after.into());
+    self
+  }
+
+  /// Returns the path of the request, relative to the Reddit root.
+  fn path(&self) -> Result<String> {
+    let names: Vec<&str> = self
+      .subreddits
+      .iter()
+      .map(|name| name.trim().trim_start_matches("r/"))
+      .filter(|name| !name.is_empty())
+      .collect();
+    if names.is_empty() {
+      return Err(Error::Settings("No subreddits to read from".into()));
+    }
+    let listing = match self.query {
+      Some(_) => "search".to_string(),
+      None => self.sort.unwrap_or(Sort::Hot).to_string()
+    };
+    Ok(format!("r/{}/{listing}.json", names.join("+")))
+  }
+
+  /// Returns the query string pairs of the request.
+  fn query_pairs(&self) -> Vec<(&'static str, String)> {
+    let mut query_params = vec![("raw_json", "1".to_string())];
+    if let Some(q) = &self.query {
+      query_params.push(("q", q.clone()));
+      query_params.push(("restrict_sr", "1".to_string()));
+      //{ Searches cannot sort by rising }
+      if let Some(sort) = self.sort.filter(|sort| *sort != Sort::Rising) {
+        query_params.push(("sort", sort.to_string()));
+      }
+    }
+    if let Some(time) = self.time {
+      if self.query.is_some() || self.sort == Some(Sort::Top) {
+        query_params.push(("t", time.to_string()));
+      }
+    }
+    if let Some(limit) = self.limit {
+      query_params.push(("limit", limit.min(MAX_LIMIT).to_string()));
+    }
+    if let Some(after) = &self.after {
+      query_params.push(("after", after.clone()));
+    }
+    query_params
+  }
+}
+
+// -- API Client --
+
+/// The main client for reading Reddit listings.
+#[cfg(feature = "online-sources")]
+#[derive(Debug, Clone)]
+pub struct Api {
+  client: Client,
+  base_url: String
+}
+
+#[cfg(feature = "online-sources")]
+impl Default for Api {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(feature = "online-sources")]
+impl Api {
+  /// Creates a new Reddit client. Reddit throttles generic user agents, so
+  /// the client names itself.
+  pub fn new() -> Self {
+    Self {
+      client: http::builder()
+        .user_agent(concat!("wallter/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default(),
+      base_url: "https://www.reddit.com".to_string()
+    }
+  }
+
+  /// Reads one page of posts.
+  pub async fn listing(&self, params: &ListingParams) -> Result<Listing> {
+    let url = format!("{}/{}", self.base_url, params.path()?);
+    let _permit = concurrency::request("reddit").await;
+    let query = params.query_pairs();
+    let response = retry::send("reddit", || {
+      self
+        .client
+        .get(&url)
+        .query(&query)
+        .timeout(http::timeouts().request())
+    })
+    .await?;
+
+    if !response.status().is_success() {
+      let status = response.status();
+      let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Could not read error body.".to_string());
+      return Err(Error::from_status("reddit", status.as_u16(), error_text));
+    }
+
+    response
+      .json::<Listing>()
+      .await
+      .map_err(|e| Error::API(e.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_listing() {
+    let listing: Listing = serde_json::from_str(
+      r#"{"kind": "Listing", "data": {"after": "t3_b", "children": [
+        {"kind": "t3", "data": {
+          "id": "a", "title": "Lake [3840x2160]", "subreddit": "wallpapers",
+          "url": "https://i.redd.it/abc123.jpeg", "permalink": "/r/wallpapers/a/",
+          "score": 512, "over_18": false,
+          "preview": {"images": [{"source": {"url": "https://x", "width": 3840, "height": 2160}}]}
+        }},
+        {"kind": "t3", "data": {
+          "id": "b", "title": "Gallery", "subreddit": "wallpapers",
+          "url": "https://www.reddit.com/gallery/b", "permalink": "/r/wallpapers/b/",
+          "score": 3
+        }}
+      ]}}"#
+    )
+    .unwrap();
+    let posts: Vec<&Post> = listing
+      .data
+      .children
+      .iter()
+      .map(|child| &child.data)
+      .collect();
+    assert_eq!(listing.data.after.as_deref(), Some("t3_b"));
+    assert_eq!(posts[0].image_size(), Some((3840, 2160)));
+    assert_eq!(posts[1].image_size(), None);
+  }
+
+  #[test]
+  fn test_is_image() {
+    assert!(is_image("https://i.redd.it/abc123"));
+    assert!(is_image("https://example.com/a/photo.PNG?width=10"));
+    assert!(!is_image("https://imgur.com/gallery/abc"));
+    assert!(!is_image("photo.jpg"));
+  }
+
+  #[test]
+  fn test_params() {
+    let params = ListingParams::new(["wallpapers", "r/EarthPorn", " "])
+      .with_sort(Sort::Top)
+      .with_time(TimeRange::Week)
+      .with_limit(500);
+    assert_eq!(params.path().unwrap(), "r/wallpapers+EarthPorn/top.json");
+    assert_eq!(
+      params.query_pairs(),
+      [
+        ("raw_json", "1".to_string()),
+        ("t", "week".to_string()),
+        ("limit", "100".to_string())
+      ]
+    );
+
+    let search = params.with_query("lake");
+    assert_eq!(search.path().unwrap(), "r/wallpapers+EarthPorn/search.json");
+    assert!(
+      search
+        .query_pairs()
+        .contains(&("restrict_sr", "1".to_string()))
+    );
+    assert!(ListingParams::new(Vec::<String>::new()).path().is_err());
+  }
+}
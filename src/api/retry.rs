@@ -0,0 +1,114 @@
+//! Retries requests that fail for a passing reason.
+//!
+//! Timeouts, dropped connections and server errors are usually over in a
+//! moment, so [send] tries again, backing off as the source's [Retry]
+//! policy says, before giving up. Each attempt goes through
+//! [rate_limit::send], so rate limits are waited out as well.
+
+use super::rate_limit;
+use crate::{
+  Error, Result,
+  config::search::{Config, Retry}
+};
+use rand::random_range;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::{collections::HashMap, sync::RwLock, time::Duration};
+use tokio::time::sleep;
+use tracing::warn;
+
+static POLICIES: RwLock<Option<HashMap<String, Retry>>> = RwLock::new(None);
+
+/// Sets the retry policy of every configured source.
+pub fn configure_all(config: &Config) {
+  let policies = config
+    .sources
+    .iter()
+    .map(|source| (source.name.clone(), source.retry()))
+    .collect();
+  *POLICIES.write().unwrap_or_else(|e| e.into_inner()) = Some(policies);
+}
+
+/// Returns the retry policy of the provider called `name`, or the default
+/// if it has none.
+pub fn policy(name: &str) -> Retry {
+  POLICIES
+    .read()
+    .unwrap_or_else(|e| e.into_inner())
+    .as_ref()
+    .and_then(|policies| policies.get(name).copied())
+    .unwrap_or_default()
+}
+
+/// Whether a server answering with `status` may well answer the same
+/// request properly a moment later.
+fn transient_status(status: StatusCode) -> bool {
+  matches!(
+    status,
+    StatusCode::REQUEST_TIMEOUT
+      | StatusCode::INTERNAL_SERVER_ERROR
+      | StatusCode::BAD_GATEWAY
+      | StatusCode::SERVICE_UNAVAILABLE
+      | StatusCode::GATEWAY_TIMEOUT
+  )
+}
+
+/// Whether an attempt failed for a passing reason.
+fn transient(result: &Result<Response>) -> bool {
+  match result {
+    Ok(response) => transient_status(response.status()),
+    Err(Error::Timeout(_)) => true,
+    Err(Error::Network(e)) => e.is_connect() || e.is_request() || e.is_body(),
+    Err(_) => false
+  }
+}
+
+/// Shortens `delay` by a random amount of up to half.
+fn jittered(delay: Duration) -> Duration {
+  delay.mul_f64(random_range(0.5..=1.0))
+}
+
+/// Sends the request `build` makes to the provider called `name`, retrying
+/// it as the provider's policy says while it fails for a passing reason.
+/// The last response or error is returned once the attempts run out.
+pub async fn send(
+  name: &str,
+  build: impl Fn() -> RequestBuilder
+) -> Result<Response> {
+  let policy = policy(name);
+  let mut attempt = 1;
+  loop {
+    let result = rate_limit::send(name, &build).await;
+    if attempt >= policy.max_attempts || !transient(&result) {
+      return result;
+    }
+
+    let mut delay = policy.delay(attempt);
+    if policy.jitter {
+      delay = jittered(delay);
+    }
+    match &result {
+      Ok(response) => warn!(
+        "{name} answered {}; retrying in {delay:.1?}",
+        response.status()
+      ),
+      Err(e) => warn!("{name} failed: {e}; retrying in {delay:.1?}")
+    }
+    sleep(delay).await;
+    attempt += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_transient_status() {
+    assert!(transient_status(StatusCode::SERVICE_UNAVAILABLE));
+    assert!(!transient_status(StatusCode::NOT_FOUND));
+    assert!(!transient_status(StatusCode::TOO_MANY_REQUESTS));
+
+    let delay = Duration::from_secs(4);
+    assert!((delay / 2..=delay).contains(&jittered(delay)));
+  }
+}
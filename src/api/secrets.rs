@@ -0,0 +1,57 @@
+//! Keeps API keys in the system keyring: Windows Credential Manager, the
+//! macOS Keychain or the Secret Service on Linux.
+//!
+//! A source whose `api_key` is `"keyring:<name>"` reads its key from the
+//! entry stored here under `<name>`, so the config file only holds the
+//! reference. Keys are stored with `wallter auth <source> --set-key`.
+
+use super::oauth::KEYRING_SERVICE;
+use crate::{
+  Error, Result,
+  simulate::{self, Action}
+};
+use keyring::Entry;
+
+/// Loads the API key stored under `name`.
+pub fn load(name: &str) -> Result<Option<String>> {
+  match entry(name)?.get_password() {
+    Ok(key) => Ok(Some(key)),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(Error::Config(format!("Failed to read the keyring: {e}")))
+  }
+}
+
+/// Stores `key` under `name`, replacing any key stored before.
+pub fn store(name: &str, key: &str) -> Result<()> {
+  if simulate::dry_run(Action::StoreKey {
+    name: name.to_string(),
+    stored: true
+  })? {
+    return Ok(());
+  }
+  entry(name)?
+    .set_password(key)
+    .map_err(|e| Error::Config(format!("Failed to update the keyring: {e}")))
+}
+
+/// Deletes the API key stored under `name`. Returns whether there was one.
+pub fn forget(name: &str) -> Result<bool> {
+  if simulate::dry_run(Action::StoreKey {
+    name: name.to_string(),
+    stored: false
+  })? {
+    return Ok(load(name)?.is_some());
+  }
+  match entry(name)?.delete_credential() {
+    Ok(()) => Ok(true),
+    Err(keyring::Error::NoEntry) => Ok(false),
+    Err(e) => Err(Error::Config(format!("Failed to update the keyring: {e}")))
+  }
+}
+
+/// Opens the entry for `name`, kept apart from the sign-in of the source
+/// with the same name.
+fn entry(name: &str) -> Result<Entry> {
+  Entry::new(KEYRING_SERVICE, &format!("{name}.api_key"))
+    .map_err(|e| Error::Config(format!("Failed to open the keyring: {e}")))
+}
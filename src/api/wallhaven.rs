@@ -4,10 +4,26 @@
 //! Wallhaven.cc API. It handles authentication, parameter validation, and
 //! deserialization of API responses.
 
+use super::Concurrency;
+#[cfg(feature = "online-sources")]
+use super::{cache, concurrency, download, http, mirrors, retry};
 use crate::{Error, Result};
-use reqwest::Client;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::fmt::{self, Display, Formatter};
+use std::{
+  fmt::{self, Display, Formatter},
+  path::{Path, PathBuf},
+  str::FromStr
+};
+use tracing::{debug, info, warn};
+#[cfg(feature = "online-sources")]
+use {
+  futures_util::{Stream, TryStreamExt, future, stream},
+  reqwest::Client,
+  std::collections::HashSet
+};
+
+/// Wallhaven allows 45 API requests a minute, so requests are kept few.
+pub const CONCURRENCY: Concurrency = Concurrency::new(2, 4);
 
 // -- Data Structures for API Responses --
 
@@ -19,6 +35,15 @@ pub struct PaginatedResponse {
   pub meta: Meta
 }
 
+/// One page of a [search stream](Api::search_pages).
+#[cfg(feature = "online-sources")]
+#[derive(Debug)]
+pub struct Page {
+  pub data: Vec<Wallpaper>,
+  /// The parameters of the page after this one, or `None` on the last.
+  pub next: Option<SearchParams>
+}
+
 /// Represents the top-level structure for a single wallpaper details response.
 #[derive(Debug, Deserialize)]
 pub struct WallpaperDetailsResponse {
@@ -50,7 +75,15 @@ pub struct Wallpaper {
   // The 'tags' field is only present in the detailed wallpaper view
   // (`/w/{id}`). It is optional to handle both search results and detailed
   // views with one struct.
-  pub tags: Option<Vec<Tag>>
+  pub tags: Option<Vec<Tag>>,
+  // Like the tags, the uploader is only present in the detailed view.
+  pub uploader: Option<Uploader>
+}
+
+/// Represents the user who uploaded a wallpaper.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Uploader {
+  pub username: String
 }
 
 /// Represents the thumbnails for a wallpaper.
@@ -130,6 +163,23 @@ impl Display for Sorting {
   }
 }
 
+impl FromStr for Sorting {
+  type Err = Error;
+
+  /// Parses the API name of a sorting method, e.g. `"toplist"`.
+  fn from_str(s: &str) -> Result<Self> {
+    match s.to_ascii_lowercase().as_str() {
+      "date_added" | "latest" => Ok(Sorting::DateAdded),
+      "relevance" => Ok(Sorting::Relevance),
+      "random" => Ok(Sorting::Random),
+      "views" => Ok(Sorting::Views),
+      "favorites" => Ok(Sorting::Favorites),
+      "toplist" => Ok(Sorting::Toplist),
+      _ => Err(Error::Settings(format!("Unknown sorting method '{s}'")))
+    }
+  }
+}
+
 /// Sorting order.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Order {
@@ -180,11 +230,30 @@ impl fmt::Display for ToplistRange {
   }
 }
 
+impl FromStr for ToplistRange {
+  type Err = Error;
+
+  /// Parses the API name of a toplist range, e.g. `"1M"`. Note that `"1m"`
+  /// is not accepted, as months and minutes are easily confused.
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "1d" => Ok(ToplistRange::Day),
+      "3d" => Ok(ToplistRange::Days3),
+      "1w" => Ok(ToplistRange::Week),
+      "1M" => Ok(ToplistRange::Month),
+      "3M" => Ok(ToplistRange::Months3),
+      "6M" => Ok(ToplistRange::Months6),
+      "1y" => Ok(ToplistRange::Year),
+      _ => Err(Error::Settings(format!("Unknown toplist range '{s}'")))
+    }
+  }
+}
+
 // -- Search Parameters Builder --
 
 /// Represents the parameters for a Wallhaven API search.
 /// Use the builder methods to construct a search query.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct SearchParams {
   pub query: Option<String>,
   pub categories: Option<(bool, bool, bool)>,
@@ -281,15 +350,78 @@ impl SearchParams {
     self.seed = Some(seed.into());
     self
   }
+
+  /// Identifies the search apart from its page and seed, so that where a
+  /// random search stopped can be remembered.
+  #[cfg(feature = "online-sources")]
+  pub fn key(&self) -> String {
+    let search = Self {
+      page: None,
+      seed: None,
+      ..self.clone()
+    };
+    let fields = serde_json::to_vec(&search).unwrap_or_default();
+    blake3::hash(&fields).to_hex()[..16].to_string()
+  }
+
+  /// Returns the parameters for the page after the one `meta` describes,
+  /// keeping the seed of a random search so its pages do not overlap, or
+  /// `None` on the last page.
+  pub fn next_page(&self, meta: &Meta) -> Option<Self> {
+    if meta.current_page >= meta.last_page {
+      return None;
+    }
+    let mut next = self.clone().with_page(meta.current_page + 1);
+    if let Some(seed) = &meta.seed {
+      next = next.with_seed(seed);
+    }
+    Some(next)
+  }
+}
+
+/// Checks whether `value` looks like a Wallhaven id.
+pub fn is_id(value: &str) -> bool {
+  !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// The length of the ids Wallhaven gives wallpapers, such as `94x38z`.
+pub const ID_LENGTH: usize = 6;
+
+/// Checks whether `value` has the shape of a whole Wallhaven id, as opposed
+/// to any word that [is_id] would let through.
+pub fn is_full_id(value: &str) -> bool {
+  value.len() == ID_LENGTH && is_id(value)
+}
+
+/// Reads the Wallhaven id from a `wallhaven-<id>` file name, as used for
+/// downloaded wallpapers, and from names a
+/// [template](crate::wallpaper::naming) adds more to after the id.
+pub fn id_from_path(file: &Path) -> Option<String> {
+  let stem = file.file_stem()?.to_str()?;
+  //{ Names from a template may carry more after the id }
+  stem
+    .strip_prefix("wallhaven-")
+    .and_then(|rest| rest.split(['-', '_', ' ', '.']).next())
+    .filter(|id| is_id(id))
+    .map(str::to_string)
+}
+
+/// Returns the address of the Wallhaven page for the wallpaper `id`.
+pub fn page_url(id: &str) -> String {
+  format!("https://wallhaven.cc/w/{id}")
 }
 
 /// The main Wallhaven API client.
+#[cfg(feature = "online-sources")]
 pub struct Api {
   client: Client,
   base_url: String,
-  api_key: Option<String>
+  api_key: Option<String>,
+  /// The parameters [Provider](super::Provider) searches start from.
+  defaults: SearchParams
 }
 
+#[cfg(feature = "online-sources")]
 impl Api {
   /// Creates a new Wallhaven API client.
   ///
@@ -297,49 +429,74 @@ impl Api {
   /// * `api_key` - An optional API key for authenticated requests.
   pub fn new(api_key: Option<String>) -> Self {
     Self {
-      client: Client::new(),
+      client: http::client(),
       base_url: "https://wallhaven.cc/api/v1".to_string(),
-      api_key
+      api_key,
+      defaults: SearchParams::default()
     }
   }
 
+  /// Sets the parameters that searches through the
+  /// [Provider](super::Provider) trait start from, such as the configured
+  /// categories and purity.
+  pub fn with_defaults(mut self, defaults: SearchParams) -> Self {
+    self.defaults = defaults;
+    self
+  }
+
+  /// Returns the parameters [Provider](super::Provider) searches start from.
+  pub fn defaults(&self) -> &SearchParams {
+    &self.defaults
+  }
+
   /// Checks if an API key is configured.
   fn has_api_key(&self) -> bool {
     self.api_key.is_some()
   }
 
   /// Sends a request, handling authentication and error responses.
+  ///
+  /// Responses are [cached](cache), except random searches without a seed,
+  /// which are meant to differ every time.
   async fn send_request<T: for<'de> Deserialize<'de>>(
     &self,
     url: String,
     params: &[(&str, String)]
   ) -> Result<T> {
-    let mut request = self.client.get(&url).query(params);
-
-    // Add API key to header if available.
-    // The API also allows it as a query param `?apikey=...`, but header is
-    // cleaner.
-    if let Some(key) = &self.api_key {
-      request = request.header("X-API-Key", key);
-    }
-
-    let response = request.send().await.map_err(Error::Network)?;
-
-    if !response.status().is_success() {
-      let status = response.status();
-      let error_text = response
-        .text()
-        .await
-        .unwrap_or_else(|_| "Could not read error body.".to_string());
-      return Err(Error::API(format!(
-        "API request failed with status {status}: {error_text}"
-      )));
+    let _permit = concurrency::request("wallhaven").await;
+    let build = || {
+      let request = self
+        .client
+        .get(&url)
+        .query(params)
+        .timeout(http::timeouts().request());
+
+      // Add API key to header if available.
+      // The API also allows it as a query param `?apikey=...`, but header is
+      // cleaner.
+      match &self.api_key {
+        Some(key) => request.header("X-API-Key", key),
+        None => request
+      }
+    };
+    let random = params
+      .iter()
+      .any(|(name, value)| *name == "sorting" && value == "random");
+    let seeded = params.iter().any(|(name, _)| *name == "seed");
+    let response = if random && !seeded {
+      cache::Cached::read(retry::send("wallhaven", build).await?).await?
+    } else {
+      cache::send("wallhaven", build).await?
+    };
+
+    if !response.status.is_success() {
+      return Err(Error::from_status(
+        "wallhaven",
+        response.status.as_u16(),
+        response.text()
+      ));
     }
-
-    response
-      .json::<T>()
-      .await
-      .map_err(|e| Error::API(e.to_string()))
+    response.json::<T>()
   }
 
   /// Searches for wallpapers on Wallhaven.
@@ -367,8 +524,8 @@ impl Api {
 
     if let Some(mut purities) = params.purity {
       if purities.2 && !self.has_api_key() {
-        eprintln!(
-          "Warning: NSFW purity filter requires an API key. Disabling NSFW for this search."
+        warn!(
+          "NSFW purity filter requires an API key. Disabling NSFW for this search."
         );
         purities.2 = false; // Disable NSFW
       }
@@ -388,8 +545,8 @@ impl Api {
           query_params.push(("topRange", range.to_string()));
         }
       } else if params.top_range.is_some() {
-        eprintln!(
-          "Warning: `top_range` is only effective when `sorting` is `Toplist`. It will be ignored."
+        warn!(
+          "`top_range` is only effective when `sorting` is `Toplist`. It will be ignored."
         );
       }
     }
@@ -425,6 +582,59 @@ impl Api {
     self.send_request(url, &query_params).await
   }
 
+  /// Streams every wallpaper matching `params`, starting at their page,
+  /// fetching each following page only once the one before is used up. A
+  /// wallpaper repeated on a later page, as happens when new uploads shift
+  /// the results, is only yielded once.
+  ///
+  /// The stream ends after the last page, or after yielding the error of a
+  /// page that failed.
+  pub fn search_iter(
+    &self,
+    params: &SearchParams
+  ) -> impl Stream<Item = Result<Wallpaper>> + '_ {
+    let mut seen = HashSet::new();
+    self
+      .search_pages(params)
+      .map_ok(|page| stream::iter(page.data.into_iter().map(Ok)))
+      .try_flatten()
+      .try_filter(move |wallpaper| {
+        future::ready(seen.insert(wallpaper.id.clone()))
+      })
+  }
+
+  /// Streams the pages of wallpapers matching `params` like
+  /// [search_iter](Self::search_iter), each with the parameters of the page
+  /// after it, for callers that pick up where they stopped later.
+  pub fn search_pages(
+    &self,
+    params: &SearchParams
+  ) -> impl Stream<Item = Result<Page>> + '_ {
+    stream::try_unfold(Some(params.clone()), move |params| self.page(params))
+  }
+
+  /// Fetches the page `params` ask for, if any, along with the parameters
+  /// of the page after it.
+  async fn page(
+    &self,
+    params: Option<SearchParams>
+  ) -> Result<Option<(Page, Option<SearchParams>)>> {
+    let Some(params) = params else {
+      return Ok(None);
+    };
+    let response = self.search(&params).await?;
+    let next = if response.data.is_empty() {
+      None
+    } else {
+      params.next_page(&response.meta)
+    };
+    let page = Page {
+      data: response.data,
+      next: next.clone()
+    };
+    Ok(Some((page, next)))
+  }
+
   /// Retrieves details for a specific wallpaper by its ID.
   /// An API key is required to view NSFW wallpapers.
   pub async fn get_wallpaper_details(&self, id: &str) -> Result<Wallpaper> {
@@ -443,22 +653,72 @@ impl Api {
   // Result<Vec<Collection>> { ... }
 
   /// Downloads a wallpaper image from its direct URL (`wallpaper.path`).
+  ///
+  /// The payload is validated as an image before it is written, and the
+  /// extension of `path` is corrected to match the actual image format.
+  /// Returns the path the image was written to.
+  ///
+  /// If the server reports the image missing, the [mirrors] are tried in
+  /// turn, and [Error::Gone] is returned if none of them has it either.
   pub async fn download_wallpaper(
     &self,
     url: &str,
-    path: &std::path::Path
-  ) -> Result<()> {
-    let response = self.client.get(url).send().await.map_err(Error::Network)?;
-
-    if !response.status().is_success() {
-      let status = response.status();
-      return Err(Error::API(format!(
-        "Failed to download wallpaper: Status {status}"
-      )));
+    path: &Path
+  ) -> Result<PathBuf> {
+    let _permit = concurrency::download("wallhaven").await;
+    if let Some(path) =
+      download::file(&self.client, "wallhaven", url, path).await?
+    {
+      return Ok(path);
     }
+    for mirror in mirrors::alternates(url) {
+      match download::file(&self.client, "wallhaven", &mirror, path).await {
+        Ok(Some(path)) => {
+          info!("'{url}' is missing; downloaded it from '{mirror}'");
+          return Ok(path);
+        }
+        Ok(None) => debug!("The mirror '{mirror}' is missing it too"),
+        Err(e) => debug!("The mirror '{mirror}' failed: {e}")
+      }
+    }
+    Err(Error::Gone(url.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_next_page() {
+    let mut meta = Meta {
+      current_page: 1,
+      last_page: 2,
+      per_page: 24,
+      total: 30,
+      query: None,
+      seed: Some("abc123".into())
+    };
+    let params = SearchParams::new()
+      .with_query("lake")
+      .with_sorting(Sorting::Random);
+    let next = params.next_page(&meta).unwrap();
+    assert_eq!(next.page, Some(2));
+    assert_eq!(next.seed.as_deref(), Some("abc123"));
+    assert_eq!(next.query.as_deref(), Some("lake"));
+
+    meta.current_page = 2;
+    assert!(next.next_page(&meta).is_none());
+  }
 
-    let bytes = response.bytes().await.map_err(Error::Network)?;
-    tokio::fs::write(path, bytes).await.map_err(Error::IO)?;
-    Ok(())
+  #[cfg(feature = "online-sources")]
+  #[test]
+  fn test_key_ignores_page_and_seed() {
+    let params = SearchParams::new()
+      .with_query("lake")
+      .with_sorting(Sorting::Random);
+    let next = params.clone().with_page(2).with_seed("abc123");
+    assert_eq!(next.key(), params.key());
+    assert_ne!(params.with_query("sea").key(), next.key());
   }
 }
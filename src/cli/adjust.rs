@@ -0,0 +1,85 @@
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Error, Result,
+  wallpaper::{self, Current, Library, Source, adjust}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// The monitor to adjust (defaults to the primary monitor)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>,
+
+  /// Move the view right by a share of the image width, e.g. 10%; negative
+  /// moves it left
+  #[arg(
+    long,
+    value_name = "SHARE",
+    allow_hyphen_values = true,
+    value_parser = adjust::parse_share
+  )]
+  pub pan_x: Option<f32>,
+
+  /// Move the view down by a share of the image height, e.g. 10%; negative
+  /// moves it up
+  #[arg(
+    long,
+    value_name = "SHARE",
+    allow_hyphen_values = true,
+    value_parser = adjust::parse_share
+  )]
+  pub pan_y: Option<f32>,
+
+  /// Magnify the view, from 1 for the whole image filling the monitor
+  #[arg(long, value_name = "FACTOR")]
+  pub zoom: Option<f32>,
+
+  /// Forget the adjustment and show the image as usual
+  #[arg(long, conflicts_with_all = ["pan_x", "pan_y", "zoom"])]
+  pub reset: bool
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let monitor = wallpaper::resolve_monitor(config, args.monitor.as_deref())?;
+  let Some(applied) = Current::load(&config.path)?.get(&monitor.name).cloned()
+  else {
+    return Err(Error::NothingToDo(format!(
+      "Nothing is shown on {} yet",
+      monitor.name
+    )));
+  };
+  let image = applied.image;
+
+  let mut library = Library::load(&config.path)?;
+  let adjustment = if args.reset {
+    if !library.forget_adjustment(&image, &monitor.name) {
+      println!("[{}] The wallpaper is not adjusted", monitor.name);
+      return Ok(());
+    }
+    None
+  } else {
+    let mut adjustment = library
+      .adjustment(&image, &monitor.name)
+      .unwrap_or_default();
+    if let Some(pan_x) = args.pan_x {
+      adjustment.pan_x = pan_x;
+    }
+    if let Some(pan_y) = args.pan_y {
+      adjustment.pan_y = pan_y;
+    }
+    if let Some(zoom) = args.zoom {
+      adjustment.zoom = zoom;
+    }
+    adjustment.validate()?;
+    library.adjust(&image, &monitor.name, adjustment);
+    Some(adjustment)
+  };
+  library.save(&config.path)?;
+
+  wallpaper::set(config, &Source::File(image), Some(&monitor.name)).await?;
+  match adjustment {
+    Some(adjustment) => println!("[{}] Adjusted: {adjustment}", monitor.name),
+    None => println!("[{}] Adjustment removed", monitor.name)
+  }
+  Ok(())
+}
@@ -0,0 +1,153 @@
+use clap::Args as ClapArgs;
+use std::io::{IsTerminal, stdin};
+use wallter::{
+  Config, Error, Result,
+  api::{oauth, secrets},
+  config::search::{KEYRING_PREFIX, Source}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// The source to sign in to, e.g. unsplash
+  #[arg(value_name = "SOURCE")]
+  pub source: String,
+
+  /// Forget the stored sign-in instead
+  #[arg(long, conflicts_with = "status")]
+  pub logout: bool,
+
+  /// Show whether you are signed in and where the API key comes from
+  #[arg(long)]
+  pub status: bool,
+
+  /// Read an API key from standard input, store it in the system keyring
+  /// and point the config at it
+  #[arg(long, conflicts_with_all = ["logout", "status", "forget_key"])]
+  pub set_key: bool,
+
+  /// Remove the API key from the system keyring
+  #[arg(long, conflicts_with_all = ["logout", "status"])]
+  pub forget_key: bool
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let name = &args.source;
+  let source = config.source.find(name).ok_or_else(|| {
+    Error::Config(format!("No source named '{name}' is configured"))
+  })?;
+
+  if args.set_key {
+    return set_key(config, source);
+  }
+
+  if args.forget_key {
+    return forget_key(config, source);
+  }
+
+  if args.logout {
+    if oauth::forget(&source.name)? {
+      println!("Signed out of {}.", source.name);
+    } else {
+      println!("Not signed in to {}.", source.name);
+    }
+    return Ok(());
+  }
+
+  if args.status {
+    let status = match oauth::load(&source.name)? {
+      None => "Not signed in".to_string(),
+      Some(token) => match token.expires_at {
+        Some(at) if token.is_expired() && token.refresh_token.is_none() =>
+          format!("Expired at {}", at.format("%Y-%m-%d %H:%M")),
+        Some(_) if token.refresh_token.is_some() =>
+          "Signed in (renewed automatically)".to_string(),
+        Some(at) => format!("Signed in until {}", at.format("%Y-%m-%d %H:%M")),
+        None => "Signed in".to_string()
+      }
+    };
+    println!("    {:<24}=| {status}", source.name);
+    println!("    {:<24}=| {}", "API Key", key_origin(source));
+    return Ok(());
+  }
+
+  oauth::authorize(source).await?;
+  println!("Signed in to {}.", source.name);
+  Ok(())
+}
+
+/// Stores a key read from standard input in the keyring, under the entry
+/// the config already refers to or else under the source name.
+fn set_key(config: &Config, source: &Source) -> Result<()> {
+  if stdin().is_terminal() {
+    println!("Paste the {} API key and press Enter:", source.name);
+  }
+  let mut key = String::new();
+  stdin().read_line(&mut key)?;
+  let key = key.trim();
+  if key.is_empty() {
+    return Err(Error::Config("No API key was given".into()));
+  }
+
+  let entry = source.keyring_entry().unwrap_or(&source.name).to_string();
+  secrets::store(&entry, key)?;
+  point_to(
+    config,
+    &source.name,
+    Some(format!("{KEYRING_PREFIX}{entry}"))
+  )?;
+  println!("Stored the {} API key in the keyring.", source.name);
+  Ok(())
+}
+
+/// Removes the key from the keyring, and the reference to it from the
+/// config.
+fn forget_key(config: &Config, source: &Source) -> Result<()> {
+  let entry = source.keyring_entry().unwrap_or(&source.name).to_string();
+  if secrets::forget(&entry)? {
+    println!("Removed the {} API key from the keyring.", source.name);
+  } else {
+    println!("No {} API key was in the keyring.", source.name);
+  }
+  if source.keyring_entry().is_some() {
+    point_to(config, &source.name, None)?;
+  }
+  Ok(())
+}
+
+/// Saves `api_key` as the key of the source called `name` in the config
+/// file.
+fn point_to(
+  config: &Config,
+  name: &str,
+  api_key: Option<String>
+) -> Result<()> {
+  let mut saved = Config::load(&config.path)?;
+  if let Some(source) = saved
+    .source
+    .sources
+    .iter_mut()
+    .find(|source| source.name == name)
+  {
+    source.api_key = api_key;
+    saved.save(&config.path)?;
+  }
+  Ok(())
+}
+
+/// Describes where the API key of `source` is read from.
+fn key_origin(source: &Source) -> String {
+  let env_var = source.env_var();
+  if std::env::var_os(&env_var).is_some() {
+    format!("From ${env_var}")
+  } else if let Some(entry) = source.keyring_entry() {
+    match secrets::load(entry) {
+      Ok(Some(_)) => format!("In the keyring as '{entry}'"),
+      Ok(None) => format!("Missing from the keyring ('{entry}')"),
+      Err(e) => format!("Unreadable: {e}")
+    }
+  } else if source.api_key.is_some() {
+    "In the config file".to_string()
+  } else {
+    "Not set".to_string()
+  }
+}
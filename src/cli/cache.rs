@@ -0,0 +1,73 @@
+use clap::Subcommand;
+#[cfg(feature = "thumbnails")]
+use wallter::wallpaper::thumbnails;
+use wallter::{
+  Config, Result,
+  api::cache as http,
+  wallpaper::cache::{self, Usage}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Show how much space downloads, thumbnails and cached responses take
+  Status,
+  /// Delete downloads that aren't on screen or favorites, thumbnails and
+  /// cached responses
+  Purge {
+    /// Only delete the thumbnails
+    #[arg(long)]
+    thumbnails: bool
+  }
+}
+
+pub fn run(config: &Config, command: &Command) -> Result<()> {
+  match command {
+    Command::Status => status(config),
+    Command::Purge { thumbnails } => purge(config, *thumbnails)
+  }
+}
+
+fn status(config: &Config) -> Result<()> {
+  let downloads = Usage::of(&config.path.downloads_dir)?;
+  let limit = match cache::limit(config) {
+    Some(bytes) => format!("{} MiB", bytes / (1024 * 1024)),
+    None => "Unlimited".to_string()
+  };
+  println!("Cache:");
+  println!("    {:<24}=| {}", "Downloads", describe(downloads));
+  println!("    {:<24}=| {limit}", "Max Cache Size");
+  println!(
+    "    {:<24}=| {}",
+    "HTTP Responses",
+    describe(Usage::of(&http::dir(config))?)
+  );
+  #[cfg(feature = "thumbnails")]
+  {
+    let previews = Usage::of(&thumbnails::dir(config))?;
+    println!("    {:<24}=| {}", "Thumbnails", describe(previews));
+    println!(
+      "    {:<24}=| {}",
+      "Thumbnail Limit", config.wallpaper.thumbnails
+    );
+  }
+  Ok(())
+}
+
+fn purge(config: &Config, thumbnails_only: bool) -> Result<()> {
+  if !thumbnails_only {
+    let removed = cache::purge(config)?;
+    println!("Deleted {} downloads.", removed.len());
+    println!("Deleted {} cached responses.", http::clear(config)?);
+  }
+  #[cfg(feature = "thumbnails")]
+  println!("Deleted {} thumbnails.", thumbnails::clear(config)?);
+  Ok(())
+}
+
+fn describe(usage: Usage) -> String {
+  format!(
+    "{} files, {:.1} MiB",
+    usage.files,
+    usage.bytes as f64 / (1024.0 * 1024.0)
+  )
+}
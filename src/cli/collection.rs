@@ -0,0 +1,229 @@
+use super::output::Format;
+use chrono::{DateTime, Local};
+use clap::Subcommand;
+use serde::Serialize;
+use std::path::PathBuf;
+use wallter::{
+  Config, Error, Result,
+  slideshow::{self, Prefetch, State},
+  utils::files,
+  wallpaper::{self, Current, Library}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Create an empty collection
+  Create {
+    /// The name of the collection, e.g. minimal-dark
+    name: String
+  },
+  /// Add wallpapers to a collection, or move them within it
+  Add {
+    /// The collection to add to
+    name: String,
+    /// The wallpapers to add (defaults to the one on screen)
+    #[arg(value_name = "current|FILE", default_value = "current")]
+    targets: Vec<String>,
+    /// With 'current', the monitor to take it from
+    #[arg(short, long, value_name = "NAME|ID")]
+    monitor: Option<String>,
+    /// Where to put them, counting from 1 (defaults to the end)
+    #[arg(long, value_name = "POSITION")]
+    at: Option<usize>
+  },
+  /// Take wallpapers out of a collection, leaving their images on disk
+  Remove {
+    /// The collection to remove from
+    name: String,
+    /// File names, paths or positions of the wallpapers
+    #[arg(value_name = "FILE|POSITION", required = true)]
+    keys: Vec<String>
+  },
+  /// Delete a collection, leaving its images on disk
+  Delete {
+    /// The collection to delete
+    name: String
+  },
+  /// Show the collections, or the wallpapers of one in order
+  List {
+    /// The collection to show
+    name: Option<String>
+  },
+  /// Have the slideshow step through a collection instead of its usual
+  /// sources, starting now
+  SetAsSlideshowSource {
+    /// The collection to show; leave out to return to the usual sources
+    name: Option<String>
+  }
+}
+
+/// A collection, as listed in structured output.
+#[derive(Serialize)]
+struct Row<'a> {
+  name: &'a str,
+  images: usize,
+  slideshow_source: bool,
+  created_at: DateTime<Local>
+}
+
+/// A wallpaper of a collection, as listed in structured output.
+#[derive(Serialize)]
+struct ImageRow<'a> {
+  position: usize,
+  file: &'a PathBuf
+}
+
+pub async fn run(
+  config: &Config,
+  command: &Command,
+  format: Format
+) -> Result<()> {
+  let mut library = Library::load(&config.path)?;
+  match command {
+    Command::Create { name } => {
+      library.create_collection(name)?;
+      library.save(&config.path)?;
+      println!("Created the collection {name}");
+    }
+    Command::Add {
+      name,
+      targets,
+      monitor,
+      at
+    } => {
+      let mut images = Vec::new();
+      for target in targets {
+        images.push(resolve(config, target, monitor.as_deref())?);
+      }
+      let collection = library.collection_mut(name)?;
+      for (offset, image) in images.into_iter().enumerate() {
+        let position = at.map(|at| at + offset);
+        println!("Added to {name}: {}", image.display());
+        collection.insert(image, position);
+      }
+      library.save(&config.path)?;
+    }
+    Command::Remove { name, keys } => {
+      //{ Positions refer to the collection as it was listed }
+      let collection = library.collection_mut(name)?;
+      let images: Vec<PathBuf> = keys
+        .iter()
+        .map(|key| {
+          collection.get(key).cloned().ok_or_else(|| {
+            Error::NothingToDo(format!(
+              "'{key}' is not in the collection {name}"
+            ))
+          })
+        })
+        .collect::<Result<_>>()?;
+      collection.images.retain(|image| !images.contains(image));
+      library.save(&config.path)?;
+      for image in images {
+        println!("Removed from {name}: {}", image.display());
+      }
+    }
+    Command::Delete { name } => {
+      library.collection(name)?;
+      library.collections.remove(name);
+      library.save(&config.path)?;
+      let mut state = State::load(&config.path)?;
+      if state.collection.as_ref() == Some(name) {
+        state.collection = None;
+        state.save(&config.path)?;
+        println!("The slideshow is back to its usual sources");
+      }
+      println!("Deleted the collection {name}");
+    }
+    Command::List { name: Some(name) } => {
+      let collection = library.collection(name)?;
+      let rows: Vec<ImageRow> = collection
+        .images
+        .iter()
+        .enumerate()
+        .map(|(index, file)| ImageRow {
+          position: index + 1,
+          file
+        })
+        .collect();
+      if !format.print(&rows)? {
+        print!("{name}:\n{collection}");
+      }
+    }
+    Command::List { name: None } => {
+      let source = State::load(&config.path)?.collection;
+      let rows: Vec<Row> = library
+        .collections
+        .iter()
+        .map(|(name, collection)| Row {
+          name,
+          images: collection.images.len(),
+          slideshow_source: source.as_ref() == Some(name),
+          created_at: collection.created_at
+        })
+        .collect();
+      if !format.print(&rows)? {
+        println!("Collections:");
+        if rows.is_empty() {
+          println!("    {:<24}=| [None]", "Collections");
+        }
+        for row in rows {
+          let marker = if row.slideshow_source {
+            ", slideshow source"
+          } else {
+            ""
+          };
+          println!("    {:<24}=| {} images{marker}", row.name, row.images);
+        }
+      }
+    }
+    Command::SetAsSlideshowSource { name } => {
+      if let Some(name) = name {
+        library.collection(name)?;
+      }
+      let mut state = State::load(&config.path)?;
+      state.collection = name.clone();
+      state.save(&config.path)?;
+
+      //{ What was fetched ahead came from the previous source }
+      let mut prefetch = Prefetch::load(&config.path)?;
+      prefetch.monitors.clear();
+      prefetch.save(&config.path)?;
+
+      let Some(name) = name else {
+        println!("The slideshow is back to its usual sources");
+        return Ok(());
+      };
+      println!("The slideshow now steps through {name}");
+      for monitor in &config.monitors {
+        match slideshow::advance(config, monitor).await? {
+          Some(source) => println!("[{}] Now showing: {source}", monitor.name),
+          None => println!("[{}] Nothing to show", monitor.name)
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Returns the image `target` names: `current` for the wallpaper on
+/// `monitor`, or an image file.
+fn resolve(
+  config: &Config,
+  target: &str,
+  monitor: Option<&str>
+) -> Result<PathBuf> {
+  if target == "current" {
+    let monitor = wallpaper::resolve_monitor(config, monitor)?;
+    return Current::load(&config.path)?
+      .get(&monitor.name)
+      .map(|applied| applied.image.clone())
+      .ok_or_else(|| {
+        Error::NothingToDo(format!("Nothing is shown on {} yet", monitor.name))
+      });
+  }
+  let image = PathBuf::from(target);
+  if !files::is_image(&image) || !image.is_file() {
+    return Err(Error::Settings(format!("'{target}' is not an image file")));
+  }
+  Ok(image.canonicalize()?)
+}
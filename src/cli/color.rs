@@ -0,0 +1,74 @@
+use clap::Subcommand;
+use wallter::{
+  Config, Result,
+  config::{ColorMode, conflicts::Subsystem}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Set and apply the configured color mode
+  Set {
+    /// The mode to use; auto leaves it to the system
+    #[arg(value_name = "MODE", value_parser = ["light", "dark", "auto"])]
+    mode: String
+  },
+  /// Switch the system between light and dark
+  Toggle,
+  /// Show the configured and the detected system color mode
+  Status
+}
+
+pub fn run(config: &Config, command: &Command) -> Result<()> {
+  match command {
+    Command::Set { mode } => {
+      let mode = match mode.as_str() {
+        "light" => ColorMode::Light,
+        "dark" => ColorMode::Dark,
+        _ => ColorMode::Auto
+      };
+      save_mode(config, mode)?;
+      if let Some(manager) = config.conflicts.yield_to(Subsystem::ColorMode) {
+        println!(
+          "Color mode saved as {mode}; the system mode is left to {manager}."
+        );
+        return Ok(());
+      }
+      config
+        .color
+        .clone()
+        .with_mode(mode)
+        .apply_mode(&config.monitors)?;
+      println!("Color mode set to {mode}.");
+      Ok(())
+    }
+    Command::Toggle => {
+      if let Some(manager) = config.conflicts.yield_to(Subsystem::ColorMode) {
+        println!("The color mode is left to {manager}; nothing toggled.");
+        return Ok(());
+      }
+      let mode = ColorMode::toggle()?;
+      config.color.follow(mode, &config.monitors)?;
+
+      //{ Keep an explicit configured mode in step, or the next run would
+      //{ switch the system straight back }
+      if config.color.mode != ColorMode::Auto {
+        save_mode(config, mode)?;
+      }
+      println!("Color mode toggled to {mode}.");
+      Ok(())
+    }
+    Command::Status => {
+      println!("Color Mode:");
+      println!("    {:<24}=| {}", "Configured", config.color.mode);
+      println!("    {:<24}=| {}", "System", ColorMode::get_current());
+      Ok(())
+    }
+  }
+}
+
+/// Stores `mode` in the configuration file.
+fn save_mode(config: &Config, mode: ColorMode) -> Result<()> {
+  let mut saved = Config::load(&config.path)?;
+  saved.color.mode = mode;
+  saved.save(&config.path)
+}
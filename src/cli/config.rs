@@ -0,0 +1,96 @@
+use clap::Subcommand;
+use serde_json::Value;
+use std::{env, process};
+use wallter::{
+  Config, Error, Result,
+  config::keys,
+  simulate::{self, Action}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Print the whole configuration, with detected monitors and paths
+  Show,
+  /// Print the config file location
+  Path,
+  /// Print a setting
+  Get {
+    /// A dotted key, e.g. slideshow.interval
+    #[arg(value_name = "KEY")]
+    key: String
+  },
+  /// Change a setting and save the config file
+  Set {
+    /// A dotted key, e.g. color.mode
+    #[arg(value_name = "KEY")]
+    key: String,
+    /// The new value, as plain text or JSON
+    #[arg(value_name = "VALUE")]
+    value: String
+  },
+  /// Open the config file in $VISUAL or $EDITOR
+  Edit
+}
+
+pub fn run(config: &Config, command: &Command) -> Result<()> {
+  let file = &config.path.config_file;
+  match command {
+    Command::Show => {
+      println!("{config}");
+      Ok(())
+    }
+    Command::Get { key } => {
+      match keys::get(&Config::load(&config.path)?, key)? {
+        Value::String(value) => println!("{value}"),
+        value => println!(
+          "{}",
+          serde_json::to_string_pretty(&value)
+            .map_err(|e| Error::Config(e.to_string()))?
+        )
+      }
+      Ok(())
+    }
+    Command::Set { key, value } => {
+      let updated = keys::set(&Config::load(&config.path)?, key, value)?;
+      updated.save(&config.path)?;
+      println!("Set {key} = {}", keys::get(&updated, key)?);
+      Ok(())
+    }
+    Command::Edit => {
+      let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+          if cfg!(windows) { "notepad" } else { "vi" }.to_string()
+        });
+      if simulate::dry_run(Action::Spawn {
+        command: format!("{editor} {}", file.display())
+      })? {
+        return Ok(());
+      }
+      let status =
+        process::Command::new(&editor)
+          .arg(file)
+          .status()
+          .map_err(|e| {
+            Error::Config(format!("Failed to launch editor '{editor}': {e}"))
+          })?;
+      if !status.success() {
+        return Err(Error::Config(format!("Editor '{editor}' failed")));
+      }
+
+      //{ An unreadable config is replaced with the defaults on the next
+      //{ run, so point out mistakes while they are easy to fix }
+      Config::load(&config.path).map_err(|e| {
+        Error::Config(format!(
+          "The config file no longer parses and will be reset on the next run unless fixed: {e}"
+        ))
+      })?;
+      println!("Config saved.");
+      Ok(())
+    }
+    Command::Path => {
+      println!("{}", file.display());
+      Ok(())
+    }
+  }
+}
@@ -0,0 +1,44 @@
+use chrono::Local;
+use clap::Subcommand;
+use std::path::PathBuf;
+use wallter::{Config, Result, support::Bundle};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Collect the config, monitors, platform and recent errors into a ZIP
+  /// file to attach to a bug report. Secrets are redacted.
+  Bundle {
+    /// Where to write the bundle [default: wallter-debug-<time>.zip]
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Include the end of this log file, e.g. the daemon's --log-file
+    #[arg(long = "log", value_name = "PATH")]
+    logs: Vec<PathBuf>
+  }
+}
+
+pub fn run(config: &Config, command: &Command) -> Result<()> {
+  match command {
+    Command::Bundle { output, logs } => bundle(config, output.clone(), logs)
+  }
+}
+
+fn bundle(
+  config: &Config,
+  output: Option<PathBuf>,
+  logs: &[PathBuf]
+) -> Result<()> {
+  let output = output.unwrap_or_else(|| {
+    let time = Local::now().format("%Y%m%d-%H%M%S");
+    PathBuf::from(format!("wallter-debug-{time}.zip"))
+  });
+  let bundle = Bundle::collect(config, logs);
+  bundle.write(&output)?;
+
+  println!("Wrote {}:", output.display());
+  for (name, data) in &bundle.files {
+    println!("    {name:<24}=| {} bytes", data.len());
+  }
+  println!("Check it for anything private before sharing it.");
+  Ok(())
+}
@@ -0,0 +1,21 @@
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Result,
+  wallpaper::digest::{self, Outcome}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Fetch a new wallpaper even if today's was already fetched
+  #[arg(short, long)]
+  pub force: bool
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  match digest::run(config, args.force).await? {
+    Outcome::Fetched(source) => println!("Today's wallpaper: {source}"),
+    Outcome::Current(image) =>
+      println!("Today's wallpaper is already set: {}", image.display()),
+  }
+  Ok(())
+}
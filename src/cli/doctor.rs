@@ -0,0 +1,13 @@
+use wallter::{Config, Result, support::Diagnosis};
+
+pub fn run(config: &Config) -> Result<()> {
+  let diagnosis = Diagnosis::detect(config);
+  println!("Platform:");
+  print!("{diagnosis}");
+  if diagnosis.wallpaper == "unsupported" {
+    println!(
+      "No wallpaper backend fits this session; under Wayland, install swww, hyprpaper or swaybg."
+    );
+  }
+  Ok(())
+}
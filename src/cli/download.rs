@@ -0,0 +1,191 @@
+use super::search;
+use clap::{Args as ClapArgs, builder::RangedU64ValueParser};
+use std::{
+  io::{IsTerminal, stderr},
+  sync::atomic::{AtomicU8, Ordering}
+};
+#[cfg(feature = "thumbnails")]
+use wallter::wallpaper::thumbnails;
+use wallter::{
+  Config, Error, Result,
+  api::{download, wallhaven},
+  wallpaper::{
+    self, Source, cache,
+    naming::{self, Details}
+  }
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Wallhaven ids to download
+  #[arg(value_name = "ID", required_unless_present = "query")]
+  pub ids: Vec<String>,
+
+  /// Download the results of this search instead
+  #[arg(long, value_name = "QUERY")]
+  pub query: Option<String>,
+
+  #[command(flatten)]
+  pub filters: search::Filters,
+
+  /// Download at most this many search results
+  #[arg(short = 'n', long, value_name = "COUNT")]
+  pub count: Option<usize>,
+
+  /// How many downloads to run at the same time (defaults to the Wallhaven
+  /// source's max_concurrent_downloads)
+  #[arg(
+    short,
+    long,
+    value_name = "JOBS",
+    value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+  )]
+  pub jobs: Option<usize>,
+
+  /// Download for this monitor (defaults to the primary monitor)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>
+}
+
+/// A wallpaper to download, with its details if already known.
+struct Item {
+  id: String,
+  wallpaper: Option<wallhaven::Wallpaper>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let monitor = wallpaper::resolve_monitor(config, args.monitor.as_deref())?;
+  let jobs = args.jobs.unwrap_or_else(|| {
+    config
+      .source
+      .find("wallhaven")
+      .map_or(wallhaven::CONCURRENCY, |source| source.concurrency())
+      .downloads
+  });
+  let client = search::client(config);
+
+  //{ Collect the ids given directly, then the search results }
+  let mut items: Vec<Item> = args
+    .ids
+    .iter()
+    .map(|id| Item {
+      id: id.clone(),
+      wallpaper: None
+    })
+    .collect();
+  if let Some(query) = &args.query {
+    let params = search::params(config, Some(query), &args.filters);
+    let response = client.search(&params).await?;
+    items.extend(
+      response
+        .data
+        .into_iter()
+        .take(args.count.unwrap_or(usize::MAX))
+        .map(|wallpaper| Item {
+          id: wallpaper.id.clone(),
+          wallpaper: Some(wallpaper)
+        })
+    );
+  }
+  if items.is_empty() {
+    return Err(Error::NothingToDo("Nothing to download.".to_string()));
+  }
+
+  //{ Downloads run concurrently on this task, so state file updates made
+  //{ while fetching never interleave }
+  let total = items.len();
+  let client = &client;
+  let results: Vec<(String, Result<_>)> =
+    download::queue(items.into_iter().enumerate(), jobs, |(index, item)| {
+      let step = format!("[{}/{total}]", index + 1);
+      async move {
+        println!("{step} Downloading {}", item.id);
+        let found = match item.wallpaper {
+          Some(found) => found,
+          None => match client.get_wallpaper_details(&item.id).await {
+            Ok(details) => details,
+            Err(e) => return (item.id, Err(e))
+          }
+        };
+        let source = Source::Url(found.path.clone());
+        let fetch = naming::with_details(
+          Details::from(&found),
+          wallpaper::fetch(config, &source, monitor)
+        );
+        let result = if stderr().is_terminal() {
+          download::with_progress(
+            progress(step.clone(), item.id.clone()),
+            fetch
+          )
+          .await
+        } else {
+          fetch.await
+        };
+        match &result {
+          Ok(path) => println!("{step} Saved {}", path.display()),
+          Err(e) => eprintln!("{step} Failed {}: {e}", item.id)
+        }
+        (item.id, result)
+      }
+    })
+    .await;
+
+  let failed: Vec<&str> = results
+    .iter()
+    .filter(|(_, result)| result.is_err())
+    .map(|(id, _)| id.as_str())
+    .collect();
+  println!("Downloaded {} of {total} wallpapers.", total - failed.len());
+
+  //{ Warm the thumbnail cache so the new images can be previewed right away }
+  #[cfg(feature = "thumbnails")]
+  {
+    let saved: Vec<_> = results
+      .iter()
+      .filter_map(|(_, result)| result.as_ref().ok().cloned())
+      .collect();
+    match tokio::task::block_in_place(|| thumbnails::generate(config, &saved)) {
+      Ok(report) if !report.failed.is_empty() =>
+        eprintln!("Could not create {} thumbnails.", report.failed.len()),
+      Ok(_) => {}
+      Err(e) => eprintln!("Could not update the thumbnail cache: {e}")
+    }
+  }
+  match cache::enforce(config) {
+    Ok(evicted) if !evicted.is_empty() => println!(
+      "Evicted {} older downloads to stay within the cache size.",
+      evicted.len()
+    ),
+    Ok(_) => {}
+    Err(e) => eprintln!("Could not enforce the cache size: {e}")
+  }
+
+  if failed.is_empty() {
+    Ok(())
+  } else {
+    Err(Error::Wallpaper(format!(
+      "Failed to download: {}",
+      failed.join(", ")
+    )))
+  }
+}
+
+/// Reports every quarter of a download on standard error.
+fn progress(
+  step: String,
+  id: String
+) -> impl Fn(&download::Progress) + Send + Sync + 'static {
+  let reported = AtomicU8::new(0);
+  move |progress| {
+    let Some(fraction) = progress.fraction() else {
+      return;
+    };
+    let quarter = (fraction * 4.0) as u8;
+    if quarter > 0
+      && quarter < 4
+      && reported.fetch_max(quarter, Ordering::Relaxed) < quarter
+    {
+      eprintln!("{step} {id}: {progress}");
+    }
+  }
+}
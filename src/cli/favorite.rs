@@ -0,0 +1,83 @@
+use super::output::Format;
+use chrono::{DateTime, Local};
+use clap::Subcommand;
+use serde::Serialize;
+use std::path::Path;
+use wallter::{
+  Config, Result,
+  favorites::{self, Favorite, Favorites}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Add a wallpaper to the favorites
+  Add {
+    /// The wallpaper to add (defaults to the one on screen)
+    #[arg(value_name = "current|FILE|URL|ID", default_value = "current")]
+    target: String,
+    /// With 'current', the monitor to take it from
+    #[arg(short, long, value_name = "NAME|ID")]
+    monitor: Option<String>
+  },
+  /// Remove favorites and delete their images
+  Remove {
+    /// File names or Wallhaven ids of the favorites
+    #[arg(value_name = "NAME|ID", required = true)]
+    keys: Vec<String>
+  },
+  /// Show the favorites
+  List
+}
+
+/// A favorite, as listed in structured output.
+#[derive(Serialize)]
+struct Row<'a> {
+  name: String,
+  source: &'a str,
+  wallhaven_id: Option<&'a str>,
+  url: Option<&'a str>,
+  file: &'a Path,
+  added_at: DateTime<Local>
+}
+
+impl<'a> From<&'a Favorite> for Row<'a> {
+  fn from(favorite: &'a Favorite) -> Self {
+    Self {
+      name: favorite.name(),
+      source: &favorite.source,
+      wallhaven_id: favorite.wallhaven_id.as_deref(),
+      url: favorite.url.as_deref(),
+      file: &favorite.file,
+      added_at: favorite.added_at
+    }
+  }
+}
+
+pub async fn run(
+  config: &Config,
+  command: &Command,
+  format: Format
+) -> Result<()> {
+  match command {
+    Command::Add { target, monitor } => {
+      let favorite = favorites::add(config, target, monitor.as_deref()).await?;
+      println!("Added to favorites: {}", favorite.name());
+      Ok(())
+    }
+    Command::Remove { keys } => {
+      for key in keys {
+        let favorite = favorites::remove(config, key)?;
+        println!("Removed from favorites: {}", favorite.name());
+      }
+      Ok(())
+    }
+    Command::List => {
+      let favorites = Favorites::load(&config.path)?;
+      let rows: Vec<Row> = favorites.items.iter().map(Row::from).collect();
+      if !format.print(&rows)? {
+        print!("Favorites:\n{favorites}");
+      }
+      Ok(())
+    }
+  }
+}
@@ -1,20 +1,267 @@
-use clap::{Arg, Command};
-
-pub fn parse_args() -> Command {
-  Command::new("wallter")
-    .about("A wallpaper management utility")
-    .arg(
-      Arg::new("search")
-        .short('s')
-        .long("search")
-        .value_name("QUERY")
-        .help("Search for wallpapers using a query")
-    )
-    .arg(
-      Arg::new("set")
-        .short('w')
-        .long("set")
-        .value_name("URL")
-        .help("Set wallpaper from a URL")
-    )
+#[cfg(feature = "daemon")]
+use super::slideshow;
+#[cfg(feature = "tui")]
+use super::tui;
+use super::{
+  adjust, auth, cache, collection, color, config, debug, digest, doctor,
+  download, favorite, history, import, info, monitor, nightlight,
+  output::Format, preview, previous, queue, random, retry, search, set, skip,
+  snapshot, snooze, stats, status, tuner
+};
+use clap::{ArgAction, Parser, Subcommand};
+use std::path::Path;
+use tracing::Level;
+use wallter::{Config, Result, wallpaper::orphans::Resolution};
+
+/// A wallpaper management utility
+#[derive(Debug, Parser)]
+#[command(name = "wallter", arg_required_else_help = true)]
+pub struct Cli {
+  /// Record platform actions to a journal instead of performing them, so
+  /// system settings, the registry and the desktop are left unchanged while
+  /// downloads still happen. Also set with `guest = true` in the config
+  #[arg(long, global = true, visible_alias = "guest")]
+  pub simulate: bool,
+
+  /// Print what would change without changing anything
+  #[arg(long, global = true)]
+  pub dry_run: bool,
+
+  /// Log more detail; repeat for even more
+  #[arg(short, long, global = true, action = ArgAction::Count)]
+  pub verbose: u8,
+
+  /// Log only errors
+  #[arg(short, long, global = true, conflicts_with = "verbose")]
+  pub quiet: bool,
+
+  /// How list commands print their rows
+  #[arg(short, long, global = true, value_enum, default_value = "text")]
+  pub output: Format,
+
+  /// What to do with saved monitors that are no longer connected: keep,
+  /// archive, delete or migrate:<monitor>. Asked in a terminal if left out
+  #[arg(long, global = true, value_name = "RESOLUTION")]
+  pub missing_monitors: Option<Resolution>,
+
+  #[command(subcommand)]
+  pub command: Commands
+}
+
+impl Cli {
+  /// Returns the least severe level of log events to show.
+  pub fn level(&self) -> Level {
+    match (self.quiet, self.verbose) {
+      (true, _) => Level::ERROR,
+      (false, 0) => Level::INFO,
+      (false, 1) => Level::DEBUG,
+      (false, _) => Level::TRACE
+    }
+  }
+
+  /// Returns the verbosity flags as given, to pass on to a child process.
+  pub fn verbosity_args(&self) -> Vec<String> {
+    if self.quiet {
+      return vec!["--quiet".to_string()];
+    }
+    (0..self.verbose).map(|_| "--verbose".to_string()).collect()
+  }
+
+  /// Returns the file the log goes to instead of stderr, if any.
+  pub fn log_file(&self) -> Option<&Path> {
+    match &self.command {
+      #[cfg(feature = "daemon")]
+      Commands::Slideshow(slideshow::Command::Start { log_file, .. }) =>
+        log_file.as_deref(),
+      _ => None
+    }
+  }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+  /// Crop and pan the current wallpaper of a monitor, remembered for the image
+  Adjust(adjust::Args),
+  /// Sign in to a source, or keep its API key in the system keyring
+  Auth(auth::Args),
+  /// Show or reclaim the space used by downloads and thumbnails
+  #[command(subcommand)]
+  Cache(cache::Command),
+  /// Curate named, ordered collections of wallpapers for the slideshow
+  #[command(subcommand)]
+  Collection(collection::Command),
+  /// Switch between light and dark mode
+  #[command(subcommand)]
+  Color(color::Command),
+  /// Read and change the configuration
+  #[command(subcommand)]
+  Config(config::Command),
+  /// Gather diagnostics for bug reports
+  #[command(subcommand)]
+  Debug(debug::Command),
+  /// Fetch one new wallpaper a day, for low-power machines and e-paper
+  Digest(digest::Args),
+  /// Show the detected desktop and session, and the backends chosen for them
+  Doctor,
+  /// Download wallpapers by id or from a search, several at a time
+  Download(download::Args),
+  /// Keep wallpapers in the favorites collection
+  #[command(subcommand)]
+  Favorite(favorite::Command),
+  /// Show the wallpapers applied so far
+  #[command(subcommand)]
+  History(history::Command),
+  /// Add an existing wallpaper collection to the library
+  Import(import::Args),
+  /// Show what the provider reported about downloaded wallpapers
+  Info(info::Args),
+  /// Inspect the detected monitors
+  #[command(subcommand)]
+  Monitor(monitor::Command),
+  /// Turn the system night light on or off
+  #[command(subcommand)]
+  Nightlight(nightlight::Command),
+  /// Try a wallpaper for a few seconds, then revert
+  Preview(preview::Args),
+  /// Go back to the wallpaper shown before the current one
+  Previous(previous::Args),
+  /// Line up wallpapers for upcoming slideshow rotations
+  #[command(subcommand)]
+  Queue(queue::Command),
+  /// Download and set a new random wallpaper on every monitor, fitted to each
+  Random,
+  /// Retry downloads, wallpaper changes and notifications that failed
+  #[command(subcommand)]
+  Retry(retry::Command),
+  /// Search Wallhaven and optionally download results
+  Search(search::Args),
+  /// Set the wallpaper from a URL or a local file
+  Set(set::Args),
+  /// Advance to the next wallpaper, recording why the current one was skipped
+  Skip(skip::Args),
+  /// Control the background slideshow daemon
+  #[cfg(feature = "daemon")]
+  #[command(subcommand)]
+  Slideshow(slideshow::Command),
+  /// Save and restore the wallpaper layout across monitors
+  #[command(subcommand)]
+  Snapshot(snapshot::Command),
+  /// Pause automatic wallpaper rotation for a while
+  Snooze(snooze::Args),
+  /// Show local usage statistics
+  Stats(stats::Args),
+  /// Show what is on screen, what comes next, and source health
+  Status(status::Args),
+  /// Browse, search and apply wallpapers interactively
+  #[cfg(feature = "tui")]
+  Tui(tui::Args),
+  /// Show which sources and tags are skipped soon after they appear
+  Tuner(tuner::Args)
+}
+
+/// Parses the command line, exiting with usage help if it is invalid.
+pub fn parse_args() -> Cli {
+  Cli::parse()
+}
+
+/// Runs the subcommand selected in `cli`.
+pub async fn dispatch(cli: &Cli, config: &Config) -> Result<()> {
+  match &cli.command {
+    Commands::Adjust(args) => adjust::run(config, args).await,
+    Commands::Auth(args) => auth::run(config, args).await,
+    Commands::Cache(command) => cache::run(config, command),
+    Commands::Collection(command) =>
+      collection::run(config, command, cli.output).await,
+    Commands::Color(command) => color::run(config, command),
+    Commands::Config(command) => config::run(config, command),
+    Commands::Debug(command) => debug::run(config, command),
+    Commands::Digest(args) => digest::run(config, args).await,
+    Commands::Doctor => doctor::run(config),
+    Commands::Download(args) => download::run(config, args).await,
+    Commands::Favorite(command) =>
+      favorite::run(config, command, cli.output).await,
+    Commands::History(command) => history::run(config, command, cli.output),
+    Commands::Import(args) => import::run(config, args),
+    Commands::Info(args) => info::run(config, args, cli.output),
+    Commands::Monitor(command) => monitor::run(config, command, cli.output),
+    Commands::Nightlight(command) => nightlight::run(config, command),
+    Commands::Preview(args) => preview::run(config, args).await,
+    Commands::Previous(args) => previous::run(config, args).await,
+    Commands::Queue(command) => queue::run(config, command, cli.output),
+    Commands::Random => random::run(config).await,
+    Commands::Retry(command) => retry::run(config, command).await,
+    Commands::Search(args) => search::run(config, args, cli.output).await,
+    Commands::Set(args) => set::run(config, args).await,
+    Commands::Skip(args) => skip::run(config, args).await,
+    #[cfg(feature = "daemon")]
+    Commands::Slideshow(command) =>
+      slideshow::run(config, command, &cli.verbosity_args()).await,
+    Commands::Snapshot(command) =>
+      snapshot::run(config, command, cli.output).await,
+    Commands::Snooze(args) => snooze::run(config, args),
+    Commands::Stats(args) => stats::run(config, args),
+    Commands::Status(args) => status::run(config, args),
+    #[cfg(feature = "tui")]
+    Commands::Tui(args) => tui::run(config, args).await,
+    Commands::Tuner(args) => tuner::run(config, args)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::CommandFactory;
+
+  #[test]
+  fn test_cli() {
+    Cli::command().debug_assert();
+
+    let cli = Cli::parse_from(["wallter", "config", "show", "--dry-run"]);
+    assert!(cli.dry_run);
+    assert!(matches!(
+      cli.command,
+      Commands::Config(config::Command::Show)
+    ));
+    assert_eq!(cli.level(), Level::INFO);
+
+    let cli = Cli::parse_from(["wallter", "--guest", "random"]);
+    assert!(cli.simulate && !cli.dry_run);
+  }
+
+  #[test]
+  fn test_adjust_args() {
+    let cli = Cli::parse_from([
+      "wallter",
+      "adjust",
+      "--monitor",
+      "DP-1",
+      "--pan-x",
+      "-10%",
+      "--zoom",
+      "1.2"
+    ]);
+    let Commands::Adjust(args) = cli.command else {
+      panic!("expected the adjust command");
+    };
+    assert_eq!(args.monitor.as_deref(), Some("DP-1"));
+    assert_eq!(args.pan_x, Some(-0.1));
+    assert_eq!(args.zoom, Some(1.2));
+    assert!(
+      Cli::try_parse_from(["wallter", "adjust", "--reset", "--zoom", "2"])
+        .is_err()
+    );
+  }
+
+  #[test]
+  fn test_verbosity() {
+    let cli = Cli::parse_from(["wallter", "-vv", "status"]);
+    assert_eq!(cli.level(), Level::TRACE);
+    assert_eq!(cli.verbosity_args(), ["--verbose", "--verbose"]);
+
+    let cli = Cli::parse_from(["wallter", "status", "--quiet"]);
+    assert_eq!(cli.level(), Level::ERROR);
+    assert_eq!(cli.verbosity_args(), ["--quiet"]);
+
+    assert!(Cli::try_parse_from(["wallter", "-q", "-v", "status"]).is_err());
+  }
 }
@@ -0,0 +1,41 @@
+use super::output::Format;
+use clap::Subcommand;
+use wallter::{Config, Result, wallpaper::History};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// List applied wallpapers, newest first
+  List {
+    /// Only list wallpapers applied to this monitor
+    #[arg(short, long, value_name = "NAME")]
+    monitor: Option<String>,
+    /// How many entries to show
+    #[arg(short = 'n', long, value_name = "COUNT", default_value_t = 20)]
+    limit: usize
+  }
+}
+
+pub fn run(config: &Config, command: &Command, format: Format) -> Result<()> {
+  let Command::List { monitor, limit } = command;
+  let history = History::load(&config.path)?;
+
+  let entries: Vec<_> = history.list(monitor.as_deref()).take(*limit).collect();
+  if format.print(&entries)? {
+    return Ok(());
+  }
+  if entries.is_empty() {
+    println!("No wallpapers applied yet.");
+  }
+  for entry in entries {
+    println!(
+      "{}  [{}] {}",
+      entry.applied_at.format("%Y-%m-%d %H:%M"),
+      entry.monitor,
+      entry
+        .url
+        .as_deref()
+        .map_or_else(|| entry.image.display().to_string(), str::to_string)
+    );
+  }
+  Ok(())
+}
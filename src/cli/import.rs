@@ -0,0 +1,74 @@
+use clap::Args as ClapArgs;
+use std::path::PathBuf;
+use wallter::{
+  Config, Error, Result,
+  utils::files,
+  wallpaper::{Library, Probes, library::Imported}
+};
+
+/// How many imports go by between saves of the library index, so that an
+/// interrupted import keeps most of its progress.
+const SAVE_EVERY: usize = 100;
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// The directory holding the wallpapers
+  #[arg(value_name = "DIR")]
+  pub dir: PathBuf,
+
+  /// Move the images instead of copying them (the library then holds the
+  /// only copy, which the cache size limit never evicts)
+  #[arg(long = "move")]
+  pub move_files: bool,
+
+  /// Skip subdirectories
+  #[arg(long)]
+  pub no_recursive: bool
+}
+
+pub fn run(config: &Config, args: &Args) -> Result<()> {
+  if !args.dir.is_dir() {
+    return Err(Error::Config(format!(
+      "'{}' is not a directory",
+      args.dir.display()
+    )));
+  }
+  let images = files::list_images(&args.dir, !args.no_recursive)?;
+  if images.is_empty() {
+    return Err(Error::NothingToDo(format!(
+      "No images found in {}.",
+      args.dir.display()
+    )));
+  }
+
+  let total = images.len();
+  let mut probes = Probes::load(&config.path)?;
+  let probed = probes.probe_all(&images, &config.wallpaper.limits);
+  probes.save(&config.path)?;
+
+  let mut library = Library::load(&config.path)?;
+  let (mut added, mut duplicates, mut failed) = (0, 0, 0);
+  for (index, (image, probe)) in images.iter().zip(probed).enumerate() {
+    let imported = probe.and_then(|probe| {
+      library.import_probed(config, image, probe, !args.move_files)
+    });
+    match imported {
+      Ok(Imported::Added(_)) => added += 1,
+      Ok(Imported::Duplicate(_)) => duplicates += 1,
+      Err(e) => {
+        eprintln!("Skipped {}: {e}", image.display());
+        failed += 1;
+      }
+    }
+    if (index + 1) % SAVE_EVERY == 0 {
+      library.save(&config.path)?;
+      println!("[{}/{total}] Importing...", index + 1);
+    }
+  }
+  library.save(&config.path)?;
+
+  println!(
+    "Imported {added} of {total} images ({duplicates} already in the library, {failed} skipped)."
+  );
+  Ok(())
+}
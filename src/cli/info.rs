@@ -0,0 +1,54 @@
+use super::output::Format;
+use clap::Args as ClapArgs;
+use serde::Serialize;
+use std::path::{self, PathBuf};
+use wallter::{
+  Config, Error, Result,
+  wallpaper::metadata::{Index, Metadata}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Downloaded images to describe
+  #[arg(value_name = "FILE", required = true)]
+  pub files: Vec<PathBuf>
+}
+
+/// A described image, as printed in structured output.
+#[derive(Debug, Serialize)]
+struct Row<'a> {
+  file: PathBuf,
+  #[serde(flatten)]
+  metadata: &'a Metadata
+}
+
+pub fn run(config: &Config, args: &Args, format: Format) -> Result<()> {
+  let index = Index::load(&config.path)?;
+  let mut rows = Vec::new();
+  for file in &args.files {
+    //{ Downloads are recorded by absolute path, links included }
+    let found = path::absolute(file)
+      .ok()
+      .and_then(|absolute| index.get(&absolute).map(|m| (absolute, m)))
+      .or_else(|| {
+        let canonical = file.canonicalize().ok()?;
+        index.get(&canonical).map(|m| (canonical, m))
+      });
+    match found {
+      Some((file, metadata)) => rows.push(Row { file, metadata }),
+      None => eprintln!("No metadata is recorded for {}", file.display())
+    }
+  }
+  if rows.is_empty() {
+    return Err(Error::NothingToDo(
+      "None of the files were downloaded by wallter".to_string()
+    ));
+  }
+  if format.print(&rows)? {
+    return Ok(());
+  }
+  for row in rows {
+    print!("{}:\n{}", row.file.display(), row.metadata);
+  }
+  Ok(())
+}
@@ -0,0 +1,26 @@
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+use tracing::Level;
+use wallter::Result;
+
+/// Sends log events at `level` and above to stderr or, when given, appended
+/// to `log_file` with timestamps, as the background daemon has no terminal.
+pub fn init(level: Level, log_file: Option<&Path>) -> Result<()> {
+  let builder = tracing_subscriber::fmt()
+    .with_max_level(level)
+    .with_target(false);
+
+  match log_file {
+    Some(path) => {
+      if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      let file = OpenOptions::new().create(true).append(true).open(path)?;
+      builder
+        .with_ansi(false)
+        .with_writer(Mutex::new(file))
+        .init();
+    }
+    None => builder.without_time().with_writer(std::io::stderr).init()
+  }
+  Ok(())
+}
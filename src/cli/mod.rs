@@ -1 +1,38 @@
+pub mod handler;
+pub use handler::{dispatch, parse_args};
 
+pub mod adjust;
+pub mod auth;
+pub mod cache;
+pub mod collection;
+pub mod color;
+pub mod config;
+pub mod debug;
+pub mod digest;
+pub mod doctor;
+pub mod download;
+pub mod favorite;
+pub mod history;
+pub mod import;
+pub mod info;
+pub mod logging;
+pub mod monitor;
+pub mod nightlight;
+pub mod output;
+pub mod preview;
+pub mod previous;
+pub mod queue;
+pub mod random;
+pub mod retry;
+pub mod search;
+pub mod set;
+pub mod skip;
+#[cfg(feature = "daemon")]
+pub mod slideshow;
+pub mod snapshot;
+pub mod snooze;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod tuner;
@@ -0,0 +1,186 @@
+use super::output::Format;
+use clap::Subcommand;
+use serde::Serialize;
+use std::io::{IsTerminal, Write, stdin, stdout};
+use wallter::{
+  Config, Error, Result,
+  config::{
+    Monitor,
+    monitor::{Orientation, Position, Role}
+  },
+  wallpaper::orphans::{self, Resolution}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// List the detected monitors
+  List {
+    /// Print the monitors as JSON, the same as `--output json`
+    #[arg(long)]
+    json: bool
+  },
+  /// Settle saved monitors that are no longer connected
+  Resolve {
+    /// keep, archive, delete or migrate:<monitor>
+    #[arg(value_name = "RESOLUTION")]
+    resolution: Resolution,
+    /// The missing monitors to settle (defaults to all of them)
+    #[arg(value_name = "NAME")]
+    names: Vec<String>
+  }
+}
+
+/// A monitor with its derived properties spelled out, for structured
+/// output.
+#[derive(Serialize)]
+struct Entry<'a> {
+  id: u32,
+  name: &'a str,
+  width: u32,
+  height: u32,
+  resolution: &'static str,
+  ratio: &'static str,
+  orientation: Orientation,
+  scale: f32,
+  position: &'a Position,
+  primary: bool,
+  role: Role
+}
+
+impl<'a> From<&'a Monitor> for Entry<'a> {
+  fn from(monitor: &'a Monitor) -> Self {
+    Self {
+      id: monitor.id,
+      name: &monitor.name,
+      width: monitor.size.width,
+      height: monitor.size.height,
+      resolution: monitor.size.resolution_str(),
+      ratio: monitor.size.ratio_str(),
+      orientation: monitor.size.orientation(),
+      scale: monitor.scale,
+      position: &monitor.position,
+      primary: monitor.primary,
+      role: monitor.role()
+    }
+  }
+}
+
+/// Lists the monitors enumerated by [Monitor::get_info] when the
+/// configuration was loaded.
+pub fn run(config: &Config, command: &Command, format: Format) -> Result<()> {
+  let json = match command {
+    Command::List { json } => *json,
+    Command::Resolve { resolution, names } => {
+      let names = if names.is_empty() {
+        missing(config)
+      } else {
+        names.clone()
+      };
+      if names.is_empty() {
+        return Err(Error::NothingToDo("No monitors are missing.".into()));
+      }
+      return settle(&mut config.clone(), &names, resolution);
+    }
+  };
+
+  let format = if json { Format::Json } else { format };
+  let entries: Vec<Entry> = config.monitors.iter().map(Entry::from).collect();
+  if format.print(&entries)? {
+    return Ok(());
+  }
+
+  if config.monitors.is_empty() {
+    println!("Monitors: No monitors detected");
+    return Ok(());
+  }
+  println!("Monitors:");
+  for monitor in &config.monitors {
+    println!("{monitor}");
+  }
+  let missing = missing(config);
+  if !missing.is_empty() {
+    println!(
+      "Missing (see `wallter monitor resolve`): {}",
+      missing.join(", ")
+    );
+  }
+  Ok(())
+}
+
+/// Returns the names of the saved monitors that are no longer connected.
+fn missing(config: &Config) -> Vec<String> {
+  config
+    .orphaned_monitors()
+    .into_iter()
+    .map(|paths| paths.name.clone())
+    .collect()
+}
+
+/// Applies `resolution` to the named missing monitors and saves the config.
+fn settle(
+  config: &mut Config,
+  names: &[String],
+  resolution: &Resolution
+) -> Result<()> {
+  if *resolution == Resolution::Keep {
+    return Ok(());
+  }
+  for name in names {
+    orphans::resolve(config, name, resolution)?;
+    println!("Monitor '{name}': {resolution}");
+  }
+  config.save(&config.path.clone())
+}
+
+/// Settles the saved monitors that are no longer connected before a
+/// command runs: with `auto` if given, otherwise by asking in a terminal.
+/// Without either they are kept and a warning is logged.
+pub fn reconcile(config: &mut Config, auto: Option<&Resolution>) -> Result<()> {
+  let names = missing(config);
+  if names.is_empty() {
+    return Ok(());
+  }
+  if let Some(resolution) = auto {
+    return settle(config, &names, resolution);
+  }
+  if !stdin().is_terminal() || !stdout().is_terminal() {
+    tracing::warn!(
+      "Saved monitors are no longer connected: {}; see `wallter monitor resolve`",
+      names.join(", ")
+    );
+    return Ok(());
+  }
+
+  let connected: Vec<String> =
+    config.monitors.iter().map(|m| m.name.clone()).collect();
+  for name in names {
+    let resolution = ask(&name, &connected)?;
+    settle(config, &[name], &resolution)?;
+  }
+  Ok(())
+}
+
+/// Asks what to do with the missing monitor `name`, until given an answer
+/// that makes sense.
+fn ask(name: &str, connected: &[String]) -> Result<Resolution> {
+  println!(
+    "Monitor '{name}' is no longer connected. Connected now: {}.",
+    connected.join(", ")
+  );
+  loop {
+    print!(
+      "Migrate it to a connected monitor (type its name), [a]rchive, [d]elete or [k]eep? [k] "
+    );
+    stdout().flush()?;
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    match answer.trim() {
+      "" | "k" | "keep" => return Ok(Resolution::Keep),
+      "a" | "archive" => return Ok(Resolution::Archive),
+      "d" | "delete" => return Ok(Resolution::Delete),
+      target if connected.iter().any(|name| name == target) =>
+        return Ok(Resolution::Migrate(target.to_string())),
+      other => println!("'{other}' is not a connected monitor.")
+    }
+  }
+}
@@ -0,0 +1,51 @@
+use clap::Subcommand;
+use wallter::{Config, Result, config::color::night};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Turn the system night light on
+  On,
+  /// Turn the system night light off
+  Off,
+  /// Switch the system night light on or off
+  Toggle,
+  /// Show whether the system night light is on
+  Status
+}
+
+pub fn run(config: &Config, command: &Command) -> Result<()> {
+  match command {
+    Command::On | Command::Off => {
+      let enabled = matches!(command, Command::On);
+      let state = if enabled { "on" } else { "off" };
+      if night::set_system(enabled)? {
+        println!("Night light turned {state}.");
+      } else {
+        println!("Night light is already {state}.");
+      }
+      Ok(())
+    }
+    Command::Toggle => {
+      let state = if night::toggle_system()? { "on" } else { "off" };
+      println!("Night light turned {state}.");
+      Ok(())
+    }
+    Command::Status => {
+      let state = if night::system_enabled()? {
+        "On"
+      } else {
+        "Off"
+      };
+      println!("Night Light:");
+      println!("    {:<24}=| {state}", "System");
+      if config.color.night_light.is_gated() {
+        println!(
+          "    {:<24}=| {}",
+          "Monitors",
+          config.color.night_light.monitors.join(", ")
+        );
+      }
+      Ok(())
+    }
+  }
+}
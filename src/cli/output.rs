@@ -0,0 +1,155 @@
+//! Structured output for the list commands, so that nushell and PowerShell
+//! can filter the rows directly instead of parsing text.
+//!
+//! Each command prints one row per item, always with the same fields, as a
+//! JSON array of objects or a nuon table.
+
+use chrono::DateTime;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use wallter::{Error, Result};
+
+/// How the list commands print their rows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+  /// Aligned text, for reading
+  #[default]
+  Text,
+  /// A JSON array of objects, e.g. for PowerShell's ConvertFrom-Json
+  Json,
+  /// A nuon table, for nushell's `from nuon`
+  Nuon
+}
+
+impl Format {
+  /// Prints `rows` in this format. Returns `false` without printing for
+  /// [Format::Text], which each command lays out itself.
+  pub fn print<T: Serialize>(self, rows: &[T]) -> Result<bool> {
+    if self == Self::Text {
+      return Ok(false);
+    }
+    let value =
+      serde_json::to_value(rows).map_err(|e| Error::Config(e.to_string()))?;
+    match self {
+      Self::Json => println!(
+        "{}",
+        serde_json::to_string_pretty(&value)
+          .map_err(|e| Error::Config(e.to_string()))?
+      ),
+      _ => println!("{}", nuon(&value))
+    }
+    Ok(true)
+  }
+}
+
+/// Writes `value` as nuon. A list of objects with the same fields becomes a
+/// table, and RFC 3339 timestamps become dates.
+pub fn nuon(value: &Value) -> String {
+  match value {
+    Value::Null => "null".to_string(),
+    Value::Bool(value) => value.to_string(),
+    Value::Number(value) => value.to_string(),
+    Value::String(value) if DateTime::parse_from_rfc3339(value).is_ok() =>
+      value.clone(),
+    Value::String(value) => quote(value),
+    Value::Array(items) => match table_columns(items) {
+      Some(columns) => {
+        let header: Vec<String> =
+          columns.iter().map(|key| field(key)).collect();
+        let rows: Vec<String> = items
+          .iter()
+          .filter_map(Value::as_object)
+          .map(|row| {
+            let cells: Vec<String> = row.values().map(nuon).collect();
+            format!("[{}]", cells.join(", "))
+          })
+          .collect();
+        format!("[[{}]; {}]", header.join(", "), rows.join(", "))
+      }
+      None => {
+        let items: Vec<String> = items.iter().map(nuon).collect();
+        format!("[{}]", items.join(", "))
+      }
+    },
+    Value::Object(fields) => {
+      let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", field(key), nuon(value)))
+        .collect();
+      format!("{{{}}}", fields.join(", "))
+    }
+  }
+}
+
+/// Returns the shared fields if `items` are objects that all have the same
+/// fields in the same order.
+fn table_columns(items: &[Value]) -> Option<Vec<&String>> {
+  let first = items.first()?.as_object()?;
+  let columns: Vec<&String> = first.keys().collect();
+  items
+    .iter()
+    .all(|item| {
+      item
+        .as_object()
+        .is_some_and(|row| row.keys().eq(columns.iter().copied()))
+    })
+    .then_some(columns)
+}
+
+/// Writes a field name bare if nuon allows it, quoted otherwise.
+fn field(key: &str) -> String {
+  let bare = key
+    .chars()
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && key
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+  if bare { key.to_string() } else { quote(key) }
+}
+
+/// Writes a double-quoted nuon string.
+fn quote(value: &str) -> String {
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+      c => quoted.push(c)
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_nuon() {
+    let rows = json!([
+      {"name": "a \"b\"", "count": 2, "at": "2025-06-01T08:30:00+02:00"},
+      {"name": "c\\d", "count": null, "at": "not a date"},
+    ]);
+    assert_eq!(
+      nuon(&rows),
+      r#"[[name, count, at]; ["a \"b\"", 2, 2025-06-01T08:30:00+02:00], ["c\\d", null, "not a date"]]"#
+    );
+
+    //{ Rows with different fields stay a list of records }
+    let mixed = json!([{"a": 1}, {"b": [true, false]}, {"odd key": "x"}]);
+    assert_eq!(
+      nuon(&mixed),
+      r#"[{a: 1}, {b: [true, false]}, {"odd key": "x"}]"#
+    );
+    assert_eq!(nuon(&json!([])), "[]");
+  }
+}
@@ -0,0 +1,78 @@
+use clap::Args as ClapArgs;
+use std::{io::stdin, time::Duration};
+use tokio::{select, signal, task::spawn_blocking, time::timeout};
+use wallter::{
+  Config, Result,
+  slideshow::QueueItem,
+  wallpaper::{self, Current}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// A Wallhaven id or a local image file
+  #[arg(value_name = "ID|FILE")]
+  pub item: String,
+
+  /// How long to show the preview before reverting
+  #[arg(short, long, value_name = "SECONDS", default_value_t = 10)]
+  pub seconds: u64,
+
+  /// Ask whether to keep the wallpaper instead of always reverting
+  #[arg(short, long)]
+  pub prompt: bool,
+
+  /// Only preview on this monitor (defaults to all monitors)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let item = QueueItem::parse(&args.item)?;
+  let seconds = args.seconds;
+  let monitor = args.monitor.as_deref();
+
+  //{ Remember what is shown now so it can be restored }
+  let snapshot = Current::load(&config.path)?;
+  let source = item.resolve(config).await?;
+  //{ Only recorded if kept, so undo never brings back a reverted preview }
+  let Some(shown) = wallpaper::show(config, &source, monitor).await? else {
+    return Ok(());
+  };
+
+  let decision = async {
+    if args.prompt {
+      println!(
+        "Previewing {item}. Keep it? [k]eep/[r]evert (reverts in {seconds}s)"
+      );
+      let answer = spawn_blocking(|| {
+        let mut line = String::new();
+        stdin().read_line(&mut line).map(|_| line)
+      });
+      match timeout(Duration::from_secs(seconds), answer).await {
+        Ok(Ok(Ok(line))) => line.trim().to_lowercase().starts_with('k'),
+        _ => false
+      }
+    } else {
+      println!("Previewing {item} for {seconds}s...");
+      tokio::time::sleep(Duration::from_secs(seconds)).await;
+      false
+    }
+  };
+  let keep = select! {
+    keep = decision => keep,
+    _ = signal::ctrl_c() => false
+  };
+
+  if keep {
+    wallpaper::record(config, &shown, &source)?;
+    println!("Keeping {item}.");
+    return Ok(());
+  }
+
+  let missing = wallpaper::revert(config, &snapshot, monitor).await?;
+  for name in missing {
+    eprintln!("Warning: No previous wallpaper recorded for '{name}'");
+  }
+  println!("Reverted to the previous wallpaper.");
+  Ok(())
+}
@@ -0,0 +1,29 @@
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Result,
+  wallpaper::{self, History, Source}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Only go back on this monitor (defaults to all monitors)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  for monitor in wallpaper::resolve_monitors(config, args.monitor.as_deref())? {
+    //{ Save the history first, so reapplying the entry isn't logged twice }
+    let mut history = History::load(&config.path)?;
+    let Some(entry) = history.step_back(&monitor.name) else {
+      println!("[{}] No earlier wallpaper in the history", monitor.name);
+      continue;
+    };
+    history.save(&config.path)?;
+
+    let source = Source::File(entry.image);
+    wallpaper::set(config, &source, Some(&monitor.name)).await?;
+    println!("[{}] Now showing: {source}", monitor.name);
+  }
+  Ok(())
+}
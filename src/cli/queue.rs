@@ -0,0 +1,81 @@
+use super::output::Format;
+use clap::Subcommand;
+use serde::Serialize;
+use wallter::{
+  Config, Result,
+  slideshow::{Queue, QueueItem}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Add wallpapers to the end of the queue
+  Add {
+    /// Wallhaven ids or local image files
+    #[arg(value_name = "ID|FILE", required = true)]
+    items: Vec<String>
+  },
+  /// Show the queued wallpapers
+  List,
+  /// Remove all queued wallpapers
+  Clear
+}
+
+/// A queued wallpaper, as listed in structured output.
+#[derive(Serialize)]
+struct Row {
+  position: usize,
+  kind: &'static str,
+  value: String
+}
+
+impl Row {
+  fn new(index: usize, item: &QueueItem) -> Self {
+    let (kind, value) = match item {
+      QueueItem::File(path) => ("file", path.display().to_string()),
+      QueueItem::Wallhaven(id) => ("wallhaven", id.clone())
+    };
+    Self {
+      position: index + 1,
+      kind,
+      value
+    }
+  }
+}
+
+pub fn run(config: &Config, command: &Command, format: Format) -> Result<()> {
+  let mut queue = Queue::load(&config.path)?;
+  match command {
+    Command::Add { items } => {
+      let items = items
+        .iter()
+        .map(|value| QueueItem::parse(value))
+        .collect::<Result<Vec<_>>>()?;
+      for item in &items {
+        queue.push(item.clone());
+      }
+      queue.save(&config.path)?;
+      for item in items {
+        println!("Queued: {item}");
+      }
+      Ok(())
+    }
+    Command::Clear => {
+      queue.clear();
+      queue.save(&config.path)?;
+      println!("Queue cleared.");
+      Ok(())
+    }
+    Command::List => {
+      let rows: Vec<Row> = queue
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| Row::new(index, item))
+        .collect();
+      if !format.print(&rows)? {
+        print!("Queue:\n{queue}");
+      }
+      Ok(())
+    }
+  }
+}
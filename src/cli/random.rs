@@ -0,0 +1,34 @@
+use wallter::{
+  Config, Result,
+  search::{self, Narrowing},
+  wallpaper::{self, Source, cache}
+};
+
+pub async fn run(config: &Config) -> Result<()> {
+  //{ Backends that can't target a monitor show one image everywhere, so
+  //{ search for the primary monitor only }
+  let per_monitor = wallpaper::detect(config).supports_per_monitor();
+  let monitors = if per_monitor {
+    wallpaper::resolve_monitors(config, None)?
+  } else {
+    vec![wallpaper::resolve_monitor(config, None)?]
+  };
+
+  for monitor in monitors {
+    let report =
+      search::fallback(config, monitor, &Narrowing::default()).await?;
+    let Some(candidate) = &report.picked else {
+      println!("[{}] No wallpaper found to fit the monitor", monitor.name);
+      continue;
+    };
+    for attempt in report.passed_over() {
+      println!("[{}] Passed over: {attempt}", monitor.name);
+    }
+    let source = Source::parse(&candidate.url);
+    let target = per_monitor.then_some(monitor.name.as_str());
+    wallpaper::set(config, &source, target).await?;
+    println!("[{}] Now showing: {candidate}", monitor.name);
+  }
+  cache::enforce(config)?;
+  Ok(())
+}
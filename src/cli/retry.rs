@@ -0,0 +1,37 @@
+use clap::Subcommand;
+use wallter::{Config, Error, Result, slideshow::Retries};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Retry every failed operation now, without waiting for its backoff
+  Now,
+  /// Show the failed operations waiting to be retried
+  List,
+  /// Forget every failed operation
+  Clear
+}
+
+pub async fn run(config: &Config, command: &Command) -> Result<()> {
+  let mut retries = Retries::load(&config.path)?;
+  match command {
+    Command::Now => {
+      if retries.is_empty() {
+        return Err(Error::NothingToDo("Nothing to retry.".to_string()));
+      }
+      let outcome = retries.run(config, true).await;
+      retries.save(&config.path)?;
+      println!("Retried: {outcome}");
+      Ok(())
+    }
+    Command::List => {
+      print!("Retries:\n{retries}");
+      Ok(())
+    }
+    Command::Clear => {
+      retries.clear();
+      retries.save(&config.path)?;
+      println!("Retries cleared.");
+      Ok(())
+    }
+  }
+}
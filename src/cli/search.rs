@@ -0,0 +1,396 @@
+use super::output::Format;
+use clap::{Args as ClapArgs, ValueEnum, value_parser};
+use futures_util::{StreamExt, TryStreamExt};
+use serde::Serialize;
+#[cfg(feature = "terminal-preview")]
+use wallter::utils::{limits, preview::Protocol};
+use wallter::{
+  Config, Error, Result,
+  api::{
+    Query, provider, rate_limit,
+    wallhaven::{Api as Wallhaven, SearchParams, Wallpaper}
+  },
+  search,
+  wallpaper::{
+    self, Source, cache,
+    naming::{self, Details}
+  }
+};
+
+/// How many terminal columns a preview spans.
+#[cfg(feature = "terminal-preview")]
+const PREVIEW_COLUMNS: u32 = 40;
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Search terms, e.g. 'nature', '+blue -city' or 'id:123'
+  #[arg(value_name = "QUERY")]
+  pub query: Option<String>,
+
+  #[command(flatten)]
+  pub filters: Filters,
+
+  /// Download these results, by Wallhaven id or row number
+  #[arg(short, long, value_name = "ID|ROW", value_delimiter = ',')]
+  pub download: Vec<String>,
+
+  /// Download for this monitor (defaults to the primary monitor)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>,
+
+  /// Download random results that fit the monitors and apply one to each
+  /// right away
+  #[arg(long, conflicts_with_all = ["download", "monitor"])]
+  pub random: bool,
+
+  /// How many random wallpapers to download, the ones not applied being
+  /// kept for later (defaults to one per monitor)
+  #[arg(
+    short = 'n',
+    long,
+    value_name = "N",
+    requires = "random",
+    value_parser = value_parser!(u32).range(1..)
+  )]
+  pub count: Option<u32>,
+
+  /// Show a thumbnail of each result, with the protocol detected from the
+  /// terminal unless one is given
+  #[cfg(feature = "terminal-preview")]
+  #[arg(long, value_name = "PROTOCOL", num_args = 0..=1)]
+  pub preview: Option<Option<Protocol>>
+}
+
+/// A Wallhaven category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Category {
+  General,
+  Anime,
+  People
+}
+
+/// A Wallhaven purity level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Purity {
+  Sfw,
+  Sketchy,
+  Nsfw
+}
+
+/// The flags that narrow down a search, shared with other commands that
+/// search before acting.
+#[derive(Debug, Default, ClapArgs)]
+pub struct Filters {
+  /// Only include these categories
+  #[arg(short, long, value_name = "CATEGORY", value_delimiter = ',')]
+  pub category: Vec<Category>,
+
+  /// Only include these purity levels (nsfw requires an API key)
+  #[arg(short, long, value_name = "PURITY", value_delimiter = ',')]
+  pub purity: Vec<Purity>,
+
+  /// Minimum resolution, e.g. 1920x1080
+  #[arg(short, long, value_name = "WxH")]
+  pub atleast: Option<String>,
+
+  /// Exact resolutions, e.g. 2560x1440
+  #[arg(short, long, value_name = "WxH", value_delimiter = ',')]
+  pub resolution: Vec<String>,
+
+  /// Aspect ratios, e.g. 16x9
+  #[arg(long, value_name = "WxH", value_delimiter = ',')]
+  pub ratio: Vec<String>,
+
+  /// The page of results to use
+  #[arg(long, value_name = "PAGE", value_parser = value_parser!(u32).range(1..))]
+  pub page: Option<u32>
+}
+
+/// Builds the search parameters from the configured Wallhaven settings,
+/// overridden by `query` and the [Filters], and clamped to SFW during safe
+/// hours.
+pub fn params(
+  config: &Config,
+  query: Option<&str>,
+  filters: &Filters
+) -> SearchParams {
+  let mut params = config
+    .source
+    .find("wallhaven")
+    .map(|source| SearchParams::from(&config.source.wallhaven_params(source)))
+    .unwrap_or_default();
+  if let Some(query) = query {
+    params = params.with_query(query);
+  }
+  if !filters.category.is_empty() {
+    let has = |category| filters.category.contains(&category);
+    params = params.with_categories((
+      has(Category::General),
+      has(Category::Anime),
+      has(Category::People)
+    ));
+  }
+  if !filters.purity.is_empty() {
+    let has = |purity| filters.purity.contains(&purity);
+    params = params.with_purity((
+      has(Purity::Sfw),
+      has(Purity::Sketchy),
+      has(Purity::Nsfw)
+    ));
+  }
+  if let Some(atleast) = &filters.atleast {
+    params = params.with_atleast(atleast);
+  }
+  if !filters.resolution.is_empty() {
+    params = params.with_resolutions(filters.resolution.join(","));
+  }
+  if !filters.ratio.is_empty() {
+    params = params.with_ratios(filters.ratio.join(","));
+  }
+  if let Some(page) = filters.page {
+    params = params.with_page(page);
+  }
+
+  //{ Safe hours win over any filter }
+  search::safe::clamp_wallhaven(config, &mut params);
+  params
+}
+
+/// Creates a Wallhaven client using the configured API key, if any.
+pub fn client(config: &Config) -> Wallhaven {
+  let api_key = config
+    .source
+    .find("wallhaven")
+    .and_then(|source| source.api_key());
+  Wallhaven::new(api_key)
+}
+
+/// A search result, as listed in structured output.
+#[derive(Serialize)]
+struct Row<'a> {
+  row: usize,
+  id: &'a str,
+  resolution: &'a str,
+  ratio: &'a str,
+  purity: &'a str,
+  category: &'a str,
+  favorites: u32,
+  views: u32,
+  file_size: u64,
+  page: &'a str,
+  image: &'a str
+}
+
+impl<'a> Row<'a> {
+  fn new(index: usize, wallpaper: &'a Wallpaper) -> Self {
+    Self {
+      row: index + 1,
+      id: &wallpaper.id,
+      resolution: &wallpaper.resolution,
+      ratio: &wallpaper.ratio,
+      purity: &wallpaper.purity,
+      category: &wallpaper.category,
+      favorites: wallpaper.favorites,
+      views: wallpaper.views,
+      file_size: wallpaper.file_size,
+      page: &wallpaper.url,
+      image: &wallpaper.path
+    }
+  }
+}
+
+pub async fn run(config: &Config, args: &Args, format: Format) -> Result<()> {
+  if args.random {
+    return run_random(config, args).await;
+  }
+  let params = params(config, args.query.as_deref(), &args.filters);
+  let response = client(config).search(&params).await?;
+  if response.data.is_empty() {
+    return Err(Error::NothingToDo("No wallpapers found.".to_string()));
+  }
+  let rows: Vec<Row> = response
+    .data
+    .iter()
+    .enumerate()
+    .map(|(index, wallpaper)| Row::new(index, wallpaper))
+    .collect();
+  let structured = format.print(&rows)?;
+  if !structured {
+    #[cfg(feature = "terminal-preview")]
+    match args.preview {
+      None => print_table(&response.data),
+      Some(protocol) => match protocol.or_else(Protocol::detect) {
+        Some(protocol) =>
+          print_previews(config, &response.data, protocol).await,
+        None => {
+          eprintln!("This terminal does not support inline images.");
+          print_table(&response.data);
+        }
+      }
+    }
+    #[cfg(not(feature = "terminal-preview"))]
+    print_table(&response.data);
+    println!(
+      "Page {} of {} ({} results)",
+      response.meta.current_page, response.meta.last_page, response.meta.total
+    );
+    if let Some(quota) = rate_limit::quota("wallhaven") {
+      println!("Wallhaven quota: {quota}");
+    }
+  }
+
+  if args.download.is_empty() {
+    return Ok(());
+  }
+  let monitor = wallpaper::resolve_monitor(config, args.monitor.as_deref())?;
+  for selection in &args.download {
+    let result = select(&response.data, selection)?;
+    let source = Source::Url(result.path.clone());
+    let path = naming::with_details(
+      Details::from(result),
+      wallpaper::fetch(config, &source, monitor)
+    )
+    .await?;
+    //{ Keep structured output parseable }
+    if structured {
+      tracing::info!("Downloaded: {}", path.display());
+    } else {
+      println!("Downloaded: {}", path.display());
+    }
+  }
+  Ok(())
+}
+
+/// Downloads `args.count` random results that fit every monitor, and
+/// applies one to each.
+async fn run_random(config: &Config, args: &Args) -> Result<()> {
+  //{ Backends that can't target a monitor show one image everywhere }
+  let per_monitor = wallpaper::detect(config).supports_per_monitor();
+  let monitors = if per_monitor {
+    wallpaper::resolve_monitors(config, None)?
+  } else {
+    vec![wallpaper::resolve_monitor(config, None)?]
+  };
+
+  let mut query = Query::new().random();
+  if let Some(text) = &args.query {
+    query = query.with_text(text);
+  }
+  //{ Results must fit the largest monitor, unless a minimum was given }
+  if args.filters.atleast.is_none() {
+    let width = monitors.iter().map(|m| m.size.width).max();
+    let height = monitors.iter().map(|m| m.size.height).max();
+    if let (Some(width), Some(height)) = (width, height) {
+      query = query.with_at_least(width, height);
+    }
+  }
+  let count = args.count.map_or(monitors.len(), |count| count as usize);
+  let params = params(config, None, &args.filters).with(&query);
+  let found: Vec<provider::Wallpaper> = client(config)
+    .search_iter(&params)
+    .map_ok(|wallpaper| provider::Wallpaper::from(&wallpaper))
+    .take(count)
+    .try_collect()
+    .await?;
+  if found.is_empty() {
+    return Err(Error::NothingToDo("No wallpapers found.".to_string()));
+  }
+
+  let mut found = found.into_iter();
+  for (monitor, wallpaper) in monitors.iter().zip(found.by_ref()) {
+    let source = Source::Url(wallpaper.url.clone());
+    let target = per_monitor.then_some(monitor.name.as_str());
+    naming::with_details(
+      Details::from(&wallpaper),
+      wallpaper::set(config, &source, target)
+    )
+    .await?;
+    println!("[{}] Now showing: {wallpaper}", monitor.name);
+  }
+  for wallpaper in found {
+    let source = Source::Url(wallpaper.url.clone());
+    let path = naming::with_details(
+      Details::from(&wallpaper),
+      wallpaper::fetch(config, &source, monitors[0])
+    )
+    .await?;
+    println!("Downloaded: {}", path.display());
+  }
+  cache::enforce(config)?;
+  Ok(())
+}
+
+/// Finds a result by its Wallhaven id or 1-based row number.
+fn select<'a>(
+  results: &'a [Wallpaper],
+  selection: &str
+) -> Result<&'a Wallpaper> {
+  results
+    .iter()
+    .find(|wallpaper| wallpaper.id == selection)
+    .or_else(|| {
+      selection
+        .parse::<usize>()
+        .ok()
+        .and_then(|row| row.checked_sub(1))
+        .and_then(|index| results.get(index))
+    })
+    .ok_or_else(|| {
+      Error::Config(format!("'{selection}' is not in the search results"))
+    })
+}
+
+fn print_table(results: &[Wallpaper]) {
+  println!(
+    "{:>3}  {:<8}  {:<11}  {:<8}  URL",
+    "#", "ID", "RESOLUTION", "PURITY"
+  );
+  for (index, wallpaper) in results.iter().enumerate() {
+    println!(
+      "{:>3}  {:<8}  {:<11}  {:<8}  {}",
+      index + 1,
+      wallpaper.id,
+      wallpaper.resolution,
+      wallpaper.purity,
+      wallpaper.path
+    );
+  }
+}
+
+/// Prints each result's row followed by its thumbnail.
+#[cfg(feature = "terminal-preview")]
+async fn print_previews(
+  config: &Config,
+  results: &[Wallpaper],
+  protocol: Protocol
+) {
+  let client = reqwest::Client::new();
+  for (index, wallpaper) in results.iter().enumerate() {
+    println!(
+      "{:>3}  {}  {}  {}",
+      index + 1,
+      wallpaper.id,
+      wallpaper.resolution,
+      wallpaper.purity
+    );
+    let preview = async {
+      let thumbnail = wallter::api::cache::send("wallhaven", || {
+        client.get(&wallpaper.thumbs.small)
+      })
+      .await?;
+      if !thumbnail.status.is_success() {
+        return Err(Error::from_status(
+          "wallhaven",
+          thumbnail.status.as_u16(),
+          thumbnail.text()
+        ));
+      }
+      let image = limits::decode(&thumbnail.body, &config.wallpaper.limits)?;
+      protocol.render(&image, PREVIEW_COLUMNS)
+    };
+    match preview.await {
+      Ok(escape) => println!("{escape}"),
+      Err(e) => eprintln!("     No preview: {e}")
+    }
+  }
+}
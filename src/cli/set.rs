@@ -0,0 +1,41 @@
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Result,
+  wallpaper::{self, Source}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// The image to apply
+  #[arg(value_name = "URL|FILE")]
+  pub source: String,
+
+  /// Only apply to this monitor (defaults to all monitors)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>,
+
+  /// Apply on this machine over SSH instead of locally
+  #[arg(long, value_name = "USER@MACHINE")]
+  pub host: Vec<String>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let source = Source::parse(&args.source);
+  let monitor = args.monitor.as_deref();
+
+  //{ Fetch the image once and push it to each remote host }
+  if !args.host.is_empty() {
+    let local = wallpaper::resolve_monitor(config, None)?;
+    let image = wallpaper::fetch(config, &source, local).await?;
+    for host in &args.host {
+      let path = wallpaper::remote::set(host, &image, monitor)?;
+      println!("Wallpaper set on {host}: {path}");
+    }
+    return Ok(());
+  }
+
+  for path in wallpaper::set(config, &source, monitor).await? {
+    println!("Wallpaper set: {}", path.display());
+  }
+  Ok(())
+}
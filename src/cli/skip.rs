@@ -0,0 +1,55 @@
+use chrono::Local;
+use clap::Args as ClapArgs;
+use tracing::warn;
+use wallter::{
+  Config, Result,
+  slideshow::{self, Feedback, Tuner},
+  wallpaper::{self, Current}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Why the wallpaper was skipped (e.g. too-bright, too-busy)
+  #[arg(short, long, value_name = "REASON")]
+  pub reason: Option<String>,
+
+  /// Only skip on this monitor (defaults to all monitors)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let reason = args.reason.as_deref();
+  let monitors = wallpaper::resolve_monitors(config, args.monitor.as_deref())?;
+
+  //{ Record the reason against the wallpapers being skipped }
+  let current = Current::load(&config.path)?;
+  let mut feedback = Feedback::load(&config.path)?;
+  let mut tuner = Tuner::load(&config.path)?;
+  let window = config.slideshow.tuning.window.to_duration();
+  for monitor in &monitors {
+    let Some(applied) = current.get(&monitor.name) else {
+      continue;
+    };
+    feedback.record_skip(&applied.image, reason);
+
+    //{ Only a skip soon after the change says the wallpaper was unwanted }
+    let shown_for = (Local::now() - applied.since).to_std().unwrap_or_default();
+    if shown_for <= window {
+      if let Err(e) = tuner.learn_tags(config, &applied.image).await {
+        warn!("Failed to look up the tags of the skipped wallpaper: {e}");
+      }
+      tuner.record_skip(&applied.image);
+    }
+  }
+  feedback.save(&config.path)?;
+  tuner.save(&config.path)?;
+
+  for monitor in monitors {
+    match slideshow::advance(config, monitor).await? {
+      Some(source) => println!("[{}] Now showing: {source}", monitor.name),
+      None => println!("[{}] No other wallpapers available", monitor.name)
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,183 @@
+use clap::Subcommand;
+use std::{
+  env,
+  path::PathBuf,
+  process::{self, Stdio}
+};
+use wallter::{
+  Config, Error, Result,
+  simulate::{self, Action},
+  slideshow::{
+    self, State,
+    daemon::{self, POLL, Request, Running}
+  }
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Start rotating wallpapers in the background
+  Start {
+    /// Run in this terminal instead of in the background
+    #[arg(long)]
+    foreground: bool,
+    /// Append the daemon's log to this file
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>
+  },
+  /// Stop the slideshow daemon
+  Stop,
+  /// Show whether the slideshow is running
+  Status,
+  /// Change to the next wallpaper right away
+  Next
+}
+
+pub async fn run(
+  config: &Config,
+  command: &Command,
+  verbosity: &[String]
+) -> Result<()> {
+  match command {
+    Command::Start {
+      foreground: true, ..
+    } => {
+      println!("Slideshow running (trigger: {}).", config.slideshow.trigger);
+      daemon::run(config).await
+    }
+    Command::Start {
+      foreground: false,
+      log_file
+    } => start(config, log_file.as_ref(), verbosity).await,
+    Command::Stop => stop(config).await,
+    Command::Next => next(config).await,
+    Command::Status => status(config)
+  }
+}
+
+/// Launches the daemon as a detached copy of this executable, passing on the
+/// log file and the verbosity flags.
+async fn start(
+  config: &Config,
+  log_file: Option<&PathBuf>,
+  verbosity: &[String]
+) -> Result<()> {
+  if let Some(running) = Running::load(&config.path)? {
+    println!("Slideshow is already running (pid {}).", running.pid);
+    return Ok(());
+  }
+
+  let exe = env::current_exe()?;
+  if simulate::dry_run(Action::Spawn {
+    command: format!("{} slideshow start --foreground", exe.display())
+  })? {
+    return Ok(());
+  }
+  let mut command = process::Command::new(exe);
+  command.args(["slideshow", "start", "--foreground"]);
+  if let Some(log_file) = log_file {
+    command.arg("--log-file").arg(log_file);
+  }
+  command.args(verbosity);
+  if simulate::is_enabled() {
+    command.arg("--simulate");
+  }
+  command
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null());
+
+  //{ Keep the daemon out of the terminal's process group so that closing
+  //{ the terminal or pressing Ctrl+C doesn't stop it }
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+  }
+  #[cfg(windows)]
+  {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+  }
+  command.spawn()?;
+
+  //{ Wait for the daemon to report in }
+  for _ in 0..5 {
+    tokio::time::sleep(POLL / 2).await;
+    if let Some(running) = Running::load(&config.path)? {
+      println!("Slideshow started (pid {}).", running.pid);
+      return Ok(());
+    }
+  }
+  Err(Error::Config(
+    "The slideshow daemon did not start; try `wallter slideshow start --foreground` to see why"
+      .to_string()
+  ))
+}
+
+/// Asks the daemon to exit and waits for it to do so.
+async fn stop(config: &Config) -> Result<()> {
+  if Running::load(&config.path)?.is_none() {
+    println!("Slideshow is not running.");
+    return Ok(());
+  }
+
+  Request::Stop.send(&config.path)?;
+  for _ in 0..5 {
+    tokio::time::sleep(POLL).await;
+    if Running::load(&config.path)?.is_none() {
+      println!("Slideshow stopped.");
+      return Ok(());
+    }
+  }
+  Err(Error::Config(
+    "The slideshow daemon did not respond to the stop request".to_string()
+  ))
+}
+
+/// Hands the change to the daemon if it is running, so its interval restarts,
+/// or changes the wallpapers directly otherwise.
+async fn next(config: &Config) -> Result<()> {
+  if Running::load(&config.path)?.is_some() {
+    Request::Next.send(&config.path)?;
+    println!("Asked the slideshow to change wallpapers.");
+    return Ok(());
+  }
+
+  for monitor in &config.monitors {
+    match slideshow::advance(config, monitor).await? {
+      Some(source) => println!("[{}] Now showing: {source}", monitor.name),
+      None => println!("[{}] No other wallpapers available", monitor.name)
+    }
+  }
+  Ok(())
+}
+
+fn status(config: &Config) -> Result<()> {
+  println!("Slideshow:");
+  let Some(running) = Running::load(&config.path)? else {
+    println!("    {:<24}=| Not running", "Daemon");
+    return Ok(());
+  };
+
+  println!(
+    "    {:<24}=| Running (pid {}) since {}",
+    "Daemon",
+    running.pid,
+    running.started_at.format("%Y-%m-%d %H:%M")
+  );
+  println!("    {:<24}=| {}", "Interval", config.slideshow.interval);
+  println!("    {:<24}=| {}", "Trigger", config.slideshow.trigger);
+  let state = State::load(&config.path)?;
+  let next_change = match state.snoozed_until {
+    Some(until) if state.is_snoozed() =>
+      format!("Snoozed until {}", until.format("%Y-%m-%d %H:%M")),
+    _ => match running.next_change {
+      Some(at) => at.format("%Y-%m-%d %H:%M:%S").to_string(),
+      None => "On unlock or resume".to_string()
+    }
+  };
+  println!("    {:<24}=| {next_change}", "Next Change");
+  Ok(())
+}
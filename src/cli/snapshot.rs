@@ -0,0 +1,110 @@
+use super::output::Format;
+use chrono::{DateTime, Local};
+use clap::Subcommand;
+use serde::Serialize;
+use wallter::{
+  Config, Error, Result,
+  wallpaper::snapshot::{Snapshot, Snapshots}
+};
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Save the wallpaper on each monitor and the color mode
+  Save {
+    #[arg(value_name = "NAME")]
+    name: String,
+    /// Replace an existing snapshot with the same name
+    #[arg(short, long)]
+    force: bool
+  },
+  /// Bring back a saved snapshot
+  Restore {
+    #[arg(value_name = "NAME")]
+    name: String
+  },
+  /// List the saved snapshots
+  List,
+  /// Delete a saved snapshot
+  Delete {
+    #[arg(value_name = "NAME")]
+    name: String
+  }
+}
+
+/// A snapshot, as listed in structured output.
+#[derive(Serialize)]
+struct Row<'a> {
+  name: &'a str,
+  monitors: usize,
+  color_mode: String,
+  saved_at: DateTime<Local>
+}
+
+pub async fn run(
+  config: &Config,
+  command: &Command,
+  format: Format
+) -> Result<()> {
+  let mut snapshots = Snapshots::load(&config.path)?;
+  match command {
+    Command::Save { name, force } => {
+      if snapshots.saved.contains_key(name) && !force {
+        return Err(Error::Config(format!(
+          "A snapshot named '{name}' already exists; use --force to replace it"
+        )));
+      }
+      let snapshot = Snapshot::capture(config)?;
+      if snapshot.wallpapers.monitors.is_empty() {
+        return Err(Error::Wallpaper(
+          "No wallpapers have been applied yet".to_string()
+        ));
+      }
+      let count = snapshot.wallpapers.monitors.len();
+      snapshots.saved.insert(name.clone(), snapshot);
+      snapshots.save(&config.path)?;
+      println!("Saved snapshot '{name}' of {count} monitors.");
+    }
+    Command::Restore { name } => {
+      let missing = snapshots.get(name)?.restore(config).await?;
+      for monitor in missing {
+        eprintln!(
+          "Warning: Snapshot '{name}' has no wallpaper for '{monitor}'"
+        );
+      }
+      println!("Restored snapshot '{name}'.");
+    }
+    Command::List => {
+      let rows: Vec<Row> = snapshots
+        .saved
+        .iter()
+        .map(|(name, snapshot)| Row {
+          name,
+          monitors: snapshot.wallpapers.monitors.len(),
+          color_mode: snapshot.color_mode.to_string(),
+          saved_at: snapshot.saved_at
+        })
+        .collect();
+      if format.print(&rows)? {
+        return Ok(());
+      }
+      if snapshots.saved.is_empty() {
+        println!("No snapshots saved.");
+      }
+      for (name, snapshot) in &snapshots.saved {
+        println!(
+          "{}  {name} ({} monitors, {})",
+          snapshot.saved_at.format("%Y-%m-%d %H:%M"),
+          snapshot.wallpapers.monitors.len(),
+          snapshot.color_mode
+        );
+      }
+    }
+    Command::Delete { name } => {
+      snapshots.get(name)?;
+      snapshots.saved.remove(name);
+      snapshots.save(&config.path)?;
+      println!("Deleted snapshot '{name}'.");
+    }
+  }
+  Ok(())
+}
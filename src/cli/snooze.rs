@@ -0,0 +1,35 @@
+use chrono::Local;
+use clap::Args as ClapArgs;
+use wallter::{Config, Result, config::slideshow::Interval, slideshow::State};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// How long to pause (e.g. 30m, 2h, 1d)
+  #[arg(value_name = "DURATION", default_value = "1h")]
+  pub duration: String,
+
+  /// Resume rotation immediately
+  #[arg(long)]
+  pub clear: bool
+}
+
+pub fn run(config: &Config, args: &Args) -> Result<()> {
+  let mut state = State::load(&config.path)?;
+
+  if args.clear {
+    state.snoozed_until = None;
+    state.save(&config.path)?;
+    println!("Rotation resumed.");
+    return Ok(());
+  }
+
+  let interval: Interval = args.duration.parse()?;
+  let until = Local::now()
+    + chrono::Duration::from_std(interval.to_duration()).map_err(|e| {
+      wallter::Error::Settings(format!("Invalid snooze duration: {e}"))
+    })?;
+  state.snoozed_until = Some(until);
+  state.save(&config.path)?;
+  println!("Rotation paused until {}.", until.format("%Y-%m-%d %H:%M"));
+  Ok(())
+}
@@ -0,0 +1,35 @@
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Result,
+  slideshow::Feedback,
+  stats::Stats,
+  wallpaper::journal::{self, Summary}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Clear all recorded statistics
+  #[arg(long)]
+  pub reset: bool
+}
+
+pub fn run(config: &Config, args: &Args) -> Result<()> {
+  if args.reset {
+    Stats::default().save(&config.path)?;
+    println!("Statistics cleared.");
+    return Ok(());
+  }
+
+  if !config.stats.enabled {
+    println!(
+      "Statistics are disabled. Set `enabled = true` in the [stats] section of {} to start recording.",
+      config.path.config_file.display()
+    );
+  }
+  print!("Statistics:\n{}", Stats::load(&config.path)?);
+  print!("  Skip Reasons:\n{}", Feedback::load(&config.path)?);
+
+  let events = journal::read(&config.path)?;
+  print!("  Changes by Trigger:\n{}", Summary::of(&events));
+  Ok(())
+}
@@ -0,0 +1,117 @@
+use chrono::Local;
+use clap::Args as ClapArgs;
+use wallter::{
+  Config, Error, Result,
+  config::conflicts::Manager,
+  search::Health,
+  slideshow::{Prefetch, Queue, Retries, State},
+  wallpaper::{Current, NowShowing}
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Print only what is on screen, as JSON for widgets and overlays
+  #[arg(long)]
+  pub json: bool
+}
+
+pub fn run(config: &Config, args: &Args) -> Result<()> {
+  let now_showing = NowShowing::load(&config.path)?;
+  if args.json {
+    let json = serde_json::to_string_pretty(&now_showing)
+      .map_err(|e| Error::Config(e.to_string()))?;
+    println!("{json}");
+    return Ok(());
+  }
+
+  let current = Current::load(&config.path)?;
+  let prefetch = Prefetch::load(&config.path)?;
+
+  println!("Status:");
+  for monitor in &config.monitors {
+    println!("  {}:", monitor.name);
+    let showing = match current.get(&monitor.name) {
+      Some(applied) => format!(
+        "{} (since {})",
+        applied.image.display(),
+        applied.since.format("%Y-%m-%d %H:%M")
+      ),
+      None => "[Unknown]".to_string()
+    };
+    println!("    {:<24}=| {showing}", "Showing");
+    if let Some(page) = now_showing
+      .monitors
+      .get(&monitor.name)
+      .and_then(|showing| showing.page.as_ref())
+    {
+      println!("    {:<24}=| {page}", "Credit");
+    }
+    if let Some(ready) = prefetch.monitors.get(&monitor.name) {
+      println!("    {:<24}=| {}", "Up Next", ready.origin);
+    }
+  }
+
+  let state = State::load(&config.path)?;
+  println!("  Slideshow:");
+  let rotation = match state.snoozed_until {
+    Some(until) if until > Local::now() =>
+      format!("Snoozed until {}", until.format("%Y-%m-%d %H:%M")),
+    _ if config.slideshow.enabled =>
+      format!("Every {}", config.slideshow.interval),
+    _ => "Disabled".to_string()
+  };
+  println!("    {:<24}=| {rotation}", "Rotation");
+  if let Some(collection) = &state.collection {
+    println!("    {:<24}=| {collection}", "Collection");
+  }
+  if let Some(theme) = state.theme.filter(|_| config.slideshow.theme.enabled) {
+    println!("    {:<24}=| {} ({})", "Theme", theme.query, theme.week);
+  }
+  #[cfg(feature = "daemon")]
+  {
+    use wallter::slideshow::daemon::Running;
+    let daemon = match Running::load(&config.path)? {
+      Some(running) => match running.next_change {
+        Some(at) =>
+          format!("Running, next change at {}", at.format("%H:%M:%S")),
+        None => "Running, changing on unlock or resume".to_string()
+      },
+      None => "Not running".to_string()
+    };
+    println!("    {:<24}=| {daemon}", "Daemon");
+  }
+  println!(
+    "    {:<24}=| {}",
+    "Queued",
+    Queue::load(&config.path)?.len()
+  );
+  let retries = Retries::load(&config.path)?;
+  if let Some(at) = retries.next_due() {
+    println!(
+      "    {:<24}=| {}, next at {}",
+      "Retrying",
+      retries.len(),
+      at.format("%Y-%m-%d %H:%M")
+    );
+  }
+
+  print!("  Sources:\n{}", Health::load(&config.path)?);
+
+  if config.conflicts.is_enabled() {
+    let managers = Manager::detect();
+    let others = if managers.is_empty() {
+      "None detected".to_string()
+    } else {
+      managers
+        .iter()
+        .map(|manager| {
+          format!("{manager} ({})", config.conflicts.policy_for(*manager))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    };
+    println!("  Conflicts:");
+    println!("    {:<24}=| {others}", "Other Managers");
+  }
+  Ok(())
+}
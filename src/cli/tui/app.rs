@@ -0,0 +1,280 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::PathBuf;
+use wallter::{api::wallhaven::Wallpaper, wallpaper::Source};
+
+/// How far Page Up and Page Down move the selection.
+const PAGE: usize = 10;
+
+/// The key bindings, as shown in the footer.
+pub const KEYS: &str = "↑↓ move  ⇥ local/remote  / search  ⏎ apply  d download  f favorite  b block  m monitor  r refresh  q quit";
+
+/// Which list is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+  Local,
+  Remote
+}
+
+/// An image in the downloads or favorites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Local {
+  pub path: PathBuf,
+  pub favorite: bool,
+  pub blocked: bool
+}
+
+/// A search result.
+#[derive(Debug, Clone)]
+pub struct Remote {
+  pub wallpaper: Wallpaper,
+  pub favorite: bool,
+  pub blocked: bool
+}
+
+/// Something the browser asks to be done, which needs the configuration or
+/// the network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+  Quit,
+  Search(String),
+  Apply,
+  Download,
+  Favorite,
+  Block,
+  Refresh
+}
+
+impl Action {
+  /// Returns the message shown while the action runs.
+  pub fn progress(&self) -> &'static str {
+    match self {
+      Self::Search(_) => "Searching...",
+      Self::Apply => "Applying...",
+      Self::Download => "Downloading...",
+      Self::Favorite => "Adding to favorites...",
+      Self::Quit | Self::Block | Self::Refresh => ""
+    }
+  }
+}
+
+/// The state of the browser.
+#[derive(Debug)]
+pub struct App {
+  pub tab: Tab,
+  pub local: Vec<Local>,
+  pub remote: Vec<Remote>,
+  /// The selected row in the local and remote lists.
+  pub selected: (usize, usize),
+  /// The names of the monitors to apply to.
+  pub monitors: Vec<String>,
+  pub monitor: usize,
+  /// The last search, if any.
+  pub query: Option<String>,
+  /// The search being typed, if any.
+  pub input: Option<String>,
+  pub status: String
+}
+
+impl App {
+  pub fn new(monitors: Vec<String>, monitor: usize) -> Self {
+    Self {
+      tab: Tab::Local,
+      local: Vec::new(),
+      remote: Vec::new(),
+      selected: (0, 0),
+      monitors,
+      monitor,
+      query: None,
+      input: None,
+      status: String::new()
+    }
+  }
+
+  /// Returns the number of rows in the shown list.
+  pub fn len(&self) -> usize {
+    match self.tab {
+      Tab::Local => self.local.len(),
+      Tab::Remote => self.remote.len()
+    }
+  }
+
+  /// Returns the selected row in the shown list.
+  pub fn selected(&self) -> usize {
+    match self.tab {
+      Tab::Local => self.selected.0,
+      Tab::Remote => self.selected.1
+    }
+  }
+
+  fn select(&mut self, index: usize) {
+    let index = index.min(self.len().saturating_sub(1));
+    match self.tab {
+      Tab::Local => self.selected.0 = index,
+      Tab::Remote => self.selected.1 = index
+    }
+  }
+
+  /// Keeps the selections within their lists after they change.
+  pub fn clamp(&mut self) {
+    self.selected.0 = self.selected.0.min(self.local.len().saturating_sub(1));
+    self.selected.1 = self.selected.1.min(self.remote.len().saturating_sub(1));
+  }
+
+  /// Returns the selected local image, if the local list is shown.
+  pub fn selected_local(&self) -> Option<&Local> {
+    (self.tab == Tab::Local)
+      .then(|| self.local.get(self.selected.0))
+      .flatten()
+  }
+
+  /// Returns the selected search result, if the remote list is shown.
+  pub fn selected_remote(&self) -> Option<&Remote> {
+    (self.tab == Tab::Remote)
+      .then(|| self.remote.get(self.selected.1))
+      .flatten()
+  }
+
+  /// Returns where the selected wallpaper comes from.
+  pub fn source(&self) -> Option<Source> {
+    match self.tab {
+      Tab::Local => self
+        .selected_local()
+        .map(|local| Source::File(local.path.clone())),
+      Tab::Remote => self
+        .selected_remote()
+        .map(|remote| Source::Url(remote.wallpaper.path.clone()))
+    }
+  }
+
+  /// Returns the name of the monitor to apply to.
+  pub fn monitor(&self) -> Option<&str> {
+    self.monitors.get(self.monitor).map(String::as_str)
+  }
+
+  /// Updates the state for a key press, returning what needs doing.
+  pub fn handle(&mut self, key: KeyEvent) -> Option<Action> {
+    if key.modifiers.contains(KeyModifiers::CONTROL)
+      && key.code == KeyCode::Char('c')
+    {
+      return Some(Action::Quit);
+    }
+    if let Some(input) = &mut self.input {
+      match key.code {
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+          input.pop();
+        }
+        KeyCode::Esc => self.input = None,
+        KeyCode::Enter => {
+          let query = self.input.take().unwrap_or_default();
+          let query = query.trim();
+          if !query.is_empty() {
+            return Some(Action::Search(query.to_string()));
+          }
+        }
+        _ => {}
+      }
+      return None;
+    }
+
+    self.status.clear();
+    match key.code {
+      KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+      KeyCode::Down | KeyCode::Char('j') => self.select(self.selected() + 1),
+      KeyCode::Up | KeyCode::Char('k') =>
+        self.select(self.selected().saturating_sub(1)),
+      KeyCode::PageDown => self.select(self.selected() + PAGE),
+      KeyCode::PageUp => self.select(self.selected().saturating_sub(PAGE)),
+      KeyCode::Home | KeyCode::Char('g') => self.select(0),
+      KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+      KeyCode::Tab
+      | KeyCode::BackTab
+      | KeyCode::Left
+      | KeyCode::Right
+      | KeyCode::Char('h')
+      | KeyCode::Char('l') =>
+        self.tab = match self.tab {
+          Tab::Local => Tab::Remote,
+          Tab::Remote => Tab::Local
+        },
+      KeyCode::Char('/') => {
+        self.tab = Tab::Remote;
+        self.input = Some(self.query.clone().unwrap_or_default());
+      }
+      KeyCode::Char('m') if !self.monitors.is_empty() => {
+        self.monitor = (self.monitor + 1) % self.monitors.len();
+        self.status = format!("Applying to {}", self.monitor().unwrap_or(""));
+      }
+      KeyCode::Enter => return Some(Action::Apply),
+      KeyCode::Char('d') => return Some(Action::Download),
+      KeyCode::Char('f') => return Some(Action::Favorite),
+      KeyCode::Char('b') => return Some(Action::Block),
+      KeyCode::Char('r') => return Some(Action::Refresh),
+      _ => {}
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+    app.handle(KeyEvent::new(code, KeyModifiers::NONE))
+  }
+
+  fn local(name: &str) -> Local {
+    Local {
+      path: PathBuf::from(name),
+      favorite: false,
+      blocked: false
+    }
+  }
+
+  #[test]
+  fn test_navigation() {
+    let mut app = App::new(vec!["DP-1".into(), "HDMI-1".into()], 0);
+    app.local = vec![local("a.jpg"), local("b.jpg"), local("c.jpg")];
+
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.selected(), 2);
+    assert_eq!(app.source(), Some(Source::File(PathBuf::from("c.jpg"))));
+    press(&mut app, KeyCode::Home);
+    assert_eq!(app.selected(), 0);
+
+    //{ The remote list keeps its own, empty, selection }
+    press(&mut app, KeyCode::Tab);
+    assert_eq!(app.tab, Tab::Remote);
+    assert_eq!(app.source(), None);
+
+    press(&mut app, KeyCode::Char('m'));
+    assert_eq!(app.monitor(), Some("HDMI-1"));
+    press(&mut app, KeyCode::Char('m'));
+    assert_eq!(app.monitor(), Some("DP-1"));
+    assert_eq!(press(&mut app, KeyCode::Char('q')), Some(Action::Quit));
+  }
+
+  #[test]
+  fn test_search_input() {
+    let mut app = App::new(Vec::new(), 0);
+    press(&mut app, KeyCode::Char('/'));
+    assert_eq!(app.tab, Tab::Remote);
+    for c in "sea q".chars() {
+      assert_eq!(press(&mut app, KeyCode::Char(c)), None);
+    }
+    press(&mut app, KeyCode::Backspace);
+    assert_eq!(
+      press(&mut app, KeyCode::Enter),
+      Some(Action::Search("sea".to_string()))
+    );
+    assert_eq!(app.input, None);
+
+    //{ Escape cancels the search without quitting }
+    press(&mut app, KeyCode::Char('/'));
+    assert_eq!(press(&mut app, KeyCode::Esc), None);
+    assert_eq!(app.input, None);
+  }
+}
@@ -0,0 +1,228 @@
+//! `wallter tui`: an interactive browser over the downloads, favorites and
+//! Wallhaven search, to pick wallpapers and apply them to a monitor.
+
+mod app;
+mod ui;
+
+use super::search::{self, Filters};
+use app::{Action, App, Local, Remote, Tab};
+use clap::Args as ClapArgs;
+use futures_util::{StreamExt, TryStreamExt};
+use ratatui::{
+  DefaultTerminal,
+  crossterm::event::{self, Event, KeyEventKind}
+};
+use std::collections::{BTreeSet, HashSet};
+use wallter::{
+  Config, Result,
+  favorites::{self, Favorites},
+  utils::files,
+  wallpaper::{
+    self, Blocklist, Source,
+    naming::{self, Details}
+  }
+};
+
+/// The reason recorded for wallpapers blocked from the browser.
+const BLOCK_REASON: &str = "blocked in the browser";
+
+/// How many results a search loads, across as many pages as it takes.
+const SEARCH_RESULTS: usize = 64;
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Start with a search for these terms
+  #[arg(value_name = "QUERY")]
+  pub query: Option<String>,
+
+  /// The monitor to apply to (defaults to the primary monitor)
+  #[arg(short, long, value_name = "NAME|ID")]
+  pub monitor: Option<String>
+}
+
+pub async fn run(config: &Config, args: &Args) -> Result<()> {
+  let monitors: Vec<String> = config
+    .monitors
+    .iter()
+    .map(|monitor| monitor.name.clone())
+    .collect();
+  let target = wallpaper::resolve_monitor(config, args.monitor.as_deref())?;
+  let index = monitors
+    .iter()
+    .position(|name| *name == target.name)
+    .unwrap_or_default();
+  let mut app = App::new(monitors, index);
+  reload(config, &mut app)?;
+  if let Some(query) = &args.query {
+    app.status =
+      report(perform(config, &mut app, Action::Search(query.clone())).await);
+  }
+
+  let mut terminal = ratatui::init();
+  let result = browse(config, &mut app, &mut terminal).await;
+  ratatui::restore();
+  result
+}
+
+/// Draws the browser and acts on key presses until asked to quit.
+async fn browse(
+  config: &Config,
+  app: &mut App,
+  terminal: &mut DefaultTerminal
+) -> Result<()> {
+  loop {
+    terminal.draw(|frame| ui::draw(frame, app))?;
+    let Event::Key(key) = event::read()? else {
+      continue;
+    };
+    if key.kind != KeyEventKind::Press {
+      continue;
+    }
+    let Some(action) = app.handle(key) else {
+      continue;
+    };
+    if action == Action::Quit {
+      return Ok(());
+    }
+
+    //{ Show progress while the network is busy }
+    app.status = action.progress().to_string();
+    terminal.draw(|frame| ui::draw(frame, app))?;
+    app.status = report(perform(config, app, action).await);
+  }
+}
+
+/// Turns the outcome of an action into a status line.
+fn report(outcome: Result<String>) -> String {
+  outcome.unwrap_or_else(|e| format!("Error: {e}"))
+}
+
+/// Carries out an action, returning a message for the status line.
+async fn perform(
+  config: &Config,
+  app: &mut App,
+  action: Action
+) -> Result<String> {
+  if let Action::Search(query) = action {
+    let params = search::params(config, Some(&query), &Filters::default());
+    app.remote = search::client(config)
+      .search_iter(&params)
+      .map_ok(|wallpaper| Remote {
+        wallpaper,
+        favorite: false,
+        blocked: false
+      })
+      .take(SEARCH_RESULTS)
+      .try_collect()
+      .await?;
+    app.selected.1 = 0;
+    app.tab = Tab::Remote;
+    app.query = Some(query.clone());
+    mark(config, app)?;
+    return Ok(format!("{} results for '{query}'", app.remote.len()));
+  }
+  if action == Action::Refresh {
+    reload(config, app)?;
+    return Ok(format!("{} local wallpapers", app.local.len()));
+  }
+
+  let Some(source) = app.source() else {
+    return Ok("Nothing selected".to_string());
+  };
+  let monitor = wallpaper::resolve_monitor(config, app.monitor())?;
+  let details = app
+    .selected_remote()
+    .map(|remote| Details::from(&remote.wallpaper))
+    .unwrap_or_default();
+  let message = match action {
+    Action::Apply => {
+      naming::with_details(
+        details,
+        wallpaper::set(config, &source, Some(&monitor.name))
+      )
+      .await?;
+      format!("Applied to {}", monitor.name)
+    }
+    Action::Download => {
+      if app.tab == Tab::Local {
+        return Ok("Already downloaded".to_string());
+      }
+      let path = naming::with_details(
+        details,
+        wallpaper::fetch(config, &source, monitor)
+      )
+      .await?;
+      format!("Downloaded {}", path.display())
+    }
+    Action::Favorite => {
+      let favorite = favorites::add(config, &source.to_string(), None).await?;
+      format!("Added to favorites: {}", favorite.name())
+    }
+    Action::Block => {
+      let key = source.to_string();
+      let mut blocklist = Blocklist::load(&config.path)?;
+      let message = if blocklist.remove(&key) {
+        format!("Unblocked {key}")
+      } else {
+        blocklist.add(key.clone(), BLOCK_REASON);
+        format!("Blocked {key}")
+      };
+      blocklist.save(&config.path)?;
+      message
+    }
+    Action::Quit | Action::Search(_) | Action::Refresh => String::new()
+  };
+  reload(config, app)?;
+  Ok(message)
+}
+
+/// Lists the downloads and favorites again.
+fn reload(config: &Config, app: &mut App) -> Result<()> {
+  let mut images: BTreeSet<_> =
+    files::list_images(&config.path.downloads_dir, true)?
+      .into_iter()
+      .collect();
+  images.extend(Favorites::images(&config.path)?);
+  app.local = images
+    .into_iter()
+    .map(|path| Local {
+      path,
+      favorite: false,
+      blocked: false
+    })
+    .collect();
+  mark(config, app)
+}
+
+/// Flags the favorites and blocked wallpapers in both lists.
+fn mark(config: &Config, app: &mut App) -> Result<()> {
+  let favorites = Favorites::load(&config.path)?;
+  let blocklist = Blocklist::load(&config.path)?;
+  let names: HashSet<String> = favorites
+    .items
+    .iter()
+    .map(|favorite| favorite.name())
+    .collect();
+  let in_favorites = |local: &Local| {
+    local.path.starts_with(&config.path.favorites_dir)
+      || local
+        .path
+        .file_name()
+        .is_some_and(|name| names.contains(name.to_string_lossy().as_ref()))
+  };
+  for local in &mut app.local {
+    local.favorite = in_favorites(local);
+    local.blocked =
+      blocklist.contains(&Source::File(local.path.clone()).to_string());
+  }
+  for remote in &mut app.remote {
+    let wallpaper = &remote.wallpaper;
+    remote.favorite = favorites.items.iter().any(|favorite| {
+      favorite.wallhaven_id.as_deref() == Some(wallpaper.id.as_str())
+        || favorite.url.as_deref() == Some(wallpaper.path.as_str())
+    });
+    remote.blocked = blocklist.contains(&wallpaper.path);
+  }
+  app.clamp();
+  Ok(())
+}
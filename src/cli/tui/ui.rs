@@ -0,0 +1,189 @@
+use super::app::{App, KEYS, Local, Remote, Tab};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Layout, Rect},
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, List, ListItem, ListState, Paragraph, Tabs, Wrap}
+};
+use std::fs;
+
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Draws the whole browser.
+pub fn draw(frame: &mut Frame, app: &App) {
+  let [header, body, footer] = Layout::vertical([
+    Constraint::Length(1),
+    Constraint::Min(0),
+    Constraint::Length(1)
+  ])
+  .areas(frame.area());
+  let [list, details] = Layout::horizontal([
+    Constraint::Percentage(55),
+    Constraint::Percentage(45)
+  ])
+  .areas(body);
+
+  draw_header(frame, app, header);
+  draw_list(frame, app, list);
+  draw_details(frame, app, details);
+  draw_footer(frame, app, footer);
+}
+
+fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
+  let [tabs, monitor] =
+    Layout::horizontal([Constraint::Min(0), Constraint::Length(30)])
+      .areas(area);
+  let titles = [
+    format!("Local ({})", app.local.len()),
+    format!("Remote ({})", app.remote.len())
+  ];
+  let selected = match app.tab {
+    Tab::Local => 0,
+    Tab::Remote => 1
+  };
+  frame.render_widget(
+    Tabs::new(titles)
+      .select(selected)
+      .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+    tabs
+  );
+  frame.render_widget(
+    Line::from(format!("Monitor: {}", app.monitor().unwrap_or("none")))
+      .right_aligned(),
+    monitor
+  );
+}
+
+fn draw_list(frame: &mut Frame, app: &App, area: Rect) {
+  let items: Vec<ListItem> = match app.tab {
+    Tab::Local => app.local.iter().map(local_row).collect(),
+    Tab::Remote => app.remote.iter().map(remote_row).collect()
+  };
+  let title = match (&app.tab, &app.query) {
+    (Tab::Local, _) => "Downloads and favorites".to_string(),
+    (Tab::Remote, Some(query)) => format!("Results for '{query}'"),
+    (Tab::Remote, None) => "Press / to search".to_string()
+  };
+  let mut state = ListState::default()
+    .with_selected((!items.is_empty()).then_some(app.selected()));
+  frame.render_stateful_widget(
+    List::new(items)
+      .block(Block::bordered().title(title))
+      .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+    area,
+    &mut state
+  );
+}
+
+/// Marks favorites with a star and blocked wallpapers with a cross.
+fn mark(favorite: bool, blocked: bool) -> Span<'static> {
+  match (favorite, blocked) {
+    (_, true) => Span::styled("✗ ", Style::new().fg(Color::Red)),
+    (true, false) => Span::styled("★ ", Style::new().fg(Color::Yellow)),
+    (false, false) => Span::raw("  ")
+  }
+}
+
+fn local_row(local: &Local) -> ListItem<'static> {
+  let name = local
+    .path
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  ListItem::new(Line::from(vec![
+    mark(local.favorite, local.blocked),
+    name.into(),
+  ]))
+}
+
+fn remote_row(remote: &Remote) -> ListItem<'static> {
+  let wallpaper = &remote.wallpaper;
+  ListItem::new(Line::from(vec![
+    mark(remote.favorite, remote.blocked),
+    format!(
+      "{:<8}  {:<11}  {}",
+      wallpaper.id, wallpaper.resolution, wallpaper.purity
+    )
+    .into(),
+  ]))
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+  let lines = match (app.selected_local(), app.selected_remote()) {
+    (Some(local), _) => local_details(local),
+    (_, Some(remote)) => remote_details(remote),
+    _ => Vec::new()
+  };
+  frame.render_widget(
+    Paragraph::new(lines)
+      .block(Block::bordered().title("Details"))
+      .wrap(Wrap { trim: false }),
+    area
+  );
+}
+
+/// Formats a labelled line of the details panel.
+fn detail(label: &str, value: impl Into<String>) -> Line<'static> {
+  Line::from(vec![
+    Span::styled(format!("{label:<11}"), Style::new().fg(Color::DarkGray)),
+    Span::raw(value.into()),
+  ])
+}
+
+fn yes_no(value: bool) -> &'static str {
+  if value { "Yes" } else { "No" }
+}
+
+fn local_details(local: &Local) -> Vec<Line<'static>> {
+  let size = fs::metadata(&local.path)
+    .map(|metadata| format!("{:.1} MiB", metadata.len() as f64 / MIB))
+    .unwrap_or_else(|_| "Missing".to_string());
+  let dimensions = image::image_dimensions(&local.path)
+    .map(|(width, height)| format!("{width}x{height}"))
+    .unwrap_or_else(|_| "Unknown".to_string());
+  vec![
+    detail("Path", local.path.display().to_string()),
+    detail("Resolution", dimensions),
+    detail("Size", size),
+    detail("Favorite", yes_no(local.favorite)),
+    detail("Blocked", yes_no(local.blocked)),
+  ]
+}
+
+fn remote_details(remote: &Remote) -> Vec<Line<'static>> {
+  let wallpaper = &remote.wallpaper;
+  vec![
+    detail("ID", wallpaper.id.clone()),
+    detail(
+      "Resolution",
+      format!("{} ({})", wallpaper.resolution, wallpaper.ratio)
+    ),
+    detail("Category", wallpaper.category.clone()),
+    detail("Purity", wallpaper.purity.clone()),
+    detail(
+      "Size",
+      format!("{:.1} MiB", wallpaper.file_size as f64 / MIB)
+    ),
+    detail(
+      "Popularity",
+      format!(
+        "{} favorites, {} views",
+        wallpaper.favorites, wallpaper.views
+      )
+    ),
+    detail("Colors", wallpaper.colors.join(" ")),
+    detail("Page", wallpaper.url.clone()),
+    detail("Favorite", yes_no(remote.favorite)),
+    detail("Blocked", yes_no(remote.blocked)),
+  ]
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+  let line = match (&app.input, app.status.is_empty()) {
+    (Some(input), _) => Line::from(format!("Search: {input}█")),
+    (None, false) => Line::from(app.status.clone()),
+    (None, true) => Line::styled(KEYS, Style::new().fg(Color::DarkGray))
+  };
+  frame.render_widget(line, area);
+}
@@ -0,0 +1,26 @@
+use clap::Args as ClapArgs;
+use wallter::{Config, Result, slideshow::Tuner};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+  /// Forget everything the tuner has learned
+  #[arg(long)]
+  pub reset: bool
+}
+
+pub fn run(config: &Config, args: &Args) -> Result<()> {
+  if args.reset {
+    Tuner::default().save(&config.path)?;
+    println!("Tuner reset.");
+    return Ok(());
+  }
+
+  if !config.slideshow.tuning.auto_adjust {
+    println!(
+      "Auto-adjusting is disabled. Set `auto_adjust = true` in the [slideshow.tuning] section of {} to apply what was learned.",
+      config.path.config_file.display()
+    );
+  }
+  print!("Tuner:\n{}", Tuner::load(&config.path)?);
+  Ok(())
+}
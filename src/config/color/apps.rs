@@ -0,0 +1,165 @@
+//! Keeps individual applications in step with the system color mode.
+//!
+//! Each entry in `color.apps` names an application and the commands that
+//! switch it to light or dark. When the system mode changes, every app is
+//! switched to match unless it has an exception: `follow = "skip"` leaves the
+//! app alone, and `follow = "light"` or `"dark"` pins it to that mode, for apps
+//! that look broken in the other one.
+
+use super::Mode;
+use crate::{
+  Error, Result,
+  simulate::{self, Action}
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  process::Command
+};
+use tracing::{info, warn};
+
+/// How an application follows system mode changes.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Follow {
+  /// Switch the app to the same mode as the system.
+  #[default]
+  System,
+  /// Never touch the app.
+  Skip,
+  /// Keep the app in light mode.
+  Light,
+  /// Keep the app in dark mode.
+  Dark
+}
+
+impl Display for Follow {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::System => write!(f, "system"),
+      Self::Skip => write!(f, "skip"),
+      Self::Light => write!(f, "light"),
+      Self::Dark => write!(f, "dark")
+    }
+  }
+}
+
+/// An application whose theme is switched along with the system mode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct App {
+  /// A name for the app, used in messages.
+  pub name: String,
+  /// The shell command that switches the app to light mode.
+  #[serde(default)]
+  pub light: Option<String>,
+  /// The shell command that switches the app to dark mode.
+  #[serde(default)]
+  pub dark: Option<String>,
+  /// How the app follows the system mode.
+  #[serde(default)]
+  pub follow: Follow
+}
+
+impl App {
+  /// Returns the mode this app should use when the system switches to
+  /// `system`, or `None` if the app is skipped.
+  pub fn target(&self, system: Mode) -> Option<Mode> {
+    match self.follow {
+      Follow::System => Some(system),
+      Follow::Skip => None,
+      Follow::Light => Some(Mode::Light),
+      Follow::Dark => Some(Mode::Dark)
+    }
+  }
+
+  /// Returns the command that switches this app to `mode`, if configured.
+  pub fn command(&self, mode: Mode) -> Option<&str> {
+    match mode {
+      Mode::Light => self.light.as_deref(),
+      Mode::Dark => self.dark.as_deref(),
+      Mode::Auto => None
+    }
+  }
+
+  /// Switches this app to the mode it should use when the system switches
+  /// to `system`. Returns the mode applied, if any.
+  pub fn apply(&self, system: Mode) -> Result<Option<Mode>> {
+    let Some(mode) = self.target(system) else {
+      return Ok(None);
+    };
+    let Some(command) = self.command(mode) else {
+      return Ok(None);
+    };
+    if simulate::record(Action::AppTheme {
+      app: self.name.clone(),
+      mode
+    })? {
+      return Ok(Some(mode));
+    }
+
+    let status = shell(command).status().map_err(|e| {
+      Error::ColorMode(format!(
+        "{}: Failed to execute '{command}': {e}",
+        self.name
+      ))
+    })?;
+    if !status.success() {
+      return Err(Error::ColorMode(format!(
+        "{}: '{command}' exited with {status}",
+        self.name
+      )));
+    }
+    Ok(Some(mode))
+  }
+}
+
+/// Switches every app in `apps` to follow the system switching to `system`.
+///
+/// A failing app is reported and doesn't stop the others, since the system
+/// mode has already changed by the time this runs.
+pub fn sync(apps: &[App], system: Mode) {
+  for app in apps {
+    match app.apply(system) {
+      Ok(Some(mode)) if mode != system =>
+        info!("Kept {} in {mode} mode", app.name),
+      Ok(_) => {}
+      Err(e) => warn!("Failed to switch {} to match the system: {e}", app.name)
+    }
+  }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+  let mut shell = Command::new("sh");
+  shell.args(["-c", command]);
+  shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+  let mut shell = Command::new("cmd");
+  shell.args(["/C", command]);
+  shell
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_app_target_honors_exceptions() {
+    let app = |follow| App {
+      name: "app".into(),
+      light: Some("light".into()),
+      dark: Some("dark".into()),
+      follow
+    };
+    assert_eq!(app(Follow::System).target(Mode::Dark), Some(Mode::Dark));
+    assert_eq!(app(Follow::Skip).target(Mode::Dark), None);
+    assert_eq!(app(Follow::Light).target(Mode::Dark), Some(Mode::Light));
+    assert_eq!(app(Follow::Dark).target(Mode::Light), Some(Mode::Dark));
+    assert_eq!(app(Follow::Light).command(Mode::Light), Some("light"));
+  }
+}
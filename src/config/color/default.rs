@@ -2,7 +2,8 @@
 //! including the system color mode (light/dark) and a list of
 //! color tags for wallpaper filtering.
 
-use super::Mode;
+use super::{App, Mode, NightLight, apps};
+use crate::{Result, config::Monitor};
 use rand::{prelude::SliceRandom, rng};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
@@ -29,14 +30,22 @@ pub struct Config {
   /// The desired system color mode (Light/Dark).
   pub mode: Mode,
   /// Color list validated against `ALLOWED_COLORS`
-  pub colors: Vec<String>
+  pub colors: Vec<String>,
+  /// Applications switched along with the system mode, with any exceptions.
+  #[serde(default)]
+  pub apps: Vec<App>,
+  /// Which monitors night light warms when in dark mode.
+  #[serde(default)]
+  pub night_light: NightLight
 }
 
 impl Default for Config {
   fn default() -> Self {
     Self {
       mode: Mode::default(),
-      colors: Self::randomize_colors(DEFAULT_RANDOM_COLOR_COUNT)
+      colors: Self::randomize_colors(DEFAULT_RANDOM_COLOR_COUNT),
+      apps: Vec::new(),
+      night_light: NightLight::default()
     }
   }
 }
@@ -46,10 +55,32 @@ impl Config {
   pub fn new(mode: Mode, colors: Vec<String>) -> Self {
     Self {
       mode,
-      colors: Self::validate_colors(colors)
+      colors: Self::validate_colors(colors),
+      apps: Vec::new(),
+      night_light: NightLight::default()
     }
   }
 
+  /// Applies an explicit mode to the system and, if that changed it, brings
+  /// the configured apps and night light in line. `Auto` leaves everything to
+  /// the system, handing night light back to its schedule.
+  pub fn apply_mode(&self, monitors: &[Monitor]) -> Result<()> {
+    if self.mode == Mode::Auto {
+      return self.night_light.release();
+    }
+    if self.mode.apply()? {
+      self.follow(self.mode, monitors)?;
+    }
+    Ok(())
+  }
+
+  /// Brings the configured apps and night light in line with the system
+  /// having switched to `mode`.
+  pub fn follow(&self, mode: Mode, monitors: &[Monitor]) -> Result<()> {
+    apps::sync(&self.apps, mode);
+    self.night_light.apply(mode, monitors)
+  }
+
   // pub fn toggle_mode(&mut self) -> Result<()> {
   //   Ok(Mode::toggle())
   //   // self.mode=self.mode.
@@ -117,6 +148,22 @@ impl Display for Config {
     };
     printf!(f, "Colors", colors_display)?;
 
+    let exceptions: Vec<String> = self
+      .apps
+      .iter()
+      .filter(|app| app.follow != super::Follow::System)
+      .map(|app| format!("{} ({})", app.name, app.follow))
+      .collect();
+    if !exceptions.is_empty() {
+      printf!(f, "App Exceptions", exceptions.join(", "))?;
+    }
+    if self.night_light.is_gated() {
+      printf!(f, "Night Light", self.night_light.monitors.join(", "))?;
+    }
+    if self.night_light.respect_schedule {
+      printf!(f, "Night Light Schedule", "Respected")?;
+    }
+
     Ok(())
   }
 }
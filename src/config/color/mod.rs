@@ -1,5 +1,11 @@
+pub mod apps;
+pub use apps::{App, Follow};
+
 pub mod default;
 pub use default::Config;
 
 pub mod mode;
 pub use mode::Config as Mode;
+
+pub mod night;
+pub use night::NightLight;
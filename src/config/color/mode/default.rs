@@ -2,6 +2,7 @@ use crate::{Error, Result};
 use dark_light::{Mode, detect};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
+use tracing::{info, warn};
 
 pub trait Manager {
   fn set(&self, config: Config) -> Result<()>;
@@ -12,9 +13,12 @@ pub trait Manager {
   Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone, Copy,
 )]
 pub enum Config {
+  #[serde(alias = "light")]
   Light,
+  #[serde(alias = "dark")]
   Dark,
   #[default]
+  #[serde(alias = "auto")]
   Auto
 }
 
@@ -26,20 +30,22 @@ impl Config {
     Ok(Self::default())
   }
 
-  fn get_current() -> Self {
+  /// Detects the system's current mode, falling back to `Dark` if it cannot
+  /// be determined. Never returns `Auto`.
+  pub fn get_current() -> Self {
     let fallback = Self::Dark;
     let detected = detect();
     match detected {
       Ok(Mode::Dark) => Self::Dark,
       Ok(Mode::Light) => Self::Light,
       Ok(Mode::Unspecified) => {
-        eprintln!(
+        warn!(
           "System color mode is unspecified. Using default mode: {fallback}"
         );
         fallback
       }
       Err(e) => {
-        eprintln!(
+        warn!(
           "Failed to detect the system's color mode: {e}. Using default mode: {fallback}"
         );
         fallback
@@ -61,7 +67,9 @@ impl Config {
     desired.apply().map(|_| desired)
   }
 
-  pub fn apply(&self) -> Result<()> {
+  /// Switches the system to this mode, or leaves it alone for `Auto`.
+  /// Returns whether the system mode changed.
+  pub fn apply(&self) -> Result<bool> {
     let current = Self::get_current();
     // let desired = *self;
     let desired = match *self {
@@ -73,13 +81,15 @@ impl Config {
 
     //{ Early return if mode is already set }
     if current == desired {
-      println!("System mode is already {desired:?}");
-      return Ok(());
+      info!("System mode is already {desired:?}");
+      return Ok(false);
     };
 
     //{ Set the system mode using the necessary platform-specific manager }
-    println!("Setting system mode to {desired:?}");
-    let manager: Box<dyn self::Manager> = {
+    info!("Setting system mode to {desired:?}");
+    let manager: Box<dyn self::Manager> = if crate::simulate::is_enabled() {
+      Box::new(crate::simulate::Manager)
+    } else {
       #[cfg(target_os = "windows")]
       {
         Box::new(super::windows::Manager::new_default())
@@ -88,15 +98,21 @@ impl Config {
       {
         Box::new(super::linux::Manager)
       }
-      #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+      #[cfg(target_os = "macos")]
+      {
+        Box::new(super::macos::Manager)
+      }
+      #[cfg(not(any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "macos"
+      )))]
       {
         // Define and implement UnsupportedManager directly here
         struct UnsupportedManager;
         impl self::Manager for UnsupportedManager {
           fn set(&self, _config: Config) -> Result<()> {
-            eprintln!(
-              "System theme setting is not supported on this platform."
-            );
+            warn!("System theme setting is not supported on this platform.");
             Ok(())
           }
 
@@ -108,8 +124,7 @@ impl Config {
         Box::new(UnsupportedManager)
       }
     };
-    manager.set(desired);
-    Ok(())
+    manager.set(desired).map(|_| true)
   }
 }
 
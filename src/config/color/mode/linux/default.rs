@@ -7,22 +7,71 @@
 
 use super::super::{Config, Manager as ModeManager};
 use crate::{Error, Result};
-use std::{env, process::Command};
+use std::{
+  env,
+  fmt::{self, Display, Formatter},
+  process::Command
+};
+use tracing::warn;
 
 /// A manager for Linux system color mode settings.
 pub struct Manager;
 
 /// Represents supported Linux desktop environments and outcomes of detection.
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq)]
-enum DesktopEnvironment {
+pub(crate) enum DesktopEnvironment {
   KDE,
   GNOME,
   Unsupported(String),
   Unknown
 }
 
+/// The kind of graphical session, which decides how wallpapers and gamma can
+/// be changed: GNOME on Xorg and GNOME on Wayland differ for both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SessionType {
+  X11,
+  Wayland,
+  Unknown
+}
+
+impl SessionType {
+  /// Detects the session from `XDG_SESSION_TYPE`, falling back to whether
+  /// `WAYLAND_DISPLAY` or `DISPLAY` is set.
+  pub(crate) fn detect() -> Self {
+    Self::from_vars(
+      env::var("XDG_SESSION_TYPE").ok().as_deref(),
+      env::var_os("WAYLAND_DISPLAY").is_some(),
+      env::var_os("DISPLAY").is_some()
+    )
+  }
+
+  fn from_vars(session_type: Option<&str>, wayland: bool, x11: bool) -> Self {
+    match session_type.map(str::to_ascii_lowercase).as_deref() {
+      Some("wayland") => Self::Wayland,
+      Some("x11") => Self::X11,
+      //{ Sessions started by hand, e.g. with startx, report `tty` or nothing }
+      _ if wayland => Self::Wayland,
+      _ if x11 => Self::X11,
+      _ => Self::Unknown
+    }
+  }
+}
+
+impl Display for SessionType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::X11 => "X11",
+      Self::Wayland => "Wayland",
+      Self::Unknown => "Unknown"
+    })
+  }
+}
+
 impl DesktopEnvironment {
-  fn detect() -> Self {
+  /// Detects the running desktop environment from `XDG_CURRENT_DESKTOP`.
+  pub(crate) fn detect() -> Self {
     let desktop = env::var("XDG_CURRENT_DESKTOP")
       .ok()
       .map(|d| d.to_lowercase());
@@ -106,7 +155,7 @@ impl DesktopEnvironment {
     }
 
     if let Err(e) = self.set_kde_persistent_theme(theme_name) {
-      eprintln!("Warning: Failed to set persistent KDE theme: {e}");
+      warn!("Failed to set persistent KDE theme: {e}");
     }
     Ok(())
   }
@@ -139,7 +188,7 @@ impl DesktopEnvironment {
     }
 
     if let Err(e) = self.set_gnome_gtk_theme(config) {
-      eprintln!("Warning: Failed to set GTK theme: {e}");
+      warn!("Failed to set GTK theme: {e}");
     }
     Ok(())
   }
@@ -149,13 +198,13 @@ impl DesktopEnvironment {
       DesktopEnvironment::KDE => self.apply_kde_theme_config(config),
       DesktopEnvironment::GNOME => self.apply_gnome_theme_config(config),
       DesktopEnvironment::Unsupported(ref desktop_name) => {
-        eprintln!(
+        warn!(
           "Unsupported Linux desktop environment for theme setting: {desktop_name}"
         );
         Ok(())
       }
       DesktopEnvironment::Unknown => {
-        eprintln!(
+        warn!(
           "Could not determine Linux desktop environment for theme setting."
         );
         Ok(())
@@ -216,6 +265,19 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_session_type() {
+    use SessionType::*;
+    assert_eq!(
+      SessionType::from_vars(Some("wayland"), false, true),
+      Wayland
+    );
+    assert_eq!(SessionType::from_vars(Some("X11"), true, true), X11);
+    assert_eq!(SessionType::from_vars(Some("tty"), false, true), X11);
+    assert_eq!(SessionType::from_vars(None, true, true), Wayland);
+    assert_eq!(SessionType::from_vars(None, false, false), Unknown);
+  }
+
   #[test]
   fn test_desktop_environment_enum() {
     let kde = DesktopEnvironment::KDE;
@@ -1,2 +1,6 @@
 mod default;
 pub use default::Manager;
+pub(crate) use default::{DesktopEnvironment, SessionType};
+
+pub mod nightlight;
+pub mod watch;
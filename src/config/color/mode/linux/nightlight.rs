@@ -0,0 +1,141 @@
+//! Reads and switches the desktop's own night light on Linux: GNOME Night
+//! Light through `gsettings`, and KDE Night Color through KWin's config file.
+//!
+//! Plasma 6 ships `kreadconfig6` and `kwriteconfig6`, Plasma 5 the `5`
+//! variants; whichever is installed is used.
+
+use super::DesktopEnvironment;
+use crate::{Error, Result};
+use std::process::Command;
+use tracing::debug;
+
+const GNOME_SCHEMA: &str = "org.gnome.settings-daemon.plugins.color";
+const GNOME_KEY: &str = "night-light-enabled";
+const KDE_ARGS: [&str; 6] = [
+  "--file",
+  "kwinrc",
+  "--group",
+  "NightColor",
+  "--key",
+  "Active"
+];
+
+/// Checks if night light is currently enabled.
+pub fn is_enabled() -> Result<bool> {
+  match DesktopEnvironment::detect() {
+    DesktopEnvironment::GNOME =>
+      parse(&run("gsettings", &["get", GNOME_SCHEMA, GNOME_KEY])?),
+    DesktopEnvironment::KDE => {
+      let mut args = KDE_ARGS.to_vec();
+      args.extend(["--default", "false"]);
+      parse(&run_either(["kreadconfig6", "kreadconfig5"], &args)?)
+    }
+    desktop => Err(unsupported(desktop))
+  }
+}
+
+/// Enables night light.
+///
+/// Returns `true` if the state was changed, `false` otherwise.
+pub fn enable() -> Result<bool> {
+  set(true)
+}
+
+/// Disables night light.
+///
+/// Returns `true` if the state was changed, `false` otherwise.
+pub fn disable() -> Result<bool> {
+  set(false)
+}
+
+/// Toggles night light, returning whether it changed and the new state.
+pub fn toggle() -> Result<(bool, bool)> {
+  let enabled = !is_enabled()?;
+  Ok((set(enabled)?, enabled))
+}
+
+fn set(enabled: bool) -> Result<bool> {
+  if is_enabled()? == enabled {
+    return Ok(false);
+  }
+  let value = if enabled { "true" } else { "false" };
+  match DesktopEnvironment::detect() {
+    DesktopEnvironment::GNOME => {
+      run("gsettings", &["set", GNOME_SCHEMA, GNOME_KEY, value])?;
+    }
+    DesktopEnvironment::KDE => {
+      let mut args = KDE_ARGS.to_vec();
+      args.extend(["--type", "bool", value]);
+      run_either(["kwriteconfig6", "kwriteconfig5"], &args)?;
+
+      //{ KWin only rereads its config when asked to }
+      run(
+        "dbus-send",
+        &[
+          "--session",
+          "--type=method_call",
+          "--dest=org.kde.KWin",
+          "/KWin",
+          "org.kde.KWin.reconfigure"
+        ]
+      )?;
+    }
+    desktop => return Err(unsupported(desktop))
+  }
+  Ok(true)
+}
+
+/// Reads a boolean as printed by `gsettings` or `kreadconfig`.
+fn parse(output: &str) -> Result<bool> {
+  match output.trim() {
+    "true" => Ok(true),
+    "false" | "" => Ok(false),
+    other => Err(Error::ColorMode(format!(
+      "Linux: Unexpected night light state '{other}'"
+    )))
+  }
+}
+
+fn unsupported(desktop: DesktopEnvironment) -> Error {
+  Error::ColorMode(match desktop {
+    DesktopEnvironment::Unsupported(name) =>
+      format!("Linux: Night light is not supported on '{name}'"),
+    _ => "Linux: Could not determine the desktop environment for night light"
+      .to_string()
+  })
+}
+
+/// Runs the first of `programs` that is installed.
+fn run_either(programs: [&str; 2], args: &[&str]) -> Result<String> {
+  run(programs[0], args).or_else(|e| {
+    debug!("{e}; trying {}", programs[1]);
+    run(programs[1], args)
+  })
+}
+
+/// Runs `program`, returning what it printed.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+  let output = Command::new(program).args(args).output().map_err(|e| {
+    Error::ColorMode(format!("Linux: Failed to execute {program}: {e}"))
+  })?;
+  if !output.status.success() {
+    return Err(Error::ColorMode(format!(
+      "Linux: {program} failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert!(parse("true\n").unwrap());
+    assert!(!parse("false\n").unwrap());
+    assert!(!parse("").unwrap());
+    assert!(parse("'maybe'").is_err());
+  }
+}
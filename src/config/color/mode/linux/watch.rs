@@ -0,0 +1,46 @@
+//! Waits for GNOME's `color-scheme` setting to change, as reported by
+//! `gsettings monitor`.
+
+use super::DesktopEnvironment;
+use std::{
+  io::{BufRead, BufReader},
+  process::{Command, Stdio},
+  sync::mpsc::{self, RecvTimeoutError},
+  thread,
+  time::Duration
+};
+
+/// Blocks until `color-scheme` changes or `timeout` passes, returning
+/// whether it changed. Returns `None` outside GNOME or if `gsettings` can't
+/// watch the setting.
+pub fn wait(timeout: Duration) -> Option<bool> {
+  if DesktopEnvironment::detect() != DesktopEnvironment::GNOME {
+    return None;
+  }
+  let mut monitor = Command::new("gsettings")
+    .args(["monitor", "org.gnome.desktop.interface", "color-scheme"])
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .ok()?;
+  let stdout = monitor.stdout.take()?;
+
+  //{ gsettings prints a line per change, and nothing if it gives up }
+  let (sender, receiver) = mpsc::channel();
+  thread::spawn(move || {
+    let mut line = String::new();
+    let changed = BufReader::new(stdout)
+      .read_line(&mut line)
+      .is_ok_and(|read| read > 0);
+    let _ = sender.send(changed);
+  });
+  let changed = match receiver.recv_timeout(timeout) {
+    Ok(true) => Some(true),
+    Err(RecvTimeoutError::Timeout) => Some(false),
+    Ok(false) | Err(RecvTimeoutError::Disconnected) => None
+  };
+
+  let _ = monitor.kill();
+  let _ = monitor.wait();
+  changed
+}
@@ -0,0 +1,42 @@
+//! Manages the system appearance (light/dark) on macOS.
+//!
+//! The appearance is changed through System Events via `osascript`, which
+//! also notifies running applications, so no separate broadcast is needed.
+
+use super::super::{Config, Manager as ModeManager};
+use crate::{Error, Result};
+use std::process::Command;
+
+/// A manager for the macOS system appearance.
+pub struct Manager;
+
+impl ModeManager for Manager {
+  fn set(&self, mode: Config) -> Result<()> {
+    let dark = match mode {
+      Config::Dark => "true",
+      Config::Light => "false",
+      Config::Auto => unreachable!()
+    };
+    let script = format!(
+      "tell application \"System Events\" to tell appearance preferences to set dark mode to {dark}"
+    );
+
+    let status = Command::new("osascript")
+      .args(["-e", &script])
+      .status()
+      .map_err(|e| {
+        Error::ColorMode(format!("macOS: Failed to execute osascript: {e}"))
+      })?;
+
+    if !status.success() {
+      return Err(Error::ColorMode(
+        "macOS: Failed to set the appearance".to_string()
+      ));
+    }
+    Ok(())
+  }
+
+  fn notify(&self) -> Result<()> {
+    Ok(())
+  }
+}
@@ -1,7 +1,11 @@
 pub mod default;
 pub use default::{Config, Manager};
 
+pub mod watch;
+
 #[cfg(target_os = "linux")]
 pub mod linux;
+#[cfg(target_os = "macos")]
+pub mod macos;
 #[cfg(target_os = "windows")]
 pub mod windows;
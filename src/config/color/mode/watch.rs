@@ -0,0 +1,75 @@
+//! Waits for the system color mode to change.
+//!
+//! Where the platform can report changes to the mode setting, the wait
+//! sleeps until it does: on Windows through `RegNotifyChangeKeyValue` on the
+//! theme key, and on GNOME through `gsettings monitor`. Elsewhere, or when
+//! the setting can't be watched, the mode is read again every [POLL].
+
+use super::Config;
+use std::{
+  thread,
+  time::{Duration, Instant}
+};
+
+/// How often the mode is read where changes to it can't be watched.
+pub const POLL: Duration = Duration::from_secs(1);
+
+/// Waits up to `timeout` for the system mode to differ from `from`. Returns
+/// the new mode, or `None` if it did not change in time.
+pub fn wait_for_change(from: Config, timeout: Duration) -> Option<Config> {
+  let mut current = from;
+  wait_until(timeout, || {
+    current = Config::get_current();
+    current != from
+  })
+  .then_some(current)
+}
+
+/// Waits up to `timeout` for `done` to hold, checking it again whenever the
+/// mode setting changes. Returns whether it held in time.
+pub fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+  let deadline = Instant::now() + timeout;
+  loop {
+    if done() {
+      return true;
+    }
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+      return false;
+    }
+    if notified(remaining).is_none() {
+      thread::sleep(remaining.min(POLL));
+    }
+  }
+}
+
+/// Blocks until the platform reports a change to the mode setting or
+/// `timeout` passes, returning whether it changed. Returns `None` at once if
+/// changes can't be watched. Changes may be reported that leave the mode as
+/// it was, such as to other settings under the same key.
+fn notified(timeout: Duration) -> Option<bool> {
+  #[cfg(target_os = "windows")]
+  {
+    super::windows::watch::wait(timeout)
+  }
+  #[cfg(target_os = "linux")]
+  {
+    super::linux::watch::wait(timeout)
+  }
+  #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+  {
+    let _ = timeout;
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wait_until() {
+    assert!(wait_until(Duration::from_secs(5), || true));
+    assert!(!wait_until(Duration::ZERO, || false));
+  }
+}
@@ -0,0 +1,370 @@
+//! Switches the color schemes of console windows and Windows Terminal along
+//! with the system mode.
+//!
+//! Console windows (conhost, used by `cmd` and Windows PowerShell) read a
+//! 16-color table from `HKCU\Console` and its per-application subkeys. The
+//! whole table is written, together with the default text and popup colors,
+//! so that PSReadLine's syntax colors, which refer to table entries, stay
+//! readable in both modes. Before the first change, the user's own values are
+//! copied to `HKCU\Software\Wallter\Console Backup`, and [restore] puts them
+//! back. If any value fails to write, the values already written are rolled
+//! back, so a console is never left with half a scheme.
+//!
+//! Windows Terminal gets matching `Wallter Light` and `Wallter Dark` schemes
+//! in its `settings.json`, selected in `profiles.defaults`. The file is copied
+//! to `settings.json.wallter-backup` once and replaced atomically. Settings
+//! files with comments aren't strict JSON and are left untouched.
+
+use crate::{
+  Error, Result,
+  config::color::mode::Config as Mode,
+  simulate::{self, Action},
+  utils::{registry, store}
+};
+use serde_json::{Map, Value, json};
+use std::{env, fs, path::PathBuf};
+use tracing::warn;
+use winreg::enums::HKEY_CURRENT_USER;
+
+/// Console keys that may carry their own colors. Keys that don't exist are
+/// skipped, except the defaults in `Console`.
+const CONSOLE_KEYS: [&str; 4] = [
+  r"Console",
+  r"Console\%SystemRoot%_system32_WindowsPowerShell_v1.0_powershell.exe",
+  r"Console\%SystemRoot%_SysWOW64_WindowsPowerShell_v1.0_powershell.exe",
+  r"Console\%SystemRoot%_System32_cmd.exe"
+];
+
+/// Where the user's console colors are kept before the first change.
+const BACKUP_KEY: &str = r"Software\Wallter\Console Backup";
+
+/// Windows Terminal settings files, relative to `%LOCALAPPDATA%`.
+const TERMINAL_SETTINGS: [&str; 3] = [
+  r"Packages\Microsoft.WindowsTerminal_8wekyb3d8bbwe\LocalState\settings.json",
+  r"Packages\Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe\LocalState\settings.json",
+  r"Microsoft\Windows Terminal\settings.json"
+];
+
+/// Windows Terminal color names, in console table order.
+const TERMINAL_COLORS: [&str; 16] = [
+  "black",
+  "blue",
+  "green",
+  "cyan",
+  "red",
+  "purple",
+  "yellow",
+  "white",
+  "brightBlack",
+  "brightBlue",
+  "brightGreen",
+  "brightCyan",
+  "brightRed",
+  "brightPurple",
+  "brightYellow",
+  "brightWhite"
+];
+
+/// A 16-color terminal scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scheme {
+  pub name: &'static str,
+  /// RGB colors in console table order: black, blue, green, cyan, red,
+  /// magenta, yellow, white, then the bright variants.
+  pub table: [(u8, u8, u8); 16],
+  /// The table index of the default text color.
+  pub foreground: u8,
+  /// The table index of the default background color.
+  pub background: u8
+}
+
+impl Scheme {
+  /// The Campbell scheme Windows ships with.
+  pub const DARK: Self = Self {
+    name: "Wallter Dark",
+    table: [
+      (12, 12, 12),
+      (0, 55, 218),
+      (19, 161, 14),
+      (58, 150, 221),
+      (197, 15, 31),
+      (136, 23, 152),
+      (193, 156, 0),
+      (204, 204, 204),
+      (118, 118, 118),
+      (59, 120, 255),
+      (22, 198, 12),
+      (97, 214, 214),
+      (231, 72, 86),
+      (180, 0, 158),
+      (249, 241, 165),
+      (242, 242, 242)
+    ],
+    foreground: 7,
+    background: 0
+  };
+  /// The One Half Light scheme, dark text on a white background.
+  pub const LIGHT: Self = Self {
+    name: "Wallter Light",
+    table: [
+      (56, 58, 66),
+      (1, 132, 188),
+      (80, 161, 79),
+      (9, 151, 179),
+      (228, 86, 73),
+      (166, 38, 164),
+      (193, 131, 1),
+      (250, 250, 250),
+      (79, 82, 93),
+      (97, 175, 239),
+      (152, 195, 121),
+      (86, 181, 193),
+      (223, 108, 117),
+      (197, 119, 221),
+      (228, 192, 122),
+      (255, 255, 255)
+    ],
+    foreground: 0,
+    background: 7
+  };
+
+  /// Returns the scheme for `mode`, which must be resolved.
+  pub fn for_mode(mode: Mode) -> Self {
+    match mode {
+      Mode::Light => Self::LIGHT,
+      _ => Self::DARK
+    }
+  }
+
+  /// The console registry values for the scheme: `ColorTable00` to
+  /// `ColorTable15` as `0x00BBGGRR`, then `ScreenColors` and `PopupColors`.
+  pub fn console_values(&self) -> Vec<(String, u32)> {
+    let mut values: Vec<(String, u32)> = self
+      .table
+      .iter()
+      .enumerate()
+      .map(|(i, (r, g, b))| {
+        (
+          format!("ColorTable{i:02}"),
+          u32::from(*b) << 16 | u32::from(*g) << 8 | u32::from(*r)
+        )
+      })
+      .collect();
+    let screen = u32::from(self.background) << 4 | u32::from(self.foreground);
+    //{ Popups swap the default colors }
+    let popup = u32::from(self.foreground) << 4 | u32::from(self.background);
+    values.push(("ScreenColors".into(), screen));
+    values.push(("PopupColors".into(), popup));
+    values
+  }
+
+  /// The scheme as a Windows Terminal `schemes` entry.
+  pub fn to_terminal(&self) -> Value {
+    let hex = |index: u8| {
+      let (r, g, b) = self.table[usize::from(index)];
+      format!("#{r:02X}{g:02X}{b:02X}")
+    };
+    let mut scheme = Map::new();
+    scheme.insert("name".into(), json!(self.name));
+    scheme.insert("foreground".into(), json!(hex(self.foreground)));
+    scheme.insert("background".into(), json!(hex(self.background)));
+    scheme.insert("cursorColor".into(), json!(hex(self.foreground)));
+    scheme.insert("selectionBackground".into(), json!(hex(8)));
+    for (i, name) in TERMINAL_COLORS.iter().enumerate() {
+      scheme.insert((*name).into(), json!(hex(i as u8)));
+    }
+    Value::Object(scheme)
+  }
+}
+
+/// Applies the scheme for `mode` to console windows and Windows Terminal.
+/// Both are attempted; the first failure is returned.
+pub fn apply(mode: Mode) -> Result<()> {
+  let scheme = Scheme::for_mode(mode);
+  let console = apply_console(&scheme);
+  let terminal = apply_terminal(&scheme);
+  console.and(terminal)
+}
+
+/// Puts back the console colors saved before the first change.
+pub fn restore() -> Result<()> {
+  for path in CONSOLE_KEYS {
+    let saved = backup_values(path);
+    if !saved.is_empty() {
+      write_values(path, &saved)?;
+    }
+  }
+  Ok(())
+}
+
+/// Writes the scheme to every console key, rolling all of them back if any
+/// write fails.
+fn apply_console(scheme: &Scheme) -> Result<()> {
+  let values = scheme.console_values();
+  let keys: Vec<&str> = CONSOLE_KEYS
+    .into_iter()
+    .filter(|path| {
+      *path == CONSOLE_KEYS[0] || registry::key_exists(HKEY_CURRENT_USER, path)
+    })
+    .collect();
+
+  let mut written: Vec<(&str, Vec<(String, u32)>)> = Vec::new();
+  for path in keys {
+    let previous = read_values(path, &values);
+    backup(path, &previous)?;
+    if let Err(e) = write_values(path, &values) {
+      //{ Undo this key's partial write, then every key written before it }
+      let _ = write_values(path, &previous);
+      for (path, previous) in written.iter().rev() {
+        let _ = write_values(path, previous);
+      }
+      return Err(e);
+    }
+    written.push((path, previous));
+  }
+  Ok(())
+}
+
+/// Reads the current values of the entries in `names` that are set.
+fn read_values(path: &str, names: &[(String, u32)]) -> Vec<(String, u32)> {
+  names
+    .iter()
+    .filter_map(|(name, _)| {
+      registry::read_dword(HKEY_CURRENT_USER, path, name)
+        .map(|value| (name.clone(), value))
+    })
+    .collect()
+}
+
+fn write_values(path: &str, values: &[(String, u32)]) -> Result<()> {
+  for (name, value) in values {
+    registry::write_dword(HKEY_CURRENT_USER, path, name, *value)?;
+  }
+  Ok(())
+}
+
+/// Saves the user's values for a console key, unless they were saved before.
+fn backup(path: &str, values: &[(String, u32)]) -> Result<()> {
+  let key = backup_key(path);
+  if values.is_empty() || registry::key_exists(HKEY_CURRENT_USER, &key) {
+    return Ok(());
+  }
+  write_values(&key, values)
+}
+
+fn backup_values(path: &str) -> Vec<(String, u32)> {
+  read_values(&backup_key(path), &Scheme::DARK.console_values())
+}
+
+fn backup_key(path: &str) -> String {
+  format!(r"{BACKUP_KEY}\{}", path.replace('\\', "_"))
+}
+
+/// Adds the schemes to each Windows Terminal settings file found and selects
+/// `scheme` for all profiles.
+fn apply_terminal(scheme: &Scheme) -> Result<()> {
+  let Ok(local) = env::var("LOCALAPPDATA") else {
+    return Ok(());
+  };
+  let files = TERMINAL_SETTINGS
+    .iter()
+    .map(|path| PathBuf::from(&local).join(path))
+    .filter(|path| path.is_file());
+
+  for file in files {
+    let content = fs::read_to_string(&file)?;
+    let Ok(mut settings) = serde_json::from_str::<Value>(&content) else {
+      warn!(
+        "Skipping '{}', which has comments or isn't valid JSON",
+        file.display()
+      );
+      continue;
+    };
+    if !select_scheme(&mut settings, scheme) {
+      continue;
+    }
+
+    let backup = file.with_extension("json.wallter-backup");
+    if !backup.exists()
+      && !simulate::dry_run(Action::CopyFile {
+        from: file.clone(),
+        to: backup.clone()
+      })?
+    {
+      fs::copy(&file, &backup)?;
+    }
+    let contents = serde_json::to_string_pretty(&settings)
+      .map_err(|e| Error::Config(e.to_string()))?;
+    store::replace(&file, &contents)?;
+  }
+  Ok(())
+}
+
+/// Adds both schemes to Windows Terminal `settings` and selects `scheme` as
+/// the default for all profiles. Returns whether anything changed.
+fn select_scheme(settings: &mut Value, scheme: &Scheme) -> bool {
+  let Some(root) = settings.as_object_mut() else {
+    return false;
+  };
+  let before = root.clone();
+
+  //{ Replace earlier copies of our schemes, leaving the user's alone }
+  let schemes = root.entry("schemes").or_insert_with(|| json!([]));
+  if let Some(schemes) = schemes.as_array_mut() {
+    let ours = [Scheme::LIGHT, Scheme::DARK];
+    schemes.retain(|existing| {
+      !ours.iter().any(|s| existing["name"] == json!(s.name))
+    });
+    schemes.extend(ours.iter().map(Scheme::to_terminal));
+  }
+
+  let profiles = root.entry("profiles").or_insert_with(|| json!({}));
+  match profiles {
+    Value::Object(profiles) => {
+      let defaults = profiles.entry("defaults").or_insert_with(|| json!({}));
+      if let Some(defaults) = defaults.as_object_mut() {
+        defaults.insert("colorScheme".into(), json!(scheme.name));
+      }
+    }
+    //{ Older settings list the profiles directly }
+    Value::Array(list) =>
+      for profile in list.iter_mut().filter_map(Value::as_object_mut) {
+        profile.insert("colorScheme".into(), json!(scheme.name));
+      },
+    _ => {}
+  }
+
+  *root != before
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::BTreeMap;
+
+  #[test]
+  fn test_console_values() {
+    let values: BTreeMap<_, _> =
+      Scheme::LIGHT.console_values().into_iter().collect();
+    assert_eq!(values.len(), 18);
+    assert_eq!(values["ColorTable01"], 0x00BC_8401);
+    assert_eq!(values["ScreenColors"], 0x70);
+    assert_eq!(values["PopupColors"], 0x07);
+  }
+
+  #[test]
+  fn test_select_scheme() {
+    let mut settings = json!({
+      "profiles": { "list": [] },
+      "schemes": [{ "name": "Mine" }, { "name": "Wallter Dark" }]
+    });
+    assert!(select_scheme(&mut settings, &Scheme::DARK));
+    assert_eq!(
+      settings["profiles"]["defaults"]["colorScheme"],
+      "Wallter Dark"
+    );
+    assert_eq!(settings["schemes"].as_array().unwrap().len(), 3);
+    assert_eq!(settings["schemes"][2]["background"], "#0C0C0C");
+    assert!(!select_scheme(&mut settings, &Scheme::DARK));
+  }
+}
@@ -9,12 +9,16 @@
 // use super::NightlightState;
 use crate::{
   Error, Result,
-  config::color::mode::{
-    Config as Mode, Manager as ModeManager, windows::nightlight
-  },
-  utils::registry
+  config::{
+    color::mode::{
+      Config as Mode, Manager as ModeManager, watch,
+      windows::{console, nightlight}
+    },
+    conflicts::Manager as Other
+  }
 };
 use std::{io, process::Command};
+use tracing::{debug, info, warn};
 use winreg::{RegKey, enums::*};
 
 /// Enhanced theme switching strategy with proper night-light support
@@ -35,23 +39,12 @@ pub enum Strategy {
 
 impl Default for Strategy {
   fn default() -> Self {
-    if registry::value_exists(
-      HKEY_CURRENT_USER,
-      r"Software\Microsoft\Windows\CurrentVersion\Run",
-      "AutoDarkMode"
-    ) || registry::key_exists(
-      HKEY_CURRENT_USER,
-      r"Software\AutoDarkMode\Installed"
-    ) || registry::key_exists(HKEY_LOCAL_MACHINE, r"SOFTWARE\AutoDarkMode")
-    {
-      eprintln!(
-        "[DEBUG] Default Strategy: Auto Dark Mode detected, setting to Nightlight."
-      );
-
+    if Other::AutoDarkMode.is_installed() {
+      debug!("Auto Dark Mode detected; defaulting to the Nightlight strategy");
       Self::Nightlight
     } else {
-      eprintln!(
-        "[DEBUG] Default Strategy: Auto Dark Mode not detected, setting to SystemComponents."
+      debug!(
+        "Auto Dark Mode not detected; defaulting to the SystemComponents strategy"
       );
       Self::SystemComponents
     }
@@ -65,7 +58,7 @@ pub struct Manager {
 
 impl Manager {
   /// Primary registry paths
-  const REGISTRY_PATH: &str =
+  pub(crate) const REGISTRY_PATH: &str =
     r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
   const DWM_PATH: &str = r"Software\Microsoft\Windows\DWM";
 
@@ -102,22 +95,16 @@ impl Manager {
   /// Mode)
   /// Set only Night Light (blue light filter) settings
   fn set_night_light(&self, mode: Mode) -> Result<()> {
-    // No println! here, use logging if needed
-
     // Use the functions from the dedicated nightlight module.
     // This encapsulates all the complex byte parsing and registry logic.
     let changed = match mode {
-      Mode::Dark => nightlight::enable()?, // Enable night light for dark mode
+      Mode::Dark => nightlight::enable_for_dark_mode()?, // Enable night light for dark mode
       Mode::Light => nightlight::disable()?, /* Disable night light for light */ // Use info! for logging
       // mode
       Mode::Auto => unreachable!()
     };
 
-    // if changed {
-    //   // No println! here, use logging if needed
-    // } else {
-    //   // No println! here, use logging if needed
-    // }
+    debug!(changed, "Set Night Light for {mode:?} mode");
     Ok(())
   }
 
@@ -272,26 +259,9 @@ impl Manager {
   fn set_system_specific_keys(&self, value: u32, config: Mode) -> Result<()> {
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
 
-    // Windows Terminal and Console settings
-    let console_paths = [
-      r"Console",
-      r"Console\%SystemRoot%_system32_WindowsPowerShell_v1.0_powershell.exe",
-      r"Console\%SystemRoot%_System32_cmd.exe"
-    ];
-
-    for path in &console_paths {
-      if let Ok(console_key) = hkcu.open_subkey_with_flags(path, KEY_ALL_ACCESS)
-      {
-        // Set console color scheme based on theme
-        let color_table = match config {
-          Mode::Light => 0x00F0F0F0u32, // Light background
-          Mode::Dark => 0x00000000u32,  // Dark background
-          Mode::Auto => unreachable!()
-        };
-        let _ = console_key.set_value("ColorTable00", &color_table);
-        let _ =
-          console_key.set_value("ColorTable07", &(!color_table & 0x00FFFFFF));
-      }
+    // Console and Windows Terminal color schemes
+    if let Err(e) = console::apply(config) {
+      warn!("Failed to switch the console color scheme: {e}");
     }
 
     // Windows Explorer settings
@@ -497,25 +467,19 @@ impl Manager {
     })
   }
 
-  /// Wait for theme change to take effect (polling method)
+  /// Wait for theme change to take effect, sleeping until the theme key
+  /// changes rather than polling it
   pub fn wait_for_theme_change(
     &self,
     expected: Mode,
     timeout_ms: u64
   ) -> Result<bool> {
-    let start = std::time::Instant::now();
     let timeout = std::time::Duration::from_millis(timeout_ms);
-
-    while start.elapsed() < timeout {
-      if let Ok(current) = self.get_current_theme()
-        && std::mem::discriminant(&current) == std::mem::discriminant(&expected)
-      {
-        return Ok(true);
-      }
-      std::thread::sleep(std::time::Duration::from_millis(100));
-    }
-
-    Ok(false)
+    Ok(watch::wait_until(timeout, || {
+      self
+        .get_current_theme()
+        .is_ok_and(|current| current == expected)
+    }))
   }
 }
 
@@ -544,8 +508,7 @@ impl ModeManager for Manager {
         self.notify_theme_change()?;
         // Gentle refresh without restarting explorer (log warning if it fails)
         if let Err(e) = self.refresh_explorer() {
-          // Use error! for warnings
-          eprintln!("[DEBUG] Failed to refresh desktop: {e}");
+          warn!("Failed to refresh the desktop: {e}");
         }
       }
       Strategy::ForceRefresh => {
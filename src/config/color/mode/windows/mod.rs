@@ -1,4 +1,7 @@
+pub mod console;
+
 mod default;
 pub use default::*;
 
 pub mod nightlight;
+pub mod watch;
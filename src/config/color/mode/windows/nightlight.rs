@@ -1,13 +1,27 @@
 use crate::{Error, Result, consts::*, utils::parse};
+use chrono::{Local, NaiveTime};
 use std::{
   io,
+  sync::RwLock,
   time::{SystemTime, UNIX_EPOCH}
 };
+use tracing::{debug, trace};
 use winreg::{RegKey, enums::*};
 
 const NIGHTLIGHT_STATE_REGISTRY_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.bluelightreductionstate\windows.data.bluelightreduction.bluelightreductionstate";
 const NIGHTLIGHT_STATE_REGISTRY_VAL: &str = "Data";
 const NIGHTLIGHT_STATE_ENABLED_BYTES: [u8; 2] = [0x10, 0x00];
+const NIGHTLIGHT_SETTINGS_REGISTRY_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.settings\windows.data.bluelightreduction.settings";
+const NIGHTLIGHT_SCHEDULE_ENABLED_BYTES: [u8; 2] = [0x02, 0x01];
+const NIGHTLIGHT_SUNSET_TO_SUNRISE_BYTES: [u8; 3] = [0xC2, 0x0A, 0x00];
+const NIGHTLIGHT_START_TIME_PREFIX: [u8; 2] = [0xCA, 0x14];
+const NIGHTLIGHT_END_TIME_PREFIX: [u8; 2] = [0xCA, 0x1E];
+const NIGHTLIGHT_SUNSET_TIME_PREFIX: [u8; 2] = [0xCA, 0x32];
+const NIGHTLIGHT_SUNRISE_TIME_PREFIX: [u8; 2] = [0xCA, 0x3C];
+
+/// Whether dark mode leaves Night Light to its schedule during scheduled
+/// hours, rather than forcing it on.
+static RESPECT_SCHEDULE: RwLock<bool> = RwLock::new(false);
 
 /// The nightlight state data structure has the following binary format:
 ///
@@ -92,10 +106,19 @@ impl State {
 
   /// Writes the nightlight state to the Windows registry
   pub fn write_to_registry(&self) -> Result<()> {
-    let key = Self::open_nightlight_registry_key(KEY_SET_VALUE)?;
-
     let data = self.serialize_to_bytes();
 
+    //{ Journal the write instead of performing it when simulating }
+    if crate::simulate::record(crate::simulate::Action::RegistryWrite {
+      path: NIGHTLIGHT_STATE_REGISTRY_KEY.to_string(),
+      name: NIGHTLIGHT_STATE_REGISTRY_VAL.to_string(),
+      bytes: data.clone()
+    })? {
+      return Ok(());
+    }
+
+    let key = Self::open_nightlight_registry_key(KEY_SET_VALUE)?;
+
     // Set raw binary data to registry
     key
       .set_raw_value(
@@ -194,9 +217,7 @@ impl State {
     }
     pos = end;
 
-    println!(
-      "[DEBUG] State::deserialize_from_bytes: Parsed state: timestamp={timestamp}, is_enabled={is_enabled}"
-    );
+    trace!(timestamp, is_enabled, "Parsed the night light state");
     Ok(Self {
       timestamp,
       is_enabled,
@@ -208,7 +229,7 @@ impl State {
   /// See [State] for more information about the binary format.
   pub fn serialize_to_bytes(&self) -> Vec<u8> {
     let mut bytes: Vec<u8> = Vec::new();
-    println!("[DEBUG] State::serialize_to_bytes: Serializing state: {self:?}");
+    trace!(state = ?self, "Serializing the night light state");
 
     bytes.extend_from_slice(&STRUCT_HEADER_BYTES);
     bytes.extend_from_slice(&TIMESTAMP_HEADER_BYTES);
@@ -248,10 +269,7 @@ impl State {
   /// Returns true if a change was made (i.e. the nightlight was previously
   /// disabled).
   pub fn enable(&mut self) -> bool {
-    println!(
-      "[DEBUG] State::enable: Called. Current state is_enabled={}",
-      self.is_enabled
-    );
+    debug!(is_enabled = self.is_enabled, "Enabling night light");
     if !self.is_enabled {
       self.is_enabled = true;
       self.update_timestamp();
@@ -265,10 +283,7 @@ impl State {
   /// Returns true if a change was made (i.e. the nightlight was previously
   /// enabled).
   pub fn disable(&mut self) -> bool {
-    println!(
-      "[DEBUG] State::disable: Called. Current state is_enabled={}",
-      self.is_enabled
-    );
+    debug!(is_enabled = self.is_enabled, "Disabling night light");
     if self.is_enabled {
       self.is_enabled = false;
       self.update_timestamp();
@@ -297,6 +312,113 @@ impl State {
   }
 }
 
+/// The Night Light schedule, kept in a settings blob beside the state.
+///
+/// The settings share the header, timestamp and size byte of [State]. The
+/// rest holds [NIGHTLIGHT_SCHEDULE_ENABLED_BYTES] when the schedule is on,
+/// [NIGHTLIGHT_SUNSET_TO_SUNRISE_BYTES] when it follows the sun, and a
+/// [time block](parse::time_block) after each of the time prefixes. Other
+/// settings, such as the color temperature, are not read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+  /// Whether Night Light is scheduled at all.
+  pub is_enabled: bool,
+  /// Whether it runs from sunset to sunrise rather than set hours.
+  pub sunset_to_sunrise: bool,
+  pub start: NaiveTime,
+  pub end: NaiveTime,
+  /// The sunset and sunrise Windows last worked out for the location.
+  pub sunset: NaiveTime,
+  pub sunrise: NaiveTime
+}
+
+impl Schedule {
+  /// Reads the Night Light schedule from the Windows registry
+  pub fn read_from_registry() -> Result<Self> {
+    let data = RegKey::predef(HKEY_CURRENT_USER)
+      .open_subkey_with_flags(NIGHTLIGHT_SETTINGS_REGISTRY_KEY, KEY_READ)
+      .and_then(|key| key.get_raw_value(NIGHTLIGHT_STATE_REGISTRY_VAL))
+      .map_err(|e| {
+        Error::IO(io::Error::new(
+          io::ErrorKind::NotFound,
+          format!("Failed to read the Night Light schedule: {e}")
+        ))
+      })?
+      .bytes;
+    Self::deserialize_from_bytes(&data)
+  }
+
+  /// Deserializes a [Schedule] from the settings blob.
+  pub fn deserialize_from_bytes(data: &[u8]) -> Result<Self> {
+    if data.get(..STRUCT_HEADER_BYTES.len()) != Some(&STRUCT_HEADER_BYTES) {
+      return Err(Error::Parse(parse::Error::StructHeader {
+        expected: STRUCT_HEADER_BYTES.to_vec(),
+        actual: data
+          .get(..STRUCT_HEADER_BYTES.len())
+          .unwrap_or_default()
+          .to_vec()
+      }));
+    }
+    let (_, pos) =
+      parse::last_modified_timestamp_block(data, STRUCT_HEADER_BYTES.len())?;
+
+    //{ Skip the size byte and the inner header }
+    let pos = pos + 1 + STRUCT_HEADER_BYTES.len();
+    let body = data.get(pos..).ok_or_else(|| {
+      Error::Parse(parse::Error::Block("Missing settings".to_string()))
+    })?;
+    let time = |prefix: &[u8]| -> Result<NaiveTime> {
+      Ok(parse::time_block(data, pos, prefix)?.unwrap_or_default())
+    };
+    let schedule = Self {
+      is_enabled: body.starts_with(&NIGHTLIGHT_SCHEDULE_ENABLED_BYTES),
+      sunset_to_sunrise: body
+        .windows(NIGHTLIGHT_SUNSET_TO_SUNRISE_BYTES.len())
+        .any(|w| w == NIGHTLIGHT_SUNSET_TO_SUNRISE_BYTES),
+      start: time(&NIGHTLIGHT_START_TIME_PREFIX)?,
+      end: time(&NIGHTLIGHT_END_TIME_PREFIX)?,
+      sunset: time(&NIGHTLIGHT_SUNSET_TIME_PREFIX)?,
+      sunrise: time(&NIGHTLIGHT_SUNRISE_TIME_PREFIX)?
+    };
+    trace!(?schedule, "Parsed the night light schedule");
+    Ok(schedule)
+  }
+
+  /// Whether the schedule has Night Light on at `time`. A window that ends
+  /// before it starts runs past midnight.
+  pub fn is_on_at(&self, time: NaiveTime) -> bool {
+    let (start, end) = if self.sunset_to_sunrise {
+      (self.sunset, self.sunrise)
+    } else {
+      (self.start, self.end)
+    };
+    self.is_enabled
+      && if start <= end {
+        start <= time && time < end
+      } else {
+        time >= start || time < end
+      }
+  }
+}
+
+/// Sets whether dark mode leaves Night Light to its schedule during
+/// scheduled hours.
+pub fn configure(respect_schedule: bool) {
+  *RESPECT_SCHEDULE.write().unwrap_or_else(|e| e.into_inner()) =
+    respect_schedule;
+}
+
+fn respects_schedule() -> bool {
+  *RESPECT_SCHEDULE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Reads the schedule, or `None` if it can't be read.
+fn schedule() -> Option<Schedule> {
+  Schedule::read_from_registry()
+    .inspect_err(|e| debug!("Could not read the Night Light schedule: {e}"))
+    .ok()
+}
+
 /// Gets the current nightlight state from the registry.
 pub fn get_state() -> Result<State> {
   State::read_from_registry()
@@ -343,6 +465,44 @@ pub fn disable() -> Result<bool> {
   }
 }
 
+/// Turns Night Light on for dark mode.
+///
+/// While the schedule is [respected](configure) and has Night Light on
+/// anyway, the force flag is not written, so the schedule still turns it off
+/// when its hours are up.
+///
+/// Returns `true` if the state was changed, `false` otherwise.
+pub fn enable_for_dark_mode() -> Result<bool> {
+  if respects_schedule()
+    && schedule().is_some_and(|s| s.is_on_at(Local::now().time()))
+  {
+    debug!("Night Light is scheduled on; leaving it to the schedule");
+    return Ok(false);
+  }
+  enable()
+}
+
+/// Clears the force flag when the color mode returns to Auto, so dark mode
+/// doesn't keep Night Light on past its schedule. Only done while the
+/// schedule is [respected](configure), is on, and has Night Light off at this
+/// hour, as Night Light on at such a time can only have been forced.
+///
+/// Returns `true` if the state was changed, `false` otherwise.
+pub fn release() -> Result<bool> {
+  if !respects_schedule() {
+    return Ok(false);
+  }
+  match schedule() {
+    Some(schedule)
+      if schedule.is_enabled && !schedule.is_on_at(Local::now().time()) =>
+    {
+      debug!("Handing Night Light back to its schedule");
+      disable()
+    }
+    _ => Ok(false)
+  }
+}
+
 /// Toggles the nightlight state and saves it to the registry.
 ///
 /// Returns a tuple `(changed, new_state)`, where `changed` is a boolean
@@ -442,6 +602,34 @@ mod tests {
     assert_eq!(state_deserialized, state_enabled);
   }
 
+  #[test]
+  fn test_schedule() {
+    //{ Scheduled from 21:30 to 07:00, with sunset at 18:45 and sunrise at 06:15
+    //{ }
+    let data = [
+      0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0x89, 0x95,
+      0xFC, 0xBE, 0x06, 0x2A, 0x2B, 0x0E, 0x23, 0x43, 0x42, 0x01, 0x00, 0x02,
+      0x01, 0xCA, 0x14, 0x0E, 0x15, 0x2E, 0x1E, 0x00, 0xCA, 0x1E, 0x0E, 0x07,
+      0x00, 0xCA, 0x32, 0x0E, 0x12, 0x2E, 0x2D, 0x00, 0xCA, 0x3C, 0x0E, 0x06,
+      0x2E, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00
+    ];
+    let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+    let mut schedule = Schedule::deserialize_from_bytes(&data).unwrap();
+    assert!(schedule.is_enabled && !schedule.sunset_to_sunrise);
+    assert_eq!((schedule.start, schedule.end), (time(21, 30), time(7, 0)));
+    assert_eq!(
+      (schedule.sunset, schedule.sunrise),
+      (time(18, 45), time(6, 15))
+    );
+    assert!(schedule.is_on_at(time(23, 0)) && schedule.is_on_at(time(6, 59)));
+    assert!(!schedule.is_on_at(time(7, 0)) && !schedule.is_on_at(time(20, 0)));
+
+    schedule.sunset_to_sunrise = true;
+    assert!(schedule.is_on_at(time(20, 0)) && !schedule.is_on_at(time(6, 30)));
+    schedule.is_enabled = false;
+    assert!(!schedule.is_on_at(time(23, 0)));
+  }
+
   #[test]
   fn test_timestamp_conversion() {
     let timestamp = 1742670473u64;
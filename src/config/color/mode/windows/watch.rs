@@ -0,0 +1,63 @@
+//! Waits for the theme registry key to change, as reported by
+//! `RegNotifyChangeKeyValue`.
+
+#![allow(unsafe_code)]
+
+use super::Manager;
+use std::{ffi::OsStr, os::windows::ffi::OsStrExt, ptr, time::Duration};
+use winapi::{
+  shared::{
+    minwindef::{FALSE, HKEY, TRUE},
+    winerror::ERROR_SUCCESS
+  },
+  um::{
+    handleapi::CloseHandle,
+    synchapi::{CreateEventW, WaitForSingleObject},
+    winbase::WAIT_OBJECT_0,
+    winnt::{KEY_NOTIFY, REG_NOTIFY_CHANGE_LAST_SET},
+    winreg::{
+      HKEY_CURRENT_USER, RegCloseKey, RegNotifyChangeKeyValue, RegOpenKeyExW
+    }
+  }
+};
+
+/// Blocks until a value under the theme key changes or `timeout` passes,
+/// returning whether it changed. Returns `None` if the key can't be watched.
+pub fn wait(timeout: Duration) -> Option<bool> {
+  let path: Vec<u16> = OsStr::new(Manager::REGISTRY_PATH)
+    .encode_wide()
+    .chain(Some(0))
+    .collect();
+  //{ u32::MAX would mean waiting forever }
+  let millis = u32::try_from(timeout.as_millis())
+    .unwrap_or(u32::MAX)
+    .min(u32::MAX - 1);
+
+  // SAFETY: `path` is NUL-terminated, and the key and event are checked
+  // before use and closed exactly once.
+  unsafe {
+    let mut key: HKEY = ptr::null_mut();
+    if RegOpenKeyExW(HKEY_CURRENT_USER, path.as_ptr(), 0, KEY_NOTIFY, &mut key)
+      != ERROR_SUCCESS as i32
+    {
+      return None;
+    }
+    let event = CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null());
+    if event.is_null() {
+      RegCloseKey(key);
+      return None;
+    }
+    let watching = RegNotifyChangeKeyValue(
+      key,
+      FALSE,
+      REG_NOTIFY_CHANGE_LAST_SET,
+      event,
+      TRUE
+    ) == ERROR_SUCCESS as i32;
+    let changed =
+      watching.then(|| WaitForSingleObject(event, millis) == WAIT_OBJECT_0);
+    CloseHandle(event);
+    RegCloseKey(key);
+    changed
+  }
+}
@@ -0,0 +1,280 @@
+//! Limits night light to some monitors.
+//!
+//! Night light follows dark mode. By default it is left to the platform; once
+//! `color.night_light.monitors` lists any monitors, only those are warmed:
+//!
+//! - `internal` matches built-in laptop panels (`eDP`, `LVDS` and `DSI`
+//!   outputs), `external` matches every other monitor, and anything else is
+//!   matched against monitor names.
+//! - On X11, each covered output gets a warm gamma ramp through `xrandr` and
+//!   the others are reset, so a color-accurate external display stays neutral.
+//! - Windows, and Linux desktops under Wayland, where the compositor owns the
+//!   gamma, only have a system-wide night light, so it is turned on only when
+//!   every connected monitor is covered. Windows monitor names don't reveal
+//!   built-in panels, so list them by name there.
+//! - With `respect_schedule`, dark mode leaves Windows Night Light to its
+//!   schedule during scheduled hours instead of forcing it on, and returning to
+//!   `Auto` clears a force flag left on outside them.
+//!
+//! [system_enabled], [set_system] and [toggle_system] drive the desktop's own,
+//! system-wide night light instead: Windows Night Light, GNOME Night Light or
+//! KDE Night Color.
+
+use super::Mode;
+use crate::{
+  Error, Result,
+  config::Monitor,
+  simulate::{self, Action}
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+#[cfg(target_os = "linux")]
+use super::mode::linux::nightlight as system;
+#[cfg(target_os = "windows")]
+use super::mode::windows::nightlight as system;
+
+const DEFAULT_TEMPERATURE: u32 = 4500;
+
+/// Output name prefixes used for built-in panels.
+const INTERNAL_PREFIXES: [&str; 3] = ["edp", "lvds", "dsi"];
+
+/// Which monitors get night light, and how warm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NightLight {
+  /// Monitors to warm: `internal`, `external` or monitor names. Empty leaves
+  /// night light to the platform.
+  #[serde(default)]
+  pub monitors: Vec<String>,
+  /// The color temperature in Kelvin used for per-monitor gamma.
+  #[serde(default = "default_temperature")]
+  pub temperature: u32,
+  /// Whether Windows Night Light is left to its schedule rather than forced
+  /// on by dark mode.
+  #[serde(default)]
+  pub respect_schedule: bool
+}
+
+fn default_temperature() -> u32 {
+  DEFAULT_TEMPERATURE
+}
+
+impl Default for NightLight {
+  fn default() -> Self {
+    Self {
+      monitors: Vec::new(),
+      temperature: DEFAULT_TEMPERATURE,
+      respect_schedule: false
+    }
+  }
+}
+
+impl NightLight {
+  /// Whether night light is limited to some monitors.
+  pub fn is_gated(&self) -> bool {
+    !self.monitors.is_empty()
+  }
+
+  /// Whether night light should warm `monitor`.
+  pub fn covers(&self, monitor: &Monitor) -> bool {
+    !self.is_gated()
+      || self.monitors.iter().any(|entry| match entry.as_str() {
+        "internal" => is_internal(&monitor.name),
+        "external" => !is_internal(&monitor.name),
+        name => name.eq_ignore_ascii_case(&monitor.name)
+      })
+  }
+
+  /// Turns night light on for the covered monitors when switching to dark
+  /// mode, and off everywhere when switching to light. Does nothing unless
+  /// night light is gated.
+  pub fn apply(&self, mode: Mode, monitors: &[Monitor]) -> Result<()> {
+    if !self.is_gated() {
+      return Ok(());
+    }
+    let dark = mode == Mode::Dark;
+    //{ Outside X11 the compositor owns the gamma ramps }
+    #[cfg(target_os = "linux")]
+    let per_output = super::mode::linux::SessionType::detect()
+      == super::mode::linux::SessionType::X11;
+    for monitor in monitors {
+      let enabled = dark && self.covers(monitor);
+      if simulate::record(Action::NightLight {
+        monitor: monitor.name.clone(),
+        enabled
+      })? {
+        continue;
+      }
+      #[cfg(target_os = "linux")]
+      if per_output {
+        let gamma = if enabled {
+          gamma(self.temperature)
+        } else {
+          (1.0, 1.0, 1.0)
+        };
+        set_gamma(&monitor.name, gamma)?;
+      }
+    }
+
+    #[cfg(target_os = "linux")]
+    if !per_output && !simulate::is_enabled() {
+      if let Err(e) = self.apply_system_wide(dark, monitors) {
+        warn!("Per-monitor night light needs an X11 session: {e}");
+      }
+    }
+
+    #[cfg(target_os = "windows")]
+    if !simulate::is_enabled() {
+      self.apply_system_wide(dark, monitors)?;
+    }
+
+    #[cfg(target_os = "macos")]
+    if dark && !simulate::is_enabled() {
+      warn!("Per-monitor night light is not supported on macOS.");
+    }
+
+    Ok(())
+  }
+
+  /// Turns the desktop's system-wide night light on for dark mode when every
+  /// monitor is covered, and off otherwise.
+  #[cfg(any(target_os = "linux", target_os = "windows"))]
+  fn apply_system_wide(&self, dark: bool, monitors: &[Monitor]) -> Result<()> {
+    let everywhere = monitors.iter().all(|monitor| self.covers(monitor));
+    if dark && everywhere {
+      #[cfg(target_os = "linux")]
+      system::enable()?;
+      #[cfg(target_os = "windows")]
+      system::enable_for_dark_mode()?;
+    } else {
+      if dark {
+        info!(
+          "Night light is system-wide here; leaving it off while an uncovered monitor is connected"
+        );
+      }
+      system::disable()?;
+    }
+    Ok(())
+  }
+
+  /// Hands night light back to the system when the mode returns to `Auto`,
+  /// clearing a Windows Night Light force flag left on outside its schedule
+  /// if the schedule is respected.
+  pub fn release(&self) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    if self.respect_schedule {
+      super::mode::windows::nightlight::release()?;
+    }
+    Ok(())
+  }
+}
+
+/// Whether the system-wide night light is on.
+pub fn system_enabled() -> Result<bool> {
+  #[cfg(any(target_os = "linux", target_os = "windows"))]
+  return system::is_enabled();
+  #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+  Err(unsupported())
+}
+
+/// Turns the system-wide night light on or off.
+///
+/// Returns `true` if the state was changed, `false` otherwise.
+pub fn set_system(enabled: bool) -> Result<bool> {
+  if simulate::record(Action::SystemNightLight { enabled })? {
+    return Ok(true);
+  }
+  #[cfg(any(target_os = "linux", target_os = "windows"))]
+  return if enabled {
+    system::enable()
+  } else {
+    system::disable()
+  };
+  #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+  Err(unsupported())
+}
+
+/// Flips the system-wide night light, returning its new state.
+pub fn toggle_system() -> Result<bool> {
+  let enabled = !system_enabled()?;
+  set_system(enabled)?;
+  Ok(enabled)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn unsupported() -> Error {
+  Error::ColorMode(
+    "A system night light is only supported on Windows, GNOME and KDE"
+      .to_string()
+  )
+}
+
+/// Whether `name` is the output name of a built-in panel.
+pub fn is_internal(name: &str) -> bool {
+  let name = name.to_ascii_lowercase();
+  INTERNAL_PREFIXES
+    .iter()
+    .any(|prefix| name.starts_with(prefix))
+}
+
+/// Approximates the gamma multipliers for a color temperature in Kelvin,
+/// normalized so that 6500K is neutral.
+pub fn gamma(temperature: u32) -> (f32, f32, f32) {
+  let green = |t: f32| 0.390_081_58 * t.ln() - 0.631_841_4;
+  let blue = |t: f32| 0.543_206_8 * (t - 10.0).ln() - 1.196_254_1;
+
+  let t = temperature.clamp(1000, 6500) as f32 / 100.0;
+  let blue = if t <= 19.0 { 0.0 } else { blue(t) / blue(65.0) };
+  (
+    1.0,
+    (green(t) / green(65.0)).clamp(0.0, 1.0),
+    blue.clamp(0.0, 1.0)
+  )
+}
+
+/// Sets the gamma of an X11 output through `xrandr`.
+#[cfg(target_os = "linux")]
+fn set_gamma(output: &str, (red, green, blue): (f32, f32, f32)) -> Result<()> {
+  let status = std::process::Command::new("xrandr")
+    .args([
+      "--output",
+      output,
+      "--gamma",
+      &format!("{red:.3}:{green:.3}:{blue:.3}")
+    ])
+    .status()
+    .map_err(|e| {
+      Error::ColorMode(format!("Linux/X11: Failed to execute xrandr: {e}"))
+    })?;
+  if !status.success() {
+    return Err(Error::ColorMode(format!(
+      "Linux/X11: xrandr could not set the gamma of '{output}'"
+    )));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_night_light_covers() {
+    let night = NightLight {
+      monitors: vec!["internal".into(), "HDMI-1".into()],
+      ..NightLight::default()
+    };
+    assert!(night.covers(&Monitor::test("eDP-1", 1920, 1080)));
+    assert!(night.covers(&Monitor::test("hdmi-1", 1920, 1080)));
+    assert!(!night.covers(&Monitor::test("DP-2", 1920, 1080)));
+    assert!(NightLight::default().covers(&Monitor::test("DP-2", 1920, 1080)));
+  }
+
+  #[test]
+  fn test_gamma() {
+    let (red, green, blue) = gamma(6500);
+    assert_eq!((red, green, blue), (1.0, 1.0, 1.0));
+    let (red, green, blue) = gamma(DEFAULT_TEMPERATURE);
+    assert!(red == 1.0 && green < 1.0 && blue < green);
+  }
+}
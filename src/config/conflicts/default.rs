@@ -0,0 +1,155 @@
+//! What to do when another program already manages wallpapers or the color
+//! mode.
+//!
+//! Running alongside Variety, Komorebi, Wallpaper Engine or Auto Dark Mode
+//! means both programs fight over the desktop. The policy decides who wins:
+//! wallter can warn and carry on, defer the subsystems the other manager
+//! handles, or integrate by only downloading wallpapers for it to show.
+
+use super::{Manager, Subsystem};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter}
+};
+use tracing::warn;
+
+/// How to share the desktop with another manager.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+  /// Warn about the other manager, then manage everything anyway.
+  #[default]
+  Warn,
+  /// Leave the subsystems the other manager handles alone.
+  Defer,
+  /// Only download wallpapers; leave wallpapers and the color mode alone.
+  Integrate,
+  /// Don't look for other managers.
+  Ignore
+}
+
+impl Policy {
+  /// Whether `manager` running means `subsystem` should be left alone.
+  pub fn yields(self, manager: Manager, subsystem: Subsystem) -> bool {
+    match self {
+      Self::Warn | Self::Ignore => false,
+      Self::Defer => manager.manages(subsystem),
+      Self::Integrate => true
+    }
+  }
+}
+
+impl Display for Policy {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Warn => write!(f, "Warn"),
+      Self::Defer => write!(f, "Defer"),
+      Self::Integrate => write!(f, "Integrate (downloads only)"),
+      Self::Ignore => write!(f, "Ignore")
+    }
+  }
+}
+
+/// Settings for coexisting with other wallpaper and theme managers.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  /// The policy for every detected manager.
+  pub policy: Policy,
+  /// Policies for specific managers, keyed by id: `variety`, `komorebi`,
+  /// `wallpaper-engine` or `auto-dark-mode`.
+  pub overrides: BTreeMap<String, Policy>
+}
+
+impl Config {
+  /// Returns the policy for `manager`.
+  pub fn policy_for(&self, manager: Manager) -> Policy {
+    self
+      .overrides
+      .get(manager.id())
+      .copied()
+      .unwrap_or(self.policy)
+  }
+
+  /// Whether any manager policy looks for other managers at all.
+  pub fn is_enabled(&self) -> bool {
+    self.policy != Policy::Ignore
+      || self
+        .overrides
+        .values()
+        .any(|policy| *policy != Policy::Ignore)
+  }
+
+  /// Checks the running managers before wallter changes `subsystem`.
+  ///
+  /// Returns the manager `subsystem` is left to, if any. Managers whose policy
+  /// is to warn are reported on stderr.
+  pub fn yield_to(&self, subsystem: Subsystem) -> Option<Manager> {
+    if !self.is_enabled() {
+      return None;
+    }
+    self.decide(&Manager::detect(), subsystem)
+  }
+
+  fn decide(
+    &self,
+    running: &[Manager],
+    subsystem: Subsystem
+  ) -> Option<Manager> {
+    let mut yielded = None;
+    for &manager in running {
+      let policy = self.policy_for(manager);
+      if policy.yields(manager, subsystem) {
+        yielded.get_or_insert(manager);
+      } else if policy == Policy::Warn && manager.manages(subsystem) {
+        warn!(
+          "{manager} is also managing {subsystem}; set `conflicts.policy` to `defer` to leave them to it."
+        );
+      }
+    }
+    yielded
+  }
+}
+
+impl Display for Config {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    printf!(f, "Policy", self.policy)?;
+    for (manager, policy) in &self.overrides {
+      printf!(f, manager, policy)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_conflicts_decide() {
+    let running = [Manager::AutoDarkMode];
+    let mut config = Config::default();
+    assert_eq!(config.decide(&running, Subsystem::ColorMode), None);
+
+    config.policy = Policy::Defer;
+    assert_eq!(
+      config.decide(&running, Subsystem::ColorMode),
+      Some(Manager::AutoDarkMode)
+    );
+    assert_eq!(config.decide(&running, Subsystem::Wallpaper), None);
+
+    config.policy = Policy::Integrate;
+    assert_eq!(
+      config.decide(&running, Subsystem::Wallpaper),
+      Some(Manager::AutoDarkMode)
+    );
+
+    config
+      .overrides
+      .insert("auto-dark-mode".to_string(), Policy::Ignore);
+    assert_eq!(config.decide(&running, Subsystem::Wallpaper), None);
+  }
+}
@@ -0,0 +1,164 @@
+//! Detects other programs that manage wallpapers or the color mode.
+
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  process::Command
+};
+
+/// What another manager may be in charge of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Subsystem {
+  Wallpaper,
+  #[serde(rename = "color-mode")]
+  ColorMode
+}
+
+impl Display for Subsystem {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Wallpaper => write!(f, "wallpapers"),
+      Self::ColorMode => write!(f, "the color mode")
+    }
+  }
+}
+
+/// A known wallpaper or theme manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manager {
+  Variety,
+  Komorebi,
+  WallpaperEngine,
+  AutoDarkMode
+}
+
+impl Manager {
+  pub const ALL: [Self; 4] = [
+    Self::Variety,
+    Self::Komorebi,
+    Self::WallpaperEngine,
+    Self::AutoDarkMode
+  ];
+
+  /// The key used for the manager in `conflicts.overrides`.
+  pub fn id(self) -> &'static str {
+    match self {
+      Self::Variety => "variety",
+      Self::Komorebi => "komorebi",
+      Self::WallpaperEngine => "wallpaper-engine",
+      Self::AutoDarkMode => "auto-dark-mode"
+    }
+  }
+
+  /// What the manager takes charge of while it runs.
+  pub fn manages(self, subsystem: Subsystem) -> bool {
+    match self {
+      Self::Variety | Self::Komorebi | Self::WallpaperEngine =>
+        subsystem == Subsystem::Wallpaper,
+      Self::AutoDarkMode => subsystem == Subsystem::ColorMode
+    }
+  }
+
+  /// Process names the manager runs as, lowercase and without extension.
+  fn processes(self) -> &'static [&'static str] {
+    match self {
+      Self::Variety => &["variety"],
+      Self::Komorebi => &["komorebi"],
+      Self::WallpaperEngine => &["wallpaper32", "wallpaper64"],
+      Self::AutoDarkMode => &["autodarkmodesvc", "autodarkmodeapp"]
+    }
+  }
+
+  /// Returns the managers that are currently running.
+  pub fn detect() -> Vec<Self> {
+    let running = processes();
+    Self::ALL
+      .into_iter()
+      .filter(|manager| {
+        manager
+          .processes()
+          .iter()
+          .any(|name| running.iter().any(|process| process == name))
+          || manager.is_installed()
+      })
+      .collect()
+  }
+
+  /// Whether the manager is set up to start with the session, for managers
+  /// that may only run on a schedule.
+  pub(crate) fn is_installed(self) -> bool {
+    #[cfg(target_os = "windows")]
+    if self == Self::AutoDarkMode {
+      use crate::utils::registry;
+      use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+      return registry::value_exists(
+        HKEY_CURRENT_USER,
+        r"Software\Microsoft\Windows\CurrentVersion\Run",
+        "AutoDarkMode"
+      ) || registry::key_exists(
+        HKEY_CURRENT_USER,
+        r"Software\AutoDarkMode\Installed"
+      ) || registry::key_exists(
+        HKEY_LOCAL_MACHINE,
+        r"SOFTWARE\AutoDarkMode"
+      );
+    }
+    false
+  }
+}
+
+impl Display for Manager {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Variety => write!(f, "Variety"),
+      Self::Komorebi => write!(f, "Komorebi"),
+      Self::WallpaperEngine => write!(f, "Wallpaper Engine"),
+      Self::AutoDarkMode => write!(f, "Auto Dark Mode")
+    }
+  }
+}
+
+/// Lists the names of running processes, lowercase and without extension.
+/// Returns an empty list if they cannot be read.
+fn processes() -> Vec<String> {
+  let output = if cfg!(target_os = "windows") {
+    Command::new("tasklist")
+      .args(["/FO", "CSV", "/NH"])
+      .output()
+  } else {
+    Command::new("ps").args(["-A", "-o", "comm="]).output()
+  };
+  match output {
+    Ok(output) if output.status.success() =>
+      parse_processes(&String::from_utf8_lossy(&output.stdout)),
+    _ => Vec::new()
+  }
+}
+
+/// Reads process names from `ps -o comm=` lines (possibly full paths) or
+/// `tasklist /FO CSV` rows.
+fn parse_processes(output: &str) -> Vec<String> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let name = line.split("\",\"").next()?.trim().trim_matches('"');
+      let name = name.rsplit(['/', '\\']).next()?;
+      let name = name.strip_suffix(".exe").unwrap_or(name);
+      (!name.is_empty()).then(|| name.to_ascii_lowercase())
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_processes() {
+    let ps = "/usr/bin/python3\nvariety\n  /opt/komorebi/komorebi\n";
+    assert_eq!(parse_processes(ps), ["python3", "variety", "komorebi"]);
+    let tasklist = "\"explorer.exe\",\"4242\",\"Console\",\"1\",\"90,112 K\"\n\"wallpaper64.exe\",\"77\",\"Console\",\"1\",\"1 K\"";
+    assert_eq!(parse_processes(tasklist), ["explorer", "wallpaper64"]);
+  }
+}
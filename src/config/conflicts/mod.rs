@@ -0,0 +1,5 @@
+mod default;
+pub use default::{Config, Policy};
+
+pub mod manager;
+pub use manager::{Manager, Subsystem};
@@ -1,21 +1,43 @@
-use super::{Color, ColorMode, ConfigType, Monitor, Path, Search, Slideshow};
-use crate::{Error, Result};
+use super::{
+  Color, ColorMode, ConfigType, Conflicts, Monitor, Path, Search, Slideshow,
+  Stats, Wallpaper, conflicts::Subsystem, path::MonitorPaths
+};
+use crate::{Error, Result, utils::store};
 use serde::{Deserialize, Serialize};
 use std::{
   fmt::{self, Display, Formatter},
-  fs::{create_dir_all, read_to_string, write}
+  fs::{create_dir_all, read_to_string}
 };
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config {
+  /// Never change the system, such as the wallpaper, the color mode or the
+  /// registry: only download and report what would change, as
+  /// `--simulate` does.
+  #[serde(default)]
+  pub guest: bool,
   pub path: Path,
   pub monitors: Vec<Monitor>,
   pub color: Color,
   pub slideshow: Slideshow,
-  pub source: Search
+  pub source: Search,
+  #[serde(default)]
+  pub stats: Stats,
+  #[serde(default)]
+  pub wallpaper: Wallpaper,
+  #[serde(default)]
+  pub conflicts: Conflicts
 }
 
 impl Config {
+  /// In guest mode, journals changes to the system instead of making them,
+  /// unless a simulation is running already.
+  pub fn enter_guest_mode(&self) {
+    if self.guest && !crate::simulate::is_enabled() {
+      crate::simulate::enable(crate::simulate::Journal::new().with_echo(true));
+    }
+  }
+
   /// Initializes the config: creates all dirs, creates config file if missing,
   /// and loads or saves config.
   pub fn init(path_config: &mut Path) -> Result<Self> {
@@ -35,22 +57,73 @@ impl Config {
       }
     };
 
-    //{ Apply color mode from config if it's explicit and differs from system }
-    match config.color.mode {
-      ColorMode::Light | ColorMode::Dark => {
-        config.color.mode.apply()?;
-      }
-      ColorMode::Auto => { /* Do nothing, let the system control the theme */ }
+    config.enter_guest_mode();
+
+    //{ Apply color mode from config if it's explicit and differs from system,
+    //{ unless another manager is in charge of it }
+    if config.conflicts.yield_to(Subsystem::ColorMode).is_none() {
+      config.color.apply_mode(&detected_monitors)?;
     }
 
-    //{ Update the config with the detected monitors and paths }
+    //{ Update the config with the detected monitors and paths, keeping the
+    //{ user's cache limit and the paths of monitors that went missing until
+    //{ they are resolved }
+    path_config.max_cache_size = config.path.max_cache_size;
+    let missing: Vec<MonitorPaths> = config
+      .path
+      .monitor_paths
+      .iter()
+      .filter(|paths| !detected_monitors.iter().any(|m| m.name == paths.name))
+      .cloned()
+      .collect();
+    path_config.monitor_paths.extend(missing);
     config.monitors = detected_monitors;
     config.path = path_config.clone();
 
+    //{ Hold every provider to the configured concurrency limits,
+    //{ timeouts, retries, rate limit handling, download size and response
+    //{ caching }
+    #[cfg(feature = "online-sources")]
+    {
+      crate::api::concurrency::configure_all(&config.source);
+      crate::api::retry::configure_all(&config.source);
+      crate::api::http::configure(config.source.timeouts);
+      crate::api::rate_limit::configure(config.source.rate_limit);
+      crate::api::download::configure(config.wallpaper.limits.max_file_size);
+      crate::api::cache::configure(
+        crate::api::cache::dir(&config),
+        config.source.http_cache
+      );
+    }
+
+    //{ Hold notifications back as configured while not to be disturbed }
+    crate::utils::notify::configure(config.wallpaper.do_not_disturb);
+
+    //{ Leave Night Light to its schedule if asked to }
+    #[cfg(target_os = "windows")]
+    crate::config::color::mode::windows::nightlight::configure(
+      config.color.night_light.respect_schedule
+    );
+
     //{ Return the initialized config }
     Ok(config)
   }
 
+  /// Returns the saved paths of monitors that are no longer detected, such
+  /// as ones renamed or unplugged since. None are reported when no monitors
+  /// were detected at all.
+  pub fn orphaned_monitors(&self) -> Vec<&MonitorPaths> {
+    if self.monitors.is_empty() {
+      return Vec::new();
+    }
+    self
+      .path
+      .monitor_paths
+      .iter()
+      .filter(|paths| !self.monitors.iter().any(|m| m.name == paths.name))
+      .collect()
+  }
+
   /// Loads the configuration from the config file if it exists, otherwise
   /// returns default.
   pub fn load(path_config: &Path) -> Result<Self> {
@@ -77,14 +150,16 @@ impl Config {
     };
 
     //{ Update the configuration file }
-    write(&path_config.config_file, contents)?;
-    Ok(())
+    store::replace(&path_config.config_file, &contents)
   }
 }
 
 impl Display for Config {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
     writeln!(f, "Configuration:")?;
+    if self.guest {
+      writeln!(f, "  Guest mode: the system is left unchanged")?;
+    }
 
     //|-> Paths Section
     writeln!(f, "  Paths:\n{}", self.path)?;
@@ -126,6 +201,15 @@ impl Display for Config {
       writeln!(f, "{}", self.slideshow)?;
     }
 
+    //|-> Statistics Section
+    writeln!(f, "  Statistics:\n{}", self.stats)?;
+
+    //|-> Wallpaper Section
+    writeln!(f, "  Wallpaper:\n{}", self.wallpaper)?;
+
+    //|-> Conflicts Section
+    writeln!(f, "  Conflicts:\n{}", self.conflicts)?;
+
     Ok(())
   }
 }
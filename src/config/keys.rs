@@ -0,0 +1,101 @@
+//! Reads and writes individual settings by dotted key path, such as
+//! `slideshow.interval` or `color.mode`.
+//!
+//! Keys are resolved against the serialized form of [Config], so every field
+//! that is saved to the config file can be addressed, and a changed value is
+//! checked by deserializing the whole configuration again.
+
+use super::Config;
+use crate::{Error, Result};
+use serde_json::Value;
+
+/// Returns the value stored under `key`.
+pub fn get(config: &Config, key: &str) -> Result<Value> {
+  let root = to_value(config)?;
+  let mut value = &root;
+  for part in key.split('.') {
+    value = child(value, part)
+      .ok_or_else(|| Error::Config(format!("Unknown config key '{key}'")))?;
+  }
+  Ok(value.clone())
+}
+
+/// Returns a copy of `config` with `key` set to `raw`.
+///
+/// `raw` is read as JSON where possible, so numbers, booleans, lists and
+/// objects can be given directly. If that doesn't fit the setting, or `raw`
+/// isn't JSON, it is taken as a string, so `60` works for an interval too.
+pub fn set(config: &Config, key: &str, raw: &str) -> Result<Config> {
+  let root = to_value(config)?;
+  let string = Value::String(raw.into());
+  let result = match serde_json::from_str::<Value>(raw) {
+    Ok(parsed) if parsed != string =>
+      replace(root.clone(), key, parsed).or_else(|_| replace(root, key, string)),
+    _ => replace(root, key, string)
+  };
+  result.map_err(|e| match e {
+    Error::Settings(reason) =>
+      Error::Config(format!("Invalid value '{raw}' for '{key}': {reason}")),
+    e => e
+  })
+}
+
+/// Replaces the value under `key` in `root` and deserializes the result.
+fn replace(mut root: Value, key: &str, new: Value) -> Result<Config> {
+  let mut value = &mut root;
+  for part in key.split('.') {
+    value = child_mut(value, part)
+      .ok_or_else(|| Error::Config(format!("Unknown config key '{key}'")))?;
+  }
+  *value = new;
+  serde_json::from_value(root).map_err(|e| Error::Settings(e.to_string()))
+}
+
+fn to_value(config: &Config) -> Result<Value> {
+  serde_json::to_value(config).map_err(|e| Error::Config(e.to_string()))
+}
+
+/// Looks up an object field, or a list item by index.
+fn child<'a>(value: &'a Value, part: &str) -> Option<&'a Value> {
+  match value {
+    Value::Object(map) => map.get(part),
+    Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+    _ => None
+  }
+}
+
+fn child_mut<'a>(value: &'a mut Value, part: &str) -> Option<&'a mut Value> {
+  match value {
+    Value::Object(map) => map.get_mut(part),
+    Value::Array(items) => items.get_mut(part.parse::<usize>().ok()?),
+    _ => None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::{ColorMode, slideshow::Interval};
+
+  #[test]
+  fn test_get_and_set() {
+    let config = Config::default();
+    assert_eq!(get(&config, "color.mode").unwrap(), "Auto");
+    assert!(get(&config, "color.shade").is_err());
+
+    let config = set(&config, "color.mode", "dark").unwrap();
+    assert_eq!(config.color.mode, ColorMode::Dark);
+
+    let config = set(&config, "slideshow.interval", "30m").unwrap();
+    assert_eq!(config.slideshow.interval, Interval::with_minutes(30));
+
+    let config = set(&config, "slideshow.interval", "90").unwrap();
+    assert_eq!(config.slideshow.interval, Interval::with_seconds(90));
+
+    let config = set(&config, "slideshow.enabled", "true").unwrap();
+    assert!(config.slideshow.enabled);
+
+    assert!(set(&config, "slideshow.enabled", "sometimes").is_err());
+    assert!(set(&config, "slideshow.speed", "1").is_err());
+  }
+}
@@ -4,9 +4,14 @@ pub use default::*;
 pub mod color;
 pub use color::{Config as Color, Mode as ColorMode};
 
+pub mod conflicts;
+pub use conflicts::Config as Conflicts;
+
 pub mod search;
 pub use search::Config as Search;
 
+pub mod keys;
+
 pub mod monitor;
 pub use monitor::Config as Monitor;
 
@@ -15,3 +20,9 @@ pub use path::{Config as Path, types::Config as ConfigType};
 
 pub mod slideshow;
 pub use slideshow::Config as Slideshow;
+
+pub mod stats;
+pub use stats::Config as Stats;
+
+pub mod wallpaper;
+pub use wallpaper::Config as Wallpaper;
@@ -1,4 +1,4 @@
-use super::{Position, Size};
+use super::{Position, Role, Size};
 use crate::config::path::Config as PathConfig;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -7,6 +7,7 @@ use std::{
   fmt::{self, Display, Formatter}
 };
 use thiserror::Error as ThisError;
+#[cfg(feature = "monitor-winit")]
 use winit::{
   application::ApplicationHandler,
   dpi::{PhysicalPosition, PhysicalSize},
@@ -18,6 +19,7 @@ use winit::{
 
 #[derive(ThisError, Debug)]
 pub enum Error {
+  #[cfg(feature = "monitor-winit")]
   #[error("Winit event loop error: {0}")]
   EventLoop(#[from] winit::error::EventLoopError)
 }
@@ -53,13 +55,37 @@ impl Display for Config {
     printf!(f, "Scale", format!("{:.1}x", self.scale))?;
     printf!(f, "Position", &self.position)?;
     printf!(f, "Primary", self.primary)?;
+    printf!(f, "Role", self.role())?;
 
     Ok(())
   }
 }
 
 impl Config {
+  /// Returns the role of the monitor.
+  pub fn role(&self) -> Role {
+    if self.primary {
+      Role::Primary
+    } else {
+      Role::Secondary
+    }
+  }
+
+  /// A secondary monitor of the given size at the origin, for tests.
+  #[cfg(test)]
+  pub fn test(name: &str, width: u32, height: u32) -> Self {
+    Self {
+      id: 0,
+      name: name.into(),
+      size: Size::new(&width, &height),
+      position: Position::default(),
+      scale: 1.0,
+      primary: false
+    }
+  }
+
   /// Enumerate all monitors and return their information.
+  #[cfg(feature = "monitor-winit")]
   pub fn get_info() -> Result<Vec<Self>> {
     let result = RefCell::new(Vec::new());
 
@@ -142,6 +168,12 @@ impl Config {
     Ok(result.into_inner())
   }
 
+  /// Without a monitor backend, no monitors are detected.
+  #[cfg(not(feature = "monitor-winit"))]
+  pub fn get_info() -> Result<Vec<Self>> {
+    Ok(Vec::new())
+  }
+
   /// Helper function to display wallpaper paths for a given monitor.
   /// This can be commented out in the `Display` impl to toggle visibility.
   pub fn display_wallpaper_paths(
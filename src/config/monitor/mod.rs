@@ -9,3 +9,6 @@ pub use position::Config as Position;
 
 mod orientation;
 pub use orientation::Config as Orientation;
+
+mod role;
+pub use role::Config as Role;
@@ -6,7 +6,7 @@ use std::{
 };
 
 /// Represents the orientation of a monitor based on its resolution.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Config {
   /// Width > Height (e.g., 1920x1080)
   Landscape,
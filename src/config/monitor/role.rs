@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// The part a monitor plays in the setup, derived from its `primary` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Config {
+  /// The primary monitor.
+  Primary,
+  /// Any other monitor.
+  Secondary
+}
+
+impl Display for Config {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Primary => write!(f, "primary"),
+      Self::Secondary => write!(f, "secondary")
+    }
+  }
+}
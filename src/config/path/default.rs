@@ -1,5 +1,9 @@
 use super::types;
-use crate::{Error, Result, config::Monitor};
+use crate::{
+  Error, Result,
+  config::{Monitor, monitor::Size},
+  simulate::{self, Action}
+};
 use serde::{Deserialize, Serialize};
 use std::{
   fmt::{self, Display, Formatter},
@@ -7,10 +11,9 @@ use std::{
   io::Write,
   path::{Path, PathBuf}
 };
-use winit::monitor;
 
 /// Holds paths specific to a single monitor.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct MonitorPaths {
   /// The name of the monitor (e.g., "DP-1").
   pub name: String,
@@ -34,6 +37,24 @@ pub struct Config {
   /// This directory houses the current wallpaper for each monitor
   pub wallpaper_dir: PathBuf,
 
+  /// Persistent application state (statistics, history, queues)
+  #[serde(default)]
+  pub state_dir: PathBuf,
+
+  /// Regenerable data, such as thumbnails
+  #[serde(default)]
+  pub cache_dir: PathBuf,
+
+  /// The largest the downloads may grow, in MiB, before the least recently
+  /// used are evicted. Unlimited if unset.
+  #[serde(default)]
+  pub max_cache_size: Option<u64>,
+
+  /// How downloads are named, such as `{source}-{id}-{resolution}.{ext}`.
+  /// Downloads keep the name at the end of their URL if unset.
+  #[serde(default)]
+  pub filename_template: Option<String>,
+
   /// The name of the configuration file
   pub config_name: String,
 
@@ -54,6 +75,15 @@ impl Display for Config {
     printf!(f, "Downloads Directory", self.downloads_dir.display())?;
     printf!(f, "Favorites Directory", self.favorites_dir.display())?;
     printf!(f, "Wallpaper Directory", self.wallpaper_dir.display())?;
+    printf!(f, "State Directory", self.state_dir.display())?;
+    printf!(f, "Cache Directory", self.cache_dir.display())?;
+    match self.max_cache_size {
+      Some(size) => printf!(f, "Max Cache Size", format!("{size} MiB"))?,
+      None => printf!(f, "Max Cache Size", "Unlimited")?
+    }
+    if let Some(template) = &self.filename_template {
+      printf!(f, "Filename Template", template)?;
+    }
     printf!(f, "Config File", self.config_file.display())?;
 
     // for paths in &self.monitor_paths {
@@ -91,6 +121,8 @@ impl Default for Config {
     let downloads_dir = home_dir.join("downloads");
     let favorites_dir = home_dir.join("favorites");
     let wallpaper_dir = home_dir.join("wallpaper");
+    let state_dir = home_dir.join("state");
+    let cache_dir = home_dir.join("cache");
     let config_name = "config".to_string();
     let config_type = types::Config::default();
     let config_file =
@@ -101,6 +133,10 @@ impl Default for Config {
       downloads_dir,
       favorites_dir,
       wallpaper_dir,
+      state_dir,
+      cache_dir,
+      max_cache_size: None,
+      filename_template: None,
       config_name,
       config_file,
       config_type,
@@ -116,25 +152,37 @@ impl Config {
 
   /// Returns the path to the monitor-specific wallpaper download directory.
   pub fn get_download_dir(&self, monitor: &Monitor) -> PathBuf {
-    let monitor = &monitor.size;
-    let ratio_dir = monitor.ratio_str();
-    let resolution_dir = monitor.resolution_str();
-    self.downloads_dir.join(ratio_dir).join(resolution_dir)
+    self.get_bucket_dir(&monitor.size)
+  }
+
+  /// Returns the download directory for images of the given size, sorted
+  /// by ratio and then resolution.
+  pub fn get_bucket_dir(&self, size: &Size) -> PathBuf {
+    self
+      .downloads_dir
+      .join(size.ratio_str())
+      .join(size.resolution_str())
   }
 
   /// Create all necessary directories (home, downloads, favorites, wallpaper,
-  /// monitor-specific) and the config file.
+  /// state, cache, monitor-specific) and the config file.
   pub fn create_all(&mut self, monitors: &[Monitor]) -> Result<()> {
-    create_dir_all(&self.home_dir)?;
-    create_dir_all(&self.downloads_dir)?;
-    create_dir_all(&self.favorites_dir)?;
-    create_dir_all(&self.wallpaper_dir)?;
+    for dir in [
+      &self.home_dir,
+      &self.downloads_dir,
+      &self.favorites_dir,
+      &self.wallpaper_dir,
+      &self.state_dir,
+      &self.cache_dir
+    ] {
+      ensure_dir(dir)?;
+    }
 
     //{ Clear old paths and create monitor-specific paths }
     self.monitor_paths.clear();
     for monitor in monitors {
       let download_dir = self.get_download_dir(monitor);
-      create_dir_all(&download_dir)?;
+      ensure_dir(&download_dir)?;
 
       // The path for the active wallpaper for this monitor.
       // We assume a default extension for now; the `set` command will manage
@@ -158,7 +206,11 @@ impl Config {
     &self,
     default_content: Option<&str>
   ) -> Result<()> {
-    if !self.config_exists() {
+    if !self.config_exists()
+      && !simulate::dry_run(Action::WriteFile {
+        path: self.config_file.clone()
+      })?
+    {
       let mut file = File::create(&self.config_file)?;
       if let Some(content) = default_content {
         file.write_all(content.as_bytes())?;
@@ -195,3 +247,16 @@ impl Config {
     ));
   }
 }
+
+/// Creates `dir` and its parents if missing, unless this is a dry run.
+fn ensure_dir(dir: &Path) -> Result<()> {
+  if dir.is_dir()
+    || simulate::dry_run(Action::CreateDir {
+      path: dir.to_path_buf()
+    })?
+  {
+    return Ok(());
+  }
+  create_dir_all(dir)?;
+  Ok(())
+}
@@ -1,4 +1,4 @@
 mod default;
-pub use default::Config;
+pub use default::{Config, MonitorPaths};
 
 pub mod types;
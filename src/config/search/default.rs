@@ -1,4 +1,8 @@
-use super::{Source, wallhaven::Params as Wallhaven};
+use super::{
+  Defaults, HttpCache, OAuth, RateLimit, SafeHours, Scoring, SeenFilter,
+  Source, Timeouts, pexels::Params as Pexels, reddit::Params as Reddit,
+  wallhaven::Params as Wallhaven
+};
 use crate::{Error, Result, api::wallhaven::Sorting};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
@@ -12,8 +16,38 @@ pub struct Config {
 
   /// The ordered list of source names by priority. When fetching, the
   /// application will attempt to use sources in this order until a wallpaper
-  /// is successfully retrieved.
-  pub ordered: Vec<String>
+  /// is successfully retrieved. Enabled sources missing from the list are
+  /// tried after the listed ones.
+  pub ordered: Vec<String>,
+
+  /// How candidates from different sources are weighed against a monitor.
+  #[serde(default)]
+  pub scoring: Scoring,
+
+  /// Settings every source inherits unless it sets its own.
+  #[serde(default)]
+  pub defaults: Defaults,
+
+  /// When only safe-for-work wallpapers are shown, whatever the sources
+  /// allow.
+  #[serde(default)]
+  pub safe_hours: SafeHours,
+
+  /// How long network requests and downloads may take.
+  #[serde(default)]
+  pub timeouts: Timeouts,
+
+  /// What to do when a source limits how often it may be called.
+  #[serde(default)]
+  pub rate_limit: RateLimit,
+
+  /// How long search results and thumbnails are reused from disk.
+  #[serde(default)]
+  pub http_cache: HttpCache,
+
+  /// Whether results resembling wallpapers shown before are skipped.
+  #[serde(default)]
+  pub seen: SeenFilter
 }
 
 impl Display for Config {
@@ -26,9 +60,31 @@ impl Display for Config {
         printf!(f, "Rank", rank + 1)?;
       }
 
-      //{ Display source information }
-      writeln!(f, "{source}")?;
+      //{ Display source information and what it inherits }
+      write!(f, "{source}")?;
+      let inherited = self.defaults.inherited(source.wallhaven.as_ref());
+      if source.wallhaven.is_some() && !inherited.is_empty() {
+        printf!(
+          f,
+          "Inherited",
+          format!("{} (defaults)", inherited.join(", "))
+        )?;
+      }
+      writeln!(f)?;
     }
+
+    //{ Display the shared defaults }
+    printh!(f, "Defaults:", 4)?;
+    write!(f, "{}", self.defaults)?;
+    printf!(f, "Safe Hours", &self.safe_hours)?;
+    printf!(f, "Timeouts", &self.timeouts)?;
+    printf!(f, "Rate Limits", &self.rate_limit)?;
+    printf!(f, "HTTP Cache", &self.http_cache)?;
+    printf!(f, "Seen Filter", &self.seen)?;
+
+    //{ Display the candidate scoring weights }
+    printh!(f, "Scoring:", 4)?;
+    write!(f, "{}", self.scoring)?;
     Ok(())
   }
 }
@@ -43,9 +99,9 @@ impl Default for Config {
       //? base_url is not used by our new API client, so it's empty.
       base_url: "".into(),
       requires_api_key: false,
+      enabled: true,
+      valid: true,
       wallhaven: Some(Wallhaven {
-        categories: Some((true, true, false)), // General & Anime
-        purity: Some((true, true, false)),     // SFW & Sketchy
         sorting: Some(Sorting::Random),
         ..Default::default()
       }),
@@ -56,6 +112,7 @@ impl Default for Config {
       name: "unsplash".into(),
       base_url: "https://api.unsplash.com/".into(),
       requires_api_key: true,
+      oauth: Some(OAuth::unsplash()),
       ..Default::default()
     };
 
@@ -66,8 +123,30 @@ impl Default for Config {
       ..Default::default()
     };
 
-    let default_sources =
-      vec![wallhaven_source, unsplash_source, pixabay_source];
+    let pexels_source = Source {
+      name: "pexels".into(),
+      base_url: "https://api.pexels.com/v1/".into(),
+      requires_api_key: true,
+      pexels: Some(Pexels::default()),
+      ..Default::default()
+    };
+
+    let reddit_source = Source {
+      name: "reddit".into(),
+      base_url: "https://www.reddit.com/".into(),
+      requires_api_key: false,
+      valid: true,
+      reddit: Some(Reddit::default()),
+      ..Default::default()
+    };
+
+    let default_sources = vec![
+      wallhaven_source,
+      pexels_source,
+      reddit_source,
+      unsplash_source,
+      pixabay_source,
+    ];
 
     //{ Define default rank order based on the default sources' names }
     let default_rank_names: Vec<String> = default_sources
@@ -77,7 +156,17 @@ impl Default for Config {
 
     Self {
       sources: default_sources,
-      ordered: default_rank_names
+      ordered: default_rank_names,
+      scoring: Scoring::default(),
+      defaults: Defaults {
+        categories: Some((true, true, false)), // General & Anime
+        purity: Some((true, true, false))      // SFW & Sketchy
+      },
+      safe_hours: SafeHours::default(),
+      timeouts: Timeouts::default(),
+      rate_limit: RateLimit::default(),
+      http_cache: HttpCache::default(),
+      seen: SeenFilter::default()
     }
   }
 }
@@ -86,4 +175,61 @@ impl Config {
   pub fn new() -> Self {
     Self::default()
   }
+
+  /// Finds a configured source by name.
+  pub fn find(&self, name: &str) -> Option<&Source> {
+    self.sources.iter().find(|source| source.name == name)
+  }
+
+  /// Returns the Wallhaven parameters of `source`, with the settings it
+  /// leaves unset inherited from the [Defaults].
+  pub fn wallhaven_params(&self, source: &Source) -> Wallhaven {
+    self.defaults.apply(source.wallhaven.as_ref())
+  }
+
+  /// Returns the names of the enabled sources in the order they are tried:
+  /// those in `ordered` first, then any others in the order they are
+  /// configured. Names in `ordered` without a source are left out.
+  pub fn ranked(&self) -> Vec<String> {
+    let enabled = |name: &String| self.find(name).is_some_and(|s| s.enabled);
+    let mut names: Vec<String> = Vec::new();
+    for name in self.ordered.iter().filter(|name| enabled(name)) {
+      if !names.contains(name) {
+        names.push(name.clone());
+      }
+    }
+    for source in self.sources.iter().filter(|s| s.enabled) {
+      if !names.contains(&source.name) {
+        names.push(source.name.clone());
+      }
+    }
+    names
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_ranked() {
+    let mut config = Config {
+      ordered: vec!["reddit".into(), "unsplash".into(), "ghost".into()],
+      ..Default::default()
+    };
+    for source in &mut config.sources {
+      source.enabled = source.name != "pexels";
+    }
+    config.sources.push(Source {
+      name: "photos".into(),
+      enabled: true,
+      ..Default::default()
+    });
+
+    //{ Listed sources first, then enabled ones the list forgot }
+    assert_eq!(
+      config.ranked(),
+      ["reddit", "unsplash", "wallhaven", "pixabay", "photos"]
+    );
+  }
 }
@@ -0,0 +1,86 @@
+use super::wallhaven::{Params as Wallhaven, categories_str, purity_str};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Search settings shared by every source. A source inherits each setting
+/// it leaves unset, and overrides those it sets itself.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Defaults {
+  /// Categories (General, Anime, People).
+  pub categories: Option<(bool, bool, bool)>,
+
+  /// Purity (SFW, Sketchy, NSFW).
+  pub purity: Option<(bool, bool, bool)>
+}
+
+impl Defaults {
+  /// Returns `params` with every setting it leaves unset taken from the
+  /// defaults.
+  pub fn apply(&self, params: Option<&Wallhaven>) -> Wallhaven {
+    let mut merged = params.cloned().unwrap_or_default();
+    merged.categories = merged.categories.or(self.categories);
+    merged.purity = merged.purity.or(self.purity);
+    merged
+  }
+
+  /// Returns the names of the settings `params` inherits from the defaults.
+  pub fn inherited(&self, params: Option<&Wallhaven>) -> Vec<&'static str> {
+    let mut names = Vec::new();
+    if self.categories.is_some()
+      && params.is_none_or(|p| p.categories.is_none())
+    {
+      names.push("categories");
+    }
+    if self.purity.is_some() && params.is_none_or(|p| p.purity.is_none()) {
+      names.push("purity");
+    }
+    names
+  }
+}
+
+impl Display for Defaults {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self.categories {
+      Some(cats) => printf!(f, "Categories", categories_str(cats))?,
+      None => printf!(f, "Categories", "[Source Decides]")?
+    }
+    match self.purity {
+      Some(purs) => printf!(f, "Purity", purity_str(purs))?,
+      None => printf!(f, "Purity", "[Source Decides]")?
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_apply() {
+    let defaults = Defaults {
+      categories: Some((true, false, false)),
+      purity: Some((true, false, false))
+    };
+    let own = Wallhaven {
+      purity: Some((true, true, false)),
+      ..Default::default()
+    };
+
+    //{ Unset settings are inherited, set ones override }
+    let merged = defaults.apply(Some(&own));
+    assert_eq!(merged.categories, Some((true, false, false)));
+    assert_eq!(merged.purity, Some((true, true, false)));
+    assert_eq!(defaults.inherited(Some(&own)), ["categories"]);
+
+    //{ A source without parameters inherits everything }
+    assert_eq!(defaults.apply(None).purity, Some((true, false, false)));
+    assert_eq!(defaults.inherited(None), ["categories", "purity"]);
+
+    //{ Empty defaults change nothing }
+    let merged = Defaults::default().apply(Some(&own));
+    assert_eq!(merged.categories, None);
+    assert!(Defaults::default().inherited(None).is_empty());
+  }
+}
@@ -0,0 +1,37 @@
+use crate::config::slideshow::Interval;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// How API responses and thumbnails are cached on disk.
+///
+/// A cached response is reused as is until it is older than `ttl`. After
+/// that it is revalidated: the server is asked whether it changed since,
+/// using its `ETag` or `Last-Modified`, and only sends it again if it did.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct HttpCache {
+  /// Keep responses on disk at all.
+  pub enabled: bool,
+
+  /// How long a response is reused without asking the server.
+  pub ttl: Interval
+}
+
+impl Default for HttpCache {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      ttl: Interval::with_minutes(15)
+    }
+  }
+}
+
+impl Display for HttpCache {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.enabled {
+      write!(f, "Reuse responses for {}, then revalidate", self.ttl)
+    } else {
+      write!(f, "Disabled")
+    }
+  }
+}
@@ -0,0 +1,52 @@
+use crate::utils::files;
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+
+/// Folders on disk searched like an online source.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Params {
+  /// Folders to take images from.
+  pub dirs: Vec<PathBuf>,
+
+  /// File name patterns images must match. Example: ["*.png", "space-*"]
+  /// Empty matches every image.
+  pub patterns: Vec<String>,
+
+  /// Whether subfolders are searched too.
+  pub recursive: bool
+}
+
+impl Params {
+  /// Whether the image called `name` matches the patterns.
+  pub fn matches(&self, name: &str) -> bool {
+    self.patterns.is_empty()
+      || self
+        .patterns
+        .iter()
+        .any(|pattern| files::matches_glob(pattern, name))
+  }
+}
+
+impl Display for Params {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    const PAD: usize = 22;
+    const TAB: usize = 6;
+
+    let dirs: Vec<String> = self
+      .dirs
+      .iter()
+      .map(|dir| dir.display().to_string())
+      .collect();
+    printf!(f, "Folders", dirs.join(", "), PAD, TAB)?;
+    if !self.patterns.is_empty() {
+      printf!(f, "Patterns", self.patterns.join(", "), PAD, TAB)?;
+    }
+    printf!(f, "Recursive", self.recursive, PAD, TAB)?;
+
+    Ok(())
+  }
+}
@@ -1,7 +1,40 @@
 mod default;
 pub use default::Config;
 
+mod defaults;
+pub use defaults::Defaults;
+
+pub mod local;
+
+mod rate_limit;
+pub use rate_limit::RateLimit;
+
+mod http_cache;
+pub use http_cache::HttpCache;
+
+mod oauth;
+pub use oauth::OAuth;
+
+mod retry;
+pub use retry::Retry;
+
+mod safe;
+pub use safe::SafeHours;
+
+mod scoring;
+pub use scoring::Scoring;
+
+mod seen;
+pub use seen::SeenFilter;
+
 mod source;
-pub use source::Source;
+pub use source::{KEYRING_PREFIX, Source};
+
+mod timeouts;
+pub use timeouts::Timeouts;
+
+pub mod pexels;
+
+pub mod reddit;
 
 pub mod wallhaven;
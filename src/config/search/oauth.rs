@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// OAuth settings for a source whose write actions need a signed-in user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuth {
+  /// The application's client id. Defaults to the source's API key, which is
+  /// what Unsplash uses.
+  #[serde(default)]
+  pub client_id: Option<String>,
+  /// The application's client secret, if the provider requires one.
+  #[serde(default)]
+  pub client_secret: Option<String>,
+  /// Where the user is sent to grant access.
+  pub authorize_url: String,
+  /// Where authorization codes and refresh tokens are exchanged for tokens.
+  pub token_url: String,
+  /// The permissions to request.
+  #[serde(default)]
+  pub scopes: Vec<String>
+}
+
+impl OAuth {
+  /// The endpoints and scopes for liking photos and managing collections on
+  /// Unsplash.
+  pub fn unsplash() -> Self {
+    Self {
+      client_id: None,
+      client_secret: None,
+      authorize_url: "https://unsplash.com/oauth/authorize".into(),
+      token_url: "https://unsplash.com/oauth/token".into(),
+      scopes: ["public", "write_likes", "write_collections"]
+        .map(String::from)
+        .to_vec()
+    }
+  }
+}
@@ -0,0 +1,76 @@
+use crate::api::pexels::{Orientation, SearchParams, Size};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Pexels-specific search parameters for the configuration.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Params {
+  /// Default search query. Without one, the curated photos are used.
+  pub query: Option<String>,
+
+  /// Orientation of the photos. Chosen per monitor when left unset.
+  pub orientation: Option<Orientation>,
+
+  /// Minimum size of the photos. Chosen per monitor when left unset.
+  pub size: Option<Size>,
+
+  /// Color name or hex code. Example: "blue", "#663399".
+  pub color: Option<String>,
+
+  /// Search locale. Example: "en-US".
+  pub locale: Option<String>,
+
+  /// Results per page, up to 80.
+  pub per_page: Option<u32>
+}
+
+impl From<&Params> for SearchParams {
+  fn from(params: &Params) -> Self {
+    Self {
+      query: params.query.clone(),
+      orientation: params.orientation,
+      size: params.size,
+      color: params.color.clone(),
+      locale: params.locale.clone(),
+      per_page: params.per_page,
+      ..Default::default()
+    }
+  }
+}
+
+impl Display for Params {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    const PAD: usize = 22;
+    const TAB: usize = 6;
+
+    printf!(
+      f,
+      "Default Query",
+      self.query.as_deref().unwrap_or("[Curated]"),
+      PAD,
+      TAB
+    )?;
+
+    if let Some(orientation) = self.orientation {
+      printf!(f, "Orientation", orientation, PAD, TAB)?;
+    }
+
+    if let Some(size) = self.size {
+      printf!(f, "Min Size", size, PAD, TAB)?;
+    }
+
+    if let Some(color) = &self.color {
+      printf!(f, "Color", color, PAD, TAB)?;
+    }
+
+    if let Some(locale) = &self.locale {
+      printf!(f, "Locale", locale, PAD, TAB)?;
+    }
+
+    if let Some(per_page) = self.per_page {
+      printf!(f, "Per Page", per_page, PAD, TAB)?;
+    }
+
+    Ok(())
+  }
+}
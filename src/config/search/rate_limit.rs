@@ -0,0 +1,73 @@
+use crate::config::slideshow::Interval;
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  time::Duration
+};
+
+/// What to do when a source answers that it is being called too often.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct RateLimit {
+  /// Wait and send the request again, rather than failing at once.
+  pub retry: bool,
+
+  /// How many times one request is sent again.
+  pub max_retries: u32,
+
+  /// The longest a source may ask to be waited out. Requests asked to wait
+  /// longer fail instead.
+  pub max_wait: Interval
+}
+
+impl Default for RateLimit {
+  fn default() -> Self {
+    Self {
+      retry: true,
+      max_retries: 3,
+      max_wait: Interval::with_minutes(1)
+    }
+  }
+}
+
+impl RateLimit {
+  /// Whether the `attempt`th retry may go ahead after waiting `wait`.
+  pub fn allows(&self, attempt: u32, wait: Duration) -> bool {
+    self.retry
+      && attempt <= self.max_retries
+      && wait <= self.max_wait.to_duration()
+  }
+}
+
+impl Display for RateLimit {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.retry {
+      write!(
+        f,
+        "Wait up to {} and retry {} times",
+        self.max_wait, self.max_retries
+      )
+    } else {
+      write!(f, "Fail at once")
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_allows() {
+    let rate_limit: RateLimit = toml::from_str("max_retries = 1").unwrap();
+    assert!(rate_limit.allows(1, Duration::from_secs(60)));
+    assert!(!rate_limit.allows(2, Duration::from_secs(1)));
+    assert!(!rate_limit.allows(1, Duration::from_secs(61)));
+
+    let rate_limit = RateLimit {
+      retry: false,
+      ..rate_limit
+    };
+    assert!(!rate_limit.allows(1, Duration::ZERO));
+  }
+}
@@ -0,0 +1,76 @@
+use crate::api::reddit::{ListingParams, Sort, TimeRange};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Reddit-specific listing parameters for the configuration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Params {
+  /// Subreddits to read image posts from. Example: ["wallpapers"]
+  pub subreddits: Vec<String>,
+
+  /// Listing sort (hot, new, top, rising).
+  pub sort: Sort,
+
+  /// Time range for `top` listings (hour, day, week, month, year, all).
+  pub time: TimeRange,
+
+  /// Posts per request, up to 100.
+  pub limit: u32,
+
+  /// Minimum resolution, from the post's metadata. Example: "1920x1080".
+  pub atleast: Option<String>,
+
+  /// Whether posts marked NSFW are kept.
+  pub nsfw: bool
+}
+
+impl Default for Params {
+  fn default() -> Self {
+    Self {
+      subreddits: vec!["wallpapers".into(), "EarthPorn".into()],
+      sort: Sort::Top,
+      time: TimeRange::Week,
+      limit: 100,
+      atleast: None,
+      nsfw: false
+    }
+  }
+}
+
+impl Params {
+  /// Returns the minimum width and height, if `atleast` is set and valid.
+  pub fn minimum(&self) -> Option<(u32, u32)> {
+    let (width, height) = self.atleast.as_deref()?.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+  }
+}
+
+impl From<&Params> for ListingParams {
+  fn from(params: &Params) -> Self {
+    ListingParams::new(params.subreddits.iter().cloned())
+      .with_sort(params.sort)
+      .with_time(params.time)
+      .with_limit(params.limit)
+  }
+}
+
+impl Display for Params {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    const PAD: usize = 22;
+    const TAB: usize = 6;
+
+    printf!(f, "Subreddits", self.subreddits.join(", "), PAD, TAB)?;
+    printf!(f, "Sort", self.sort, PAD, TAB)?;
+    if self.sort == Sort::Top {
+      printf!(f, "Time Range", self.time, PAD, TAB)?;
+    }
+    printf!(f, "Limit", self.limit, PAD, TAB)?;
+    if let Some(res) = &self.atleast {
+      printf!(f, "Min Resolution", res, PAD, TAB)?;
+    }
+    printf!(f, "NSFW", self.nsfw, PAD, TAB)?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,84 @@
+use crate::config::slideshow::Interval;
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  time::Duration
+};
+
+/// How requests and downloads that fail for a passing reason are retried.
+///
+/// Timeouts, dropped connections and server errors are retried, waiting
+/// twice as long after each failure, from `backoff` up to `max_backoff`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Retry {
+  /// How many times a request is sent in all, including the first. 1 never
+  /// retries.
+  pub max_attempts: u32,
+
+  /// The wait before the first retry.
+  pub backoff: Interval,
+
+  /// The longest wait between two attempts.
+  pub max_backoff: Interval,
+
+  /// Shorten each wait by a random amount of up to half, so that requests
+  /// failing together are not all retried at the same moment.
+  pub jitter: bool
+}
+
+impl Default for Retry {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      backoff: Interval::with_seconds(1),
+      max_backoff: Interval::with_seconds(30),
+      jitter: true
+    }
+  }
+}
+
+impl Retry {
+  /// Returns the wait after the `attempt`th failed attempt, before jitter.
+  pub fn delay(&self, attempt: u32) -> Duration {
+    let doublings = attempt.saturating_sub(1).min(16);
+    self
+      .backoff
+      .to_duration()
+      .saturating_mul(1 << doublings)
+      .min(self.max_backoff.to_duration())
+  }
+}
+
+impl Display for Retry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.max_attempts <= 1 {
+      return write!(f, "Never");
+    }
+    write!(
+      f,
+      "Up to {} attempts, waiting {} to {}",
+      self.max_attempts, self.backoff, self.max_backoff
+    )?;
+    if self.jitter {
+      write!(f, " with jitter")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_delay() {
+    let retry: Retry =
+      toml::from_str("backoff = \"2s\"\nmax_backoff = \"10s\"").unwrap();
+    assert_eq!(retry.max_attempts, 3);
+    let delays: Vec<u64> = (1..=4)
+      .map(|attempt| retry.delay(attempt).as_secs())
+      .collect();
+    assert_eq!(delays, [2, 4, 8, 10]);
+  }
+}
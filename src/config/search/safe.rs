@@ -0,0 +1,127 @@
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// A weekly window during which only safe-for-work wallpapers are shown,
+/// whatever the sources allow.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SafeHours {
+  pub enabled: bool,
+
+  /// The days the window opens on. Example: ["Mon", "Tue"]
+  pub days: Vec<Weekday>,
+
+  /// When the window opens. Example: "09:00"
+  pub start: NaiveTime,
+
+  /// When the window closes. An end before the start runs past midnight
+  /// into the next day; an end equal to the start keeps it open all day.
+  pub end: NaiveTime
+}
+
+impl Default for SafeHours {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      days: vec![
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+      ],
+      start: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+      end: NaiveTime::from_hms_opt(17, 0, 0).unwrap_or_default()
+    }
+  }
+}
+
+impl SafeHours {
+  /// Whether only safe-for-work wallpapers may be shown right now.
+  pub fn is_active(&self) -> bool {
+    self.is_active_at(Local::now().naive_local())
+  }
+
+  /// Whether the window is open at `at`.
+  pub fn is_active_at(&self, at: NaiveDateTime) -> bool {
+    if !self.enabled {
+      return false;
+    }
+    let (day, time) = (at.weekday(), at.time());
+    if self.start == self.end {
+      self.days.contains(&day)
+    } else if self.start < self.end {
+      self.days.contains(&day) && self.start <= time && time < self.end
+    } else {
+      //{ Overnight: the early hours belong to the previous day's window }
+      (self.days.contains(&day) && time >= self.start)
+        || (self.days.contains(&day.pred()) && time < self.end)
+    }
+  }
+}
+
+impl Display for SafeHours {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if !self.enabled {
+      return write!(f, "Disabled");
+    }
+    let days: Vec<String> =
+      self.days.iter().map(|day| day.to_string()).collect();
+    write!(
+      f,
+      "SFW only on {} from {} to {}",
+      days.join(", "),
+      self.start.format("%H:%M"),
+      self.end.format("%H:%M")
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+
+  fn at(day: u32, hour: u32) -> NaiveDateTime {
+    //{ 2025-06-02 was a Monday }
+    NaiveDate::from_ymd_opt(2025, 6, day)
+      .and_then(|date| date.and_hms_opt(hour, 0, 0))
+      .unwrap()
+  }
+
+  #[test]
+  fn test_is_active_at() {
+    let mut hours = SafeHours::default();
+    assert!(!hours.is_active_at(at(2, 10)));
+
+    hours.enabled = true;
+    assert!(hours.is_active_at(at(2, 9)));
+    assert!(!hours.is_active_at(at(2, 17)));
+    assert!(!hours.is_active_at(at(7, 10)));
+
+    //{ Friday night into Saturday morning }
+    hours.days = vec![Weekday::Fri];
+    hours.start = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+    hours.end = NaiveTime::from_hms_opt(6, 0, 0).unwrap();
+    assert!(hours.is_active_at(at(6, 23)));
+    assert!(hours.is_active_at(at(7, 5)));
+    assert!(!hours.is_active_at(at(7, 23)));
+
+    //{ All day }
+    hours.end = hours.start;
+    assert!(hours.is_active_at(at(6, 3)));
+    assert!(!hours.is_active_at(at(7, 3)));
+  }
+
+  #[test]
+  fn test_deserialize() {
+    let hours: SafeHours = serde_json::from_str(
+      r#"{"enabled": true, "days": ["Sat", "Sunday"], "start": "08:30"}"#
+    )
+    .unwrap();
+    assert_eq!(hours.days, [Weekday::Sat, Weekday::Sun]);
+    assert_eq!(hours.start, NaiveTime::from_hms_opt(8, 30, 0).unwrap());
+    assert_eq!(hours.end, SafeHours::default().end);
+  }
+}
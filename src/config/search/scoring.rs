@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Weights used to score search candidates against a monitor when several
+/// sources return results. Each criterion scores between 0 and 1 and is
+/// multiplied by its weight; a weight of 0 disables the criterion.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Scoring {
+  /// Rewards images at or slightly above the monitor resolution.
+  pub resolution: f32,
+
+  /// Rewards images whose aspect ratio is close to the monitor's.
+  pub aspect: f32,
+
+  /// Rewards images matching the preferred colors, or the color mode if no
+  /// colors are preferred.
+  pub color: f32,
+
+  /// Rewards images with more favorites relative to the other candidates.
+  pub popularity: f32,
+
+  /// Preferred colors as hex codes. Example: `["663399", "#424153"]`.
+  pub preferred_colors: Vec<String>
+}
+
+impl Default for Scoring {
+  fn default() -> Self {
+    Self {
+      resolution: 1.0,
+      aspect: 1.5,
+      color: 0.5,
+      popularity: 0.5,
+      preferred_colors: Vec::new()
+    }
+  }
+}
+
+impl Display for Scoring {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    printf!(f, "Resolution Weight", self.resolution)?;
+    printf!(f, "Aspect Weight", self.aspect)?;
+    printf!(f, "Color Weight", self.color)?;
+    printf!(f, "Popularity Weight", self.popularity)?;
+    let colors = if self.preferred_colors.is_empty() {
+      "[Follow Color Mode]".to_string()
+    } else {
+      self.preferred_colors.join(", ")
+    };
+    printf!(f, "Preferred Colors", colors)
+  }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Skips search results that look like wallpapers shown before.
+///
+/// Every shown image leaves a perceptual fingerprint, kept even after the
+/// image itself is pruned. With the filter enabled, the thumbnails of the
+/// best search results are fingerprinted too, and a result whose
+/// fingerprint differs from a seen one by at most `max_distance` bits is
+/// passed over.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct SeenFilter {
+  pub enabled: bool,
+
+  /// How many of the 64 fingerprint bits may differ for two images to count
+  /// as the same. Thumbnails are re-encoded copies, so they rarely match
+  /// exactly.
+  pub max_distance: u32,
+
+  /// How many thumbnails are checked per pick before settling for the best
+  /// result left.
+  pub max_checks: usize
+}
+
+impl Default for SeenFilter {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      max_distance: 6,
+      max_checks: 8
+    }
+  }
+}
+
+impl Display for SeenFilter {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.enabled {
+      write!(
+        f,
+        "Skip results within {} bits of a seen image (checking up to {})",
+        self.max_distance, self.max_checks
+      )
+    } else {
+      write!(f, "Disabled")
+    }
+  }
+}
@@ -1,20 +1,69 @@
-use super::wallhaven::Params as Wallhaven;
+use super::{
+  OAuth, Retry, local::Params as Local, pexels::Params as Pexels,
+  reddit::Params as Reddit, wallhaven::Params as Wallhaven
+};
+use crate::api::Concurrency;
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Display, Formatter};
+use std::{
+  env,
+  fmt::{self, Display, Formatter}
+};
+use tracing::warn;
+
+/// Marks an `api_key` that names an entry in the system keyring rather than
+/// holding the key itself, as in `"keyring:wallhaven"`.
+pub const KEYRING_PREFIX: &str = "keyring:";
 
 /// Configuration for an individual wallpaper source API.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Source {
   pub name: String,
+  /// The API key, or a reference to one in the system keyring. Read it
+  /// through [Source::api_key], which also honours the environment.
   pub api_key: Option<String>,
+  #[serde(default)]
   pub base_url: String,
+  #[serde(default)]
   pub requires_api_key: bool,
   pub enabled: bool,
+  #[serde(default)]
   pub valid: bool,
 
+  /// How many API requests may run at once. Defaults to the provider's own
+  /// limit.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_concurrent_requests: Option<usize>,
+
+  /// How many downloads may run at once. Defaults to the provider's own
+  /// limit.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_concurrent_downloads: Option<usize>,
+
+  /// How requests and downloads failing for a passing reason are retried.
+  /// Defaults to three attempts with exponential backoff.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub retry: Option<Retry>,
+
   /// Wallhaven-specific default parameters.
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub wallhaven: Option<Wallhaven>
+  pub wallhaven: Option<Wallhaven>,
+
+  /// Pexels-specific default parameters.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub pexels: Option<Pexels>,
+
+  /// Reddit-specific default parameters.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reddit: Option<Reddit>,
+
+  /// Folders on disk. A source with these is a local source, whatever its
+  /// name.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub local: Option<Local>,
+
+  /// How to sign in to the source, for actions that need a user.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub oauth: Option<OAuth>
 }
 
 impl Source {
@@ -48,11 +97,120 @@ impl Source {
     self
   }
 
+  /// Sets the Pexels-specific parameters.
+  pub fn with_pexels_params(mut self, params: Pexels) -> Self {
+    self.pexels = Some(params);
+    self
+  }
+
+  /// Sets the Reddit-specific parameters.
+  pub fn with_reddit_params(mut self, params: Reddit) -> Self {
+    self.reddit = Some(params);
+    self
+  }
+
+  /// Makes this a local source reading from folders on disk.
+  pub fn with_local_params(mut self, params: Local) -> Self {
+    self.local = Some(params);
+    self
+  }
+
+  /// Sets how to sign in to the source.
+  pub fn with_oauth(mut self, oauth: OAuth) -> Self {
+    self.oauth = Some(oauth);
+    self
+  }
+
   /// Sets the enabled status of the source.
   pub fn with_enabled(mut self, enabled: bool) -> Self {
     self.enabled = enabled;
     self
   }
+
+  /// Sets how many requests and downloads may run at once.
+  pub fn with_concurrency(mut self, limits: Concurrency) -> Self {
+    self.max_concurrent_requests = Some(limits.requests);
+    self.max_concurrent_downloads = Some(limits.downloads);
+    self
+  }
+
+  /// Sets how failing requests and downloads are retried.
+  pub fn with_retry(mut self, retry: Retry) -> Self {
+    self.retry = Some(retry);
+    self
+  }
+
+  /// Returns how failing requests and downloads are retried.
+  pub fn retry(&self) -> Retry {
+    self.retry.unwrap_or_default()
+  }
+
+  /// Returns the environment variable that overrides the API key, e.g.
+  /// `WALLTER_WALLHAVEN_API_KEY`.
+  pub fn env_var(&self) -> String {
+    let name: String = self
+      .name
+      .chars()
+      .map(|c| {
+        if c.is_ascii_alphanumeric() {
+          c.to_ascii_uppercase()
+        } else {
+          '_'
+        }
+      })
+      .collect();
+    format!("WALLTER_{name}_API_KEY")
+  }
+
+  /// Returns the keyring entry the API key is kept in, if the config refers
+  /// to one.
+  pub fn keyring_entry(&self) -> Option<&str> {
+    self.api_key.as_deref()?.strip_prefix(KEYRING_PREFIX)
+  }
+
+  /// Returns the API key: from the [environment](Self::env_var) if set
+  /// there, then from the keyring entry the config refers to, then as
+  /// written in the config. A keyring that cannot be read is reported and
+  /// treated as holding no key.
+  pub fn api_key(&self) -> Option<String> {
+    if let Some(key) = env::var(self.env_var())
+      .ok()
+      .filter(|key| !key.trim().is_empty())
+    {
+      return Some(key);
+    }
+    let Some(entry) = self.keyring_entry() else {
+      return self.api_key.clone();
+    };
+
+    #[cfg(feature = "online-sources")]
+    match crate::api::secrets::load(entry) {
+      Ok(Some(key)) => Some(key),
+      Ok(None) => {
+        warn!("No API key is stored in the keyring as '{entry}'");
+        None
+      }
+      Err(e) => {
+        warn!("Failed to read the {} API key: {e}", self.name);
+        None
+      }
+    }
+    #[cfg(not(feature = "online-sources"))]
+    {
+      warn!("Reading the API key '{entry}' needs the keyring support");
+      None
+    }
+  }
+
+  /// Returns how many requests and downloads may run at once: the
+  /// provider's own limits, overridden by those set on the source.
+  pub fn concurrency(&self) -> Concurrency {
+    let baked = Concurrency::of(&self.name);
+    Concurrency::new(
+      self.max_concurrent_requests.unwrap_or(baked.requests),
+      self.max_concurrent_downloads.unwrap_or(baked.downloads)
+    )
+  }
 }
 
 impl Display for Source {
@@ -67,13 +225,54 @@ impl Display for Source {
     printf!(f, "Requires API Key", self.requires_api_key)?;
     printf!(f, "Enabled (User)", self.enabled)?;
     printf!(f, "Valid (Runtime)", self.valid)?;
-    printf!(f, "API Key", self.api_key.as_deref().unwrap_or("[Not Set]"))?;
+    let env_var = self.env_var();
+    let api_key = if env::var_os(&env_var).is_some() {
+      format!("[From ${env_var}]")
+    } else if let Some(entry) = self.keyring_entry() {
+      format!("[In the keyring as '{entry}']")
+    } else {
+      self.api_key.clone().unwrap_or_else(|| "[Not Set]".into())
+    };
+    printf!(f, "API Key", api_key)?;
+    printf!(f, "Concurrency", self.concurrency())?;
+    printf!(f, "Retries", self.retry())?;
+    if self.oauth.is_some() {
+      printf!(f, "Sign In", "OAuth (wallter auth)")?;
+    }
     if let Some(params) = &self.wallhaven {
       printh!(f, "API Parameters:", 4)?; // Heading at indent 4
       writeln!(f, "{params}")?;
       // params.display_indented(f, 4)?; // Pass 4 as base_indent, so fields
       // will be at 6
     }
+    if let Some(params) = &self.pexels {
+      printh!(f, "API Parameters:", 4)?;
+      writeln!(f, "{params}")?;
+    }
+    if let Some(params) = &self.reddit {
+      printh!(f, "API Parameters:", 4)?;
+      writeln!(f, "{params}")?;
+    }
+    if let Some(params) = &self.local {
+      printh!(f, "Local Parameters:", 4)?;
+      writeln!(f, "{params}")?;
+    }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_api_key() {
+    let source = Source::new("my-photos", "", true).with_api_key("secret");
+    assert_eq!(source.env_var(), "WALLTER_MY_PHOTOS_API_KEY");
+    assert_eq!(source.keyring_entry(), None);
+    assert_eq!(source.api_key().as_deref(), Some("secret"));
+
+    let source = source.with_api_key("keyring:photos");
+    assert_eq!(source.keyring_entry(), Some("photos"));
+  }
+}
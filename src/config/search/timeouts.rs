@@ -0,0 +1,71 @@
+use crate::config::slideshow::Interval;
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  time::Duration
+};
+
+/// How long network operations may take before they are given up on.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Timeouts {
+  /// Connecting to a server.
+  pub connect: Interval,
+
+  /// A whole API request, such as a search or a detail lookup.
+  pub request: Interval,
+
+  /// A whole image download, which can run to tens of megabytes.
+  pub download: Interval
+}
+
+impl Default for Timeouts {
+  fn default() -> Self {
+    Self {
+      connect: Interval::with_seconds(10),
+      request: Interval::with_seconds(30),
+      download: Interval::with_minutes(5)
+    }
+  }
+}
+
+impl Timeouts {
+  /// Returns the connect timeout.
+  pub fn connect(&self) -> Duration {
+    self.connect.to_duration()
+  }
+
+  /// Returns the timeout of an API request.
+  pub fn request(&self) -> Duration {
+    self.request.to_duration()
+  }
+
+  /// Returns the timeout of an image download.
+  pub fn download(&self) -> Duration {
+    self.download.to_duration()
+  }
+}
+
+impl Display for Timeouts {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Connect {}, requests {}, downloads {}",
+      self.connect, self.request, self.download
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_deserialize() {
+    let timeouts: Timeouts =
+      toml::from_str("request = \"1m\"\ndownload = \"900\"").unwrap();
+    assert_eq!(timeouts.connect(), Duration::from_secs(10));
+    assert_eq!(timeouts.request(), Duration::from_secs(60));
+    assert_eq!(timeouts.download(), Duration::from_secs(900));
+  }
+}
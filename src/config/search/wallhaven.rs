@@ -1,4 +1,4 @@
-use crate::api::wallhaven::{Order, Sorting, ToplistRange};
+use crate::api::wallhaven::{Order, SearchParams, Sorting, ToplistRange};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 
@@ -36,6 +36,24 @@ pub struct Params {
   pub colors: Option<String>
 }
 
+impl From<&Params> for SearchParams {
+  fn from(params: &Params) -> Self {
+    Self {
+      query: params.query.clone(),
+      categories: params.categories,
+      purity: params.purity,
+      sorting: params.sorting,
+      order: params.order,
+      top_range: params.top_range,
+      atleast: params.atleast.clone(),
+      resolutions: params.resolutions.clone(),
+      ratios: params.ratios.clone(),
+      colors: params.colors.clone(),
+      ..Default::default()
+    }
+  }
+}
+
 impl Display for Params {
   fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
     const PAD: usize = 22;
@@ -50,23 +68,11 @@ impl Display for Params {
     )?;
 
     if let Some(cats) = self.categories {
-      let cat_str = format!(
-        "G:{} A:{} P:{}",
-        if cats.0 { "✓" } else { "✗" },
-        if cats.1 { "✓" } else { "✗" },
-        if cats.2 { "✓" } else { "✗" }
-      );
-      printf!(f, "Categories", cat_str, PAD, TAB)?;
+      printf!(f, "Categories", categories_str(cats), PAD, TAB)?;
     }
 
     if let Some(purs) = self.purity {
-      let pur_str = format!(
-        "SFW:{} Sketchy:{} NSFW:{}",
-        if purs.0 { "✓" } else { "✗" },
-        if purs.1 { "✓" } else { "✗" },
-        if purs.2 { "✓" } else { "✗" }
-      );
-      printf!(f, "Purity", pur_str, PAD, TAB)?;
+      printf!(f, "Purity", purity_str(purs), PAD, TAB)?;
     }
 
     if let Some(sorting) = self.sorting {
@@ -100,3 +106,22 @@ impl Display for Params {
     Ok(())
   }
 }
+
+fn mark(enabled: bool) -> &'static str {
+  if enabled { "✓" } else { "✗" }
+}
+
+/// Formats a (General, Anime, People) tuple for display.
+pub(super) fn categories_str(cats: (bool, bool, bool)) -> String {
+  format!("G:{} A:{} P:{}", mark(cats.0), mark(cats.1), mark(cats.2))
+}
+
+/// Formats a (SFW, Sketchy, NSFW) tuple for display.
+pub(super) fn purity_str(purs: (bool, bool, bool)) -> String {
+  format!(
+    "SFW:{} Sketchy:{} NSFW:{}",
+    mark(purs.0),
+    mark(purs.1),
+    mark(purs.2)
+  )
+}
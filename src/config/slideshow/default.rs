@@ -1,7 +1,16 @@
+use super::{Rule, Selector};
+use crate::Error;
+use crate::config::Monitor;
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Display, Formatter};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::PathBuf,
+  str::FromStr,
+  time::Duration
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Unit {
   #[serde(rename = "seconds")]
   Seconds,
@@ -24,12 +33,46 @@ impl Display for Unit {
   }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Unit {
+  /// Returns the number of seconds in one unit.
+  pub fn seconds(self) -> u64 {
+    match self {
+      Unit::Seconds => 1,
+      Unit::Minutes => 60,
+      Unit::Hours => 60 * 60,
+      Unit::Days => 24 * 60 * 60
+    }
+  }
+}
+
+/// A time span, written in configuration either as `{ value, unit }` or in
+/// the compact form accepted by [Interval::from_str], e.g. `"30m"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "IntervalRepr")]
 pub struct Interval {
   pub value: u32,
   pub unit: Unit
 }
 
+/// The accepted serialized forms of an [Interval].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IntervalRepr {
+  Compact(String),
+  Full { value: u32, unit: Unit }
+}
+
+impl TryFrom<IntervalRepr> for Interval {
+  type Error = Error;
+
+  fn try_from(repr: IntervalRepr) -> Result<Self, Self::Error> {
+    match repr {
+      IntervalRepr::Compact(s) => s.parse(),
+      IntervalRepr::Full { value, unit } => Ok(Self { value, unit })
+    }
+  }
+}
+
 impl Default for Interval {
   fn default() -> Self {
     Self {
@@ -67,6 +110,37 @@ impl Interval {
       unit: Unit::Days
     }
   }
+
+  /// Converts the interval to a [Duration].
+  pub fn to_duration(self) -> Duration {
+    Duration::from_secs(u64::from(self.value) * self.unit.seconds())
+  }
+}
+
+impl FromStr for Interval {
+  type Err = Error;
+
+  /// Parses a compact interval such as `"45s"`, `"30m"`, `"2h"` or `"1d"`.
+  /// A bare number is read as seconds.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value = value
+      .parse()
+      .map_err(|_| Error::Settings(format!("Invalid interval: '{s}'")))?;
+    let unit = match unit.trim().to_lowercase().as_str() {
+      "" | "s" | "sec" | "secs" | "second" | "seconds" => Unit::Seconds,
+      "m" | "min" | "mins" | "minute" | "minutes" => Unit::Minutes,
+      "h" | "hr" | "hrs" | "hour" | "hours" => Unit::Hours,
+      "d" | "day" | "days" => Unit::Days,
+      other =>
+        return Err(Error::Settings(format!(
+          "Invalid interval unit '{other}' in '{s}'"
+        ))),
+    };
+    Ok(Self { value, unit })
+  }
 }
 
 impl Display for Interval {
@@ -75,11 +149,383 @@ impl Display for Interval {
   }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Controls preparing the next wallpaper ahead of the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prefetch {
+  pub enabled: bool,
+  /// The minimum time between network prefetches, to go easy on the sources.
+  pub min_gap: Interval
+}
+
+impl Default for Prefetch {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      min_gap: Interval::with_minutes(5)
+    }
+  }
+}
+
+impl Display for Prefetch {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.enabled {
+      write!(f, "Enabled (at most every {})", self.min_gap)
+    } else {
+      write!(f, "Disabled")
+    }
+  }
+}
+
+/// Holds timed changes back while the user is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Idle {
+  pub enabled: bool,
+  /// How long the user must have been idle before a change is made.
+  pub min_idle: Interval
+}
+
+impl Default for Idle {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      min_idle: Interval::with_minutes(2)
+    }
+  }
+}
+
+impl Display for Idle {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.enabled {
+      write!(f, "Only change after {} idle", self.min_idle)
+    } else {
+      write!(f, "Disabled")
+    }
+  }
+}
+
+/// What makes the slideshow daemon change the wallpaper.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+  /// Every time the interval elapses.
+  #[default]
+  Timer,
+  /// Every time the session is unlocked or the machine resumes from sleep.
+  Unlock,
+  /// Both on the timer and on unlock or resume.
+  Both
+}
+
+impl Trigger {
+  /// Whether the wallpaper changes when the interval elapses.
+  pub fn on_timer(self) -> bool {
+    matches!(self, Self::Timer | Self::Both)
+  }
+
+  /// Whether the wallpaper changes on unlock or resume.
+  pub fn on_unlock(self) -> bool {
+    matches!(self, Self::Unlock | Self::Both)
+  }
+}
+
+impl Display for Trigger {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Timer => write!(f, "Timer"),
+      Self::Unlock => write!(f, "Unlock/Resume"),
+      Self::Both => write!(f, "Timer and Unlock/Resume")
+    }
+  }
+}
+
+/// Folders the daemon imports new images from, such as a directory shared
+/// with other devices.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watch {
+  pub dirs: Vec<PathBuf>,
+  /// Apply each newly imported image right away.
+  #[serde(default)]
+  pub set_immediately: bool
+}
+
+impl Display for Watch {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.dirs.is_empty() {
+      return write!(f, "None");
+    }
+    let dirs: Vec<String> = self
+      .dirs
+      .iter()
+      .map(|dir| dir.display().to_string())
+      .collect();
+    write!(f, "{}", dirs.join(", "))?;
+    if self.set_immediately {
+      write!(f, " (applied on arrival)")?;
+    }
+    Ok(())
+  }
+}
+
+/// Narrows the slideshow to one theme a week, chosen from `pool` or, when
+/// the pool is empty, from the tags trending on Wallhaven.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+  pub enabled: bool,
+  /// Searches to choose the weekly theme from, e.g. `"mountains"`.
+  #[serde(default)]
+  pub pool: Vec<String>
+}
+
+impl Display for Theme {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match (self.enabled, self.pool.is_empty()) {
+      (false, _) => write!(f, "Disabled"),
+      (true, true) => write!(f, "Weekly, from trending tags"),
+      (true, false) => write!(f, "Weekly, from {}", self.pool.join(", "))
+    }
+  }
+}
+
+/// Keeps the wallpapers of several machines in step. A primary serves its
+/// changes on `serve`; a follower subscribes to a primary with `follow` and
+/// shows the same wallpapers instead of rotating on its own.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mirror {
+  /// The address to serve changes on, e.g. `"0.0.0.0:7979"`.
+  #[serde(default)]
+  pub serve: Option<String>,
+  /// The primary to follow, e.g. `"http://desk-pc:7979"`.
+  #[serde(default)]
+  pub follow: Option<String>,
+  /// Which local monitor shows each of the primary's monitors, by name.
+  /// Monitors missing here follow the primary's monitor of the same name.
+  #[serde(default)]
+  pub monitors: BTreeMap<String, String>
+}
+
+impl Display for Mirror {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match (&self.serve, &self.follow) {
+      (None, None) => write!(f, "Disabled"),
+      (Some(serve), None) => write!(f, "Serving on {serve}"),
+      (None, Some(follow)) => write!(f, "Following {follow}"),
+      (Some(serve), Some(follow)) =>
+        write!(f, "Serving on {serve}, following {follow}"),
+    }
+  }
+}
+
+/// Learns which sources and tags are skipped soon after they appear, and
+/// optionally shows them less often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+  /// Lower the selection weight of often skipped sources and tags.
+  pub auto_adjust: bool,
+  /// A skip this soon after a change counts against the wallpaper.
+  pub window: Interval
+}
+
+impl Default for Tuning {
+  fn default() -> Self {
+    Self {
+      auto_adjust: false,
+      window: Interval::with_seconds(30)
+    }
+  }
+}
+
+impl Display for Tuning {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Learning from skips within {}", self.window)?;
+    if self.auto_adjust {
+      write!(f, ", auto-adjusting")?;
+    }
+    Ok(())
+  }
+}
+
+/// Gives each workspace of a tiling window manager its own wallpaper.
+/// Supported on Hyprland and Sway.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Workspaces {
+  pub enabled: bool,
+  /// How long focus must rest on a workspace before its wallpaper is shown,
+  /// so flicking past workspaces does not change the wallpaper each time.
+  pub debounce: Interval,
+  /// Images always shown on a workspace, by workspace name or tag. The tag
+  /// of `"2:web"` is `"web"`.
+  pub pinned: BTreeMap<String, PathBuf>
+}
+
+impl Default for Workspaces {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      debounce: Interval::with_seconds(1),
+      pinned: BTreeMap::new()
+    }
+  }
+}
+
+impl Workspaces {
+  /// Returns the image pinned to the workspace called `name`, matched by
+  /// the full name first, then by its tag, then by its number.
+  pub fn pinned_for(&self, name: &str) -> Option<&PathBuf> {
+    let (number, tag) = name.split_once(':').unwrap_or((name, name));
+    [name, tag, number]
+      .into_iter()
+      .find_map(|key| self.pinned.get(key))
+  }
+}
+
+impl Display for Workspaces {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if !self.enabled {
+      return write!(f, "Disabled");
+    }
+    write!(f, "Per workspace, after {}", self.debounce)?;
+    if !self.pinned.is_empty() {
+      write!(f, ", {} pinned", self.pinned.len())?;
+    }
+    Ok(())
+  }
+}
+
+/// How images are compared to keep consecutive wallpapers apart.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+  /// Don't compare images.
+  #[default]
+  Off,
+  /// Compare perceptual fingerprints, which tells near-identical shots apart
+  /// from different ones. The difference is in bits, out of 64.
+  Fingerprint,
+  /// Compare dominant hues, which keeps the colors changing. The difference
+  /// is in degrees, out of 180.
+  Hue
+}
+
+impl Comparison {
+  /// The least difference asked for unless configured.
+  pub fn default_threshold(self) -> u32 {
+    match self {
+      Self::Off => 0,
+      Self::Fingerprint => 16,
+      Self::Hue => 30
+    }
+  }
+}
+
+impl Display for Comparison {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Off => write!(f, "off"),
+      Self::Fingerprint => write!(f, "fingerprint"),
+      Self::Hue => write!(f, "hue")
+    }
+  }
+}
+
+/// Keeps each wallpaper looking different enough from the ones shown just
+/// before it on the same monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Variety {
+  pub compare: Comparison,
+  /// How many of the wallpapers shown last a new one is compared with.
+  pub depth: usize,
+  /// The least difference, in the units of the comparison. Defaults to 16
+  /// bits for fingerprints and 30 degrees for hues.
+  pub threshold: Option<u32>,
+  /// Thresholds for particular monitors, by name.
+  pub monitors: BTreeMap<String, u32>
+}
+
+impl Default for Variety {
+  fn default() -> Self {
+    Self {
+      compare: Comparison::Off,
+      depth: 2,
+      threshold: None,
+      monitors: BTreeMap::new()
+    }
+  }
+}
+
+impl Variety {
+  /// Returns the least difference asked for on `monitor`.
+  pub fn threshold_for(&self, monitor: &Monitor) -> u32 {
+    self
+      .monitors
+      .get(&monitor.name)
+      .copied()
+      .or(self.threshold)
+      .unwrap_or_else(|| self.compare.default_threshold())
+  }
+}
+
+impl Display for Variety {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.compare == Comparison::Off {
+      return write!(f, "Disabled");
+    }
+    write!(
+      f,
+      "Differs by {} from the last {}",
+      self.compare, self.depth
+    )?;
+    if !self.monitors.is_empty() {
+      write!(f, ", {} monitor thresholds", self.monitors.len())?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Config {
   pub interval: Interval,
   pub enabled: bool,
-  pub sources: Vec<String>
+  /// Where wallpapers come from, as source expressions such as
+  /// `"favorites"` or `"wallhaven:toplist:1M"`.
+  pub sources: Vec<Selector>,
+  #[serde(default)]
+  pub prefetch: Prefetch,
+  #[serde(default)]
+  pub trigger: Trigger,
+  #[serde(default)]
+  pub idle: Idle,
+  /// Only show wallpapers from the favorites directory.
+  #[serde(default)]
+  pub favorites_only: bool,
+  #[serde(default)]
+  pub watch: Watch,
+  #[serde(default)]
+  pub mirror: Mirror,
+  #[serde(default)]
+  pub theme: Theme,
+  #[serde(default)]
+  pub tuning: Tuning,
+  #[serde(default)]
+  pub workspaces: Workspaces,
+  #[serde(default)]
+  pub variety: Variety,
+  /// How wallpapers are chosen for monitors by their role.
+  #[serde(default)]
+  pub roles: Vec<Rule>
+}
+
+impl Config {
+  /// Returns the first role rule that applies to `monitor`.
+  pub fn rule_for(&self, monitor: &Monitor) -> Option<&Rule> {
+    self.roles.iter().find(|rule| rule.matches(monitor))
+  }
 }
 
 impl Display for Config {
@@ -87,6 +533,70 @@ impl Display for Config {
     writeln!(f, "Slideshow Settings:")?;
     writeln!(f, "  Change Interval: {}", self.interval)?;
     writeln!(f, "  Enabled: {}", self.enabled)?;
-    writeln!(f, "  Sources: {}", self.sources.join(", "))
+    let sources: Vec<String> =
+      self.sources.iter().map(Selector::to_string).collect();
+    writeln!(f, "  Sources: {}", sources.join(", "))?;
+    writeln!(f, "  Prefetch: {}", self.prefetch)?;
+    writeln!(f, "  Trigger: {}", self.trigger)?;
+    writeln!(f, "  Idle Deferral: {}", self.idle)?;
+    writeln!(f, "  Favorites Only: {}", self.favorites_only)?;
+    writeln!(f, "  Watch Folders: {}", self.watch)?;
+    writeln!(f, "  Mirror: {}", self.mirror)?;
+    writeln!(f, "  Theme of the Week: {}", self.theme)?;
+    writeln!(f, "  Tuning: {}", self.tuning)?;
+    writeln!(f, "  Workspaces: {}", self.workspaces)?;
+    writeln!(f, "  Variety: {}", self.variety)?;
+    let roles: Vec<String> = self.roles.iter().map(Rule::to_string).collect();
+    if roles.is_empty() {
+      writeln!(f, "  Roles: None")
+    } else {
+      writeln!(f, "  Roles: {}", roles.join(", "))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_interval_from_str() {
+    assert_eq!(
+      "30m".parse::<Interval>().unwrap(),
+      Interval::with_minutes(30)
+    );
+    assert_eq!(
+      "2 hours".parse::<Interval>().unwrap(),
+      Interval::with_hours(2)
+    );
+    assert_eq!(
+      "90".parse::<Interval>().unwrap(),
+      Interval::with_seconds(90)
+    );
+    assert!("soon".parse::<Interval>().is_err());
+    assert!("5y".parse::<Interval>().is_err());
+  }
+
+  #[test]
+  fn test_pinned_for() {
+    let workspaces = Workspaces {
+      pinned: BTreeMap::from([
+        ("web".into(), PathBuf::from("web.jpg")),
+        ("3".into(), PathBuf::from("three.jpg")),
+        ("2:web".into(), PathBuf::from("exact.jpg"))
+      ]),
+      ..Default::default()
+    };
+    assert_eq!(workspaces.pinned_for("2:web"), Some(&"exact.jpg".into()));
+    assert_eq!(workspaces.pinned_for("4:web"), Some(&"web.jpg".into()));
+    assert_eq!(workspaces.pinned_for("3:code"), Some(&"three.jpg".into()));
+    assert_eq!(workspaces.pinned_for("3"), Some(&"three.jpg".into()));
+    assert_eq!(workspaces.pinned_for("5"), None);
+  }
+
+  #[test]
+  fn test_interval_to_duration() {
+    assert_eq!(Interval::with_days(1).to_duration().as_secs(), 86_400);
+    assert_eq!(Interval::default().to_duration().as_secs(), 60);
   }
 }
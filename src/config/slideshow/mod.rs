@@ -1,2 +1,11 @@
 mod default;
-pub use default::Config;
+pub use default::{
+  Comparison, Config, Idle, Interval, Mirror, Prefetch, Theme, Trigger, Tuning,
+  Unit, Variety, Watch, Workspaces
+};
+
+mod selector;
+pub use selector::Selector;
+
+mod role;
+pub use role::Rule;
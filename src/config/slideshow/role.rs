@@ -0,0 +1,96 @@
+//! Rules that pick wallpapers for monitors by their role.
+//!
+//! Each entry in `slideshow.roles` applies to the monitors with its `role`,
+//! optionally only those of one orientation, so a setup can be described
+//! without naming every monitor. The first rule matching a monitor narrows
+//! the search its wallpapers come from.
+
+use crate::{
+  api::wallhaven::Sorting,
+  config::{
+    Monitor,
+    monitor::{Orientation, Role}
+  }
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// How wallpapers are chosen for the monitors playing a role.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+  pub role: Role,
+  /// Only apply to monitors of this orientation.
+  #[serde(default)]
+  pub orientation: Option<Orientation>,
+  /// Search keywords, e.g. `"mountains"`.
+  #[serde(default)]
+  pub query: Option<String>,
+  /// How Wallhaven sorts the results, e.g. `"Toplist"`.
+  #[serde(default)]
+  pub sorting: Option<Sorting>,
+  /// Only show images with soft, low-contrast colors, where the source
+  /// reports them.
+  #[serde(default)]
+  pub muted: bool
+}
+
+impl Rule {
+  /// Whether the rule applies to `monitor`.
+  pub fn matches(&self, monitor: &Monitor) -> bool {
+    self.role == monitor.role()
+      && self
+        .orientation
+        .is_none_or(|orientation| orientation == monitor.size.orientation())
+  }
+}
+
+impl Display for Rule {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.role)?;
+    if let Some(orientation) = self.orientation {
+      write!(f, " {orientation}")?;
+    }
+    let mut narrowing = Vec::new();
+    if let Some(sorting) = self.sorting {
+      narrowing.push(sorting.to_string());
+    }
+    if let Some(query) = &self.query {
+      narrowing.push(format!("q={query}"));
+    }
+    if self.muted {
+      narrowing.push("muted".to_string());
+    }
+    if !narrowing.is_empty() {
+      write!(f, " ({})", narrowing.join(", "))?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_matches() {
+    let rule: Rule = toml::from_str(
+      "role = \"secondary\"\norientation = \"Portrait\"\nmuted = true"
+    )
+    .unwrap();
+    assert!(rule.matches(&Monitor::test("DP-1", 1080, 1920)));
+    assert!(!rule.matches(&Monitor::test("DP-1", 1920, 1080)));
+    assert!(!rule.matches(&Monitor {
+      primary: true,
+      ..Monitor::test("DP-1", 1080, 1920)
+    }));
+    assert_eq!(rule.to_string(), "secondary Portrait (muted)");
+
+    let rule: Rule =
+      toml::from_str("role = \"primary\"\nsorting = \"Toplist\"").unwrap();
+    assert!(rule.matches(&Monitor {
+      primary: true,
+      ..Monitor::test("DP-1", 2560, 1440)
+    }));
+    assert_eq!(rule.to_string(), "primary (toplist)");
+  }
+}
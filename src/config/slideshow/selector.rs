@@ -0,0 +1,190 @@
+//! Source expressions for the slideshow.
+//!
+//! Each entry in `slideshow.sources` is a short expression naming where
+//! wallpapers come from, optionally narrowed by filters separated by `:`:
+//!
+//! - `favorites`: the favorites directory.
+//! - `library`, `library:tag=nature`: downloaded wallpapers, optionally only
+//!   those with a tag.
+//! - `wallhaven`, `wallhaven:toplist:1M`, `wallhaven:random:q=nature`: a search
+//!   source, with an optional sorting method, toplist range and query.
+
+use crate::{
+  Error, Result,
+  api::wallhaven::{Sorting, ToplistRange}
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  str::FromStr
+};
+
+/// Where the slideshow takes wallpapers from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Selector {
+  /// Wallpapers in the favorites directory.
+  Favorites,
+  /// Downloaded wallpapers, optionally only those with `tag`.
+  Library { tag: Option<String> },
+  /// Results from a configured search source.
+  Search {
+    source: String,
+    sorting: Option<Sorting>,
+    range: Option<ToplistRange>,
+    query: Option<String>
+  }
+}
+
+impl FromStr for Selector {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let invalid = |reason: String| {
+      Error::Settings(format!("Invalid source '{s}': {reason}"))
+    };
+    let mut parts = s.trim().split(':');
+    let name = parts.next().unwrap_or_default().trim();
+    let filters: Vec<&str> = parts.map(str::trim).collect();
+
+    match name {
+      "" => Err(invalid("missing source name".to_string())),
+      "favorites" => match filters.as_slice() {
+        [] => Ok(Self::Favorites),
+        _ => Err(invalid("favorites takes no filters".to_string()))
+      },
+      "library" => {
+        let mut tag = None;
+        for filter in filters {
+          match filter.split_once('=') {
+            Some(("tag", value)) if !value.is_empty() =>
+              tag = Some(value.to_string()),
+            _ => return Err(invalid(format!("unknown filter '{filter}'")))
+          }
+        }
+        Ok(Self::Library { tag })
+      }
+      source => {
+        let (mut sorting, mut range, mut query) = (None, None, None);
+        for filter in filters {
+          if let Some(value) = filter.strip_prefix("q=") {
+            query = Some(value.to_string());
+          } else if let Ok(value) = filter.parse::<ToplistRange>() {
+            range = Some(value);
+          } else if let Ok(value) = filter.parse::<Sorting>() {
+            sorting = Some(value);
+          } else {
+            return Err(invalid(format!("unknown filter '{filter}'")));
+          }
+        }
+        if range.is_some() && sorting.is_none() {
+          sorting = Some(Sorting::Toplist);
+        }
+        if range.is_some() && sorting != Some(Sorting::Toplist) {
+          return Err(invalid("a range only applies to toplist".to_string()));
+        }
+        Ok(Self::Search {
+          source: source.to_string(),
+          sorting,
+          range,
+          query
+        })
+      }
+    }
+  }
+}
+
+impl TryFrom<String> for Selector {
+  type Error = Error;
+
+  fn try_from(value: String) -> Result<Self> {
+    value.parse()
+  }
+}
+
+impl From<Selector> for String {
+  fn from(selector: Selector) -> Self {
+    selector.to_string()
+  }
+}
+
+impl Display for Selector {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Favorites => write!(f, "favorites"),
+      Self::Library { tag } => {
+        write!(f, "library")?;
+        if let Some(tag) = tag {
+          write!(f, ":tag={tag}")?;
+        }
+        Ok(())
+      }
+      Self::Search {
+        source,
+        sorting,
+        range,
+        query
+      } => {
+        write!(f, "{source}")?;
+        if let Some(sorting) = sorting {
+          write!(f, ":{sorting}")?;
+        }
+        if let Some(range) = range {
+          write!(f, ":{range}")?;
+        }
+        if let Some(query) = query {
+          write!(f, ":q={query}")?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_selector_from_str() {
+    assert_eq!(
+      "favorites".parse::<Selector>().unwrap(),
+      Selector::Favorites
+    );
+    assert_eq!(
+      "library:tag=nature".parse::<Selector>().unwrap(),
+      Selector::Library {
+        tag: Some("nature".into())
+      }
+    );
+    assert_eq!(
+      "wallhaven:toplist:1M".parse::<Selector>().unwrap(),
+      Selector::Search {
+        source: "wallhaven".into(),
+        sorting: Some(Sorting::Toplist),
+        range: Some(ToplistRange::Month),
+        query: None
+      }
+    );
+    assert!("".parse::<Selector>().is_err());
+    assert!("favorites:tag=x".parse::<Selector>().is_err());
+    assert!("library:color=red".parse::<Selector>().is_err());
+    assert!("wallhaven:random:1w".parse::<Selector>().is_err());
+    assert!("wallhaven:sideways".parse::<Selector>().is_err());
+  }
+
+  #[test]
+  fn test_selector_round_trip() {
+    for expression in [
+      "favorites",
+      "library",
+      "library:tag=nature",
+      "wallhaven",
+      "wallhaven:toplist:1M",
+      "wallhaven:random:q=city lights"
+    ] {
+      let selector: Selector = expression.parse().unwrap();
+      assert_eq!(selector.to_string(), expression);
+    }
+  }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Settings for the local usage statistics.
+///
+/// Statistics are opt-in and are only ever stored on disk, in the state
+/// directory. Nothing is reported over the network.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Config {
+  /// Whether usage statistics are recorded.
+  pub enabled: bool
+}
+
+impl Display for Config {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    printf!(f, "Enabled", self.enabled)
+  }
+}
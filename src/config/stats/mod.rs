@@ -0,0 +1,2 @@
+mod default;
+pub use default::Config;
@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Settings for applying wallpapers.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Config {
+  /// The animation used when changing wallpapers, on backends that support
+  /// one (currently `swww`).
+  #[serde(default)]
+  pub transition: Transition,
+
+  /// Cut an image set on every monitor across rows of adjacent monitors of
+  /// the same height, for a continuous panorama on backends that can't span.
+  #[serde(default)]
+  pub continuity: bool,
+
+  /// How wallpapers are applied in X11 sessions without GNOME or KDE.
+  #[serde(default)]
+  pub x11: X11Tool,
+
+  /// How wallpapers are fitted and encoded by the Windows fallback backend.
+  #[serde(default)]
+  pub windows: Windows,
+
+  /// Brightness compensation for monitors in HDR mode.
+  #[serde(default)]
+  pub hdr: Hdr,
+
+  /// Dimmed copies of light wallpapers, shown while the system is dark.
+  #[serde(default)]
+  pub dark_variant: DarkVariant,
+
+  /// Size limits checked before any image is decoded.
+  #[serde(default)]
+  pub limits: Limits,
+
+  /// Cached previews of library images.
+  #[serde(default)]
+  pub thumbnails: Thumbnails,
+
+  /// What happens to notifications while the desktop is set not to disturb.
+  #[serde(default)]
+  pub do_not_disturb: DoNotDisturb
+}
+
+/// The size of library thumbnails and of their cache.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Thumbnails {
+  /// The longest side of a thumbnail, in pixels.
+  pub size: u32,
+  /// The most the cache may hold, in MiB. The least recently used
+  /// thumbnails are removed first.
+  pub max_cache_size: u64
+}
+
+impl Default for Thumbnails {
+  fn default() -> Self {
+    Self {
+      size: 320,
+      max_cache_size: 256
+    }
+  }
+}
+
+impl Display for Thumbnails {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{}px, up to {} MiB", self.size, self.max_cache_size)
+  }
+}
+
+/// Limits that protect against decompression bombs and broken files.
+/// Images exceeding them are rejected and blocklisted.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Limits {
+  /// The maximum number of pixels (width × height).
+  pub max_pixels: u64,
+  /// The maximum file size in bytes.
+  pub max_file_size: u64
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Self {
+      max_pixels: 128 * 1024 * 1024,
+      max_file_size: 128 * 1024 * 1024
+    }
+  }
+}
+
+impl Display for Limits {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{:.0} MP, {:.0} MiB",
+      self.max_pixels as f64 / 1_000_000.0,
+      self.max_file_size as f64 / (1024.0 * 1024.0)
+    )
+  }
+}
+
+/// What happens to a notification while the desktop is set not to disturb.
+#[derive(
+  Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DoNotDisturb {
+  /// Hold it back and show it once do not disturb is turned off.
+  #[default]
+  Queue,
+  /// Drop it.
+  Suppress,
+  /// Show it anyway.
+  Ignore
+}
+
+impl Display for DoNotDisturb {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Queue => write!(f, "Queue Notifications"),
+      Self::Suppress => write!(f, "Suppress Notifications"),
+      Self::Ignore => write!(f, "Ignore")
+    }
+  }
+}
+
+/// When to compensate for SDR images looking dim on HDR monitors.
+#[derive(
+  Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HdrMode {
+  /// Only on monitors detected to be in HDR mode (currently Windows only).
+  #[default]
+  Auto,
+  /// On every monitor.
+  Always,
+  /// Never.
+  Off
+}
+
+/// Tone mapping applied to SDR wallpapers shown on HDR monitors.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Hdr {
+  pub mode: HdrMode,
+  /// The brightness multiplier applied in linear light. Highlights are
+  /// rolled off smoothly instead of clipping.
+  pub gain: f32
+}
+
+impl Default for Hdr {
+  fn default() -> Self {
+    Self {
+      mode: HdrMode::Auto,
+      gain: 1.3
+    }
+  }
+}
+
+impl Display for Hdr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self.mode {
+      HdrMode::Auto => write!(f, "Auto (gain {})", self.gain),
+      HdrMode::Always => write!(f, "Always (gain {})", self.gain),
+      HdrMode::Off => write!(f, "Off")
+    }
+  }
+}
+
+/// How light wallpapers are dimmed for dark mode, when they have no dark
+/// counterpart of their own.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct DarkVariant {
+  pub enabled: bool,
+  /// The exposure change in stops, applied in linear light. `-1.0` halves
+  /// the brightness.
+  pub exposure: f32,
+  /// How much of the color saturation is kept, between 0 and 1.
+  pub saturation: f32,
+  /// Images whose average brightness, between 0 and 1, is below this are
+  /// dark enough already and shown as they are.
+  pub threshold: f32
+}
+
+impl Default for DarkVariant {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      exposure: -1.0,
+      saturation: 0.85,
+      threshold: 0.35
+    }
+  }
+}
+
+impl Display for DarkVariant {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if !self.enabled {
+      return write!(f, "Off");
+    }
+    write!(
+      f,
+      "{} EV, {}% saturation, above {}% brightness",
+      self.exposure,
+      (self.saturation * 100.0).round(),
+      (self.threshold * 100.0).round()
+    )
+  }
+}
+
+/// How a wallpaper is fitted to the desktop on Windows.
+#[derive(
+  Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+  /// Scale to cover the monitor, cropping the overflow.
+  #[default]
+  Fill,
+  /// Scale to fit inside the monitor, leaving bars.
+  Fit,
+  /// Scale to the monitor's size, ignoring the aspect ratio.
+  Stretch,
+  /// Repeat the image at its own size.
+  Tile,
+  /// Show the image at its own size in the middle.
+  Center,
+  /// Stretch one image across all monitors.
+  Span
+}
+
+impl Style {
+  /// The `WallpaperStyle` and `TileWallpaper` registry values.
+  pub fn registry_values(self) -> (&'static str, &'static str) {
+    match self {
+      Self::Center => ("0", "0"),
+      Self::Tile => ("0", "1"),
+      Self::Stretch => ("2", "0"),
+      Self::Fit => ("6", "0"),
+      Self::Fill => ("10", "0"),
+      Self::Span => ("22", "0")
+    }
+  }
+}
+
+impl Display for Style {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Fill => write!(f, "Fill"),
+      Self::Fit => write!(f, "Fit"),
+      Self::Stretch => write!(f, "Stretch"),
+      Self::Tile => write!(f, "Tile"),
+      Self::Center => write!(f, "Center"),
+      Self::Span => write!(f, "Span")
+    }
+  }
+}
+
+/// Settings for the Windows backend that applies wallpapers through
+/// `SystemParametersInfo`. They are written on every change, so whatever was
+/// last picked in Settings doesn't apply.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Windows {
+  pub style: Style,
+  /// The quality Windows re-encodes JPEG wallpapers at, from 60 to 100. Its
+  /// own default of 85 visibly blurs fine detail.
+  pub jpeg_quality: u8
+}
+
+impl Default for Windows {
+  fn default() -> Self {
+    Self {
+      style: Style::Fill,
+      jpeg_quality: 100
+    }
+  }
+}
+
+impl Display for Windows {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "{} (JPEG quality {})", self.style, self.jpeg_quality)
+  }
+}
+
+/// The method used to set wallpapers on plain X11 window managers.
+#[derive(
+  Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum X11Tool {
+  /// Use `xwallpaper` or `feh` if installed, otherwise draw the root window.
+  #[default]
+  Auto,
+  /// Draw the image onto the root window directly.
+  Root,
+  /// Shell out to `feh --bg-fill`.
+  Feh,
+  /// Shell out to `xwallpaper --output`.
+  Xwallpaper
+}
+
+impl Display for X11Tool {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Auto => write!(f, "Auto"),
+      Self::Root => write!(f, "Root Window"),
+      Self::Feh => write!(f, "feh"),
+      Self::Xwallpaper => write!(f, "xwallpaper")
+    }
+  }
+}
+
+/// A wallpaper change animation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Transition {
+  /// The transition type. Example: "simple", "fade", "wipe", "grow".
+  pub kind: String,
+  /// The duration in seconds.
+  pub duration: f32,
+  /// The frame rate of the animation.
+  pub fps: u32
+}
+
+impl Default for Transition {
+  fn default() -> Self {
+    Self {
+      kind: "simple".into(),
+      duration: 1.0,
+      fps: 60
+    }
+  }
+}
+
+impl Display for Config {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let transition = &self.transition;
+    printf!(
+      f,
+      "Transition",
+      format!(
+        "{} ({}s at {} fps)",
+        transition.kind, transition.duration, transition.fps
+      )
+    )?;
+    printf!(f, "Continuity", self.continuity)?;
+    printf!(f, "X11 Method", self.x11)?;
+    printf!(f, "Windows Style", self.windows)?;
+    printf!(f, "HDR Compensation", self.hdr)?;
+    printf!(f, "Dark Variants", self.dark_variant)?;
+    printf!(f, "Image Limits", self.limits)?;
+    printf!(f, "Thumbnails", self.thumbnails)?;
+    printf!(f, "Do Not Disturb", self.do_not_disturb)
+  }
+}
@@ -0,0 +1,5 @@
+mod default;
+pub use default::{
+  Config, DarkVariant, DoNotDisturb, Hdr, HdrMode, Limits, Style, Thumbnails,
+  Transition, Windows, X11Tool
+};
@@ -17,3 +17,15 @@ pub const TIMESTAMP_SIZE: usize = 5;
 /// Identifies the end of the timestamp definition, and will always be preceded
 /// by the timestamp value
 pub const TIMESTAMP_SUFFIX_BYTES: [u8; 3] = [0x2A, 0x2B, 0x0E];
+/// Precedes the hour of a time block, and is left out with it when the hour
+/// is zero
+pub const TIME_HOUR_PREFIX: u8 = 0x0E;
+/// Precedes the minutes of a time block, and is left out with them when the
+/// minutes are zero
+pub const TIME_MINUTE_PREFIX: u8 = 0x2E;
+/// Identifies the end of a time block
+pub const TIME_SUFFIX: u8 = 0x00;
+
+/// File extensions (lowercase, without dot) recognized as wallpaper images
+pub const IMAGE_EXTENSIONS: [&str; 7] =
+  ["jpg", "jpeg", "png", "webp", "bmp", "gif", "tiff"];
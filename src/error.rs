@@ -1,23 +1,59 @@
 use crate::utils::parse;
-use std::io;
+use std::{io, path::PathBuf, time::Duration};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
   #[error("API error: {0}")]
   API(String),
 
+  /// A source refused the credentials, or asked for some.
+  #[error("Unauthorized (status {status}): {body}")]
+  Unauthorized { status: u16, body: String },
+
+  /// A source has nothing at the address asked for.
+  #[error("Not found (status {status}): {body}")]
+  NotFound { status: u16, body: String },
+
+  /// A source rejected the parameters of a request.
+  #[error("Invalid request (status {status}): {body}")]
+  InvalidParams { status: u16, body: String },
+
+  /// A source failed to answer a request it accepted.
+  #[error("Server error (status {status}): {body}")]
+  ServerError { status: u16, body: String },
+
   #[error("Configuration error: {0}")]
   Config(String),
 
   #[error("IO error: {0}")]
   IO(#[from] io::Error),
 
+  #[cfg(feature = "online-sources")]
   #[error("Network error: {0}")]
-  Network(#[from] reqwest::Error),
+  Network(reqwest::Error),
+
+  /// A network operation ran past its configured timeout.
+  #[error("Timed out: {0}")]
+  Timeout(String),
+
+  /// A source kept refusing requests for being called too often, asking to
+  /// wait `retry_after` before the next one, if it said.
+  #[error("Rate limited by {provider}{}", wait_hint(.retry_after))]
+  RateLimited {
+    provider: String,
+    retry_after: Option<Duration>
+  },
+
+  /// An image is missing from its source and from every mirror.
+  #[error("'{0}' is gone from its source and every mirror")]
+  Gone(String),
 
   #[error("Image processing error: {0}")]
   Image(String),
 
+  #[error("Image '{}' rejected: {reason}", path.display())]
+  ImageRejected { path: PathBuf, reason: String },
+
   #[error("Monitor detection error: {0}")]
   Monitor(#[from] crate::config::monitor::Error),
 
@@ -27,6 +63,189 @@ pub enum Error {
   #[error("Color mode error: {0}")]
   ColorMode(String),
 
+  #[error("Wallpaper error: {0}")]
+  Wallpaper(String),
+
   #[error("Parse error: {0}")]
-  Parse(#[from] parse::Error)
+  Parse(#[from] parse::Error),
+
+  /// Something was held back to be tried later, such as a notification
+  /// while the desktop is set not to disturb.
+  #[error("Deferred: {0}")]
+  Deferred(String),
+
+  /// The command had nothing to act on, such as a search without results.
+  #[error("{0}")]
+  NothingToDo(String)
+}
+
+/// The exit codes `wallter` returns, so scripts can tell failures apart.
+pub mod exit_code {
+  /// The command succeeded.
+  pub const SUCCESS: u8 = 0;
+  /// Any failure without a more specific code.
+  pub const FAILURE: u8 = 1;
+  /// The command line was invalid.
+  pub const USAGE: u8 = 2;
+  /// There was nothing to do, such as a search without results.
+  pub const NOTHING_TO_DO: u8 = 3;
+  /// The configuration, a setting or the parameters of a request are
+  /// invalid.
+  pub const CONFIG: u8 = 4;
+  /// A source could not be reached or refused the request.
+  pub const NETWORK: u8 = 5;
+  /// Access was denied, a source refused the credentials, or the system
+  /// refused a change to its settings, such as a registry write.
+  pub const PERMISSION: u8 = 6;
+  /// An image could not be read or was rejected.
+  pub const IMAGE: u8 = 7;
+}
+
+/// Describes how long a source asked to wait, if it said.
+fn wait_hint(retry_after: &Option<Duration>) -> String {
+  match retry_after {
+    Some(wait) => format!("; retry after {}s", wait.as_secs()),
+    None => String::new()
+  }
+}
+
+impl Error {
+  /// Sorts a failed response from the source called `provider` by its HTTP
+  /// `status`, keeping the status and `body` for callers to act on.
+  pub fn from_status(
+    provider: &str,
+    status: u16,
+    body: impl Into<String>
+  ) -> Self {
+    let body = body.into();
+    match status {
+      401 | 403 => Self::Unauthorized { status, body },
+      404 | 410 => Self::NotFound { status, body },
+      429 => Self::RateLimited {
+        provider: provider.to_string(),
+        retry_after: None
+      },
+      400 | 422 => Self::InvalidParams { status, body },
+      500..=599 => Self::ServerError { status, body },
+      _ =>
+        Self::API(format!("{provider} answered with status {status}: {body}")),
+    }
+  }
+
+  /// Returns the HTTP status of a failed response, where the error came from
+  /// one.
+  pub fn status(&self) -> Option<u16> {
+    match self {
+      Self::Unauthorized { status, .. }
+      | Self::NotFound { status, .. }
+      | Self::InvalidParams { status, .. }
+      | Self::ServerError { status, .. } => Some(*status),
+      Self::RateLimited { .. } => Some(429),
+      _ => None
+    }
+  }
+
+  /// Whether repeating what failed cannot succeed, such as a request refused
+  /// for its credentials or parameters, or an image over the size limits.
+  pub fn is_permanent(&self) -> bool {
+    matches!(
+      self,
+      Self::Unauthorized { .. }
+        | Self::NotFound { .. }
+        | Self::InvalidParams { .. }
+        | Self::Gone(_)
+        | Self::ImageRejected { .. }
+    )
+  }
+
+  /// Returns the process exit code for this error, see [exit_code].
+  pub fn exit_code(&self) -> u8 {
+    match self {
+      Self::NothingToDo(_) => exit_code::NOTHING_TO_DO,
+      Self::Config(_)
+      | Self::Settings(_)
+      | Self::Parse(_)
+      | Self::InvalidParams { .. } => exit_code::CONFIG,
+      Self::API(_)
+      | Self::NotFound { .. }
+      | Self::ServerError { .. }
+      | Self::Gone(_)
+      | Self::Timeout(_)
+      | Self::RateLimited { .. } => exit_code::NETWORK,
+      Self::Unauthorized { .. } => exit_code::PERMISSION,
+      #[cfg(feature = "online-sources")]
+      Self::Network(_) => exit_code::NETWORK,
+      Self::IO(e) if e.kind() == io::ErrorKind::PermissionDenied =>
+        exit_code::PERMISSION,
+      Self::ColorMode(_) => exit_code::PERMISSION,
+      Self::Image(_) | Self::ImageRejected { .. } => exit_code::IMAGE,
+      Self::IO(_)
+      | Self::Monitor(_)
+      | Self::Wallpaper(_)
+      | Self::Deferred(_) => exit_code::FAILURE
+    }
+  }
+}
+
+/// Sorts network errors, so that timeouts can be told apart from servers
+/// refusing a request.
+#[cfg(feature = "online-sources")]
+impl From<reqwest::Error> for Error {
+  fn from(error: reqwest::Error) -> Self {
+    if error.is_timeout() {
+      Self::Timeout(error.to_string())
+    } else {
+      Self::Network(error)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_exit_code() {
+    let denied = io::Error::new(io::ErrorKind::PermissionDenied, "registry");
+    assert_eq!(Error::IO(denied).exit_code(), exit_code::PERMISSION);
+    let missing = io::Error::new(io::ErrorKind::NotFound, "file");
+    assert_eq!(Error::IO(missing).exit_code(), exit_code::FAILURE);
+    assert_eq!(
+      Error::NothingToDo("no results".into()).exit_code(),
+      exit_code::NOTHING_TO_DO
+    );
+    assert_eq!(Error::Settings("x".into()).exit_code(), exit_code::CONFIG);
+  }
+
+  #[test]
+  fn test_from_status() {
+    let error = Error::from_status("wallhaven", 401, "Unauthenticated");
+    assert!(matches!(error, Error::Unauthorized { status: 401, .. }));
+    assert_eq!(error.exit_code(), exit_code::PERMISSION);
+    assert!(matches!(
+      Error::from_status("wallhaven", 404, ""),
+      Error::NotFound { .. }
+    ));
+    assert!(matches!(
+      Error::from_status("pexels", 422, "bad page"),
+      Error::InvalidParams { .. }
+    ));
+    let error = Error::from_status("reddit", 503, "maintenance");
+    assert_eq!(error.status(), Some(503));
+    assert_eq!(error.to_string(), "Server error (status 503): maintenance");
+    assert!(!error.is_permanent());
+    assert!(matches!(
+      Error::from_status("reddit", 418, "teapot"),
+      Error::API(_)
+    ));
+
+    let limited = Error::RateLimited {
+      provider: "wallhaven".into(),
+      retry_after: Some(Duration::from_secs(30))
+    };
+    assert_eq!(
+      limited.to_string(),
+      "Rate limited by wallhaven; retry after 30s"
+    );
+  }
 }
@@ -0,0 +1,267 @@
+use crate::{
+  Config, Error, Result,
+  api::wallhaven::{self, Api as Wallhaven},
+  config::Path,
+  simulate::{self, Action},
+  utils::{files, limits, store},
+  wallpaper::{
+    self, Current,
+    naming::{self, Details}
+  }
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  fs::{copy, create_dir_all, remove_file},
+  path::{self, PathBuf}
+};
+
+/// The name of the favorites index within the favorites directory.
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// A wallpaper in the favorites directory and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favorite {
+  /// The image, within the favorites directory.
+  pub file: PathBuf,
+  /// Where the image came from: `wallhaven`, `url` or `local`.
+  pub source: String,
+  pub wallhaven_id: Option<String>,
+  /// The original page or download URL.
+  pub url: Option<String>,
+  pub added_at: DateTime<Local>
+}
+
+impl Favorite {
+  /// Returns the file name, which identifies the favorite.
+  pub fn name(&self) -> String {
+    self
+      .file
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default()
+  }
+
+  /// Checks whether `key` names this favorite by file name, file stem or
+  /// Wallhaven id.
+  pub fn matches(&self, key: &str) -> bool {
+    self.name() == key
+      || self.file.file_stem().is_some_and(|stem| stem == key)
+      || self.wallhaven_id.as_deref() == Some(key)
+  }
+}
+
+/// The index of favorite wallpapers, kept next to the images.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favorites {
+  pub items: Vec<Favorite>
+}
+
+impl Favorites {
+  /// Returns the path of the favorites index.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.favorites_dir.join(FAVORITES_FILE)
+  }
+
+  /// Loads the favorites index.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the favorites index.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns the favorite matching `key`, see [Favorite::matches].
+  pub fn get(&self, key: &str) -> Option<&Favorite> {
+    self.items.iter().find(|favorite| favorite.matches(key))
+  }
+
+  /// Lists the images in the favorites directory, including any that were
+  /// placed there by hand.
+  pub fn images(path_config: &Path) -> Result<Vec<PathBuf>> {
+    files::list_images(&path_config.favorites_dir, false)
+  }
+}
+
+impl Display for Favorites {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.items.is_empty() {
+      return printf!(f, "Favorites", "[None]");
+    }
+    for favorite in &self.items {
+      let origin = favorite
+        .url
+        .clone()
+        .unwrap_or_else(|| favorite.source.clone());
+      printf!(f, &favorite.name(), origin)?;
+    }
+    Ok(())
+  }
+}
+
+/// Adds a favorite and returns it.
+///
+/// `target` is `current` for the wallpaper shown on `monitor` (the primary
+/// monitor by default), an image file, an image URL or a Wallhaven id.
+/// Images are copied or downloaded into the favorites directory.
+pub async fn add(
+  config: &Config,
+  target: &str,
+  monitor: Option<&str>
+) -> Result<Favorite> {
+  let dir = &config.path.favorites_dir;
+  if !simulate::is_dry_run() {
+    create_dir_all(dir)?;
+  }
+
+  let favorite = if target.starts_with("http://")
+    || target.starts_with("https://")
+  {
+    let file = download(config, target, None).await?;
+    Favorite {
+      wallhaven_id: wallhaven::id_from_path(&file),
+      file,
+      source: "url".into(),
+      url: Some(target.into()),
+      added_at: Local::now()
+    }
+  } else {
+    let image = if target == "current" {
+      let monitor = wallpaper::resolve_monitor(config, monitor)?;
+      Current::load(&config.path)?
+        .get(&monitor.name)
+        .map(|applied| applied.image.clone())
+        .ok_or_else(|| {
+          Error::Settings(format!(
+            "No wallpaper is recorded for monitor '{}'",
+            monitor.name
+          ))
+        })?
+    } else {
+      PathBuf::from(target)
+    };
+
+    if image.is_file() {
+      let file = dir.join(image.file_name().unwrap_or_default());
+      if path::absolute(&image)? != path::absolute(&file)?
+        && !simulate::dry_run(Action::CopyFile {
+          from: image.clone(),
+          to: file.clone()
+        })?
+      {
+        copy(&image, &file)?;
+      }
+      let id = wallhaven::id_from_path(&file);
+      Favorite {
+        file,
+        source: if id.is_some() { "wallhaven" } else { "local" }.into(),
+        url: id.as_deref().map(wallhaven::page_url),
+        wallhaven_id: id,
+        added_at: Local::now()
+      }
+    } else if wallhaven::is_id(target) {
+      let api_key = config
+        .source
+        .find("wallhaven")
+        .and_then(|source| source.api_key());
+      let details = Wallhaven::new(api_key)
+        .get_wallpaper_details(target)
+        .await?;
+      let file = download(config, &details.path, Some(&details.id)).await?;
+      Favorite {
+        file,
+        source: "wallhaven".into(),
+        wallhaven_id: Some(details.id),
+        url: Some(details.url),
+        added_at: Local::now()
+      }
+    } else {
+      return Err(Error::Settings(format!(
+        "'{target}' is neither 'current', an image file, a URL nor a Wallhaven id"
+      )));
+    }
+  };
+
+  let mut favorites = Favorites::load(&config.path)?;
+  favorites.items.retain(|item| item.file != favorite.file);
+  favorites.items.push(favorite.clone());
+  favorites.save(&config.path)?;
+  Ok(favorite)
+}
+
+/// Removes the favorite matching `key` and deletes its image.
+pub fn remove(config: &Config, key: &str) -> Result<Favorite> {
+  let mut favorites = Favorites::load(&config.path)?;
+  let index = favorites
+    .items
+    .iter()
+    .position(|favorite| favorite.matches(key))
+    .ok_or_else(|| Error::Settings(format!("'{key}' is not a favorite")))?;
+  let favorite = favorites.items.remove(index);
+  if favorite.file.is_file()
+    && !simulate::dry_run(Action::RemoveFile {
+      path: favorite.file.clone()
+    })?
+  {
+    remove_file(&favorite.file)?;
+  }
+  favorites.save(&config.path)?;
+  Ok(favorite)
+}
+
+/// Downloads `url` into the favorites directory and checks it against the
+/// image limits.
+async fn download(
+  config: &Config,
+  url: &str,
+  id: Option<&str>
+) -> Result<PathBuf> {
+  let known = Details {
+    source: id.map(|_| "wallhaven".to_string()),
+    id: id.map(str::to_string),
+    ..Details::default()
+  };
+  let name = match (naming::name(&config.path, url, known), id) {
+    (Err(Error::Wallpaper(_)), Some(id)) => format!("wallhaven-{id}"),
+    (name, _) => name?
+  };
+  let path = config.path.favorites_dir.join(name);
+  if simulate::dry_run(Action::Download {
+    url: url.to_string(),
+    path: path.clone()
+  })? {
+    return Ok(path);
+  }
+  let path = wallpaper::library::download(&config.path, url, &path).await?;
+  if let Err(e) = limits::check(&path, &config.wallpaper.limits) {
+    remove_file(&path)?;
+    return Err(e);
+  }
+  Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::Path;
+
+  #[test]
+  fn test_favorite_matches() {
+    let favorite = Favorite {
+      file: PathBuf::from("/favorites/wallhaven-94x38z.jpg"),
+      source: "wallhaven".into(),
+      wallhaven_id: wallhaven::id_from_path(Path::new("wallhaven-94x38z.jpg")),
+      url: None,
+      added_at: Local::now()
+    };
+    assert_eq!(favorite.wallhaven_id.as_deref(), Some("94x38z"));
+    assert!(favorite.matches("94x38z"));
+    assert!(favorite.matches("wallhaven-94x38z"));
+    assert!(favorite.matches("wallhaven-94x38z.jpg"));
+    assert!(!favorite.matches("94x38"));
+    assert_eq!(wallhaven::id_from_path(Path::new("forest.png")), None);
+  }
+}
@@ -0,0 +1,4 @@
+//! Wallpapers the user wants to keep, stored in the favorites directory.
+
+mod default;
+pub use default::*;
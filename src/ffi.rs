@@ -0,0 +1,239 @@
+//! A C API for shells, status bars and plugins that embed wallter.
+//!
+//! Every function that can fail returns one of the [exit_code]s, `0` on
+//! success, and keeps the error message for [wallter_last_error]. Strings
+//! handed out by wallter are owned by the caller and must be released with
+//! [wallter_string_free]. Each call loads the configuration afresh, so
+//! changes made by the CLI in the meantime are picked up.
+//!
+//! The header, `include/wallter.h`, is generated by cbindgen. Building with
+//! the `ffi` feature writes a fresh copy to the build's `OUT_DIR`, leaving
+//! the source tree alone; after changing the API, update the checked-in copy
+//! with:
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/wallter.h
+//! ```
+//!
+//! To build a shared or static library:
+//!
+//! ```sh
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+
+#![allow(unsafe_code)]
+
+use crate::{
+  Config, Error, Result,
+  config::{self, ColorMode, conflicts::Subsystem},
+  exit_code,
+  wallpaper::{self, NowShowing, Source}
+};
+use std::{
+  cell::RefCell,
+  ffi::{CStr, CString, c_char, c_int},
+  panic::{self, AssertUnwindSafe},
+  path::PathBuf,
+  ptr
+};
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Sets the system color mode to `"light"`, `"dark"` or `"auto"` and saves
+/// it to the configuration, as `wallter color set` does.
+///
+/// # Safety
+///
+/// `mode` must be a valid, NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wallter_set_mode(mode: *const c_char) -> c_int {
+  report(|| {
+    let mode = match unsafe { to_str(mode) }? {
+      "light" => ColorMode::Light,
+      "dark" => ColorMode::Dark,
+      "auto" => ColorMode::Auto,
+      other => {
+        return Err(Error::Settings(format!(
+          "'{other}' is not a color mode; use light, dark or auto"
+        )));
+      }
+    };
+    let config = config::init()?;
+    let mut saved = Config::load(&config.path)?;
+    saved.color.mode = mode;
+    saved.save(&config.path)?;
+    if config.conflicts.yield_to(Subsystem::ColorMode).is_none() {
+      config
+        .color
+        .clone()
+        .with_mode(mode)
+        .apply_mode(&config.monitors)?;
+    }
+    Ok(())
+  })
+}
+
+/// Turns the system night light on if it is off and off if it is on. When
+/// `enabled` isn't null, the new state is written to it.
+///
+/// Windows Night Light, GNOME Night Light and KDE Night Color are supported;
+/// elsewhere this fails with a permission error.
+///
+/// # Safety
+///
+/// `enabled` must be null or point to a writable `bool`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wallter_toggle_nightlight(
+  enabled: *mut bool
+) -> c_int {
+  report(|| {
+    let state = config::color::night::toggle_system()?;
+    if !enabled.is_null() {
+      unsafe { *enabled = state };
+    }
+    Ok(())
+  })
+}
+
+/// Sets the wallpaper from a local image, on the monitor named `monitor` or
+/// on every monitor when `monitor` is null.
+///
+/// # Safety
+///
+/// `path` must be a valid, NUL-terminated string, and `monitor` null or one.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wallter_set_wallpaper_path(
+  path: *const c_char,
+  monitor: *const c_char
+) -> c_int {
+  report(|| {
+    let source = Source::File(PathBuf::from(unsafe { to_str(path) }?));
+    let monitor = if monitor.is_null() {
+      None
+    } else {
+      Some(unsafe { to_str(monitor) }?)
+    };
+    let config = config::init()?;
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()?
+      .block_on(wallpaper::set(&config, &source, monitor))?;
+    Ok(())
+  })
+}
+
+/// Returns what is on screen as JSON, the same as `wallter status --json`,
+/// or null on failure. Release the string with [wallter_string_free].
+#[unsafe(no_mangle)]
+pub extern "C" fn wallter_get_status_json() -> *mut c_char {
+  let mut json = ptr::null_mut();
+  report(|| {
+    let config = config::init()?;
+    let now_showing = NowShowing::load(&config.path)?;
+    let text = serde_json::to_string(&now_showing)
+      .map_err(|e| Error::Config(e.to_string()))?;
+    json = to_c_string(text).into_raw();
+    Ok(())
+  });
+  json
+}
+
+/// Returns the message of the last error on this thread, or null if there
+/// was none. Release the string with [wallter_string_free].
+#[unsafe(no_mangle)]
+pub extern "C" fn wallter_last_error() -> *mut c_char {
+  LAST_ERROR.with(|last| {
+    last
+      .borrow()
+      .as_ref()
+      .map_or(ptr::null_mut(), |message| message.clone().into_raw())
+  })
+}
+
+/// Releases a string returned by wallter. Null is ignored.
+///
+/// # Safety
+///
+/// `string` must be null or have come from wallter, and not be freed twice.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn wallter_string_free(string: *mut c_char) {
+  if !string.is_null() {
+    drop(unsafe { CString::from_raw(string) });
+  }
+}
+
+/// Runs `f`, recording its error for [wallter_last_error], and returns the
+/// matching exit code.
+///
+/// A panic in `f` is caught and reported as a failure, since unwinding out of
+/// an `extern "C"` function aborts the host process.
+fn report(f: impl FnOnce() -> Result<()>) -> c_int {
+  let (code, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+    Ok(Ok(())) => (exit_code::SUCCESS, None),
+    Ok(Err(e)) => (e.exit_code(), Some(to_c_string(e.to_string()))),
+    Err(payload) => {
+      let reason = payload
+        .downcast_ref::<&str>()
+        .map(|reason| reason.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "an unknown error".to_string());
+      let message = format!("wallter panicked: {reason}");
+      (exit_code::FAILURE, Some(to_c_string(message)))
+    }
+  };
+  LAST_ERROR.with(|last| *last.borrow_mut() = message);
+  c_int::from(code)
+}
+
+/// Borrows a C string as UTF-8.
+///
+/// # Safety
+///
+/// `string` must be null or a valid, NUL-terminated string that outlives the
+/// returned reference.
+unsafe fn to_str<'a>(string: *const c_char) -> Result<&'a str> {
+  if string.is_null() {
+    return Err(Error::Settings("A required string was null".to_string()));
+  }
+  unsafe { CStr::from_ptr(string) }
+    .to_str()
+    .map_err(|e| Error::Settings(format!("A string was not UTF-8: {e}")))
+}
+
+/// Converts to a C string, dropping any interior NUL bytes.
+fn to_c_string(text: String) -> CString {
+  CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_errors_are_reported() {
+    let mode = CString::new("sepia").unwrap();
+    let code = unsafe { wallter_set_mode(mode.as_ptr()) };
+    assert_eq!(code, c_int::from(exit_code::CONFIG));
+
+    let message = wallter_last_error();
+    assert!(!message.is_null());
+    let text = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+    assert!(text.contains("'sepia' is not a color mode"));
+    unsafe { wallter_string_free(message) };
+
+    let code = unsafe { wallter_set_mode(ptr::null()) };
+    assert_eq!(code, c_int::from(exit_code::CONFIG));
+  }
+
+  #[test]
+  fn test_panics_are_reported() {
+    let code = report(|| panic!("out of cheese"));
+    assert_eq!(code, c_int::from(exit_code::FAILURE));
+    let message = wallter_last_error();
+    let text = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+    assert_eq!(text, "wallter panicked: out of cheese");
+    unsafe { wallter_string_free(message) };
+  }
+}
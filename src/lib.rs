@@ -1,14 +1,40 @@
 #[macro_use]
 pub mod utils;
 
-mod api;
+pub mod api;
+#[cfg(feature = "online-sources")]
 pub use api::Api;
 
 pub mod consts;
 
 mod error;
-pub use error::Error;
+pub use error::{Error, exit_code};
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub mod config;
 pub use config::Config;
+
+#[cfg(feature = "online-sources")]
+pub mod favorites;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "online-sources")]
+pub mod search;
+
+#[cfg(feature = "online-sources")]
+pub mod scheduler;
+
+pub mod simulate;
+
+#[cfg(feature = "online-sources")]
+pub mod slideshow;
+
+pub mod stats;
+
+#[cfg(feature = "online-sources")]
+pub mod support;
+
+#[cfg(feature = "online-sources")]
+pub mod wallpaper;
@@ -1,13 +1,43 @@
-use wallter::{Error, Result};
+mod cli;
 
-fn main() -> Result<()> {
-  println!("Welcome to {}!", env!("CARGO_PKG_NAME"));
+use std::process::ExitCode;
+use wallter::{Error, Result, simulate};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let cli = cli::parse_args();
+  match run(&cli).await {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      //{ Having nothing to do isn't a failure worth an error message }
+      if let Error::NothingToDo(message) = &e {
+        println!("{message}");
+      } else {
+        eprintln!("Error: {e}");
+      }
+      ExitCode::from(e.exit_code())
+    }
+  }
+}
+
+async fn run(cli: &cli::handler::Cli) -> Result<()> {
+  cli::logging::init(cli.level(), cli.log_file())?;
+  tracing::debug!("Welcome to {}!", env!("CARGO_PKG_NAME"));
+
+  //{ Journal platform actions instead of performing them, and in a dry run
+  //{ every other change as well }
+  if cli.simulate || cli.dry_run {
+    simulate::enable(
+      simulate::Journal::new()
+        .with_echo(true)
+        .with_dry_run(cli.dry_run)
+    );
+  }
 
   // nightlight::toggle()?;
   // nightlight::enable()
   // let config = wallter::config::Config::default();
-  let config = wallter::config::init()?;
-  println!("Config: {config}");
-
-  Ok(())
+  let mut config = wallter::config::init()?;
+  cli::monitor::reconcile(&mut config, cli.missing_monitors.as_ref())?;
+  cli::dispatch(cli, &config).await
 }
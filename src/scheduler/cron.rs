@@ -0,0 +1,217 @@
+//! Cron expressions with the five classic fields: minute, hour, day of the
+//! month, month and day of the week.
+//!
+//! Each field is `*`, a number, a range such as `1-5`, or a list of these
+//! separated by commas, optionally with a step such as `*/15` or `8-18/2`.
+//! Sunday is `0` or `7`. As in cron, when both the day of the month and the
+//! day of the week are restricted, a day matching either of them runs.
+
+use crate::{Error, Result};
+use chrono::{
+  DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike
+};
+use std::{
+  fmt::{self, Display, Formatter},
+  str::FromStr
+};
+
+/// How many days ahead a match is looked for, enough to reach a leap day.
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+  expression: String,
+  minutes: u64,
+  hours: u64,
+  days: u64,
+  months: u64,
+  weekdays: u64,
+  /// Whether the day of the month is `*`.
+  any_day: bool,
+  /// Whether the day of the week is `*`.
+  any_weekday: bool
+}
+
+impl Cron {
+  /// Parses a five-field cron expression, such as `0 7 * * 1-5`.
+  pub fn parse(expression: &str) -> Result<Self> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let [minutes, hours, days, months, weekdays] = fields[..] else {
+      return Err(Error::Settings(format!(
+        "Invalid cron expression '{expression}': expected 5 fields, found {}",
+        fields.len()
+      )));
+    };
+    let field = |text: &str, min: u32, max: u32| {
+      parse_field(text, min, max).map_err(|e| {
+        Error::Settings(format!("Invalid cron expression '{expression}': {e}"))
+      })
+    };
+    let mut weekday_mask = field(weekdays, 0, 7)?;
+    //{ Sunday is both 0 and 7 }
+    if weekday_mask & (1 << 7) != 0 {
+      weekday_mask = (weekday_mask | 1) & !(1 << 7);
+    }
+    Ok(Self {
+      expression: fields.join(" "),
+      minutes: field(minutes, 0, 59)?,
+      hours: field(hours, 0, 23)?,
+      days: field(days, 1, 31)?,
+      months: field(months, 1, 12)?,
+      weekdays: weekday_mask,
+      any_day: days == "*",
+      any_weekday: weekdays == "*"
+    })
+  }
+
+  /// Returns the first time after `after` that matches, to the minute.
+  /// Times skipped by a change to daylight saving time are passed over.
+  pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    let start = after.naive_local() + Duration::minutes(1);
+    let start_day = start.date();
+    for offset in 0..SEARCH_DAYS {
+      let day = start_day + Duration::days(offset);
+      if !self.matches_day(day) {
+        continue;
+      }
+      let first_day = offset == 0;
+      for hour in 0..24 {
+        if !has(self.hours, hour) || (first_day && hour < start.hour()) {
+          continue;
+        }
+        for minute in 0..60 {
+          let before_start =
+            first_day && hour == start.hour() && minute < start.minute();
+          if !has(self.minutes, minute) || before_start {
+            continue;
+          }
+          let time = day.and_hms_opt(hour, minute, 0)?;
+          if let Some(time) = Local.from_local_datetime(&time).earliest() {
+            return Some(time);
+          }
+        }
+      }
+    }
+    None
+  }
+
+  fn matches_day(&self, day: NaiveDate) -> bool {
+    if !has(self.months, day.month()) {
+      return false;
+    }
+    let by_day = has(self.days, day.day());
+    let by_weekday = has(self.weekdays, day.weekday().num_days_from_sunday());
+    match (self.any_day, self.any_weekday) {
+      (false, false) => by_day || by_weekday,
+      _ => by_day && by_weekday
+    }
+  }
+}
+
+impl FromStr for Cron {
+  type Err = Error;
+
+  fn from_str(expression: &str) -> Result<Self> {
+    Self::parse(expression)
+  }
+}
+
+impl Display for Cron {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.expression)
+  }
+}
+
+/// Whether `value` is set in `mask`.
+fn has(mask: u64, value: u32) -> bool {
+  mask & (1 << value) != 0
+}
+
+/// Parses one field into a mask of the values between `min` and `max` it
+/// matches.
+fn parse_field(
+  text: &str,
+  min: u32,
+  max: u32
+) -> std::result::Result<u64, String> {
+  let number = |value: &str| {
+    value
+      .parse::<u32>()
+      .ok()
+      .filter(|value| (min..=max).contains(value))
+      .ok_or_else(|| format!("'{value}' is not between {min} and {max}"))
+  };
+  let mut mask = 0;
+  for part in text.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => (
+        range,
+        step
+          .parse::<u32>()
+          .ok()
+          .filter(|step| *step > 0)
+          .ok_or_else(|| format!("'{step}' is not a valid step"))?
+      ),
+      None => (part, 1)
+    };
+    let (first, last) = match range.split_once('-') {
+      _ if range == "*" => (min, max),
+      Some((first, last)) => (number(first)?, number(last)?),
+      //{ A single value with a step runs on to the end, as in `5/15` }
+      None if step > 1 => (number(range)?, max),
+      None => {
+        let value = number(range)?;
+        (value, value)
+      }
+    };
+    if first > last {
+      return Err(format!("'{range}' runs backwards"));
+    }
+    for value in (first..=last).step_by(step as usize) {
+      mask |= 1 << value;
+    }
+  }
+  Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    //{ January has no daylight saving changes anywhere in the north }
+    Local
+      .with_ymd_and_hms(2025, 1, day, hour, minute, 0)
+      .unwrap()
+  }
+
+  #[test]
+  fn test_parse() {
+    assert!(Cron::parse("*/15 8-18 * * 1-5").is_ok());
+    assert!(Cron::parse("0 7 * *").is_err());
+    assert!(Cron::parse("60 * * * *").is_err());
+    assert!(Cron::parse("0 9-5 * * *").is_err());
+    assert!(Cron::parse("*/0 * * * *").is_err());
+    assert_eq!(Cron::parse("0  7 * * 7").unwrap().to_string(), "0 7 * * 7");
+  }
+
+  #[test]
+  fn test_next_after() {
+    //{ 2025-01-03 is a Friday }
+    let weekdays = Cron::parse("30 7 * * 1-5").unwrap();
+    assert_eq!(weekdays.next_after(at(3, 6, 0)), Some(at(3, 7, 30)));
+    assert_eq!(weekdays.next_after(at(3, 7, 30)), Some(at(6, 7, 30)));
+
+    let quarter = Cron::parse("*/15 * * * *").unwrap();
+    assert_eq!(quarter.next_after(at(3, 6, 7)), Some(at(3, 6, 15)));
+
+    //{ Either the 1st of the month or a Sunday }
+    let either = Cron::parse("0 0 1 * 0").unwrap();
+    assert_eq!(either.next_after(at(2, 0, 0)), Some(at(5, 0, 0)));
+    assert_eq!(
+      Cron::parse("0 0 1 * 7").unwrap().next_after(at(2, 0, 0)),
+      Some(at(5, 0, 0))
+    );
+  }
+}
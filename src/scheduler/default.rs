@@ -0,0 +1,356 @@
+use super::Cron;
+use crate::{
+  Config, Error, Result,
+  config::{ColorMode, conflicts::Subsystem},
+  slideshow::{State, advance},
+  wallpaper::{
+    self,
+    journal::{self, with_trigger}
+  }
+};
+use chrono::{DateTime, Local, TimeDelta};
+use std::{
+  collections::BTreeSet,
+  fmt::{self, Display, Formatter},
+  time::Duration
+};
+use tokio::{
+  select,
+  sync::mpsc::{Receiver, Sender, channel},
+  time::sleep
+};
+
+/// How many events are held for a slow receiver by default.
+const DEFAULT_CAPACITY: usize = 32;
+
+/// The longest the scheduler sleeps before checking the clock again, so that
+/// jobs still run on time after the machine resumes or the clock changes.
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+  /// Every time the duration elapses, starting when the scheduler starts.
+  Every(Duration),
+  /// At the times matching a cron expression.
+  Cron(Cron)
+}
+
+impl Trigger {
+  /// Runs at the times matching the cron `expression`, such as `0 7 * * *`.
+  pub fn cron(expression: &str) -> Result<Self> {
+    Cron::parse(expression).map(Self::Cron)
+  }
+
+  /// Returns when the job next runs after `after`.
+  pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    match self {
+      Self::Every(interval) => TimeDelta::from_std(*interval)
+        .ok()
+        .map(|delta| after + delta),
+      Self::Cron(cron) => cron.next_after(after)
+    }
+  }
+}
+
+impl Display for Trigger {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Every(interval) => write!(f, "every {}s", interval.as_secs()),
+      Self::Cron(cron) => write!(f, "cron '{cron}'")
+    }
+  }
+}
+
+/// What a job does when it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Task {
+  /// Applies a color mode, bringing the configured apps and night light in
+  /// line, as `wallter color set` does without saving it.
+  ColorMode(ColorMode),
+  /// Switches the system between light and dark.
+  ToggleColorMode,
+  /// Advances the slideshow on the monitor matching the name or id, or on
+  /// every monitor. Skipped while rotation is snoozed.
+  Wallpaper(Option<String>),
+  /// Brings the configured apps and night light in line with the system
+  /// color mode, after it was changed by something else.
+  Sync,
+  /// Does nothing but send an event, for the consumer to act on.
+  Tick
+}
+
+impl Task {
+  /// Carries out the task.
+  async fn run(&self, config: &Config) -> Outcome {
+    let color_conflict = || {
+      config
+        .conflicts
+        .yield_to(Subsystem::ColorMode)
+        .map(|manager| Outcome::Skipped(format!("Left to {manager}")))
+    };
+    let outcome = match self {
+      Self::ColorMode(mode) => {
+        if let Some(skipped) = color_conflict() {
+          return skipped;
+        }
+        let color = config.color.clone().with_mode(*mode);
+        color
+          .apply_mode(&config.monitors)
+          .map(|()| format!("Color mode set to {mode}"))
+      }
+      Self::ToggleColorMode => {
+        if let Some(skipped) = color_conflict() {
+          return skipped;
+        }
+        ColorMode::toggle().and_then(|mode| {
+          config.color.follow(mode, &config.monitors)?;
+          Ok(format!("Color mode toggled to {mode}"))
+        })
+      }
+      Self::Wallpaper(monitor) => match State::load(&config.path) {
+        Ok(state) if state.is_snoozed() =>
+          return Outcome::Skipped("Rotation is snoozed".to_string()),
+        Ok(_) => rotate(config, monitor.as_deref()).await,
+        Err(e) => Err(e)
+      },
+      Self::Sync => {
+        let mode = ColorMode::get_current();
+        config
+          .color
+          .follow(mode, &config.monitors)
+          .map(|()| format!("Followed the system into {mode} mode"))
+      }
+      Self::Tick => Ok(String::new())
+    };
+    match outcome {
+      Ok(message) => Outcome::Done(message),
+      Err(e) => Outcome::Failed(e.to_string())
+    }
+  }
+}
+
+/// Advances the monitors matching `selector`, describing what each shows.
+async fn rotate(config: &Config, selector: Option<&str>) -> Result<String> {
+  let mut shown = Vec::new();
+  for monitor in wallpaper::resolve_monitors(config, selector)? {
+    let trigger = journal::Trigger::Schedule;
+    match with_trigger(trigger, advance(config, monitor)).await? {
+      Some(source) => shown.push(format!("{}: {source}", monitor.name)),
+      None => shown.push(format!("{}: nothing else to show", monitor.name))
+    }
+  }
+  Ok(shown.join("; "))
+}
+
+/// A named task and when it runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+  pub name: String,
+  pub trigger: Trigger,
+  pub task: Task
+}
+
+/// How a job went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+  /// The task was carried out, as described.
+  Done(String),
+  /// The task was not needed or not allowed, for the reason given.
+  Skipped(String),
+  /// The task failed with the error given.
+  Failed(String)
+}
+
+/// A job having run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+  /// The name of the job.
+  pub job: String,
+  pub at: DateTime<Local>,
+  pub outcome: Outcome,
+  /// When the job runs next, if ever.
+  pub next: Option<DateTime<Local>>
+}
+
+/// Sets up a [Scheduler].
+///
+/// ```no_run
+/// # async fn example(config: wallter::Config) -> wallter::Result<()> {
+/// use std::time::Duration;
+/// use wallter::{
+///   config::ColorMode,
+///   scheduler::{Builder, Task, Trigger}
+/// };
+///
+/// let (scheduler, mut events) = Builder::new()
+///   .job("morning", Trigger::cron("0 7 * * *")?, Task::ColorMode(ColorMode::Light))
+///   .job("evening", Trigger::cron("0 19 * * *")?, Task::ColorMode(ColorMode::Dark))
+///   .job("rotate", Trigger::Every(Duration::from_secs(1800)), Task::Wallpaper(None))
+///   .build(config)?;
+/// tokio::spawn(scheduler.run());
+/// while let Some(event) = events.recv().await {
+///   println!("{}: {:?}", event.job, event.outcome);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+  jobs: Vec<Job>,
+  capacity: usize
+}
+
+impl Default for Builder {
+  fn default() -> Self {
+    Self {
+      jobs: Vec::new(),
+      capacity: DEFAULT_CAPACITY
+    }
+  }
+}
+
+impl Builder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a job running `task` whenever `trigger` fires.
+  #[must_use]
+  pub fn job(mut self, name: &str, trigger: Trigger, task: Task) -> Self {
+    self.jobs.push(Job {
+      name: name.to_string(),
+      trigger,
+      task
+    });
+    self
+  }
+
+  /// Sets how many events are held for a receiver that falls behind. Once
+  /// it is full, the scheduler waits for the receiver before running more
+  /// jobs.
+  #[must_use]
+  pub fn capacity(mut self, capacity: usize) -> Self {
+    self.capacity = capacity;
+    self
+  }
+
+  /// Checks the jobs and returns the scheduler, which runs them with
+  /// `config`, along with the receiver of its events.
+  pub fn build(self, config: Config) -> Result<(Scheduler, Receiver<Event>)> {
+    if self.capacity == 0 {
+      return Err(Error::Settings(
+        "The scheduler needs room for at least one event".to_string()
+      ));
+    }
+    let mut names = BTreeSet::new();
+    for job in &self.jobs {
+      if !names.insert(job.name.as_str()) {
+        return Err(Error::Settings(format!(
+          "More than one scheduled job is named '{}'",
+          job.name
+        )));
+      }
+      if job.trigger == Trigger::Every(Duration::ZERO) {
+        return Err(Error::Settings(format!(
+          "The job '{}' needs an interval longer than zero",
+          job.name
+        )));
+      }
+    }
+
+    let (events, receiver) = channel(self.capacity);
+    let now = Local::now();
+    let jobs = self
+      .jobs
+      .into_iter()
+      .map(|job| {
+        let next = job.trigger.next_after(now);
+        (job, next)
+      })
+      .collect();
+    let scheduler = Scheduler {
+      config,
+      jobs,
+      events
+    };
+    Ok((scheduler, receiver))
+  }
+}
+
+/// Runs jobs on their triggers, independently of the slideshow daemon, and
+/// reports each run as an [Event].
+#[derive(Debug)]
+pub struct Scheduler {
+  config: Config,
+  /// The jobs, with when each runs next.
+  jobs: Vec<(Job, Option<DateTime<Local>>)>,
+  events: Sender<Event>
+}
+
+impl Scheduler {
+  /// Returns when the next job is due, if any job runs again.
+  pub fn next_due(&self) -> Option<DateTime<Local>> {
+    self.jobs.iter().filter_map(|(_, next)| *next).min()
+  }
+
+  /// Runs the jobs as they fall due until the event receiver is dropped or
+  /// no job runs again. Jobs due together run in the order they were added.
+  pub async fn run(mut self) -> Result<()> {
+    while let Some(due) = self.next_due() {
+      let wait = (due - Local::now()).to_std().unwrap_or_default();
+      if !wait.is_zero() {
+        select! {
+          _ = sleep(wait.min(MAX_SLEEP)) => continue,
+          _ = self.events.closed() => break
+        }
+      }
+
+      let now = Local::now();
+      for (job, next) in &mut self.jobs {
+        if !next.is_some_and(|at| at <= now) {
+          continue;
+        }
+        let outcome = job.task.run(&self.config).await;
+        //{ Missed runs are not made up, the job moves on from now }
+        *next = job.trigger.next_after(Local::now());
+        let event = Event {
+          job: job.name.clone(),
+          at: now,
+          outcome,
+          next: *next
+        };
+        if self.events.send(event).await.is_err() {
+          return Ok(());
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_builder() {
+    let every = |secs| Trigger::Every(Duration::from_secs(secs));
+    let (scheduler, _events) = Builder::new()
+      .job("tick", every(60), Task::Tick)
+      .job("night", Trigger::cron("0 19 * * *").unwrap(), Task::Sync)
+      .build(Config::default())
+      .unwrap();
+    let due = scheduler.next_due().unwrap();
+    assert!(due - Local::now() <= TimeDelta::seconds(60));
+
+    let twice = Builder::new().job("tick", every(60), Task::Tick).job(
+      "tick",
+      every(30),
+      Task::Tick
+    );
+    assert!(twice.build(Config::default()).is_err());
+    let zero = Builder::new().job("tick", every(0), Task::Tick);
+    assert!(zero.build(Config::default()).is_err());
+  }
+}
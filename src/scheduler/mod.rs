@@ -0,0 +1,12 @@
+//! A scheduling engine for embedding wallter in other programs.
+//!
+//! A [Builder] registers jobs, each a [Task] such as a color mode flip, a
+//! wallpaper change or a sync, run on an interval or at the times of a
+//! [Cron] expression. The [Scheduler] it builds runs them on its own,
+//! without the slideshow daemon, and reports every run on a channel.
+
+mod cron;
+pub use cron::Cron;
+
+mod default;
+pub use default::{Builder, Event, Job, Outcome, Scheduler, Task, Trigger};
@@ -0,0 +1,80 @@
+use crate::api::{pexels::Photo, reddit::Post, wallhaven::Wallpaper};
+use std::fmt::{self, Display, Formatter};
+
+/// A search result from any source, reduced to what is needed to score it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+  /// The name of the source that returned the candidate.
+  pub source: String,
+  /// The identifier of the image within its source.
+  pub id: String,
+  /// The direct URL of the full-size image, or its path for local sources.
+  pub url: String,
+  /// The URL of a small preview of the image, if the source has one.
+  pub thumbnail: Option<String>,
+  pub width: u32,
+  pub height: u32,
+  /// The dominant colors of the image as hex codes, if known.
+  pub colors: Vec<String>,
+  /// A source-specific popularity count, such as favorites.
+  pub popularity: u64
+}
+
+impl From<&Wallpaper> for Candidate {
+  fn from(wallpaper: &Wallpaper) -> Self {
+    Self {
+      source: "wallhaven".into(),
+      id: wallpaper.id.clone(),
+      url: wallpaper.path.clone(),
+      thumbnail: Some(wallpaper.thumbs.small.clone()),
+      width: wallpaper.dimension_x,
+      height: wallpaper.dimension_y,
+      colors: wallpaper.colors.clone(),
+      popularity: wallpaper.favorites.into()
+    }
+  }
+}
+
+impl From<&Photo> for Candidate {
+  fn from(photo: &Photo) -> Self {
+    Self {
+      source: "pexels".into(),
+      id: photo.id.to_string(),
+      url: photo.src.original.clone(),
+      thumbnail: Some(photo.src.tiny.clone()),
+      width: photo.width,
+      height: photo.height,
+      colors: photo.avg_color.iter().cloned().collect(),
+      //{ Pexels does not publish like counts }
+      popularity: 0
+    }
+  }
+}
+
+impl Candidate {
+  /// Reduces a Reddit post to a candidate, if it links straight to an image
+  /// of known size.
+  pub fn from_post(post: &Post) -> Option<Self> {
+    let (width, height) = post.image_size()?;
+    Some(Self {
+      source: "reddit".into(),
+      id: post.id.clone(),
+      url: post.url.clone(),
+      thumbnail: post.thumbnail_url().map(str::to_string),
+      width,
+      height,
+      colors: Vec::new(),
+      popularity: post.score.max(0) as u64
+    })
+  }
+}
+
+impl Display for Candidate {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}:{} ({}x{})",
+      self.source, self.id, self.width, self.height
+    )
+  }
+}
@@ -0,0 +1,99 @@
+//! Records how each source fared while a wallpaper was being looked for.
+//!
+//! Sources are tried in rank order until one has something suitable. The
+//! [Fallback] report keeps every attempt, so callers can tell which source
+//! served the wallpaper and why the ones before it did not.
+
+use super::Candidate;
+use std::fmt::{self, Display, Formatter};
+
+/// How a source fared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+  /// The request failed, with this error.
+  Failed(String),
+  /// The source answered, but had nothing suitable.
+  Empty,
+  /// The source provided the wallpaper.
+  Served
+}
+
+/// One source tried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+  pub source: String,
+  pub outcome: Outcome
+}
+
+impl Display for Attempt {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match &self.outcome {
+      Outcome::Failed(error) => write!(f, "{} failed ({error})", self.source),
+      Outcome::Empty => write!(f, "{} had nothing suitable", self.source),
+      Outcome::Served => write!(f, "{} served it", self.source)
+    }
+  }
+}
+
+/// The sources tried for a wallpaper, in order, and what was picked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fallback {
+  pub picked: Option<Candidate>,
+  pub attempts: Vec<Attempt>
+}
+
+impl Fallback {
+  /// Records how `source` fared.
+  pub fn record(&mut self, source: &str, outcome: Outcome) {
+    self.attempts.push(Attempt {
+      source: source.to_string(),
+      outcome
+    });
+  }
+
+  /// Returns the name of the source that served the wallpaper, if any.
+  pub fn served_by(&self) -> Option<&str> {
+    self
+      .attempts
+      .iter()
+      .find(|attempt| attempt.outcome == Outcome::Served)
+      .map(|attempt| attempt.source.as_str())
+  }
+
+  /// Returns the attempts that came to nothing.
+  pub fn passed_over(&self) -> impl Iterator<Item = &Attempt> {
+    self
+      .attempts
+      .iter()
+      .filter(|attempt| attempt.outcome != Outcome::Served)
+  }
+}
+
+impl Display for Fallback {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let attempts: Vec<String> =
+      self.attempts.iter().map(ToString::to_string).collect();
+    write!(f, "{}", attempts.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_served_by() {
+    let mut fallback = Fallback::default();
+    fallback.record("wallhaven", Outcome::Failed("timed out".into()));
+    fallback.record("pexels", Outcome::Empty);
+    assert_eq!(fallback.served_by(), None);
+
+    fallback.record("reddit", Outcome::Served);
+    assert_eq!(fallback.served_by(), Some("reddit"));
+    assert_eq!(fallback.passed_over().count(), 2);
+    assert_eq!(
+      fallback.to_string(),
+      "wallhaven failed (timed out), pexels had nothing suitable, reddit served it"
+    );
+  }
+}
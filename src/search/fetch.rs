@@ -0,0 +1,494 @@
+use super::{
+  Candidate, Fallback, Health, best, fallback::Outcome, local, safe,
+  score::is_muted
+};
+use crate::{
+  Config, Error, Result,
+  api::{
+    cache, http,
+    pexels::{self, Api as Pexels, Orientation, Size},
+    reddit::{Api as Reddit, ListingParams},
+    wallhaven::{Api as Wallhaven, Page, SearchParams, Sorting}
+  },
+  config::{
+    ColorMode, Monitor, search::Source, slideshow::Rule, wallpaper::Limits
+  },
+  slideshow::{RandomPage, RandomPages},
+  stats,
+  wallpaper::{Blocklist, Fingerprints, Library, fingerprints}
+};
+use futures_util::TryStreamExt;
+use rand::{prelude::IndexedRandom, random_range, rng};
+use std::{path::PathBuf, pin::pin};
+use tracing::{debug, info, warn};
+
+/// How many pages deep a random Pexels pick may look.
+const PEXELS_PAGES: u32 = 10;
+
+/// How many pages deep a Wallhaven pick with a fixed sorting may look.
+const WALLHAVEN_PAGES: u32 = 5;
+
+/// What a random pick is narrowed to, beyond fitting the monitor.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Narrowing {
+  /// Search keywords replacing the source's own.
+  pub query: Option<String>,
+  /// How Wallhaven sorts the results, instead of randomly.
+  pub sorting: Option<Sorting>,
+  /// Only pick candidates with soft, low-contrast colors.
+  pub muted: bool
+}
+
+impl Narrowing {
+  /// Narrows the pick to results matching `query`.
+  pub fn query(query: impl Into<String>) -> Self {
+    Self {
+      query: Some(query.into()),
+      ..Default::default()
+    }
+  }
+}
+
+impl From<&Rule> for Narrowing {
+  fn from(rule: &Rule) -> Self {
+    Self {
+      query: rule.query.clone(),
+      sorting: rule.sorting,
+      muted: rule.muted
+    }
+  }
+}
+
+/// Queries every enabled source in rank order and collects their results.
+///
+/// Sources that have been failing are queried last. A failing source is
+/// reported and skipped; an error is only returned if no source produced any
+/// candidates.
+pub async fn candidates(config: &Config) -> Result<Vec<Candidate>> {
+  let mut health = Health::load(&config.path)?;
+  let mut candidates = Vec::new();
+  let mut last_error = None;
+
+  let ranked = config.source.ranked();
+  for name in health.rank(&ranked) {
+    let Some(source) = config.source.find(name) else {
+      continue;
+    };
+    match fetch(config, source, None, &Narrowing::default()).await {
+      Ok(found) => {
+        health.record_success(name);
+        stats::update(config, |s| s.record_source(name, !found.is_empty()))?;
+        candidates.extend(found);
+      }
+      Err(e) => {
+        warn!("Source '{name}' failed: {e}");
+        health.record_failure(name, &e);
+        stats::update(config, |s| s.record_source(name, false))?;
+        last_error = Some(e);
+      }
+    }
+  }
+  health.save(&config.path)?;
+
+  match last_error {
+    Some(e) if candidates.is_empty() => Err(e),
+    _ => Ok(candidates)
+  }
+}
+
+/// Searches all enabled sources and returns the candidate that best fits
+/// `monitor`, if any source returned results. Blocklisted candidates and
+/// images known to be gone are skipped.
+pub async fn best_match(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<Candidate>> {
+  let blocklist = Blocklist::load(&config.path)?;
+  let library = Library::load(&config.path)?;
+  let mut candidates = candidates(config).await?;
+  candidates.retain(|candidate| {
+    !blocklist.contains(&candidate.url) && !library.is_dead(&candidate.url)
+  });
+  let mode = match config.color.mode {
+    ColorMode::Auto => ColorMode::get_current(),
+    mode => mode
+  };
+  Ok(unseen(config, candidates, monitor, mode).await)
+}
+
+/// Picks a random wallpaper for `monitor` from the highest ranked enabled
+/// source that returns any. See [fallback].
+pub async fn random(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<Candidate>> {
+  Ok(
+    fallback(config, monitor, &Narrowing::default())
+      .await?
+      .picked
+  )
+}
+
+/// Picks a random wallpaper for `monitor` matching the search `query`, like
+/// [random].
+pub async fn themed(
+  config: &Config,
+  monitor: &Monitor,
+  query: &str
+) -> Result<Option<Candidate>> {
+  Ok(
+    fallback(config, monitor, &Narrowing::query(query))
+      .await?
+      .picked
+  )
+}
+
+/// Picks a wallpaper for `monitor` the way `rule` asks, like [random].
+pub async fn for_role(
+  config: &Config,
+  monitor: &Monitor,
+  rule: &Rule
+) -> Result<Option<Candidate>> {
+  Ok(fallback(config, monitor, &rule.into()).await?.picked)
+}
+
+/// Walks the enabled sources in rank order until one has a random wallpaper
+/// for `monitor`, as narrowed by `narrowing`, and reports how each source
+/// fared.
+///
+/// The search is narrowed to images at least as large as the monitor, with a
+/// matching aspect ratio where the source supports one, and the best fitting
+/// result is picked. The next source is only tried if the one before fails
+/// or has nothing suitable. Blocklisted results and images known to be gone
+/// are skipped. An error is returned only if no source served a wallpaper
+/// and at least one failed.
+pub async fn fallback(
+  config: &Config,
+  monitor: &Monitor,
+  narrowing: &Narrowing
+) -> Result<Fallback> {
+  let blocklist = Blocklist::load(&config.path)?;
+  let library = Library::load(&config.path)?;
+  let mut health = Health::load(&config.path)?;
+  let mode = match config.color.mode {
+    ColorMode::Auto => ColorMode::get_current(),
+    mode => mode
+  };
+  let mut report = Fallback::default();
+  let mut last_error = None;
+
+  let ranked = config.source.ranked();
+  for name in health.rank(&ranked) {
+    let Some(source) = config.source.find(name) else {
+      continue;
+    };
+    match fetch(config, source, Some(monitor), narrowing).await {
+      Ok(mut found) => {
+        health.record_success(name);
+        stats::update(config, |s| s.record_source(name, !found.is_empty()))?;
+        found.retain(|candidate| {
+          !blocklist.contains(&candidate.url)
+            && !library.is_dead(&candidate.url)
+            && (!narrowing.muted || is_muted(candidate))
+        });
+        report.picked = unseen(config, found, monitor, mode).await;
+        if report.picked.is_some() {
+          report.record(name, Outcome::Served);
+          break;
+        }
+        report.record(name, Outcome::Empty);
+      }
+      Err(e) => {
+        warn!("Source '{name}' failed: {e}");
+        health.record_failure(name, &e);
+        stats::update(config, |s| s.record_source(name, false))?;
+        report.record(name, Outcome::Failed(e.to_string()));
+        last_error = Some(e);
+      }
+    }
+  }
+  health.save(&config.path)?;
+
+  if let Some(name) = report.served_by() {
+    info!("Served by {name} after trying: {report}");
+  }
+  match last_error {
+    Some(e) if report.picked.is_none() => Err(e),
+    _ => Ok(report)
+  }
+}
+
+/// Returns the candidate that best fits `monitor`. With `source.seen`
+/// enabled, candidates whose thumbnails look like a wallpaper shown before
+/// are passed over, checking at most `max_checks` of them.
+async fn unseen(
+  config: &Config,
+  mut candidates: Vec<Candidate>,
+  monitor: &Monitor,
+  mode: ColorMode
+) -> Option<Candidate> {
+  let scoring = &config.source.scoring;
+  let filter = config.source.seen;
+  let fingerprints = match Fingerprints::load(&config.path) {
+    Ok(fingerprints) if filter.enabled && !fingerprints.is_empty() =>
+      fingerprints,
+    Ok(_) => Fingerprints::default(),
+    Err(e) => {
+      warn!("Failed to load the fingerprints of seen wallpapers: {e}");
+      Fingerprints::default()
+    }
+  };
+
+  for _ in 0..filter.max_checks {
+    if fingerprints.is_empty() {
+      break;
+    }
+    let candidate = best(&candidates, monitor, scoring, mode)?.0.clone();
+    let limits = &config.wallpaper.limits;
+    match resembles(&candidate, &fingerprints, filter.max_distance, limits)
+      .await
+    {
+      Some(seen) => {
+        info!("Skipping {candidate}, which looks like {}", seen.display());
+        candidates.retain(|c| c != &candidate);
+      }
+      None => return Some(candidate)
+    }
+  }
+  best(&candidates, monitor, scoring, mode).map(|(c, _)| c.clone())
+}
+
+/// Returns the seen image the thumbnail of `candidate` is within
+/// `max_distance` of, if any. A thumbnail that cannot be fetched counts as
+/// unseen.
+async fn resembles(
+  candidate: &Candidate,
+  fingerprints: &Fingerprints,
+  max_distance: u32,
+  limits: &Limits
+) -> Option<PathBuf> {
+  let url = candidate.thumbnail.as_deref()?;
+  let response = cache::send(&candidate.source, || {
+    http::client().get(url).timeout(http::timeouts().request())
+  })
+  .await;
+  let hash = match response {
+    Ok(thumbnail) if thumbnail.status.is_success() =>
+      fingerprints::of_bytes(&thumbnail.body, limits),
+    Ok(thumbnail) => Err(Error::from_status(
+      &candidate.source,
+      thumbnail.status.as_u16(),
+      thumbnail.text()
+    )),
+    Err(e) => Err(e)
+  };
+  match hash {
+    Ok(hash) => fingerprints
+      .closest(hash)
+      .filter(|(_, distance)| *distance <= max_distance)
+      .map(|(seen, _)| seen.clone()),
+    Err(e) => {
+      debug!("Could not check the thumbnail of {candidate}: {e}");
+      None
+    }
+  }
+}
+
+/// Fetches the first page of a Wallhaven search.
+async fn first_page(api: &Wallhaven, params: &SearchParams) -> Result<Page> {
+  let page = pin!(api.search_pages(params)).try_next().await?;
+  Ok(page.unwrap_or(Page {
+    data: Vec::new(),
+    next: None
+  }))
+}
+
+/// Fetches one page of results from `source`. With a monitor, the results
+/// are random and limited to images that fit it. The `narrowing` query
+/// replaces the source's own. Settings the source leaves unset are inherited
+/// from `source.defaults`. A random Wallhaven search picks up on the page
+/// after the one it last fetched, with the same seed, kept in
+/// [RandomPages].
+async fn fetch(
+  config: &Config,
+  source: &Source,
+  monitor: Option<&Monitor>,
+  narrowing: &Narrowing
+) -> Result<Vec<Candidate>> {
+  let query = narrowing.query.as_deref();
+  if let Some(params) = &source.local {
+    return local::candidates(&source.name, params, monitor);
+  }
+  match source.name.as_str() {
+    "wallhaven" => {
+      let mut params =
+        SearchParams::from(&config.source.wallhaven_params(source));
+      safe::clamp_wallhaven(config, &mut params);
+      if let Some(query) = query {
+        params.query = Some(query.to_string());
+      }
+      if let Some(monitor) = monitor {
+        params.sorting = Some(Sorting::Random);
+        let ranked = narrowing.sorting.filter(|s| *s != Sorting::Random);
+        if let Some(sorting) = ranked {
+          //{ A ranked sorting stands in for randomness with a random page }
+          params.sorting = Some(sorting);
+          params.page = Some(random_range(1..=WALLHAVEN_PAGES));
+        }
+        params.atleast = Some(monitor.size.resolution_str().to_string());
+        params.ratios =
+          wallhaven_ratio(monitor.size.width, monitor.size.height)
+            .map(str::to_string);
+      }
+      let api = Wallhaven::new(source.api_key());
+      if monitor.is_none() || params.sorting != Some(Sorting::Random) {
+        let response = api.search(&params).await?;
+        return Ok(response.data.iter().map(Candidate::from).collect());
+      }
+
+      //{ Carry on through the pages of the last random order, or the next
+      //{ call would likely show some of the same wallpapers again }
+      let key = params.key();
+      let resumed = RandomPages::load(&config.path)?.searches.remove(&key);
+      if let Some(resume) = &resumed {
+        params.page = Some(resume.page);
+        params.seed = Some(resume.seed.clone());
+      }
+      let mut page = first_page(&api, &params).await?;
+      if page.data.is_empty() && resumed.is_some() {
+        params.page = None;
+        params.seed = None;
+        page = first_page(&api, &params).await?;
+      }
+
+      let next = match page.next {
+        Some(SearchParams {
+          seed: Some(seed),
+          page: Some(page),
+          ..
+        }) => Some(RandomPage::new(seed, page)),
+        _ => None
+      };
+      if let Err(e) = RandomPages::update(&config.path, &key, next) {
+        warn!("Failed to remember the random Wallhaven seed: {e}");
+      }
+      Ok(page.data.iter().map(Candidate::from).collect())
+    }
+    "pexels" => {
+      let Some(api_key) = source.api_key() else {
+        return Err(Error::API(format!(
+          "Pexels requires an API key; set ${} or `api_key`",
+          source.env_var()
+        )));
+      };
+      let mut params = source
+        .pexels
+        .as_ref()
+        .map(pexels::SearchParams::from)
+        .unwrap_or_default();
+      if let Some(query) = query {
+        params.query = Some(query.to_string());
+      }
+      if let Some(monitor) = monitor {
+        //{ Pexels cannot sort randomly, so a random page stands in }
+        let (width, height) = (monitor.size.width, monitor.size.height);
+        params.page = Some(random_range(1..=PEXELS_PAGES));
+        params
+          .orientation
+          .get_or_insert(Orientation::of(width, height));
+        params.size.get_or_insert(Size::at_least(width, height));
+      }
+      let api = Pexels::new(api_key);
+      let mut response = api.photos(&params).await?;
+
+      //{ A narrow search may not reach the random page }
+      if response.photos.is_empty() && params.page.is_some_and(|p| p > 1) {
+        params.page = Some(1);
+        response = api.photos(&params).await?;
+      }
+      Ok(response.photos.iter().map(Candidate::from).collect())
+    }
+    "reddit" => {
+      let settings = source.reddit.clone().unwrap_or_default();
+      let mut params = ListingParams::from(&settings);
+      if let Some(query) = query {
+        params.query = Some(query.to_string());
+      }
+      let listing = Reddit::new().listing(&params).await?;
+      let (min_width, min_height) = settings.minimum().unwrap_or_default();
+      let found: Vec<Candidate> = listing
+        .data
+        .children
+        .iter()
+        .map(|child| &child.data)
+        .filter(|post| safe::allows_post(config, settings.nsfw, post))
+        .filter_map(Candidate::from_post)
+        .filter(|c| c.width >= min_width && c.height >= min_height)
+        .collect();
+
+      //{ Listings are ranked, so a random fitting post stands in }
+      Ok(match monitor {
+        Some(monitor) => found
+          .iter()
+          .filter(|c| {
+            c.width >= monitor.size.width && c.height >= monitor.size.height
+          })
+          .collect::<Vec<_>>()
+          .choose(&mut rng())
+          .map(|c| vec![(*c).clone()])
+          .unwrap_or_default(),
+        None => found
+      })
+    }
+    name => Err(Error::API(format!(
+      "Searching '{name}' is not supported yet"
+    )))
+  }
+}
+
+/// Aspect ratios Wallhaven can filter by, as width, height and parameter.
+const WALLHAVEN_RATIOS: [(u32, u32, &str); 12] = [
+  (16, 9, "16x9"),
+  (16, 10, "16x10"),
+  (21, 9, "21x9"),
+  (32, 9, "32x9"),
+  (48, 9, "48x9"),
+  (9, 16, "9x16"),
+  (10, 16, "10x16"),
+  (9, 18, "9x18"),
+  (1, 1, "1x1"),
+  (3, 2, "3x2"),
+  (4, 3, "4x3"),
+  (5, 4, "5x4")
+];
+
+/// Returns the Wallhaven ratio filter closest to `width` x `height`, if one
+/// is within 3%. Otherwise the ratio is left to scoring.
+fn wallhaven_ratio(width: u32, height: u32) -> Option<&'static str> {
+  const TOLERANCE: f32 = 0.03;
+  if width == 0 || height == 0 {
+    return None;
+  }
+  let ratio = width as f32 / height as f32;
+  WALLHAVEN_RATIOS
+    .iter()
+    .map(|(w, h, name)| ((*w as f32 / *h as f32 / ratio - 1.0).abs(), *name))
+    .filter(|(error, _)| *error <= TOLERANCE)
+    .min_by(|a, b| a.0.total_cmp(&b.0))
+    .map(|(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wallhaven_ratio() {
+    assert_eq!(wallhaven_ratio(2560, 1440), Some("16x9"));
+    assert_eq!(wallhaven_ratio(1366, 768), Some("16x9"));
+    assert_eq!(wallhaven_ratio(3440, 1440), Some("21x9"));
+    assert_eq!(wallhaven_ratio(1080, 1920), Some("9x16"));
+    assert_eq!(wallhaven_ratio(2000, 1000), None);
+    assert_eq!(wallhaven_ratio(0, 0), None);
+  }
+}
@@ -0,0 +1,181 @@
+//! Rolling health of each wallpaper source.
+//!
+//! Every search records whether the source succeeded. A source whose recent
+//! requests all failed is demoted behind the healthy sources in the rank
+//! order, and is promoted again as soon as it succeeds.
+
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, VecDeque},
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+
+/// The name of the health file within the state directory.
+const HEALTH_FILE: &str = "health.json";
+
+/// The number of recent outcomes kept per source.
+const WINDOW: usize = 20;
+
+/// Consecutive failures after which a source is demoted.
+const FAILURE_THRESHOLD: usize = 3;
+
+/// The recent outcomes of requests to one source.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceHealth {
+  /// Outcomes of the most recent requests, oldest first (`true` = success).
+  pub recent: VecDeque<bool>,
+  pub last_success: Option<DateTime<Local>>,
+  pub last_error: Option<String>,
+  pub last_error_at: Option<DateTime<Local>>
+}
+
+impl SourceHealth {
+  fn push(&mut self, success: bool) {
+    if self.recent.len() == WINDOW {
+      self.recent.pop_front();
+    }
+    self.recent.push_back(success);
+  }
+
+  /// Returns the fraction of recent requests that succeeded, if any were
+  /// made.
+  pub fn success_rate(&self) -> Option<f64> {
+    let total = self.recent.len();
+    let successes = self.recent.iter().filter(|ok| **ok).count();
+    (total > 0).then(|| successes as f64 / total as f64)
+  }
+
+  /// Checks whether the most recent requests all failed.
+  pub fn is_failing(&self) -> bool {
+    self.recent.len() >= FAILURE_THRESHOLD
+      && self
+        .recent
+        .iter()
+        .rev()
+        .take(FAILURE_THRESHOLD)
+        .all(|ok| !ok)
+  }
+}
+
+/// Health of all sources, keyed by source name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Health {
+  pub sources: BTreeMap<String, SourceHealth>
+}
+
+impl Health {
+  /// Returns the path of the health file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(HEALTH_FILE)
+  }
+
+  /// Loads the source health from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the source health to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records a successful request to `source`.
+  pub fn record_success(&mut self, source: &str) {
+    let health = self.sources.entry(source.to_string()).or_default();
+    health.push(true);
+    health.last_success = Some(Local::now());
+  }
+
+  /// Records a failed request to `source`.
+  pub fn record_failure(&mut self, source: &str, error: impl Display) {
+    let health = self.sources.entry(source.to_string()).or_default();
+    health.push(false);
+    health.last_error = Some(error.to_string());
+    health.last_error_at = Some(Local::now());
+  }
+
+  /// Checks whether `source` is currently demoted.
+  pub fn is_failing(&self, source: &str) -> bool {
+    self
+      .sources
+      .get(source)
+      .is_some_and(SourceHealth::is_failing)
+  }
+
+  /// Returns `ordered` with failing sources moved behind the healthy ones,
+  /// keeping the configured order within each group.
+  pub fn rank<'a>(&self, ordered: &'a [String]) -> Vec<&'a String> {
+    let (healthy, failing): (Vec<_>, Vec<_>) =
+      ordered.iter().partition(|name| !self.is_failing(name));
+    healthy.into_iter().chain(failing).collect()
+  }
+}
+
+impl Display for Health {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.sources.is_empty() {
+      return printf!(f, "Health", "[No requests]");
+    }
+    for (name, health) in &self.sources {
+      let rate = match health.success_rate() {
+        Some(rate) => format!(
+          "{:.0}% of last {}{}",
+          rate * 100.0,
+          health.recent.len(),
+          if health.is_failing() {
+            " (demoted)"
+          } else {
+            ""
+          }
+        ),
+        None => "[No requests]".to_string()
+      };
+      printf!(f, name, rate)?;
+      if let (Some(error), Some(at)) =
+        (&health.last_error, health.last_error_at)
+      {
+        printf!(
+          f,
+          "Last Error",
+          format!("{} ({})", error, at.format("%Y-%m-%d %H:%M")),
+          24,
+          6
+        )?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_failing_source_is_demoted_until_it_recovers() {
+    let ordered: Vec<String> =
+      vec!["wallhaven".into(), "unsplash".into(), "pixabay".into()];
+    let mut health = Health::default();
+    for _ in 0..FAILURE_THRESHOLD {
+      health.record_failure("wallhaven", "timed out");
+    }
+    health.record_success("unsplash");
+
+    assert!(health.is_failing("wallhaven"));
+    assert_eq!(
+      health.rank(&ordered),
+      [&ordered[1], &ordered[2], &ordered[0]]
+    );
+
+    health.record_success("wallhaven");
+    assert!(!health.is_failing("wallhaven"));
+    assert_eq!(health.rank(&ordered), ordered.iter().collect::<Vec<_>>());
+    assert_eq!(
+      health.sources["wallhaven"].success_rate(),
+      Some(1.0 / (FAILURE_THRESHOLD + 1) as f64)
+    );
+  }
+}
@@ -0,0 +1,122 @@
+//! Treats folders on disk as a source, so local images are ranked, scored
+//! and fallen back on exactly like search results.
+
+use super::Candidate;
+use crate::{
+  Result,
+  config::{Monitor, search::local::Params},
+  utils::files
+};
+use rand::{prelude::SliceRandom, rng};
+use tracing::debug;
+
+/// The most images whose size is read per search, so huge folders stay
+/// quick. A different random sample is taken every time.
+const SAMPLE: usize = 100;
+
+/// Lists the images in the folders of `params` as candidates of `source`.
+/// With a monitor, images smaller than it are left out if any are large
+/// enough.
+pub fn candidates(
+  source: &str,
+  params: &Params,
+  monitor: Option<&Monitor>
+) -> Result<Vec<Candidate>> {
+  let mut images = Vec::new();
+  for dir in &params.dirs {
+    images.extend(
+      files::list_images(dir, params.recursive)?
+        .into_iter()
+        .filter(|image| {
+          image
+            .file_name()
+            .is_some_and(|name| params.matches(&name.to_string_lossy()))
+        })
+    );
+  }
+  images.shuffle(&mut rng());
+
+  let found: Vec<Candidate> = images
+    .iter()
+    .take(SAMPLE)
+    .filter_map(|image| match image::image_dimensions(image) {
+      Ok((width, height)) => Some(Candidate {
+        source: source.to_string(),
+        id: image.display().to_string(),
+        url: image.display().to_string(),
+        thumbnail: None,
+        width,
+        height,
+        colors: Vec::new(),
+        popularity: 0
+      }),
+      Err(e) => {
+        debug!("Skipping unreadable {}: {e}", image.display());
+        None
+      }
+    })
+    .collect();
+
+  let Some(monitor) = monitor else {
+    return Ok(found);
+  };
+  let fitting: Vec<Candidate> = found
+    .iter()
+    .filter(|c| {
+      c.width >= monitor.size.width && c.height >= monitor.size.height
+    })
+    .cloned()
+    .collect();
+  Ok(if fitting.is_empty() { found } else { fitting })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::RgbImage;
+  use std::fs;
+
+  #[test]
+  fn test_candidates() {
+    let dir = std::env::temp_dir().join("wallter-local-source");
+    let nested = dir.join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    RgbImage::new(64, 32)
+      .save(dir.join("space-wide.png"))
+      .unwrap();
+    RgbImage::new(8, 8)
+      .save(dir.join("space-small.png"))
+      .unwrap();
+    RgbImage::new(64, 32).save(dir.join("city.png")).unwrap();
+    RgbImage::new(64, 32)
+      .save(nested.join("space-deep.png"))
+      .unwrap();
+
+    let mut params = Params {
+      dirs: vec![dir.clone()],
+      patterns: vec!["space-*".into()],
+      recursive: false
+    };
+    let names = |params: &Params, monitor: Option<&Monitor>| {
+      let mut found: Vec<String> = candidates("photos", params, monitor)
+        .unwrap()
+        .into_iter()
+        .map(|c| c.url.rsplit(['/', '\\']).next().unwrap().to_string())
+        .collect();
+      found.sort();
+      found
+    };
+    assert_eq!(names(&params, None), ["space-small.png", "space-wide.png"]);
+
+    //{ Images too small for the monitor are left out }
+    let monitor = Monitor::test("DP-1", 32, 16);
+    assert_eq!(names(&params, Some(&monitor)), ["space-wide.png"]);
+
+    params.recursive = true;
+    assert_eq!(
+      names(&params, Some(&monitor)),
+      ["space-deep.png", "space-wide.png"]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,29 @@
+//! Searches the configured sources and picks the candidate that best fits a
+//! monitor.
+//!
+//! Every enabled source is queried and its results are scored against the
+//! target monitor using the weights in [`config::search::Scoring`], rather
+//! than taking whatever the highest ranked source returns first.
+//!
+//! [`config::search::Scoring`]: crate::config::search::Scoring
+
+mod candidate;
+pub use candidate::Candidate;
+
+pub mod fallback;
+pub use fallback::Fallback;
+
+mod fetch;
+pub use fetch::{
+  Narrowing, best_match, candidates, fallback, for_role, random, themed
+};
+
+pub mod health;
+pub use health::Health;
+
+mod local;
+
+pub mod safe;
+
+mod score;
+pub use score::{Score, best, score};
@@ -0,0 +1,155 @@
+//! Enforces `source.safe_hours`, the window in which only safe-for-work
+//! wallpapers are shown.
+//!
+//! While it is open, every search is clamped here before it reaches a
+//! provider: Wallhaven is limited to SFW purity and Reddit drops NSFW posts.
+//! Pexels only has safe photos to begin with. The slideshow picker checks
+//! images on disk with [Purities::allows], which looks up the purity of
+//! Wallhaven images once and remembers it.
+
+use crate::{
+  Config, Result,
+  api::{
+    reddit::Post,
+    wallhaven::{self, Api as Wallhaven, SearchParams}
+  },
+  config::Path,
+  utils::store
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  path::{Path as FilePath, PathBuf}
+};
+use tracing::{debug, warn};
+
+/// The name of the purity cache within the state directory.
+const PURITY_FILE: &str = "purity.json";
+
+/// The Wallhaven purity of safe-for-work images.
+const SFW: &str = "sfw";
+
+/// Whether only safe-for-work wallpapers may be shown right now.
+pub fn is_active(config: &Config) -> bool {
+  config.source.safe_hours.is_active()
+}
+
+/// Limits a Wallhaven search to safe-for-work results while the window is
+/// open.
+pub fn clamp_wallhaven(config: &Config, params: &mut SearchParams) {
+  if is_active(config) {
+    params.purity = Some((true, false, false));
+  }
+}
+
+/// Whether a Reddit post may be shown, given the source's own `nsfw`
+/// setting and the window.
+pub fn allows_post(config: &Config, nsfw: bool, post: &Post) -> bool {
+  !post.over_18 || (nsfw && !is_active(config))
+}
+
+/// The known Wallhaven purity of images on disk, by Wallhaven id.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Purities {
+  pub images: BTreeMap<String, String>
+}
+
+impl Purities {
+  /// Returns the path of the purity cache.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(PURITY_FILE)
+  }
+
+  /// Loads the purity cache from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the purity cache to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Whether `image` may be shown right now. Outside the window, every
+  /// image may. Inside it, Wallhaven images must be known to be SFW; their
+  /// purity is looked up if needed, and an image that cannot be checked is
+  /// held back. Other images are the user's own and are allowed.
+  pub async fn allows(&mut self, config: &Config, image: &FilePath) -> bool {
+    if let Some(allowed) = self.check(config, image) {
+      return allowed;
+    }
+    let Some(id) = wallhaven::id_from_path(image) else {
+      return true;
+    };
+
+    let api_key = config
+      .source
+      .find("wallhaven")
+      .and_then(|source| source.api_key());
+    match Wallhaven::new(api_key).get_wallpaper_details(&id).await {
+      Ok(details) => {
+        debug!("{id} is {}", details.purity);
+        let safe = details.purity == SFW;
+        self.images.insert(id, details.purity);
+        safe
+      }
+      Err(e) => {
+        warn!(
+          "Holding back {id} during safe hours; its purity is unknown: {e}"
+        );
+        false
+      }
+    }
+  }
+
+  /// Decides [allows](Self::allows) without a lookup, or returns `None` if
+  /// the purity of `image` has to be looked up first.
+  fn check(&self, config: &Config, image: &FilePath) -> Option<bool> {
+    if !is_active(config) {
+      return Some(true);
+    }
+    match wallhaven::id_from_path(image) {
+      Some(id) => self.images.get(&id).map(|purity| purity == SFW),
+      None => Some(true)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveTime;
+
+  #[test]
+  fn test_check() {
+    let mut config = Config::default();
+    let mut purities = Purities::default();
+    purities.images.insert("abc123".into(), "sketchy".into());
+    purities.images.insert("def456".into(), SFW.into());
+    let sketchy = FilePath::new("wallhaven-abc123.jpg");
+    assert_eq!(purities.check(&config, sketchy), Some(true));
+
+    //{ A window open all day, every day }
+    let hours = &mut config.source.safe_hours;
+    hours.enabled = true;
+    hours
+      .days
+      .extend([chrono::Weekday::Sat, chrono::Weekday::Sun]);
+    hours.start = NaiveTime::MIN;
+    hours.end = NaiveTime::MIN;
+    assert_eq!(purities.check(&config, sketchy), Some(false));
+    assert_eq!(
+      purities.check(&config, "wallhaven-def456.jpg".as_ref()),
+      Some(true)
+    );
+    assert_eq!(
+      purities.check(&config, "wallhaven-zzz999.jpg".as_ref()),
+      None
+    );
+    assert_eq!(purities.check(&config, "holiday.png".as_ref()), Some(true));
+
+    let mut params = SearchParams::default();
+    clamp_wallhaven(&config, &mut params);
+    assert_eq!(params.purity, Some((true, false, false)));
+  }
+}
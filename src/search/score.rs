@@ -0,0 +1,274 @@
+use super::Candidate;
+use crate::config::{ColorMode, Monitor, search::Scoring};
+
+/// Colors closer than this (in RGB space) count as a match.
+const COLOR_TOLERANCE: f32 = 64.0;
+
+/// How well a candidate fits a monitor, per criterion and in total.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Score {
+  pub resolution: f32,
+  pub aspect: f32,
+  pub color: f32,
+  pub popularity: f32,
+  /// The weighted sum of the criteria.
+  pub total: f32
+}
+
+/// Scores `candidate` against `monitor`.
+///
+/// `max_popularity` is the highest popularity among the candidates being
+/// compared, so popularity is relative to the result set. `mode` should be
+/// resolved (`Light` or `Dark`); it is only used when no preferred colors are
+/// configured.
+pub fn score(
+  candidate: &Candidate,
+  monitor: &Monitor,
+  scoring: &Scoring,
+  mode: ColorMode,
+  max_popularity: u64
+) -> Score {
+  let resolution = resolution_fit(candidate, monitor);
+  let aspect = aspect_fit(candidate, monitor);
+  let color = color_fit(candidate, scoring, mode);
+  let popularity = if max_popularity == 0 {
+    0.0
+  } else {
+    (candidate.popularity as f32).ln_1p() / (max_popularity as f32).ln_1p()
+  };
+
+  let total = scoring.resolution * resolution
+    + scoring.aspect * aspect
+    + scoring.color * color
+    + scoring.popularity * popularity;
+
+  Score {
+    resolution,
+    aspect,
+    color,
+    popularity,
+    total
+  }
+}
+
+/// Picks the highest scoring candidate for `monitor`. Ties go to the earlier
+/// candidate, so source rank still breaks them.
+pub fn best<'a>(
+  candidates: &'a [Candidate],
+  monitor: &Monitor,
+  scoring: &Scoring,
+  mode: ColorMode
+) -> Option<(&'a Candidate, Score)> {
+  let max_popularity = candidates.iter().map(|c| c.popularity).max()?;
+  candidates
+    .iter()
+    .map(|c| (c, score(c, monitor, scoring, mode, max_popularity)))
+    .fold(None, |best, (candidate, score)| match best {
+      Some((_, top)) if score.total <= top.total => best,
+      _ => Some((candidate, score))
+    })
+}
+
+/// Rewards images that cover the monitor without upscaling. A small surplus
+/// is ideal; a large one only wastes bandwidth and is mildly penalized.
+fn resolution_fit(candidate: &Candidate, monitor: &Monitor) -> f32 {
+  let (width, height) = (monitor.size.width, monitor.size.height);
+  if width == 0 || height == 0 {
+    return 1.0;
+  }
+
+  let surplus = (candidate.width as f32 / width as f32)
+    .min(candidate.height as f32 / height as f32);
+  if surplus < 1.0 {
+    surplus * surplus
+  } else {
+    1.0 / (1.0 + 0.5 * surplus.ln())
+  }
+}
+
+/// Rewards aspect ratios close to the monitor's, symmetrically for wider and
+/// narrower images.
+fn aspect_fit(candidate: &Candidate, monitor: &Monitor) -> f32 {
+  let target = monitor.size.ratio();
+  if candidate.height == 0 || target <= 0.0 {
+    return 0.0;
+  }
+
+  let ratio = candidate.width as f32 / candidate.height as f32;
+  let error = (ratio / target).ln().abs();
+  (-4.0 * error).exp()
+}
+
+/// Rewards matches with the preferred colors, or a palette that suits the
+/// color mode if there are none.
+fn color_fit(candidate: &Candidate, scoring: &Scoring, mode: ColorMode) -> f32 {
+  let palette: Vec<[u8; 3]> = candidate
+    .colors
+    .iter()
+    .filter_map(|c| parse_hex(c))
+    .collect();
+  if palette.is_empty() {
+    return 0.5;
+  }
+
+  if !scoring.preferred_colors.is_empty() {
+    let preferred: Vec<[u8; 3]> = scoring
+      .preferred_colors
+      .iter()
+      .filter_map(|c| parse_hex(c))
+      .collect();
+    if preferred.is_empty() {
+      return 0.5;
+    }
+    let matched = preferred
+      .iter()
+      .filter(|p| palette.iter().any(|c| distance(p, c) < COLOR_TOLERANCE))
+      .count();
+    return matched as f32 / preferred.len() as f32;
+  }
+
+  let luminance =
+    palette.iter().map(luminance).sum::<f32>() / palette.len() as f32;
+  match mode {
+    ColorMode::Dark => 1.0 - luminance,
+    ColorMode::Light => luminance,
+    ColorMode::Auto => 0.5
+  }
+}
+
+/// Whether the colors of `candidate` are soft and close in brightness, as
+/// suits a monitor that should not draw the eye. Candidates whose colors are
+/// unknown pass.
+pub(super) fn is_muted(candidate: &Candidate) -> bool {
+  /// The highest average saturation that still counts as muted.
+  const MAX_SATURATION: f32 = 0.35;
+  /// The largest brightness gap between colors that still counts as muted.
+  const MAX_CONTRAST: f32 = 0.5;
+
+  let palette: Vec<[u8; 3]> = candidate
+    .colors
+    .iter()
+    .filter_map(|c| parse_hex(c))
+    .collect();
+  if palette.is_empty() {
+    return true;
+  }
+  let saturation =
+    palette.iter().map(saturation).sum::<f32>() / palette.len() as f32;
+  let (darkest, brightest) = palette
+    .iter()
+    .map(luminance)
+    .fold((1.0f32, 0.0f32), |(lo, hi), l| (lo.min(l), hi.max(l)));
+  saturation <= MAX_SATURATION && brightest - darkest <= MAX_CONTRAST
+}
+
+/// Parses a hex color such as `"#663399"` or `"663399"`.
+fn parse_hex(value: &str) -> Option<[u8; 3]> {
+  let hex = value.trim().trim_start_matches('#');
+  if hex.len() != 6 {
+    return None;
+  }
+  let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+  Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn distance(a: &[u8; 3], b: &[u8; 3]) -> f32 {
+  a.iter()
+    .zip(b)
+    .map(|(x, y)| (*x as f32 - *y as f32).powi(2))
+    .sum::<f32>()
+    .sqrt()
+}
+
+/// The relative luminance of a color, between 0 and 1.
+fn luminance(color: &[u8; 3]) -> f32 {
+  let [r, g, b] = color.map(|c| c as f32 / 255.0);
+  0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// The saturation of a color in the HSL model, between 0 and 1.
+fn saturation(color: &[u8; 3]) -> f32 {
+  let [r, g, b] = color.map(|c| c as f32 / 255.0);
+  let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+  let lightness = (max + min) / 2.0;
+  if max == min {
+    0.0
+  } else {
+    (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn candidate(
+    id: &str,
+    width: u32,
+    height: u32,
+    popularity: u64
+  ) -> Candidate {
+    Candidate {
+      source: "test".into(),
+      id: id.into(),
+      url: format!("https://example.com/{id}.jpg"),
+      thumbnail: None,
+      width,
+      height,
+      colors: vec!["#1a1a1a".into(), "#333333".into()],
+      popularity
+    }
+  }
+
+  #[test]
+  fn test_best_prefers_fitting_aspect_over_popularity() {
+    let candidates = [
+      candidate("portrait", 2160, 3840, 5000),
+      candidate("landscape", 3840, 2160, 10)
+    ];
+    let (picked, score) = best(
+      &candidates,
+      &Monitor::test("DP-1", 2560, 1440),
+      &Scoring::default(),
+      ColorMode::Dark
+    )
+    .unwrap();
+    assert_eq!(picked.id, "landscape");
+    assert!(score.aspect > 0.99);
+  }
+
+  #[test]
+  fn test_resolution_penalizes_upscaling() {
+    let target = Monitor::test("DP-1", 3840, 2160);
+    let small = candidate("small", 1920, 1080, 0);
+    let exact = candidate("exact", 3840, 2160, 0);
+    assert!(resolution_fit(&small, &target) < resolution_fit(&exact, &target));
+    assert_eq!(resolution_fit(&exact, &target), 1.0);
+  }
+
+  #[test]
+  fn test_color_preferences() {
+    let dark = candidate("dark", 1920, 1080, 0);
+    let mut scoring = Scoring::default();
+    assert!(color_fit(&dark, &scoring, ColorMode::Dark) > 0.8);
+    assert!(color_fit(&dark, &scoring, ColorMode::Light) < 0.2);
+
+    scoring.preferred_colors = vec!["663399".into(), "#202020".into()];
+    assert_eq!(color_fit(&dark, &scoring, ColorMode::Light), 0.5);
+    assert_eq!(parse_hex("#663399"), Some([0x66, 0x33, 0x99]));
+    assert_eq!(parse_hex("purple"), None);
+  }
+
+  #[test]
+  fn test_is_muted() {
+    let mut image = candidate("grey", 1920, 1080, 0);
+    assert!(is_muted(&image));
+
+    image.colors = vec!["#ff0000".into(), "#00ff00".into()];
+    assert!(!is_muted(&image));
+    image.colors = vec!["#000000".into(), "#ffffff".into()];
+    assert!(!is_muted(&image));
+    image.colors.clear();
+    assert!(is_muted(&image));
+  }
+}
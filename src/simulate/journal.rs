@@ -0,0 +1,205 @@
+use crate::{Result, config::ColorMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  fs::{OpenOptions, create_dir_all},
+  io::Write,
+  path::PathBuf,
+  sync::{Arc, Mutex}
+};
+
+/// An action a platform backend would have performed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Action {
+  /// The system color mode would have been changed.
+  ColorMode { mode: ColorMode },
+  /// A registry value would have been written.
+  RegistryWrite {
+    path: String,
+    name: String,
+    bytes: Vec<u8>
+  },
+  /// A wallpaper would have been applied to a monitor.
+  SetWallpaper { monitor: String, path: PathBuf },
+  /// A wallpaper would have been copied to and applied on a remote host.
+  RemoteWallpaper { host: String, path: PathBuf },
+  /// An application would have been switched to a color mode.
+  AppTheme { app: String, mode: ColorMode },
+  /// Night light would have been turned on or off for a monitor.
+  NightLight { monitor: String, enabled: bool },
+  /// The system-wide night light would have been turned on or off.
+  SystemNightLight { enabled: bool },
+  /// A desktop notification would have been shown.
+  Notify { title: String, body: String },
+  /// A file would have been written. Only journaled in dry runs.
+  WriteFile { path: PathBuf },
+  /// A file would have been copied. Only journaled in dry runs.
+  CopyFile { from: PathBuf, to: PathBuf },
+  /// A file would have been deleted. Only journaled in dry runs.
+  RemoveFile { path: PathBuf },
+  /// A directory would have been created. Only journaled in dry runs.
+  CreateDir { path: PathBuf },
+  /// An image would have been downloaded. Only journaled in dry runs.
+  Download { url: String, path: PathBuf },
+  /// A program would have been started. Only journaled in dry runs.
+  Spawn { command: String },
+  /// A sign-in would have been stored in or removed from the keyring. Only
+  /// journaled in dry runs.
+  StoreSecret { name: String, stored: bool },
+  /// An API key would have been stored in or removed from the keyring. Only
+  /// journaled in dry runs.
+  StoreKey { name: String, stored: bool }
+}
+
+impl Display for Action {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::ColorMode { mode } => write!(f, "Set color mode to {mode}"),
+      Self::RegistryWrite { path, name, bytes } => write!(
+        f,
+        "Write {} bytes to registry value '{name}' in '{path}'",
+        bytes.len()
+      ),
+      Self::SetWallpaper { monitor, path } =>
+        write!(f, "Set wallpaper on '{monitor}' to '{}'", path.display()),
+      Self::RemoteWallpaper { host, path } =>
+        write!(f, "Set wallpaper on host '{host}' to '{}'", path.display()),
+      Self::AppTheme { app, mode } => write!(f, "Set {app} to {mode} mode"),
+      Self::NightLight { monitor, enabled } => write!(
+        f,
+        "Turn night light {} on '{monitor}'",
+        if *enabled { "on" } else { "off" }
+      ),
+      Self::SystemNightLight { enabled } => write!(
+        f,
+        "Turn the system night light {}",
+        if *enabled { "on" } else { "off" }
+      ),
+      Self::Notify { title, body } =>
+        write!(f, "Show the notification '{title}: {body}'"),
+      Self::WriteFile { path } => write!(f, "Write '{}'", path.display()),
+      Self::CopyFile { from, to } =>
+        write!(f, "Copy '{}' to '{}'", from.display(), to.display()),
+      Self::RemoveFile { path } => write!(f, "Delete '{}'", path.display()),
+      Self::CreateDir { path } =>
+        write!(f, "Create directory '{}'", path.display()),
+      Self::Download { url, path } =>
+        write!(f, "Download '{url}' to '{}'", path.display()),
+      Self::Spawn { command } => write!(f, "Run '{command}'"),
+      Self::StoreSecret { name, stored: true } =>
+        write!(f, "Store the {name} sign-in in the keyring"),
+      Self::StoreSecret {
+        name,
+        stored: false
+      } => write!(f, "Remove the {name} sign-in from the keyring"),
+      Self::StoreKey { name, stored: true } =>
+        write!(f, "Store the {name} API key in the keyring"),
+      Self::StoreKey {
+        name,
+        stored: false
+      } => write!(f, "Remove the {name} API key from the keyring")
+    }
+  }
+}
+
+/// A timestamped [Action] in the journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+  pub timestamp: DateTime<Utc>,
+  #[serde(flatten)]
+  pub action: Action
+}
+
+/// Records the actions intercepted during a simulation.
+///
+/// Entries are kept in memory and, optionally, appended to a file as
+/// line-delimited JSON. Clones share the same entries.
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+  entries: Arc<Mutex<Vec<Entry>>>,
+  file: Option<PathBuf>,
+  echo: bool,
+  dry_run: bool
+}
+
+impl Journal {
+  /// Creates a new, empty in-memory journal.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Builder method to also append entries to a JSON lines file.
+  pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.file = Some(path.into());
+    self
+  }
+
+  /// Builder method to print each entry to stdout as it is recorded.
+  pub fn with_echo(mut self, echo: bool) -> Self {
+    self.echo = echo;
+    self
+  }
+
+  /// Builder method to also intercept file writes, downloads and spawned
+  /// programs, so that nothing at all is changed.
+  pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  /// Whether this journal intercepts every change, not just platform
+  /// actions.
+  pub fn is_dry_run(&self) -> bool {
+    self.dry_run
+  }
+
+  /// Records an action.
+  pub fn record(&self, action: Action) -> Result<()> {
+    let entry = Entry {
+      timestamp: Utc::now(),
+      action
+    };
+
+    if self.echo {
+      let label = if self.dry_run { "DRY RUN" } else { "SIMULATE" };
+      println!("[{label}] {}", entry.action);
+    }
+
+    if let Some(path) = &self.file {
+      if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+      }
+      let line = serde_json::to_string(&entry)
+        .map_err(|e| crate::Error::Settings(e.to_string()))?;
+      let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+      writeln!(file, "{line}")?;
+    }
+
+    self
+      .entries
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .push(entry);
+    Ok(())
+  }
+
+  /// Returns a snapshot of all recorded entries.
+  pub fn entries(&self) -> Vec<Entry> {
+    self
+      .entries
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clone()
+  }
+
+  /// Removes all recorded entries from memory.
+  pub fn clear(&self) {
+    self
+      .entries
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clear();
+  }
+}
@@ -0,0 +1,19 @@
+use super::{Action, record};
+use crate::{
+  Result,
+  config::color::mode::{Config as Mode, Manager as ModeManager}
+};
+
+/// A color mode manager that journals mode changes instead of applying them.
+pub struct Manager;
+
+impl ModeManager for Manager {
+  fn set(&self, mode: Mode) -> Result<()> {
+    record(Action::ColorMode { mode })?;
+    Ok(())
+  }
+
+  fn notify(&self) -> Result<()> {
+    Ok(())
+  }
+}
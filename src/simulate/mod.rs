@@ -0,0 +1,147 @@
+//! Simulation mode for platform backends.
+//!
+//! When simulation is enabled (e.g. via `--simulate`), the platform backends
+//! (color mode managers, registry writes, wallpaper setters) record the
+//! actions they would have performed to a [Journal] instead of touching the
+//! system. This makes end-to-end runs safe on any OS, for demos and tests.
+//!
+//! A dry run (`--dry-run`) goes further: state and config file writes,
+//! downloads and spawned programs are journaled and skipped too, so nothing
+//! changes at all.
+
+mod journal;
+pub use journal::{Action, Entry, Journal};
+
+mod manager;
+pub use manager::Manager;
+
+#[cfg(feature = "online-sources")]
+mod setter;
+#[cfg(feature = "online-sources")]
+pub use setter::Setter;
+
+use crate::Result;
+use std::sync::RwLock;
+
+/// The journal of the active simulation, if any.
+#[cfg(not(test))]
+static ACTIVE: RwLock<Option<Journal>> = RwLock::new(None);
+
+//{ Tests run in parallel, so each keeps its own simulation to itself }
+#[cfg(test)]
+thread_local! {
+  static ACTIVE: RwLock<Option<Journal>> = const { RwLock::new(None) };
+}
+
+/// Runs `f` on the slot holding the active journal.
+fn with_active<T>(f: impl FnOnce(&RwLock<Option<Journal>>) -> T) -> T {
+  #[cfg(not(test))]
+  return f(&ACTIVE);
+  #[cfg(test)]
+  return ACTIVE.with(f);
+}
+
+/// Enables simulation mode, recording all intercepted actions to `journal`.
+pub fn enable(journal: Journal) {
+  with_active(|active| {
+    *active.write().unwrap_or_else(|e| e.into_inner()) = Some(journal);
+  });
+}
+
+/// Disables simulation mode, returning the journal that was active.
+pub fn disable() -> Option<Journal> {
+  with_active(|active| active.write().unwrap_or_else(|e| e.into_inner()).take())
+}
+
+/// Returns the journal of the active simulation, if simulation is enabled.
+pub fn active() -> Option<Journal> {
+  with_active(|active| active.read().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Checks whether simulation mode is enabled.
+pub fn is_enabled() -> bool {
+  active().is_some()
+}
+
+/// Checks whether a dry run is in progress.
+pub fn is_dry_run() -> bool {
+  active().is_some_and(|journal| journal.is_dry_run())
+}
+
+/// Records `action` if simulation mode is enabled.
+///
+/// Returns `true` if the action was recorded, in which case the caller must
+/// skip performing it.
+pub fn record(action: Action) -> Result<bool> {
+  match active() {
+    Some(journal) => journal.record(action).map(|_| true),
+    None => Ok(false)
+  }
+}
+
+/// Records `action` if a dry run is in progress. Used for the changes that
+/// simulation mode lets through, such as state files and downloads.
+///
+/// Returns `true` if the action was recorded, in which case the caller must
+/// skip performing it.
+pub fn dry_run(action: Action) -> Result<bool> {
+  match active() {
+    Some(journal) if journal.is_dry_run() =>
+      journal.record(action).map(|_| true),
+    _ => Ok(false)
+  }
+}
+
+#[cfg(all(test, feature = "online-sources"))]
+mod tests {
+  use super::*;
+  use crate::{
+    config::{
+      Monitor,
+      color::mode::{Config as Mode, Manager as ModeManager}
+    },
+    wallpaper::Setter as WallpaperSetter
+  };
+  use std::path::PathBuf;
+
+  #[test]
+  fn test_simulated_actions_are_journaled() {
+    let journal = Journal::new();
+    enable(journal.clone());
+
+    Manager.set(Mode::Dark).unwrap();
+    assert!(
+      record(Action::RegistryWrite {
+        path: r"Software\Wallter".into(),
+        name: "Test".into(),
+        bytes: vec![0x01]
+      })
+      .unwrap()
+    );
+
+    let monitor = Monitor::test("DP-1", 1920, 1080);
+    let wallpaper = PathBuf::from("forest.jpg");
+    Setter.set(&wallpaper, &monitor).unwrap();
+    assert_eq!(Setter.get_current(&monitor).unwrap(), Some(wallpaper));
+
+    let actions: Vec<Action> =
+      journal.entries().into_iter().map(|e| e.action).collect();
+    assert_eq!(actions.len(), 3);
+    assert_eq!(actions[0], Action::ColorMode { mode: Mode::Dark });
+
+    //{ File writes only stop in dry runs }
+    let write = || Action::WriteFile {
+      path: PathBuf::from("state.json")
+    };
+    assert!(!dry_run(write()).unwrap());
+    let dry = Journal::new().with_dry_run(true);
+    enable(dry.clone());
+    assert!(is_dry_run() && dry_run(write()).unwrap());
+    assert_eq!(dry.entries().len(), 1);
+    enable(journal.clone());
+
+    disable();
+    assert!(!record(Action::ColorMode { mode: Mode::Light }).unwrap());
+    assert_eq!(journal.entries().len(), 3);
+  }
+}
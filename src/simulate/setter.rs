@@ -0,0 +1,43 @@
+use super::{Action, active, record};
+use crate::{Result, config::Monitor, wallpaper::Setter as WallpaperSetter};
+use std::path::{Path, PathBuf};
+
+/// A wallpaper setter that journals wallpaper changes instead of applying
+/// them. It reports the last journaled image as the current wallpaper.
+pub struct Setter;
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "simulate"
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    record(Action::SetWallpaper {
+      monitor: monitor.name.clone(),
+      path: path.to_path_buf()
+    })?;
+    Ok(())
+  }
+
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let entries = active()
+      .map(|journal| journal.entries())
+      .unwrap_or_default();
+    let current =
+      entries
+        .into_iter()
+        .rev()
+        .find_map(|entry| match entry.action {
+          Action::SetWallpaper {
+            monitor: name,
+            path
+          } if name == monitor.name => Some(path),
+          _ => None
+        });
+    Ok(current)
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    true
+  }
+}
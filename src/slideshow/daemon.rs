@@ -0,0 +1,398 @@
+//! The slideshow daemon: rotates wallpapers on the configured interval.
+//!
+//! The daemon is controlled through small files in the state directory, so
+//! the commands that drive it work the same way on every platform. While it
+//! runs, it publishes a [Running] record with a regular heartbeat and polls
+//! for a [Request] every few seconds.
+
+use super::{
+  Retries, State, advance, mirror,
+  session::{Watcher, idle_time},
+  watch::Folders,
+  workspace::{self, Focus, Tracker}
+};
+use crate::{
+  Config, Error, Result,
+  config::Path,
+  utils::store,
+  wallpaper::{
+    self, NowShowing, Source,
+    journal::{Trigger, with_trigger}
+  }
+};
+use chrono::{DateTime, Local, TimeDelta};
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::{
+  future::pending,
+  path::PathBuf,
+  pin::pin,
+  process,
+  sync::{Arc, Mutex, MutexGuard, PoisonError},
+  time::Duration
+};
+use tokio::{
+  net::TcpListener,
+  select, signal,
+  sync::mpsc::{Receiver, channel},
+  time::{Instant, sleep, sleep_until}
+};
+use tracing::{error, info};
+
+/// The name of the daemon status file within the state directory.
+const STATUS_FILE: &str = "daemon.json";
+
+/// The name of the daemon request file within the state directory.
+const REQUEST_FILE: &str = "daemon-request.json";
+
+/// How often the daemon checks for requests and due changes.
+pub const POLL: Duration = Duration::from_secs(2);
+
+/// How often the daemon refreshes its heartbeat. A daemon whose heartbeat is
+/// older than three times this is considered dead.
+const HEARTBEAT: TimeDelta = TimeDelta::seconds(15);
+
+/// A command sent to the running daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+  /// Change the wallpaper now and restart the interval.
+  Next,
+  /// Shut the daemon down.
+  Stop
+}
+
+impl Request {
+  /// Returns the path of the request file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(REQUEST_FILE)
+  }
+
+  /// Leaves the request for the daemon to pick up.
+  pub fn send(self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), &Some(self))
+  }
+
+  /// Removes and returns the pending request, if any.
+  pub fn take(path_config: &Path) -> Result<Option<Self>> {
+    let request: Option<Self> = store::load(&Self::file(path_config))?;
+    if request.is_some() {
+      store::save(&Self::file(path_config), &None::<Self>)?;
+    }
+    Ok(request)
+  }
+}
+
+/// Describes the running daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Running {
+  pub pid: u32,
+  pub started_at: DateTime<Local>,
+  pub heartbeat: DateTime<Local>,
+  /// When the wallpaper is next due to change, if it changes on a timer.
+  pub next_change: Option<DateTime<Local>>
+}
+
+impl Running {
+  /// Returns the path of the daemon status file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(STATUS_FILE)
+  }
+
+  /// Loads the daemon status, returning `None` if no live daemon has
+  /// published one.
+  pub fn load(path_config: &Path) -> Result<Option<Self>> {
+    let running: Option<Self> = store::load(&Self::file(path_config))?;
+    Ok(running.filter(Self::is_alive))
+  }
+
+  /// Publishes the daemon status.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), &Some(self))
+  }
+
+  /// Withdraws the daemon status when the daemon exits.
+  pub fn clear(path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), &None::<Self>)
+  }
+
+  /// Checks whether the heartbeat is recent enough for the daemon to still
+  /// be running.
+  pub fn is_alive(&self) -> bool {
+    Local::now() - self.heartbeat < HEARTBEAT * 3
+  }
+}
+
+/// Runs the slideshow in the current process until it is asked to stop or
+/// interrupted.
+///
+/// Every monitor advances to its next wallpaper each time the configured
+/// interval elapses and, depending on `slideshow.trigger`, whenever the
+/// session is unlocked or the machine resumes, unless rotation is snoozed.
+/// With `slideshow.idle` enabled, timed changes wait until the user has been
+/// idle for a while. A [Request::Next] changes the wallpapers immediately and
+/// restarts the interval. New images in the watch folders are imported into
+/// the library as they arrive, and failed operations are
+/// [retried](super::retries) as they fall due.
+///
+/// With `slideshow.mirror.serve` set, the daemon also serves its changes to
+/// other machines. With `slideshow.mirror.follow` set, it shows what the
+/// primary shows instead of rotating on a timer or on unlock.
+///
+/// With `slideshow.workspaces` enabled under Hyprland or Sway, each
+/// workspace shows its own wallpaper once focus has rested on it for the
+/// debounce time.
+pub async fn run(config: &Config) -> Result<()> {
+  if let Some(other) = Running::load(&config.path)? {
+    return Err(Error::Config(format!(
+      "The slideshow daemon is already running (pid {})",
+      other.pid
+    )));
+  }
+
+  let interval = TimeDelta::from_std(config.slideshow.interval.to_duration())
+    .map_err(|e| {
+    Error::Settings(format!("Invalid slideshow interval: {e}"))
+  })?;
+  let trigger = config.slideshow.trigger;
+  let mirror = &config.slideshow.mirror;
+  let following = mirror.follow.is_some();
+  let schedule = |from: DateTime<Local>| {
+    (trigger.on_timer() && !following).then(|| from + interval)
+  };
+  let mut watcher = (trigger.on_unlock() && !following).then(Watcher::new);
+  let mut folders = Folders::new();
+
+  if let Some(address) = &mirror.serve {
+    let listener = TcpListener::bind(address).await?;
+    info!("Serving wallpaper changes on {address}");
+    tokio::spawn(mirror::serve(listener, config.path.clone()));
+  }
+  let mut primary = NowShowing::default();
+  let mut events = mirror.follow.clone().map(|url| {
+    let (sender, receiver) = channel(8);
+    tokio::spawn(mirror::follow(url, sender));
+    receiver
+  });
+  let workspaces = &config.slideshow.workspaces;
+  let mut focus_events = workspaces.enabled.then(workspace::listen).flatten();
+  let mut tracker = Tracker::new();
+  let mut settling: Option<(Focus, Instant)> = None;
+
+  let now = Local::now();
+  let running = Arc::new(Mutex::new(Running {
+    pid: process::id(),
+    started_at: now,
+    heartbeat: now,
+    next_change: schedule(now)
+  }));
+  Request::take(&config.path)?;
+  lock(&running).save(&config.path)?;
+  //{ Beat on a task of its own, so a long rotation never looks like a dead
+  //  daemon and lets a second one start }
+  let heartbeat = tokio::spawn(beat(running.clone(), config.path.clone()));
+
+  //{ Listen from the start, so an interrupt during a rotation is not lost }
+  let mut interrupted = pin!(signal::ctrl_c());
+  let _ = interrupted.as_mut().now_or_never();
+
+  let result: Result<()> = async {
+    loop {
+      let request = Request::take(&config.path)?;
+      if request == Some(Request::Stop) {
+        break Ok(());
+      }
+
+      let now = Local::now();
+      let next_change = lock(&running).next_change;
+      let mut due = next_change.is_some_and(|at| now >= at);
+      let woke = watcher.as_mut().is_some_and(Watcher::poll);
+
+      //{ Hold a timed change back until the user has been idle long enough }
+      if due && !woke && request.is_none() {
+        if let Some(wait) = idle_wait(config) {
+          lock(&running).next_change = Some(now + wait);
+          due = false;
+        }
+      }
+
+      if request == Some(Request::Next)
+        || ((due || woke) && !State::load(&config.path)?.is_snoozed())
+      {
+        let trigger = if request == Some(Request::Next) {
+          Trigger::Request
+        } else if woke {
+          Trigger::Unlock
+        } else {
+          Trigger::Timer
+        };
+        with_trigger(trigger, rotate(config)).await;
+      }
+      match folders.poll(config) {
+        Ok(added) if config.slideshow.watch.set_immediately =>
+          if let Some(image) = added.last() {
+            with_trigger(Trigger::Watch, apply(config, image.clone())).await;
+          },
+        Ok(_) => {}
+        Err(e) => error!("Failed to scan the watch folders: {e}")
+      }
+      if let Err(e) = retry_due(config).await {
+        error!("Failed to retry failed operations: {e}");
+      }
+      if request.is_some() || due || woke {
+        let mut running = lock(&running);
+        running.next_change = schedule(Local::now());
+        running.heartbeat = Local::now();
+        running.save(&config.path)?;
+      }
+
+      let settled_at = settling.as_ref().map(|(_, at)| *at);
+      select! {
+        _ = sleep(POLL) => {}
+        Some(next) = next_event(&mut events) => {
+          let followed =
+            with_trigger(Trigger::Mirror, follow(config, &primary, &next));
+          match followed.await {
+            Ok(()) => primary = next,
+            Err(e) => error!("Failed to follow the primary: {e}")
+          }
+        }
+        Some(focus) = next_event(&mut focus_events) => {
+          settling = Some((focus, Instant::now() + workspaces.debounce.to_duration()));
+        }
+        _ = sleep_until(settled_at.unwrap_or_else(Instant::now)), if settled_at.is_some() => {
+          if let Some((focus, _)) = settling.take() {
+            let switched =
+              with_trigger(Trigger::Workspace, tracker.switch(config, &focus));
+            if let Err(e) = switched.await {
+              error!("Failed to switch to the wallpaper of {}: {e}", focus.workspace);
+            }
+          }
+        }
+        _ = interrupted.as_mut() => break Ok(())
+      }
+    }
+  }
+  .await;
+
+  //{ Let a save in progress finish, so it can't undo the clearing }
+  heartbeat.abort();
+  let _ = heartbeat.await;
+  Running::clear(&config.path)?;
+  result
+}
+
+/// Locks the shared daemon status, even if a panic poisoned it.
+fn lock(running: &Mutex<Running>) -> MutexGuard<'_, Running> {
+  running.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Refreshes the heartbeat of `running` every [HEARTBEAT] until aborted.
+async fn beat(running: Arc<Mutex<Running>>, path_config: Path) {
+  let period = HEARTBEAT.to_std().unwrap_or(POLL);
+  loop {
+    sleep(period).await;
+    let mut running = lock(&running);
+    running.heartbeat = Local::now();
+    if let Err(e) = running.save(&path_config) {
+      error!("Failed to refresh the daemon heartbeat: {e}");
+    }
+  }
+}
+
+/// Returns how much longer the user must stay idle before a timed change, or
+/// `None` if the change can go ahead. Changes are never held back when the
+/// idle time can't be determined.
+fn idle_wait(config: &Config) -> Option<TimeDelta> {
+  let settings = config.slideshow.idle;
+  if !settings.enabled {
+    return None;
+  }
+  let idle = idle_time()?;
+  let remaining = settings.min_idle.to_duration().checked_sub(idle)?;
+  TimeDelta::from_std(remaining)
+    .ok()
+    .filter(|remaining| !remaining.is_zero())
+}
+
+/// Waits for the next event from `events`, or forever when there are none
+/// to wait for, such as when not following a primary.
+async fn next_event<T>(events: &mut Option<Receiver<T>>) -> Option<T> {
+  match events {
+    Some(events) => events.recv().await,
+    None => pending().await
+  }
+}
+
+/// Applies what changed on the primary, unless rotation is snoozed.
+async fn follow(
+  config: &Config,
+  previous: &NowShowing,
+  next: &NowShowing
+) -> Result<()> {
+  if State::load(&config.path)?.is_snoozed() {
+    return Ok(());
+  }
+  let changes =
+    mirror::changes(&config.slideshow.mirror, &config.monitors, previous, next);
+  for (monitor, source) in changes {
+    match wallpaper::set(config, &source, Some(&monitor)).await {
+      Ok(_) =>
+        info!(monitor = %monitor, "Now showing from the primary: {source}"),
+      Err(e) => error!(monitor = %monitor, "Failed to apply {source}: {e}")
+    }
+  }
+  Ok(())
+}
+
+/// Advances every monitor, reporting failures without stopping the daemon.
+async fn rotate(config: &Config) {
+  for monitor in &config.monitors {
+    let name = &monitor.name;
+    match advance(config, monitor).await {
+      Ok(Some(source)) => info!(monitor = %name, "Now showing: {source}"),
+      Ok(None) => info!(monitor = %name, "No other wallpapers available"),
+      Err(e) => error!(monitor = %name, "Failed to change wallpaper: {e}")
+    }
+  }
+}
+
+/// Retries the failed operations that are due, unless rotation is snoozed.
+async fn retry_due(config: &Config) -> Result<()> {
+  let mut retries = Retries::load(&config.path)?;
+  let due = retries.next_due().is_some_and(|at| at <= Local::now());
+  if !due || State::load(&config.path)?.is_snoozed() {
+    return Ok(());
+  }
+  retries.run(config, false).await;
+  retries.save(&config.path)
+}
+
+/// Applies a newly imported image to every monitor.
+async fn apply(config: &Config, image: PathBuf) {
+  let source = Source::File(image);
+  match wallpaper::set(config, &source, None).await {
+    Ok(_) => info!("Now showing from the watch folders: {source}"),
+    Err(e) => error!("Failed to apply {source}: {e}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_stale_heartbeat_is_not_alive() {
+    let now = Local::now();
+    let mut running = Running {
+      pid: 1,
+      started_at: now,
+      heartbeat: now,
+      next_change: None
+    };
+    assert!(running.is_alive());
+
+    running.heartbeat = now - HEARTBEAT * 4;
+    assert!(!running.is_alive());
+  }
+}
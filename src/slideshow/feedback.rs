@@ -0,0 +1,171 @@
+//! Learns from skipped wallpapers to weight future selections.
+//!
+//! A skipped image becomes less likely to be picked again. A reason given
+//! for skips says the dislike is about more than the one image, so the
+//! reason given most often for images from the same directory lowers the
+//! weight of the rest of that directory too.
+
+use crate::{Result, config::Path, utils::store};
+use serde::{Deserialize, Serialize};
+use std::{
+  cmp::Reverse,
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::{Path as FilePath, PathBuf}
+};
+
+/// The name of the feedback file within the state directory.
+const FEEDBACK_FILE: &str = "feedback.json";
+
+/// The reason recorded when none is given.
+pub const UNSPECIFIED_REASON: &str = "unspecified";
+
+/// How much each skip of a neighbouring image for a shared reason weighs
+/// against an image, relative to a skip of the image itself.
+const SHARED_PENALTY: f64 = 0.25;
+
+/// Skip history for a single image.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rating {
+  /// How often the image was skipped.
+  pub skips: u32,
+  /// How often each reason was given.
+  pub reasons: BTreeMap<String, u32>
+}
+
+/// Skip reasons accumulated per image, used to weight the random picker.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Feedback {
+  pub images: BTreeMap<PathBuf, Rating>
+}
+
+impl Feedback {
+  /// Returns the path of the feedback file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(FEEDBACK_FILE)
+  }
+
+  /// Loads the feedback from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the feedback to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records that `image` was skipped, optionally with a reason.
+  pub fn record_skip(&mut self, image: &FilePath, reason: Option<&str>) {
+    let rating = self.images.entry(image.to_path_buf()).or_default();
+    rating.skips += 1;
+    let reason = reason
+      .map(|r| r.trim().to_lowercase())
+      .filter(|r| !r.is_empty())
+      .unwrap_or_else(|| UNSPECIFIED_REASON.to_string());
+    *rating.reasons.entry(reason).or_default() += 1;
+  }
+
+  /// Returns the selection weight of `image`. Images start at a weight of 1
+  /// and become less likely to be picked each time they are skipped, or
+  /// each time an image beside them is skipped for their [shared
+  /// reason](Self::shared_reason).
+  pub fn weight(&self, image: &FilePath) -> f64 {
+    let skips = self.images.get(image).map_or(0, |rating| rating.skips);
+    let shared = self.shared_reason(image).map_or(0, |(_, count)| count);
+    1.0 / (1.0 + f64::from(skips)) / (1.0 + SHARED_PENALTY * f64::from(shared))
+  }
+
+  /// Returns the reason given most often for skipping the other images in
+  /// the directory of `image`, with how often it was given. Skips without a
+  /// reason are left out.
+  pub fn shared_reason(&self, image: &FilePath) -> Option<(String, u32)> {
+    let directory = image.parent()?;
+    let mut totals: BTreeMap<&str, u32> = BTreeMap::new();
+    for (other, rating) in &self.images {
+      if other == image || other.parent() != Some(directory) {
+        continue;
+      }
+      for (reason, count) in &rating.reasons {
+        if reason != UNSPECIFIED_REASON {
+          *totals.entry(reason).or_default() += count;
+        }
+      }
+    }
+    totals
+      .into_iter()
+      .max_by_key(|(_, count)| *count)
+      .map(|(reason, count)| (reason.to_string(), count))
+  }
+
+  /// Returns how often each reason was given across all images, most common
+  /// first.
+  pub fn reasons(&self) -> Vec<(String, u32)> {
+    let mut totals: BTreeMap<String, u32> = BTreeMap::new();
+    for rating in self.images.values() {
+      for (reason, count) in &rating.reasons {
+        *totals.entry(reason.clone()).or_default() += count;
+      }
+    }
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by_key(|(_, count)| Reverse(*count));
+    totals
+  }
+}
+
+impl Display for Feedback {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let reasons = self.reasons();
+    if reasons.is_empty() {
+      return printh!(f, "No skips recorded");
+    }
+    for (reason, count) in reasons {
+      printf!(f, &reason, count)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_skips_lower_weight() {
+    let mut feedback = Feedback::default();
+    let image = FilePath::new("bright.jpg");
+    assert_eq!(feedback.weight(image), 1.0);
+
+    feedback.record_skip(image, Some("Too-Bright"));
+    feedback.record_skip(image, None);
+    assert!(feedback.weight(image) < 0.5);
+    assert_eq!(
+      feedback.reasons(),
+      vec![("too-bright".into(), 1), (UNSPECIFIED_REASON.into(), 1)]
+    );
+  }
+
+  #[test]
+  fn test_reasons_spread_to_neighbours() {
+    let mut feedback = Feedback::default();
+    let bright = FilePath::new("/walls/beach/noon.jpg");
+    feedback.record_skip(bright, Some("too-bright"));
+    feedback
+      .record_skip(FilePath::new("/walls/beach/dunes.jpg"), Some("too-bright"));
+    feedback.record_skip(FilePath::new("/walls/beach/pier.jpg"), Some("busy"));
+    feedback.record_skip(FilePath::new("/walls/forest/pines.jpg"), None);
+
+    let sunset = FilePath::new("/walls/beach/sunset.jpg");
+    assert_eq!(
+      feedback.shared_reason(sunset),
+      Some(("too-bright".into(), 2))
+    );
+    assert!(feedback.weight(sunset) < 1.0);
+    assert!(feedback.weight(bright) < feedback.weight(sunset));
+
+    //{ Skips without a reason stay with their image }
+    let moss = FilePath::new("/walls/forest/moss.jpg");
+    assert_eq!(feedback.shared_reason(moss), None);
+    assert_eq!(feedback.weight(moss), 1.0);
+  }
+}
@@ -0,0 +1,249 @@
+//! Keeps the wallpapers of several machines in step.
+//!
+//! A primary daemon serves what it shows over plain HTTP on
+//! `slideshow.mirror.serve`:
+//!
+//! - `GET /now-showing` returns the [NowShowing] record once.
+//! - `GET /events` streams it as line-delimited JSON: the current record right
+//!   away, then a new line every time a wallpaper changes.
+//!
+//! A follower daemon subscribes to the events of `slideshow.mirror.follow`
+//! and applies each change itself, downloading the image from the address
+//! the primary got it from. Images the primary set from local files have no
+//! such address and are skipped. The stream is reopened whenever it drops.
+
+use super::daemon::POLL;
+use crate::{
+  Error, Result,
+  config::{Monitor, Path, slideshow::Mirror},
+  wallpaper::{NowShowing, Source}
+};
+use std::time::Duration;
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+  net::{TcpListener, TcpStream},
+  sync::mpsc::Sender,
+  time::sleep
+};
+use tracing::{debug, info, warn};
+
+/// How long a follower waits before reconnecting to its primary.
+const RECONNECT: Duration = Duration::from_secs(10);
+
+/// Serves the published [NowShowing] record on `listener` until the
+/// process exits.
+pub async fn serve(listener: TcpListener, path_config: Path) {
+  loop {
+    match listener.accept().await {
+      Ok((stream, peer)) => {
+        debug!("Mirror connection from {peer}");
+        let path_config = path_config.clone();
+        tokio::spawn(async move {
+          if let Err(e) = respond(stream, &path_config).await {
+            debug!("Mirror connection from {peer} closed: {e}");
+          }
+        });
+      }
+      Err(e) => warn!("Failed to accept a mirror connection: {e}")
+    }
+  }
+}
+
+/// Answers a single HTTP request.
+async fn respond(stream: TcpStream, path_config: &Path) -> Result<()> {
+  let mut reader = BufReader::new(stream);
+  let mut request = String::new();
+  reader.read_line(&mut request).await?;
+
+  //{ Skip the headers; nothing in them matters here }
+  let mut header = String::new();
+  while reader.read_line(&mut header).await? > 2 {
+    header.clear();
+  }
+
+  let mut stream = reader.into_inner();
+  match route(&request) {
+    Some("/now-showing") => {
+      let body = json(&NowShowing::load(path_config)?)?;
+      stream
+        .write_all(
+          format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+          )
+          .as_bytes()
+        )
+        .await?;
+      Ok(())
+    }
+    Some("/events") => {
+      stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")
+        .await?;
+      stream_events(&mut stream, path_config).await
+    }
+    _ => {
+      stream
+        .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        .await?;
+      Ok(())
+    }
+  }
+}
+
+/// Returns the path of a `GET` request line.
+fn route(request: &str) -> Option<&str> {
+  let mut parts = request.split_whitespace();
+  if parts.next()? != "GET" {
+    return None;
+  }
+  parts
+    .next()
+    .map(|target| target.split('?').next().unwrap_or(target))
+}
+
+/// Writes the record, and then every change to it, until the follower
+/// disconnects.
+async fn stream_events(
+  stream: &mut (impl AsyncWrite + Unpin),
+  path_config: &Path
+) -> Result<()> {
+  let mut sent: Option<NowShowing> = None;
+  loop {
+    let now_showing = NowShowing::load(path_config)?;
+    if sent.as_ref() != Some(&now_showing) {
+      stream
+        .write_all(format!("{}\n", json(&now_showing)?).as_bytes())
+        .await?;
+      stream.flush().await?;
+      sent = Some(now_showing);
+    }
+    sleep(POLL).await;
+  }
+}
+
+fn json(now_showing: &NowShowing) -> Result<String> {
+  serde_json::to_string(now_showing).map_err(|e| Error::Config(e.to_string()))
+}
+
+/// Subscribes to the primary at `url`, passing on every record it sends.
+/// Reconnects when the stream drops, and returns once `sender` is closed.
+pub async fn follow(url: String, sender: Sender<NowShowing>) {
+  let events = format!("{}/events", url.trim_end_matches('/'));
+  let client = reqwest::Client::new();
+  loop {
+    match subscribe(&client, &events, &sender).await {
+      Ok(()) if sender.is_closed() => return,
+      Ok(()) => info!("The primary at {url} closed the stream"),
+      Err(e) => warn!("Failed to follow {url}: {e}")
+    }
+    sleep(RECONNECT).await;
+  }
+}
+
+/// Reads the event stream until it ends.
+async fn subscribe(
+  client: &reqwest::Client,
+  events: &str,
+  sender: &Sender<NowShowing>
+) -> Result<()> {
+  let mut response = client.get(events).send().await?.error_for_status()?;
+  info!("Following {events}");
+  let mut buffer = Vec::new();
+  while let Some(chunk) = response.chunk().await? {
+    buffer.extend_from_slice(&chunk);
+    while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+      let line: Vec<u8> = buffer.drain(..=end).collect();
+      match serde_json::from_slice::<NowShowing>(&line) {
+        Ok(now_showing) =>
+          if sender.send(now_showing).await.is_err() {
+            return Ok(());
+          },
+        Err(e) => warn!("Ignoring an unreadable mirror event: {e}")
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Works out what to apply locally when the primary goes from `previous` to
+/// `next`: the local monitor name and the image for every monitor that
+/// changed.
+pub fn changes(
+  mirror: &Mirror,
+  monitors: &[Monitor],
+  previous: &NowShowing,
+  next: &NowShowing
+) -> Vec<(String, Source)> {
+  next
+    .monitors
+    .iter()
+    .filter(|(name, showing)| previous.monitors.get(*name) != Some(showing))
+    .filter_map(|(name, showing)| {
+      let local = mirror.monitors.get(name).unwrap_or(name);
+      if !monitors.iter().any(|monitor| monitor.name == *local) {
+        debug!("No local monitor follows '{name}'");
+        return None;
+      }
+      let Some(url) = &showing.url else {
+        warn!(
+          "Skipping {} on '{name}': it was set from a local file on the primary",
+          showing.name
+        );
+        return None;
+      };
+      Some((local.clone(), Source::Url(url.clone())))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::wallpaper::now_showing::Showing;
+  use std::path::PathBuf;
+
+  fn showing(image: &str, url: Option<&str>) -> Showing {
+    Showing::new(
+      PathBuf::from(image),
+      &match url {
+        Some(url) => Source::Url(url.into()),
+        None => Source::File(image.into())
+      }
+    )
+  }
+
+  #[test]
+  fn test_route() {
+    assert_eq!(route("GET /events HTTP/1.1\r\n"), Some("/events"));
+    assert_eq!(route("GET /now-showing?x=1 HTTP/1.1"), Some("/now-showing"));
+    assert_eq!(route("POST /events HTTP/1.1"), None);
+    assert_eq!(route(""), None);
+  }
+
+  #[test]
+  fn test_changes() {
+    let mirror = Mirror {
+      monitors: [("DP-1".to_string(), "eDP-1".to_string())].into(),
+      ..Mirror::default()
+    };
+    let monitors = [
+      Monitor::test("eDP-1", 1920, 1080),
+      Monitor::test("HDMI-1", 1920, 1080)
+    ];
+    let mut previous = NowShowing::default();
+    previous.insert("HDMI-1", showing("a.jpg", Some("https://x/a.jpg")));
+
+    let mut next = previous.clone();
+    next.insert("DP-1", showing("b.jpg", Some("https://x/b.jpg")));
+    next.insert("DP-2", showing("c.jpg", Some("https://x/c.jpg")));
+    next.insert("HDMI-2", showing("d.jpg", None));
+
+    //{ Only the changed, mapped, downloadable monitor is applied }
+    assert_eq!(
+      changes(&mirror, &monitors, &previous, &next),
+      [("eDP-1".to_string(), Source::Url("https://x/b.jpg".into()))]
+    );
+    assert!(changes(&mirror, &monitors, &next, &next).is_empty());
+  }
+}
@@ -0,0 +1,42 @@
+//! The slideshow engine: decides which wallpaper is shown next and rotates
+//! wallpapers on a schedule.
+
+pub mod feedback;
+pub use feedback::Feedback;
+
+pub mod queue;
+pub use queue::{Item as QueueItem, Queue};
+
+pub mod prefetch;
+pub use prefetch::{Prefetch, prefetch};
+
+mod select;
+pub use select::{advance, next};
+
+#[cfg(feature = "daemon")]
+pub mod daemon;
+
+#[cfg(feature = "daemon")]
+pub mod session;
+
+#[cfg(feature = "daemon")]
+pub mod mirror;
+
+pub mod retries;
+pub use retries::Retries;
+
+mod state;
+pub use state::{RandomPage, RandomPages, State};
+
+pub mod theme;
+
+pub mod tuner;
+pub use tuner::Tuner;
+
+pub mod variety;
+
+#[cfg(feature = "daemon")]
+pub mod watch;
+
+#[cfg(feature = "daemon")]
+pub mod workspace;
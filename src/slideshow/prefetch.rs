@@ -0,0 +1,132 @@
+//! Prepares upcoming wallpapers ahead of time.
+//!
+//! Searching and downloading can take a while, so the next wallpaper for each
+//! monitor is fetched right after a change. At the following change it is
+//! already on disk and can be applied instantly. Network prefetches are
+//! limited by `slideshow.prefetch.min_gap`.
+
+use super::{
+  retries::{self, Operation},
+  select::pick
+};
+use crate::{
+  Config, Result,
+  config::{Monitor, Path},
+  utils::store,
+  wallpaper::{self, Source}
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the prefetch state file within the state directory.
+const PREFETCH_FILE: &str = "prefetch.json";
+
+/// A wallpaper that is downloaded and waiting to be shown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ready {
+  /// The local image file.
+  pub image: PathBuf,
+  /// Where the image came from, for display.
+  pub origin: String,
+  pub prepared_at: DateTime<Local>
+}
+
+/// The prefetched wallpapers, keyed by monitor name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prefetch {
+  pub monitors: BTreeMap<String, Ready>,
+  /// When a wallpaper was last downloaded ahead of time.
+  pub last_download: Option<DateTime<Local>>
+}
+
+impl Prefetch {
+  /// Returns the path of the prefetch state file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(PREFETCH_FILE)
+  }
+
+  /// Loads the prefetch state from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the prefetch state to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Removes and returns the wallpaper prepared for `monitor`, if its image
+  /// still exists.
+  pub fn take(path_config: &Path, monitor: &str) -> Result<Option<Ready>> {
+    let mut prefetch = Self::load(path_config)?;
+    let Some(ready) = prefetch.monitors.remove(monitor) else {
+      return Ok(None);
+    };
+    prefetch.save(path_config)?;
+    Ok(ready.image.is_file().then_some(ready))
+  }
+}
+
+/// Prepares the next wallpaper for `monitor` if none is waiting yet.
+///
+/// Returns the prepared image, or `None` if prefetching is disabled, there
+/// is nothing to show, or a network prefetch happened too recently.
+pub async fn prefetch(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<PathBuf>> {
+  let settings = config.slideshow.prefetch;
+  if !settings.enabled {
+    return Ok(None);
+  }
+
+  let mut state = Prefetch::load(&config.path)?;
+  if let Some(ready) = state.monitors.get(&monitor.name) {
+    if ready.image.is_file() {
+      return Ok(Some(ready.image.clone()));
+    }
+  }
+
+  //{ Check the rate limit before picking, so no queued item is lost }
+  let min_gap = chrono::Duration::from_std(settings.min_gap.to_duration())
+    .unwrap_or(chrono::Duration::MAX);
+  if state
+    .last_download
+    .is_some_and(|last| Local::now() - last < min_gap)
+  {
+    return Ok(None);
+  }
+
+  let Some(source) = pick(config, monitor).await? else {
+    return Ok(None);
+  };
+  let image = match wallpaper::fetch(config, &source, monitor).await {
+    Ok(image) => image,
+    Err(e) => {
+      //{ A failed download is retried later into the download directory }
+      if let Source::Url(url) = &source {
+        let operation = Operation::Download {
+          url: url.clone(),
+          monitor: monitor.name.clone()
+        };
+        retries::schedule(&config.path, operation, &e);
+      }
+      return Err(e);
+    }
+  };
+  if matches!(source, Source::Url(_)) {
+    state.last_download = Some(Local::now());
+  }
+
+  state.monitors.insert(
+    monitor.name.clone(),
+    Ready {
+      image: image.clone(),
+      origin: source.to_string(),
+      prepared_at: Local::now()
+    }
+  );
+  state.save(&config.path)?;
+  Ok(Some(image))
+}
@@ -0,0 +1,167 @@
+//! A persistent queue of wallpapers lined up for upcoming rotations.
+
+use crate::{
+  Config, Error, Result,
+  api::wallhaven::{self, Api as Wallhaven},
+  config::Path,
+  utils::{files, store},
+  wallpaper::Source
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::VecDeque,
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+
+/// The name of the queue file within the state directory.
+const QUEUE_FILE: &str = "queue.json";
+
+/// A queued wallpaper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Item {
+  /// A local image file.
+  File(PathBuf),
+  /// A Wallhaven wallpaper id, resolved to its image when dequeued.
+  Wallhaven(String)
+}
+
+impl Item {
+  /// Interprets `value` as an existing image file or a Wallhaven id.
+  pub fn parse(value: &str) -> Result<Self> {
+    let path = PathBuf::from(value);
+    if path.is_file() {
+      if !files::is_image(&path) {
+        return Err(Error::Settings(format!(
+          "Not a supported image file: {value}"
+        )));
+      }
+      return Ok(Self::File(path.canonicalize()?));
+    }
+
+    //{ Accept both bare ids and "wallhaven-<id>" file stems }
+    let id = value.strip_prefix("wallhaven-").unwrap_or(value);
+    if wallhaven::is_full_id(id) {
+      return Ok(Self::Wallhaven(id.to_lowercase()));
+    }
+
+    Err(Error::Settings(format!(
+      "'{value}' is neither an image file nor a Wallhaven id"
+    )))
+  }
+
+  /// Resolves the item to a wallpaper source, looking up Wallhaven ids.
+  pub async fn resolve(&self, config: &Config) -> Result<Source> {
+    match self {
+      Self::File(path) => Ok(Source::File(path.clone())),
+      Self::Wallhaven(id) => {
+        let api_key = config
+          .source
+          .find("wallhaven")
+          .and_then(|source| source.api_key());
+        let details = Wallhaven::new(api_key).get_wallpaper_details(id).await?;
+        Ok(Source::Url(details.path))
+      }
+    }
+  }
+}
+
+impl Display for Item {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::File(path) => write!(f, "{}", path.display()),
+      Self::Wallhaven(id) => write!(f, "wallhaven:{id}")
+    }
+  }
+}
+
+/// Wallpapers lined up to be shown before the slideshow picks at random.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Queue {
+  pub items: VecDeque<Item>
+}
+
+impl Queue {
+  /// Returns the path of the queue file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(QUEUE_FILE)
+  }
+
+  /// Loads the queue from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the queue to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Appends an item to the end of the queue.
+  pub fn push(&mut self, item: Item) {
+    self.items.push_back(item);
+  }
+
+  /// Removes and returns the item at the front of the queue.
+  pub fn pop(&mut self) -> Option<Item> {
+    self.items.pop_front()
+  }
+
+  /// Removes all items from the queue.
+  pub fn clear(&mut self) {
+    self.items.clear();
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+}
+
+impl Display for Queue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.items.is_empty() {
+      return printh!(f, "The queue is empty");
+    }
+    for (i, item) in self.items.iter().enumerate() {
+      printf!(f, &format!("{}", i + 1), item, 4)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_item_parse_wallhaven_id() {
+    assert_eq!(
+      Item::parse("94X38z").unwrap(),
+      Item::Wallhaven("94x38z".into())
+    );
+    assert_eq!(
+      Item::parse("wallhaven-2y6wwg").unwrap(),
+      Item::Wallhaven("2y6wwg".into())
+    );
+    assert!(Item::parse("not/an/image.png").is_err());
+    assert!(Item::parse("sunsets").is_err());
+    assert!(Item::parse("94x38zz").is_err());
+  }
+
+  #[test]
+  fn test_queue_order() {
+    let mut queue = Queue::default();
+    queue.push(Item::Wallhaven("first".into()));
+    queue.push(Item::Wallhaven("second".into()));
+
+    assert_eq!(queue.pop(), Some(Item::Wallhaven("first".into())));
+    assert_eq!(queue.len(), 1);
+    queue.clear();
+    assert!(queue.pop().is_none());
+  }
+}
@@ -0,0 +1,381 @@
+//! A persistent queue of failed operations, retried with backoff.
+//!
+//! Downloads, wallpaper changes and notifications that fail are not lost:
+//! they are kept in the state directory and tried again after
+//! [BACKOFF], doubling after each failure up to [MAX_BACKOFF]. After
+//! [MAX_ATTEMPTS] failures an operation is given up on, and so is one that
+//! failed for a reason retrying cannot fix, such as a missing image. An
+//! operation [deferred](Error::Deferred), such as a notification while the
+//! desktop is set not to disturb, is tried again every [DEFERRAL] without
+//! counting as a failure. A wallpaper change is dropped instead once another
+//! wallpaper has been shown on its monitor, so a stale image never replaces a
+//! newer one. The slideshow daemon retries operations as they fall due, and
+//! `wallter retry now` retries every one of them straight away.
+
+use crate::{
+  Config, Error, Result,
+  config::Path,
+  utils::{notify, store},
+  wallpaper::{self, Applied, Current, Source}
+};
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+use tracing::{info, warn};
+
+/// The name of the retry queue within the state directory.
+const RETRIES_FILE: &str = "retries.json";
+
+/// How long after the first failure an operation is retried.
+pub const BACKOFF: TimeDelta = TimeDelta::minutes(1);
+
+/// The longest wait between retries.
+pub const MAX_BACKOFF: TimeDelta = TimeDelta::hours(1);
+
+/// How long a deferred operation waits before it is tried again.
+pub const DEFERRAL: TimeDelta = TimeDelta::minutes(5);
+
+/// How many times an operation may fail before it is given up on.
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// Something that failed and can be tried again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+  /// Download an image into a monitor's download directory.
+  Download { url: String, monitor: String },
+  /// Show an image, on one monitor or on all of them.
+  Apply {
+    source: String,
+    monitor: Option<String>
+  },
+  /// Show a desktop notification.
+  Notify { title: String, body: String }
+}
+
+impl Operation {
+  /// Tries the operation once.
+  async fn perform(&self, config: &Config) -> Result<()> {
+    match self {
+      Self::Download { url, monitor } => {
+        let target = config
+          .monitors
+          .iter()
+          .find(|m| &m.name == monitor)
+          .ok_or_else(|| {
+            Error::Settings(format!("Monitor '{monitor}' is not connected"))
+          })?;
+        wallpaper::fetch(config, &Source::Url(url.clone()), target).await?;
+      }
+      Self::Apply { source, monitor } => {
+        wallpaper::set(config, &Source::parse(source), monitor.as_deref())
+          .await?;
+      }
+      Self::Notify { title, body } => notify::send(title, body)?
+    }
+    Ok(())
+  }
+
+  /// Whether this and `other` show an image on the same monitor, so only
+  /// the later of them is worth retrying.
+  fn replaces(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Self::Apply { monitor, .. }, Self::Apply { monitor: other, .. }) =>
+        monitor.is_none() || other.is_none() || monitor == other,
+      _ => false
+    }
+  }
+}
+
+impl Display for Operation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Download { url, monitor } =>
+        write!(f, "Download {url} for {monitor}"),
+      Self::Apply {
+        source,
+        monitor: Some(monitor)
+      } => write!(f, "Show {source} on {monitor}"),
+      Self::Apply {
+        source,
+        monitor: None
+      } => write!(f, "Show {source}"),
+      Self::Notify { title, .. } => write!(f, "Notify '{title}'")
+    }
+  }
+}
+
+/// An operation waiting to be retried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+  pub operation: Operation,
+  /// How many times the operation has failed.
+  pub attempts: u32,
+  /// When the operation is next due.
+  pub next_at: DateTime<Local>,
+  /// Why the operation last failed.
+  pub last_error: String,
+  /// When the operation first failed.
+  #[serde(default = "Local::now")]
+  pub queued_at: DateTime<Local>
+}
+
+impl Job {
+  /// Counts another failure and pushes the next attempt back.
+  fn failed(&mut self, error: impl Display) {
+    self.attempts += 1;
+    self.next_at = Local::now() + delay(self.attempts);
+    self.last_error = error.to_string();
+  }
+
+  /// Whether another wallpaper has been shown on the monitor this job would
+  /// change since it first failed.
+  fn is_superseded(&self, current: &Current) -> bool {
+    let Operation::Apply { monitor, .. } = &self.operation else {
+      return false;
+    };
+    let since = |applied: &Applied| applied.since > self.queued_at;
+    match monitor {
+      Some(monitor) => current.get(monitor).is_some_and(since),
+      None => current.monitors.values().any(since)
+    }
+  }
+}
+
+/// Returns the wait after the `attempts`th failure.
+fn delay(attempts: u32) -> TimeDelta {
+  let factor = 2i32.saturating_pow(attempts.saturating_sub(1).min(16));
+  (BACKOFF * factor).min(MAX_BACKOFF)
+}
+
+/// How a round of retries went.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+  pub succeeded: usize,
+  /// Failed again, to be retried later.
+  pub failed: usize,
+  /// Failed for the last time.
+  pub abandoned: usize,
+  /// Held back, to be tried again later.
+  pub deferred: usize
+}
+
+impl Display for Outcome {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} succeeded, {} failed again, {} given up on, {} deferred",
+      self.succeeded, self.failed, self.abandoned, self.deferred
+    )
+  }
+}
+
+/// The operations waiting to be retried, persisted in the state directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retries {
+  pub jobs: Vec<Job>
+}
+
+impl Retries {
+  /// Returns the path of the retry queue.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(RETRIES_FILE)
+  }
+
+  /// Loads the retry queue from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the retry queue to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  pub fn len(&self) -> usize {
+    self.jobs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.jobs.is_empty()
+  }
+
+  /// Returns when the next operation is due, if any are waiting.
+  pub fn next_due(&self) -> Option<DateTime<Local>> {
+    self.jobs.iter().map(|job| job.next_at).min()
+  }
+
+  /// Lines `operation` up for a retry after it failed with `error`. An
+  /// operation already waiting is not added twice, and a wallpaper change
+  /// replaces any older one waiting for the same monitor.
+  pub fn schedule(&mut self, operation: Operation, error: impl Display) {
+    if self.jobs.iter().any(|job| job.operation == operation) {
+      return;
+    }
+    self.jobs.retain(|job| !operation.replaces(&job.operation));
+    let now = Local::now();
+    self.jobs.push(Job {
+      operation,
+      attempts: 1,
+      next_at: now + delay(1),
+      last_error: error.to_string(),
+      queued_at: now
+    });
+  }
+
+  /// Tries the operations that are due, or all of them with `all`, and
+  /// keeps the ones that fail for another attempt.
+  pub async fn run(&mut self, config: &Config, all: bool) -> Outcome {
+    let now = Local::now();
+    let mut outcome = Outcome::default();
+    let mut kept = Vec::new();
+    for mut job in std::mem::take(&mut self.jobs) {
+      if !all && job.next_at > now {
+        kept.push(job);
+        continue;
+      }
+      match Current::load(&config.path) {
+        Ok(current) if job.is_superseded(&current) => {
+          info!("Dropping '{}': a newer wallpaper is showing", job.operation);
+          continue;
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to check the current wallpapers: {e}")
+      }
+      match job.operation.perform(config).await {
+        Ok(()) => {
+          info!("Retried successfully: {}", job.operation);
+          outcome.succeeded += 1;
+        }
+        Err(Error::Deferred(reason)) => {
+          info!("Deferred '{}' again: {reason}", job.operation);
+          job.next_at = Local::now() + DEFERRAL;
+          outcome.deferred += 1;
+          kept.push(job);
+        }
+        Err(e) => {
+          job.failed(&e);
+          if e.is_permanent() {
+            warn!("Giving up on '{}': {e}", job.operation);
+            outcome.abandoned += 1;
+          } else if job.attempts >= MAX_ATTEMPTS {
+            warn!(
+              "Giving up on '{}' after {MAX_ATTEMPTS} attempts: {e}",
+              job.operation
+            );
+            outcome.abandoned += 1;
+          } else {
+            warn!("Retrying '{}' failed again: {e}", job.operation);
+            outcome.failed += 1;
+            kept.push(job);
+          }
+        }
+      }
+    }
+    self.jobs = kept;
+    outcome
+  }
+
+  pub fn clear(&mut self) {
+    self.jobs.clear();
+  }
+}
+
+impl Display for Retries {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.jobs.is_empty() {
+      return printh!(f, "Nothing to retry");
+    }
+    for job in &self.jobs {
+      printf!(
+        f,
+        &job.operation.to_string(),
+        format!(
+          "attempt {} of {MAX_ATTEMPTS} at {} ({})",
+          job.attempts + 1,
+          job.next_at.format("%Y-%m-%d %H:%M"),
+          job.last_error
+        )
+      )?;
+    }
+    Ok(())
+  }
+}
+
+/// Lines `operation` up for a retry after it failed with `error`, unless
+/// the failure is [permanent](Error::is_permanent). A failure to save the
+/// queue is reported rather than returned.
+pub fn schedule(path_config: &Path, operation: Operation, error: &Error) {
+  if error.is_permanent() {
+    return;
+  }
+  let result = Retries::load(path_config).and_then(|mut retries| {
+    retries.schedule(operation, error);
+    retries.save(path_config)
+  });
+  if let Err(e) = result {
+    warn!("Failed to queue the operation for a retry: {e}");
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_schedule_and_backoff() {
+    assert_eq!(delay(1), BACKOFF);
+    assert_eq!(delay(3), BACKOFF * 4);
+    assert_eq!(delay(30), MAX_BACKOFF);
+
+    let mut retries = Retries::default();
+    let operation = Operation::Notify {
+      title: "Theme of the week".into(),
+      body: "mountains".into()
+    };
+    retries.schedule(operation.clone(), "notify-send not found");
+    retries.schedule(operation.clone(), "notify-send not found");
+    assert_eq!(retries.len(), 1);
+    assert!(retries.next_due().is_some_and(|at| at > Local::now()));
+
+    let job = &mut retries.jobs[0];
+    job.failed("still missing");
+    assert_eq!(job.attempts, 2);
+    assert_eq!(job.last_error, "still missing");
+
+    let json = serde_json::to_string(&retries).unwrap();
+    assert!(json.contains(r#""kind":"notify""#));
+    assert_eq!(serde_json::from_str::<Retries>(&json).unwrap(), retries);
+  }
+
+  #[test]
+  fn test_superseded_applies() {
+    let mut retries = Retries::default();
+    let apply = |source: &str, monitor: Option<&str>| Operation::Apply {
+      source: source.into(),
+      monitor: monitor.map(Into::into)
+    };
+    retries.schedule(apply("dunes.jpg", Some("DP-1")), "compositor busy");
+    retries.schedule(apply("fjord.jpg", Some("HDMI-1")), "compositor busy");
+    retries.schedule(apply("cliffs.jpg", Some("DP-1")), "compositor busy");
+    assert_eq!(retries.len(), 2);
+    assert_eq!(retries.jobs[1].operation, apply("cliffs.jpg", Some("DP-1")));
+
+    let job = &retries.jobs[1];
+    let mut current = Current::default();
+    current.monitors.insert(
+      "DP-1".into(),
+      Applied {
+        image: "dunes.jpg".into(),
+        since: job.queued_at - TimeDelta::minutes(1)
+      }
+    );
+    assert!(!job.is_superseded(&current));
+    current.insert("DP-1", "forest.jpg".into());
+    assert!(job.is_superseded(&current));
+    assert!(!retries.jobs[0].is_superseded(&current));
+  }
+}
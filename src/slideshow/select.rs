@@ -0,0 +1,270 @@
+use super::{
+  Feedback, Prefetch, Queue, State, Tuner, prefetch,
+  retries::{self, Operation},
+  theme,
+  variety::Recent
+};
+use crate::{
+  Config, Error, Result,
+  config::Monitor,
+  favorites::Favorites,
+  search::{self, safe::Purities},
+  utils::files,
+  wallpaper::{self, Blocklist, Current, History, Library, Source}
+};
+use rand::{prelude::IndexedRandom, rng};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// The most recently shown images avoided when picking a random one.
+const RECENT_LIMIT: usize = 10;
+
+/// Picks the next wallpaper for `monitor`.
+///
+/// A wallpaper prepared by [prefetch](super::prefetch) is used first, so the
+/// change needs no network access. Otherwise, see [pick].
+pub async fn next(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<Source>> {
+  if let Some(ready) = Prefetch::take(&config.path, &monitor.name)? {
+    return Ok(Some(Source::File(ready.image)));
+  }
+  pick(config, monitor).await
+}
+
+/// Shows the next wallpaper on `monitor`, then prefetches the one after it
+/// so the following change is instant.
+///
+/// Returns the source that was applied, or `None` if there was nothing else
+/// to show. A source that fails to apply is queued for a [retry](retries).
+/// A failed prefetch is reported but not treated as an error.
+pub async fn advance(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<Source>> {
+  let source = next(config, monitor).await?;
+  if let Some(source) = &source {
+    if let Err(e) = wallpaper::set(config, source, Some(&monitor.name)).await {
+      let operation = Operation::Apply {
+        source: source.to_string(),
+        monitor: Some(monitor.name.clone())
+      };
+      retries::schedule(&config.path, operation, &e);
+      return Err(e);
+    }
+    if let Err(e) = record_shown(config, monitor) {
+      warn!(monitor = %monitor.name, "Failed to update the tuner: {e}");
+    }
+  }
+
+  if let Err(e) = prefetch(config, monitor).await {
+    warn!(monitor = %monitor.name, "Failed to prefetch: {e}");
+  }
+  Ok(source)
+}
+
+/// Counts the wallpaper now on `monitor` as shown, for the [Tuner].
+fn record_shown(config: &Config, monitor: &Monitor) -> Result<()> {
+  let Some(applied) = Current::load(&config.path)?.get(&monitor.name).cloned()
+  else {
+    return Ok(());
+  };
+  let mut tuner = Tuner::load(&config.path)?;
+  tuner.record_shown(&applied.image);
+  tuner.save(&config.path)
+}
+
+/// Chooses the next wallpaper for `monitor`, ignoring prefetched ones.
+///
+/// Queued wallpapers are consumed first; one that cannot be resolved for now
+/// stays queued while something else is shown. While a
+/// [collection](crate::wallpaper::collection) is chosen as the slideshow
+/// source, its next image comes next. While a [theme](super::theme) of
+/// the week is set, a random search result matching it comes next, unless
+/// `slideshow.favorites_only` is set. So does a result chosen the way the
+/// first of `slideshow.roles` matching the monitor asks. Otherwise, a random
+/// image is picked from the monitor's download directory, favoring images that
+/// were skipped less often and, with `slideshow.tuning.auto_adjust`, those
+/// whose source and tags the [Tuner] has seen skipped less often. Blocklisted
+/// images are skipped, and so are images not known to be SFW during safe hours.
+/// Images shown recently on the monitor, according to its [History], are
+/// avoided while at least half of the images remain to choose from, and with
+/// `slideshow.variety`, so are images that look too much like the last ones
+/// shown there, as long as any other image will do. If there
+/// are no local images to show, the candidate from the enabled sources that
+/// best fits the monitor is used instead. With `slideshow.favorites_only`, only
+/// the favorites directory is used. Returns `None` if there is nothing to
+/// show.
+pub(crate) async fn pick(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<Source>> {
+  //{ Consume the queue before falling back to random selection }
+  if let Some(item) = Queue::load(&config.path)?.items.front().cloned() {
+    let resolved = item.resolve(config).await;
+    //{ Keep an item that failed to resolve for next time, unless it never
+    //{ will, and show something else meanwhile }
+    if resolved.is_ok() || matches!(resolved, Err(Error::NotFound { .. })) {
+      let mut queue = Queue::load(&config.path)?;
+      if queue.items.front() == Some(&item) {
+        queue.pop();
+        queue.save(&config.path)?;
+      }
+    }
+    match resolved {
+      Ok(source) => return Ok(Some(source)),
+      Err(e) => warn!(monitor = %monitor.name, "Failed to resolve {item}: {e}")
+    }
+  }
+
+  if let Some(image) = from_collection(config, monitor)? {
+    return Ok(Some(Source::File(image)));
+  }
+
+  //{ A theme narrows the week to its search, but never stops the slideshow }
+  if !config.slideshow.favorites_only {
+    if let Some(source) = themed(config, monitor).await {
+      return Ok(Some(source));
+    }
+    if let Some(source) = by_role(config, monitor).await {
+      return Ok(Some(source));
+    }
+  }
+
+  let blocklist = Blocklist::load(&config.path)?;
+  let images: Vec<_> = if config.slideshow.favorites_only {
+    Favorites::images(&config.path)?
+  } else {
+    files::list_images(&config.path.get_download_dir(monitor), false)?
+  };
+  let images: Vec<_> = images
+    .into_iter()
+    .filter(|image| !blocklist.contains(&image.display().to_string()))
+    .collect();
+  if images.is_empty() {
+    if config.slideshow.favorites_only {
+      return Ok(None);
+    }
+    let candidate = search::best_match(config, monitor).await?;
+    return Ok(candidate.map(|c| Source::parse(&c.url)));
+  }
+
+  //{ Avoid repeats, but never narrow the choice to a handful of images }
+  let history = History::load(&config.path)?;
+  let recent =
+    history.recent(&monitor.name, RECENT_LIMIT.min(images.len() / 2));
+  let mut images: Vec<_> = images
+    .into_iter()
+    .filter(|image| !recent.contains(&image))
+    .collect();
+
+  let feedback = Feedback::load(&config.path)?;
+  let tuner = config
+    .slideshow
+    .tuning
+    .auto_adjust
+    .then(|| Tuner::load(&config.path))
+    .transpose()?;
+  let mut purities = Purities::load(&config.path)?;
+  let known = purities.images.len();
+  let variety = Recent::load(config, monitor, &history);
+  let mut alike = None;
+  let picked = loop {
+    let Ok(image) = images.choose_weighted(&mut rng(), |image| {
+      feedback.weight(image) * tuner.as_ref().map_or(1.0, |t| t.weight(image))
+    }) else {
+      break alike;
+    };
+
+    //{ During safe hours, pass over anything not known to be SFW, and
+    //{ keep the first image too like the last ones in case none differ }
+    let image = image.clone();
+    if purities.allows(config, &image).await {
+      if variety.allows(&image) {
+        break Some(image);
+      }
+      alike.get_or_insert_with(|| image.clone());
+    }
+    images.retain(|other| *other != image);
+  };
+  if purities.images.len() != known {
+    purities.save(&config.path)?;
+  }
+
+  match picked {
+    Some(image) => Ok(Some(Source::File(image))),
+    None if config.slideshow.favorites_only => Ok(None),
+    None => {
+      let candidate = search::best_match(config, monitor).await?;
+      Ok(candidate.map(|c| Source::parse(&c.url)))
+    }
+  }
+}
+
+/// Returns the image after the one on `monitor` in the collection chosen as
+/// the slideshow source, if one is chosen and has an image to show.
+fn from_collection(
+  config: &Config,
+  monitor: &Monitor
+) -> Result<Option<PathBuf>> {
+  let Some(name) = State::load(&config.path)?.collection else {
+    return Ok(None);
+  };
+  let library = Library::load(&config.path)?;
+  let Some(collection) = library.collections.get(&name) else {
+    warn!("The slideshow collection '{name}' no longer exists");
+    return Ok(None);
+  };
+  let blocklist = Blocklist::load(&config.path)?;
+  let current = Current::load(&config.path)?
+    .get(&monitor.name)
+    .map(|applied| applied.image.clone());
+  let next = collection.next_after(current.as_deref(), |image| {
+    image.is_file() && !blocklist.contains(&image.display().to_string())
+  });
+  if next.is_none() {
+    warn!("The slideshow collection '{name}' has no images to show");
+  }
+  Ok(next.cloned())
+}
+
+/// Picks a random search result matching this week's theme, reporting
+/// failures instead of returning them.
+async fn themed(config: &Config, monitor: &Monitor) -> Option<Source> {
+  let query = match theme::current(config).await {
+    Ok(query) => query?,
+    Err(e) => {
+      warn!("Failed to choose this week's theme: {e}");
+      return None;
+    }
+  };
+  match search::themed(config, monitor, &query).await {
+    Ok(Some(candidate)) => Some(Source::parse(&candidate.url)),
+    Ok(None) => {
+      warn!("Nothing found for this week's theme '{query}'");
+      None
+    }
+    Err(e) => {
+      warn!("Failed to search for this week's theme: {e}");
+      None
+    }
+  }
+}
+
+/// Picks a search result the way the role rule matching `monitor` asks, if
+/// any, reporting failures instead of returning them.
+async fn by_role(config: &Config, monitor: &Monitor) -> Option<Source> {
+  let rule = config.slideshow.rule_for(monitor)?;
+  match search::for_role(config, monitor, rule).await {
+    Ok(Some(candidate)) => Some(Source::parse(&candidate.url)),
+    Ok(None) => {
+      warn!(monitor = %monitor.name, "Nothing found for the {rule} rule");
+      None
+    }
+    Err(e) => {
+      warn!(monitor = %monitor.name, "Failed to search for the {rule} rule: {e}");
+      None
+    }
+  }
+}
@@ -0,0 +1,92 @@
+use std::{env, process::Command, time::Duration};
+use x11rb::{
+  connection::Connection, protocol::screensaver::ConnectionExt as _
+};
+
+/// Reads the `LockedHint` of the current logind session, which screen
+/// lockers set while the lock screen is shown.
+pub fn is_locked() -> Option<bool> {
+  let session = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".into());
+  let output = Command::new("loginctl")
+    .args(["show-session", &session, "--property=LockedHint", "--value"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())?;
+  match String::from_utf8_lossy(&output.stdout).trim() {
+    "yes" => Some(true),
+    "no" => Some(false),
+    _ => None
+  }
+}
+
+/// Asks GNOME's idle monitor, then the freedesktop screensaver interface
+/// (KDE and others), then the X11 screensaver extension.
+///
+/// Compositors that only offer the Wayland `ext-idle-notify` protocol report
+/// no idle time, so changes are not deferred there.
+pub fn idle_time() -> Option<Duration> {
+  let mutter = dbus_uint(&[
+    "--dest=org.gnome.Mutter.IdleMonitor",
+    "/org/gnome/Mutter/IdleMonitor/Core",
+    "org.gnome.Mutter.IdleMonitor.GetIdletime"
+  ]);
+  if let Some(millis) = mutter {
+    return Some(Duration::from_millis(millis));
+  }
+
+  let screensaver = dbus_uint(&[
+    "--dest=org.freedesktop.ScreenSaver",
+    "/org/freedesktop/ScreenSaver",
+    "org.freedesktop.ScreenSaver.GetSessionIdleTime"
+  ]);
+  if let Some(seconds) = screensaver {
+    return Some(Duration::from_secs(seconds));
+  }
+
+  x11_idle_time()
+}
+
+/// Calls a session bus method that returns a single unsigned integer.
+fn dbus_uint(args: &[&str]) -> Option<u64> {
+  let output = Command::new("dbus-send")
+    .args(["--session", "--print-reply"])
+    .args(args)
+    .output()
+    .ok()
+    .filter(|output| output.status.success())?;
+  parse_uint_reply(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extracts the value from a `dbus-send --print-reply` answer such as
+/// `method return ... \n   uint64 1234`.
+fn parse_uint_reply(reply: &str) -> Option<u64> {
+  reply.lines().find_map(|line| {
+    let line = line.trim();
+    let value = line
+      .strip_prefix("uint64 ")
+      .or_else(|| line.strip_prefix("uint32 "))?;
+    value.trim().parse().ok()
+  })
+}
+
+fn x11_idle_time() -> Option<Duration> {
+  env::var_os("DISPLAY")?;
+  let (conn, screen_num) = x11rb::connect(None).ok()?;
+  let root = conn.setup().roots[screen_num].root;
+  let info = conn.screensaver_query_info(root).ok()?.reply().ok()?;
+  Some(Duration::from_millis(u64::from(info.ms_since_user_input)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_uint_reply() {
+    let reply = "method return time=1.2 sender=:1.5 -> destination=:1.9 \
+                 serial=7 reply_serial=2\n   uint64 83500\n";
+    assert_eq!(parse_uint_reply(reply), Some(83_500));
+    assert_eq!(parse_uint_reply("   uint32 12\n"), Some(12));
+    assert_eq!(parse_uint_reply("   string \"x\"\n"), None);
+  }
+}
@@ -0,0 +1,17 @@
+use std::{process::Command, time::Duration};
+
+/// Reads `HIDIdleTime`, in nanoseconds, from the HID system registry entry.
+pub fn idle_time() -> Option<Duration> {
+  let output = Command::new("ioreg")
+    .args(["-c", "IOHIDSystem", "-d", "4"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())?;
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .find_map(|line| {
+      let (_, value) = line.split_once("\"HIDIdleTime\" = ")?;
+      value.trim().parse().ok()
+    })
+    .map(Duration::from_nanos)
+}
@@ -0,0 +1,97 @@
+//! Notices when the user comes back to the machine, and how long they have
+//! been away.
+//!
+//! A session unlock is detected by polling the platform's lock state, and a
+//! resume from sleep by a jump in wall-clock time between polls, since the
+//! monotonic clock doesn't advance while the machine is suspended.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(all(target_os = "windows", feature = "windows-session"))]
+mod windows;
+
+use chrono::{DateTime, Local, TimeDelta};
+use std::time::Duration;
+
+/// A gap between polls longer than this is treated as a resume from sleep.
+const RESUME_GAP: TimeDelta = TimeDelta::seconds(30);
+
+/// Tracks the session across polls to report unlock and resume events.
+#[derive(Debug)]
+pub struct Watcher {
+  locked: Option<bool>,
+  last_poll: DateTime<Local>
+}
+
+impl Default for Watcher {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Watcher {
+  pub fn new() -> Self {
+    Self {
+      locked: is_locked(),
+      last_poll: Local::now()
+    }
+  }
+
+  /// Returns `true` if the session was unlocked or the machine resumed since
+  /// the previous poll.
+  pub fn poll(&mut self) -> bool {
+    let now = Local::now();
+    let resumed = now - self.last_poll > RESUME_GAP;
+    self.last_poll = now;
+
+    let locked = is_locked();
+    let unlocked = self.locked == Some(true) && locked == Some(false);
+    self.locked = locked;
+    resumed || unlocked
+  }
+}
+
+/// Checks whether the session is locked, if the platform can tell.
+fn is_locked() -> Option<bool> {
+  #[cfg(target_os = "linux")]
+  {
+    linux::is_locked()
+  }
+  #[cfg(all(target_os = "windows", feature = "windows-session"))]
+  {
+    windows::is_locked()
+  }
+  #[cfg(not(any(
+    target_os = "linux",
+    all(target_os = "windows", feature = "windows-session")
+  )))]
+  {
+    None
+  }
+}
+
+/// Returns how long the user has been idle, if the platform can tell.
+pub fn idle_time() -> Option<Duration> {
+  #[cfg(target_os = "linux")]
+  {
+    linux::idle_time()
+  }
+  #[cfg(target_os = "macos")]
+  {
+    macos::idle_time()
+  }
+  #[cfg(all(target_os = "windows", feature = "windows-session"))]
+  {
+    windows::idle_time()
+  }
+  #[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    all(target_os = "windows", feature = "windows-session")
+  )))]
+  {
+    None
+  }
+}
@@ -0,0 +1,44 @@
+//! Detects the lock screen by trying to open the input desktop, which fails
+//! while the secure desktop is active, and the idle time from the last input
+//! event.
+
+#![allow(unsafe_code)]
+
+use std::{mem::size_of, time::Duration};
+use winapi::um::{
+  sysinfoapi::GetTickCount,
+  winuser::{
+    CloseDesktop, DESKTOP_SWITCHDESKTOP, GetLastInputInfo, LASTINPUTINFO,
+    OpenInputDesktop, SwitchDesktop
+  }
+};
+
+pub fn is_locked() -> Option<bool> {
+  // SAFETY: The handle is checked for null before use and closed once.
+  unsafe {
+    let desktop = OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP);
+    if desktop.is_null() {
+      return Some(true);
+    }
+    let switched = SwitchDesktop(desktop) != 0;
+    CloseDesktop(desktop);
+    Some(!switched)
+  }
+}
+
+pub fn idle_time() -> Option<Duration> {
+  let mut info = LASTINPUTINFO {
+    cbSize: size_of::<LASTINPUTINFO>() as u32,
+    dwTime: 0
+  };
+  // SAFETY: `info` is a properly sized, writable LASTINPUTINFO.
+  if unsafe { GetLastInputInfo(&mut info) } == 0 {
+    return None;
+  }
+  //{ Both tick counts wrap after ~49 days, so subtract with wrapping }
+  // SAFETY: GetTickCount has no preconditions.
+  let now = unsafe { GetTickCount() };
+  Some(Duration::from_millis(u64::from(
+    now.wrapping_sub(info.dwTime)
+  )))
+}
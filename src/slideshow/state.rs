@@ -0,0 +1,144 @@
+//! Persistent slideshow state shared between commands.
+
+use super::theme::Weekly;
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the slideshow state file within the state directory.
+const STATE_FILE: &str = "slideshow.json";
+
+/// The name of the file within the state directory that keeps where random
+/// searches stopped.
+const RANDOM_PAGES_FILE: &str = "random-pages.json";
+
+/// How long a random search is remembered after it was last continued.
+const FORGET_AFTER: TimeDelta = TimeDelta::days(30);
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+  /// Automatic rotation is paused until this time.
+  pub snoozed_until: Option<DateTime<Local>>,
+  /// The current theme of the week, if themes are enabled.
+  #[serde(default)]
+  pub theme: Option<Weekly>,
+  /// The [collection](crate::wallpaper::collection) the slideshow steps
+  /// through instead of its usual sources.
+  #[serde(default)]
+  pub collection: Option<String>
+}
+
+/// The page a random Wallhaven search continues from, and the seed that
+/// keeps its order the same from page to page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RandomPage {
+  pub seed: String,
+  pub page: u32,
+  /// When the search last continued.
+  pub used: DateTime<Local>
+}
+
+impl RandomPage {
+  /// Returns where a search continues from, as of now.
+  pub fn new(seed: String, page: u32) -> Self {
+    Self {
+      seed,
+      page,
+      used: Local::now()
+    }
+  }
+}
+
+/// Where each random Wallhaven search of the slideshow stopped, by
+/// [search](crate::api::wallhaven::SearchParams::key). Kept apart from the
+/// [State], since a search spans a network request that other commands may
+/// save the state during.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RandomPages {
+  pub searches: BTreeMap<String, RandomPage>
+}
+
+impl RandomPages {
+  /// Returns the path of the random pages file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(RANDOM_PAGES_FILE)
+  }
+
+  /// Loads where random searches stopped from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves where random searches stopped to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Remembers that the search `key` continues from `next`, or forgets it,
+  /// along with any search not continued for [FORGET_AFTER], such as one
+  /// for a past theme. The file is read again first, so searches saved
+  /// meanwhile are kept.
+  pub fn update(
+    path_config: &Path,
+    key: &str,
+    next: Option<RandomPage>
+  ) -> Result<()> {
+    let mut pages = Self::load(path_config)?;
+    match next {
+      Some(next) => pages.searches.insert(key.to_string(), next),
+      None => pages.searches.remove(key)
+    };
+    pages.forget_stale(Local::now());
+    pages.save(path_config)
+  }
+
+  /// Forgets the searches not continued for [FORGET_AFTER] before `now`.
+  fn forget_stale(&mut self, now: DateTime<Local>) {
+    self
+      .searches
+      .retain(|_, page| now - page.used <= FORGET_AFTER);
+  }
+}
+
+impl State {
+  /// Returns the path of the slideshow state file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(STATE_FILE)
+  }
+
+  /// Loads the slideshow state from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the slideshow state to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Checks whether automatic rotation is currently paused.
+  pub fn is_snoozed(&self) -> bool {
+    self.snoozed_until.is_some_and(|until| until > Local::now())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_stale_random_pages_are_forgotten() {
+    let now = Local::now();
+    let mut pages = RandomPages::default();
+    pages
+      .searches
+      .insert("lake".into(), RandomPage::new("abc123".into(), 3));
+    let mut autumn = RandomPage::new("def456".into(), 2);
+    autumn.used = now - TimeDelta::days(60);
+    pages.searches.insert("autumn".into(), autumn);
+
+    pages.forget_stale(now);
+    assert_eq!(pages.searches.keys().collect::<Vec<_>>(), ["lake"]);
+  }
+}
@@ -0,0 +1,165 @@
+//! The theme of the week.
+//!
+//! With `slideshow.theme` enabled, the slideshow shows wallpapers matching
+//! one search for a whole ISO week. When the week turns, a new theme is
+//! picked at random from `slideshow.theme.pool`, or, if the pool is empty,
+//! from the tags most common among the week's top wallpapers on Wallhaven.
+//! The previous theme is never picked twice in a row. Each new theme is
+//! announced with a desktop notification and shown by `wallter status`.
+
+use super::{
+  State,
+  retries::{self, Operation}
+};
+use crate::{
+  Config, Error, Result,
+  api::wallhaven::{Api as Wallhaven, SearchParams, Sorting, ToplistRange},
+  utils::notify
+};
+use chrono::{DateTime, Local, NaiveDate};
+use rand::{prelude::IndexedRandom, rng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+/// How many of the week's top wallpapers are looked up for trending tags.
+const TRENDING_SAMPLE: usize = 5;
+
+/// The theme chosen for a week.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Weekly {
+  /// The search wallpapers must match.
+  pub query: String,
+  /// The ISO week the theme is for, e.g. `2025-W23`.
+  pub week: String,
+  pub chosen_at: DateTime<Local>
+}
+
+/// Returns this week's theme, choosing and announcing a new one once the
+/// week has turned. Returns `None` if themes are disabled or none could be
+/// chosen.
+pub async fn current(config: &Config) -> Result<Option<String>> {
+  let settings = &config.slideshow.theme;
+  if !settings.enabled {
+    return Ok(None);
+  }
+  let mut state = State::load(&config.path)?;
+  let week = week_of(Local::now().date_naive());
+  if let Some(theme) = state.theme.as_ref().filter(|theme| theme.week == week) {
+    return Ok(Some(theme.query.clone()));
+  }
+
+  let previous = state.theme.as_ref().map(|theme| theme.query.as_str());
+  let query = if settings.pool.is_empty() {
+    trending(config, previous).await?
+  } else {
+    from_pool(&settings.pool, previous)
+  };
+  let Some(query) = query else {
+    return Ok(None);
+  };
+
+  state.theme = Some(Weekly {
+    query: query.clone(),
+    week,
+    chosen_at: Local::now()
+  });
+  state.save(&config.path)?;
+  info!("This week's theme is '{query}'");
+  if let Err(e) = notify::send("Theme of the week", &query) {
+    match &e {
+      Error::Deferred(reason) => info!("{reason}"),
+      _ => warn!("Failed to announce the theme: {e}")
+    }
+    let operation = Operation::Notify {
+      title: "Theme of the week".into(),
+      body: query.clone()
+    };
+    retries::schedule(&config.path, operation, &e);
+  }
+  Ok(Some(query))
+}
+
+/// Names the ISO week `date` falls in, e.g. `2025-W23`.
+pub fn week_of(date: NaiveDate) -> String {
+  date.format("%G-W%V").to_string()
+}
+
+/// Picks a theme from `pool` at random, avoiding `previous` unless it is the
+/// only one.
+fn from_pool(pool: &[String], previous: Option<&str>) -> Option<String> {
+  let fresh: Vec<&String> = pool
+    .iter()
+    .filter(|query| Some(query.as_str()) != previous)
+    .collect();
+  let choices = if fresh.is_empty() {
+    pool.iter().collect()
+  } else {
+    fresh
+  };
+  choices.choose(&mut rng()).map(|query| (*query).clone())
+}
+
+/// Picks the tag most common among the week's top wallpapers on Wallhaven.
+async fn trending(
+  config: &Config,
+  previous: Option<&str>
+) -> Result<Option<String>> {
+  let api_key = config
+    .source
+    .find("wallhaven")
+    .and_then(|source| source.api_key());
+  let api = Wallhaven::new(api_key);
+  let params = SearchParams::new()
+    .with_sorting(Sorting::Toplist)
+    .with_top_range(ToplistRange::Week);
+  let response = api.search(&params).await?;
+
+  let mut counts = BTreeMap::new();
+  for wallpaper in response.data.iter().take(TRENDING_SAMPLE) {
+    let details = api.get_wallpaper_details(&wallpaper.id).await?;
+    for tag in details.tags.unwrap_or_default() {
+      *counts.entry(tag.name).or_insert(0) += 1;
+    }
+  }
+  Ok(most_common(counts, previous))
+}
+
+/// Returns the tag counted most often other than `previous`, preferring the
+/// alphabetically first on a tie.
+fn most_common(
+  counts: BTreeMap<String, usize>,
+  previous: Option<&str>
+) -> Option<String> {
+  counts
+    .into_iter()
+    .filter(|(tag, _)| Some(tag.as_str()) != previous)
+    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+    .map(|(tag, _)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_week_of() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    assert_eq!(week_of(date(2025, 6, 4)), "2025-W23");
+    //{ The ISO year differs from the calendar year around New Year }
+    assert_eq!(week_of(date(2024, 12, 30)), "2025-W01");
+  }
+
+  #[test]
+  fn test_theme_choice() {
+    let pool = vec!["forest".to_string(), "city".to_string()];
+    assert_eq!(from_pool(&pool, Some("forest")), Some("city".to_string()));
+    assert_eq!(from_pool(&pool[..1], Some("forest")), Some("forest".into()));
+    assert_eq!(from_pool(&[], None), None);
+
+    let counts: BTreeMap<String, usize> =
+      [("nature".into(), 3), ("city".into(), 3), ("sky".into(), 1)].into();
+    assert_eq!(most_common(counts.clone(), None), Some("city".into()));
+    assert_eq!(most_common(counts, Some("city")), Some("nature".into()));
+  }
+}
@@ -0,0 +1,234 @@
+//! Learns which sources and tags are skipped right after they appear.
+//!
+//! Every wallpaper the slideshow shows is counted against its source and, for
+//! Wallhaven images, its tags. A skip within `slideshow.tuning.window` of the
+//! change counts as a quick skip. With `slideshow.tuning.auto_adjust`, the
+//! random picker shows images less often the more their source and tags are
+//! quickly skipped. Tags are looked up once per image, on its first quick
+//! skip, and remembered.
+
+use crate::{
+  Config, Result,
+  api::wallhaven::{self, Api as Wallhaven},
+  config::Path,
+  utils::store
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::{Path as FilePath, PathBuf}
+};
+use tracing::debug;
+
+/// The name of the tuner file within the state directory.
+const TUNER_FILE: &str = "tuner.json";
+
+/// Shows assumed before any are counted, so a single early skip does not
+/// condemn a source or tag.
+const PRIOR_SHOWS: f64 = 5.0;
+
+/// The lowest weight tuning gives, so nothing is ruled out completely.
+const MIN_WEIGHT: f64 = 0.1;
+
+/// How often a source or tag was shown and quickly skipped.
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct Tally {
+  pub shown: u32,
+  pub skipped: u32
+}
+
+impl Tally {
+  /// Returns the share of shows that were quickly skipped.
+  pub fn skip_rate(&self) -> f64 {
+    (f64::from(self.skipped) / (f64::from(self.shown) + PRIOR_SHOWS)).min(1.0)
+  }
+
+  /// Returns the selection weight this tally earns, between [MIN_WEIGHT]
+  /// and 1.
+  pub fn weight(&self) -> f64 {
+    (1.0 - self.skip_rate()).max(MIN_WEIGHT)
+  }
+}
+
+/// What the tuner has learned, persisted in the state directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tuner {
+  pub sources: BTreeMap<String, Tally>,
+  pub tags: BTreeMap<String, Tally>,
+  /// The tags of Wallhaven images, by id, so each is looked up only once.
+  #[serde(default)]
+  pub known_tags: BTreeMap<String, Vec<String>>
+}
+
+impl Tuner {
+  /// Returns the path of the tuner file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(TUNER_FILE)
+  }
+
+  /// Loads what the tuner has learned from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves what the tuner has learned to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records that `image` was shown.
+  pub fn record_shown(&mut self, image: &FilePath) {
+    self.sources.entry(source_of(image)).or_default().shown += 1;
+    for tag in self.tags_of(image) {
+      self.tags.entry(tag).or_default().shown += 1;
+    }
+  }
+
+  /// Records that `image` was skipped soon after it was shown.
+  pub fn record_skip(&mut self, image: &FilePath) {
+    self.sources.entry(source_of(image)).or_default().skipped += 1;
+    for tag in self.tags_of(image) {
+      self.tags.entry(tag).or_default().skipped += 1;
+    }
+  }
+
+  /// Looks up the tags of `image` on Wallhaven, unless they are known
+  /// already or the image is not from Wallhaven.
+  pub async fn learn_tags(
+    &mut self,
+    config: &Config,
+    image: &FilePath
+  ) -> Result<()> {
+    let Some(id) = wallhaven::id_from_path(image) else {
+      return Ok(());
+    };
+    if self.known_tags.contains_key(&id) {
+      return Ok(());
+    }
+    let api_key = config
+      .source
+      .find("wallhaven")
+      .and_then(|source| source.api_key());
+    let details = Wallhaven::new(api_key).get_wallpaper_details(&id).await?;
+    let tags: Vec<String> = details
+      .tags
+      .unwrap_or_default()
+      .into_iter()
+      .map(|tag| tag.name.to_lowercase())
+      .collect();
+    debug!("Learned {} tags of {id}", tags.len());
+    self.known_tags.insert(id, tags);
+    Ok(())
+  }
+
+  /// Returns the selection weight of `image`: the weight of its source,
+  /// scaled by that of its most skipped tag.
+  pub fn weight(&self, image: &FilePath) -> f64 {
+    let source = self
+      .sources
+      .get(&source_of(image))
+      .map_or(1.0, Tally::weight);
+    let tag = self
+      .tags_of(image)
+      .iter()
+      .filter_map(|tag| self.tags.get(tag))
+      .map(Tally::weight)
+      .fold(1.0, f64::min);
+    (source * tag).max(MIN_WEIGHT)
+  }
+
+  /// Returns the tags with at least one quick skip, most demoted first.
+  pub fn demoted_tags(&self) -> Vec<(&String, &Tally)> {
+    let mut tags: Vec<_> =
+      self.tags.iter().filter(|(_, t)| t.skipped > 0).collect();
+    tags.sort_by(|a, b| a.1.weight().total_cmp(&b.1.weight()));
+    tags
+  }
+
+  fn tags_of(&self, image: &FilePath) -> Vec<String> {
+    wallhaven::id_from_path(image)
+      .and_then(|id| self.known_tags.get(&id))
+      .cloned()
+      .unwrap_or_default()
+  }
+}
+
+/// Names the source an image came from, judging by its file name.
+pub fn source_of(image: &FilePath) -> String {
+  let name = image
+    .file_name()
+    .map(|name| name.to_string_lossy().to_lowercase())
+    .unwrap_or_default();
+  if wallhaven::id_from_path(image).is_some() {
+    "wallhaven".into()
+  } else if name.starts_with("pexels-photo-") {
+    "pexels".into()
+  } else {
+    "other".into()
+  }
+}
+
+impl Display for Tuner {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.sources.is_empty() {
+      return printh!(f, "Nothing learned yet");
+    }
+    printh!(f, "Sources:", 2)?;
+    for (source, tally) in &self.sources {
+      printf!(f, source, describe(tally))?;
+    }
+    let tags = self.demoted_tags();
+    if !tags.is_empty() {
+      printh!(f, "Tags:", 2)?;
+      for (tag, tally) in tags {
+        printf!(f, tag, describe(tally))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+fn describe(tally: &Tally) -> String {
+  format!(
+    "{} quick skips in {} shows, weight {:.2}",
+    tally.skipped,
+    tally.shown,
+    tally.weight()
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_weight() {
+    let mut tuner = Tuner::default();
+    let busy = FilePath::new("wallhaven-abc123.jpg");
+    let calm = FilePath::new("wallhaven-def456.jpg");
+    tuner
+      .known_tags
+      .insert("abc123".into(), vec!["busy".into()]);
+    tuner
+      .known_tags
+      .insert("def456".into(), vec!["calm".into()]);
+    assert_eq!(tuner.weight(busy), 1.0);
+
+    for _ in 0..10 {
+      tuner.record_shown(busy);
+      tuner.record_shown(calm);
+      tuner.record_skip(busy);
+    }
+    assert!(tuner.weight(busy) < tuner.weight(calm));
+    assert!(tuner.weight(busy) >= MIN_WEIGHT);
+    assert_eq!(tuner.tags["calm"].skipped, 0);
+    assert_eq!(tuner.demoted_tags()[0].0, "busy");
+
+    //{ Sources are judged by file name }
+    assert_eq!(source_of(FilePath::new("pexels-photo-1.jpeg")), "pexels");
+    assert_eq!(source_of(FilePath::new("holiday.png")), "other");
+  }
+}
@@ -0,0 +1,106 @@
+//! Keeps consecutive wallpapers on a monitor from looking alike.
+//!
+//! With `slideshow.variety.compare` set, a randomly picked image is compared
+//! with the last `depth` images shown on the monitor, and passed over unless
+//! it differs from each of them by at least the monitor's threshold. Should
+//! every image be too alike, the first one picked is shown anyway, so the
+//! slideshow never stalls.
+
+use crate::{
+  Config,
+  config::{Monitor, slideshow::Comparison, wallpaper::Limits},
+  wallpaper::{
+    History,
+    fingerprints::{self, Look}
+  }
+};
+use std::path::Path;
+use tracing::debug;
+
+/// The looks of the images shown last on a monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recent {
+  comparison: Comparison,
+  threshold: u32,
+  looks: Vec<Look>,
+  limits: Limits
+}
+
+impl Recent {
+  /// Gathers the looks of the images shown last on `monitor`. Images that
+  /// can no longer be read are left out.
+  pub fn load(config: &Config, monitor: &Monitor, history: &History) -> Self {
+    let variety = &config.slideshow.variety;
+    let looks = match variety.compare {
+      Comparison::Off => Vec::new(),
+      _ => history
+        .recent(&monitor.name, variety.depth)
+        .into_iter()
+        .filter_map(|image| Look::of_file(image, &config.wallpaper.limits).ok())
+        .collect()
+    };
+    Self {
+      comparison: variety.compare,
+      threshold: variety.threshold_for(monitor),
+      looks,
+      limits: config.wallpaper.limits
+    }
+  }
+
+  /// Whether `image` differs enough from every recent image. Images that
+  /// can't be read are allowed, and fail later where they are applied.
+  pub fn allows(&self, image: &Path) -> bool {
+    if self.looks.is_empty() {
+      return true;
+    }
+    let Ok(look) = Look::of_file(image, &self.limits) else {
+      return true;
+    };
+    self.allows_look(&look) || {
+      debug!("{} looks too much like a recent wallpaper", image.display());
+      false
+    }
+  }
+
+  fn allows_look(&self, look: &Look) -> bool {
+    self
+      .looks
+      .iter()
+      .all(|recent| difference(self.comparison, recent, look) >= self.threshold)
+  }
+}
+
+/// Returns how different two looks are, in the units of `comparison`.
+pub fn difference(comparison: Comparison, a: &Look, b: &Look) -> u32 {
+  match comparison {
+    Comparison::Off => u32::MAX,
+    Comparison::Fingerprint => fingerprints::distance(a.hash, b.hash),
+    Comparison::Hue => fingerprints::hue_distance(a.hue, b.hue)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_allows_look() {
+    let look = |hash, hue| Look { hash, hue };
+    let recent = Recent {
+      comparison: Comparison::Fingerprint,
+      threshold: 16,
+      looks: vec![look(0, Some(200)), look(u64::MAX, None)],
+      limits: Limits::default()
+    };
+    assert!(recent.allows_look(&look(0xFFFF_FFFF, Some(210))));
+    assert!(!recent.allows_look(&look(0xFF, Some(30))));
+
+    let recent = Recent {
+      comparison: Comparison::Hue,
+      threshold: 30,
+      ..recent
+    };
+    assert!(!recent.allows_look(&look(0xFFFF_FFFF, Some(210))));
+    assert!(recent.allows_look(&look(0xFF, Some(30))));
+  }
+}
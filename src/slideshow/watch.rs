@@ -0,0 +1,87 @@
+//! Imports images dropped into the watch folders while the daemon runs.
+//!
+//! The folders are rescanned every [SCAN_INTERVAL]. Files still being
+//! written, such as a sync in progress, are left until they have been
+//! unchanged for [SETTLE_TIME]. The originals stay where they are.
+
+use crate::{
+  Config, Result,
+  utils::files,
+  wallpaper::{Library, library::Imported}
+};
+use std::{
+  collections::HashMap,
+  fs,
+  path::PathBuf,
+  time::{Duration, Instant, SystemTime}
+};
+use tracing::{info, warn};
+
+/// How often the watch folders are rescanned.
+const SCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a file must go unmodified before it is imported.
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+
+/// Remembers which files in the watch folders have been dealt with.
+#[derive(Debug, Default)]
+pub struct Folders {
+  seen: HashMap<PathBuf, SystemTime>,
+  last_scan: Option<Instant>
+}
+
+impl Folders {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Imports the images that appeared or changed since the previous scan,
+  /// if a scan is due. Returns the library paths of the images added.
+  pub fn poll(&mut self, config: &Config) -> Result<Vec<PathBuf>> {
+    let dirs = &config.slideshow.watch.dirs;
+    if dirs.is_empty()
+      || self
+        .last_scan
+        .is_some_and(|last| last.elapsed() < SCAN_INTERVAL)
+    {
+      return Ok(Vec::new());
+    }
+    self.last_scan = Some(Instant::now());
+
+    let mut pending = Vec::new();
+    for dir in dirs {
+      for image in files::list_images(dir, true)? {
+        let Ok(modified) = fs::metadata(&image).and_then(|m| m.modified())
+        else {
+          continue;
+        };
+        let settled = modified
+          .elapsed()
+          .is_ok_and(|elapsed| elapsed >= SETTLE_TIME);
+        if settled && self.seen.get(&image) != Some(&modified) {
+          pending.push((image, modified));
+        }
+      }
+    }
+    if pending.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let mut library = Library::load(&config.path)?;
+    let mut added = Vec::new();
+    for (image, modified) in pending {
+      //{ Failures are reported once, not retried until the file changes }
+      match library.import(config, &image, true) {
+        Ok(Imported::Added(path)) => {
+          info!("Imported {} from the watch folders", image.display());
+          added.push(path);
+        }
+        Ok(Imported::Duplicate(_)) => {}
+        Err(e) => warn!("Skipped {} in the watch folders: {e}", image.display())
+      }
+      self.seen.insert(image, modified);
+    }
+    library.save(&config.path)?;
+    Ok(added)
+  }
+}
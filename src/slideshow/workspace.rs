@@ -0,0 +1,316 @@
+//! Switches the wallpaper as focus moves between workspaces of a tiling
+//! window manager.
+//!
+//! Focus changes are read from the compositor's event stream: the `.socket2`
+//! socket on Hyprland, and `swaymsg -t subscribe` on Sway. The stream is
+//! reopened whenever it drops. Each workspace shows its pinned image if it
+//! has one in `slideshow.workspaces.pinned`. Otherwise it shows what it
+//! showed when focus last left it, or the next wallpaper on a first visit.
+
+use super::{State, advance};
+use crate::{
+  Config, Error, Result,
+  config::Monitor,
+  wallpaper::{self, NowShowing, Source}
+};
+use serde::Deserialize;
+use std::{
+  collections::HashMap, env, path::PathBuf, process::Stdio, time::Duration
+};
+use tokio::{
+  io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+  process::Command,
+  sync::mpsc::{Receiver, Sender, channel},
+  time::sleep
+};
+use tracing::{debug, error, info, warn};
+
+/// How long to wait before reopening a dropped event stream.
+const RECONNECT: Duration = Duration::from_secs(10);
+
+/// A workspace that received focus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Focus {
+  /// The workspace name, e.g. `"2:web"`.
+  pub workspace: String,
+  /// The output showing the workspace, if the compositor said.
+  pub output: Option<String>
+}
+
+/// The compositors whose workspaces can be followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compositor {
+  Hyprland,
+  Sway
+}
+
+impl Compositor {
+  /// Recognises the compositor of the current session.
+  fn detect() -> Option<Self> {
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+      Some(Self::Hyprland)
+    } else if env::var_os("SWAYSOCK").is_some() {
+      Some(Self::Sway)
+    } else {
+      None
+    }
+  }
+}
+
+/// Starts following workspace focus, or returns `None` when the session
+/// runs neither Hyprland nor Sway.
+pub fn listen() -> Option<Receiver<Focus>> {
+  let Some(compositor) = Compositor::detect() else {
+    warn!("Workspace wallpapers need Hyprland or Sway; ignoring them");
+    return None;
+  };
+  let (sender, receiver) = channel(8);
+  tokio::spawn(follow(compositor, sender));
+  Some(receiver)
+}
+
+/// Forwards focus changes until the daemon stops listening.
+async fn follow(compositor: Compositor, sender: Sender<Focus>) {
+  loop {
+    let result = match compositor {
+      Compositor::Hyprland => follow_hyprland(&sender).await,
+      Compositor::Sway => follow_sway(&sender).await
+    };
+    if sender.is_closed() {
+      return;
+    }
+    match result {
+      Ok(()) => debug!("The workspace event stream closed; reopening"),
+      Err(e) => warn!("Lost the workspace event stream: {e}")
+    }
+    sleep(RECONNECT).await;
+  }
+}
+
+/// Reads focus changes from Hyprland's event socket.
+#[cfg(unix)]
+async fn follow_hyprland(sender: &Sender<Focus>) -> Result<()> {
+  let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").unwrap_or_default();
+  let runtime = env::var_os("XDG_RUNTIME_DIR")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| PathBuf::from("/tmp"));
+  let socket = [runtime.join("hypr"), PathBuf::from("/tmp/hypr")]
+    .into_iter()
+    .map(|dir| dir.join(&signature).join(".socket2.sock"))
+    .find(|socket| socket.exists())
+    .ok_or_else(|| {
+      Error::Config("Hyprland's event socket was not found".into())
+    })?;
+  let stream = tokio::net::UnixStream::connect(socket).await?;
+  let mut output = None;
+  forward(BufReader::new(stream), sender, |line| {
+    hyprland_focus(line, &mut output)
+  })
+  .await
+}
+
+#[cfg(not(unix))]
+async fn follow_hyprland(_sender: &Sender<Focus>) -> Result<()> {
+  Err(Error::Config("Hyprland is only supported on Unix".into()))
+}
+
+/// Reads focus changes from `swaymsg`.
+async fn follow_sway(sender: &Sender<Focus>) -> Result<()> {
+  let mut child = Command::new("swaymsg")
+    .args(["-r", "-m", "-t", "subscribe", r#"["workspace"]"#])
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .kill_on_drop(true)
+    .spawn()?;
+  let Some(stdout) = child.stdout.take() else {
+    return Ok(());
+  };
+  forward(BufReader::new(stdout), sender, sway_focus).await
+}
+
+/// Sends the focus changes `parse` finds in the lines of `reader`.
+async fn forward(
+  reader: impl AsyncBufRead + Unpin,
+  sender: &Sender<Focus>,
+  mut parse: impl FnMut(&str) -> Option<Focus>
+) -> Result<()> {
+  let mut lines = reader.lines();
+  while let Some(line) = lines.next_line().await? {
+    if let Some(focus) = parse(&line) {
+      if sender.send(focus).await.is_err() {
+        break;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Reads a focus change from a line of Hyprland's event socket. Hyprland
+/// names the output only when focus moves to another monitor, so the last
+/// one named is remembered in `output`.
+fn hyprland_focus(line: &str, output: &mut Option<String>) -> Option<Focus> {
+  let (event, data) = line.split_once(">>")?;
+  let workspace = match event {
+    "workspace" => data,
+    "focusedmon" => {
+      let (monitor, workspace) = data.split_once(',')?;
+      *output = Some(monitor.to_string());
+      workspace
+    }
+    _ => return None
+  };
+  Some(Focus {
+    workspace: workspace.to_string(),
+    output: output.clone()
+  })
+}
+
+/// Reads a focus change from a workspace event printed by `swaymsg`.
+fn sway_focus(line: &str) -> Option<Focus> {
+  #[derive(Deserialize)]
+  struct Event {
+    change: String,
+    current: Option<Workspace>
+  }
+  #[derive(Deserialize)]
+  struct Workspace {
+    name: String,
+    output: Option<String>
+  }
+
+  let event: Event = serde_json::from_str(line).ok()?;
+  let current = event.current.filter(|_| event.change == "focus")?;
+  Some(Focus {
+    workspace: current.name,
+    output: current.output
+  })
+}
+
+/// What each workspace showed when focus last left it.
+#[derive(Debug, Default)]
+pub struct Tracker {
+  /// The focused workspace on each output, by output name. Focus on an
+  /// unnamed output is kept under the empty name.
+  focused: HashMap<String, String>,
+  /// The image each workspace was left showing, by workspace name.
+  shown: HashMap<String, PathBuf>
+}
+
+impl Tracker {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Shows the wallpaper of the workspace that received `focus` on the
+  /// monitors showing it, unless rotation is snoozed.
+  pub async fn switch(&mut self, config: &Config, focus: &Focus) -> Result<()> {
+    let output = focus.output.clone().unwrap_or_default();
+    let previous = self.focused.insert(output, focus.workspace.clone());
+    if previous.as_deref() == Some(focus.workspace.as_str()) {
+      return Ok(());
+    }
+    let monitors = targets(&config.monitors, focus.output.as_deref());
+
+    //{ Remember what the workspace being left was showing }
+    if let Some(previous) = previous {
+      let now_showing = NowShowing::load(&config.path)?;
+      let image = monitors
+        .iter()
+        .find_map(|monitor| now_showing.monitors.get(&monitor.name));
+      if let Some(showing) = image {
+        self.shown.insert(previous, showing.image.clone());
+      }
+    }
+    if State::load(&config.path)?.is_snoozed() {
+      return Ok(());
+    }
+
+    let workspace = &focus.workspace;
+    let kept = config
+      .slideshow
+      .workspaces
+      .pinned_for(workspace)
+      .or_else(|| self.shown.get(workspace));
+    for monitor in monitors {
+      let name = &monitor.name;
+      let result = match kept {
+        Some(image) => {
+          let source = Source::File(image.clone());
+          wallpaper::set(config, &source, Some(name))
+            .await
+            .map(|_| Some(source))
+        }
+        None => advance(config, monitor).await
+      };
+      match result {
+        Ok(Some(source)) =>
+          info!(monitor = %name, "Now showing on {workspace}: {source}"),
+        Ok(None) => info!(monitor = %name, "No other wallpapers available"),
+        Err(e) => error!(monitor = %name, "Failed to change wallpaper: {e}")
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Returns the monitors showing `output`, or every monitor when the output
+/// is unknown or matches none of them.
+fn targets<'a>(
+  monitors: &'a [Monitor],
+  output: Option<&str>
+) -> Vec<&'a Monitor> {
+  let matching: Vec<&Monitor> = monitors
+    .iter()
+    .filter(|monitor| Some(monitor.name.as_str()) == output)
+    .collect();
+  if matching.is_empty() {
+    monitors.iter().collect()
+  } else {
+    matching
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hyprland_focus() {
+    let mut output = None;
+    assert_eq!(
+      hyprland_focus("workspace>>2", &mut output),
+      Some(Focus {
+        workspace: "2".into(),
+        output: None
+      })
+    );
+    assert!(hyprland_focus("focusedmon>>DP-1,3", &mut output).is_some());
+    assert_eq!(
+      hyprland_focus("workspace>>4", &mut output),
+      Some(Focus {
+        workspace: "4".into(),
+        output: Some("DP-1".into())
+      })
+    );
+    assert_eq!(hyprland_focus("activewindow>>kitty,~", &mut output), None);
+  }
+
+  #[test]
+  fn test_sway_focus() {
+    let focus = sway_focus(
+      r#"{"change":"focus","current":{"name":"2:web","output":"HDMI-A-1"},"old":null}"#
+    );
+    assert_eq!(
+      focus,
+      Some(Focus {
+        workspace: "2:web".into(),
+        output: Some("HDMI-A-1".into())
+      })
+    );
+    assert_eq!(
+      sway_focus(r#"{"change":"init","current":{"name":"3"}}"#),
+      None
+    );
+    assert_eq!(sway_focus("not json"), None);
+  }
+}
@@ -0,0 +1,179 @@
+use crate::{Config, Result, config::Path, utils::store};
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+
+/// The name of the statistics file within the state directory.
+const STATS_FILE: &str = "stats.json";
+
+/// The number of days summarized in the daily breakdown.
+const RECENT_DAYS: u64 = 7;
+
+/// Hit and miss counters for a single wallpaper source.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStats {
+  /// Requests that returned a usable wallpaper.
+  pub hits: u64,
+  /// Requests that failed or returned nothing.
+  pub misses: u64
+}
+
+impl SourceStats {
+  /// Returns the fraction of requests that were hits, if any were made.
+  pub fn hit_rate(&self) -> Option<f64> {
+    let total = self.hits + self.misses;
+    (total > 0).then(|| self.hits as f64 / total as f64)
+  }
+}
+
+/// Usage counters persisted in the state directory.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+  /// The number of wallpapers set on each day.
+  pub wallpapers_set: BTreeMap<NaiveDate, u64>,
+  /// Per-source request outcomes, keyed by source name.
+  pub sources: BTreeMap<String, SourceStats>,
+  /// The number of completed downloads.
+  pub downloads: u64,
+  /// The total size of all completed downloads, in bytes.
+  pub downloaded_bytes: u64
+}
+
+impl Stats {
+  /// Returns the path of the statistics file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(STATS_FILE)
+  }
+
+  /// Loads the statistics from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the statistics to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records a wallpaper being set today.
+  pub fn record_set(&mut self) {
+    *self
+      .wallpapers_set
+      .entry(Local::now().date_naive())
+      .or_default() += 1;
+  }
+
+  /// Records the outcome of a request to a source.
+  pub fn record_source(&mut self, source: &str, hit: bool) {
+    let stats = self.sources.entry(source.to_string()).or_default();
+    if hit {
+      stats.hits += 1;
+    } else {
+      stats.misses += 1;
+    }
+  }
+
+  /// Records a completed download of `bytes` bytes.
+  pub fn record_download(&mut self, bytes: u64) {
+    self.downloads += 1;
+    self.downloaded_bytes += bytes;
+  }
+
+  /// Returns the total number of wallpapers ever set.
+  pub fn total_set(&self) -> u64 {
+    self.wallpapers_set.values().sum()
+  }
+
+  /// Returns the average download size in bytes, if anything was downloaded.
+  pub fn average_download_size(&self) -> Option<u64> {
+    (self.downloads > 0).then(|| self.downloaded_bytes / self.downloads)
+  }
+}
+
+impl Display for Stats {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    printh!(f, "Wallpapers Set:")?;
+    printf!(f, "Total", self.total_set())?;
+    let today = Local::now().date_naive();
+    for offset in 0..RECENT_DAYS {
+      let Some(day) = today.checked_sub_days(Days::new(offset)) else {
+        break;
+      };
+      let count = self.wallpapers_set.get(&day).copied().unwrap_or_default();
+      printf!(f, &day.to_string(), count)?;
+    }
+
+    printh!(f, "Sources:")?;
+    if self.sources.is_empty() {
+      printf!(f, "Hit Rate", "[No requests]")?;
+    }
+    for (name, stats) in &self.sources {
+      let rate = match stats.hit_rate() {
+        Some(rate) => format!(
+          "{:.0}% ({} of {})",
+          rate * 100.0,
+          stats.hits,
+          stats.hits + stats.misses
+        ),
+        None => "[No requests]".to_string()
+      };
+      printf!(f, name, rate)?;
+    }
+
+    printh!(f, "Downloads:")?;
+    printf!(f, "Count", self.downloads)?;
+    let average = match self.average_download_size() {
+      Some(bytes) => format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0)),
+      None => "[None]".to_string()
+    };
+    printf!(f, "Average Size", average)?;
+
+    Ok(())
+  }
+}
+
+/// Applies `update` to the persisted statistics if the user opted in.
+pub fn update(config: &Config, update: impl FnOnce(&mut Stats)) -> Result<()> {
+  if !config.stats.enabled {
+    return Ok(());
+  }
+  let mut stats = Stats::load(&config.path)?;
+  update(&mut stats);
+  stats.save(&config.path)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_source_hit_rate() {
+    let mut stats = Stats::default();
+    assert_eq!(stats.average_download_size(), None);
+
+    stats.record_source("wallhaven", true);
+    stats.record_source("wallhaven", true);
+    stats.record_source("wallhaven", false);
+    stats.record_source("unsplash", false);
+
+    assert_eq!(stats.sources["unsplash"].hit_rate(), Some(0.0));
+    let rate = stats.sources["wallhaven"].hit_rate().unwrap();
+    assert!((rate - 2.0 / 3.0).abs() < f64::EPSILON);
+  }
+
+  #[test]
+  fn test_download_and_set_counters() {
+    let mut stats = Stats::default();
+    stats.record_download(1000);
+    stats.record_download(3000);
+    stats.record_set();
+    stats.record_set();
+
+    assert_eq!(stats.average_download_size(), Some(2000));
+    assert_eq!(stats.total_set(), 2);
+  }
+}
@@ -0,0 +1,4 @@
+//! Local, opt-in usage statistics.
+
+mod default;
+pub use default::*;
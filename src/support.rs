@@ -0,0 +1,360 @@
+//! Collects what a bug report needs into one ZIP file.
+//!
+//! A [Bundle] holds the effective configuration with its secrets redacted,
+//! the monitor layout, what was detected about the platform and desktop,
+//! the last errors of every source, the event journal, the system's theme
+//! and night light settings, and any log files asked for. Anything that
+//! can't be read is noted in its place, so a bundle can always be made.
+//!
+//! A [Diagnosis] is the short form of the platform part, shown by
+//! `wallter doctor`: the desktop and session detected, and the backends
+//! chosen for them.
+
+use crate::{
+  Config, Error, Result,
+  config::{color::mode::Config as Mode, conflicts::Manager},
+  search::Health,
+  simulate::{self, Action},
+  utils::zip,
+  wallpaper::{self, journal}
+};
+use std::{
+  env,
+  fmt::{self, Display, Formatter, Write as _},
+  fs::{self, File},
+  io::{BufWriter, Read, Seek, SeekFrom},
+  path::{Path, PathBuf}
+};
+
+/// The keys whose values are replaced before the config is bundled.
+const SECRETS: [&str; 6] = [
+  "api_key",
+  "client_secret",
+  "access_token",
+  "refresh_token",
+  "token",
+  "password"
+];
+
+/// What a secret is replaced with.
+const REDACTED: &str = "[redacted]";
+
+/// How much of the end of each log file is kept.
+const LOG_TAIL: u64 = 1024 * 1024;
+
+/// The files of a support bundle, by name within the archive.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Bundle {
+  pub files: Vec<(String, Vec<u8>)>
+}
+
+impl Bundle {
+  /// Collects everything about `config` and the system, along with the end
+  /// of each of `logs`.
+  pub fn collect(config: &Config, logs: &[PathBuf]) -> Self {
+    let mut bundle = Self::default();
+    bundle.add("platform.txt", platform(config));
+    bundle.add(
+      "config.toml",
+      redacted(config).unwrap_or_else(|e| format!("[Failed: {e}]"))
+    );
+    bundle.add("monitors.txt", monitors(config));
+    bundle.add("errors.txt", errors(config));
+    bundle.add("theme.txt", theme());
+
+    let events = journal::file(&config.path);
+    if let Ok(events) = fs::read(events) {
+      bundle.files.push(("events.jsonl".into(), events));
+    }
+    for log in logs {
+      let name = log
+        .file_name()
+        .map_or_else(|| "log".into(), |name| name.to_string_lossy());
+      let tail = tail(log, LOG_TAIL)
+        .unwrap_or_else(|e| format!("[Failed: {e}]").into_bytes());
+      bundle.files.push((format!("logs/{name}"), tail));
+    }
+    bundle
+  }
+
+  fn add(&mut self, name: &str, text: String) {
+    self.files.push((name.to_string(), text.into_bytes()));
+  }
+
+  /// Writes the bundle to `path` as a ZIP archive.
+  pub fn write(&self, path: &Path) -> Result<()> {
+    if simulate::dry_run(Action::WriteFile {
+      path: path.to_path_buf()
+    })? {
+      return Ok(());
+    }
+    let mut writer = zip::Writer::new(BufWriter::new(File::create(path)?));
+    for (name, data) in &self.files {
+      writer.add(name, data)?;
+    }
+    writer.finish()?;
+    Ok(())
+  }
+}
+
+/// What was detected about the platform, and how wallpapers and night light
+/// are driven on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+  pub os: String,
+  /// The desktop environment, on Linux.
+  pub desktop: Option<String>,
+  /// Whether the session is X11 or Wayland, on Linux.
+  pub session: Option<String>,
+  /// The name of the wallpaper backend.
+  pub wallpaper: String,
+  /// Whether the backend can show a different image on each monitor.
+  pub per_monitor: bool,
+  /// How an image is carried across monitors with `wallpaper.continuity`.
+  pub spanning: String,
+  /// How night light limited to some monitors is applied.
+  pub night_light: String
+}
+
+impl Diagnosis {
+  /// Detects the platform and the backends `config` selects for it.
+  pub fn detect(config: &Config) -> Self {
+    let setter = wallpaper::detect(config);
+    let per_monitor = setter.supports_per_monitor();
+    #[allow(unused_mut)]
+    let mut diagnosis = Self {
+      os: format!("{} ({})", env::consts::OS, env::consts::ARCH),
+      desktop: None,
+      session: None,
+      wallpaper: setter.name().to_string(),
+      per_monitor,
+      spanning: match (config.wallpaper.continuity, per_monitor) {
+        (false, _) => "Off".to_string(),
+        (true, true) => "Cut across adjacent monitors".to_string(),
+        (true, false) => "Unavailable with this backend".to_string()
+      },
+      night_light: if cfg!(target_os = "windows") {
+        "Windows Night Light, system-wide".to_string()
+      } else {
+        "Unavailable".to_string()
+      }
+    };
+
+    #[cfg(target_os = "linux")]
+    {
+      use crate::config::color::mode::linux::{
+        DesktopEnvironment, SessionType
+      };
+      let desktop = DesktopEnvironment::detect();
+      let session = SessionType::detect();
+      if config.wallpaper.continuity && desktop == DesktopEnvironment::GNOME {
+        diagnosis.spanning = match session {
+          SessionType::X11 => "Spanned by GNOME".to_string(),
+          _ => "Unavailable on GNOME outside X11".to_string()
+        };
+      }
+      diagnosis.night_light = match (session, &desktop) {
+        (SessionType::X11, _) => "Per-monitor gamma through xrandr".to_string(),
+        (_, DesktopEnvironment::GNOME) =>
+          "GNOME Night Light, system-wide".to_string(),
+        (_, DesktopEnvironment::KDE) =>
+          "KDE Night Color, system-wide".to_string(),
+        _ => "Unavailable".to_string()
+      };
+      diagnosis.desktop = Some(format!("{desktop:?}"));
+      diagnosis.session = Some(session.to_string());
+    }
+    diagnosis
+  }
+}
+
+impl Display for Diagnosis {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    printf!(f, "OS", &self.os)?;
+    if let Some(desktop) = &self.desktop {
+      printf!(f, "Desktop", desktop)?;
+    }
+    if let Some(session) = &self.session {
+      printf!(f, "Session", session)?;
+    }
+    printf!(f, "Wallpaper Backend", &self.wallpaper)?;
+    printf!(f, "Per Monitor", self.per_monitor)?;
+    printf!(f, "Spanning", &self.spanning)?;
+    printf!(f, "Night Light", &self.night_light)
+  }
+}
+
+/// Describes the build, the operating system and the desktop.
+fn platform(config: &Config) -> String {
+  let mut text = String::new();
+  let _ = writeln!(text, "wallter {}", env!("CARGO_PKG_VERSION"));
+  for name in [
+    "XDG_CURRENT_DESKTOP",
+    "XDG_SESSION_TYPE",
+    "DESKTOP_SESSION",
+    "WAYLAND_DISPLAY",
+    "DISPLAY"
+  ] {
+    if let Ok(value) = env::var(name) {
+      let _ = writeln!(text, "{name}: {value}");
+    }
+  }
+  let _ = write!(text, "{}", Diagnosis::detect(config));
+  let _ = writeln!(text, "Color mode: {}", Mode::get_current());
+  let managers: Vec<String> =
+    Manager::detect().iter().map(ToString::to_string).collect();
+  let _ = writeln!(
+    text,
+    "Other managers: {}",
+    if managers.is_empty() {
+      "None".to_string()
+    } else {
+      managers.join(", ")
+    }
+  );
+  text
+}
+
+/// Serializes `config` with the value of every [secret](SECRETS) replaced.
+fn redacted(config: &Config) -> Result<String> {
+  let mut value =
+    toml::Value::try_from(config).map_err(|e| Error::Config(e.to_string()))?;
+  redact(&mut value);
+  toml::to_string(&value).map_err(|e| Error::Config(e.to_string()))
+}
+
+fn redact(value: &mut toml::Value) {
+  match value {
+    toml::Value::Table(table) =>
+      for (key, value) in table.iter_mut() {
+        if SECRETS.contains(&key.as_str()) {
+          *value = toml::Value::String(REDACTED.into());
+        } else {
+          redact(value);
+        }
+      },
+    toml::Value::Array(values) => values.iter_mut().for_each(redact),
+    _ => {}
+  }
+}
+
+/// Lists the monitors as detected when the config was loaded.
+fn monitors(config: &Config) -> String {
+  config
+    .monitors
+    .iter()
+    .map(|monitor| format!("{monitor}\n"))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Reports the health and last error of every source, and the daemon.
+fn errors(config: &Config) -> String {
+  let mut text = match Health::load(&config.path) {
+    Ok(health) => health.to_string(),
+    Err(e) => format!("[Failed to read source health: {e}]\n")
+  };
+  #[cfg(feature = "daemon")]
+  {
+    use crate::slideshow::daemon::Running;
+    let daemon = match Running::load(&config.path) {
+      Ok(Some(running)) => format!(
+        "Running (pid {}, since {})",
+        running.pid,
+        running.started_at.format("%Y-%m-%d %H:%M")
+      ),
+      Ok(None) => "Not running".to_string(),
+      Err(e) => format!("[Failed: {e}]")
+    };
+    let _ = writeln!(text, "Daemon: {daemon}");
+  }
+  text
+}
+
+/// Dumps the settings that decide the theme and night light.
+fn theme() -> String {
+  let mut text = String::new();
+  #[cfg(target_os = "windows")]
+  {
+    use crate::config::color::mode::windows::{Manager, nightlight};
+    use winreg::{RegKey, enums::HKEY_CURRENT_USER};
+    let user = RegKey::predef(HKEY_CURRENT_USER);
+    for path in [Manager::REGISTRY_PATH, r"Software\Microsoft\Windows\DWM"] {
+      let _ = writeln!(text, "[HKCU\\{path}]");
+      match user.open_subkey(path) {
+        Ok(key) =>
+          for (name, value) in key.enum_values().flatten() {
+            let _ = writeln!(text, "{name} = {value}");
+          },
+        Err(e) => {
+          let _ = writeln!(text, "[Failed: {e}]");
+        }
+      }
+    }
+    let _ = writeln!(text, "\nNight light: {:?}", nightlight::get_state());
+  }
+  #[cfg(target_os = "linux")]
+  {
+    use crate::config::color::mode::linux::nightlight;
+    for key in ["color-scheme", "gtk-theme"] {
+      let output =
+        run("gsettings", &["get", "org.gnome.desktop.interface", key]);
+      let _ = writeln!(text, "{key}: {output}");
+    }
+    let _ = writeln!(text, "Night light: {:?}", nightlight::is_enabled());
+  }
+  #[cfg(target_os = "macos")]
+  {
+    let style = run("defaults", &["read", "-g", "AppleInterfaceStyle"]);
+    let _ = writeln!(text, "AppleInterfaceStyle: {style}");
+  }
+  text
+}
+
+/// Runs `program`, returning what it printed or why it failed.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run(program: &str, args: &[&str]) -> String {
+  match std::process::Command::new(program).args(args).output() {
+    Ok(output) if output.status.success() =>
+      String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    Ok(output) => format!(
+      "[Failed: {}]",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ),
+    Err(e) => format!("[Failed: {e}]")
+  }
+}
+
+/// Reads up to the last `limit` bytes of `path`.
+fn tail(path: &Path, limit: u64) -> Result<Vec<u8>> {
+  let mut file = File::open(path)?;
+  let len = file.metadata()?.len();
+  file.seek(SeekFrom::Start(len.saturating_sub(limit)))?;
+  let mut data = Vec::new();
+  file.read_to_end(&mut data)?;
+  Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_redact() {
+    let mut value: toml::Value = toml::from_str(
+      r#"
+      [[sources]]
+      name = "wallhaven"
+      api_key = "hunter2"
+      [sources.oauth]
+      client_secret = "shh"
+      token_url = "https://example.com/token"
+      "#
+    )
+    .unwrap();
+    redact(&mut value);
+    let text = toml::to_string(&value).unwrap();
+    assert!(!text.contains("hunter2") && !text.contains("shh"));
+    assert!(text.contains("token_url = \"https://example.com/token\""));
+  }
+}
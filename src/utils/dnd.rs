@@ -0,0 +1,123 @@
+//! Tells whether the desktop is set not to disturb.
+//!
+//! Windows reports Focus Assist, presentation mode and full-screen apps
+//! through `SHQueryUserNotificationState`, and notifications turned off
+//! altogether in the registry. On Linux, GNOME's switch is read with
+//! `gsettings`, and KDE Plasma's, like that of other desktops whose
+//! notification service supports it, from the service's `Inhibited`
+//! property. macOS Focus can't be read without extra permissions, so it is
+//! never reported.
+
+/// Returns whether the desktop is set not to disturb, or `None` if that
+/// can't be told.
+#[cfg(target_os = "linux")]
+pub fn is_active() -> Option<bool> {
+  use std::process::Command;
+
+  let run = |program: &str, args: &[&str]| {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+      .status
+      .success()
+      .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+  };
+
+  //{ GNOME keeps the switch as a setting, which its shell doesn't export }
+  let gnome = run(
+    "gsettings",
+    &["get", "org.gnome.desktop.notifications", "show-banners"]
+  )
+  .map(|banners| banners.trim() == "false");
+  if gnome == Some(true) {
+    return gnome;
+  }
+  run(
+    "dbus-send",
+    &[
+      "--session",
+      "--print-reply",
+      "--dest=org.freedesktop.Notifications",
+      "/org/freedesktop/Notifications",
+      "org.freedesktop.DBus.Properties.Get",
+      "string:org.freedesktop.Notifications",
+      "string:Inhibited"
+    ]
+  )
+  .and_then(|reply| parse_inhibited(&reply))
+  .or(gnome)
+}
+
+/// Returns whether the desktop is set not to disturb, or `None` if that
+/// can't be told.
+#[cfg(target_os = "windows")]
+#[allow(unsafe_code)]
+pub fn is_active() -> Option<bool> {
+  use super::registry;
+  use winapi::{
+    shared::winerror::S_OK,
+    um::shellapi::{
+      QUNS_ACCEPTS_NOTIFICATIONS, QUNS_NOT_PRESENT,
+      SHQueryUserNotificationState
+    }
+  };
+  use winreg::enums::HKEY_CURRENT_USER;
+
+  const SETTINGS: &str =
+    r"Software\Microsoft\Windows\CurrentVersion\Notifications\Settings";
+  if registry::read_dword(
+    HKEY_CURRENT_USER,
+    SETTINGS,
+    "NOC_GLOBAL_SETTING_TOASTS_ENABLED"
+  ) == Some(0)
+  {
+    return Some(true);
+  }
+
+  let mut state = QUNS_ACCEPTS_NOTIFICATIONS;
+  // SAFETY: `state` is a writable QUERY_USER_NOTIFICATION_STATE.
+  if unsafe { SHQueryUserNotificationState(&mut state) } != S_OK {
+    return None;
+  }
+  //{ Not present means locked or switched away, which isn't asking for quiet }
+  Some(!matches!(
+    state,
+    QUNS_ACCEPTS_NOTIFICATIONS | QUNS_NOT_PRESENT
+  ))
+}
+
+/// Returns whether the desktop is set not to disturb, or `None` if that
+/// can't be told.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn is_active() -> Option<bool> {
+  None
+}
+
+/// Reads the boolean from a `dbus-send --print-reply` of a property, such as
+/// `variant       boolean true`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_inhibited(reply: &str) -> Option<bool> {
+  let mut words = reply.split_whitespace();
+  words.find(|word| *word == "boolean")?;
+  match words.next()? {
+    "true" => Some(true),
+    "false" => Some(false),
+    _ => None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_inhibited() {
+    let reply = "method return time=1718 sender=:1.40 -> destination=:1.99 \
+                 serial=12 reply_serial=2\n   variant       boolean true\n";
+    assert_eq!(parse_inhibited(reply), Some(true));
+    assert_eq!(
+      parse_inhibited("   variant       boolean false"),
+      Some(false)
+    );
+    assert_eq!(parse_inhibited("Error org.freedesktop.DBus.Error"), None);
+  }
+}
@@ -0,0 +1,120 @@
+//! Helpers for locating image files on disk.
+
+use crate::{Result, consts::IMAGE_EXTENSIONS};
+use std::{
+  fs::{File, read_dir},
+  io::Read,
+  path::{Path, PathBuf}
+};
+
+/// Checks whether `path` has a supported image file extension.
+pub fn is_image(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Lists the image files in `dir`, descending into subdirectories if
+/// `recursive` is set. Hidden subdirectories, which hold generated images
+/// such as dark variants, are left out. A missing directory yields no images,
+/// and links to missing files are skipped.
+pub fn list_images(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+  let mut images = Vec::new();
+  if !dir.is_dir() {
+    return Ok(images);
+  }
+
+  for entry in read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_dir() {
+      let hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+      if recursive && !hidden {
+        images.extend(list_images(&path, true)?);
+      }
+    } else if is_image(&path) && path.exists() {
+      images.push(path);
+    }
+  }
+  images.sort();
+  Ok(images)
+}
+
+/// Checks whether `name` matches the shell-style `pattern`, ignoring case.
+/// `*` matches any run of characters and `?` any single one.
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+  let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+  let name: Vec<char> = name.to_lowercase().chars().collect();
+  let (mut p, mut n) = (0, 0);
+  //{ Where the last `*` was, and how much of the name it has taken }
+  let mut star: Option<(usize, usize)> = None;
+  while n < name.len() {
+    match pattern.get(p) {
+      Some('*') => {
+        star = Some((p, n));
+        p += 1;
+      }
+      Some(c) if *c == '?' || *c == name[n] => {
+        p += 1;
+        n += 1;
+      }
+      _ => match star {
+        Some((star_p, star_n)) => {
+          p = star_p + 1;
+          n = star_n + 1;
+          star = Some((star_p, star_n + 1));
+        }
+        None => return false
+      }
+    }
+  }
+  pattern[p..].iter().all(|c| *c == '*')
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Folds `bytes` into a 64-bit FNV-1a `hash`.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+  for byte in bytes {
+    hash = (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME);
+  }
+  hash
+}
+
+/// Hashes the contents of `path` with 64-bit FNV-1a, to recognize the same
+/// image under different names.
+pub fn content_hash(path: &Path) -> Result<u64> {
+  let mut file = File::open(path)?;
+  let mut buffer = [0; 64 * 1024];
+  let mut hash = FNV_OFFSET;
+  loop {
+    let read = file.read(&mut buffer)?;
+    if read == 0 {
+      return Ok(hash);
+    }
+    hash = fnv1a(hash, &buffer[..read]);
+  }
+}
+
+/// Hashes `bytes` like [content_hash] hashes a file.
+pub fn bytes_hash(bytes: &[u8]) -> u64 {
+  fnv1a(FNV_OFFSET, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_matches_glob() {
+    assert!(matches_glob("*.jpg", "Lake.JPG"));
+    assert!(matches_glob("city-??.png", "city-01.png"));
+    assert!(matches_glob("*night*", "city-at-night-4k.png"));
+    assert!(matches_glob("*", ""));
+    assert!(!matches_glob("*.jpg", "lake.png"));
+    assert!(!matches_glob("city-?.png", "city-01.png"));
+  }
+}
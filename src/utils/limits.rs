@@ -0,0 +1,170 @@
+//! Guards against decompression bombs and broken image files.
+//!
+//! Images are checked against the configured [Limits] using only their file
+//! size and header, before any pixel data is decoded. The same goes for
+//! images fetched into memory, such as search thumbnails.
+
+use crate::{Error, Result, config::wallpaper::Limits};
+use image::{DynamicImage, ImageReader};
+use std::{fs::metadata, io::Cursor, path::Path};
+
+/// Checks `path` against `limits` without decoding it, returning its
+/// dimensions.
+pub fn check(path: &Path, limits: &Limits) -> Result<(u32, u32)> {
+  let size = metadata(path)?.len();
+  check_file_size(path, size, limits)?;
+
+  let (width, height) = ImageReader::open(path)?
+    .with_guessed_format()?
+    .into_dimensions()
+    .map_err(|e| Error::ImageRejected {
+      path: path.to_path_buf(),
+      reason: format!("unreadable image header: {e}")
+    })?;
+  check_dimensions(path, (width, height), limits)?;
+  Ok((width, height))
+}
+
+/// Checks a file size of `size` bytes against `limits`.
+pub fn check_file_size(path: &Path, size: u64, limits: &Limits) -> Result<()> {
+  match oversized(size, limits) {
+    Some(reason) => Err(Error::ImageRejected {
+      path: path.to_path_buf(),
+      reason
+    }),
+    None => Ok(())
+  }
+}
+
+/// Explains why `size` bytes exceeds `limits`, if it does.
+fn oversized(size: u64, limits: &Limits) -> Option<String> {
+  (size > limits.max_file_size).then(|| {
+    format!(
+      "file size of {size} bytes exceeds the limit of {}",
+      limits.max_file_size
+    )
+  })
+}
+
+/// Checks already known dimensions against `limits`.
+pub fn check_dimensions(
+  path: &Path,
+  dimensions: (u32, u32),
+  limits: &Limits
+) -> Result<()> {
+  match too_many_pixels(dimensions, limits) {
+    Some(reason) => Err(Error::ImageRejected {
+      path: path.to_path_buf(),
+      reason
+    }),
+    None => Ok(())
+  }
+}
+
+/// Explains why an image of `width` by `height` exceeds `limits`, if it does.
+fn too_many_pixels(
+  (width, height): (u32, u32),
+  limits: &Limits
+) -> Option<String> {
+  let pixels = u64::from(width) * u64::from(height);
+  (pixels > limits.max_pixels).then(|| {
+    format!(
+      "{width}x{height} pixels exceeds the limit of {}",
+      limits.max_pixels
+    )
+  })
+}
+
+/// Caps the decoder's memory use to match `limits`.
+fn decoder_limits(limits: &Limits) -> image::Limits {
+  let mut decoder_limits = image::Limits::default();
+  //{ Allow room for 16-bit RGBA, the largest common in-memory layout }
+  decoder_limits.max_alloc = Some(limits.max_pixels.saturating_mul(8));
+  decoder_limits
+}
+
+/// Checks `path` against `limits` and decodes it, with the decoder's memory
+/// use capped to match.
+pub fn open(path: &Path, limits: &Limits) -> Result<DynamicImage> {
+  check(path, limits)?;
+
+  let mut reader = ImageReader::open(path)?.with_guessed_format()?;
+  reader.limits(decoder_limits(limits));
+  reader.decode().map_err(|e| Error::ImageRejected {
+    path: path.to_path_buf(),
+    reason: format!("failed to decode: {e}")
+  })
+}
+
+/// Checks an encoded image held in memory against `limits` and decodes it,
+/// with the decoder's memory use capped to match.
+pub fn decode(bytes: &[u8], limits: &Limits) -> Result<DynamicImage> {
+  let rejected = |reason| Error::Image(format!("Image rejected: {reason}"));
+  let reader = || ImageReader::new(Cursor::new(bytes)).with_guessed_format();
+  if let Some(reason) = oversized(bytes.len() as u64, limits) {
+    return Err(rejected(reason));
+  }
+  let dimensions = reader()?
+    .into_dimensions()
+    .map_err(|e| rejected(format!("unreadable image header: {e}")))?;
+  if let Some(reason) = too_many_pixels(dimensions, limits) {
+    return Err(rejected(reason));
+  }
+
+  let mut reader = reader()?;
+  reader.limits(decoder_limits(limits));
+  reader
+    .decode()
+    .map_err(|e| rejected(format!("failed to decode: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::RgbImage;
+
+  #[test]
+  fn test_check_rejects_oversized_images() {
+    let path = std::env::temp_dir().join("wallter-limits-test.png");
+    RgbImage::new(64, 32).save(&path).unwrap();
+
+    let generous = Limits::default();
+    assert_eq!(check(&path, &generous).unwrap(), (64, 32));
+
+    let strict = Limits {
+      max_pixels: 1000,
+      ..generous
+    };
+    assert!(matches!(
+      check(&path, &strict),
+      Err(Error::ImageRejected { .. })
+    ));
+
+    let tiny = Limits {
+      max_file_size: 8,
+      ..generous
+    };
+    assert!(matches!(
+      check(&path, &tiny),
+      Err(Error::ImageRejected { .. })
+    ));
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn test_decode_rejects_oversized_images() {
+    let mut bytes = Vec::new();
+    RgbImage::new(64, 32)
+      .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+      .unwrap();
+
+    let generous = Limits::default();
+    assert_eq!(decode(&bytes, &generous).unwrap().width(), 64);
+    let strict = Limits {
+      max_pixels: 1000,
+      ..generous
+    };
+    assert!(matches!(decode(&bytes, &strict), Err(Error::Image(_))));
+    assert!(decode(b"not an image", &generous).is_err());
+  }
+}
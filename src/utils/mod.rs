@@ -2,7 +2,23 @@
 pub mod print;
 pub use print::pout_field;
 
+pub mod dnd;
+
+pub mod files;
+
+#[cfg(feature = "image-processing")]
+pub mod limits;
+
+pub mod notify;
+
 pub mod parse;
 
+#[cfg(feature = "terminal-preview")]
+pub mod preview;
+
+pub mod store;
+
 #[cfg(target_os = "windows")]
 pub mod registry;
+
+pub mod zip;
@@ -0,0 +1,123 @@
+//! Shows desktop notifications.
+//!
+//! Linux uses `notify-send`, macOS `display notification` through
+//! `osascript`, and Windows a toast raised through PowerShell. Notifications
+//! are a courtesy: callers should report a failure rather than stop.
+//!
+//! While the desktop is set [not to disturb](super::dnd), notifications are
+//! held back as the configured [DoNotDisturb] policy says.
+
+use super::dnd;
+use crate::{
+  Error, Result,
+  config::wallpaper::DoNotDisturb,
+  simulate::{self, Action}
+};
+use std::{process::Command, sync::RwLock};
+use tracing::info;
+
+static DO_NOT_DISTURB: RwLock<DoNotDisturb> = RwLock::new(DoNotDisturb::Queue);
+
+/// Sets what happens to notifications while the desktop is set not to
+/// disturb.
+pub fn configure(policy: DoNotDisturb) {
+  *DO_NOT_DISTURB.write().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// Shows a notification with `title` and `body`.
+///
+/// While the desktop is set not to disturb, a queued notification fails
+/// with [Error::Deferred], so the caller can retry it later, and a
+/// suppressed one is dropped.
+pub fn send(title: &str, body: &str) -> Result<()> {
+  let policy = *DO_NOT_DISTURB.read().unwrap_or_else(|e| e.into_inner());
+  if policy != DoNotDisturb::Ignore && dnd::is_active() == Some(true) {
+    if policy == DoNotDisturb::Suppress {
+      info!("Not disturbing with the notification '{title}'");
+      return Ok(());
+    }
+    return Err(Error::Deferred(format!(
+      "The notification '{title}' waits for do not disturb to end"
+    )));
+  }
+
+  if simulate::record(Action::Notify {
+    title: title.to_string(),
+    body: body.to_string()
+  })? {
+    return Ok(());
+  }
+
+  let mut command = command(title, body);
+  let program = command.get_program().to_string_lossy().into_owned();
+  let status = command
+    .status()
+    .map_err(|e| Error::Config(format!("Failed to execute {program}: {e}")))?;
+  if !status.success() {
+    return Err(Error::Config(format!(
+      "{program} could not show the notification"
+    )));
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn command(title: &str, body: &str) -> Command {
+  let mut command = Command::new("notify-send");
+  command.args(["--app-name=wallter", title, body]);
+  command
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> Command {
+  let mut command = Command::new("osascript");
+  command.args([
+    "-e",
+    &format!(
+      "display notification {} with title {}",
+      quote(body),
+      quote(title)
+    )
+  ]);
+  command
+}
+
+#[cfg(target_os = "windows")]
+fn command(title: &str, body: &str) -> Command {
+  //{ Toasts need an application id; PowerShell's own is always registered }
+  const APP_ID: &str = r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+  let script = format!(
+    "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+     $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+     $text = $xml.GetElementsByTagName('text'); \
+     $text.Item(0).AppendChild($xml.CreateTextNode({})) > $null; \
+     $text.Item(1).AppendChild($xml.CreateTextNode({})) > $null; \
+     [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{APP_ID}').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+    quote(title),
+    quote(body)
+  );
+  let mut command = Command::new("powershell");
+  command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+  command
+}
+
+#[cfg(not(any(
+  target_os = "linux",
+  target_os = "macos",
+  target_os = "windows"
+)))]
+fn command(title: &str, body: &str) -> Command {
+  let mut command = Command::new("notify-send");
+  command.args([title, body]);
+  command
+}
+
+/// Quotes `text` for AppleScript or PowerShell.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn quote(text: &str) -> String {
+  if cfg!(target_os = "windows") {
+    format!("'{}'", text.replace('\'', "''"))
+  } else {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+  }
+}
@@ -105,6 +105,37 @@ pub fn last_modified_timestamp_block(
   Ok((timestamp, pos))
 }
 
+/// Parses the time block that follows `prefix`, searching from `start_from`.
+/// Returns `None` if there is no such block.
+pub fn time_block(
+  data: &[u8],
+  start_from: usize,
+  prefix: &[u8]
+) -> Result<Option<NaiveTime>, Error> {
+  let Some(found) = data
+    .get(start_from..)
+    .and_then(|rest| rest.windows(prefix.len()).position(|w| w == prefix))
+  else {
+    return Ok(None);
+  };
+  let mut pos = start_from + found + prefix.len();
+
+  //{ Zero hours or minutes are left out along with their prefix }
+  let mut field = |prefix: u8| match data.get(pos..pos + 2) {
+    Some([marker, value]) if *marker == prefix => {
+      pos += 2;
+      *value
+    }
+    _ => 0
+  };
+  let hours = field(TIME_HOUR_PREFIX);
+  let minutes = field(TIME_MINUTE_PREFIX);
+  if data.get(pos) != Some(&TIME_SUFFIX) {
+    return Err(Error::Block("Unterminated time block".to_string()));
+  }
+  time_to_naive_time(hours, minutes).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -124,4 +155,18 @@ mod tests {
     let kelvin_from_bytes = kelvin_from_bytes(bytes);
     assert_eq!(color_temperature, kelvin_from_bytes);
   }
+
+  #[test]
+  fn test_time_block() {
+    let data = [
+      0x43, 0x42, 0xCA, 0x14, 0x0E, 0x15, 0x2E, 0x1E, 0x00, 0xCA, 0x1E, 0x0E,
+      0x07, 0x00, 0xCA, 0x32, 0x00
+    ];
+    let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+    assert_eq!(time_block(&data, 0, &[0xCA, 0x14]).unwrap(), time(21, 30));
+    assert_eq!(time_block(&data, 0, &[0xCA, 0x1E]).unwrap(), time(7, 0));
+    assert_eq!(time_block(&data, 0, &[0xCA, 0x32]).unwrap(), time(0, 0));
+    assert_eq!(time_block(&data, 0, &[0xCA, 0x3C]).unwrap(), None);
+    assert!(time_block(&data[..8], 0, &[0xCA, 0x14]).is_err());
+  }
 }
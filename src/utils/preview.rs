@@ -0,0 +1,214 @@
+//! Draws images inline in the terminal.
+//!
+//! Three graphics protocols are supported: kitty's, iTerm2's inline images
+//! and sixel. [Protocol::detect] picks one from the environment; terminals
+//! without any of them get no preview.
+
+use crate::{Error, Result};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+use image::{DynamicImage, ImageFormat, imageops::FilterType};
+use std::{
+  env,
+  fmt::Write,
+  io::{Cursor, IsTerminal, stdout}
+};
+
+/// The size of each base64 chunk sent with the kitty protocol.
+const KITTY_CHUNK: usize = 4096;
+
+/// The assumed width of a terminal cell, used to size sixel images.
+const CELL_WIDTH: u32 = 8;
+
+/// A terminal graphics protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Protocol {
+  Kitty,
+  Iterm2,
+  Sixel
+}
+
+impl Protocol {
+  /// Guesses the protocol the terminal on stdout understands, if any.
+  pub fn detect() -> Option<Self> {
+    if !stdout().is_terminal() {
+      return None;
+    }
+    let var = |name| env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    if env::var_os("KITTY_WINDOW_ID").is_some()
+      || term.contains("kitty")
+      || term.contains("ghostty")
+      || program == "ghostty"
+    {
+      Some(Self::Kitty)
+    } else if program == "iTerm.app"
+      || program == "WezTerm"
+      || var("LC_TERMINAL") == "iTerm2"
+    {
+      Some(Self::Iterm2)
+    } else if term.contains("sixel")
+      || term.starts_with("foot")
+      || term == "mlterm"
+      || env::var_os("WT_SESSION").is_some()
+    {
+      Some(Self::Sixel)
+    } else {
+      None
+    }
+  }
+
+  /// Returns the escape sequence that draws `image` `columns` cells wide.
+  pub fn render(self, image: &DynamicImage, columns: u32) -> Result<String> {
+    match self {
+      Self::Kitty => Ok(kitty(&png(image)?, columns)),
+      Self::Iterm2 => Ok(iterm2(&png(image)?, columns)),
+      Self::Sixel => {
+        let width = columns * CELL_WIDTH;
+        Ok(sixel(&image.resize(width, width, FilterType::Triangle)))
+      }
+    }
+  }
+}
+
+fn png(image: &DynamicImage) -> Result<Vec<u8>> {
+  let mut bytes = Vec::new();
+  image
+    .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+    .map_err(|e| Error::Image(e.to_string()))?;
+  Ok(bytes)
+}
+
+/// Transmits a PNG with the kitty graphics protocol, in chunks.
+fn kitty(png: &[u8], columns: u32) -> String {
+  let encoded = STANDARD.encode(png);
+  let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK).collect();
+  let mut out = String::new();
+  for (index, chunk) in chunks.iter().enumerate() {
+    let more = u8::from(index + 1 < chunks.len());
+    let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+    if index == 0 {
+      let _ = write!(out, "\x1b_Gf=100,a=T,c={columns},m={more};{chunk}\x1b\\");
+    } else {
+      let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+    }
+  }
+  out
+}
+
+/// Sends a PNG as an iTerm2 inline image.
+fn iterm2(png: &[u8], columns: u32) -> String {
+  format!(
+    "\x1b]1337;File=inline=1;size={};width={columns};preserveAspectRatio=1:{}\x07",
+    png.len(),
+    STANDARD.encode(png)
+  )
+}
+
+/// Encodes an image as sixel, using a 6x6x6 color cube for the palette.
+fn sixel(image: &DynamicImage) -> String {
+  let rgb = image.to_rgb8();
+  let (width, height) = rgb.dimensions();
+  let level = |value: u8| (u16::from(value) * 5 + 127) / 255;
+  let indices: Vec<u16> = rgb
+    .pixels()
+    .map(|pixel| level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]))
+    .collect();
+
+  let mut out = format!("\x1bPq\"1;1;{width};{height}");
+  let mut used = [false; 216];
+  for &index in &indices {
+    used[usize::from(index)] = true;
+  }
+  for (index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+    let (r, g, b) = (index / 36, index / 6 % 6, index % 6);
+    let _ = write!(out, "#{index};2;{};{};{}", r * 20, g * 20, b * 20);
+  }
+
+  //{ Each band is six pixel rows, drawn once per color present in it }
+  let width = width as usize;
+  for top in (0..height as usize).step_by(6) {
+    let rows = (height as usize - top).min(6);
+    let band = &indices[top * width..(top + rows) * width];
+    let mut colors: Vec<u16> = band.to_vec();
+    colors.sort_unstable();
+    colors.dedup();
+
+    for (position, &color) in colors.iter().enumerate() {
+      if position > 0 {
+        out.push('$');
+      }
+      let _ = write!(out, "#{color}");
+      let columns = (0..width).map(|x| {
+        let bits = (0..rows)
+          .filter(|row| band[row * width + x] == color)
+          .fold(0u8, |bits, row| bits | 1 << row);
+        char::from(63 + bits)
+      });
+      run_length(&mut out, columns);
+    }
+    out.push('-');
+  }
+  out.push_str("\x1b\\");
+  out
+}
+
+/// Writes sixel characters, collapsing runs into `!<count><char>`.
+fn run_length(out: &mut String, chars: impl Iterator<Item = char>) {
+  let flush = |out: &mut String, char, count| match count {
+    0 => {}
+    1..=3 => (0..count).for_each(|_| out.push(char)),
+    _ => {
+      let _ = write!(out, "!{count}{char}");
+    }
+  };
+  let mut current = None;
+  let mut count = 0;
+  for char in chars {
+    if current == Some(char) {
+      count += 1;
+    } else {
+      if let Some(previous) = current {
+        flush(out, previous, count);
+      }
+      current = Some(char);
+      count = 1;
+    }
+  }
+  if let Some(previous) = current {
+    flush(out, previous, count);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{Rgb, RgbImage};
+
+  #[test]
+  fn test_sixel() {
+    //{ A 4x7 image: red on top, then a row of blue spilling into a second
+    //{ band }
+    let image = RgbImage::from_fn(4, 7, |_, y| {
+      if y < 6 {
+        Rgb([255, 0, 0])
+      } else {
+        Rgb([0, 0, 255])
+      }
+    });
+    assert_eq!(
+      sixel(&DynamicImage::ImageRgb8(image)),
+      "\x1bPq\"1;1;4;7#5;2;0;0;100#180;2;100;0;0#180!4~-#5!4@-\x1b\\"
+    );
+  }
+
+  #[test]
+  fn test_kitty_chunks() {
+    let encoded = kitty(&[0; 4000], 40);
+    assert_eq!(encoded.matches("\x1b_G").count(), 2);
+    assert!(encoded.starts_with("\x1b_Gf=100,a=T,c=40,m=1;"));
+    assert!(encoded.contains("\x1b_Gm=0;"));
+  }
+}
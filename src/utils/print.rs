@@ -4,8 +4,8 @@ use std::fmt::{self, Formatter};
 /// output.
 ///
 /// # Example
-/// ```
-/// print_field(f, "Name", "DISPLAY1", 11)?;
+/// ```ignore
+/// pout_field(f, "Name", "DISPLAY1", 11, 4)?;
 /// ```
 pub fn pout_field<T: fmt::Display>(
   f: &mut Formatter<'_>,
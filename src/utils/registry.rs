@@ -6,7 +6,7 @@ use crate::{Error, Result};
 use std::io;
 use winreg::{
   HKEY, RegKey, RegValue,
-  enums::{KEY_READ, KEY_SET_VALUE, REG_BINARY}
+  enums::{KEY_READ, KEY_SET_VALUE, REG_BINARY, REG_DWORD}
 };
 
 /// Reads a raw binary value from the specified registry key and value name.
@@ -36,6 +36,15 @@ pub fn write_bytes(
   name: &str,
   data: &[u8]
 ) -> Result<()> {
+  //{ Journal the write instead of performing it when simulating }
+  if crate::simulate::record(crate::simulate::Action::RegistryWrite {
+    path: path.to_string(),
+    name: name.to_string(),
+    bytes: data.to_vec()
+  })? {
+    return Ok(());
+  }
+
   let root = RegKey::predef(hive);
   let key = root
     .open_subkey_with_flags(path, KEY_SET_VALUE)
@@ -58,6 +67,52 @@ pub fn write_bytes(
   })
 }
 
+/// Reads a DWORD value, or `None` if the key or value doesn't exist.
+pub fn read_dword(hive: HKEY, path: &str, name: &str) -> Option<u32> {
+  RegKey::predef(hive)
+    .open_subkey_with_flags(path, KEY_READ)
+    .and_then(|key| key.get_value::<u32, _>(name))
+    .ok()
+}
+
+/// Writes a DWORD value, creating the key if needed.
+pub fn write_dword(
+  hive: HKEY,
+  path: &str,
+  name: &str,
+  value: u32
+) -> Result<()> {
+  //{ Journal the write instead of performing it when simulating }
+  if crate::simulate::record(crate::simulate::Action::RegistryWrite {
+    path: path.to_string(),
+    name: name.to_string(),
+    bytes: value.to_le_bytes().to_vec()
+  })? {
+    return Ok(());
+  }
+
+  let (key, _) = RegKey::predef(hive).create_subkey(path).map_err(|e| {
+    Error::IO(io::Error::new(
+      io::ErrorKind::PermissionDenied,
+      format!("Failed to open registry key '{path}' for writing: {e}")
+    ))
+  })?;
+  key
+    .set_raw_value(
+      name,
+      &RegValue {
+        bytes: value.to_le_bytes().to_vec(),
+        vtype: REG_DWORD
+      }
+    )
+    .map_err(|e| {
+      Error::IO(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("Failed to write registry value '{name}' to key '{path}': {e}")
+      ))
+    })
+}
+
 /// Checks if a registry key exists.
 pub fn key_exists(hive: HKEY, path: &str) -> bool {
   let root = RegKey::predef(hive);
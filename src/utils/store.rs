@@ -0,0 +1,48 @@
+//! Helpers for persisting application state as JSON files.
+
+use crate::{
+  Error, Result,
+  simulate::{self, Action}
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{
+  fs::{create_dir_all, read_to_string, rename, write},
+  path::Path
+};
+
+/// Loads a JSON state file, returning the default value if it doesn't exist.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+  if !path.exists() {
+    return Ok(T::default());
+  }
+  let content = read_to_string(path)?;
+  serde_json::from_str(&content).map_err(|e| {
+    Error::Config(format!("Failed to parse '{}': {e}", path.display()))
+  })
+}
+
+/// Saves a value to a JSON state file.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+  let contents = serde_json::to_string_pretty(value)
+    .map_err(|e| Error::Config(e.to_string()))?;
+  replace(path, &contents)
+}
+
+/// Replaces the contents of a file, unless this is a dry run.
+///
+/// The contents are written to a temporary file first and then renamed over
+/// the target, so an interrupted write never leaves a truncated file behind.
+pub fn replace(path: &Path, contents: &str) -> Result<()> {
+  if simulate::dry_run(Action::WriteFile {
+    path: path.to_path_buf()
+  })? {
+    return Ok(());
+  }
+  if let Some(parent) = path.parent() {
+    create_dir_all(parent)?;
+  }
+  let temp = path.with_extension("tmp");
+  write(&temp, contents)?;
+  rename(&temp, path)?;
+  Ok(())
+}
@@ -0,0 +1,172 @@
+//! Writes ZIP archives of files held in memory, deflate-compressed.
+//!
+//! Only what the support bundle needs is covered: whole files, no folders
+//! and no ZIP64, so every file, and the archive itself, must stay under
+//! 4 GiB.
+
+use chrono::{Datelike, Local, Timelike};
+use flate2::{Compression, write::DeflateEncoder};
+use std::io::{self, Write};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+
+/// Version 2.0, the first with deflate.
+const VERSION: u16 = 20;
+
+/// Marks names as UTF-8.
+const UTF8_NAMES: u16 = 1 << 11;
+
+const DEFLATE: u16 = 8;
+
+/// A file already written, as the central directory lists it.
+struct Entry {
+  name: String,
+  crc: u32,
+  compressed: u32,
+  size: u32,
+  offset: u32
+}
+
+/// Writes a ZIP archive to `W`, one file at a time.
+pub struct Writer<W: Write> {
+  inner: W,
+  offset: u32,
+  entries: Vec<Entry>,
+  /// The modification time given to every file, in MS-DOS format.
+  time: u16,
+  date: u16
+}
+
+impl<W: Write> Writer<W> {
+  /// Starts an archive, dating every file now.
+  pub fn new(inner: W) -> Self {
+    let now = Local::now();
+    Self {
+      inner,
+      offset: 0,
+      entries: Vec::new(),
+      time: ((now.hour() << 11) | (now.minute() << 5) | (now.second() / 2))
+        as u16,
+      date: (((now.year().max(1980) - 1980) as u32) << 9
+        | (now.month() << 5)
+        | now.day()) as u16
+    }
+  }
+
+  /// Compresses `data` into the archive as `name`.
+  pub fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    let entry = Entry {
+      name: name.to_string(),
+      crc: crc32fast::hash(data),
+      compressed: len32(compressed.len())?,
+      size: len32(data.len())?,
+      offset: self.offset
+    };
+
+    let mut header = Vec::with_capacity(30 + name.len());
+    header.extend(LOCAL_HEADER.to_le_bytes());
+    header.extend(VERSION.to_le_bytes());
+    self.describe(&mut header, &entry)?;
+    header.extend(0u16.to_le_bytes());
+    header.extend(name.as_bytes());
+
+    self.inner.write_all(&header)?;
+    self.inner.write_all(&compressed)?;
+    self.offset = self
+      .offset
+      .checked_add(len32(header.len() + compressed.len())?)
+      .ok_or_else(too_large)?;
+    self.entries.push(entry);
+    Ok(())
+  }
+
+  /// Writes the central directory, completing the archive, and returns the
+  /// underlying writer.
+  pub fn finish(mut self) -> io::Result<W> {
+    let mut directory = Vec::new();
+    for entry in &self.entries {
+      directory.extend(CENTRAL_HEADER.to_le_bytes());
+      directory.extend(VERSION.to_le_bytes());
+      directory.extend(VERSION.to_le_bytes());
+      self.describe(&mut directory, entry)?;
+      //{ No extra field or comment, on disk 0, with no attributes }
+      directory.extend([0; 12]);
+      directory.extend(entry.offset.to_le_bytes());
+      directory.extend(entry.name.as_bytes());
+    }
+
+    let count = u16::try_from(self.entries.len()).map_err(|_| too_large())?;
+    let mut end = Vec::with_capacity(22);
+    end.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    end.extend([0; 4]);
+    end.extend(count.to_le_bytes());
+    end.extend(count.to_le_bytes());
+    end.extend(len32(directory.len())?.to_le_bytes());
+    end.extend(self.offset.to_le_bytes());
+    end.extend(0u16.to_le_bytes());
+
+    self.inner.write_all(&directory)?;
+    self.inner.write_all(&end)?;
+    self.inner.flush()?;
+    Ok(self.inner)
+  }
+
+  /// Appends the fields local and central headers share, from the flags to
+  /// the name length.
+  fn describe(&self, header: &mut Vec<u8>, entry: &Entry) -> io::Result<()> {
+    header.extend(UTF8_NAMES.to_le_bytes());
+    header.extend(DEFLATE.to_le_bytes());
+    header.extend(self.time.to_le_bytes());
+    header.extend(self.date.to_le_bytes());
+    header.extend(entry.crc.to_le_bytes());
+    header.extend(entry.compressed.to_le_bytes());
+    header.extend(entry.size.to_le_bytes());
+    let name = u16::try_from(entry.name.len()).map_err(|_| too_large())?;
+    header.extend(name.to_le_bytes());
+    Ok(())
+  }
+}
+
+fn len32(len: usize) -> io::Result<u32> {
+  u32::try_from(len).map_err(|_| too_large())
+}
+
+fn too_large() -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidInput, "Too large for a ZIP archive")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use flate2::read::DeflateDecoder;
+  use std::io::Read;
+
+  #[test]
+  fn test_writer() {
+    let mut writer = Writer::new(Vec::new());
+    writer
+      .add("config.toml", b"[source]\nordered = []\n")
+      .unwrap();
+    writer.add("logs/daemon.log", &[b'x'; 4096]).unwrap();
+    let archive = writer.finish().unwrap();
+
+    assert_eq!(archive[..4], LOCAL_HEADER.to_le_bytes());
+    let end = &archive[archive.len() - 22..];
+    assert_eq!(end[..4], END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    assert_eq!(end[10..12], 2u16.to_le_bytes());
+
+    //{ The first file follows its 30-byte header and name }
+    let compressed = u32::from_le_bytes(archive[18..22].try_into().unwrap());
+    let start = 30 + "config.toml".len();
+    let mut data = String::new();
+    DeflateDecoder::new(&archive[start..start + compressed as usize])
+      .read_to_string(&mut data)
+      .unwrap();
+    assert_eq!(data, "[source]\nordered = []\n");
+  }
+}
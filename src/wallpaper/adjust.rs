@@ -0,0 +1,221 @@
+//! Crops and pans wallpapers to taste, per image and monitor.
+//!
+//! An image is normally scaled to fill a monitor, centred. An [Adjustment]
+//! moves the view off centre and zooms in, and is kept in the
+//! [library](super::Library) so the image is cropped the same way whenever
+//! it comes back on that monitor. `wallter adjust` changes the adjustment of
+//! what is on screen and shows the result straight away.
+
+use super::{Library, default::current_file};
+use crate::{
+  Config, Error, Result,
+  config::Monitor,
+  simulate::{self, Action},
+  utils::limits
+};
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  fs::create_dir_all,
+  path::{Path, PathBuf}
+};
+
+/// How an image is cropped for one monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Adjustment {
+  /// How far the view moves right of centre, as a share of the image width.
+  /// Negative values move it left.
+  pub pan_x: f32,
+  /// How far the view moves down from centre, as a share of the image
+  /// height. Negative values move it up.
+  pub pan_y: f32,
+  /// How far the view is magnified beyond filling the monitor.
+  pub zoom: f32
+}
+
+impl Default for Adjustment {
+  fn default() -> Self {
+    Self {
+      pan_x: 0.0,
+      pan_y: 0.0,
+      zoom: 1.0
+    }
+  }
+}
+
+impl Display for Adjustment {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "pan {:+.0}% x {:+.0}% y, zoom {:.2}x",
+      self.pan_x * 100.0,
+      self.pan_y * 100.0,
+      self.zoom
+    )
+  }
+}
+
+impl Adjustment {
+  /// Checks that the pans are within the image and the zoom is at least 1.
+  pub fn validate(&self) -> Result<()> {
+    if !(-1.0..=1.0).contains(&self.pan_x)
+      || !(-1.0..=1.0).contains(&self.pan_y)
+    {
+      return Err(Error::Settings(
+        "Pans must be between -100% and 100%".to_string()
+      ));
+    }
+    if !(1.0..=16.0).contains(&self.zoom) {
+      return Err(Error::Settings(
+        "The zoom must be between 1 and 16".to_string()
+      ));
+    }
+    Ok(())
+  }
+
+  /// Returns the part of a `width` by `height` image shown on a monitor of
+  /// `target_width` by `target_height`, as x, y, width and height. The view
+  /// never leaves the image, so large pans stop at its edges.
+  pub fn view(
+    &self,
+    (width, height): (u32, u32),
+    (target_width, target_height): (u32, u32)
+  ) -> (u32, u32, u32, u32) {
+    let aspect = f64::from(target_width) / f64::from(target_height.max(1));
+    let (full_width, full_height) = (f64::from(width), f64::from(height));
+    //{ The largest view of the monitor's shape, narrowed by the zoom }
+    let (view_width, view_height) = if full_width / full_height > aspect {
+      (full_height * aspect, full_height)
+    } else {
+      (full_width, full_width / aspect)
+    };
+    let zoom = f64::from(self.zoom.max(1.0));
+    let view_width = ((view_width / zoom) as u32).clamp(1, width);
+    let view_height = ((view_height / zoom) as u32).clamp(1, height);
+
+    let offset = |full: f64, view: u32, pan: f32| {
+      let centre = full / 2.0 + f64::from(pan) * full;
+      let slack = full - f64::from(view);
+      (centre - f64::from(view) / 2.0).round().clamp(0.0, slack) as u32
+    };
+    (
+      offset(full_width, view_width, self.pan_x),
+      offset(full_height, view_height, self.pan_y),
+      view_width,
+      view_height
+    )
+  }
+}
+
+/// Reads a share such as `10%`, `-5%` or `0.1`.
+pub fn parse_share(value: &str) -> Result<f32> {
+  let trimmed = value.trim();
+  let (number, scale) = match trimmed.strip_suffix('%') {
+    Some(number) => (number, 100.0),
+    None => (trimmed, 1.0)
+  };
+  number
+    .trim()
+    .parse::<f32>()
+    .map(|share| share / scale)
+    .map_err(|_| Error::Settings(format!("Invalid share '{value}'")))
+}
+
+/// Returns the adjustments of `image`, by monitor name.
+pub fn of(
+  config: &Config,
+  image: &Path
+) -> Result<BTreeMap<String, Adjustment>> {
+  Ok(
+    Library::load(&config.path)?
+      .adjustments
+      .remove(image)
+      .unwrap_or_default()
+  )
+}
+
+/// Writes `image` to the current wallpaper file of `monitor`, cropped as
+/// `adjustment` says and scaled to the monitor, returning the file.
+pub fn apply(
+  config: &Config,
+  image: &Path,
+  monitor: &Monitor,
+  adjustment: &Adjustment
+) -> Result<PathBuf> {
+  let output = current_file(config, monitor).with_extension("png");
+  if simulate::dry_run(Action::WriteFile {
+    path: output.clone()
+  })? {
+    return Ok(output);
+  }
+  let source = limits::open(image, &config.wallpaper.limits)?;
+  let target = (monitor.size.width, monitor.size.height);
+  let (x, y, width, height) =
+    adjustment.view((source.width(), source.height()), target);
+  if let Some(parent) = output.parent() {
+    create_dir_all(parent)?;
+  }
+  source
+    .crop_imm(x, y, width, height)
+    .resize_exact(target.0, target.1, FilterType::Lanczos3)
+    .save(&output)
+    .map_err(|e| Error::Image(e.to_string()))?;
+  Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_view() {
+    //{ A 4:3 image on a 16:9 monitor loses its top and bottom }
+    let centred = Adjustment::default();
+    assert_eq!(
+      centred.view((1600, 1200), (1920, 1080)),
+      (0, 150, 1600, 900)
+    );
+
+    let panned = Adjustment {
+      pan_y: -0.1,
+      ..centred
+    };
+    assert_eq!(panned.view((1600, 1200), (1920, 1080)), (0, 30, 1600, 900));
+
+    //{ Pans stop at the edges }
+    let far = Adjustment {
+      pan_y: 1.0,
+      ..centred
+    };
+    assert_eq!(far.view((1600, 1200), (1920, 1080)), (0, 300, 1600, 900));
+
+    let zoomed = Adjustment {
+      pan_x: 0.1,
+      zoom: 2.0,
+      ..centred
+    };
+    assert_eq!(
+      zoomed.view((1600, 1200), (1920, 1080)),
+      (560, 375, 800, 450)
+    );
+  }
+
+  #[test]
+  fn test_parse_share() {
+    assert_eq!(parse_share("10%").unwrap(), 0.1);
+    assert_eq!(parse_share("-25%").unwrap(), -0.25);
+    assert_eq!(parse_share("0.5").unwrap(), 0.5);
+    assert!(parse_share("left").is_err());
+    assert!(
+      Adjustment {
+        zoom: 0.5,
+        ..Default::default()
+      }
+      .validate()
+      .is_err()
+    );
+  }
+}
@@ -0,0 +1,172 @@
+//! Keeps credits for downloaded images whose providers require attribution.
+//!
+//! Images downloaded from Unsplash, Pexels or Pixabay are listed in
+//! `ATTRIBUTIONS.json` in the downloads directory, alongside a rendered
+//! `ATTRIBUTIONS.md`. An entry is written as part of the download: if it
+//! cannot be saved, the download is discarded.
+
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::Write as _,
+  path::{self, PathBuf}
+};
+
+/// The name of the attributions index within the downloads directory.
+const ATTRIBUTIONS_FILE: &str = "ATTRIBUTIONS.json";
+
+/// The name of the rendered attributions within the downloads directory.
+const ATTRIBUTIONS_MARKDOWN: &str = "ATTRIBUTIONS.md";
+
+/// Image hosts that require attribution: host, provider and license.
+const PROVIDERS: [(&str, &str, &str); 3] = [
+  ("unsplash.com", "Unsplash", "Unsplash License"),
+  ("pexels.com", "Pexels", "Pexels License"),
+  ("pixabay.com", "Pixabay", "Pixabay Content License")
+];
+
+/// The credit for a downloaded image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+  /// The provider the image was downloaded from.
+  pub provider: String,
+  /// The artist, if the provider reported one.
+  pub artist: Option<String>,
+  /// The address the image was downloaded from.
+  pub link: String,
+  /// The license the image is used under.
+  pub license: String,
+  /// When the image was downloaded.
+  pub downloaded_at: DateTime<Local>
+}
+
+impl Attribution {
+  /// Returns the credit required for an image downloaded from `url`, or
+  /// `None` if its provider doesn't require attribution.
+  pub fn required(url: &str, artist: Option<&str>) -> Option<Self> {
+    let host = Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    let (_, provider, license) = PROVIDERS.iter().find(|(domain, ..)| {
+      host == *domain || host.ends_with(&format!(".{domain}"))
+    })?;
+    Some(Self {
+      provider: provider.to_string(),
+      artist: artist.map(str::to_string),
+      link: url.to_string(),
+      license: license.to_string(),
+      downloaded_at: Local::now()
+    })
+  }
+}
+
+/// The credits for the library, keyed by image file name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attributions {
+  pub items: BTreeMap<String, Attribution>
+}
+
+impl Attributions {
+  /// Returns the path of the attributions index.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.downloads_dir.join(ATTRIBUTIONS_FILE)
+  }
+
+  /// Loads the attributions from the downloads directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the attributions index and the rendered Markdown file.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)?;
+    store::replace(
+      &path_config.downloads_dir.join(ATTRIBUTIONS_MARKDOWN),
+      &self.to_markdown()
+    )
+  }
+
+  /// Records the credit for `image`, replacing any earlier one.
+  pub fn insert(&mut self, image: &path::Path, attribution: Attribution) {
+    self.items.insert(key(image), attribution);
+  }
+
+  /// Forgets the credit for `image`.
+  pub fn remove(&mut self, image: &path::Path) -> Option<Attribution> {
+    self.items.remove(&key(image))
+  }
+
+  /// Renders the attributions as a Markdown table.
+  pub fn to_markdown(&self) -> String {
+    let mut markdown = String::from(
+      "# Attributions\n\n| Image | Artist | Source | License |\n| --- | --- | --- | --- |\n"
+    );
+    for (image, credit) in &self.items {
+      let _ = writeln!(
+        markdown,
+        "| {image} | {} | [{}]({}) | {} |",
+        credit.artist.as_deref().unwrap_or("Unknown"),
+        credit.provider,
+        credit.link,
+        credit.license
+      );
+    }
+    markdown
+  }
+}
+
+/// Records the credit for `image`, downloaded from `url`, if its provider
+/// requires one.
+pub fn record(
+  path_config: &Path,
+  image: &path::Path,
+  url: &str,
+  artist: Option<&str>
+) -> Result<()> {
+  let Some(attribution) = Attribution::required(url, artist) else {
+    return Ok(());
+  };
+  let mut attributions = Attributions::load(path_config)?;
+  attributions.insert(image, attribution);
+  attributions.save(path_config)
+}
+
+fn key(image: &path::Path) -> String {
+  image
+    .file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_attribution_required() {
+    let credit = Attribution::required(
+      "https://images.unsplash.com/photo-1?w=3840",
+      Some("Jane Doe")
+    )
+    .unwrap();
+    assert_eq!(credit.provider, "Unsplash");
+    assert_eq!(credit.artist.as_deref(), Some("Jane Doe"));
+    assert!(
+      Attribution::required("https://images.pexels.com/a.jpg", None).is_some()
+    );
+    assert!(
+      Attribution::required("https://w.wallhaven.cc/full/a.jpg", None)
+        .is_none()
+    );
+    assert!(
+      Attribution::required("https://notunsplash.com/a.jpg", None).is_none()
+    );
+
+    let mut attributions = Attributions::default();
+    attributions.insert(path::Path::new("/downloads/photo-1.jpg"), credit);
+    assert!(attributions.to_markdown().contains(
+      "| photo-1.jpg | Jane Doe | [Unsplash](https://images.unsplash.com/photo-1?w=3840) | Unsplash License |"
+    ));
+  }
+}
@@ -0,0 +1,103 @@
+//! Images that must never be shown again, such as files that exceeded the
+//! safety limits or could not be decoded.
+
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::PathBuf
+};
+
+/// The name of the blocklist file within the state directory.
+const BLOCKLIST_FILE: &str = "blocklist.json";
+
+/// Why and when an item was blocked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blocked {
+  pub reason: String,
+  pub since: DateTime<Local>
+}
+
+/// Blocked wallpapers, keyed by URL or file path.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blocklist {
+  pub items: BTreeMap<String, Blocked>
+}
+
+impl Blocklist {
+  /// Returns the path of the blocklist file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(BLOCKLIST_FILE)
+  }
+
+  /// Loads the blocklist from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the blocklist to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Blocks `item` for `reason`.
+  pub fn add(&mut self, item: impl Into<String>, reason: impl Into<String>) {
+    self.items.insert(
+      item.into(),
+      Blocked {
+        reason: reason.into(),
+        since: Local::now()
+      }
+    );
+  }
+
+  /// Unblocks `item`, returning whether it was blocked.
+  pub fn remove(&mut self, item: &str) -> bool {
+    self.items.remove(item).is_some()
+  }
+
+  /// Returns why `item` is blocked, if it is.
+  pub fn get(&self, item: &str) -> Option<&Blocked> {
+    self.items.get(item)
+  }
+
+  /// Checks whether `item` is blocked.
+  pub fn contains(&self, item: &str) -> bool {
+    self.items.contains_key(item)
+  }
+}
+
+impl Display for Blocklist {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.items.is_empty() {
+      return printf!(f, "Blocked", "[None]");
+    }
+    for (item, blocked) in &self.items {
+      printf!(f, item, &blocked.reason)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_blocklist() {
+    let mut blocklist = Blocklist::default();
+    blocklist.add("https://example.com/bomb.png", "too many pixels");
+    assert!(blocklist.contains("https://example.com/bomb.png"));
+    assert_eq!(
+      blocklist
+        .get("https://example.com/bomb.png")
+        .unwrap()
+        .reason,
+      "too many pixels"
+    );
+    assert!(blocklist.remove("https://example.com/bomb.png"));
+    assert!(!blocklist.contains("https://example.com/bomb.png"));
+  }
+}
@@ -0,0 +1,279 @@
+//! Keeps the downloads directory within the configured size.
+//!
+//! Downloads are evicted least recently used first, going by when each was
+//! last shown or, failing that, downloaded. Images the user still refers to
+//! are never evicted: those on screen, kept as favorites, imported, in a
+//! collection, waiting in the slideshow queue or pinned to a workspace.
+//! Duplicate downloads are [links](super::library) to an original, so they
+//! take no room of their own and the original is kept while they remain.
+
+use super::{Current, History, Library};
+use crate::{
+  Config, Result,
+  favorites::Favorites,
+  simulate::{self, Action},
+  slideshow::{Queue, QueueItem},
+  utils::files
+};
+use std::{
+  collections::{HashMap, HashSet},
+  fs,
+  path::{Path, PathBuf},
+  time::SystemTime
+};
+
+/// The number and total size of some files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+  pub files: usize,
+  pub bytes: u64
+}
+
+impl Usage {
+  /// Adds up the files in `dir` and its subdirectories.
+  pub fn of(dir: &Path) -> Result<Self> {
+    let mut usage = Self::default();
+    if !dir.is_dir() {
+      return Ok(usage);
+    }
+    for entry in fs::read_dir(dir)? {
+      let entry = entry?;
+      let metadata = entry.metadata()?;
+      if metadata.is_symlink() {
+        continue;
+      }
+      if metadata.is_dir() {
+        let inner = Self::of(&entry.path())?;
+        usage.files += inner.files;
+        usage.bytes += inner.bytes;
+      } else {
+        usage.files += 1;
+        usage.bytes += metadata.len();
+      }
+    }
+    Ok(usage)
+  }
+}
+
+/// A download that may be evicted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+  path: PathBuf,
+  bytes: u64,
+  last_used: SystemTime
+}
+
+/// Returns the largest the downloads may grow, in bytes, if limited.
+pub fn limit(config: &Config) -> Option<u64> {
+  config
+    .path
+    .max_cache_size
+    .map(|mib| mib.saturating_mul(1024 * 1024))
+}
+
+/// Evicts downloads until they fit within `path.max_cache_size`. Returns
+/// the images removed.
+pub fn enforce(config: &Config) -> Result<Vec<PathBuf>> {
+  let Some(limit) = limit(config) else {
+    return Ok(Vec::new());
+  };
+  let images = files::list_images(&config.path.downloads_dir, true)?;
+  let total: u64 = images
+    .iter()
+    .filter_map(|image| fs::symlink_metadata(image).ok())
+    .filter(|metadata| !metadata.is_symlink())
+    .map(|metadata| metadata.len())
+    .sum();
+  if total <= limit {
+    return Ok(Vec::new());
+  }
+  evict(&select(candidates(config, images)?, total, limit))
+}
+
+/// Evicts every download that isn't [protected](protected). Returns the
+/// images removed.
+pub fn purge(config: &Config) -> Result<Vec<PathBuf>> {
+  let images = files::list_images(&config.path.downloads_dir, true)?;
+  let candidates = candidates(config, images)?;
+  let paths: Vec<PathBuf> = candidates
+    .into_iter()
+    .map(|candidate| candidate.path)
+    .collect();
+  evict(&paths)
+}
+
+/// Returns the images that must not be evicted, as [keys](key).
+fn protected(config: &Config) -> Result<HashSet<PathBuf>> {
+  let mut protected: Vec<PathBuf> = Current::load(&config.path)?
+    .monitors
+    .into_values()
+    .map(|applied| applied.image)
+    .collect();
+  protected.extend(
+    Favorites::load(&config.path)?
+      .items
+      .into_iter()
+      .map(|favorite| favorite.file)
+  );
+  let library = Library::load(&config.path)?;
+  protected.extend(library.images.into_values());
+  protected.extend(
+    library
+      .collections
+      .into_values()
+      .flat_map(|collection| collection.images)
+  );
+  protected.extend(Queue::load(&config.path)?.items.into_iter().filter_map(
+    |item| match item {
+      QueueItem::File(path) => Some(path),
+      QueueItem::Wallhaven(_) => None
+    }
+  ));
+  protected.extend(config.slideshow.workspaces.pinned.values().cloned());
+  Ok(protected.iter().map(|path| key(path)).collect())
+}
+
+/// Returns the path `path` resolves to, so that the same image is matched
+/// however it was reached.
+fn key(path: &Path) -> PathBuf {
+  path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Lists the downloads that may be evicted, least recently used first.
+fn candidates(config: &Config, images: Vec<PathBuf>) -> Result<Vec<Candidate>> {
+  let mut protected = protected(config)?;
+  //{ A link resolves to its original, which must outlive it }
+  protected.extend(
+    images
+      .iter()
+      .filter(|image| image.is_symlink())
+      .map(|link| key(link))
+  );
+  let mut last_shown: HashMap<PathBuf, SystemTime> = HashMap::new();
+  for entry in History::load(&config.path)?.entries {
+    last_shown.insert(entry.image, entry.applied_at.into());
+  }
+
+  let mut candidates: Vec<Candidate> = images
+    .into_iter()
+    .filter(|image| !protected.contains(&key(image)))
+    .filter_map(|path| {
+      let metadata = fs::symlink_metadata(&path)
+        .ok()
+        .filter(|metadata| !metadata.is_symlink())?;
+      let modified = metadata.modified().ok()?;
+      let last_used = last_shown
+        .get(&path)
+        .map_or(modified, |shown| modified.max(*shown));
+      Some(Candidate {
+        path,
+        bytes: metadata.len(),
+        last_used
+      })
+    })
+    .collect();
+  candidates.sort_by_key(|candidate| candidate.last_used);
+  Ok(candidates)
+}
+
+/// Picks candidates, in order, until `total` drops to `limit`.
+fn select(
+  candidates: Vec<Candidate>,
+  mut total: u64,
+  limit: u64
+) -> Vec<PathBuf> {
+  let mut selected = Vec::new();
+  for candidate in candidates {
+    if total <= limit {
+      break;
+    }
+    total = total.saturating_sub(candidate.bytes);
+    selected.push(candidate.path);
+  }
+  selected
+}
+
+fn evict(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+  for path in paths {
+    if !simulate::dry_run(Action::RemoveFile { path: path.clone() })? {
+      fs::remove_file(path)?;
+    }
+  }
+  Ok(paths.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::time::Duration;
+
+  #[test]
+  fn test_select_least_recently_used() {
+    let at = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+    let candidate = |name: &str, bytes, seconds| Candidate {
+      path: PathBuf::from(name),
+      bytes,
+      last_used: at(seconds)
+    };
+    let candidates = vec![
+      candidate("first.jpg", 40, 10),
+      candidate("second.jpg", 30, 20),
+      candidate("newest.jpg", 50, 30),
+    ];
+    assert_eq!(
+      select(candidates.clone(), 120, 60),
+      vec![PathBuf::from("first.jpg"), PathBuf::from("second.jpg")]
+    );
+    assert!(select(candidates, 60, 60).is_empty());
+  }
+
+  #[test]
+  fn test_enforce_keeps_protected() {
+    let root = std::env::temp_dir().join("wallter-cache-test");
+    let _ = fs::remove_dir_all(&root);
+    let mut config = Config::default();
+    config.path.downloads_dir = root.join("downloads");
+    config.path.favorites_dir = root.join("favorites");
+    config.path.state_dir = root.join("state");
+    config.path.max_cache_size = Some(1);
+    fs::create_dir_all(&config.path.downloads_dir).unwrap();
+    let image = |name: &str| {
+      let path = config.path.downloads_dir.join(name);
+      fs::write(&path, vec![0; 700 * 1024]).unwrap();
+      path
+    };
+    let (queued, pinned, other) =
+      (image("queued.jpg"), image("pinned.jpg"), image("other.jpg"));
+    let member = image("member.jpg");
+    #[cfg(unix)]
+    let (original, duplicate) = {
+      let original = image("original.jpg");
+      let duplicate = config.path.downloads_dir.join("duplicate.jpg");
+      std::os::unix::fs::symlink(&original, &duplicate).unwrap();
+      (original, duplicate)
+    };
+    let mut library = Library::default();
+    library.create_collection("minimal").unwrap();
+    let collection = library.collection_mut("minimal").unwrap();
+    collection.insert(member.clone(), None);
+    let imported = image("imported.jpg");
+    library.images.insert("hash".into(), imported.clone());
+    library.save(&config.path).unwrap();
+    let mut queue = Queue::default();
+    queue.push(QueueItem::File(queued.clone()));
+    queue.save(&config.path).unwrap();
+    config
+      .slideshow
+      .workspaces
+      .pinned
+      .insert("web".into(), pinned.clone());
+
+    assert_eq!(enforce(&config).unwrap(), vec![other.clone()]);
+    assert!(queued.is_file() && pinned.is_file() && member.is_file());
+    assert!(imported.is_file());
+    #[cfg(unix)]
+    assert!(original.is_file() && duplicate.is_symlink());
+    assert!(!other.exists());
+    let _ = fs::remove_dir_all(&root);
+  }
+}
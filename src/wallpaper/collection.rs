@@ -0,0 +1,182 @@
+//! Named, ordered collections of wallpapers, curated by hand.
+//!
+//! Unlike the favorites, a collection doesn't copy its images: it lists
+//! images already on disk, in the order they were arranged, and is kept in
+//! the [library](super::Library). An image can be in any number of
+//! collections, and images in one are never [evicted](super::cache) to keep
+//! the downloads within their size limit. Once a collection is chosen as the
+//! slideshow source, the slideshow steps through it in order, starting over
+//! after the last image.
+
+use super::Library;
+use crate::{Error, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  fmt::{self, Display, Formatter},
+  path::{Path, PathBuf}
+};
+
+/// A named, ordered list of wallpapers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Collection {
+  pub images: Vec<PathBuf>,
+  pub created_at: DateTime<Local>
+}
+
+impl Default for Collection {
+  fn default() -> Self {
+    Self {
+      images: Vec::new(),
+      created_at: Local::now()
+    }
+  }
+}
+
+impl Collection {
+  /// Puts `image` at `position`, counting from 1, or else at the end. An
+  /// image already in the collection is moved there.
+  pub fn insert(&mut self, image: PathBuf, position: Option<usize>) {
+    self.images.retain(|other| other != &image);
+    let index = position.map_or(self.images.len(), |position| {
+      position.saturating_sub(1).min(self.images.len())
+    });
+    self.images.insert(index, image);
+  }
+
+  /// Returns the image matching `key`, by path, file name or position
+  /// counting from 1.
+  pub fn get(&self, key: &str) -> Option<&PathBuf> {
+    self.images.get(self.position(key)?)
+  }
+
+  /// Removes the image matching `key`, see [Self::get], and returns it.
+  pub fn remove(&mut self, key: &str) -> Option<PathBuf> {
+    let index = self.position(key)?;
+    Some(self.images.remove(index))
+  }
+
+  /// Returns the index of the image matching `key`, see [Self::get].
+  fn position(&self, key: &str) -> Option<usize> {
+    if let Ok(position) = key.parse::<usize>() {
+      return (1..=self.images.len())
+        .contains(&position)
+        .then(|| position - 1);
+    }
+    self.images.iter().position(|image| {
+      image == Path::new(key)
+        || image.file_name().is_some_and(|name| name == key)
+    })
+  }
+
+  /// Returns the first image after `current` that `usable` accepts, going
+  /// round to the start after the last one. Without a `current` image in the
+  /// collection, the search starts from the top.
+  pub fn next_after(
+    &self,
+    current: Option<&Path>,
+    usable: impl Fn(&Path) -> bool
+  ) -> Option<&PathBuf> {
+    let start = current
+      .and_then(|current| self.images.iter().position(|i| i == current))
+      .map_or(0, |index| index + 1);
+    let count = self.images.len();
+    (0..count)
+      .map(|offset| &self.images[(start + offset) % count])
+      .find(|image| usable(image))
+  }
+}
+
+impl Display for Collection {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.images.is_empty() {
+      return printf!(f, "Images", "[None]");
+    }
+    for (index, image) in self.images.iter().enumerate() {
+      printf!(f, &(index + 1).to_string(), image.display())?;
+    }
+    Ok(())
+  }
+}
+
+/// Checks that `name` can name a collection.
+pub fn validate_name(name: &str) -> Result<()> {
+  if name.trim().is_empty() || name != name.trim() {
+    return Err(Error::Settings(format!(
+      "'{name}' is not a valid collection name"
+    )));
+  }
+  Ok(())
+}
+
+impl Library {
+  /// Returns the collection called `name`.
+  pub fn collection(&self, name: &str) -> Result<&Collection> {
+    self
+      .collections
+      .get(name)
+      .ok_or_else(|| no_collection(name))
+  }
+
+  /// Returns the collection called `name` for changing.
+  pub fn collection_mut(&mut self, name: &str) -> Result<&mut Collection> {
+    self
+      .collections
+      .get_mut(name)
+      .ok_or_else(|| no_collection(name))
+  }
+
+  /// Creates an empty collection called `name`.
+  pub fn create_collection(&mut self, name: &str) -> Result<()> {
+    validate_name(name)?;
+    if self.collections.contains_key(name) {
+      return Err(Error::Settings(format!(
+        "A collection called '{name}' already exists"
+      )));
+    }
+    self
+      .collections
+      .insert(name.to_string(), Collection::default());
+    Ok(())
+  }
+}
+
+fn no_collection(name: &str) -> Error {
+  Error::NothingToDo(format!("There is no collection called '{name}'"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_collection() {
+    let mut collection = Collection::default();
+    for name in ["a.jpg", "b.jpg", "c.jpg"] {
+      collection.insert(PathBuf::from("/walls").join(name), None);
+    }
+    collection.insert("/walls/c.jpg".into(), Some(1));
+    assert_eq!(collection.images[0], Path::new("/walls/c.jpg"));
+    assert_eq!(collection.images.len(), 3);
+
+    let any = |_: &Path| true;
+    let next =
+      |current: &str| collection.next_after(Some(Path::new(current)), any);
+    assert_eq!(next("/walls/c.jpg").unwrap(), Path::new("/walls/a.jpg"));
+    assert_eq!(next("/walls/b.jpg").unwrap(), Path::new("/walls/c.jpg"));
+    assert_eq!(next("/elsewhere.jpg").unwrap(), Path::new("/walls/c.jpg"));
+    let no_a = |image: &Path| !image.ends_with("a.jpg");
+    assert_eq!(
+      collection
+        .next_after(Some(Path::new("/walls/c.jpg")), no_a)
+        .unwrap(),
+      Path::new("/walls/b.jpg")
+    );
+
+    assert_eq!(collection.get("3").unwrap(), Path::new("/walls/b.jpg"));
+    assert_eq!(collection.remove("2"), Some("/walls/a.jpg".into()));
+    assert_eq!(collection.remove("b.jpg"), Some("/walls/b.jpg".into()));
+    assert_eq!(collection.remove("b.jpg"), None);
+    assert!(validate_name(" minimal").is_err());
+  }
+}
@@ -0,0 +1,151 @@
+//! Continuity mode: one picture carried across a row of monitors.
+//!
+//! Backends that set each monitor on its own can't span an image across the
+//! desktop. With `wallpaper.continuity` enabled, monitors that sit side by
+//! side, level and of the same height, are treated as one canvas: the image
+//! is scaled to cover the whole row and each monitor gets the part in front
+//! of it, so the panorama continues across the bezels.
+
+use super::default::current_file;
+use crate::{
+  Config, Error, Result,
+  config::Monitor,
+  simulate::{self, Action},
+  utils::limits
+};
+use image::imageops::FilterType;
+use std::{collections::BTreeMap, path::Path, path::PathBuf};
+
+/// Groups `monitors` into rows of two or more adjacent monitors of the same
+/// height, from left to right. Monitors without a neighbor are left out.
+pub fn rows<'a>(monitors: &[&'a Monitor]) -> Vec<Vec<&'a Monitor>> {
+  let mut sorted = monitors.to_vec();
+  sorted.sort_by_key(|monitor| (monitor.position.y, monitor.position.x));
+
+  let mut rows: Vec<Vec<&Monitor>> = Vec::new();
+  for monitor in sorted {
+    let continues =
+      rows.last().and_then(|row| row.last()).is_some_and(|left| {
+        left.position.y == monitor.position.y
+          && left.size.height == monitor.size.height
+          && left.position.x + left.size.width as i32 == monitor.position.x
+      });
+    match rows.last_mut() {
+      Some(row) if continues => row.push(monitor),
+      _ => rows.push(vec![monitor])
+    }
+  }
+  rows.retain(|row| row.len() > 1);
+  rows
+}
+
+/// Cuts `image` into one wallpaper per monitor for every row of adjacent
+/// monitors, returning the files written by monitor name.
+pub fn prepare(
+  config: &Config,
+  image: &Path,
+  monitors: &[&Monitor]
+) -> Result<BTreeMap<String, PathBuf>> {
+  let mut parts = BTreeMap::new();
+  for row in rows(monitors) {
+    let left = row[0].position.x;
+    let width: u32 = row.iter().map(|monitor| monitor.size.width).sum();
+    let height = row[0].size.height;
+    let outputs: Vec<PathBuf> = row
+      .iter()
+      .map(|monitor| current_file(config, monitor).with_extension("png"))
+      .collect();
+
+    let mut dry_run = false;
+    for output in &outputs {
+      dry_run |= simulate::dry_run(Action::WriteFile {
+        path: output.clone()
+      })?;
+    }
+    if !dry_run {
+      let canvas = limits::open(image, &config.wallpaper.limits)?
+        .resize_to_fill(width, height, FilterType::Lanczos3);
+      for (monitor, output) in row.iter().zip(&outputs) {
+        let x = (monitor.position.x - left) as u32;
+        if let Some(parent) = output.parent() {
+          std::fs::create_dir_all(parent)?;
+        }
+        canvas
+          .crop_imm(x, 0, monitor.size.width, height)
+          .save(output)
+          .map_err(|e| Error::Image(e.to_string()))?;
+      }
+    }
+    for (monitor, output) in row.iter().zip(outputs) {
+      parts.insert(monitor.name.clone(), output);
+    }
+  }
+  Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::monitor::Position;
+  use image::{Rgb, RgbImage};
+
+  fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> Monitor {
+    Monitor {
+      position: Position::new(&x, &y),
+      ..Monitor::test(name, width, height)
+    }
+  }
+
+  #[test]
+  fn test_rows() {
+    let left = monitor("DP-1", 0, 0, 1920, 1080);
+    let middle = monitor("DP-2", 1920, 0, 2560, 1080);
+    let right = monitor("DP-3", 4480, 0, 1920, 1080);
+    let taller = monitor("HDMI-1", 6400, 0, 1920, 1200);
+    let below = monitor("eDP-1", 0, 1080, 1920, 1080);
+    let monitors = [&taller, &right, &below, &left, &middle];
+
+    let names: Vec<Vec<&str>> = rows(&monitors)
+      .iter()
+      .map(|row| row.iter().map(|monitor| monitor.name.as_str()).collect())
+      .collect();
+    assert_eq!(names, [["DP-1", "DP-2", "DP-3"]]);
+    assert!(rows(&[&left, &below]).is_empty());
+  }
+
+  #[test]
+  fn test_prepare() {
+    let root = std::env::temp_dir().join("wallter-continuity-test");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let mut config = Config::default();
+    config.path.wallpaper_dir = root.clone();
+
+    //{ Red on the left half, blue on the right }
+    let image = root.join("panorama.png");
+    RgbImage::from_fn(64, 16, |x, _| {
+      if x < 32 {
+        Rgb([255, 0, 0])
+      } else {
+        Rgb([0, 0, 255])
+      }
+    })
+    .save(&image)
+    .unwrap();
+
+    let left = monitor("DP-1", 0, 0, 32, 16);
+    let right = monitor("DP-2", 32, 0, 32, 16);
+    let parts = prepare(&config, &image, &[&right, &left]).unwrap();
+    assert_eq!(parts["DP-1"], root.join("DP-1.png"));
+
+    let pixel = |name: &str| {
+      *image::open(&parts[name])
+        .unwrap()
+        .to_rgb8()
+        .get_pixel(16, 8)
+    };
+    assert_eq!(pixel("DP-1"), Rgb([255, 0, 0]));
+    assert_eq!(pixel("DP-2"), Rgb([0, 0, 255]));
+    let _ = std::fs::remove_dir_all(&root);
+  }
+}
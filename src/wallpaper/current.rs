@@ -0,0 +1,57 @@
+//! Tracks which image is currently shown on each monitor.
+
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the current wallpaper file within the state directory.
+const CURRENT_FILE: &str = "current.json";
+
+/// The image applied to a monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Applied {
+  /// The original image, before it was copied to the wallpaper directory.
+  pub image: PathBuf,
+  /// When the image was applied.
+  pub since: DateTime<Local>
+}
+
+/// The images currently applied, keyed by monitor name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Current {
+  pub monitors: BTreeMap<String, Applied>
+}
+
+impl Current {
+  /// Returns the path of the current wallpaper file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(CURRENT_FILE)
+  }
+
+  /// Loads the current wallpapers from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the current wallpapers to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns the image applied to the named monitor.
+  pub fn get(&self, monitor: &str) -> Option<&Applied> {
+    self.monitors.get(monitor)
+  }
+
+  /// Records `image` as applied to the named monitor now.
+  pub fn insert(&mut self, monitor: &str, image: PathBuf) {
+    self.monitors.insert(
+      monitor.to_string(),
+      Applied {
+        image,
+        since: Local::now()
+      }
+    );
+  }
+}
@@ -0,0 +1,206 @@
+//! Dark counterparts of light wallpapers, shown while the system is in dark
+//! mode.
+//!
+//! With `wallpaper.dark_variant` enabled, an image is swapped for a dark
+//! counterpart before it is applied in dark mode. One made by the artist
+//! comes first: a sibling such as `dunes-dark.jpg` beside `dunes.jpg`, or
+//! `dunes-night.png` beside `dunes-day.png`. Otherwise an image that is light
+//! on average gets a generated variant, with the exposure lowered in linear
+//! light and the colors slightly desaturated. Variants are kept in a hidden
+//! `.dark` directory beside the original, so they are made once and never
+//! listed as wallpapers of their own.
+
+use super::hdr::{to_linear, to_srgb};
+use crate::{
+  Config, Error, Result,
+  config::{ColorMode, wallpaper::DarkVariant},
+  consts::IMAGE_EXTENSIONS,
+  simulate::{self, Action},
+  utils::limits
+};
+use image::RgbImage;
+use std::{
+  fs::create_dir_all,
+  path::{Path, PathBuf}
+};
+
+/// The directory beside an original that holds its generated variant.
+const DIR: &str = ".dark";
+
+/// Name endings marking the light and dark versions of the same wallpaper.
+const PAIRS: [(&str, &str); 2] = [("light", "dark"), ("day", "night")];
+
+/// Returns the image to show instead of `image` in dark mode, if any: its
+/// own dark counterpart, or else a generated variant when `image` is light.
+pub fn prepare(config: &Config, image: &Path) -> Result<Option<PathBuf>> {
+  let settings = config.wallpaper.dark_variant;
+  if !settings.enabled || ColorMode::get_current() != ColorMode::Dark {
+    return Ok(None);
+  }
+  validate(&settings)?;
+  let Some(stem) = image.file_stem().and_then(|stem| stem.to_str()) else {
+    return Ok(None);
+  };
+  if is_dark_name(stem) {
+    return Ok(None);
+  }
+  if let Some(native) = counterpart(image, stem) {
+    return Ok(Some(native));
+  }
+
+  let output = variant_file(image, stem);
+  if is_fresh(&output, image) {
+    return Ok(Some(output));
+  }
+  let pixels = limits::open(image, &config.wallpaper.limits)?.to_rgb8();
+  if brightness(&pixels) < settings.threshold {
+    return Ok(None);
+  }
+  if simulate::dry_run(Action::WriteFile {
+    path: output.clone()
+  })? {
+    return Ok(Some(output));
+  }
+  if let Some(parent) = output.parent() {
+    create_dir_all(parent)?;
+  }
+  darken(pixels, &settings)
+    .save(&output)
+    .map_err(|e| Error::Image(e.to_string()))?;
+  Ok(Some(output))
+}
+
+/// Returns where the generated variant of `image` is kept.
+fn variant_file(image: &Path, stem: &str) -> PathBuf {
+  image.with_file_name(DIR).join(format!("{stem}.png"))
+}
+
+/// Checks that `settings` dim rather than brighten.
+fn validate(settings: &DarkVariant) -> Result<()> {
+  if settings.exposure > 0.0 || !(0.0..=1.0).contains(&settings.saturation) {
+    return Err(Error::Settings(format!(
+      "Dark variants need an exposure of 0 or less and a saturation between 0 and 1, not {} and {}",
+      settings.exposure, settings.saturation
+    )));
+  }
+  Ok(())
+}
+
+/// Whether a variant at `output` exists and is newer than `image`.
+fn is_fresh(output: &Path, image: &Path) -> bool {
+  let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+  match (modified(output), modified(image)) {
+    (Some(variant), Some(original)) => variant >= original,
+    (Some(_), None) => true,
+    _ => false
+  }
+}
+
+/// Whether `stem` names the dark version of a wallpaper.
+fn is_dark_name(stem: &str) -> bool {
+  let stem = stem.to_lowercase();
+  PAIRS.iter().any(|(_, dark)| {
+    ['-', '_', '.']
+      .iter()
+      .any(|separator| stem.ends_with(&format!("{separator}{dark}")))
+  })
+}
+
+/// Returns the stems the dark version of a wallpaper named `stem` may have.
+fn counterpart_stems(stem: &str) -> Vec<String> {
+  let mut stems = Vec::new();
+  for (light, dark) in PAIRS {
+    for separator in ['-', '_', '.'] {
+      let ending = format!("{separator}{light}");
+      let base = stem.len().saturating_sub(ending.len());
+      let ends_light = stem
+        .get(base..)
+        .is_some_and(|end| end.eq_ignore_ascii_case(&ending));
+      if ends_light && base > 0 {
+        stems.push(format!("{}{separator}{dark}", &stem[..base]));
+      }
+    }
+  }
+  for (_, dark) in PAIRS {
+    for separator in ['-', '_', '.'] {
+      stems.push(format!("{stem}{separator}{dark}"));
+    }
+  }
+  stems
+}
+
+/// Returns an image beside `image` named as its dark version.
+fn counterpart(image: &Path, stem: &str) -> Option<PathBuf> {
+  counterpart_stems(stem).into_iter().find_map(|stem| {
+    IMAGE_EXTENSIONS
+      .iter()
+      .map(|ext| image.with_file_name(format!("{stem}.{ext}")))
+      .find(|candidate| candidate.is_file())
+  })
+}
+
+/// The average brightness of `pixels`, between 0 and 1.
+fn brightness(pixels: &RgbImage) -> f32 {
+  let count = u64::from(pixels.width()) * u64::from(pixels.height());
+  if count == 0 {
+    return 0.0;
+  }
+  let total: f64 = pixels
+    .pixels()
+    .map(|pixel| {
+      let [r, g, b] = pixel.0.map(f64::from);
+      0.2126 * r + 0.7152 * g + 0.0722 * b
+    })
+    .sum();
+  (total / count as f64 / 255.0) as f32
+}
+
+/// Lowers the exposure of `pixels` and takes some of their saturation.
+fn darken(mut pixels: RgbImage, settings: &DarkVariant) -> RgbImage {
+  let gain = 2f32.powf(settings.exposure);
+  for pixel in pixels.pixels_mut() {
+    pixel.0 = dim(pixel.0, gain, settings.saturation);
+  }
+  pixels
+}
+
+/// Dims one sRGB pixel by `gain` in linear light, moving its channels
+/// towards their luminance to keep only `saturation` of the color.
+fn dim(pixel: [u8; 3], gain: f32, saturation: f32) -> [u8; 3] {
+  let [r, g, b] = pixel.map(|value| to_linear(value as f32 / 255.0) * gain);
+  let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+  [r, g, b].map(|channel| {
+    let mixed = luminance + (channel - luminance) * saturation;
+    (to_srgb(mixed.clamp(0.0, 1.0)) * 255.0).round() as u8
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_dim() {
+    assert_eq!(dim([0, 0, 0], 0.5, 0.85), [0, 0, 0]);
+    assert_eq!(dim([200, 100, 50], 1.0, 1.0), [200, 100, 50]);
+    let [r, g, b] = dim([255, 255, 255], 0.5, 0.85);
+    assert!(r < 200 && r == g && g == b);
+    //{ Less exposure, and the channels drawn closer together }
+    let [r, _, b] = dim([220, 120, 40], 0.5, 0.5);
+    assert!(r < 220);
+    assert!(i32::from(r) - i32::from(b) < 220 - 40);
+  }
+
+  #[test]
+  fn test_counterpart_stems() {
+    let stems = counterpart_stems("dunes-Day");
+    assert_eq!(stems[0], "dunes-night");
+    assert!(stems.contains(&"dunes-Day_dark".to_string()));
+    assert!(is_dark_name("dunes_Night"));
+    assert!(!is_dark_name("darkroom"));
+    assert_eq!(
+      variant_file(Path::new("/walls/dunes.jpg"), "dunes"),
+      Path::new("/walls/.dark/dunes.png")
+    );
+  }
+}
@@ -0,0 +1,572 @@
+use crate::{
+  Config, Error, Result,
+  config::{Monitor, conflicts::Subsystem},
+  simulate::{self, Action},
+  stats,
+  utils::limits
+};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  fs::{copy, create_dir_all, metadata, remove_file},
+  path::{Path, PathBuf}
+};
+use tracing::{info, warn};
+
+/// A platform backend that applies wallpaper images to monitors.
+pub trait Setter {
+  /// A short name identifying the backend.
+  fn name(&self) -> &'static str;
+
+  /// Applies the image at `path` to `monitor`.
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()>;
+
+  /// Returns the image currently shown on `monitor`, if it can be determined.
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>>;
+
+  /// Whether the backend can show different images on each monitor. If not,
+  /// setting a wallpaper on one monitor applies it to all of them.
+  fn supports_per_monitor(&self) -> bool;
+}
+
+/// Selects the wallpaper backend for the current platform and session.
+pub fn detect(config: &Config) -> Box<dyn Setter> {
+  if simulate::is_enabled() {
+    return Box::new(simulate::Setter);
+  }
+
+  #[cfg(all(target_os = "windows", feature = "windows-desktop-wallpaper"))]
+  {
+    Box::new(super::windows::DesktopSetter)
+  }
+  #[cfg(all(
+    target_os = "windows",
+    not(feature = "windows-desktop-wallpaper")
+  ))]
+  {
+    Box::new(super::windows::RegistrySetter::new(
+      config.wallpaper.windows
+    ))
+  }
+  #[cfg(target_os = "linux")]
+  {
+    use super::linux::{
+      GnomeSetter, KdeSetter, WlrootsSetter, WlrootsTool, X11Setter
+    };
+    use crate::config::color::mode::linux::{DesktopEnvironment, SessionType};
+    let session = SessionType::detect();
+    match (DesktopEnvironment::detect(), session) {
+      (DesktopEnvironment::GNOME, _) =>
+        Box::new(GnomeSetter::new(session, config.wallpaper.continuity)),
+      (DesktopEnvironment::KDE, _) => Box::new(KdeSetter),
+      (_, SessionType::Wayland) => match WlrootsTool::detect() {
+        Some(tool) => Box::new(WlrootsSetter::new(
+          tool,
+          config.wallpaper.transition.clone()
+        )),
+        None => Box::new(Unsupported)
+      },
+      (_, SessionType::X11) => Box::new(X11Setter::new(
+        config.wallpaper.x11,
+        config.wallpaper.limits
+      )),
+      (_, SessionType::Unknown) => Box::new(Unsupported)
+    }
+  }
+  #[cfg(target_os = "macos")]
+  {
+    Box::new(super::macos::Setter)
+  }
+  #[cfg(not(any(
+    target_os = "windows",
+    target_os = "linux",
+    target_os = "macos"
+  )))]
+  {
+    Box::new(Unsupported)
+  }
+}
+
+/// The fallback backend for platforms without wallpaper support.
+struct Unsupported;
+
+impl Setter for Unsupported {
+  fn name(&self) -> &'static str {
+    "unsupported"
+  }
+
+  fn set(&self, _path: &Path, _monitor: &Monitor) -> Result<()> {
+    Err(Error::Wallpaper(
+      "Setting wallpapers is not supported on this platform".to_string()
+    ))
+  }
+
+  fn get_current(&self, _monitor: &Monitor) -> Result<Option<PathBuf>> {
+    Ok(None)
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    false
+  }
+}
+
+/// Where a wallpaper image comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+  /// A remote image that is downloaded before being applied.
+  Url(String),
+  /// An image already on disk.
+  File(PathBuf)
+}
+
+impl Source {
+  /// Interprets `value` as a URL if it has an HTTP(S) scheme, otherwise as a
+  /// file path.
+  pub fn parse(value: &str) -> Self {
+    if value.starts_with("http://") || value.starts_with("https://") {
+      Self::Url(value.to_string())
+    } else {
+      Self::File(PathBuf::from(value))
+    }
+  }
+}
+
+impl Display for Source {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Url(url) => write!(f, "{url}"),
+      Self::File(path) => write!(f, "{}", path.display())
+    }
+  }
+}
+
+/// Sets the wallpaper from `source` on the monitor matching `monitor` (by name
+/// or id), or on every detected monitor if no monitor is given, and records
+/// it in the history, the journal and the stats.
+///
+/// Returns the paths of the installed wallpaper files, one per monitor. If
+/// wallpapers are left to another manager (see
+/// [Conflicts](crate::config::Conflicts)), the image is only downloaded and
+/// nothing is returned.
+pub async fn set(
+  config: &Config,
+  source: &Source,
+  monitor: Option<&str>
+) -> Result<Vec<PathBuf>> {
+  let Some(shown) = show(config, source, monitor).await? else {
+    return Ok(Vec::new());
+  };
+  record(config, &shown, source)?;
+  Ok(shown.installed)
+}
+
+/// A wallpaper [shown](show) on the desktop, to be [recorded](record) if it
+/// stays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shown {
+  /// The image, as fetched from the source.
+  pub image: PathBuf,
+  /// The names of the monitors showing it.
+  pub monitors: Vec<String>,
+  /// The installed wallpaper files, one per monitor.
+  pub installed: Vec<PathBuf>,
+  /// How many times the backend applied it.
+  sets: usize
+}
+
+/// Applies the wallpaper from `source` like [set], without recording it
+/// anywhere, for a wallpaper that may only be shown for a moment. Returns
+/// `None` if wallpapers are left to another manager.
+///
+/// With `wallpaper.continuity`, an image set on every monitor is cut across
+/// rows of adjacent monitors, see [continuity](super::continuity). Otherwise
+/// an image [adjusted](super::adjust) for a monitor is cropped as saved. In
+/// dark mode, a light image may be shown as its [dark](super::dark) variant.
+pub async fn show(
+  config: &Config,
+  source: &Source,
+  monitor: Option<&str>
+) -> Result<Option<Shown>> {
+  let monitors = resolve_monitors(config, monitor)?;
+  let image = fetch(config, source, monitors[0]).await?;
+  if let Some(manager) = config.conflicts.yield_to(Subsystem::Wallpaper) {
+    info!(
+      "Wallpapers are left to {manager}; kept {} in the library.",
+      image.display()
+    );
+    return Ok(None);
+  }
+  let setter = detect(config);
+  if !setter.supports_per_monitor() && monitor.is_some() {
+    warn!(
+      "The {} backend cannot target a single monitor; the wallpaper will be applied to all monitors.",
+      setter.name()
+    );
+  }
+
+  //{ In dark mode, light images give way to a dark counterpart }
+  let shown =
+    super::dark::prepare(config, &image)?.unwrap_or_else(|| image.clone());
+
+  //{ Cut the image across adjacent monitors when it goes on all of them }
+  let mut parts = if config.wallpaper.continuity
+    && monitor.is_none()
+    && setter.supports_per_monitor()
+  {
+    super::continuity::prepare(config, &shown, &monitors)?
+  } else {
+    BTreeMap::new()
+  };
+
+  let adjustments = super::adjust::of(config, &image)?;
+
+  let mut names = Vec::new();
+  let mut installed = Vec::new();
+  let mut sets = 0;
+  for (i, monitor) in monitors.into_iter().enumerate() {
+    let current =
+      match (parts.remove(&monitor.name), adjustments.get(&monitor.name)) {
+        (Some(part), _) => part,
+        (None, Some(adjustment)) =>
+          super::adjust::apply(config, &shown, monitor, adjustment)?,
+        (None, None) => install(config, &shown, monitor)?
+      };
+    let current =
+      super::hdr::prepare(config, &current, monitor)?.unwrap_or(current);
+    //{ Backends without per-monitor support only need to be called once }
+    if setter.supports_per_monitor() || i == 0 {
+      setter.set(&current, monitor)?;
+      sets += 1;
+    }
+    names.push(monitor.name.clone());
+    installed.push(current);
+  }
+  Ok(Some(Shown {
+    image,
+    monitors: names,
+    installed,
+    sets
+  }))
+}
+
+/// Records a wallpaper [shown](show) from `source` as applied: in the
+/// current wallpapers, the history, the journal and the stats. A simulated
+/// wallpaper was never applied, so it is not recorded.
+pub fn record(config: &Config, shown: &Shown, source: &Source) -> Result<()> {
+  if simulate::is_enabled() {
+    return Ok(());
+  }
+  let image = &shown.image;
+  let mut applied = super::Current::load(&config.path)?;
+  let mut showing = super::NowShowing::load(&config.path)?;
+  let mut history = super::History::load(&config.path)?;
+  let mut events = Vec::new();
+  for monitor in &shown.monitors {
+    applied.insert(monitor, image.clone());
+    showing.insert(
+      monitor,
+      super::now_showing::Showing::new(image.clone(), source)
+    );
+    history.record(monitor, image.clone(), source);
+    events.push(super::journal::Event::now(
+      monitor,
+      image.clone(),
+      match source {
+        Source::Url(url) => Some(url.clone()),
+        Source::File(_) => None
+      }
+    ));
+  }
+  applied.save(&config.path)?;
+  showing.save(&config.path)?;
+  history.save(&config.path)?;
+  super::journal::append(&config.path, &events)?;
+  super::fingerprints::record(config, image);
+  stats::update(config, |s| {
+    for _ in 0..shown.sets {
+      s.record_set();
+    }
+  })
+}
+
+/// Reapplies the images recorded in `snapshot` to the monitors matching
+/// `monitor` (or all monitors). Monitors missing from the snapshot are left
+/// untouched and returned.
+pub async fn restore(
+  config: &Config,
+  snapshot: &super::Current,
+  monitor: Option<&str>
+) -> Result<Vec<String>> {
+  reapply(config, snapshot, monitor, true).await
+}
+
+/// Puts back the images in `snapshot` like [restore], without recording
+/// them, to take back a wallpaper that was only [shown](show).
+pub async fn revert(
+  config: &Config,
+  snapshot: &super::Current,
+  monitor: Option<&str>
+) -> Result<Vec<String>> {
+  reapply(config, snapshot, monitor, false).await
+}
+
+async fn reapply(
+  config: &Config,
+  snapshot: &super::Current,
+  monitor: Option<&str>,
+  recorded: bool
+) -> Result<Vec<String>> {
+  let mut missing = Vec::new();
+  for monitor in resolve_monitors(config, monitor)? {
+    let Some(applied) = snapshot.get(&monitor.name) else {
+      missing.push(monitor.name.clone());
+      continue;
+    };
+    let source = Source::File(applied.image.clone());
+    if recorded {
+      set(config, &source, Some(&monitor.name)).await?;
+    } else {
+      show(config, &source, Some(&monitor.name)).await?;
+    }
+  }
+  Ok(missing)
+}
+
+/// Finds the monitors targeted by `selector`, matching either the monitor name
+/// (case-insensitive) or its id. Without a selector, all monitors are
+/// returned.
+pub fn resolve_monitors<'a>(
+  config: &'a Config,
+  selector: Option<&str>
+) -> Result<Vec<&'a Monitor>> {
+  let monitors: Vec<&Monitor> = match selector {
+    Some(selector) => config
+      .monitors
+      .iter()
+      .filter(|m| {
+        m.name.eq_ignore_ascii_case(selector) || m.id.to_string() == selector
+      })
+      .collect(),
+    None => config.monitors.iter().collect()
+  };
+
+  if monitors.is_empty() {
+    return Err(Error::Wallpaper(match selector {
+      Some(selector) => format!("No monitor matches '{selector}'"),
+      None => "No monitors detected".to_string()
+    }));
+  }
+  Ok(monitors)
+}
+
+/// Finds the single monitor targeted by `selector`, defaulting to the primary
+/// monitor (or the first one if none is marked primary).
+pub fn resolve_monitor<'a>(
+  config: &'a Config,
+  selector: Option<&str>
+) -> Result<&'a Monitor> {
+  let monitors = resolve_monitors(config, selector)?;
+  Ok(
+    monitors
+      .iter()
+      .find(|monitor| selector.is_some() || monitor.primary)
+      .unwrap_or(&monitors[0])
+  )
+}
+
+/// Returns a local path for the image, downloading it into the monitor's
+/// download directory if needed.
+///
+/// The image is checked against the configured safety limits. Blocklisted
+/// sources are refused, and sources that fail the check are blocklisted.
+pub async fn fetch(
+  config: &Config,
+  source: &Source,
+  monitor: &Monitor
+) -> Result<PathBuf> {
+  let key = source.to_string();
+  let mut blocklist = super::Blocklist::load(&config.path)?;
+  if let Some(blocked) = blocklist.get(&key) {
+    return Err(Error::ImageRejected {
+      path: PathBuf::from(&key),
+      reason: format!("blocklisted ({})", blocked.reason)
+    });
+  }
+
+  let path = fetch_unchecked(config, source, monitor).await?;
+  if simulate::is_dry_run() && !path.exists() {
+    return Ok(path);
+  }
+  if let Err(e) = limits::check(&path, &config.wallpaper.limits) {
+    if let Error::ImageRejected { reason, .. } = &e {
+      blocklist.add(key, reason.clone());
+      blocklist.save(&config.path)?;
+      if matches!(source, Source::Url(_)) {
+        remove_file(&path)?;
+      }
+    }
+    return Err(e);
+  }
+
+  //{ Credit the download in the same step, or discard it }
+  if let Source::Url(url) = source {
+    if let Err(e) = super::attribution::record(&config.path, &path, url, None) {
+      remove_file(&path)?;
+      return Err(e);
+    }
+    if let Err(e) = super::metadata::record(&config.path, &path, url) {
+      warn!("Could not record the metadata of {}: {e}", path.display());
+    }
+  }
+  Ok(path)
+}
+
+async fn fetch_unchecked(
+  config: &Config,
+  source: &Source,
+  monitor: &Monitor
+) -> Result<PathBuf> {
+  match source {
+    Source::File(path) => {
+      if !path.is_file() {
+        return Err(Error::Wallpaper(format!(
+          "Image not found: {}",
+          path.display()
+        )));
+      }
+      Ok(path.clone())
+    }
+    Source::Url(url) => {
+      let name = super::naming::name(&config.path, url, Default::default())?;
+      let download_dir = config.path.get_download_dir(monitor);
+      let path = download_dir.join(name);
+      if simulate::dry_run(Action::Download {
+        url: url.clone(),
+        path: path.clone()
+      })? {
+        return Ok(path);
+      }
+      create_dir_all(&download_dir)?;
+
+      let path = super::library::download(&config.path, url, &path).await?;
+      let bytes = metadata(&path)?.len();
+      stats::update(config, |s| s.record_download(bytes))?;
+      Ok(path)
+    }
+  }
+}
+
+/// Returns the monitor's current wallpaper file, without an extension.
+pub(super) fn current_file(config: &Config, monitor: &Monitor) -> PathBuf {
+  config
+    .path
+    .monitor_paths
+    .iter()
+    .find(|p| p.name == monitor.name)
+    .map(|p| p.current_wallpaper.clone())
+    .unwrap_or_else(|| config.path.wallpaper_dir.join(&monitor.name))
+}
+
+/// Copies the image to the monitor's current wallpaper file, keeping the
+/// image's extension.
+fn install(
+  config: &Config,
+  image: &Path,
+  monitor: &Monitor
+) -> Result<PathBuf> {
+  let current = current_file(config, monitor);
+  let current = match image.extension() {
+    Some(ext) => current.with_extension(ext),
+    None => current
+  };
+
+  if simulate::dry_run(Action::CopyFile {
+    from: image.to_path_buf(),
+    to: current.clone()
+  })? {
+    return Ok(current);
+  }
+  if let Some(parent) = current.parent() {
+    create_dir_all(parent)?;
+  }
+  copy(image, &current)?;
+  Ok(current)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::wallpaper;
+
+  #[test]
+  fn test_source_parse() {
+    assert_eq!(
+      Source::parse("https://w.wallhaven.cc/full/ab/wallhaven-ab.jpg"),
+      Source::Url("https://w.wallhaven.cc/full/ab/wallhaven-ab.jpg".into())
+    );
+    assert_eq!(
+      Source::parse("images/forest.png"),
+      Source::File(PathBuf::from("images/forest.png"))
+    );
+  }
+
+  #[tokio::test]
+  async fn test_guest_mode_records_nothing() {
+    let root = std::env::temp_dir().join("wallter-guest-test");
+    let _ = std::fs::remove_dir_all(&root);
+    let mut config = Config {
+      guest: true,
+      monitors: vec![Monitor::test("DP-1", 1920, 1080)],
+      ..Config::default()
+    };
+    config.path.state_dir = root.join("state");
+    config.path.wallpaper_dir = root.join("wallpaper");
+    let image = root.join("forest.png");
+    create_dir_all(&root).unwrap();
+    image::RgbImage::new(16, 9).save(&image).unwrap();
+
+    config.enter_guest_mode();
+    let installed = set(&config, &Source::File(image), None).await.unwrap();
+    assert_eq!(installed.len(), 1);
+    assert!(simulate::disable().is_some_and(|journal| {
+      journal
+        .entries()
+        .iter()
+        .any(|entry| matches!(entry.action, Action::SetWallpaper { .. }))
+    }));
+    for state in [
+      wallpaper::Current::file(&config.path),
+      wallpaper::NowShowing::file(&config.path),
+      wallpaper::History::file(&config.path),
+      wallpaper::journal::file(&config.path)
+    ] {
+      assert!(!state.exists(), "{} was written", state.display());
+    }
+    std::fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn test_resolve_monitors() {
+    let config = Config {
+      monitors: vec![
+        Monitor {
+          primary: true,
+          ..Monitor::test("DP-1", 1920, 1080)
+        },
+        Monitor {
+          id: 1,
+          ..Monitor::test("HDMI-1", 1920, 1080)
+        },
+      ],
+      ..Default::default()
+    };
+
+    assert_eq!(resolve_monitors(&config, None).unwrap().len(), 2);
+    assert_eq!(resolve_monitors(&config, Some("hdmi-1")).unwrap()[0].id, 1);
+    assert_eq!(
+      resolve_monitors(&config, Some("0")).unwrap()[0].name,
+      "DP-1"
+    );
+    assert!(resolve_monitors(&config, Some("eDP-1")).is_err());
+  }
+}
@@ -0,0 +1,104 @@
+//! The daily digest: a minimal mode for low-power machines and e-paper
+//! displays.
+//!
+//! Run from a timer or at login, [run] fetches one new wallpaper per day and
+//! applies it to every monitor. Later runs on the same day do nothing, so no
+//! daemon has to stay resident and the network is used once a day at most.
+//! A failed fetch isn't recorded and is tried again on the next run.
+
+use super::Source;
+use crate::{Config, Error, Result, config::Path, search, utils::store};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The name of the digest file within the state directory.
+const DIGEST_FILE: &str = "digest.json";
+
+/// What a digest run did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+  /// A new wallpaper was fetched and applied.
+  Fetched(Source),
+  /// Today's wallpaper was already fetched; nothing was done.
+  Current(PathBuf)
+}
+
+/// The most recent digest fetch.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Digest {
+  pub fetched_on: Option<NaiveDate>,
+  pub image: Option<PathBuf>
+}
+
+impl Digest {
+  /// Returns the path of the digest file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(DIGEST_FILE)
+  }
+
+  /// Loads the digest from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the digest to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns today's image if it was already fetched and is still on disk.
+  pub fn fetched(&self, today: NaiveDate) -> Option<&PathBuf> {
+    self
+      .image
+      .as_ref()
+      .filter(|image| self.fetched_on == Some(today) && image.is_file())
+  }
+}
+
+/// Fetches and applies today's wallpaper, unless that was done already.
+/// With `force`, a new one is fetched regardless.
+pub async fn run(config: &Config, force: bool) -> Result<Outcome> {
+  let today = Local::now().date_naive();
+  let mut digest = Digest::load(&config.path)?;
+  if let Some(image) = digest.fetched(today).filter(|_| !force) {
+    return Ok(Outcome::Current(image.clone()));
+  }
+
+  let monitor = super::resolve_monitor(config, None)?;
+  let candidate = search::random(config, monitor).await?.ok_or_else(|| {
+    Error::NothingToDo("No wallpaper found for today's digest.".to_string())
+  })?;
+  let source = Source::parse(&candidate.url);
+  super::set(config, &source, None).await?;
+
+  //{ Remember the downloaded image, as recorded for the monitor }
+  digest.fetched_on = Some(today);
+  digest.image = super::Current::load(&config.path)?
+    .get(&monitor.name)
+    .map(|applied| applied.image.clone());
+  digest.save(&config.path)?;
+  Ok(Outcome::Fetched(source))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fetched_once_per_day() {
+    let image = std::env::temp_dir().join("wallter-digest-test.png");
+    std::fs::write(&image, b"").unwrap();
+    let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+    let digest = Digest {
+      fetched_on: Some(today),
+      image: Some(image.clone())
+    };
+    assert_eq!(digest.fetched(today), Some(&image));
+    assert_eq!(digest.fetched(today.succ_opt().unwrap()), None);
+
+    //{ A missing image is fetched again }
+    std::fs::remove_file(&image).unwrap();
+    assert_eq!(digest.fetched(today), None);
+  }
+}
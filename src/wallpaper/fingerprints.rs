@@ -0,0 +1,254 @@
+//! Remembers what every shown wallpaper looked like.
+//!
+//! Each applied image is reduced to a 64-bit perceptual fingerprint (a
+//! difference hash of a 9x8 grayscale copy), which survives resizing and
+//! re-encoding, so a search result's thumbnail fingerprints close to the full
+//! image it was made from. Fingerprints are kept in the state directory by
+//! path and outlive the images themselves, so a wallpaper that was cycled
+//! through and pruned is still recognised when a source offers it again.
+//!
+//! A [Look] adds the dominant hue to the fingerprint, so the slideshow can
+//! tell whether an image looks too much like those shown just before it.
+
+use crate::{
+  Config, Result,
+  config::{Path, wallpaper::Limits},
+  utils::{limits, store}
+};
+use chrono::{DateTime, Local};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  path::{Path as FilePath, PathBuf}
+};
+use tracing::warn;
+
+/// The name of the fingerprint store within the state directory.
+const FINGERPRINTS_FILE: &str = "fingerprints.json";
+
+/// Returns the perceptual fingerprint of `image`.
+pub fn fingerprint(image: &DynamicImage) -> u64 {
+  let small = image.thumbnail_exact(9, 8).to_luma8();
+  let mut hash = 0;
+  for y in 0..8 {
+    for x in 0..8 {
+      let brighter = small.get_pixel(x + 1, y)[0] > small.get_pixel(x, y)[0];
+      hash = hash << 1 | u64::from(brighter);
+    }
+  }
+  hash
+}
+
+/// Returns the fingerprint of the image file at `path`, if it is within
+/// `limits`.
+pub fn of_file(path: &FilePath, limits: &Limits) -> Result<u64> {
+  Ok(fingerprint(&limits::open(path, limits)?))
+}
+
+/// Returns the fingerprint of an encoded image, such as a thumbnail, if it
+/// is within `limits`.
+pub fn of_bytes(bytes: &[u8], limits: &Limits) -> Result<u64> {
+  Ok(fingerprint(&limits::decode(bytes, limits)?))
+}
+
+/// Returns how many bits two fingerprints differ in.
+pub fn distance(a: u64, b: u64) -> u32 {
+  (a ^ b).count_ones()
+}
+
+/// Returns the dominant hue of `image` in degrees, or `None` if it is
+/// mostly gray. Each pixel counts towards a 10 degree band as much as it is
+/// saturated, and the fullest band wins.
+pub fn dominant_hue(image: &DynamicImage) -> Option<u16> {
+  let small = image.thumbnail(32, 32).to_rgb8();
+  let mut bands = [0.0f32; 36];
+  let mut colorful = 0;
+  for pixel in small.pixels() {
+    let [red, green, blue] = pixel.0.map(|c| f32::from(c) / 255.0);
+    let max = red.max(green).max(blue);
+    let chroma = max - red.min(green).min(blue);
+    if chroma < 0.15 || max < 0.15 {
+      continue;
+    }
+    let hue = if max == red {
+      60.0 * ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+      60.0 * ((blue - red) / chroma + 2.0)
+    } else {
+      60.0 * ((red - green) / chroma + 4.0)
+    };
+    bands[(hue / 10.0) as usize % 36] += chroma / max;
+    colorful += 1;
+  }
+  //{ A few colored pixels don't make a gray image colorful }
+  if colorful * 10 < small.pixels().len() {
+    return None;
+  }
+  let (band, _) = bands
+    .iter()
+    .enumerate()
+    .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+  Some(band as u16 * 10 + 5)
+}
+
+/// Returns how many degrees apart two dominant hues are, from 0 to 180. Two
+/// gray images are alike, and a gray and a colorful one as far apart as can
+/// be.
+pub fn hue_distance(a: Option<u16>, b: Option<u16>) -> u32 {
+  match (a, b) {
+    (Some(a), Some(b)) => {
+      let apart = u32::from(a.abs_diff(b)) % 360;
+      apart.min(360 - apart)
+    }
+    (None, None) => 0,
+    _ => 180
+  }
+}
+
+/// What an image looks like, as far as telling it apart from others goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Look {
+  pub hash: u64,
+  pub hue: Option<u16>
+}
+
+impl Look {
+  /// Returns the look of `image`.
+  pub fn of(image: &DynamicImage) -> Self {
+    Self {
+      hash: fingerprint(image),
+      hue: dominant_hue(image)
+    }
+  }
+
+  /// Returns the look of the image file at `path`, if it is within
+  /// `limits`.
+  pub fn of_file(path: &FilePath, limits: &Limits) -> Result<Self> {
+    Ok(Self::of(&limits::open(path, limits)?))
+  }
+}
+
+/// A shown image, as remembered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seen {
+  pub hash: u64,
+  /// When the image was first shown.
+  pub at: DateTime<Local>
+}
+
+/// The fingerprints of every image shown, by path.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprints {
+  pub seen: BTreeMap<PathBuf, Seen>
+}
+
+impl Fingerprints {
+  /// Returns the path of the fingerprint store.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(FINGERPRINTS_FILE)
+  }
+
+  /// Loads the fingerprints from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the fingerprints to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  pub fn len(&self) -> usize {
+    self.seen.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.seen.is_empty()
+  }
+
+  /// Remembers `image` with the fingerprint `hash`. Returns whether it was
+  /// new.
+  pub fn insert(&mut self, image: &FilePath, hash: u64) -> bool {
+    if self.seen.contains_key(image) {
+      return false;
+    }
+    self.seen.insert(
+      image.to_path_buf(),
+      Seen {
+        hash,
+        at: Local::now()
+      }
+    );
+    true
+  }
+
+  /// Returns the seen image closest to `hash`, with how far it is.
+  pub fn closest(&self, hash: u64) -> Option<(&PathBuf, u32)> {
+    self
+      .seen
+      .iter()
+      .map(|(image, seen)| (image, distance(seen.hash, hash)))
+      .min_by_key(|(_, distance)| *distance)
+  }
+}
+
+/// Remembers the fingerprint of `image`, unless it is known already. Failures
+/// are reported rather than returned, since they must not keep a wallpaper
+/// from being shown.
+pub fn record(config: &Config, image: &FilePath) {
+  let path_config = &config.path;
+  let result = Fingerprints::load(path_config).and_then(|mut fingerprints| {
+    if fingerprints.seen.contains_key(image) {
+      return Ok(());
+    }
+    fingerprints.insert(image, of_file(image, &config.wallpaper.limits)?);
+    fingerprints.save(path_config)
+  });
+  if let Err(e) = result {
+    warn!("Failed to fingerprint {}: {e}", image.display());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{Rgb, RgbImage, imageops::FilterType};
+
+  #[test]
+  fn test_fingerprint() {
+    let gradient =
+      DynamicImage::ImageRgb8(RgbImage::from_fn(320, 180, |x, y| {
+        let value = ((x * 255 / 320 + y * 64 / 180) % 256) as u8;
+        Rgb([value, value / 2, 255 - value])
+      }));
+    let original = fingerprint(&gradient);
+    let thumbnail = fingerprint(&gradient.resize(64, 36, FilterType::Triangle));
+    let flipped = fingerprint(&gradient.fliph());
+    assert!(distance(original, thumbnail) <= 6);
+    assert!(distance(original, flipped) > 20);
+
+    let mut fingerprints = Fingerprints::default();
+    assert!(fingerprints.insert(FilePath::new("gradient.png"), original));
+    assert!(!fingerprints.insert(FilePath::new("gradient.png"), flipped));
+    let (closest, distance) = fingerprints.closest(thumbnail).unwrap();
+    assert_eq!(closest, FilePath::new("gradient.png"));
+    assert!(distance <= 6);
+  }
+
+  #[test]
+  fn test_dominant_hue() {
+    let solid =
+      |color| DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, color));
+    let red = dominant_hue(&solid(Rgb([200, 20, 20])));
+    let blue = dominant_hue(&solid(Rgb([20, 40, 220])));
+    let gray = dominant_hue(&solid(Rgb([128, 128, 128])));
+    assert_eq!(red, Some(5));
+    assert_eq!(blue, Some(235));
+    assert_eq!(gray, None);
+    assert_eq!(hue_distance(red, blue), 130);
+    assert_eq!(hue_distance(Some(355), Some(5)), 10);
+    assert_eq!(hue_distance(gray, None), 0);
+    assert_eq!(hue_distance(gray, red), 180);
+  }
+}
@@ -0,0 +1,133 @@
+//! Brightness compensation for SDR wallpapers on HDR monitors.
+//!
+//! With HDR enabled, Windows maps SDR content to a fixed, fairly low
+//! brightness, so ordinary JPEG wallpapers look dim and washed out. Images
+//! are brightened in linear light with a soft highlight roll-off before
+//! being applied. Formats that can carry HDR data are left untouched.
+
+use crate::{
+  Config, Error, Result,
+  config::{
+    Monitor,
+    wallpaper::{HdrMode, Limits}
+  },
+  simulate::{self, Action},
+  utils::limits
+};
+use std::path::{Path, PathBuf};
+#[cfg(all(target_os = "windows", feature = "windows-hdr"))]
+use tracing::warn;
+
+/// Extensions of formats that may already contain HDR content.
+const HDR_EXTENSIONS: [&str; 4] = ["jxr", "avif", "hdr", "exr"];
+
+/// Linear values above this are compressed rather than scaled.
+const KNEE: f32 = 0.8;
+
+/// Checks whether `monitor` is currently in HDR mode.
+pub fn is_active(monitor: &Monitor) -> bool {
+  #[cfg(all(target_os = "windows", feature = "windows-hdr"))]
+  {
+    super::windows::hdr::is_enabled(monitor).unwrap_or_else(|e| {
+      warn!(monitor = %monitor.name, "Failed to detect HDR: {e}");
+      false
+    })
+  }
+  #[cfg(not(all(target_os = "windows", feature = "windows-hdr")))]
+  {
+    let _ = monitor;
+    false
+  }
+}
+
+/// Writes a brightness-compensated copy of `image` next to it if `monitor`
+/// needs one, returning the path of the copy.
+pub fn prepare(
+  config: &Config,
+  image: &Path,
+  monitor: &Monitor
+) -> Result<Option<PathBuf>> {
+  let settings = config.wallpaper.hdr;
+  let needed = match settings.mode {
+    HdrMode::Off => false,
+    HdrMode::Always => true,
+    HdrMode::Auto => is_active(monitor)
+  };
+  let is_hdr_source = image
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| HDR_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+  if !needed || is_hdr_source {
+    return Ok(None);
+  }
+
+  let stem = image
+    .file_stem()
+    .map(|stem| stem.to_string_lossy().into_owned())
+    .unwrap_or_else(|| monitor.name.clone());
+  let output = image.with_file_name(format!("{stem}-hdr.png"));
+  if simulate::dry_run(Action::WriteFile {
+    path: output.clone()
+  })? {
+    return Ok(Some(output));
+  }
+  compensate(image, &output, settings.gain, &config.wallpaper.limits)?;
+  Ok(Some(output))
+}
+
+/// Brightens `image` by `gain` in linear light and saves it to `output`.
+pub fn compensate(
+  image: &Path,
+  output: &Path,
+  gain: f32,
+  limits: &Limits
+) -> Result<()> {
+  let mut pixels = limits::open(image, limits)?.to_rgb8();
+  for pixel in pixels.pixels_mut() {
+    for channel in pixel.0.iter_mut() {
+      *channel = tone_map(*channel, gain);
+    }
+  }
+  pixels.save(output).map_err(|e| Error::Image(e.to_string()))
+}
+
+/// Applies the gain to one sRGB channel value.
+fn tone_map(value: u8, gain: f32) -> u8 {
+  let linear = to_linear(value as f32 / 255.0) * gain;
+  let mapped = if linear <= KNEE {
+    linear
+  } else {
+    KNEE + (1.0 - KNEE) * (1.0 - (-(linear - KNEE) / (1.0 - KNEE)).exp())
+  };
+  (to_srgb(mapped.min(1.0)) * 255.0).round() as u8
+}
+
+pub(crate) fn to_linear(value: f32) -> f32 {
+  if value <= 0.04045 {
+    value / 12.92
+  } else {
+    ((value + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+pub(crate) fn to_srgb(value: f32) -> f32 {
+  if value <= 0.0031308 {
+    value * 12.92
+  } else {
+    1.055 * value.powf(1.0 / 2.4) - 0.055
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_tone_map() {
+    assert_eq!(tone_map(0, 1.3), 0);
+    assert!(tone_map(128, 1.3) > 128);
+    assert!(tone_map(250, 1.3) >= 250);
+    assert_eq!(tone_map(255, 2.0), 255);
+    assert_eq!(tone_map(128, 1.0), 128);
+  }
+}
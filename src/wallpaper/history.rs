@@ -0,0 +1,143 @@
+//! Keeps a log of every wallpaper applied, so earlier ones can be brought
+//! back and recent ones aren't repeated by the slideshow.
+
+use super::Source;
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The name of the history file within the state directory.
+const HISTORY_FILE: &str = "history.json";
+
+/// The most entries kept; the oldest are dropped first.
+const MAX_ENTRIES: usize = 1000;
+
+/// A wallpaper applied to a monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+  pub monitor: String,
+  /// The original image, before it was copied to the wallpaper directory.
+  pub image: PathBuf,
+  /// Where the image came from, if it was downloaded.
+  pub url: Option<String>,
+  pub applied_at: DateTime<Local>
+}
+
+/// Every wallpaper applied, oldest first.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+  pub entries: Vec<Entry>
+}
+
+impl History {
+  /// Returns the path of the history file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(HISTORY_FILE)
+  }
+
+  /// Loads the history from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the history to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records `image`, set from `source`, as applied to the named monitor now.
+  /// Reapplying the image already on the monitor isn't recorded again.
+  pub fn record(&mut self, monitor: &str, image: PathBuf, source: &Source) {
+    if self.last(monitor).is_some_and(|entry| entry.image == image) {
+      return;
+    }
+    self.entries.push(Entry {
+      monitor: monitor.to_string(),
+      image,
+      url: match source {
+        Source::Url(url) => Some(url.clone()),
+        Source::File(_) => None
+      },
+      applied_at: Local::now()
+    });
+    if self.entries.len() > MAX_ENTRIES {
+      self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+    }
+  }
+
+  /// Returns the entries for the named monitor, or all entries, newest first.
+  pub fn list(&self, monitor: Option<&str>) -> impl Iterator<Item = &Entry> {
+    let monitor = monitor.map(str::to_string);
+    self.entries.iter().rev().filter(move |entry| {
+      monitor.as_ref().is_none_or(|name| &entry.monitor == name)
+    })
+  }
+
+  /// Returns the latest entry for the named monitor.
+  pub fn last(&self, monitor: &str) -> Option<&Entry> {
+    self.list(Some(monitor)).next()
+  }
+
+  /// Returns the images last shown on the named monitor, newest first.
+  pub fn recent(&self, monitor: &str, count: usize) -> Vec<&PathBuf> {
+    self
+      .list(Some(monitor))
+      .take(count)
+      .map(|entry| &entry.image)
+      .collect()
+  }
+
+  /// Steps back on the named monitor: forgets the current entry and returns
+  /// the one before it that still exists on disk. Entries for missing images
+  /// are dropped along the way.
+  pub fn step_back(&mut self, monitor: &str) -> Option<Entry> {
+    let mut current = self.position(monitor)?;
+    loop {
+      self.entries.remove(current);
+      current = self.position(monitor)?;
+      if self.entries[current].image.is_file() {
+        return Some(self.entries[current].clone());
+      }
+    }
+  }
+
+  fn position(&self, monitor: &str) -> Option<usize> {
+    self
+      .entries
+      .iter()
+      .rposition(|entry| entry.monitor == monitor)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_history_step_back() {
+    let image = std::env::temp_dir().join("wallter-history-test.png");
+    std::fs::write(&image, b"").unwrap();
+    let missing = PathBuf::from("/missing/forest.jpg");
+
+    let mut history = History::default();
+    history.record("DP-1", image.clone(), &Source::File(image.clone()));
+    history.record("DP-1", missing.clone(), &Source::File(missing.clone()));
+    history.record("HDMI-1", missing.clone(), &Source::File(missing.clone()));
+    history.record("DP-1", missing.clone(), &Source::File(missing.clone()));
+    let current = PathBuf::from("/library/city.jpg");
+    history.record("DP-1", current.clone(), &Source::File(current.clone()));
+    history.record("DP-1", current.clone(), &Source::File(current.clone()));
+    assert_eq!(history.entries.len(), 4);
+    assert_eq!(history.recent("DP-1", 2), vec![&current, &missing]);
+
+    //{ The missing image is skipped over, other monitors are left alone }
+    assert_eq!(
+      history.step_back("DP-1").map(|entry| entry.image),
+      Some(image)
+    );
+    assert_eq!(history.list(Some("DP-1")).count(), 1);
+    assert_eq!(history.list(Some("HDMI-1")).count(), 1);
+    assert_eq!(history.step_back("DP-1"), None);
+  }
+}
@@ -0,0 +1,255 @@
+//! Appends every applied wallpaper to a journal in the state directory.
+//!
+//! The journal is line-delimited JSON, one [Event] per line, so external
+//! tools can follow it with `tail -f` and parse each line on its own. Unlike
+//! the [History](super::History), which is rewritten and trimmed as a whole,
+//! the journal is only ever appended to. Once it grows past [MAX_BYTES] it
+//! is rotated to `events.1.jsonl`, and so on up to [KEEP] old files.
+//!
+//! Each event records what triggered it. The slideshow daemon runs its
+//! changes inside [with_trigger]; anything else is a manual change.
+
+use crate::{
+  Error, Result,
+  config::Path,
+  simulate::{self, Action}
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  fs::{self, OpenOptions},
+  future::Future,
+  io::{BufRead, BufReader, Write},
+  path::PathBuf
+};
+use tracing::warn;
+
+/// The name of the journal within the state directory.
+const JOURNAL_FILE: &str = "events.jsonl";
+
+/// The size past which the journal is rotated.
+pub const MAX_BYTES: u64 = 1024 * 1024;
+
+/// How many rotated journals are kept.
+pub const KEEP: usize = 3;
+
+/// What caused a wallpaper change.
+#[derive(
+  Debug,
+  Default,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  PartialOrd,
+  Ord,
+  Serialize,
+  Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+  /// A command run by the user.
+  #[default]
+  Manual,
+  /// The slideshow interval elapsed.
+  Timer,
+  /// The session was unlocked or the machine resumed.
+  Unlock,
+  /// The daemon was asked for the next wallpaper.
+  Request,
+  /// An image arrived in a watch folder.
+  Watch,
+  /// The primary machine changed its wallpaper.
+  Mirror,
+  /// Focus moved to another workspace.
+  Workspace,
+  /// A job of an embedded [scheduler](crate::scheduler) ran.
+  Schedule
+}
+
+impl Display for Trigger {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}",
+      match self {
+        Trigger::Manual => "manual",
+        Trigger::Timer => "timer",
+        Trigger::Unlock => "unlock",
+        Trigger::Request => "request",
+        Trigger::Watch => "watch",
+        Trigger::Mirror => "mirror",
+        Trigger::Workspace => "workspace",
+        Trigger::Schedule => "schedule"
+      }
+    )
+  }
+}
+
+tokio::task_local! {
+  static TRIGGER: Trigger;
+}
+
+/// Runs `future`, recording the changes it makes as caused by `trigger`.
+pub async fn with_trigger<F: Future>(trigger: Trigger, future: F) -> F::Output {
+  TRIGGER.scope(trigger, future).await
+}
+
+/// Returns what caused the changes being made right now.
+pub fn current_trigger() -> Trigger {
+  TRIGGER.try_with(|trigger| *trigger).unwrap_or_default()
+}
+
+/// One applied wallpaper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+  pub at: DateTime<Local>,
+  pub monitor: String,
+  /// The image as stored in the library.
+  pub file: PathBuf,
+  /// Where the image was downloaded from, if it was.
+  pub source: Option<String>,
+  pub trigger: Trigger
+}
+
+impl Event {
+  /// Describes `file` being applied to `monitor` now, by the current
+  /// trigger.
+  pub fn now(monitor: &str, file: PathBuf, source: Option<String>) -> Self {
+    Self {
+      at: Local::now(),
+      monitor: monitor.to_string(),
+      file,
+      source,
+      trigger: current_trigger()
+    }
+  }
+}
+
+/// Returns the path of the journal.
+pub fn file(path_config: &Path) -> PathBuf {
+  path_config.state_dir.join(JOURNAL_FILE)
+}
+
+/// Returns the path of the `n`th rotated journal.
+fn rotated(path_config: &Path, n: usize) -> PathBuf {
+  path_config.state_dir.join(format!("events.{n}.jsonl"))
+}
+
+/// Appends `events` to the journal, rotating it first if it is full.
+pub fn append(path_config: &Path, events: &[Event]) -> Result<()> {
+  let path = file(path_config);
+  if events.is_empty()
+    || simulate::dry_run(Action::WriteFile { path: path.clone() })?
+  {
+    return Ok(());
+  }
+  if fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= MAX_BYTES) {
+    rotate(path_config)?;
+  }
+
+  let mut lines = String::new();
+  for event in events {
+    let line =
+      serde_json::to_string(event).map_err(|e| Error::Config(e.to_string()))?;
+    lines.push_str(&line);
+    lines.push('\n');
+  }
+  let mut journal = OpenOptions::new().create(true).append(true).open(path)?;
+  journal.write_all(lines.as_bytes())?;
+  Ok(())
+}
+
+/// Moves the journal to `events.1.jsonl`, shifting older ones along and
+/// dropping the oldest.
+fn rotate(path_config: &Path) -> Result<()> {
+  for n in (1..KEEP).rev() {
+    let from = rotated(path_config, n);
+    if from.is_file() {
+      fs::rename(from, rotated(path_config, n + 1))?;
+    }
+  }
+  fs::rename(file(path_config), rotated(path_config, 1))?;
+  Ok(())
+}
+
+/// Reads every event still on disk, oldest first. Lines that cannot be
+/// parsed are skipped.
+pub fn read(path_config: &Path) -> Result<Vec<Event>> {
+  let mut paths: Vec<PathBuf> =
+    (1..=KEEP).rev().map(|n| rotated(path_config, n)).collect();
+  paths.push(file(path_config));
+
+  let mut events = Vec::new();
+  for path in paths.iter().filter(|path| path.is_file()) {
+    for line in BufReader::new(fs::File::open(path)?).lines() {
+      let line = line?;
+      match serde_json::from_str(&line) {
+        Ok(event) => events.push(event),
+        Err(e) if !line.trim().is_empty() =>
+          warn!("Skipping a bad line in {}: {e}", path.display()),
+        Err(_) => {}
+      }
+    }
+  }
+  Ok(events)
+}
+
+/// How many changes each trigger caused.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Summary {
+  pub by_trigger: BTreeMap<Trigger, usize>
+}
+
+impl Summary {
+  /// Tallies `events` by trigger.
+  pub fn of(events: &[Event]) -> Self {
+    let mut summary = Self::default();
+    for event in events {
+      *summary.by_trigger.entry(event.trigger).or_default() += 1;
+    }
+    summary
+  }
+}
+
+impl Display for Summary {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if self.by_trigger.is_empty() {
+      return printh!(f, "No changes journaled");
+    }
+    for (trigger, count) in &self.by_trigger {
+      printf!(f, &trigger.to_string(), count)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_append_and_rotate() {
+    let path_config = Path {
+      state_dir: std::env::temp_dir().join("wallter-journal-test"),
+      ..Default::default()
+    };
+    let _ = fs::remove_dir_all(&path_config.state_dir);
+    fs::create_dir_all(&path_config.state_dir).unwrap();
+
+    let event = Event::now("DP-1", "lake.jpg".into(), None);
+    assert_eq!(event.trigger, Trigger::Manual);
+    append(&path_config, &[event.clone(), event.clone()]).unwrap();
+    assert_eq!(read(&path_config).unwrap(), [event.clone(), event.clone()]);
+
+    //{ A full journal is rotated before the next append }
+    rotate(&path_config).unwrap();
+    append(&path_config, std::slice::from_ref(&event)).unwrap();
+    assert!(rotated(&path_config, 1).is_file());
+    let events = read(&path_config).unwrap();
+    assert_eq!(Summary::of(&events).by_trigger[&Trigger::Manual], 3);
+    let _ = fs::remove_dir_all(&path_config.state_dir);
+  }
+}
@@ -0,0 +1,427 @@
+//! Brings images from elsewhere on disk into the library.
+//!
+//! Imported images are sorted into the same ratio and resolution layout as
+//! downloads, so the slideshow finds them for monitors of that size. An
+//! index of content hashes, kept in the state directory, stops the same
+//! image from being imported twice under different names. The index also
+//! remembers addresses whose images are gone for good, so they aren't
+//! downloaded again.
+//!
+//! Downloads are indexed too, by the BLAKE3 hash of their contents. A
+//! download identical to an image already anywhere under the download
+//! directory, such as the same wallpaper fetched from another provider, is
+//! not kept twice: it is replaced by a symbolic link to the existing file,
+//! or, where links aren't available, the existing file is used instead.
+
+use super::{adjust::Adjustment, collection::Collection};
+use crate::{
+  Error, Result,
+  api::wallhaven::Api as Wallhaven,
+  config::{Path, monitor::Size},
+  simulate::{self, Action},
+  utils::{files, store},
+  wallpaper::probe::Probe
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs::{self, File},
+  io,
+  path::{Path as FilePath, PathBuf},
+  sync::Mutex
+};
+use tokio::task::spawn_blocking;
+use tracing::{info, warn};
+
+/// The name of the library index within the state directory.
+const LIBRARY_FILE: &str = "library.json";
+
+/// The result of importing one image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Imported {
+  /// The image was added to the library at this path.
+  Added(PathBuf),
+  /// The library already had the image, at this path.
+  Duplicate(PathBuf)
+}
+
+impl Imported {
+  /// Returns where the image is in the library.
+  pub fn path(&self) -> &PathBuf {
+    match self {
+      Self::Added(path) | Self::Duplicate(path) => path
+    }
+  }
+}
+
+/// The images imported into the library, by content hash.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+  pub images: BTreeMap<String, PathBuf>,
+  /// Addresses missing from their source and every mirror, with when that
+  /// was found.
+  #[serde(default)]
+  pub dead: BTreeMap<String, DateTime<Local>>,
+  /// Downloads, by the BLAKE3 hash of their contents.
+  #[serde(default)]
+  pub downloads: BTreeMap<String, PathBuf>,
+  /// How images are cropped, by image and monitor name.
+  #[serde(default)]
+  pub adjustments: BTreeMap<PathBuf, BTreeMap<String, Adjustment>>,
+  /// Curated [collections](super::collection) of images, by name.
+  #[serde(default)]
+  pub collections: BTreeMap<String, Collection>
+}
+
+impl Library {
+  /// Returns the path of the library index.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(LIBRARY_FILE)
+  }
+
+  /// Loads the library index from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the library index to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Whether the image at `url` is known to be gone.
+  pub fn is_dead(&self, url: &str) -> bool {
+    self.dead.contains_key(url)
+  }
+
+  /// Remembers that the image at `url` is gone, so it isn't tried again.
+  pub fn mark_dead(&mut self, url: &str) {
+    self.dead.entry(url.to_string()).or_insert_with(Local::now);
+  }
+
+  /// Returns the adjustment of `image` on the monitor called `monitor`.
+  pub fn adjustment(
+    &self,
+    image: &FilePath,
+    monitor: &str
+  ) -> Option<Adjustment> {
+    self.adjustments.get(image)?.get(monitor).copied()
+  }
+
+  /// Remembers how `image` is cropped on the monitor called `monitor`.
+  pub fn adjust(
+    &mut self,
+    image: &FilePath,
+    monitor: &str,
+    adjustment: Adjustment
+  ) {
+    self
+      .adjustments
+      .entry(image.to_path_buf())
+      .or_default()
+      .insert(monitor.to_string(), adjustment);
+  }
+
+  /// Forgets the adjustment of `image` on `monitor`. Returns whether there
+  /// was one.
+  pub fn forget_adjustment(&mut self, image: &FilePath, monitor: &str) -> bool {
+    let Some(monitors) = self.adjustments.get_mut(image) else {
+      return false;
+    };
+    let removed = monitors.remove(monitor).is_some();
+    if monitors.is_empty() {
+      self.adjustments.remove(image);
+    }
+    removed
+  }
+
+  /// Returns a download other than `image` whose contents hash to `hash`.
+  /// Downloads not yet indexed are hashed and indexed as they are checked,
+  /// though only those the same size as `image` can match.
+  pub fn find_download(
+    &mut self,
+    downloads_dir: &FilePath,
+    image: &FilePath,
+    hash: &str
+  ) -> Result<Option<PathBuf>> {
+    if let Some(existing) = self.downloads.get(hash).filter(|p| p.is_file()) {
+      return Ok((existing != image).then(|| existing.clone()));
+    }
+    self.downloads.remove(hash);
+    let size = fs::metadata(image)?.len();
+    let indexed: HashSet<PathBuf> = self.downloads.values().cloned().collect();
+    for other in files::list_images(downloads_dir, true)? {
+      if other == image
+        || other.is_symlink()
+        || indexed.contains(&other)
+        || !fs::metadata(&other).is_ok_and(|m| m.len() == size)
+      {
+        continue;
+      }
+      let other_hash = download_hash(&other)?;
+      let found = other_hash == hash;
+      self
+        .downloads
+        .entry(other_hash)
+        .or_insert_with(|| other.clone());
+      if found {
+        return Ok(Some(other));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Copies `image` into the download directory for its size, or moves it
+  /// there if `keep` is unset. Images already in the library are left where
+  /// they are and only indexed. Imported images are never
+  /// [evicted](super::cache), since a moved image has no other copy.
+  pub fn import(
+    &mut self,
+    config: &crate::Config,
+    image: &std::path::Path,
+    keep: bool
+  ) -> Result<Imported> {
+    let probe = Probe::read(image, &config.wallpaper.limits)?;
+    self.import_probed(config, image, probe, keep)
+  }
+
+  /// Imports `image` like [import](Self::import), trusting `probe` for its
+  /// dimensions and content hash.
+  pub fn import_probed(
+    &mut self,
+    config: &crate::Config,
+    image: &std::path::Path,
+    probe: Probe,
+    keep: bool
+  ) -> Result<Imported> {
+    let Probe {
+      width,
+      height,
+      hash,
+      ..
+    } = probe;
+    if let Some(existing) = self.images.get(&hash).filter(|path| path.is_file())
+    {
+      return Ok(Imported::Duplicate(existing.clone()));
+    }
+    if image.starts_with(&config.path.downloads_dir) {
+      self.images.insert(hash, image.to_path_buf());
+      return Ok(Imported::Added(image.to_path_buf()));
+    }
+
+    let dir = config.path.get_bucket_dir(&Size::new(&width, &height));
+    let name = image.file_name().unwrap_or_default();
+    let mut target = dir.join(name);
+    if target.exists() {
+      //{ A different image with the same name; keep both }
+      let stem = image.file_stem().unwrap_or_default().to_string_lossy();
+      let extension = image.extension().unwrap_or_default().to_string_lossy();
+      target = dir.join(format!("{stem}-{}.{extension}", &hash[..8]));
+    }
+
+    if !simulate::dry_run(Action::CopyFile {
+      from: image.to_path_buf(),
+      to: target.clone()
+    })? {
+      fs::create_dir_all(&dir)?;
+      fs::copy(image, &target)?;
+    }
+    if !keep
+      && !simulate::dry_run(Action::RemoveFile {
+        path: image.to_path_buf()
+      })?
+    {
+      fs::remove_file(image)?;
+    }
+    self.images.insert(hash, target.clone());
+    Ok(Imported::Added(target))
+  }
+}
+
+/// Held while a download updates the library index.
+static INDEXING: Mutex<()> = Mutex::new(());
+
+/// Downloads the image at `url` to `path`, falling back to its mirrors.
+///
+/// Addresses known to be gone fail straight away, and an address found gone
+/// is recorded in the library so it isn't tried again. A download into the
+/// download directory that duplicates one already there is replaced by a
+/// [link](link_duplicate) to it.
+pub async fn download(
+  path_config: &Path,
+  url: &str,
+  path: &FilePath
+) -> Result<PathBuf> {
+  if Library::load(path_config)?.is_dead(url) {
+    return Err(Error::Gone(url.to_string()));
+  }
+  let result = Wallhaven::new(None).download_wallpaper(url, path).await;
+
+  //{ Hashing the download directory takes a while, so it runs off the
+  //{ async workers }
+  let (path_config, url) = (path_config.clone(), url.to_string());
+  spawn_blocking(move || index_download(&path_config, &url, result))
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Records the outcome of downloading `url` in the library, replacing a
+/// duplicate download with a link. Downloads finishing together take turns,
+/// so none of them loses the others' entries.
+fn index_download(
+  path_config: &Path,
+  url: &str,
+  result: Result<PathBuf>
+) -> Result<PathBuf> {
+  let _turn = INDEXING.lock().unwrap_or_else(|e| e.into_inner());
+  let mut library = Library::load(path_config)?;
+  let path = match result {
+    Err(Error::Gone(_)) => {
+      library.mark_dead(url);
+      library.save(path_config)?;
+      return Err(Error::Gone(url.to_string()));
+    }
+    result => result?
+  };
+  if !path.starts_with(&path_config.downloads_dir) {
+    return Ok(path);
+  }
+
+  let hash = download_hash(&path)?;
+  let existing =
+    library.find_download(&path_config.downloads_dir, &path, &hash)?;
+  let path = match existing {
+    Some(existing) => {
+      info!(
+        "{} is already downloaded as {}",
+        path.display(),
+        existing.display()
+      );
+      if simulate::dry_run(Action::RemoveFile { path: path.clone() })? {
+        return Ok(path);
+      }
+      fs::remove_file(&path)?;
+      link_duplicate(&existing, &path)
+    }
+    None => {
+      library.downloads.insert(hash, path.clone());
+      path
+    }
+  };
+  library.save(path_config)?;
+  Ok(path)
+}
+
+/// Returns the BLAKE3 hash of the contents of `path`, in hex.
+fn download_hash(path: &FilePath) -> Result<String> {
+  let mut hasher = blake3::Hasher::new();
+  hasher.update_reader(File::open(path)?)?;
+  Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Links `path` to `existing`, so a duplicate download still shows up where
+/// it was downloaded to. Returns the path to use for the image.
+#[cfg(unix)]
+fn link_duplicate(existing: &FilePath, path: &FilePath) -> PathBuf {
+  match std::os::unix::fs::symlink(existing, path) {
+    Ok(()) => path.to_path_buf(),
+    Err(e) => {
+      warn!("Failed to link {}: {e}", path.display());
+      existing.to_path_buf()
+    }
+  }
+}
+
+#[cfg(not(unix))]
+fn link_duplicate(existing: &FilePath, _path: &FilePath) -> PathBuf {
+  existing.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Config;
+  use image::RgbImage;
+
+  #[test]
+  fn test_import() {
+    let root = std::env::temp_dir().join("wallter-library-test");
+    let _ = fs::remove_dir_all(&root);
+    let mut config = Config::default();
+    config.path.downloads_dir = root.join("downloads");
+    let inbox = root.join("inbox");
+    fs::create_dir_all(&inbox).unwrap();
+
+    let image = inbox.join("dunes.png");
+    RgbImage::new(32, 18).save(&image).unwrap();
+    let copy = inbox.join("dunes-copy.png");
+    fs::copy(&image, &copy).unwrap();
+
+    let mut library = Library::default();
+    let added = library.import(&config, &image, true).unwrap();
+    assert_eq!(
+      added,
+      Imported::Added(config.path.downloads_dir.join("1.78/32x18/dunes.png"))
+    );
+    assert!(added.path().is_file() && image.is_file());
+
+    //{ The same contents under another name are recognized and left alone }
+    assert_eq!(
+      library.import(&config, &copy, false).unwrap(),
+      Imported::Duplicate(added.path().clone())
+    );
+    assert!(copy.is_file());
+    let _ = fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn test_find_download() {
+    let root = std::env::temp_dir().join("wallter-library-downloads-test");
+    let _ = fs::remove_dir_all(&root);
+    let downloads = root.join("downloads");
+    let bucket = downloads.join("1.78/32x18");
+    fs::create_dir_all(&bucket).unwrap();
+    let first = bucket.join("wallhaven-dunes.png");
+    RgbImage::new(32, 18).save(&first).unwrap();
+    let second = downloads.join("pexels-dunes.png");
+    fs::copy(&first, &second).unwrap();
+    let other = downloads.join("other.png");
+    RgbImage::from_pixel(32, 18, image::Rgb([9, 9, 9]))
+      .save(&other)
+      .unwrap();
+
+    //{ Downloads made before the index existed are found and indexed }
+    let mut library = Library::default();
+    let hash = download_hash(&second).unwrap();
+    assert_eq!(
+      library.find_download(&downloads, &second, &hash).unwrap(),
+      Some(first.clone())
+    );
+    assert_eq!(library.downloads.get(&hash), Some(&first));
+    assert_eq!(
+      library.find_download(&downloads, &first, &hash).unwrap(),
+      None
+    );
+    let hash = download_hash(&other).unwrap();
+    assert_eq!(
+      library.find_download(&downloads, &other, &hash).unwrap(),
+      None
+    );
+    let _ = fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn test_dead_addresses() {
+    //{ Indexes written before dead addresses were tracked still load }
+    let mut library: Library =
+      serde_json::from_str(r#"{"images": {}}"#).unwrap();
+    let url = "https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg";
+    assert!(!library.is_dead(url));
+    library.mark_dead(url);
+    let found = library.dead[url];
+    library.mark_dead(url);
+    assert!(library.is_dead(url));
+    assert_eq!(library.dead[url], found);
+  }
+}
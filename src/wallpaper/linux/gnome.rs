@@ -0,0 +1,99 @@
+//! Sets the wallpaper on GNOME through `gsettings`.
+//!
+//! GNOME keeps separate images for light and dark mode. The key matching the
+//! resolved color mode is updated, so the new image is shown immediately.
+//!
+//! With `wallpaper.continuity`, GNOME on Xorg is asked to span the image
+//! across the whole screen. GNOME on Wayland draws the background of each
+//! monitor on its own, so the image is scaled to each of them instead.
+
+use super::super::Setter as WallpaperSetter;
+use super::{file_uri, from_file_uri};
+use crate::{
+  Error, Result,
+  config::{ColorMode, Monitor, color::mode::linux::SessionType}
+};
+use std::{
+  path::{Path, PathBuf},
+  process::Command
+};
+
+const SCHEMA: &str = "org.gnome.desktop.background";
+
+/// A wallpaper backend for GNOME. The same image is shown on every monitor.
+pub struct Setter {
+  span: bool
+}
+
+impl Setter {
+  /// Creates the backend, spanning images across monitors if `continuity`
+  /// is enabled and the session can do that.
+  pub fn new(session: SessionType, continuity: bool) -> Self {
+    Self {
+      span: continuity && session == SessionType::X11
+    }
+  }
+
+  /// Returns the `picture-uri` key for the resolved color mode.
+  fn key() -> &'static str {
+    match ColorMode::get_current() {
+      ColorMode::Dark => "picture-uri-dark",
+      _ => "picture-uri"
+    }
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "gnome"
+  }
+
+  fn set(&self, path: &Path, _monitor: &Monitor) -> Result<()> {
+    let path = path.canonicalize()?;
+    if self.span {
+      set_key("picture-options", "spanned")?;
+    }
+    set_key(Self::key(), &file_uri(&path))
+  }
+
+  fn get_current(&self, _monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let output = Command::new("gsettings")
+      .args(["get", SCHEMA, Self::key()])
+      .output()
+      .map_err(|e| {
+        Error::Wallpaper(format!(
+          "Linux/GNOME: Failed to execute gsettings: {e}"
+        ))
+      })?;
+
+    if !output.status.success() {
+      return Ok(None);
+    }
+
+    //{ gsettings prints string values quoted, e.g. 'file:///path.jpg' }
+    let value = String::from_utf8_lossy(&output.stdout);
+    let uri = value.trim().trim_matches('\'');
+    Ok(from_file_uri(uri))
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    false
+  }
+}
+
+/// Sets `key` of the background schema to `value`.
+fn set_key(key: &str, value: &str) -> Result<()> {
+  let status = Command::new("gsettings")
+    .args(["set", SCHEMA, key, value])
+    .status()
+    .map_err(|e| {
+      Error::Wallpaper(format!("Linux/GNOME: Failed to execute gsettings: {e}"))
+    })?;
+
+  if !status.success() {
+    return Err(Error::Wallpaper(format!(
+      "Linux/GNOME: gsettings set {key} command failed"
+    )));
+  }
+  Ok(())
+}
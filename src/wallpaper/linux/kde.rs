@@ -0,0 +1,140 @@
+//! Sets the wallpaper on KDE Plasma.
+//!
+//! Wallpapers are applied per screen through the `org.kde.PlasmaShell`
+//! scripting interface, matching the Plasma desktop whose screen geometry
+//! starts at the monitor's position. If the session bus is unavailable,
+//! `plasma-apply-wallpaperimage` is used to set every screen instead.
+
+use super::super::Setter as WallpaperSetter;
+use super::{file_uri, from_file_uri};
+use crate::{Error, Result, config::Monitor};
+use std::{
+  path::{Path, PathBuf},
+  process::Command
+};
+use tracing::warn;
+
+/// A wallpaper backend for KDE Plasma, with per-screen support.
+pub struct Setter;
+
+impl Setter {
+  /// Builds a Plasma script that runs `body` for each desktop shown on
+  /// `monitor`, with `desktop` bound to the desktop.
+  fn script(monitor: &Monitor, body: &str) -> String {
+    format!(
+      r#"var all = desktops();
+var targets = all.filter(function (d) {{
+  var g = screenGeometry(d.screen);
+  return g.x == {x} && g.y == {y};
+}});
+if (targets.length == 0) {{
+  targets = all.filter(function (d) {{ return d.screen == {id}; }});
+}}
+targets.forEach(function (desktop) {{
+  desktop.wallpaperPlugin = "org.kde.image";
+  desktop.currentConfigGroup = ["Wallpaper", "org.kde.image", "General"];
+  {body}
+}});"#,
+      x = monitor.position.x,
+      y = monitor.position.y,
+      id = monitor.id
+    )
+  }
+
+  /// Evaluates a script in plasmashell, returning what it printed.
+  fn evaluate(script: &str) -> Result<String> {
+    let output = Command::new("dbus-send")
+      .args([
+        "--session",
+        "--print-reply",
+        "--dest=org.kde.plasmashell",
+        "/PlasmaShell",
+        "org.kde.PlasmaShell.evaluateScript"
+      ])
+      .arg(format!("string:{script}"))
+      .output()
+      .map_err(|e| {
+        Error::Wallpaper(format!("Linux/KDE: Failed to execute dbus-send: {e}"))
+      })?;
+
+    if !output.status.success() {
+      return Err(Error::Wallpaper(format!(
+        "Linux/KDE: plasmashell script failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      )));
+    }
+    Ok(parse_reply(&String::from_utf8_lossy(&output.stdout)))
+  }
+
+  /// Applies the image to every screen with the Plasma helper.
+  fn apply_all(path: &Path) -> Result<()> {
+    let status = Command::new("plasma-apply-wallpaperimage")
+      .arg(path)
+      .status()
+      .map_err(|e| {
+        Error::Wallpaper(format!(
+          "Linux/KDE: Failed to execute plasma-apply-wallpaperimage: {e}"
+        ))
+      })?;
+
+    if !status.success() {
+      return Err(Error::Wallpaper(
+        "Linux/KDE: plasma-apply-wallpaperimage command failed".to_string()
+      ));
+    }
+    Ok(())
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "kde"
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    let path = path.canonicalize()?;
+    let uri = serde_json::to_string(&file_uri(&path))
+      .map_err(|e| Error::Wallpaper(e.to_string()))?;
+    let body = format!(r#"desktop.writeConfig("Image", {uri});"#);
+
+    match Self::evaluate(&Self::script(monitor, &body)) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        warn!("{e}. Applying to all screens instead.");
+        Self::apply_all(&path)
+      }
+    }
+  }
+
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let body = r#"print(desktop.readConfig("Image") + "\n");"#;
+    let printed = Self::evaluate(&Self::script(monitor, body))?;
+    Ok(printed.lines().find_map(|line| from_file_uri(line.trim())))
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    true
+  }
+}
+
+/// Extracts the string payload from a `dbus-send --print-reply` response.
+fn parse_reply(reply: &str) -> String {
+  reply
+    .split_once("string \"")
+    .and_then(|(_, rest)| rest.rsplit_once('"'))
+    .map(|(value, _)| value.to_string())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_reply() {
+    let reply = "method return time=1 sender=:1.20 -> destination=:1.99 \
+                 serial=4 reply_serial=2\n   string \"file:///tmp/a.jpg\n\"\n";
+    assert_eq!(parse_reply(reply), "file:///tmp/a.jpg\n");
+    assert_eq!(parse_reply("Error org.freedesktop.DBus"), "");
+  }
+}
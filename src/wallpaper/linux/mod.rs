@@ -0,0 +1,84 @@
+mod gnome;
+pub use gnome::Setter as GnomeSetter;
+
+mod kde;
+pub use kde::Setter as KdeSetter;
+
+mod wlroots;
+pub use wlroots::{Setter as WlrootsSetter, Tool as WlrootsTool};
+
+mod x11;
+pub use x11::Setter as X11Setter;
+
+use std::{
+  env,
+  os::unix::ffi::{OsStrExt, OsStringExt},
+  path::{Path, PathBuf}
+};
+
+/// Checks whether `program` is an executable file on the `PATH`.
+pub(crate) fn has_command(program: &str) -> bool {
+  env::var_os("PATH").is_some_and(|paths| {
+    env::split_paths(&paths).any(|dir| dir.join(program).is_file())
+  })
+}
+
+/// Converts an absolute path to a `file://` URI, percent-encoding any bytes
+/// outside the unreserved set.
+pub(crate) fn file_uri(path: &Path) -> String {
+  let mut uri = String::from("file://");
+  for &byte in path.as_os_str().as_bytes() {
+    match byte {
+      b'A'..=b'Z'
+      | b'a'..=b'z'
+      | b'0'..=b'9'
+      | b'-'
+      | b'.'
+      | b'_'
+      | b'~'
+      | b'/' => uri.push(byte as char),
+      _ => uri.push_str(&format!("%{byte:02X}"))
+    }
+  }
+  uri
+}
+
+/// Converts a `file://` URI back to a path, decoding percent-encoded bytes.
+/// Returns `None` for other schemes.
+pub(crate) fn from_file_uri(uri: &str) -> Option<PathBuf> {
+  let encoded = uri.strip_prefix("file://")?.as_bytes();
+  let mut bytes = Vec::with_capacity(encoded.len());
+  let mut index = 0;
+  while index < encoded.len() {
+    let decoded = (encoded[index] == b'%')
+      .then(|| encoded.get(index + 1..index + 3))
+      .flatten()
+      .and_then(|hex| std::str::from_utf8(hex).ok())
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    match decoded {
+      Some(byte) => {
+        bytes.push(byte);
+        index += 3;
+      }
+      None => {
+        bytes.push(encoded[index]);
+        index += 1;
+      }
+    }
+  }
+  Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_file_uri_round_trip() {
+    let path = Path::new("/home/user/Pictures/Misty Forest #2.jpg");
+    let uri = file_uri(path);
+    assert_eq!(uri, "file:///home/user/Pictures/Misty%20Forest%20%232.jpg");
+    assert_eq!(from_file_uri(&uri).as_deref(), Some(path));
+    assert_eq!(from_file_uri("https://example.com/a.jpg"), None);
+  }
+}
@@ -0,0 +1,214 @@
+//! Sets wallpapers on wlroots-based Wayland compositors such as Hyprland and
+//! Sway.
+//!
+//! These compositors have no wallpaper setting of their own, so one of the
+//! common wallpaper daemons is driven instead, in order of preference:
+//! `swww` (with transitions), `hyprpaper`, then `swaybg`. Outputs are
+//! selected by the monitor names from `config::monitor`.
+
+use super::super::Setter as WallpaperSetter;
+use super::has_command;
+use crate::{
+  Error, Result,
+  config::{Monitor, wallpaper::Transition}
+};
+use std::{
+  env,
+  path::{Path, PathBuf},
+  process::{Command, Output, Stdio}
+};
+use tracing::warn;
+
+/// The wallpaper daemon being driven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+  Swww,
+  Hyprpaper,
+  Swaybg
+}
+
+impl Tool {
+  /// Finds the preferred tool that is installed and usable in this session.
+  pub fn detect() -> Option<Self> {
+    if has_command("swww") && run("swww", &["query"]).is_ok() {
+      return Some(Self::Swww);
+    }
+    if has_command("hyprctl")
+      && run("hyprctl", &["hyprpaper", "listloaded"]).is_ok()
+    {
+      return Some(Self::Hyprpaper);
+    }
+    if env::var_os("SWAYSOCK").is_some() || has_command("swaybg") {
+      return Some(Self::Swaybg);
+    }
+    None
+  }
+}
+
+/// A wallpaper backend for wlroots compositors, with per-output support.
+pub struct Setter {
+  tool: Tool,
+  transition: Transition
+}
+
+impl Setter {
+  pub fn new(tool: Tool, transition: Transition) -> Self {
+    Self { tool, transition }
+  }
+
+  fn set_swww(&self, path: &str, output: &str) -> Result<()> {
+    run(
+      "swww",
+      &[
+        "img",
+        path,
+        "--outputs",
+        output,
+        "--transition-type",
+        &self.transition.kind,
+        "--transition-duration",
+        &self.transition.duration.to_string(),
+        "--transition-fps",
+        &self.transition.fps.to_string()
+      ]
+    )
+    .map(drop)
+  }
+
+  fn set_hyprpaper(path: &str, output: &str) -> Result<()> {
+    run("hyprctl", &["hyprpaper", "preload", path])?;
+    run(
+      "hyprctl",
+      &["hyprpaper", "wallpaper", &format!("{output},{path}")]
+    )?;
+
+    //{ Free images that are no longer shown on any output }
+    if let Err(e) = run("hyprctl", &["hyprpaper", "unload", "unused"]) {
+      warn!("Failed to unload unused hyprpaper images: {e}");
+    }
+    Ok(())
+  }
+
+  fn set_swaybg(path: &str, output: &str) -> Result<()> {
+    //{ Let Sway manage its own swaybg instances when running under Sway }
+    if env::var_os("SWAYSOCK").is_some() {
+      return run("swaymsg", &["output", output, "bg", path, "fill"]).map(drop);
+    }
+
+    //{ Otherwise replace the swaybg instance for this output }
+    let pattern = format!("swaybg -o {output} ");
+    let _ = run("pkill", &["-f", &pattern]);
+    Command::new("swaybg")
+      .args(["-o", output, "-i", path, "-m", "fill"])
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .map_err(|e| {
+        Error::Wallpaper(format!("Linux/wlroots: Failed to start swaybg: {e}"))
+      })?;
+    Ok(())
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    match self.tool {
+      Tool::Swww => "swww",
+      Tool::Hyprpaper => "hyprpaper",
+      Tool::Swaybg => "swaybg"
+    }
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    let path = path.canonicalize()?;
+    let path = path.to_str().ok_or_else(|| {
+      Error::Wallpaper(format!(
+        "Linux/wlroots: Path is not valid UTF-8: {}",
+        path.display()
+      ))
+    })?;
+
+    match self.tool {
+      Tool::Swww => self.set_swww(path, &monitor.name),
+      Tool::Hyprpaper => Self::set_hyprpaper(path, &monitor.name),
+      Tool::Swaybg => Self::set_swaybg(path, &monitor.name)
+    }
+  }
+
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let output = match self.tool {
+      Tool::Swww => run("swww", &["query"])?,
+      Tool::Hyprpaper => run("hyprctl", &["hyprpaper", "listactive"])?,
+      Tool::Swaybg => return Ok(None)
+    };
+    let listing = String::from_utf8_lossy(&output.stdout);
+    Ok(match self.tool {
+      Tool::Swww => parse_swww_query(&listing, &monitor.name),
+      _ => parse_hyprpaper_active(&listing, &monitor.name)
+    })
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    true
+  }
+}
+
+/// Runs `program`, failing if it cannot be started or exits unsuccessfully.
+fn run(program: &str, args: &[&str]) -> Result<Output> {
+  let output = Command::new(program).args(args).output().map_err(|e| {
+    Error::Wallpaper(format!("Linux/wlroots: Failed to execute {program}: {e}"))
+  })?;
+
+  if !output.status.success() {
+    return Err(Error::Wallpaper(format!(
+      "Linux/wlroots: {program} {} failed: {}",
+      args.first().unwrap_or(&""),
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+  Ok(output)
+}
+
+/// Finds the image shown on `output` in `swww query` output, which looks like
+/// `DP-1: 2560x1440, scale: 1, currently displaying: image: /path/a.jpg`.
+fn parse_swww_query(listing: &str, output: &str) -> Option<PathBuf> {
+  listing.lines().find_map(|line| {
+    let line = line.trim().strip_prefix(':').unwrap_or(line.trim());
+    let rest = line.strip_prefix(output)?.strip_prefix(':')?;
+    let (_, image) = rest.split_once("image: ")?;
+    Some(PathBuf::from(image.trim()))
+  })
+}
+
+/// Finds the image shown on `output` in `hyprctl hyprpaper listactive`
+/// output, which looks like `DP-1 = /path/a.jpg`.
+fn parse_hyprpaper_active(listing: &str, output: &str) -> Option<PathBuf> {
+  listing.lines().find_map(|line| {
+    let (name, image) = line.split_once(" = ")?;
+    (name.trim() == output).then(|| PathBuf::from(image.trim()))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_listings() {
+    let swww = "DP-1: 2560x1440, scale: 1, currently displaying: image: \
+                /home/user/a.jpg\n: HDMI-A-1: 1920x1080, scale: 1, \
+                currently displaying: color: 000000\n";
+    assert_eq!(
+      parse_swww_query(swww, "DP-1"),
+      Some(PathBuf::from("/home/user/a.jpg"))
+    );
+    assert_eq!(parse_swww_query(swww, "HDMI-A-1"), None);
+
+    let hyprpaper = "DP-1 = /home/user/a.jpg\nHDMI-A-1 = /home/user/b.png\n";
+    assert_eq!(
+      parse_hyprpaper_active(hyprpaper, "HDMI-A-1"),
+      Some(PathBuf::from("/home/user/b.png"))
+    );
+  }
+}
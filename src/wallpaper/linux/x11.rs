@@ -0,0 +1,267 @@
+//! Sets wallpapers on plain X11 window managers such as i3 and bspwm.
+//!
+//! The image is either drawn onto the root window directly, following the
+//! `_XROOTPMAP_ID`/`ESETROOT_PMAP_ID` convention so that pseudo-transparent
+//! programs pick it up, or applied by shelling out to `xwallpaper` or `feh`.
+
+use super::super::Setter as WallpaperSetter;
+use super::has_command;
+use crate::{
+  Error, Result,
+  config::{
+    Monitor,
+    wallpaper::{Limits, X11Tool}
+  },
+  utils::limits
+};
+use image::imageops::FilterType;
+use std::{
+  env,
+  error::Error as StdError,
+  fs::read_to_string,
+  path::{Path, PathBuf},
+  process::Command
+};
+use tracing::warn;
+use x11rb::{
+  connection::{Connection, RequestConnection},
+  protocol::xproto::{
+    AtomEnum, ChangeWindowAttributesAux, CloseDown, ConnectionExt as _,
+    CreateGCAux, ImageFormat, ImageOrder, PropMode, Window
+  },
+  wrapper::ConnectionExt as _
+};
+
+/// The method used to apply the wallpaper, with `Auto` resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+  Root,
+  Feh,
+  Xwallpaper
+}
+
+/// A wallpaper backend for X11 sessions without a desktop environment.
+pub struct Setter {
+  method: Method,
+  limits: Limits
+}
+
+impl Setter {
+  pub fn new(tool: X11Tool, limits: Limits) -> Self {
+    let method = match tool {
+      X11Tool::Root => Method::Root,
+      X11Tool::Feh => Method::Feh,
+      X11Tool::Xwallpaper => Method::Xwallpaper,
+      X11Tool::Auto if has_command("xwallpaper") => Method::Xwallpaper,
+      X11Tool::Auto if has_command("feh") => Method::Feh,
+      X11Tool::Auto => Method::Root
+    };
+    Self { method, limits }
+  }
+
+  fn run(program: &str, args: &[&std::ffi::OsStr]) -> Result<()> {
+    let status = Command::new(program).args(args).status().map_err(|e| {
+      Error::Wallpaper(format!("Linux/X11: Failed to execute {program}: {e}"))
+    })?;
+    if !status.success() {
+      return Err(Error::Wallpaper(format!(
+        "Linux/X11: {program} command failed"
+      )));
+    }
+    Ok(())
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    match self.method {
+      Method::Root => "x11-root",
+      Method::Feh => "feh",
+      Method::Xwallpaper => "xwallpaper"
+    }
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    match self.method {
+      Method::Root => draw_root(path, monitor, &self.limits).map_err(|e| {
+        Error::Wallpaper(format!("Linux/X11: Failed to draw root window: {e}"))
+      }),
+      Method::Feh =>
+        Self::run("feh", &["--bg-fill".as_ref(), path.as_os_str()]),
+      Method::Xwallpaper => Self::run(
+        "xwallpaper",
+        &[
+          "--output".as_ref(),
+          monitor.name.as_ref(),
+          "--zoom".as_ref(),
+          path.as_os_str()
+        ]
+      )
+    }
+  }
+
+  fn get_current(&self, _monitor: &Monitor) -> Result<Option<PathBuf>> {
+    if self.method != Method::Feh {
+      return Ok(None);
+    }
+
+    //{ feh records its last command in ~/.fehbg }
+    let Some(home) = env::var_os("HOME") else {
+      return Ok(None);
+    };
+    let script = match read_to_string(Path::new(&home).join(".fehbg")) {
+      Ok(script) => script,
+      Err(_) => return Ok(None)
+    };
+    Ok(parse_fehbg(&script))
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    self.method != Method::Feh
+  }
+}
+
+/// Draws the image, scaled to fill `monitor`, onto a new root pixmap that
+/// keeps the other monitors' contents, and installs it as the background.
+fn draw_root(
+  path: &Path,
+  monitor: &Monitor,
+  limits: &Limits
+) -> std::result::Result<(), Box<dyn StdError>> {
+  let (width, height) = (monitor.size.width, monitor.size.height);
+  let image = limits::open(path, limits)?
+    .resize_to_fill(width, height, FilterType::Lanczos3)
+    .to_rgba8();
+
+  let (conn, screen_num) = x11rb::connect(None)?;
+  let setup = conn.setup();
+  let screen = &setup.roots[screen_num];
+  let root = screen.root;
+  let depth = screen.root_depth;
+  let bits_per_pixel = setup
+    .pixmap_formats
+    .iter()
+    .find(|format| format.depth == depth)
+    .map(|format| format.bits_per_pixel);
+  if !matches!(depth, 24 | 32) || bits_per_pixel != Some(32) {
+    return Err(format!("unsupported root depth {depth}").into());
+  }
+
+  let (screen_width, screen_height) =
+    (screen.width_in_pixels, screen.height_in_pixels);
+  let pixmap = conn.generate_id()?;
+  conn.create_pixmap(depth, pixmap, root, screen_width, screen_height)?;
+  let gc = conn.generate_id()?;
+  conn.create_gc(gc, pixmap, &CreateGCAux::new())?;
+
+  //{ Start from the current background so other monitors are kept }
+  let xrootpmap = conn.intern_atom(false, b"_XROOTPMAP_ID")?.reply()?.atom;
+  let esetroot = conn.intern_atom(false, b"ESETROOT_PMAP_ID")?.reply()?.atom;
+  let old = root_pixmap(&conn, root, xrootpmap)?;
+  if let Some(old) = old {
+    let copied = conn
+      .copy_area(old, pixmap, gc, 0, 0, 0, 0, screen_width, screen_height)?
+      .check();
+    if copied.is_err() {
+      warn!("The previous root background could not be copied");
+    }
+  }
+
+  //{ Convert to the server's 32-bit pixel layout and upload in strips }
+  let lsb_first = setup.image_byte_order == ImageOrder::LSB_FIRST;
+  let data: Vec<u8> = image
+    .pixels()
+    .flat_map(|pixel| {
+      let [r, g, b, _] = pixel.0;
+      if lsb_first {
+        [b, g, r, 0]
+      } else {
+        [0, r, g, b]
+      }
+    })
+    .collect();
+  let row_bytes = width as usize * 4;
+  let rows_per_request =
+    ((conn.maximum_request_bytes() - 64) / row_bytes).max(1);
+  for (index, strip) in data.chunks(row_bytes * rows_per_request).enumerate() {
+    let y = monitor.position.y + (index * rows_per_request) as i32;
+    conn.put_image(
+      ImageFormat::Z_PIXMAP,
+      pixmap,
+      gc,
+      width as u16,
+      (strip.len() / row_bytes) as u16,
+      monitor.position.x as i16,
+      y as i16,
+      0,
+      depth,
+      strip
+    )?;
+  }
+  conn.free_gc(gc)?;
+
+  //{ Publish the pixmap and free the previous one if it was set the same way }
+  let old_esetroot = root_pixmap(&conn, root, esetroot)?;
+  conn.change_property32(
+    PropMode::REPLACE,
+    root,
+    xrootpmap,
+    AtomEnum::PIXMAP,
+    &[pixmap]
+  )?;
+  conn.change_property32(
+    PropMode::REPLACE,
+    root,
+    esetroot,
+    AtomEnum::PIXMAP,
+    &[pixmap]
+  )?;
+  if let (Some(old), Some(old_esetroot)) = (old, old_esetroot) {
+    if old == old_esetroot {
+      conn.kill_client(old)?;
+    }
+  }
+  conn.change_window_attributes(
+    root,
+    &ChangeWindowAttributesAux::new().background_pixmap(pixmap)
+  )?;
+  conn.clear_area(false, root, 0, 0, 0, 0)?;
+
+  //{ Keep the pixmap alive after this connection closes }
+  conn.set_close_down_mode(CloseDown::RETAIN_PERMANENT)?;
+  conn.flush()?;
+  Ok(())
+}
+
+/// Reads a pixmap id stored in a property of the root window.
+fn root_pixmap(
+  conn: &impl Connection,
+  root: Window,
+  atom: u32
+) -> std::result::Result<Option<u32>, Box<dyn StdError>> {
+  let reply = conn
+    .get_property(false, root, atom, AtomEnum::PIXMAP, 0, 1)?
+    .reply()?;
+  Ok(reply.value32().and_then(|mut values| values.next()))
+}
+
+/// Extracts the image path from a `~/.fehbg` script, which ends with a line
+/// such as `feh --no-fehbg --bg-fill '/home/user/a.jpg'`.
+fn parse_fehbg(script: &str) -> Option<PathBuf> {
+  let line = script.lines().rev().find(|line| line.contains("feh "))?;
+  let (_, rest) = line.split_once('\'')?;
+  let (path, _) = rest.split_once('\'')?;
+  Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_fehbg() {
+    let script = "#!/bin/sh\nfeh --no-fehbg --bg-fill '/home/user/a.jpg' \n";
+    assert_eq!(parse_fehbg(script), Some(PathBuf::from("/home/user/a.jpg")));
+    assert_eq!(parse_fehbg("#!/bin/sh\n"), None);
+  }
+}
@@ -0,0 +1,95 @@
+//! Sets wallpapers on macOS through System Events via `osascript`.
+//!
+//! Each System Events desktop corresponds to a display. Desktops are matched
+//! by their display name, which is the monitor name reported by the system,
+//! falling back to the enumeration order.
+
+use super::Setter as WallpaperSetter;
+use crate::{Error, Result, config::Monitor};
+use std::{
+  path::{Path, PathBuf},
+  process::Command
+};
+
+/// A wallpaper backend for macOS, with per-display support.
+pub struct Setter;
+
+impl Setter {
+  /// Builds a script that runs `body` against the desktop of `monitor`,
+  /// bound to `target`.
+  fn script(monitor: &Monitor, body: &str) -> String {
+    format!(
+      r#"tell application "System Events"
+  set target to missing value
+  repeat with d in desktops
+    if display name of d is "{name}" then set target to d
+  end repeat
+  if target is missing value and (count of desktops) > {id} then
+    set target to desktop {index}
+  end if
+  if target is missing value then error "No desktop for display {name}"
+  {body}
+end tell"#,
+      name = escape(&monitor.name),
+      id = monitor.id,
+      index = monitor.id + 1
+    )
+  }
+
+  fn run(script: &str) -> Result<String> {
+    let output = Command::new("osascript")
+      .args(["-e", script])
+      .output()
+      .map_err(|e| {
+        Error::Wallpaper(format!("macOS: Failed to execute osascript: {e}"))
+      })?;
+
+    if !output.status.success() {
+      return Err(Error::Wallpaper(format!(
+        "macOS: osascript failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "macos"
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    let path = path.canonicalize()?;
+    let body = format!(
+      r#"set picture of target to "{}""#,
+      escape(&path.to_string_lossy())
+    );
+    Self::run(&Self::script(monitor, &body)).map(drop)
+  }
+
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let picture =
+      Self::run(&Self::script(monitor, "return picture of target"))?;
+    Ok((!picture.is_empty()).then(|| PathBuf::from(picture)))
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    true
+  }
+}
+
+/// Escapes a value for use inside an AppleScript string literal.
+fn escape(value: &str) -> String {
+  value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(escape(r#"My "Best" \ Photo"#), r#"My \"Best\" \\ Photo"#);
+  }
+}
@@ -0,0 +1,166 @@
+//! Keeps what providers report about downloaded wallpapers, so it can be
+//! looked up offline.
+//!
+//! Every download is recorded in `metadata.json` in the state directory,
+//! keyed by the path it was saved to: the provider and id, the addresses of
+//! the image and its page, and the tags, colors, purity and uploader where
+//! the provider reported them. `wallter info <file>` shows the record.
+//! Records of images that have since been deleted are dropped whenever a new
+//! one is added.
+
+use super::naming::Details;
+use crate::{Result, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  path::{self, PathBuf}
+};
+
+/// The name of the metadata index within the state directory.
+const METADATA_FILE: &str = "metadata.json";
+
+/// What is known of a downloaded wallpaper.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+  /// The provider, or the host the image was downloaded from.
+  pub source: Option<String>,
+  /// The identifier of the wallpaper within its provider.
+  pub id: Option<String>,
+  /// The address the image was downloaded from.
+  pub url: String,
+  /// The wallpaper's page on the provider's site.
+  pub page: Option<String>,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  /// The dominant colors of the image as hex codes.
+  #[serde(default)]
+  pub colors: Vec<String>,
+  pub purity: Option<String>,
+  pub category: Option<String>,
+  pub uploader: Option<String>,
+  /// The size the provider reported, as width and height.
+  pub resolution: Option<(u32, u32)>,
+  pub downloaded_at: DateTime<Local>
+}
+
+impl Metadata {
+  /// Describes an image downloaded from `url` just now.
+  pub fn new(url: &str, details: Details) -> Self {
+    let details = details.or(Details::from_url(url));
+    Self {
+      source: details.source,
+      id: details.id,
+      url: url.to_string(),
+      page: details.page,
+      tags: details.tags,
+      colors: details.colors,
+      purity: details.purity,
+      category: details.category,
+      uploader: details.uploader,
+      resolution: details.resolution,
+      downloaded_at: Local::now()
+    }
+  }
+}
+
+impl Display for Metadata {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let or_unknown =
+      |value: &Option<String>| value.clone().unwrap_or("Unknown".to_string());
+    printf!(f, "Source", or_unknown(&self.source))?;
+    printf!(f, "Id", or_unknown(&self.id))?;
+    printf!(f, "URL", &self.url)?;
+    if let Some(page) = &self.page {
+      printf!(f, "Page", page)?;
+    }
+    if let Some((width, height)) = self.resolution {
+      printf!(f, "Resolution", format!("{width}x{height}"))?;
+    }
+    if !self.tags.is_empty() {
+      printf!(f, "Tags", self.tags.join(", "))?;
+    }
+    if !self.colors.is_empty() {
+      printf!(f, "Colors", self.colors.join(", "))?;
+    }
+    if let Some(purity) = &self.purity {
+      printf!(f, "Purity", purity)?;
+    }
+    if let Some(category) = &self.category {
+      printf!(f, "Category", category)?;
+    }
+    if let Some(uploader) = &self.uploader {
+      printf!(f, "Uploader", uploader)?;
+    }
+    printf!(f, "Downloaded", self.downloaded_at.format("%Y-%m-%d %H:%M"))
+  }
+}
+
+/// The metadata of the downloaded wallpapers, by path.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Index {
+  pub items: BTreeMap<PathBuf, Metadata>
+}
+
+impl Index {
+  /// Returns the path of the metadata index.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(METADATA_FILE)
+  }
+
+  /// Loads the metadata index from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the metadata index to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns the metadata of `image`, if it was recorded.
+  pub fn get(&self, image: &path::Path) -> Option<&Metadata> {
+    self.items.get(image)
+  }
+
+  /// Records the metadata of `image`, replacing any earlier record and
+  /// dropping those of images that no longer exist.
+  pub fn insert(&mut self, image: &path::Path, metadata: Metadata) {
+    self.items.retain(|path, _| path.exists());
+    self.items.insert(image.to_path_buf(), metadata);
+  }
+}
+
+/// Records the metadata of `image`, just downloaded from `url`, from the
+/// URL and the [details](super::naming::with_details) of the download.
+pub fn record(path_config: &Path, image: &path::Path, url: &str) -> Result<()> {
+  let mut index = Index::load(path_config)?;
+  index.insert(image, Metadata::new(url, super::naming::current()));
+  index.save(path_config)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_metadata() {
+    let details = Details {
+      tags: vec!["dunes".into()],
+      purity: Some("sfw".into()),
+      ..Details::default()
+    };
+    let metadata = Metadata::new(
+      "https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg",
+      details
+    );
+    assert_eq!(metadata.source.as_deref(), Some("wallhaven"));
+    assert_eq!(metadata.id.as_deref(), Some("94x38z"));
+    assert_eq!(metadata.tags, ["dunes"]);
+
+    let text = metadata.to_string();
+    assert!(text.contains("94x38z") && text.contains("sfw"));
+    assert!(!text.contains("Uploader"));
+  }
+}
@@ -0,0 +1,65 @@
+//! Applies wallpapers to the desktop, per monitor.
+
+mod default;
+pub use default::*;
+
+pub mod adjust;
+
+pub mod attribution;
+pub use attribution::Attributions;
+
+pub mod blocklist;
+pub use blocklist::Blocklist;
+
+pub mod cache;
+
+pub mod collection;
+pub use collection::Collection;
+
+pub mod continuity;
+
+pub mod dark;
+
+mod current;
+pub use current::{Applied, Current};
+
+pub mod digest;
+
+pub mod fingerprints;
+pub use fingerprints::Fingerprints;
+
+pub mod hdr;
+
+pub mod history;
+pub use history::History;
+
+pub mod journal;
+
+pub mod library;
+pub use library::Library;
+
+pub mod metadata;
+
+pub mod naming;
+
+pub mod now_showing;
+pub use now_showing::NowShowing;
+
+pub mod orphans;
+
+pub mod probe;
+pub use probe::Probes;
+
+pub mod remote;
+
+pub mod snapshot;
+
+#[cfg(feature = "thumbnails")]
+pub mod thumbnails;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
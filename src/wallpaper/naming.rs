@@ -0,0 +1,310 @@
+//! Names downloads after a template, such as
+//! `{source}-{id}-{resolution}.{ext}`.
+//!
+//! Without a `filename_template` in the path config, a download keeps the
+//! name at the end of its URL. With one, the tokens are filled in from what
+//! is known of the wallpaper:
+//!
+//! - `source` and `id` are read from the URL, as in `https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg`.
+//! - `purity`, `category` and `resolution` come from the provider, when the
+//!   code starting the download installs them with [with_details].
+//! - `date` is the day of the download and `ext` the URL's extension, which is
+//!   corrected once the actual image format is known.
+//!
+//! Tokens that aren't known are left out along with the separator before
+//! them, so `{source}-{id}-{resolution}.{ext}` still gives
+//! `wallhaven-94x38z.jpg` for a bare URL.
+
+use crate::{Error, Result, api::wallhaven, config::Path};
+use chrono::{Local, NaiveDate};
+use std::{future::Future, path::Path as FilePath};
+
+/// The tokens a template may use.
+pub const TOKENS: [&str; 7] = [
+  "source",
+  "id",
+  "purity",
+  "category",
+  "resolution",
+  "date",
+  "ext"
+];
+
+/// What is known of a wallpaper being downloaded, beyond its URL. Besides
+/// naming the download, it is kept as its [metadata](super::metadata).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Details {
+  pub source: Option<String>,
+  pub id: Option<String>,
+  /// The wallpaper's page on the provider's site.
+  pub page: Option<String>,
+  pub purity: Option<String>,
+  pub category: Option<String>,
+  pub resolution: Option<(u32, u32)>,
+  pub tags: Vec<String>,
+  pub colors: Vec<String>,
+  pub uploader: Option<String>
+}
+
+impl From<&wallhaven::Wallpaper> for Details {
+  fn from(wallpaper: &wallhaven::Wallpaper) -> Self {
+    Self {
+      purity: Some(wallpaper.purity.clone()),
+      category: Some(wallpaper.category.clone()),
+      ..Self::from(&crate::api::Wallpaper::from(wallpaper))
+    }
+  }
+}
+
+impl From<&crate::api::Wallpaper> for Details {
+  fn from(wallpaper: &crate::api::Wallpaper) -> Self {
+    Self {
+      source: Some(wallpaper.provider.clone()),
+      id: Some(wallpaper.id.clone()),
+      page: wallpaper.page.clone(),
+      purity: Some(if wallpaper.safe { "sfw" } else { "nsfw" }.into()),
+      category: None,
+      resolution: Some((wallpaper.width, wallpaper.height)),
+      tags: wallpaper.tags.clone(),
+      colors: wallpaper.colors.clone(),
+      uploader: wallpaper.author.clone()
+    }
+  }
+}
+
+impl Details {
+  /// Reads the source and id from `url`. The source is the provider serving
+  /// it, or else its host, and the id is the file stem without the source
+  /// and any other prefix joined by `-`.
+  pub fn from_url(url: &str) -> Self {
+    let host = url
+      .split("://")
+      .nth(1)
+      .and_then(|rest| rest.split(['/', '?', '#']).next())
+      .unwrap_or_default()
+      .to_ascii_lowercase();
+    let source = ["wallhaven", "pexels", "pixabay", "unsplash", "reddit"]
+      .into_iter()
+      .find(|provider| host.contains(provider))
+      .map(str::to_string)
+      .or_else(|| (host == "i.redd.it").then(|| "reddit".to_string()))
+      .or_else(|| (!host.is_empty()).then_some(host));
+
+    let stem = file_name(url)
+      .and_then(|name| FilePath::new(name).file_stem())
+      .and_then(|stem| stem.to_str())
+      .unwrap_or_default();
+    let id = match &source {
+      Some(source) if stem.starts_with(&format!("{source}-")) =>
+        stem.rsplit('-').next(),
+      _ => Some(stem)
+    }
+    .filter(|id| !id.is_empty())
+    .map(str::to_string);
+    Self {
+      source,
+      id,
+      ..Self::default()
+    }
+  }
+
+  /// Fills in what `self` doesn't know from `other`.
+  #[must_use]
+  pub fn or(self, other: Self) -> Self {
+    let or_list = |list: Vec<String>, other: Vec<String>| {
+      if list.is_empty() { other } else { list }
+    };
+    Self {
+      source: self.source.or(other.source),
+      id: self.id.or(other.id),
+      page: self.page.or(other.page),
+      purity: self.purity.or(other.purity),
+      category: self.category.or(other.category),
+      resolution: self.resolution.or(other.resolution),
+      tags: or_list(self.tags, other.tags),
+      colors: or_list(self.colors, other.colors),
+      uploader: self.uploader.or(other.uploader)
+    }
+  }
+
+  fn token(&self, name: &str, date: NaiveDate, ext: &str) -> Option<String> {
+    match name {
+      "source" => self.source.clone(),
+      "id" => self.id.clone(),
+      "purity" => self.purity.clone(),
+      "category" => self.category.clone(),
+      "resolution" => self.resolution.map(|(w, h)| format!("{w}x{h}")),
+      "date" => Some(date.format("%Y-%m-%d").to_string()),
+      "ext" => Some(ext.to_string()).filter(|ext| !ext.is_empty()),
+      _ => None
+    }
+  }
+}
+
+tokio::task_local! {
+  static DETAILS: Details;
+}
+
+/// Runs `future`, naming the downloads it makes with `details` as well as
+/// their URLs.
+pub async fn with_details<F: Future>(details: Details, future: F) -> F::Output {
+  DETAILS.scope(details, future).await
+}
+
+/// Returns the details installed with [with_details], if any.
+pub fn current() -> Details {
+  DETAILS.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Returns the name at the end of `url`, without any query.
+fn file_name(url: &str) -> Option<&str> {
+  url
+    .split(['?', '#'])
+    .next()?
+    .rsplit('/')
+    .next()
+    .filter(|name| !name.is_empty())
+}
+
+/// Checks that `template` only uses known tokens, and names each download
+/// apart by its `{id}`.
+pub fn validate(template: &str) -> Result<()> {
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    let Some(end) = rest[start..].find('}') else {
+      return Err(Error::Config(format!(
+        "Unclosed token in the file name template '{template}'"
+      )));
+    };
+    let token = &rest[start + 1..start + end];
+    if !TOKENS.contains(&token) {
+      return Err(Error::Config(format!(
+        "Unknown token '{{{token}}}' in the file name template; use {}",
+        TOKENS.map(|token| format!("{{{token}}}")).join(", ")
+      )));
+    }
+    rest = &rest[start + end + 1..];
+  }
+  if !template.contains("{id}") {
+    return Err(Error::Config(
+      "The file name template needs an {id}, or downloads overwrite each other"
+        .to_string()
+    ));
+  }
+  Ok(())
+}
+
+/// Fills in `template`, which is [valid](validate), for a download on
+/// `date` with the extension `ext`.
+fn render(
+  template: &str,
+  details: &Details,
+  date: NaiveDate,
+  ext: &str
+) -> Result<String> {
+  let mut name = String::new();
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    let end = start + rest[start..].find('}').unwrap_or_default();
+    let literal = &rest[..start];
+    match details.token(&rest[start + 1..end], date, ext) {
+      Some(value) => {
+        name.push_str(literal);
+        name.push_str(&sanitize(&value));
+      }
+      //{ Leave out the separator before a missing token too }
+      None => name.push_str(literal.trim_end_matches(['-', '_', ' ']))
+    }
+    rest = &rest[end + 1..];
+  }
+  name.push_str(rest);
+  let name = name.trim_matches(['-', '_', ' ', '.']).to_string();
+  if name.is_empty() {
+    return Err(Error::Config(format!(
+      "The file name template '{template}' gives an empty name"
+    )));
+  }
+  Ok(name)
+}
+
+/// Replaces characters that aren't allowed in file names.
+fn sanitize(value: &str) -> String {
+  value
+    .chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_control() => '_',
+      c => c
+    })
+    .collect()
+}
+
+/// Returns the file name to download `url` to, following the template in
+/// `path_config` if it has one, or else the name at the end of the URL.
+/// `known` adds to what the URL and any [with_details] reveal.
+pub fn name(path_config: &Path, url: &str, known: Details) -> Result<String> {
+  let Some(template) = &path_config.filename_template else {
+    return file_name(url).map(str::to_string).ok_or_else(|| {
+      Error::Wallpaper(format!("Cannot derive a file name from '{url}'"))
+    });
+  };
+  let details = known.or(current()).or(Details::from_url(url));
+  let ext = file_name(url)
+    .and_then(|name| FilePath::new(name).extension())
+    .and_then(|ext| ext.to_str())
+    .unwrap_or_default()
+    .to_ascii_lowercase();
+  validate(template)?;
+  render(template, &details, Local::now().date_naive(), &ext)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_from_url() {
+    let wallhaven =
+      Details::from_url("https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg");
+    assert_eq!(wallhaven.source.as_deref(), Some("wallhaven"));
+    assert_eq!(wallhaven.id.as_deref(), Some("94x38z"));
+
+    let pexels = Details::from_url(
+      "https://images.pexels.com/photos/2014422/pexels-photo-2014422.jpeg?w=10"
+    );
+    assert_eq!(pexels.source.as_deref(), Some("pexels"));
+    assert_eq!(pexels.id.as_deref(), Some("2014422"));
+
+    let other = Details::from_url("https://example.com/a/dunes-at-dusk.png");
+    assert_eq!(other.source.as_deref(), Some("example.com"));
+    assert_eq!(other.id.as_deref(), Some("dunes-at-dusk"));
+  }
+
+  #[test]
+  fn test_render() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    let template = "{source}-{id}-{resolution}.{ext}";
+    let mut details = Details::from_url("https://w.wallhaven.cc/wallhaven-ab");
+    assert_eq!(
+      render(template, &details, date, "jpg").unwrap(),
+      "wallhaven-ab.jpg"
+    );
+    details.resolution = Some((2560, 1440));
+    details.purity = Some("sf/w".into());
+    assert_eq!(
+      render("{date}_{purity}_{id}_{resolution}", &details, date, "").unwrap(),
+      "2025-03-01_sf_w_ab_2560x1440"
+    );
+
+    assert!(validate(template).is_ok());
+    assert!(validate("{source}-{name}.{ext}").is_err());
+    assert!(validate("{source}-{date}.{ext}").is_err());
+    assert!(validate("{id").is_err());
+
+    //{ Names that start with the source and id stay recognizable }
+    assert_eq!(
+      wallhaven::id_from_path(FilePath::new("wallhaven-ab-2560x1440.jpg")),
+      Some("ab".to_string())
+    );
+  }
+}
@@ -0,0 +1,121 @@
+//! Publishes what is on screen for other programs to read.
+//!
+//! Every time a wallpaper is set, `now-showing.json` in the state directory is
+//! rewritten with the image shown on each monitor and where it came from, so
+//! that desktop widgets, stream overlays and screenshot tools can credit the
+//! artwork. The file is replaced atomically and is only ever read by others.
+
+use super::Source;
+use crate::{Result, api::wallhaven, config::Path, utils::store};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the now-showing file within the state directory.
+const NOW_SHOWING_FILE: &str = "now-showing.json";
+
+/// The image shown on a monitor and its origin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Showing {
+  /// The original image on disk.
+  pub image: PathBuf,
+  /// The image's file name, for display.
+  pub name: String,
+  /// When the image was applied.
+  pub since: DateTime<Local>,
+  /// The address the image was downloaded from, if it was set from a URL.
+  pub url: Option<String>,
+  /// The Wallhaven id, if the image came from Wallhaven.
+  pub wallhaven_id: Option<String>,
+  /// The page crediting the artwork, if known.
+  pub page: Option<String>
+}
+
+impl Showing {
+  /// Describes `image`, set from `source`.
+  pub fn new(image: PathBuf, source: &Source) -> Self {
+    let wallhaven_id = wallhaven::id_from_path(&image);
+    Self {
+      name: image
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default(),
+      since: Local::now(),
+      url: match source {
+        Source::Url(url) => Some(url.clone()),
+        Source::File(_) => None
+      },
+      page: wallhaven_id.as_deref().map(wallhaven::page_url),
+      wallhaven_id,
+      image
+    }
+  }
+}
+
+/// The images on screen, keyed by monitor name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NowShowing {
+  /// When the file was last written.
+  pub updated_at: DateTime<Local>,
+  pub monitors: BTreeMap<String, Showing>
+}
+
+impl Default for NowShowing {
+  fn default() -> Self {
+    Self {
+      updated_at: Local::now(),
+      monitors: BTreeMap::new()
+    }
+  }
+}
+
+impl NowShowing {
+  /// Returns the path of the now-showing file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(NOW_SHOWING_FILE)
+  }
+
+  /// Loads the published state from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Publishes the state to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Records `showing` on the named monitor.
+  pub fn insert(&mut self, monitor: &str, showing: Showing) {
+    self.updated_at = showing.since;
+    self.monitors.insert(monitor.to_string(), showing);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_showing_credits_wallhaven() {
+    let showing = Showing::new(
+      PathBuf::from("/downloads/wallhaven-94x38z.jpg"),
+      &Source::Url(
+        "https://w.wallhaven.cc/full/94/wallhaven-94x38z.jpg".into()
+      )
+    );
+    assert_eq!(showing.name, "wallhaven-94x38z.jpg");
+    assert_eq!(showing.wallhaven_id.as_deref(), Some("94x38z"));
+    assert_eq!(
+      showing.page.as_deref(),
+      Some("https://wallhaven.cc/w/94x38z")
+    );
+    assert!(showing.url.is_some());
+
+    let local = Showing::new(
+      PathBuf::from("forest.png"),
+      &Source::File("forest.png".into())
+    );
+    assert_eq!((local.url, local.page), (None, None));
+  }
+}
@@ -0,0 +1,220 @@
+//! Settles what to do with monitors the config remembers but that are no
+//! longer connected, such as ones renamed by a driver update or unplugged.
+//!
+//! Their paths stay in the config until a [Resolution] is chosen for them,
+//! so nothing is lost by starting once without a monitor. A missing monitor
+//! can be migrated to a connected one, which takes over its history and, if
+//! it shows nothing yet, its wallpaper; archived, which keeps its history and
+//! wallpaper in the state directory but forgets the monitor; or deleted.
+
+use super::{Applied, Current, History, NowShowing, history::Entry};
+use crate::{
+  Config, Error, Result,
+  config::{Path, path::MonitorPaths},
+  simulate::{self, Action},
+  utils::store
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display, Formatter},
+  fs,
+  path::PathBuf,
+  str::FromStr
+};
+
+/// The name of the archive of missing monitors within the state directory.
+const ARCHIVE_FILE: &str = "archived-monitors.json";
+
+/// What to do with a monitor that is no longer connected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+  /// Leave it be, in case it comes back.
+  Keep,
+  /// Hand its history and wallpaper to the named monitor.
+  Migrate(String),
+  /// Move its history and wallpaper to the archive.
+  Archive,
+  /// Forget it along with its history and wallpaper.
+  Delete
+}
+
+impl FromStr for Resolution {
+  type Err = Error;
+
+  /// Parses `keep`, `archive`, `delete` or `migrate:<monitor>`.
+  fn from_str(s: &str) -> Result<Self> {
+    match s.split_once(':') {
+      Some(("migrate", target)) if !target.is_empty() =>
+        Ok(Self::Migrate(target.to_string())),
+      None if s == "keep" => Ok(Self::Keep),
+      None if s == "archive" => Ok(Self::Archive),
+      None if s == "delete" => Ok(Self::Delete),
+      _ => Err(Error::Config(format!(
+        "Invalid resolution '{s}'; use keep, archive, delete or migrate:<monitor>"
+      )))
+    }
+  }
+}
+
+impl Display for Resolution {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Keep => write!(f, "keep"),
+      Self::Migrate(target) => write!(f, "migrate:{target}"),
+      Self::Archive => write!(f, "archive"),
+      Self::Delete => write!(f, "delete")
+    }
+  }
+}
+
+/// What was kept of a monitor that went missing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archived {
+  pub archived_at: DateTime<Local>,
+  pub paths: MonitorPaths,
+  /// The wallpaper it last showed.
+  pub applied: Option<Applied>,
+  pub history: Vec<Entry>
+}
+
+/// Archived monitors, by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archive {
+  pub monitors: BTreeMap<String, Archived>
+}
+
+impl Archive {
+  /// Returns the path of the archive.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(ARCHIVE_FILE)
+  }
+
+  /// Loads the archive from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the archive to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+}
+
+/// Applies `resolution` to the missing monitor called `name`, updating the
+/// state files and `config`. The caller saves the config.
+pub fn resolve(
+  config: &mut Config,
+  name: &str,
+  resolution: &Resolution
+) -> Result<()> {
+  let Some(index) = config
+    .path
+    .monitor_paths
+    .iter()
+    .position(|p| p.name == name)
+  else {
+    return Err(Error::Config(format!("No monitor named '{name}' is saved")));
+  };
+  if let Resolution::Migrate(target) = resolution {
+    if !config.monitors.iter().any(|m| &m.name == target) {
+      return Err(Error::Config(format!(
+        "Cannot migrate to '{target}': no such monitor is connected"
+      )));
+    }
+  }
+  if *resolution == Resolution::Keep {
+    return Ok(());
+  }
+
+  let path_config = config.path.clone();
+  let mut current = Current::load(&path_config)?;
+  let mut history = History::load(&path_config)?;
+  let mut showing = NowShowing::load(&path_config)?;
+  let applied = current.monitors.remove(name);
+  showing.monitors.remove(name);
+
+  match resolution {
+    Resolution::Migrate(target) => {
+      for entry in history.entries.iter_mut().filter(|e| e.monitor == name) {
+        entry.monitor = target.clone();
+      }
+      if let Some(applied) = applied {
+        current.monitors.entry(target.clone()).or_insert(applied);
+      }
+    }
+    Resolution::Archive => {
+      let (kept, left): (Vec<Entry>, Vec<Entry>) =
+        history.entries.drain(..).partition(|e| e.monitor == name);
+      history.entries = left;
+      let mut archive = Archive::load(&path_config)?;
+      archive.monitors.insert(
+        name.to_string(),
+        Archived {
+          archived_at: Local::now(),
+          paths: config.path.monitor_paths[index].clone(),
+          applied,
+          history: kept
+        }
+      );
+      archive.save(&path_config)?;
+    }
+    Resolution::Delete | Resolution::Keep =>
+      history.entries.retain(|e| e.monitor != name),
+  }
+  current.save(&path_config)?;
+  history.save(&path_config)?;
+  showing.save(&path_config)?;
+
+  //{ The copy installed for the monitor is no longer needed }
+  let installed = config.path.monitor_paths.remove(index).current_wallpaper;
+  if installed.is_file()
+    && !simulate::dry_run(Action::RemoveFile {
+      path: installed.clone()
+    })?
+  {
+    fs::remove_file(&installed)?;
+  }
+
+  //{ Settings naming the monitor follow it or forget it }
+  let renamed = match resolution {
+    Resolution::Migrate(target) => Some(target.as_str()),
+    _ => None
+  };
+  let night = &mut config.color.night_light.monitors;
+  match renamed {
+    Some(target) => night
+      .iter_mut()
+      .filter(|monitor| *monitor == name)
+      .for_each(|monitor| *monitor = target.to_string()),
+    None => night.retain(|monitor| monitor != name)
+  }
+  let mirror = &mut config.slideshow.mirror.monitors;
+  match renamed {
+    Some(target) => mirror
+      .values_mut()
+      .filter(|local| *local == name)
+      .for_each(|local| *local = target.to_string()),
+    None => mirror.retain(|_, local| local != name)
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_resolution() {
+    for text in ["keep", "archive", "delete", "migrate:DP-3"] {
+      assert_eq!(text.parse::<Resolution>().unwrap().to_string(), text);
+    }
+    assert_eq!(
+      "migrate:HDMI-A-1".parse::<Resolution>().unwrap(),
+      Resolution::Migrate("HDMI-A-1".into())
+    );
+    assert!("migrate:".parse::<Resolution>().is_err());
+    assert!("move".parse::<Resolution>().is_err());
+  }
+}
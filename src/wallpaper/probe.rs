@@ -0,0 +1,202 @@
+//! Remembers the dimensions and content hash of imported images.
+//!
+//! Sorting an import into ratio buckets needs every image's dimensions, and
+//! spotting duplicates needs its content hash. Dimensions come from the
+//! image header alone, and images are probed on several threads at once.
+//! Results are cached in the state directory by path, and reused while the
+//! file's size and modification time are unchanged, so importing the same
+//! folder again reads next to nothing.
+
+use crate::{
+  Result,
+  config::{Path, wallpaper::Limits},
+  utils::{files, limits, store}
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path as FilePath, PathBuf},
+  thread,
+  time::SystemTime
+};
+use tracing::debug;
+
+/// The name of the probe cache within the state directory.
+const PROBES_FILE: &str = "probes.json";
+
+/// What probing an image found out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+  /// The file size in bytes when it was probed.
+  pub len: u64,
+  /// The modification time when it was probed.
+  pub modified: SystemTime,
+  pub width: u32,
+  pub height: u32,
+  /// The content hash, as used by the library index.
+  pub hash: String
+}
+
+impl Probe {
+  /// Reads the dimensions and content hash of `image`, checking it against
+  /// `limits` first.
+  pub fn read(image: &FilePath, limits: &Limits) -> Result<Self> {
+    let metadata = fs::metadata(image)?;
+    let (width, height) = limits::check(image, limits)?;
+    Ok(Self {
+      len: metadata.len(),
+      modified: metadata.modified()?,
+      width,
+      height,
+      hash: format!("{:016x}", files::content_hash(image)?)
+    })
+  }
+
+  /// Whether the probe still describes a file with this size and
+  /// modification time.
+  fn matches(&self, len: u64, modified: SystemTime) -> bool {
+    self.len == len && self.modified == modified
+  }
+}
+
+/// Probes of images by path, persisted in the state directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probes {
+  pub images: BTreeMap<PathBuf, Probe>
+}
+
+impl Probes {
+  /// Returns the path of the probe cache.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(PROBES_FILE)
+  }
+
+  /// Loads the probe cache from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the probe cache to the state directory, dropping entries for
+  /// files that no longer exist.
+  pub fn save(&mut self, path_config: &Path) -> Result<()> {
+    self.images.retain(|image, _| image.is_file());
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns the cached probe of `image` if the file is unchanged since,
+  /// checked against `limits` in case they were tightened.
+  fn cached(&self, image: &FilePath, limits: &Limits) -> Option<Result<Probe>> {
+    let metadata = fs::metadata(image).ok()?;
+    let modified = metadata.modified().ok()?;
+    let probe = self
+      .images
+      .get(image)
+      .filter(|probe| probe.matches(metadata.len(), modified))?;
+    let checked =
+      limits::check_file_size(image, probe.len, limits).and_then(|()| {
+        limits::check_dimensions(image, (probe.width, probe.height), limits)
+      });
+    Some(checked.map(|()| probe.clone()))
+  }
+
+  /// Probes every image, in order. Cached probes are reused, and the rest
+  /// are read on as many threads as there are cores.
+  pub fn probe_all(
+    &mut self,
+    images: &[PathBuf],
+    limits: &Limits
+  ) -> Vec<Result<Probe>> {
+    let mut results: Vec<Option<Result<Probe>>> = images
+      .iter()
+      .map(|image| self.cached(image, limits))
+      .collect();
+    let pending: Vec<usize> = results
+      .iter()
+      .enumerate()
+      .filter(|(_, result)| result.is_none())
+      .map(|(index, _)| index)
+      .collect();
+    debug!(
+      "Probing {} of {} images; the rest are cached",
+      pending.len(),
+      images.len()
+    );
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = pending.len().div_ceil(threads).max(1);
+    let probed: Vec<(usize, Result<Probe>)> = thread::scope(|scope| {
+      let workers: Vec<_> = pending
+        .chunks(chunk)
+        .map(|indices| {
+          scope.spawn(move || {
+            indices
+              .iter()
+              .map(|&index| (index, Probe::read(&images[index], limits)))
+              .collect::<Vec<_>>()
+          })
+        })
+        .collect();
+      workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap_or_default())
+        .collect()
+    });
+
+    for (index, result) in probed {
+      if let Ok(probe) = &result {
+        self.images.insert(images[index].clone(), probe.clone());
+      }
+      results[index] = Some(result);
+    }
+    results.into_iter().flatten().collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::RgbImage;
+
+  #[test]
+  fn test_probe_all() {
+    let dir = std::env::temp_dir().join("wallter-probe-test");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let images: Vec<PathBuf> = (1..=5)
+      .map(|n| {
+        let image = dir.join(format!("{n}.png"));
+        RgbImage::new(16 * n, 9 * n).save(&image).unwrap();
+        image
+      })
+      .collect();
+
+    let limits = Limits::default();
+    let mut probes = Probes::default();
+    let results = probes.probe_all(&images, &limits);
+    let sizes: Vec<(u32, u32)> = results
+      .iter()
+      .map(|result| {
+        let probe = result.as_ref().unwrap();
+        (probe.width, probe.height)
+      })
+      .collect();
+    assert_eq!(sizes, [(16, 9), (32, 18), (48, 27), (64, 36), (80, 45)]);
+    assert_eq!(probes.images.len(), 5);
+
+    //{ Cached probes are still held to the limits }
+    let strict = Limits {
+      max_pixels: 1000,
+      ..limits
+    };
+    let results = probes.probe_all(&images, &strict);
+    assert!(results[0].is_ok() && results[4].is_err());
+
+    //{ A changed file is probed again }
+    let mut stale = probes.images[&images[0]].clone();
+    stale.len += 1;
+    probes.images.insert(images[0].clone(), stale);
+    assert!(probes.cached(&images[0], &limits).is_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
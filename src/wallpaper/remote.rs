@@ -0,0 +1,168 @@
+//! Applies wallpapers on other machines over SSH.
+//!
+//! The image is copied into `~/.cache/wallter` on the remote host with `scp`
+//! and applied there by the remote `wallter` if it is installed, falling back
+//! to the desktop environment's own tools otherwise. Authentication is left
+//! to the user's SSH setup; password prompts are disabled so that a missing
+//! key fails fast instead of hanging.
+
+use crate::{
+  Error, Result,
+  simulate::{self, Action}
+};
+use std::{path::Path, process::Command};
+
+/// The directory, relative to the remote home, that receives the images.
+const REMOTE_DIR: &str = ".cache/wallter";
+
+/// Copies `image` to `host` and applies it there, optionally only on the
+/// remote monitor matching `monitor`.
+///
+/// Returns the path of the image on the remote host.
+pub fn set(host: &str, image: &Path, monitor: Option<&str>) -> Result<String> {
+  //{ A host such as `-oProxyCommand=...` would be read as an option }
+  if host.is_empty() || host.starts_with('-') {
+    return Err(Error::Settings(format!("Invalid SSH host: '{host}'")));
+  }
+  let name = image
+    .file_name()
+    .and_then(|name| name.to_str())
+    .ok_or_else(|| {
+      Error::Wallpaper(format!("Invalid image path: {}", image.display()))
+    })?;
+  let remote = format!("{REMOTE_DIR}/{}", remote_name(name));
+
+  if simulate::record(Action::RemoteWallpaper {
+    host: host.to_string(),
+    path: image.to_path_buf()
+  })? {
+    return Ok(remote);
+  }
+
+  //{ A relative path with a colon in it would be read as `host:path` }
+  let local = if image.is_relative() {
+    Path::new(".").join(image)
+  } else {
+    image.to_path_buf()
+  };
+  ssh(host, &format!("mkdir -p {REMOTE_DIR}"))?;
+  let status = Command::new("scp")
+    .args(["-q", "-o", "BatchMode=yes"])
+    .arg(local)
+    .arg(format!("{host}:{remote}"))
+    .status()
+    .map_err(|e| {
+      Error::Wallpaper(format!("SSH: Failed to execute scp: {e}"))
+    })?;
+  if !status.success() {
+    return Err(Error::Wallpaper(format!(
+      "SSH: Failed to copy the image to '{host}'"
+    )));
+  }
+
+  ssh(host, &script(&remote, monitor))?;
+  Ok(remote)
+}
+
+/// Runs `command` through the remote shell on `host`.
+fn ssh(host: &str, command: &str) -> Result<()> {
+  let status = Command::new("ssh")
+    .args(["-o", "BatchMode=yes", host, command])
+    .status()
+    .map_err(|e| {
+      Error::Wallpaper(format!("SSH: Failed to execute ssh: {e}"))
+    })?;
+  if !status.success() {
+    return Err(Error::Wallpaper(format!(
+      "SSH: Command failed on '{host}': {command}"
+    )));
+  }
+  Ok(())
+}
+
+/// Builds the remote shell script that applies the image at `remote`, which
+/// is relative to the remote home directory.
+fn script(remote: &str, monitor: Option<&str>) -> String {
+  let monitor = match monitor {
+    Some(monitor) => format!(" --monitor {}", quote(monitor)),
+    None => String::new()
+  };
+  format!(
+    r#"f="$HOME"/{file}
+uid=$(id -u)
+export DISPLAY="${{DISPLAY:-:0}}"
+export XDG_RUNTIME_DIR="${{XDG_RUNTIME_DIR:-/run/user/$uid}}"
+export DBUS_SESSION_BUS_ADDRESS="${{DBUS_SESSION_BUS_ADDRESS:-unix:path=$XDG_RUNTIME_DIR/bus}}"
+if command -v wallter >/dev/null 2>&1; then
+  wallter set "$f"{monitor}
+elif command -v plasma-apply-wallpaperimage >/dev/null 2>&1; then
+  plasma-apply-wallpaperimage "$f"
+elif command -v gsettings >/dev/null 2>&1; then
+  gsettings set org.gnome.desktop.background picture-uri "file://$f" &&
+  gsettings set org.gnome.desktop.background picture-uri-dark "file://$f"
+elif command -v feh >/dev/null 2>&1; then
+  feh --bg-fill "$f"
+else
+  echo "No supported wallpaper tool found" >&2
+  exit 1
+fi"#,
+    file = quote(remote)
+  )
+}
+
+/// Returns `name` with anything but letters, digits, `.`, `_` and `-`
+/// replaced, so that it reaches the remote host unchanged whether `scp`
+/// goes through the remote shell or SFTP.
+fn remote_name(name: &str) -> String {
+  let name: String = name
+    .chars()
+    .map(|c| match c {
+      'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' | '-' => c,
+      _ => '_'
+    })
+    .collect();
+  match name.strip_prefix(['-', '.']) {
+    Some(rest) => format!("_{rest}"),
+    None => name
+  }
+}
+
+/// Quotes `value` for a POSIX shell.
+fn quote(value: &str) -> String {
+  format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quote() {
+    assert_eq!(quote("forest.jpg"), "'forest.jpg'");
+    assert_eq!(quote("it's here.png"), r"'it'\''s here.png'");
+  }
+
+  #[test]
+  fn test_remote_name() {
+    assert_eq!(remote_name("forest.jpg"), "forest.jpg");
+    assert_eq!(remote_name("it's a $(view).png"), "it_s_a___view_.png");
+    assert_eq!(remote_name("-rf.jpg"), "_rf.jpg");
+    assert_eq!(remote_name(".hidden.png"), "_hidden.png");
+  }
+
+  #[test]
+  fn test_script_targets_monitor() {
+    let script = script(".cache/wallter/a b.jpg", Some("DP-1"));
+    assert!(script.starts_with(r#"f="$HOME"/'.cache/wallter/a b.jpg'"#));
+    assert!(script.contains(r#"wallter set "$f" --monitor 'DP-1'"#));
+  }
+
+  #[test]
+  fn test_option_hosts_are_rejected() {
+    let image = Path::new("forest.jpg");
+    assert!(matches!(
+      set("-oProxyCommand=touch pwned", image, None),
+      Err(Error::Settings(_))
+    ));
+  }
+}
@@ -0,0 +1,79 @@
+//! Named snapshots of the desktop: the wallpaper on each monitor and the
+//! system color mode, saved to be brought back exactly later.
+
+use super::Current;
+use crate::{
+  Config, Error, Result,
+  config::{ColorMode, Path, conflicts::Subsystem},
+  utils::store
+};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The name of the snapshots file within the state directory.
+const SNAPSHOTS_FILE: &str = "snapshots.json";
+
+/// The desktop as it was when the snapshot was saved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+  pub wallpapers: Current,
+  /// The system color mode, never `Auto`.
+  pub color_mode: ColorMode,
+  pub saved_at: DateTime<Local>
+}
+
+impl Snapshot {
+  /// Captures the wallpapers and color mode shown now.
+  pub fn capture(config: &Config) -> Result<Self> {
+    Ok(Self {
+      wallpapers: Current::load(&config.path)?,
+      color_mode: ColorMode::get_current(),
+      saved_at: Local::now()
+    })
+  }
+
+  /// Reapplies the snapshot. Returns the monitors it had no wallpaper for.
+  pub async fn restore(&self, config: &Config) -> Result<Vec<String>> {
+    let missing = super::restore(config, &self.wallpapers, None).await?;
+    if config.conflicts.yield_to(Subsystem::ColorMode).is_none() {
+      config
+        .color
+        .clone()
+        .with_mode(self.color_mode)
+        .apply_mode(&config.monitors)?;
+    }
+    Ok(missing)
+  }
+}
+
+/// The saved snapshots, by name.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshots {
+  pub saved: BTreeMap<String, Snapshot>
+}
+
+impl Snapshots {
+  /// Returns the path of the snapshots file.
+  pub fn file(path_config: &Path) -> PathBuf {
+    path_config.state_dir.join(SNAPSHOTS_FILE)
+  }
+
+  /// Loads the snapshots from the state directory.
+  pub fn load(path_config: &Path) -> Result<Self> {
+    store::load(&Self::file(path_config))
+  }
+
+  /// Saves the snapshots to the state directory.
+  pub fn save(&self, path_config: &Path) -> Result<()> {
+    store::save(&Self::file(path_config), self)
+  }
+
+  /// Returns the snapshot with the given name.
+  pub fn get(&self, name: &str) -> Result<&Snapshot> {
+    self
+      .saved
+      .get(name)
+      .ok_or_else(|| Error::Config(format!("No snapshot named '{name}'")))
+  }
+}
@@ -0,0 +1,231 @@
+//! Small previews of library images, cached on disk.
+//!
+//! Thumbnails are stored in `cache_dir/thumbnails`, named after a hash of the
+//! image's contents, so renamed or duplicated images share one thumbnail and
+//! edited ones get a fresh one. [generate] fills the cache for many images on
+//! a pool of worker threads. Whenever the cache grows past
+//! `wallpaper.thumbnails.max_cache_size`, the least recently used thumbnails
+//! are removed.
+
+use crate::{
+  Config, Result,
+  config::wallpaper::Thumbnails,
+  simulate::{self, Action},
+  utils::{files, limits}
+};
+use image::{ImageFormat, imageops::FilterType};
+use std::{
+  fs::{self, File},
+  path::{Path, PathBuf},
+  sync::{
+    Mutex,
+    atomic::{AtomicUsize, Ordering}
+  },
+  thread,
+  time::SystemTime
+};
+
+/// The name of the thumbnail directory within the cache directory.
+const THUMBNAIL_DIR: &str = "thumbnails";
+
+/// The most worker threads used by [generate].
+const MAX_WORKERS: usize = 8;
+
+/// What [generate] did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+  /// Thumbnails that were created.
+  pub created: usize,
+  /// Thumbnails that were already cached.
+  pub cached: usize,
+  /// Images that couldn't be read, with the reason.
+  pub failed: Vec<(PathBuf, String)>,
+  /// Thumbnails evicted to stay within the cache size.
+  pub evicted: usize
+}
+
+/// Returns the thumbnail directory.
+pub fn dir(config: &Config) -> PathBuf {
+  config.path.cache_dir.join(THUMBNAIL_DIR)
+}
+
+/// Returns every image in the library: downloads and favorites.
+pub fn library(config: &Config) -> Result<Vec<PathBuf>> {
+  let mut images = files::list_images(&config.path.downloads_dir, true)?;
+  if config.path.favorites_dir.is_dir() {
+    images.extend(files::list_images(&config.path.favorites_dir, true)?);
+  }
+  Ok(images)
+}
+
+/// Returns the thumbnail for `image`, creating it if it isn't cached.
+pub fn get(config: &Config, image: &Path) -> Result<PathBuf> {
+  let (thumbnail, created) = thumbnail(config, image)?;
+  if created {
+    evict(config)?;
+  }
+  Ok(thumbnail)
+}
+
+/// Creates any missing thumbnails for `images` on a pool of worker threads,
+/// then trims the cache. Images that fail are reported, not returned as
+/// errors.
+pub fn generate(config: &Config, images: &[PathBuf]) -> Result<Report> {
+  let next = AtomicUsize::new(0);
+  let report = Mutex::new(Report::default());
+  let workers = thread::available_parallelism()
+    .map_or(1, usize::from)
+    .min(MAX_WORKERS)
+    .min(images.len().max(1));
+
+  thread::scope(|scope| {
+    for _ in 0..workers {
+      scope.spawn(|| {
+        while let Some(image) = images.get(next.fetch_add(1, Ordering::Relaxed))
+        {
+          let result = thumbnail(config, image);
+          let mut report = report.lock().unwrap_or_else(|e| e.into_inner());
+          match result {
+            Ok((_, true)) => report.created += 1,
+            Ok((_, false)) => report.cached += 1,
+            Err(e) => report.failed.push((image.clone(), e.to_string()))
+          }
+        }
+      });
+    }
+  });
+
+  let mut report = report.into_inner().unwrap_or_else(|e| e.into_inner());
+  report.evicted = evict(config)?;
+  Ok(report)
+}
+
+/// Removes the least recently used thumbnails until the cache fits within
+/// the configured size. Returns how many were removed.
+pub fn evict(config: &Config) -> Result<usize> {
+  trim(config, max_bytes(&config.wallpaper.thumbnails))
+}
+
+/// Removes every thumbnail. Returns how many were removed.
+pub fn clear(config: &Config) -> Result<usize> {
+  trim(config, 0)
+}
+
+/// Removes the least recently used thumbnails until at most `max` bytes
+/// remain.
+fn trim(config: &Config, max: u64) -> Result<usize> {
+  let dir = dir(config);
+  if !dir.is_dir() {
+    return Ok(0);
+  }
+  let mut entries: Vec<(PathBuf, u64, SystemTime)> = fs::read_dir(&dir)?
+    .filter_map(|entry| {
+      let entry = entry.ok()?;
+      let metadata = entry.metadata().ok()?;
+      let used = metadata.modified().ok()?;
+      metadata
+        .is_file()
+        .then(|| (entry.path(), metadata.len(), used))
+    })
+    .collect();
+
+  let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+  entries.sort_by_key(|(_, _, used)| *used);
+
+  let mut evicted = 0;
+  for (path, size, _) in entries {
+    if total <= max {
+      break;
+    }
+    if !simulate::dry_run(Action::RemoveFile { path: path.clone() })? {
+      fs::remove_file(&path)?;
+    }
+    total -= size;
+    evicted += 1;
+  }
+  Ok(evicted)
+}
+
+/// Returns the thumbnail for `image` and whether it had to be created.
+/// Cached thumbnails are marked as used.
+fn thumbnail(config: &Config, image: &Path) -> Result<(PathBuf, bool)> {
+  let settings = &config.wallpaper.thumbnails;
+  let path = dir(config).join(format!(
+    "{:016x}-{}.jpg",
+    files::content_hash(image)?,
+    settings.size
+  ));
+
+  if path.is_file() {
+    //{ The modification time doubles as the last use, for eviction }
+    let _ = File::options()
+      .append(true)
+      .open(&path)
+      .and_then(|file| file.set_modified(SystemTime::now()));
+    return Ok((path, false));
+  }
+
+  let decoded = limits::open(image, &config.wallpaper.limits)?;
+  if simulate::dry_run(Action::WriteFile { path: path.clone() })? {
+    return Ok((path, true));
+  }
+  let small = decoded
+    .resize(settings.size, settings.size, FilterType::Triangle)
+    .into_rgb8();
+  fs::create_dir_all(dir(config))?;
+
+  //{ Write to a temporary file first so that concurrent readers never see a
+  //{ partial thumbnail }
+  let temp = path.with_extension(format!("{:?}.tmp", thread::current().id()));
+  small
+    .save_with_format(&temp, ImageFormat::Jpeg)
+    .map_err(|e| crate::Error::Image(e.to_string()))?;
+  fs::rename(&temp, &path)?;
+  Ok((path, true))
+}
+
+fn max_bytes(settings: &Thumbnails) -> u64 {
+  settings.max_cache_size.saturating_mul(1024 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::RgbImage;
+
+  #[test]
+  fn test_thumbnails() {
+    let root = std::env::temp_dir().join("wallter-thumbnails-test");
+    let _ = fs::remove_dir_all(&root);
+    let mut config = Config::default();
+    config.path.downloads_dir = root.join("downloads");
+    config.path.favorites_dir = root.join("favorites");
+    config.path.cache_dir = root.join("cache");
+    fs::create_dir_all(&config.path.downloads_dir).unwrap();
+
+    let images: Vec<PathBuf> = (0..3u8)
+      .map(|i| {
+        let path = config.path.downloads_dir.join(format!("{i}.png"));
+        RgbImage::from_pixel(640, 400, image::Rgb([i, 0, 0]))
+          .save(&path)
+          .unwrap();
+        path
+      })
+      .collect();
+    //{ A copy shares the thumbnail of its original }
+    let copy = config.path.downloads_dir.join("copy.png");
+    fs::copy(&images[0], &copy).unwrap();
+
+    let report = generate(&config, &library(&config).unwrap()).unwrap();
+    assert_eq!(report.created + report.cached, 4);
+    assert_eq!(fs::read_dir(dir(&config)).unwrap().count(), 3);
+
+    let thumbnail = get(&config, &copy).unwrap();
+    let (width, height) = image::image_dimensions(&thumbnail).unwrap();
+    assert_eq!((width, height), (320, 200));
+
+    config.wallpaper.thumbnails.max_cache_size = 0;
+    assert_eq!(evict(&config).unwrap(), 3);
+    let _ = fs::remove_dir_all(&root);
+  }
+}
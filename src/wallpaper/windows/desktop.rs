@@ -0,0 +1,247 @@
+//! Sets per-monitor wallpapers on Windows through the `IDesktopWallpaper` COM
+//! interface.
+//!
+//! The interface identifies monitors by device path. These are matched back
+//! to the entries in [Monitor] through the device paths of the display each
+//! is named after, such as `DISPLAY1`, falling back to their position in the
+//! virtual screen.
+
+#![allow(unsafe_code)]
+
+use super::super::Setter as WallpaperSetter;
+use crate::{Error, Result, config::Monitor};
+use std::{
+  ffi::{OsStr, OsString},
+  os::windows::ffi::{OsStrExt, OsStringExt},
+  path::{Path, PathBuf},
+  ptr
+};
+use winapi::{
+  Interface,
+  shared::{
+    minwindef::{DWORD, UINT},
+    windef::RECT,
+    winerror::FAILED
+  },
+  um::{
+    combaseapi::{
+      CLSCTX_ALL, CoCreateInstance, CoInitializeEx, CoTaskMemFree,
+      CoUninitialize
+    },
+    objbase::COINIT_APARTMENTTHREADED,
+    shobjidl_core::{CLSID_DesktopWallpaper, IDesktopWallpaper},
+    wingdi::DISPLAY_DEVICEW,
+    winnt::{HRESULT, LPWSTR},
+    winuser::{EDD_GET_DEVICE_INTERFACE_NAME, EnumDisplayDevicesW}
+  }
+};
+
+/// A wallpaper backend using `IDesktopWallpaper`, which supports a different
+/// image on each monitor.
+pub struct Setter;
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "windows-desktop-wallpaper"
+  }
+
+  fn set(&self, path: &Path, monitor: &Monitor) -> Result<()> {
+    let desktop = DesktopWallpaper::new()?;
+    let id = desktop.monitor_id(monitor)?;
+    desktop.set(&id, path)
+  }
+
+  fn get_current(&self, monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let desktop = DesktopWallpaper::new()?;
+    let id = desktop.monitor_id(monitor)?;
+    desktop.get(&id)
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    true
+  }
+}
+
+/// An `IDesktopWallpaper` instance within an initialized COM apartment, both
+/// released on drop.
+struct DesktopWallpaper {
+  ptr: *mut IDesktopWallpaper,
+  uninitialize: bool
+}
+
+impl DesktopWallpaper {
+  fn new() -> Result<Self> {
+    //{ A negative result means COM is already set up in another mode }
+    let hr =
+      unsafe { CoInitializeEx(ptr::null_mut(), COINIT_APARTMENTTHREADED) };
+    let uninitialize = hr >= 0;
+
+    let mut ptr: *mut IDesktopWallpaper = ptr::null_mut();
+    let hr = unsafe {
+      CoCreateInstance(
+        &CLSID_DesktopWallpaper,
+        ptr::null_mut(),
+        CLSCTX_ALL,
+        &IDesktopWallpaper::uuidof(),
+        &mut ptr as *mut *mut IDesktopWallpaper as *mut _
+      )
+    };
+    if FAILED(hr) || ptr.is_null() {
+      if uninitialize {
+        unsafe { CoUninitialize() };
+      }
+      return Err(com_error("create the desktop wallpaper instance", hr));
+    }
+
+    Ok(Self { ptr, uninitialize })
+  }
+
+  /// Lists the device path and screen rectangle of each attached monitor.
+  fn monitors(&self) -> Result<Vec<(Vec<u16>, RECT)>> {
+    let mut count: UINT = 0;
+    let hr = unsafe { (*self.ptr).GetMonitorDevicePathCount(&mut count) };
+    if FAILED(hr) {
+      return Err(com_error("count monitors", hr));
+    }
+
+    let mut monitors = Vec::new();
+    for index in 0..count {
+      let mut raw: LPWSTR = ptr::null_mut();
+      let hr = unsafe { (*self.ptr).GetMonitorDevicePathAt(index, &mut raw) };
+      if FAILED(hr) || raw.is_null() {
+        continue;
+      }
+      let id = unsafe { take_wide(raw) };
+
+      let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: 0,
+        bottom: 0
+      };
+      //{ Detached monitors have no rectangle and cannot show a wallpaper }
+      let hr = unsafe { (*self.ptr).GetMonitorRECT(id.as_ptr(), &mut rect) };
+      if !FAILED(hr) {
+        monitors.push((id, rect));
+      }
+    }
+    Ok(monitors)
+  }
+
+  /// Finds the device path of `monitor`, matching the display it is named
+  /// after first and its position second. Positions only agree when the
+  /// process is DPI aware, as winit makes it while detecting monitors.
+  fn monitor_id(&self, monitor: &Monitor) -> Result<Vec<u16>> {
+    let monitors = self.monitors()?;
+    let paths = device_paths(&monitor.name);
+    let by_path = monitors
+      .iter()
+      .position(|(id, _)| paths.contains(&path_key(id)));
+    by_path
+      .or_else(|| {
+        monitors.iter().position(|(_, rect)| {
+          rect.left == monitor.position.x && rect.top == monitor.position.y
+        })
+      })
+      .or_else(|| (monitors.len() == 1).then_some(0))
+      .map(|index| monitors[index].0.clone())
+      .ok_or_else(|| {
+        Error::Wallpaper(format!(
+          "Windows: No desktop wallpaper target matches monitor '{}'",
+          monitor.name
+        ))
+      })
+  }
+
+  fn set(&self, id: &[u16], path: &Path) -> Result<()> {
+    let path = wide(path.as_os_str());
+    let hr = unsafe { (*self.ptr).SetWallpaper(id.as_ptr(), path.as_ptr()) };
+    if FAILED(hr) {
+      return Err(com_error("set the wallpaper", hr));
+    }
+    Ok(())
+  }
+
+  fn get(&self, id: &[u16]) -> Result<Option<PathBuf>> {
+    let mut raw: LPWSTR = ptr::null_mut();
+    let hr = unsafe { (*self.ptr).GetWallpaper(id.as_ptr(), &mut raw) };
+    if FAILED(hr) || raw.is_null() {
+      return Err(com_error("read the wallpaper", hr));
+    }
+    let path = unsafe { take_wide(raw) };
+    let path = OsString::from_wide(&path[..path.len() - 1]);
+    Ok((!path.is_empty()).then(|| PathBuf::from(path)))
+  }
+}
+
+impl Drop for DesktopWallpaper {
+  fn drop(&mut self) {
+    unsafe {
+      (*self.ptr).Release();
+      if self.uninitialize {
+        CoUninitialize();
+      }
+    }
+  }
+}
+
+/// Encodes a string as a null-terminated UTF-16 buffer.
+fn wide(value: &OsStr) -> Vec<u16> {
+  value.encode_wide().chain(Some(0)).collect()
+}
+
+/// Returns the device paths of the monitors attached to the display called
+/// `name`, such as `DISPLAY1`, as [path_key]s.
+fn device_paths(name: &str) -> Vec<String> {
+  let device = wide(OsStr::new(&format!(r"\\.\{name}")));
+  let mut paths = Vec::new();
+  for index in 0.. {
+    let mut info: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+    info.cb = size_of::<DISPLAY_DEVICEW>() as DWORD;
+    let found = unsafe {
+      EnumDisplayDevicesW(
+        device.as_ptr(),
+        index,
+        &mut info,
+        EDD_GET_DEVICE_INTERFACE_NAME
+      )
+    };
+    if found == 0 {
+      break;
+    }
+    paths.push(path_key(&info.DeviceID));
+  }
+  paths
+}
+
+/// Normalizes a UTF-16 device path, up to any terminator, for comparison.
+fn path_key(path: &[u16]) -> String {
+  let len = path.iter().position(|c| *c == 0).unwrap_or(path.len());
+  String::from_utf16_lossy(&path[..len]).to_lowercase()
+}
+
+/// Copies a COM-allocated, null-terminated UTF-16 string (including the
+/// terminator) and frees the original.
+///
+/// # Safety
+///
+/// `raw` must point to a null-terminated UTF-16 string allocated with
+/// `CoTaskMemAlloc`, which is not used again afterwards.
+unsafe fn take_wide(raw: LPWSTR) -> Vec<u16> {
+  let mut len = 0;
+  unsafe {
+    while *raw.add(len) != 0 {
+      len += 1;
+    }
+    let copy = std::slice::from_raw_parts(raw, len + 1).to_vec();
+    CoTaskMemFree(raw as *mut _);
+    copy
+  }
+}
+
+fn com_error(action: &str, hr: HRESULT) -> Error {
+  Error::Wallpaper(format!(
+    "Windows: Failed to {action} (HRESULT {:#010X})",
+    hr as u32
+  ))
+}
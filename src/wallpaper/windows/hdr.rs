@@ -0,0 +1,138 @@
+//! Detects whether a monitor is in HDR (advanced color) mode using the
+//! display configuration API.
+
+#![allow(unsafe_code)]
+
+use crate::{Error, Result, config::Monitor};
+use std::{mem::size_of, mem::zeroed, ptr};
+use winapi::{
+  shared::{basetsd::UINT32, ntdef::LONG, winerror::ERROR_SUCCESS},
+  um::wingdi::{
+    DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO,
+    DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+    DISPLAYCONFIG_DEVICE_INFO_HEADER, DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO,
+    DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE,
+    DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SOURCE_DEVICE_NAME,
+    QDC_ONLY_ACTIVE_PATHS
+  }
+};
+
+//{ Not declared by winapi 0.3 }
+#[link(name = "user32")]
+unsafe extern "system" {
+  fn GetDisplayConfigBufferSizes(
+    flags: UINT32,
+    num_paths: *mut UINT32,
+    num_modes: *mut UINT32
+  ) -> LONG;
+  fn QueryDisplayConfig(
+    flags: UINT32,
+    num_paths: *mut UINT32,
+    paths: *mut DISPLAYCONFIG_PATH_INFO,
+    num_modes: *mut UINT32,
+    modes: *mut DISPLAYCONFIG_MODE_INFO,
+    topology: *mut UINT32
+  ) -> LONG;
+  fn DisplayConfigGetDeviceInfo(
+    packet: *mut DISPLAYCONFIG_DEVICE_INFO_HEADER
+  ) -> LONG;
+}
+
+/// Checks whether HDR is enabled on `monitor`, matching its GDI device name
+/// (e.g. `\\.\DISPLAY1`) or, failing that, its desktop position.
+pub fn is_enabled(monitor: &Monitor) -> Result<bool> {
+  let (paths, modes) = query_paths()?;
+  for path in &paths {
+    let name = source_name(path)?;
+    let position = modes
+      .get(path.sourceInfo.modeInfoIdx as usize)
+      .filter(|mode| mode.infoType == DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE)
+      .map(|mode| unsafe { mode.u.sourceMode() }.position);
+    let matches = name.eq_ignore_ascii_case(&monitor.name)
+      || position.is_some_and(|p| {
+        p.x == monitor.position.x && p.y == monitor.position.y
+      });
+    if matches {
+      return advanced_color_enabled(path);
+    }
+  }
+  Ok(false)
+}
+
+/// Lists the active display paths and their modes.
+fn query_paths()
+-> Result<(Vec<DISPLAYCONFIG_PATH_INFO>, Vec<DISPLAYCONFIG_MODE_INFO>)> {
+  let (mut num_paths, mut num_modes): (UINT32, UINT32) = (0, 0);
+  check(
+    unsafe {
+      GetDisplayConfigBufferSizes(
+        QDC_ONLY_ACTIVE_PATHS,
+        &mut num_paths,
+        &mut num_modes
+      )
+    },
+    "size the display configuration"
+  )?;
+
+  let mut paths: Vec<DISPLAYCONFIG_PATH_INFO> =
+    vec![unsafe { zeroed() }; num_paths as usize];
+  let mut modes: Vec<DISPLAYCONFIG_MODE_INFO> =
+    vec![unsafe { zeroed() }; num_modes as usize];
+  check(
+    unsafe {
+      QueryDisplayConfig(
+        QDC_ONLY_ACTIVE_PATHS,
+        &mut num_paths,
+        paths.as_mut_ptr(),
+        &mut num_modes,
+        modes.as_mut_ptr(),
+        ptr::null_mut()
+      )
+    },
+    "query the display configuration"
+  )?;
+  paths.truncate(num_paths as usize);
+  modes.truncate(num_modes as usize);
+  Ok((paths, modes))
+}
+
+/// Returns the GDI device name of the path's source.
+fn source_name(path: &DISPLAYCONFIG_PATH_INFO) -> Result<String> {
+  let mut name: DISPLAYCONFIG_SOURCE_DEVICE_NAME = unsafe { zeroed() };
+  name.header._type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+  name.header.size = size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as UINT32;
+  name.header.adapterId = path.sourceInfo.adapterId;
+  name.header.id = path.sourceInfo.id;
+  check(
+    unsafe { DisplayConfigGetDeviceInfo(&mut name.header) },
+    "read the display source name"
+  )?;
+
+  let device = &name.viewGdiDeviceName;
+  let len = device.iter().position(|c| *c == 0).unwrap_or(device.len());
+  Ok(String::from_utf16_lossy(&device[..len]))
+}
+
+/// Checks whether advanced color is enabled on the path's target.
+fn advanced_color_enabled(path: &DISPLAYCONFIG_PATH_INFO) -> Result<bool> {
+  let mut info: DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO = unsafe { zeroed() };
+  info.header._type = DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO;
+  info.header.size =
+    size_of::<DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO>() as UINT32;
+  info.header.adapterId = path.targetInfo.adapterId;
+  info.header.id = path.targetInfo.id;
+  check(
+    unsafe { DisplayConfigGetDeviceInfo(&mut info.header) },
+    "read the advanced color state"
+  )?;
+  Ok(info.advancedColorEnabled() != 0)
+}
+
+fn check(result: LONG, action: &str) -> Result<()> {
+  if result != ERROR_SUCCESS as LONG {
+    return Err(Error::Wallpaper(format!(
+      "Windows: Failed to {action} (error {result})"
+    )));
+  }
+  Ok(())
+}
@@ -0,0 +1,10 @@
+mod registry;
+pub use registry::Setter as RegistrySetter;
+
+#[cfg(feature = "windows-desktop-wallpaper")]
+mod desktop;
+#[cfg(feature = "windows-desktop-wallpaper")]
+pub use desktop::Setter as DesktopSetter;
+
+#[cfg(feature = "windows-hdr")]
+pub mod hdr;
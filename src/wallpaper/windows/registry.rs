@@ -0,0 +1,116 @@
+//! Sets the desktop wallpaper on Windows through `SystemParametersInfo`.
+
+#![allow(unsafe_code)]
+
+use super::super::Setter as WallpaperSetter;
+use crate::{
+  Error, Result,
+  config::{Monitor, wallpaper::Windows}
+};
+use std::{
+  ffi::OsStr,
+  io,
+  os::windows::ffi::OsStrExt,
+  path::{Path, PathBuf}
+};
+use winapi::um::winuser::{
+  SPI_SETDESKWALLPAPER, SPIF_SENDCHANGE, SPIF_UPDATEINIFILE,
+  SystemParametersInfoW
+};
+use winreg::{RegKey, enums::*};
+
+const DESKTOP_PATH: &str = r"Control Panel\Desktop";
+const WALLPAPER_KEY: &str = "Wallpaper";
+const STYLE_KEY: &str = "WallpaperStyle";
+const TILE_KEY: &str = "TileWallpaper";
+const JPEG_QUALITY_KEY: &str = "JPEGImportQuality";
+
+/// The JPEG qualities Windows accepts.
+const JPEG_QUALITY_RANGE: std::ops::RangeInclusive<u8> = 60..=100;
+
+/// A wallpaper backend that applies the image with `SystemParametersInfo`.
+/// The same image is shown on every monitor, so this is only used when
+/// `IDesktopWallpaper` is unavailable.
+///
+/// The fit style and JPEG import quality are written first, so the result
+/// doesn't depend on whatever was last chosen in Settings.
+pub struct Setter {
+  settings: Windows
+}
+
+impl Setter {
+  pub fn new(settings: Windows) -> Self {
+    Self { settings }
+  }
+
+  /// Writes the style and quality values read when the wallpaper is applied.
+  fn write_settings(&self) -> Result<()> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let key = hkcu
+      .open_subkey_with_flags(DESKTOP_PATH, KEY_SET_VALUE)
+      .map_err(|e| {
+        Error::Wallpaper(format!(
+          "Windows: Failed to open registry key '{DESKTOP_PATH}': {e}"
+        ))
+      })?;
+
+    let (style, tile) = self.settings.style.registry_values();
+    let quality = self
+      .settings
+      .jpeg_quality
+      .clamp(*JPEG_QUALITY_RANGE.start(), *JPEG_QUALITY_RANGE.end());
+    key
+      .set_value(STYLE_KEY, &style)
+      .and_then(|()| key.set_value(TILE_KEY, &tile))
+      .and_then(|()| key.set_value(JPEG_QUALITY_KEY, &u32::from(quality)))
+      .map_err(|e| {
+        Error::Wallpaper(format!(
+          "Windows: Failed to set wallpaper style registry values: {e}"
+        ))
+      })
+  }
+}
+
+impl WallpaperSetter for Setter {
+  fn name(&self) -> &'static str {
+    "windows-registry"
+  }
+
+  fn set(&self, path: &Path, _monitor: &Monitor) -> Result<()> {
+    self.write_settings()?;
+
+    //{ Updating the ini file also stores the path in the registry }
+    let mut wide: Vec<u16> = OsStr::new(path).encode_wide().collect();
+    wide.push(0);
+    let ok = unsafe {
+      SystemParametersInfoW(
+        SPI_SETDESKWALLPAPER,
+        0,
+        wide.as_mut_ptr().cast(),
+        SPIF_UPDATEINIFILE | SPIF_SENDCHANGE
+      )
+    };
+    if ok == 0 {
+      return Err(Error::Wallpaper(format!(
+        "Windows: Failed to set the wallpaper: {}",
+        io::Error::last_os_error()
+      )));
+    }
+    Ok(())
+  }
+
+  fn get_current(&self, _monitor: &Monitor) -> Result<Option<PathBuf>> {
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let current = hkcu
+      .open_subkey(DESKTOP_PATH)
+      .and_then(|key| key.get_value::<String, _>(WALLPAPER_KEY))
+      .ok()
+      .filter(|value| !value.is_empty())
+      .map(PathBuf::from);
+    Ok(current)
+  }
+
+  fn supports_per_monitor(&self) -> bool {
+    false
+  }
+}